@@ -7,3 +7,4 @@ export const URL_SYNC_DELAY = 200;
 export const PANEL_SEPARATOR = "~";
 export const GROUP_SEPARATOR = ";";
 export const LIST_SEPARATOR = ",";
+export const ANNOTATION_SERVICE = "annotations";
@@ -1,4 +1,4 @@
-import { LIST_SEPARATOR } from "./constants";
+import { ANNOTATION_SERVICE, LIST_SEPARATOR } from "./constants";
 import type { LogEvent, PanelState } from "./types";
 
 export function normalizeFilterToken(value: string): string {
@@ -59,6 +59,9 @@ export function buildPanelMeta(panel: PanelState): string {
   const includeCount = panel.include.length;
   const excludeCount = panel.exclude.length;
   const parts = [label];
+  if (panel.stream) {
+    parts.push(panel.stream.toUpperCase());
+  }
   if (includeCount) {
     parts.push(`+${includeCount} include`);
   }
@@ -69,13 +72,22 @@ export function buildPanelMeta(panel: PanelState): string {
 }
 
 export function entryMatchesPanel(panel: PanelState, entry: LogEvent): boolean {
+  if (entry.annotation) {
+    return (
+      entry.service === ANNOTATION_SERVICE || !panel.filter || panel.filter.includes(entry.service)
+    );
+  }
   if (panel.filter && !panel.filter.includes(entry.service)) {
     return false;
   }
+  if (panel.stream && (entry.stream ?? "stdout") !== panel.stream) {
+    return false;
+  }
   if (panel.include.length === 0 && panel.exclude.length === 0) {
     return true;
   }
-  const normalizedLine = String(entry.line).toLowerCase();
+  const tags = entry.kind ? ` kind:${entry.kind} exception:${entry.exception ?? ""}` : "";
+  const normalizedLine = `${entry.line}${tags}`.toLowerCase();
   if (panel.include.length && !panel.include.some((token) => normalizedLine.includes(token))) {
     return false;
   }
@@ -10,6 +10,10 @@ export function getEndpoints(service: ServiceInfo): string[] {
   return [];
 }
 
+export function isWebEndpoint(endpoint: string): boolean {
+  return /^https?:\/\//.test(endpoint);
+}
+
 export function endpointLabel(endpoint: string): string {
   try {
     const url = new URL(endpoint);
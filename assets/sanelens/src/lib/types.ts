@@ -3,18 +3,44 @@ export interface ServiceInfo {
   endpoints?: string[];
   endpoint?: string | null;
   exposed?: boolean;
+  published?: PublishedEndpoint[];
+}
+
+export type EndpointProtocol =
+  | "http"
+  | "https"
+  | "postgres"
+  | "mysql"
+  | "redis"
+  | "mongodb"
+  | "amqp"
+  | "tcp";
+
+export interface PublishedEndpoint {
+  host: string;
+  port: number;
+  url: string;
+  protocol?: EndpointProtocol | null;
 }
 
 export interface LogEvent {
   seq: number;
   service: string;
   container_ts?: string | null;
+  ts_ms?: number | null;
   line: string;
+  stream?: LogStream;
+  repeat_count?: number;
+  annotation?: boolean;
+  kind?: "error";
+  exception?: string;
 }
 
+export type LogStream = "stdout" | "stderr";
+
 export type EntityId =
   | { kind: "workload"; name: string; instance?: string | null }
-  | { kind: "external"; ip: string; dns_name?: string | null }
+  | { kind: "external"; ips: string[]; dns_name?: string | null }
   | { kind: "host"; name: string }
   | { kind: "unknown" };
 
@@ -67,6 +93,14 @@ export type EdgeKey =
       method: string;
       route: string;
     }
+  | {
+      kind: "db";
+      from: EntityId;
+      to: EntityId;
+      protocol: string;
+      command: string;
+      statement: string;
+    }
   | {
       kind: "grpc";
       from: EntityId;
@@ -82,15 +116,24 @@ export interface EdgeStats {
   errors: number;
   p50_ms?: number | null;
   p95_ms?: number | null;
+  p99_ms?: number | null;
   visibility: "l4_flow" | "l7_envelope" | "l7_semantics";
+  networks?: string[];
 }
 
 export interface TrafficEdge {
+  id?: number;
   key: EdgeKey;
   stats: EdgeStats;
   last_seen_ms: number;
 }
 
+export interface TrafficEdgeDelta {
+  id: number;
+  stats: EdgeStats;
+  last_seen_ms: number;
+}
+
 export interface TrafficCall {
   seq: number;
   at_ms: number;
@@ -107,6 +150,43 @@ export interface TrafficCall {
   response_body?: string | null;
   correlation: Correlation;
   attrs: ObservationAttrs;
+  upgrade?: UpgradeInfo;
+}
+
+export interface CallTiming {
+  started_at_ms: number;
+  finished_at_ms?: number | null;
+  duration_ms?: number | null;
+  upstream_ms?: number | null;
+  proxy_ms?: number | null;
+}
+
+export interface CallDetail extends TrafficCall {
+  timing: CallTiming;
+}
+
+export interface UpgradeInfo {
+  protocol: string;
+  messages_in?: number | null;
+  messages_out?: number | null;
+}
+
+export type DependencyKind = "depends_on" | "link" | "network_mode";
+
+export interface GraphEdge {
+  from: string;
+  to: string;
+  declared: DependencyKind[];
+  same_network: boolean;
+  status: "declared" | "confirmed" | "undeclared";
+  calls: number;
+  errors: number;
+}
+
+export interface GraphView {
+  services: string[];
+  networks: Record<string, string[]>;
+  edges: GraphEdge[];
 }
 
 export interface PanelState {
@@ -115,6 +195,7 @@ export interface PanelState {
   filter: string[] | null;
   include: string[];
   exclude: string[];
+  stream: LogStream | null;
   autoScroll: boolean;
   logs: LogEvent[];
   delay: number;
@@ -124,5 +205,6 @@ export interface PanelConfig {
   services: string[] | null;
   include: string[];
   exclude: string[];
+  stream: LogStream | null;
   follow: boolean;
 }
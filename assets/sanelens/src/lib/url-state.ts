@@ -47,6 +47,9 @@ export function serializePanelConfig(panel: PanelState): string {
   if (excludeTokens.length) {
     parts.push(`exc=${encodeTokenList(excludeTokens)}`);
   }
+  if (panel.stream) {
+    parts.push(`std=${panel.stream}`);
+  }
   if (!panel.autoScroll) {
     parts.push("follow=0");
   }
@@ -65,6 +68,7 @@ export function parsePanelConfig(raw: string): PanelConfig {
     services: null,
     include: [],
     exclude: [],
+    stream: null,
     follow: true,
   };
   if (!raw) {
@@ -93,6 +97,10 @@ export function parsePanelConfig(raw: string): PanelConfig {
       config.exclude = decodeTokenList(value, normalizeFilterToken);
       return;
     }
+    if (key === "std") {
+      config.stream = value === "stdout" || value === "stderr" ? value : null;
+      return;
+    }
     if (key === "follow") {
       config.follow = value !== "0";
     }
@@ -145,6 +153,7 @@ export function buildSearchString(panelsValue: string, activeIndex: number | nul
   const params = new URLSearchParams(window.location.search);
   params.delete(URL_STATE_KEY);
   params.delete(URL_ACTIVE_KEY);
+  params.delete("starting");
   const parts: string[] = [];
   const base = params.toString();
   if (base) {
@@ -161,3 +170,16 @@ export function buildSearchString(panelsValue: string, activeIndex: number | nul
   }
   return `?${parts.join("&")}`;
 }
+
+export function readStartingFlag(): boolean {
+  return getRawQueryParam("starting") === "1";
+}
+
+export function withAuthToken(path: string): string {
+  const token = getRawQueryParam("token");
+  if (!token) {
+    return path;
+  }
+  const separator = path.includes("?") ? "&" : "?";
+  return `${path}${separator}token=${token}`;
+}
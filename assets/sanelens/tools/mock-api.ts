@@ -18,8 +18,8 @@ export function mockApiPlugin(): Plugin {
     { name: "search-service", endpoint: "https://search.sanelens.local" },
     { name: "notifications-worker" },
     { name: "inventory-sync" },
-    { name: "postgres" },
-    { name: "redis" },
+    { name: "postgres", endpoints: ["postgres://localhost:5432"] },
+    { name: "redis", endpoints: ["redis://localhost:6379"] },
   ];
 
   const users = [
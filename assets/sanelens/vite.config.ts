@@ -9,6 +9,7 @@ export default defineConfig(({ mode }) => {
   }
 
   return {
+    base: "./",
     plugins,
     build: {
       outDir: "dist",
@@ -0,0 +1,92 @@
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{never, select, Receiver};
+
+use crate::domain::LogEvent;
+use crate::support::alerts::{AlertEvent, AlertHub, AlertState};
+use crate::support::clock::now_ms;
+use crate::support::logging::LogHub;
+use crate::support::traffic::{TrafficHub, TrafficUpdate};
+
+const TICK: Duration = Duration::from_secs(1);
+
+pub fn start_alerts(
+    hub: Arc<AlertHub>,
+    log_hub: &LogHub,
+    traffic_hub: Option<&TrafficHub>,
+    stop_event: Arc<AtomicBool>,
+) -> thread::JoinHandle<()> {
+    let (logs, _) = log_hub.register_client();
+    let traffic = traffic_hub.map_or_else(never, |hub| hub.register_client(None).0);
+    thread::spawn(move || watch_alerts(&hub, &logs, &traffic, &stop_event))
+}
+
+fn watch_alerts(
+    hub: &AlertHub,
+    logs: &Receiver<LogEvent>,
+    traffic: &Receiver<TrafficUpdate>,
+    stop_event: &AtomicBool,
+) {
+    while !stop_event.load(Ordering::SeqCst) {
+        let events = select! {
+            recv(logs) -> event => match event {
+                Ok(event) => hub.observe_log(&event, now_ms()),
+                Err(_) => break,
+            },
+            recv(traffic) -> update => match update {
+                Ok(update) => hub.observe_traffic(&update),
+                Err(_) => break,
+            },
+            default(TICK) => hub.evaluate(now_ms()),
+        };
+        for event in &events {
+            report(event);
+        }
+    }
+}
+
+fn report(event: &AlertEvent) {
+    let state = match event.state {
+        AlertState::Firing => "FIRING",
+        AlertState::Resolved => "resolved",
+    };
+    eprintln!("[alert] {state} {}: {}", event.rule, event.message);
+    if event.notify {
+        notify_desktop(&format!("sanelens: {} {state}", event.rule), &event.message);
+    }
+}
+
+fn notify_desktop(title: &str, body: &str) {
+    let mut cmd = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {} with title {}",
+            apple_script_string(body),
+            apple_script_string(title)
+        );
+        let mut cmd = Command::new("osascript");
+        cmd.args(["-e", &script]);
+        cmd
+    } else {
+        let mut cmd = Command::new("notify-send");
+        cmd.args([title, body]);
+        cmd
+    };
+    let spawned = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    if let Ok(mut child) = spawned {
+        thread::spawn(move || {
+            let _ = child.wait();
+        });
+    }
+}
+
+fn apple_script_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
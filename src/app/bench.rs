@@ -0,0 +1,228 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{tick, Receiver};
+
+use crate::domain::Scope;
+use crate::infra::engine::Engine;
+use crate::support::args::{parse_duration, take_option};
+use crate::support::bench::{summarize, BenchReport, BenchSample};
+use crate::support::services::build_service_info;
+
+use super::output::{self, OutputFormat};
+use super::{
+    load_run_containers, render, resolve_run_id, run_metadata_from_containers, runner,
+    setup_signals,
+};
+
+const DEFAULT_RPS: u32 = 10;
+const DEFAULT_DURATION: Duration = Duration::from_secs(10);
+const DEFAULT_CONCURRENCY: usize = 8;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+const TICK_WAIT: Duration = Duration::from_millis(200);
+
+pub struct BenchArgs {
+    pub run_id: Option<String>,
+    pub service: Option<String>,
+    path: Option<String>,
+    rps: Option<String>,
+    duration: Option<String>,
+    concurrency: Option<String>,
+}
+
+pub fn parse_bench_args(args: &[String]) -> BenchArgs {
+    let (args, path) = take_option(args, "--path");
+    let (args, rps) = take_option(&args, "--rps");
+    let (args, duration) = take_option(&args, "--duration");
+    let (args, concurrency) = take_option(&args, "--concurrency");
+    let mut positional = args.into_iter();
+    BenchArgs {
+        run_id: positional.next(),
+        service: positional.next(),
+        path,
+        rps,
+        duration,
+        concurrency,
+    }
+}
+
+struct BenchPlan {
+    addr: SocketAddr,
+    host: String,
+    path: String,
+    rps: u32,
+    duration: Duration,
+    concurrency: usize,
+}
+
+pub fn run_bench(
+    engine: &Engine,
+    mut args: BenchArgs,
+    output: OutputFormat,
+) -> Result<i32, String> {
+    let run_id = resolve_run_id(engine, "bench", args.run_id.take())?;
+    let service = args
+        .service
+        .take()
+        .ok_or_else(|| "Usage: sanelens bench <run_id|tag> <service> [--path /x]".to_string())?;
+    let plan = plan_bench(engine, &run_id, &service, args)?;
+    let target = format!("http://{}{}", plan.host, plan.path);
+    eprintln!(
+        "[bench] {target}: {} req/s for {}s with {} workers",
+        plan.rps,
+        plan.duration.as_secs(),
+        plan.concurrency
+    );
+
+    let stop_event = Arc::new(AtomicBool::new(false));
+    let exit_code = Arc::new(AtomicI32::new(0));
+    setup_signals(runner::SignalContext::new(
+        stop_event.clone(),
+        Arc::new(AtomicBool::new(false)),
+        exit_code.clone(),
+        Arc::new(runner::ProcessHandles::new()),
+    ));
+    let started = Instant::now();
+    let samples = generate_load(&plan, &stop_event);
+    let report = summarize(&target, &samples, started.elapsed());
+    print_report(&report, output);
+    let signal_exit = exit_code.load(Ordering::SeqCst);
+    if signal_exit != 0 {
+        return Ok(signal_exit);
+    }
+    Ok(i32::from(report.errors > 0))
+}
+
+fn plan_bench(
+    engine: &Engine,
+    run_id: &str,
+    service: &str,
+    args: BenchArgs,
+) -> Result<BenchPlan, String> {
+    let containers = load_run_containers(engine, run_id, Scope::Running)?;
+    let metadata = run_metadata_from_containers(run_id, &containers);
+    let compose_file = metadata
+        .compose_file
+        .ok_or_else(|| format!("Run {run_id} is missing compose file metadata."))?;
+    let endpoint = build_service_info(&compose_file, engine.endpoint_host())
+        .into_iter()
+        .find(|info| info.name == service)
+        .ok_or_else(|| format!("Service {service} not found in run {run_id}."))?
+        .endpoint
+        .ok_or_else(|| format!("Service {service} has no published port to bench."))?;
+    let host = endpoint
+        .strip_prefix("http://")
+        .unwrap_or(&endpoint)
+        .to_string();
+    let addr = host
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .ok_or_else(|| format!("Cannot resolve {host}."))?;
+    let path = args.path.unwrap_or_else(|| "/".to_string());
+    if !path.starts_with('/') {
+        return Err(format!("Invalid --path '{path}'. It must start with /."));
+    }
+    Ok(BenchPlan {
+        addr,
+        host,
+        path,
+        rps: parse_positive(args.rps, "--rps", DEFAULT_RPS)?,
+        duration: args.duration.map_or(Ok(DEFAULT_DURATION), |value| {
+            parse_duration(&value)
+                .filter(|duration| !duration.is_zero())
+                .ok_or_else(|| format!("Invalid --duration '{value}'. Use e.g. 30s or 2m."))
+        })?,
+        concurrency: parse_positive(args.concurrency, "--concurrency", DEFAULT_CONCURRENCY)?,
+    })
+}
+
+fn parse_positive<T: std::str::FromStr + Default + PartialEq>(
+    value: Option<String>,
+    flag: &str,
+    default: T,
+) -> Result<T, String> {
+    let Some(value) = value else {
+        return Ok(default);
+    };
+    value
+        .parse()
+        .ok()
+        .filter(|parsed| *parsed != T::default())
+        .ok_or_else(|| format!("Invalid {flag} '{value}'. Use a positive number."))
+}
+
+fn generate_load(plan: &BenchPlan, stop_event: &AtomicBool) -> Vec<BenchSample> {
+    let deadline = Instant::now() + plan.duration;
+    let ticks = tick(Duration::from_secs(1) / plan.rps);
+    let sequence = AtomicU64::new(0);
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..plan.concurrency)
+            .map(|_| scope.spawn(|| run_worker(plan, &ticks, deadline, stop_event, &sequence)))
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_default())
+            .collect()
+    })
+}
+
+fn run_worker(
+    plan: &BenchPlan,
+    ticks: &Receiver<Instant>,
+    deadline: Instant,
+    stop_event: &AtomicBool,
+    sequence: &AtomicU64,
+) -> Vec<BenchSample> {
+    let mut samples = Vec::new();
+    while Instant::now() < deadline && !stop_event.load(Ordering::SeqCst) {
+        if ticks.recv_timeout(TICK_WAIT).is_ok() {
+            let id = sequence.fetch_add(1, Ordering::SeqCst);
+            samples.push(send_request(plan, id));
+        }
+    }
+    samples
+}
+
+fn send_request(plan: &BenchPlan, id: u64) -> BenchSample {
+    let started = Instant::now();
+    let status = request_status(plan, id).ok().flatten();
+    BenchSample {
+        latency_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+        status,
+    }
+}
+
+fn request_status(plan: &BenchPlan, id: u64) -> io::Result<Option<u16>> {
+    let mut stream = TcpStream::connect_timeout(&plan.addr, CONNECT_TIMEOUT)?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: sanelens-bench\r\nX-Request-Id: sanelens-bench-{id}\r\nConnection: close\r\n\r\n",
+        plan.path, plan.host
+    )?;
+    let mut reader = BufReader::new(stream);
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line)?;
+    io::copy(&mut reader, &mut io::sink())?;
+    Ok(status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok()))
+}
+
+fn print_report(report: &BenchReport, output: OutputFormat) {
+    if output.is_json() {
+        output::print_json(report);
+        return;
+    }
+    let mut stdout = io::stdout();
+    for line in render::render_bench(report) {
+        let _ = writeln!(stdout, "{line}");
+    }
+}
@@ -0,0 +1,78 @@
+use std::io::{self, Write};
+
+use crate::infra::engine::{Engine, RunContainers};
+use crate::infra::envoy::post_admin;
+use crate::support::args::{take_flag, take_option};
+use crate::support::chaos::ChaosPlan;
+
+use super::output::{self, OutputFormat};
+use super::resolve_run_id;
+
+pub struct ChaosArgs {
+    run_id: Option<String>,
+    service: Option<String>,
+    latency: Option<String>,
+    latency_rate: Option<String>,
+    abort_rate: Option<String>,
+    abort_status: Option<String>,
+    clear: bool,
+}
+
+pub fn parse_chaos_args(args: &[String]) -> ChaosArgs {
+    let (args, latency) = take_option(args, "--latency");
+    let (args, latency_rate) = take_option(&args, "--latency-rate");
+    let (args, abort_rate) = take_option(&args, "--abort-rate");
+    let (args, abort_status) = take_option(&args, "--abort-status");
+    let (args, clear) = take_flag(&args, "--clear");
+    let mut positional = args.into_iter();
+    ChaosArgs {
+        run_id: positional.next(),
+        service: positional.next(),
+        latency,
+        latency_rate,
+        abort_rate,
+        abort_status,
+        clear,
+    }
+}
+
+pub fn run_chaos(engine: &Engine, args: ChaosArgs, output: OutputFormat) -> Result<i32, String> {
+    let run_id = resolve_run_id(engine, "chaos", args.run_id)?;
+    let service = args.service.ok_or_else(|| {
+        "Usage: sanelens chaos <run_id|tag> <service> [--latency 300ms] [--abort-rate 0.1] [--clear]"
+            .to_string()
+    })?;
+    let plan = if args.clear {
+        ChaosPlan::clear()
+    } else {
+        ChaosPlan::parse(
+            args.latency.as_deref(),
+            args.latency_rate.as_deref(),
+            args.abort_rate.as_deref(),
+            args.abort_status.as_deref(),
+        )?
+    };
+    let admin = RunContainers::new(engine.clone(), run_id.clone())
+        .proxy_admin_addr(&service)
+        .ok_or_else(|| {
+            format!("Service {service} has no running sanelens proxy with a published admin port in run {run_id}.")
+        })?;
+    post_admin(admin, &format!("/runtime_modify?{}", plan.runtime_query()))?;
+    if output.is_json() {
+        output::print_json(&serde_json::json!({
+            "run_id": run_id,
+            "service": service,
+            "latency_ms": plan.latency.map(|latency| latency.as_millis()),
+            "latency_percent": plan.latency_percent,
+            "abort_percent": plan.abort_percent,
+            "abort_status": plan.abort_status,
+        }));
+        return Ok(0);
+    }
+    let _ = writeln!(
+        io::stdout(),
+        "[compose] chaos on {service} in {run_id}: {}",
+        plan.describe()
+    );
+    Ok(0)
+}
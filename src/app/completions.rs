@@ -0,0 +1,49 @@
+use std::io::{self, Write};
+
+use crate::domain::EngineKind;
+use crate::infra::compose::{detect_compose_cmd, ComposeProvider};
+use crate::infra::engine::Engine;
+use crate::support::completions::{script, Shell, COMPLETE_RUNS_COMMAND};
+use crate::support::constants::BIN_NAME;
+
+use super::collect_active_runs;
+
+pub fn handle_completions(
+    args: &[String],
+    engine_preference: Option<EngineKind>,
+) -> Option<Result<i32, String>> {
+    match args.first().map(String::as_str) {
+        Some("completions") => Some(print_script(args.get(1).map(String::as_str))),
+        Some(COMPLETE_RUNS_COMMAND) => Some(Ok(print_run_ids(engine_preference))),
+        _ => None,
+    }
+}
+
+fn print_script(shell: Option<&str>) -> Result<i32, String> {
+    let shell = shell.ok_or_else(|| format!("Usage: {BIN_NAME} completions <bash|zsh|fish>"))?;
+    let _ = io::stdout().write_all(script(Shell::parse(shell)?).as_bytes());
+    Ok(0)
+}
+
+fn print_run_ids(engine_preference: Option<EngineKind>) -> i32 {
+    let Ok(selection) = detect_compose_cmd(ComposeProvider {
+        engine: engine_preference,
+        allow_legacy: false,
+    }) else {
+        return 0;
+    };
+    let engine = Engine::new(selection.engine, &selection.compose_cmd);
+    let mut runs = collect_active_runs(&engine);
+    runs.sort_by_key(|run| std::cmp::Reverse(run.started_at_ts));
+    let mut stdout = io::stdout();
+    if !runs.is_empty() {
+        let _ = writeln!(stdout, "@last");
+    }
+    for run in &runs {
+        let _ = writeln!(stdout, "{}", run.run_id);
+    }
+    for tag in runs.iter().filter_map(|run| run.tag.as_deref()) {
+        let _ = writeln!(stdout, "{tag}");
+    }
+    0
+}
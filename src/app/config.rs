@@ -0,0 +1,92 @@
+use crate::domain::EngineKind;
+use crate::infra::engine::Engine;
+use crate::support::constants::PROJECT_PREFIX;
+use crate::support::settings::{env_settings, process_env, ResolvedArgs, Setting, Source};
+
+use super::output::{self, OutputFormat};
+use super::print_table;
+
+pub struct ConfigShow<'a> {
+    resolved: &'a ResolvedArgs,
+    engine_flag: bool,
+    output: OutputFormat,
+}
+
+impl<'a> ConfigShow<'a> {
+    pub const fn new(
+        resolved: &'a ResolvedArgs,
+        engine_preference: Option<EngineKind>,
+        output: OutputFormat,
+    ) -> Self {
+        Self {
+            resolved,
+            engine_flag: engine_preference.is_some(),
+            output,
+        }
+    }
+
+    pub fn run(&self, engine: &Engine, compose_cmd: &[String]) -> i32 {
+        let settings = self.collect_settings(engine, compose_cmd);
+        if self.output.is_json() {
+            output::print_json(&serde_json::json!({ "settings": settings }));
+            return 0;
+        }
+        print_settings(settings);
+        0
+    }
+
+    fn collect_settings(&self, engine: &Engine, compose_cmd: &[String]) -> Vec<Setting> {
+        let compose_cmd_env = process_env("COMPOSE_CMD").is_some();
+        let engine_source = if self.engine_flag {
+            Source::Flag
+        } else if compose_cmd_env {
+            Source::Env
+        } else {
+            Source::Default
+        };
+        let engine_name = if engine.is_podman() {
+            "podman"
+        } else {
+            "docker"
+        };
+        let compose_source = if compose_cmd_env {
+            Source::Env
+        } else {
+            engine_source
+        };
+        let mut settings = vec![
+            Setting::new("engine", Some(engine_name.to_string()), engine_source),
+            Setting::new(
+                "compose command",
+                Some(compose_cmd.join(" ")),
+                compose_source,
+            ),
+            self.resolved.compose_file.clone(),
+            self.resolved.profiles.clone(),
+            self.resolved.run_dir.clone(),
+            Setting::new(
+                "run project name",
+                Some(format!("{PROJECT_PREFIX}<run_id>")),
+                Source::Default,
+            ),
+        ];
+        settings.extend(self.resolved.project_name.clone());
+        settings.extend(env_settings(&process_env));
+        settings
+    }
+}
+
+fn print_settings(settings: Vec<Setting>) {
+    let rows: Vec<[String; 4]> = settings
+        .into_iter()
+        .map(|setting| {
+            [
+                setting.name,
+                setting.value.unwrap_or_else(|| "-".to_string()),
+                setting.source.label().to_string(),
+                setting.note.unwrap_or_default(),
+            ]
+        })
+        .collect();
+    print_table(["SETTING", "VALUE", "SOURCE", "NOTE"], &rows);
+}
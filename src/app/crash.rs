@@ -0,0 +1,138 @@
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::infra::engine::{ContainerInfo, Engine};
+use crate::support::alerts::{AlertEvent, AlertHub, AlertState};
+use crate::support::clock::now_ms;
+use crate::support::crash::{
+    crash_dir_name, CrashLoopDetector, CrashLoopPolicy, CRASH_DIR, CRASH_LOG_LINES,
+};
+use crate::support::events::EventKind;
+use crate::support::logging::LogHub;
+
+use super::format_duration;
+use super::hooks::{publish, EventContext};
+use super::inspect::write_snapshot;
+
+const CRASH_LOOP_RULE: &str = "crash-loop";
+
+pub struct CrashCapture {
+    pub policy: CrashLoopPolicy,
+    pub log_hub: Arc<LogHub>,
+    pub alert_hub: Option<Arc<AlertHub>>,
+    pub run_dir: Option<PathBuf>,
+}
+
+pub struct CrashLoopWatch {
+    capture: CrashCapture,
+    detector: CrashLoopDetector,
+}
+
+impl CrashLoopWatch {
+    pub fn new(capture: CrashCapture) -> Self {
+        Self {
+            detector: CrashLoopDetector::new(capture.policy),
+            capture,
+        }
+    }
+
+    pub fn observe(&mut self, context: &EventContext, containers: &[ContainerInfo]) {
+        let now = now_ms();
+        for info in containers.iter().filter(|info| !info.is_proxy()) {
+            let Some(service) = info.service.as_deref() else {
+                continue;
+            };
+            let Some(restarts) = self
+                .detector
+                .observe(service, &info.id, info.restart_count, now)
+            else {
+                continue;
+            };
+            let snapshot = self.snapshot(&context.engine, service, &info.id, now);
+            if let Some(dir) = self.capture.run_dir.as_deref() {
+                let label = format!("crash-{}", crash_dir_name(service, now));
+                write_snapshot(&context.engine, &context.run_id, dir, &label);
+            }
+            self.report(service, restarts, snapshot.as_deref(), now);
+            let kind = EventKind::CrashLoop {
+                service: service.to_string(),
+                container: info.id.clone(),
+                restarts,
+                window_ms: self.capture.policy.window_ms(),
+                snapshot: snapshot.map(|path| path.display().to_string()),
+            };
+            publish(&context.bus, &context.run_id, kind);
+        }
+    }
+
+    fn snapshot(
+        &self,
+        engine: &Engine,
+        service: &str,
+        container: &str,
+        at_ms: u64,
+    ) -> Option<PathBuf> {
+        let dir = self
+            .capture
+            .run_dir
+            .as_ref()?
+            .join(CRASH_DIR)
+            .join(crash_dir_name(service, at_ms));
+        match write_evidence(&dir, engine, &self.capture.log_hub, service, container) {
+            Ok(()) => Some(dir),
+            Err(err) => {
+                eprintln!("[alert] failed to capture crash evidence for {service}: {err}");
+                None
+            }
+        }
+    }
+
+    fn report(&self, service: &str, restarts: u64, snapshot: Option<&Path>, at_ms: u64) {
+        let window = i64::try_from(self.capture.policy.window.as_secs()).unwrap_or(i64::MAX);
+        let mut message = format!(
+            "{service} restarted {restarts} times in {}",
+            format_duration(window)
+        );
+        if let Some(path) = snapshot {
+            let _ = write!(message, "; evidence in {}", path.display());
+        }
+        eprintln!("[alert] FIRING {CRASH_LOOP_RULE}: {message}");
+        if let Some(hub) = self.capture.alert_hub.as_ref() {
+            hub.raise(AlertEvent {
+                rule: CRASH_LOOP_RULE.to_string(),
+                state: AlertState::Firing,
+                message,
+                at_ms,
+                notify: false,
+            });
+        }
+    }
+}
+
+fn write_evidence(
+    dir: &Path,
+    engine: &Engine,
+    log_hub: &LogHub,
+    service: &str,
+    container: &str,
+) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let mut logs = String::new();
+    for event in log_hub.recent(service, CRASH_LOG_LINES) {
+        if let Some(ts) = event.container_ts.as_deref() {
+            logs.push_str(ts);
+            logs.push(' ');
+        }
+        logs.push_str(&event.line);
+        logs.push('\n');
+    }
+    fs::write(dir.join("logs.txt"), logs)?;
+    let inspect = engine.inspect_raw(&[container.to_string()]);
+    fs::write(
+        dir.join("inspect.json"),
+        serde_json::to_vec_pretty(&inspect).unwrap_or_default(),
+    )
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::domain::Scope;
+use crate::infra::engine::{Engine, RunContainers};
+use crate::infra::ui::{UiBind, UiServer};
+use crate::infra::ui_daemon::{remove_daemon_info, write_daemon_info, UiRegistry};
+use crate::support::constants::HISTORY_LIMIT;
+use crate::support::logging::{LogHub, DEFAULT_LOG_REORDER};
+use crate::support::run::project_name_from_run_id;
+use crate::support::traffic::TrafficHub;
+
+use super::{
+    collect_active_runs, load_run_containers, run_services_from_containers, run_tap_dir, runner,
+    session_ui_run, setup_signals, RunMetadata,
+};
+
+pub const UI_DAEMON_PORT: u16 = 7437;
+const SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+struct AttachedRun {
+    stop_event: Arc<AtomicBool>,
+    handles: Arc<runner::ProcessHandles>,
+    threads: Vec<thread::JoinHandle<i32>>,
+}
+
+impl AttachedRun {
+    fn stop(self) {
+        self.stop_event.store(true, Ordering::SeqCst);
+        self.handles.stop_log_procs();
+        for handle in self.threads {
+            let _ = handle.join();
+        }
+    }
+}
+
+pub fn run_ui_daemon(engine: &Engine, bind: &UiBind) -> Result<i32, String> {
+    let stop_event = Arc::new(AtomicBool::new(false));
+    let exit_code = Arc::new(AtomicI32::new(0));
+    setup_signals(runner::SignalContext::new(
+        stop_event.clone(),
+        Arc::new(AtomicBool::new(false)),
+        exit_code.clone(),
+        Arc::new(runner::ProcessHandles::new()),
+    ));
+
+    let registry = Arc::new(UiRegistry::new());
+    let mut server = UiServer::start_multi(registry.clone(), stop_event.clone(), bind)
+        .map_err(|err| format!("log UI failed: {err}"))?;
+    if let Err(err) = write_daemon_info(&server.daemon_info()) {
+        eprintln!("[compose] ui daemon registration file not written: {err}");
+    }
+    let _ = writeln!(std::io::stdout(), "[compose] log UI: {}", server.url());
+
+    let mut attached: HashMap<String, AttachedRun> = HashMap::new();
+    while !stop_event.load(Ordering::SeqCst) {
+        sync_runs(engine, &registry, &mut attached);
+        thread::sleep(SYNC_INTERVAL);
+    }
+
+    for (_, run) in attached.drain() {
+        run.stop();
+    }
+    server.stop();
+    remove_daemon_info();
+    Ok(exit_code.load(Ordering::SeqCst))
+}
+
+fn sync_runs(engine: &Engine, registry: &UiRegistry, attached: &mut HashMap<String, AttachedRun>) {
+    let active = collect_active_runs(engine);
+    let stale: Vec<String> = attached
+        .keys()
+        .filter(|run_id| !active.iter().any(|run| &run.run_id == *run_id))
+        .cloned()
+        .collect();
+    for run_id in stale {
+        registry.remove(&run_id);
+        if let Some(run) = attached.remove(&run_id) {
+            run.stop();
+        }
+    }
+    for metadata in active {
+        if attached.contains_key(&metadata.run_id) {
+            continue;
+        }
+        match attach_run(engine, registry, &metadata) {
+            Ok(run) => {
+                attached.insert(metadata.run_id.clone(), run);
+            }
+            Err(err) => eprintln!("[compose] ui daemon: {err}"),
+        }
+    }
+}
+
+fn attach_run(
+    engine: &Engine,
+    registry: &UiRegistry,
+    metadata: &RunMetadata,
+) -> Result<AttachedRun, String> {
+    let run_id = metadata.run_id.clone();
+    let containers = load_run_containers(engine, &run_id, Scope::Running)?;
+    let services = run_services_from_containers(&containers);
+    let project_name = metadata
+        .project_name
+        .clone()
+        .unwrap_or_else(|| project_name_from_run_id(&run_id));
+    let stop_event = Arc::new(AtomicBool::new(false));
+    let handles = Arc::new(runner::ProcessHandles::new());
+    let log_hub = LogHub::with_reorder(HISTORY_LIMIT, DEFAULT_LOG_REORDER);
+    let traffic_hub = Arc::new(TrafficHub::new());
+
+    let log_follower = runner::LogFollower::new(
+        engine.clone(),
+        run_id.clone(),
+        project_name.clone(),
+        stop_event.clone(),
+        Some(log_hub.clone()),
+        handles.clone(),
+        services.proxy_services.clone(),
+        services.service_aliases.clone(),
+    );
+    let mut traffic_follower = runner::TrafficFollower::new(
+        engine.clone(),
+        run_id.clone(),
+        project_name,
+        stop_event.clone(),
+        handles.clone(),
+        traffic_hub.clone(),
+        services.proxy_services,
+        services.service_aliases,
+        services.egress_proxy,
+        run_tap_dir(metadata),
+    );
+    traffic_follower.set_container_watch(log_follower.container_watch());
+    let threads = vec![
+        thread::spawn(move || log_follower.follow_logs(false, &mut Vec::new())),
+        thread::spawn(move || traffic_follower.follow()),
+    ];
+
+    let run = session_ui_run(
+        metadata,
+        log_hub,
+        Some(traffic_hub),
+        Arc::new(RunContainers::new(engine.clone(), run_id.clone())),
+    );
+    registry.insert(&run_id, run);
+    Ok(AttachedRun {
+        stop_event,
+        handles,
+        threads,
+    })
+}
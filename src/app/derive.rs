@@ -0,0 +1,67 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::support::args::{take_flag, take_option};
+use crate::support::constants::BIN_NAME;
+
+use super::output::{self, OutputFormat};
+use super::runner::ComposeRunner;
+
+pub struct DeriveArgs {
+    out: Option<PathBuf>,
+    explain: bool,
+}
+
+pub fn take_derive_args(args: &mut Vec<String>) -> DeriveArgs {
+    let (rest, explain) = take_flag(args, "--explain");
+    let (rest, _) = take_flag(&rest, "--print");
+    let (rest, out) = take_option(&rest, "--out");
+    *args = rest;
+    DeriveArgs {
+        out: out.map(PathBuf::from),
+        explain,
+    }
+}
+
+pub fn run_derive(
+    runner: &mut ComposeRunner,
+    args: &DeriveArgs,
+    output: OutputFormat,
+) -> Result<i32, String> {
+    let (path, rewrites) = runner.derive_only()?;
+    let compose = fs::read_to_string(&path)
+        .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+    let run_dir = path.parent().map(|dir| dir.display().to_string());
+    if output.is_json() {
+        output::print_json(&serde_json::json!({
+            "derived_compose": path.display().to_string(),
+            "run_dir": run_dir,
+            "rewrites": rewrites,
+            "compose": compose,
+        }));
+        return Ok(0);
+    }
+    let mut rendered = String::new();
+    if args.explain {
+        for rewrite in &rewrites {
+            let _ = writeln!(rendered, "# {rewrite}");
+        }
+    }
+    rendered.push_str(&compose);
+    match args.out.as_ref() {
+        Some(out) => {
+            fs::write(out, rendered)
+                .map_err(|err| format!("failed to write {}: {err}", out.display()))?;
+            eprintln!("[compose] wrote derived compose to {}", out.display());
+        }
+        None => {
+            let _ = io::stdout().write_all(rendered.as_bytes());
+        }
+    }
+    if let Some(dir) = run_dir {
+        eprintln!("[compose] proxy configs stay in {dir}; `{BIN_NAME} gc` removes them");
+    }
+    Ok(0)
+}
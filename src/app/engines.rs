@@ -0,0 +1,75 @@
+use crate::domain::Scope;
+use crate::infra::compose::{
+    detect_other_engine, display_engine, ComposeProvider, ComposeSelection,
+};
+use crate::infra::engine::Engine;
+use crate::support::run::{route_run_ref, RunRef};
+
+use super::collect_runs;
+
+pub struct EngineTarget {
+    pub engine: Engine,
+    pub compose_cmd: Vec<String>,
+}
+
+impl EngineTarget {
+    fn new(selection: ComposeSelection) -> Self {
+        Self {
+            engine: Engine::new(selection.engine, &selection.compose_cmd),
+            compose_cmd: selection.compose_cmd,
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        display_engine(self.engine.kind())
+    }
+}
+
+pub struct SessionEngines {
+    primary: EngineTarget,
+    others: Vec<EngineTarget>,
+}
+
+impl SessionEngines {
+    pub fn detect(selection: ComposeSelection, provider: ComposeProvider, span: bool) -> Self {
+        let others = span
+            .then(|| detect_other_engine(provider, selection.engine))
+            .flatten()
+            .map(EngineTarget::new)
+            .into_iter()
+            .collect();
+        Self {
+            primary: EngineTarget::new(selection),
+            others,
+        }
+    }
+
+    pub fn all(&self) -> Vec<&EngineTarget> {
+        std::iter::once(&self.primary)
+            .chain(self.others.iter())
+            .collect()
+    }
+
+    pub fn route(&self, run_ref: Option<&str>) -> Result<&EngineTarget, String> {
+        let Some(run_ref) = run_ref.filter(|_| !self.others.is_empty()) else {
+            return Ok(&self.primary);
+        };
+        let targets = self.all();
+        let runs = targets
+            .iter()
+            .map(|target| collect_runs(&target.engine, Scope::All));
+        let routed = route_run_ref(run_ref, runs, |run| RunRef {
+            run_id: &run.run_id,
+            tag: run.tag.as_deref(),
+            started_at: run.started_at_ts,
+        })?;
+        let Some((owner, run_id)) = routed.filter(|(idx, _)| *idx > 0) else {
+            return Ok(&self.primary);
+        };
+        let Some(owner) = targets.get(owner).copied() else {
+            return Ok(&self.primary);
+        };
+        eprintln!("[compose] {run_id} belongs to {0}; using {0}", owner.name());
+        Ok(owner)
+    }
+}
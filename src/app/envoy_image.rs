@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+use crate::infra::engine::Engine;
+use crate::support::images::{ensure_image, ImageStore};
+
+const PULL_BACKOFF: Duration = Duration::from_secs(2);
+
+impl ImageStore for Engine {
+    fn image_exists(&self, image: &str) -> bool {
+        Self::image_exists(self, image)
+    }
+
+    fn pull_image(&self, image: &str) -> Result<(), String> {
+        Self::pull_image(self, image)
+    }
+
+    fn local_image_tags(&self, repository: &str) -> Vec<String> {
+        Self::local_image_tags(self, repository)
+    }
+}
+
+pub fn ensure_envoy_image(engine: &Engine, image: &str) -> Result<String, String> {
+    ensure_image(engine, image, PULL_BACKOFF)
+}
@@ -0,0 +1,190 @@
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::domain::Scope;
+use crate::infra::engine::{Engine, RunContainers};
+use crate::infra::forward::{first_reachable, PortForward};
+use crate::infra::ui::{UiEndpoint, FORWARDS_PATH};
+use crate::infra::ui_daemon::post_run_api;
+use crate::support::forward::{relay_args, relay_command, relay_name, Forward, ForwardTarget};
+use crate::support::oneoff::{attachable_networks, OneOffSpec};
+use crate::support::run::project_name_from_run_id;
+use crate::support::settings::{env_or_default, FORWARD_RELAY_IMAGE_ENV};
+
+use super::mute::run_ui_endpoint;
+use super::output::{self, OutputFormat};
+use super::{
+    load_run_containers, resolve_run_id, run_metadata_from_containers, runner, setup_signals,
+};
+
+const STOP_POLL: Duration = Duration::from_millis(200);
+
+pub struct ForwardArgs {
+    run_id: Option<String>,
+    target: Option<String>,
+    local_port: Option<String>,
+}
+
+pub fn parse_forward_args(args: &[String]) -> ForwardArgs {
+    let mut positional = args.iter().cloned();
+    ForwardArgs {
+        run_id: positional.next(),
+        target: positional.next(),
+        local_port: positional.next(),
+    }
+}
+
+pub fn run_forward(
+    engine: &Engine,
+    args: ForwardArgs,
+    output: OutputFormat,
+) -> Result<i32, String> {
+    let run_id = resolve_run_id(engine, "forward", args.run_id)?;
+    let target = args.target.ok_or_else(|| {
+        "Usage: sanelens forward <run_id|tag> <service>:<port> [local_port]".to_string()
+    })?;
+    let target = ForwardTarget::parse(&target, args.local_port.as_deref())?;
+    let (address, relay) = reachable_address(engine, &run_id, &target)?;
+    let mut forward = PortForward::open(target.local_port, address)?;
+    let local = forward.local_addr();
+    let stop_event = Arc::new(AtomicBool::new(false));
+    let exit_code = Arc::new(AtomicI32::new(0));
+    setup_signals(runner::SignalContext::new(
+        stop_event.clone(),
+        Arc::new(AtomicBool::new(false)),
+        exit_code.clone(),
+        Arc::new(runner::ProcessHandles::new()),
+    ));
+
+    let entry = Forward {
+        service: target.service.clone(),
+        host: local.ip().to_string(),
+        port: local.port(),
+    };
+    let ui = register(engine, &run_id, &entry);
+    if output.is_json() {
+        output::print_json(&serde_json::json!({
+            "run_id": run_id,
+            "service": target.service,
+            "port": target.port,
+            "local": local.to_string(),
+            "target": address.to_string(),
+        }));
+    } else {
+        let _ = writeln!(
+            io::stdout(),
+            "[compose] forwarding {local} -> {}:{} ({address}); press Ctrl-C to stop",
+            target.service,
+            target.port
+        );
+    }
+    while !stop_event.load(Ordering::SeqCst) {
+        thread::sleep(STOP_POLL);
+    }
+    if let Some(ui) = ui {
+        let body = serde_json::json!({ "remove": [entry] }).to_string();
+        let _ = post_run_api(&ui, FORWARDS_PATH, &body);
+    }
+    forward.stop();
+    drop(relay);
+    Ok(exit_code.load(Ordering::SeqCst))
+}
+
+// Sidecar on the run's networks, used when container IPs are not routable
+// from the host (Docker Desktop, podman machine).
+struct Relay {
+    engine: Engine,
+    id: String,
+}
+
+impl Relay {
+    fn start(engine: &Engine, run_id: &str, target: &ForwardTarget) -> Result<Self, String> {
+        let containers = RunContainers::new(engine.clone(), run_id.to_string());
+        let networks = attachable_networks(&containers.service_networks(&target.service));
+        if networks.is_empty() {
+            return Err(format!(
+                "Service {} has no network to relay through.",
+                target.service
+            ));
+        }
+        let metadata = run_metadata_from_containers(
+            run_id,
+            &load_run_containers(engine, run_id, Scope::Running)?,
+        );
+        let spec = OneOffSpec {
+            run_id: run_id.to_string(),
+            project_name: metadata
+                .project_name
+                .unwrap_or_else(|| project_name_from_run_id(run_id)),
+            name: relay_name(&target.service),
+            image: env_or_default(FORWARD_RELAY_IMAGE_ENV).unwrap_or_default(),
+            networks,
+            env: Vec::new(),
+            command: relay_command(target),
+            tty: false,
+        };
+        let relay = Self {
+            engine: engine.clone(),
+            id: engine.start_container(&relay_args(&spec, target.port))?,
+        };
+        Ok(relay)
+    }
+
+    fn address(&self, port: u16) -> Result<SocketAddr, String> {
+        self.engine
+            .published_port(&self.id, port)
+            .ok_or_else(|| format!("relay container {} published no port", self.id))
+    }
+}
+
+impl Drop for Relay {
+    fn drop(&mut self) {
+        if let Err(err) = self
+            .engine
+            .remove_containers(std::slice::from_ref(&self.id))
+        {
+            eprintln!("[compose] forward: {err}");
+        }
+    }
+}
+
+fn reachable_address(
+    engine: &Engine,
+    run_id: &str,
+    target: &ForwardTarget,
+) -> Result<(SocketAddr, Option<Relay>), String> {
+    let ips = RunContainers::new(engine.clone(), run_id.to_string()).service_ips(&target.service);
+    if ips.is_empty() {
+        return Err(format!(
+            "Service {} has no running container in run {run_id}.",
+            target.service
+        ));
+    }
+    let candidates: Vec<SocketAddr> = ips
+        .iter()
+        .map(|ip| SocketAddr::new(*ip, target.port))
+        .collect();
+    if let Some(address) = first_reachable(&candidates) {
+        return Ok((address, None));
+    }
+    let relay = Relay::start(engine, run_id, target)?;
+    let address = relay.address(target.port)?;
+    Ok((address, Some(relay)))
+}
+
+fn register(engine: &Engine, run_id: &str, entry: &Forward) -> Option<UiEndpoint> {
+    let body = serde_json::json!({ "add": [entry] }).to_string();
+    let registered = run_ui_endpoint(engine, run_id)
+        .and_then(|ui| post_run_api(&ui, FORWARDS_PATH, &body).map(|_| ui));
+    match registered {
+        Ok(ui) => Some(ui),
+        Err(err) => {
+            eprintln!("[compose] forward not listed in the log UI: {err}");
+            None
+        }
+    }
+}
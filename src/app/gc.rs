@@ -0,0 +1,212 @@
+use std::collections::{BTreeSet, HashSet};
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use crate::domain::Scope;
+use crate::infra::engine::Engine;
+use crate::support::cleanup::CleanupResult;
+use crate::support::run::{
+    is_run_stopped, is_stale, merge_engine_runs, orphaned_run_dirs, project_name_from_run_id,
+};
+use crate::support::shutdown::ShutdownOrder;
+
+use super::engines::EngineTarget;
+use super::output::{print_json, OutputFormat};
+use super::{
+    collect_active_runs, collect_runs, load_run_containers, run_down, DownOptions, RunMetadata,
+};
+
+pub const DEFAULT_GC_TTL: Duration = Duration::from_hours(1);
+const DERIVED_ROOT: &str = ".sanelens";
+
+pub fn run_down_all(targets: &[&EngineTarget], keep_volumes: bool, output: OutputFormat) -> i32 {
+    let runs = merge_engine_runs(
+        targets
+            .iter()
+            .map(|target| collect_runs(&target.engine, Scope::All)),
+        |run| run.run_id.as_str(),
+    );
+    let mut results: Vec<CleanupResult> = Vec::new();
+    for (idx, run) in runs {
+        let Some(target) = targets.get(idx) else {
+            continue;
+        };
+        let outcome = teardown_run(&target.engine, &target.compose_cmd, &run, keep_volumes);
+        results.push(CleanupResult::new(&run.run_id, outcome));
+    }
+    let exit_code = i32::from(results.iter().any(|result| result.error.is_some()));
+    if output.is_json() {
+        print_json(&serde_json::json!({ "runs": results }));
+        return exit_code;
+    }
+    let mut stdout = io::stdout();
+    if results.is_empty() {
+        let _ = writeln!(stdout, "No runs.");
+    }
+    for result in &results {
+        match &result.error {
+            None => {
+                let _ = writeln!(stdout, "[compose] removed run {}", result.target);
+            }
+            Some(err) => eprintln!("[compose] {err}"),
+        }
+    }
+    exit_code
+}
+
+pub struct GcOptions {
+    pub ttl: Duration,
+    pub dry_run: bool,
+    pub keep_volumes: bool,
+    pub output: OutputFormat,
+    pub run_root: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct GcReport {
+    dry_run: bool,
+    runs: Vec<CleanupResult>,
+    dirs: Vec<CleanupResult>,
+}
+
+pub fn run_gc(engine: &Engine, compose_cmd: &[String], options: &GcOptions) -> i32 {
+    let active = collect_active_runs(engine);
+    let active_ids: HashSet<&str> = active.iter().map(|run| run.run_id.as_str()).collect();
+    let runs = collect_runs(engine, Scope::All);
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let stale_runs = runs.iter().filter(|run| {
+        !active_ids.contains(run.run_id.as_str())
+            && !run_dir(run).is_some_and(is_run_stopped)
+            && is_stale(run.started_at_ts, now, options.ttl)
+    });
+    let mut report = GcReport {
+        dry_run: options.dry_run,
+        runs: Vec::new(),
+        dirs: Vec::new(),
+    };
+    for run in stale_runs {
+        report.runs.push(if options.dry_run {
+            CleanupResult::planned(&run.run_id)
+        } else {
+            let outcome = teardown_run(engine, compose_cmd, run, options.keep_volumes);
+            CleanupResult::new(&run.run_id, outcome)
+        });
+    }
+
+    let live_projects: HashSet<String> = active.iter().map(run_project_name).collect();
+    for dir in orphaned_derived_dirs(&runs, &live_projects, options) {
+        let target = dir.display().to_string();
+        report.dirs.push(if options.dry_run {
+            CleanupResult::planned(target)
+        } else {
+            let outcome =
+                fs::remove_dir_all(&dir).map_err(|err| format!("failed to remove {target}: {err}"));
+            CleanupResult::new(target, outcome)
+        });
+    }
+
+    let failed = report
+        .runs
+        .iter()
+        .chain(&report.dirs)
+        .any(|result| result.error.is_some());
+    if options.output.is_json() {
+        print_json(&report);
+    } else {
+        print_gc_report(&report);
+    }
+    i32::from(failed)
+}
+
+fn print_gc_report(report: &GcReport) {
+    let mut stdout = io::stdout();
+    if report.runs.is_empty() && report.dirs.is_empty() {
+        let _ = writeln!(stdout, "Nothing to clean up.");
+        return;
+    }
+    for (result, label) in report
+        .runs
+        .iter()
+        .map(|result| (result, "stopped run "))
+        .chain(report.dirs.iter().map(|result| (result, "")))
+    {
+        if report.dry_run {
+            let _ = writeln!(stdout, "would remove {label}{}", result.target);
+        } else if let Some(err) = &result.error {
+            eprintln!("[compose] {err}");
+        } else {
+            let _ = writeln!(stdout, "[compose] removed {label}{}", result.target);
+        }
+    }
+}
+
+fn teardown_run(
+    engine: &Engine,
+    compose_cmd: &[String],
+    run: &RunMetadata,
+    keep_volumes: bool,
+) -> Result<(), String> {
+    let derived_exists = run
+        .derived_compose
+        .as_deref()
+        .is_some_and(|path| Path::new(path).exists());
+    if derived_exists {
+        let down = DownOptions {
+            keep_volumes,
+            shutdown_order: ShutdownOrder::None,
+        };
+        return run_down(engine, compose_cmd, &run.run_id, down);
+    }
+    let containers = load_run_containers(engine, &run.run_id, Scope::All)?;
+    let ids: Vec<String> = containers
+        .into_iter()
+        .map(|container| container.id)
+        .collect();
+    engine.remove_containers(&ids)
+}
+
+fn run_dir(run: &RunMetadata) -> Option<&Path> {
+    run.derived_compose
+        .as_deref()
+        .and_then(|file| Path::new(file).parent())
+}
+
+fn run_project_name(run: &RunMetadata) -> String {
+    run.project_name
+        .clone()
+        .unwrap_or_else(|| project_name_from_run_id(&run.run_id))
+}
+
+fn orphaned_derived_dirs(
+    runs: &[RunMetadata],
+    live_projects: &HashSet<String>,
+    options: &GcOptions,
+) -> Vec<PathBuf> {
+    let mut roots: BTreeSet<PathBuf> = options.run_root.iter().cloned().collect();
+    if let Ok(cwd) = env::current_dir() {
+        roots.insert(cwd.join(DERIVED_ROOT));
+    }
+    for run in runs {
+        if let Some(dir) = run
+            .compose_file
+            .as_deref()
+            .and_then(|file| Path::new(file).parent())
+        {
+            roots.insert(dir.join(DERIVED_ROOT));
+        }
+        if let Some(root) = run_dir(run).and_then(Path::parent) {
+            roots.insert(root.to_path_buf());
+        }
+    }
+
+    roots
+        .iter()
+        .flat_map(|root| orphaned_run_dirs(root, live_projects, options.ttl))
+        .collect()
+}
@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{never, select, tick, Receiver};
+
+use crate::domain::traffic::TrafficCall;
+use crate::domain::Scope;
+use crate::infra::engine::{ContainerInfo, Engine};
+use crate::support::clock::now_ms;
+use crate::support::events::{CallSampler, EventBus, EventKind, LifecycleEvent};
+use crate::support::traffic::TrafficHub;
+
+use super::crash::{CrashCapture, CrashLoopWatch};
+
+const HEALTH_POLL: Duration = Duration::from_secs(2);
+const CALL_SAMPLE_INTERVAL_MS: u64 = 1_000;
+
+pub struct EventContext {
+    pub bus: Arc<EventBus>,
+    pub engine: Engine,
+    pub run_id: String,
+    pub stop_event: Arc<AtomicBool>,
+    pub crash: Option<CrashCapture>,
+}
+
+pub fn start_hooks(command: String, bus: &EventBus) -> thread::JoinHandle<()> {
+    let events = bus.subscribe();
+    thread::spawn(move || {
+        for event in &events {
+            run_hook(&command, &event);
+        }
+    })
+}
+
+pub fn watch_events(
+    context: EventContext,
+    traffic_hub: Option<&TrafficHub>,
+) -> thread::JoinHandle<()> {
+    let calls = traffic_hub.map_or_else(never, |hub| hub.register_call_client().0);
+    thread::spawn(move || {
+        let mut context = context;
+        let crash_loops = context.crash.take().map(CrashLoopWatch::new);
+        watch_run(&context, &calls, crash_loops);
+    })
+}
+
+pub fn publish(bus: &EventBus, run_id: &str, kind: EventKind) {
+    bus.publish(&LifecycleEvent {
+        run_id: run_id.to_string(),
+        at_ms: now_ms(),
+        kind,
+    });
+}
+
+fn watch_run(
+    context: &EventContext,
+    calls: &Receiver<TrafficCall>,
+    mut crash_loops: Option<CrashLoopWatch>,
+) {
+    let mut sampler = CallSampler::new(CALL_SAMPLE_INTERVAL_MS);
+    let mut healthy = HashSet::new();
+    let health_tick = tick(HEALTH_POLL);
+    while !context.stop_event.load(Ordering::SeqCst) {
+        select! {
+            recv(calls) -> call => match call {
+                Ok(call) if sampler.admit(&call, now_ms()) => {
+                    let kind = EventKind::CallObserved { call: Box::new(call) };
+                    publish(&context.bus, &context.run_id, kind);
+                }
+                Ok(_) => {}
+                Err(_) => break,
+            },
+            recv(health_tick) -> _ => poll_containers(context, &mut healthy, crash_loops.as_mut()),
+        }
+    }
+}
+
+fn poll_containers(
+    context: &EventContext,
+    healthy: &mut HashSet<String>,
+    crash_loops: Option<&mut CrashLoopWatch>,
+) {
+    let ids = context
+        .engine
+        .collect_run_container_ids(&context.run_id, Scope::All);
+    let containers = context.engine.inspect_containers(&ids);
+    if let Some(crash_loops) = crash_loops {
+        crash_loops.observe(context, &containers);
+    }
+    report_healthy(context, healthy, containers);
+}
+
+fn report_healthy(
+    context: &EventContext,
+    healthy: &mut HashSet<String>,
+    containers: Vec<ContainerInfo>,
+) {
+    for info in containers {
+        if !info.is_ready() || info.is_proxy() {
+            continue;
+        }
+        let Some(service) = info.service else {
+            continue;
+        };
+        if healthy.insert(info.id.clone()) {
+            let kind = EventKind::ServiceHealthy {
+                service,
+                container: info.id,
+            };
+            publish(&context.bus, &context.run_id, kind);
+        }
+    }
+}
+
+fn run_hook(command: &str, event: &LifecycleEvent) {
+    let Ok(mut payload) = serde_json::to_vec(event) else {
+        return;
+    };
+    payload.push(b'\n');
+    let spawned = Command::new("sh")
+        .args(["-c", command])
+        .env("SANELENS_EVENT", event.kind.name())
+        .stdin(Stdio::piped())
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(err) => {
+            eprintln!("[hook] failed to start hook: {err}");
+            return;
+        }
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(&payload);
+    }
+    match child.wait() {
+        Ok(status) if !status.success() => {
+            eprintln!("[hook] hook for {} exited with {status}", event.kind.name());
+        }
+        Ok(_) => {}
+        Err(err) => eprintln!("[hook] hook for {} failed: {err}", event.kind.name()),
+    }
+}
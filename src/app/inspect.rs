@@ -0,0 +1,150 @@
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::domain::Scope;
+use crate::infra::derive::run_dir;
+use crate::infra::engine::Engine;
+use crate::support::args::take_flag;
+use crate::support::inspect::{
+    container_service, is_proxy_container, latest_snapshot, summarize_container, ContainerSnapshot,
+    INSPECT_DIR,
+};
+use crate::support::run::project_name_from_run_id;
+
+use super::output::{self, OutputFormat};
+use super::{resolve_run_id, run_metadata_from_containers};
+
+pub struct InspectArgs {
+    run_id: Option<String>,
+    service: Option<String>,
+    snapshot: bool,
+}
+
+pub fn parse_inspect_args(args: &[String]) -> InspectArgs {
+    let (args, snapshot) = take_flag(args, "--snapshot");
+    let mut positional = args.into_iter();
+    InspectArgs {
+        run_id: positional.next(),
+        service: positional.next(),
+        snapshot,
+    }
+}
+
+pub fn write_snapshot(engine: &Engine, run_id: &str, run_dir: &Path, label: &str) {
+    let ids = engine.collect_run_container_ids(run_id, Scope::All);
+    if ids.is_empty() {
+        return;
+    }
+    let dir = run_dir.join(INSPECT_DIR);
+    let path = dir.join(format!("{label}.json"));
+    let payload = serde_json::to_vec_pretty(&engine.inspect_raw(&ids)).unwrap_or_default();
+    if let Err(err) = fs::create_dir_all(&dir).and_then(|()| fs::write(&path, payload)) {
+        eprintln!("[compose] failed to write {}: {err}", path.display());
+    }
+}
+
+pub fn run_inspect(
+    engine: &Engine,
+    args: InspectArgs,
+    run_root: Option<&Path>,
+    output: OutputFormat,
+) -> Result<i32, String> {
+    let run_ref = args.run_id.clone();
+    let run_id = resolve_run_id(engine, "inspect", args.run_id)
+        .or_else(|err| run_ref.filter(|_| args.snapshot).ok_or(err))?;
+    let service = args
+        .service
+        .ok_or_else(|| "Usage: sanelens inspect <run_id|tag> <service> [--snapshot]".to_string())?;
+    let ids = engine.collect_run_container_ids(&run_id, Scope::All);
+    let (values, source) = if ids.is_empty() || args.snapshot {
+        let dir = snapshot_run_dir(engine, &run_id, &ids, run_root)?;
+        let path = latest_snapshot(&dir)
+            .ok_or_else(|| format!("Run {run_id} has no stored inspect snapshot."))?;
+        let values: Vec<Value> = fs::read(&path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .ok_or_else(|| format!("failed to read {}", path.display()))?;
+        (values, Some(path))
+    } else {
+        (engine.inspect_raw(&ids), None)
+    };
+    let snapshots: Vec<ContainerSnapshot> = values
+        .iter()
+        .filter(|value| !is_proxy_container(value))
+        .filter(|value| container_service(value).as_deref() == Some(service.as_str()))
+        .map(summarize_container)
+        .collect();
+    if snapshots.is_empty() {
+        return Err(format!("Service {service} not found in run {run_id}."));
+    }
+    if output.is_json() {
+        output::print_json(&serde_json::json!({
+            "run_id": run_id,
+            "snapshot": source.map(|path| path.display().to_string()),
+            "containers": snapshots,
+        }));
+        return Ok(0);
+    }
+    if let Some(path) = source {
+        eprintln!("[compose] from snapshot {}", path.display());
+    }
+    print_snapshots(&snapshots);
+    Ok(0)
+}
+
+fn snapshot_run_dir(
+    engine: &Engine,
+    run_id: &str,
+    ids: &[String],
+    run_root: Option<&Path>,
+) -> Result<PathBuf, String> {
+    let labelled = run_metadata_from_containers(run_id, &engine.inspect_containers(ids))
+        .derived_compose
+        .and_then(|path| Path::new(&path).parent().map(Path::to_path_buf));
+    if let Some(dir) = labelled {
+        return Ok(dir);
+    }
+    let cwd =
+        env::current_dir().map_err(|err| format!("failed to resolve working directory: {err}"))?;
+    Ok(run_dir(run_root, &cwd, &project_name_from_run_id(run_id)))
+}
+
+fn print_snapshots(snapshots: &[ContainerSnapshot]) {
+    let mut stdout = io::stdout();
+    for snapshot in snapshots {
+        let id: String = snapshot.container.chars().take(12).collect();
+        let status = snapshot.status.as_deref().unwrap_or("unknown");
+        let exit = snapshot
+            .exit_code
+            .map(|code| format!(" (exit {code})"))
+            .unwrap_or_default();
+        let restart = snapshot.restart_policy.as_deref().unwrap_or("no");
+        let _ = writeln!(
+            stdout,
+            "{} ({id})  {status}{exit}  restart: {restart}",
+            snapshot.service.as_deref().unwrap_or("?")
+        );
+        let env: Vec<String> = snapshot
+            .env
+            .iter()
+            .map(|var| format!("{}={}", var.key, var.value))
+            .collect();
+        for (title, lines) in [
+            ("mounts", &snapshot.mounts),
+            ("networks", &snapshot.networks),
+            ("env", &env),
+        ] {
+            if lines.is_empty() {
+                continue;
+            }
+            let _ = writeln!(stdout, "  {title}:");
+            for line in lines {
+                let _ = writeln!(stdout, "    {line}");
+            }
+        }
+    }
+}
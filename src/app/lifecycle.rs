@@ -0,0 +1,80 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::domain::Scope;
+use crate::infra::engine::Engine;
+use crate::support::args::take_flag;
+use crate::support::cleanup::CleanupResult;
+use crate::support::run::set_run_stopped;
+
+use super::logs::{self, LogsArgs, LogsOptions};
+use super::output::{print_json, OutputFormat};
+use super::{load_run_containers, resolve_run_id, run_metadata_from_containers};
+
+pub fn run_stop(
+    engine: &Engine,
+    compose_cmd: &[String],
+    run_ref: Option<String>,
+    output: OutputFormat,
+) -> Result<i32, String> {
+    let run_id = resolve_run_id(engine, "stop", run_ref)?;
+    let outcome = compose_action(engine, compose_cmd, &run_id, "stop");
+    if output.is_json() {
+        print_json(&CleanupResult::new(&run_id, outcome));
+        return Ok(0);
+    }
+    outcome?;
+    let _ = writeln!(
+        io::stdout(),
+        "[compose] stopped run {run_id}; resume it with `sanelens start {run_id}`"
+    );
+    Ok(0)
+}
+
+pub struct StartArgs {
+    logs: LogsArgs,
+    detach: bool,
+}
+
+pub fn parse_start_args(args: &[String]) -> StartArgs {
+    let (args, detach) = take_flag(args, "--detach");
+    let (args, short_detach) = take_flag(&args, "-d");
+    let mut logs = logs::parse_logs_args(&args);
+    logs.traffic = true;
+    StartArgs {
+        logs,
+        detach: detach || short_detach,
+    }
+}
+
+pub fn run_start(
+    engine: &Engine,
+    compose_cmd: &[String],
+    mut args: StartArgs,
+    options: LogsOptions,
+) -> Result<i32, String> {
+    let run_id = resolve_run_id(engine, "start", args.logs.run_id.take())?;
+    compose_action(engine, compose_cmd, &run_id, "start")?;
+    eprintln!("[compose] started run {run_id}");
+    if args.detach {
+        return Ok(0);
+    }
+    args.logs.run_id = Some(run_id);
+    logs::run_logs(engine, &args.logs, options)
+}
+
+fn compose_action(
+    engine: &Engine,
+    compose_cmd: &[String],
+    run_id: &str,
+    action: &str,
+) -> Result<(), String> {
+    let containers = load_run_containers(engine, run_id, Scope::All)?;
+    let derived_compose = run_metadata_from_containers(run_id, &containers)
+        .derived_compose
+        .ok_or_else(|| format!("Run {run_id} is missing derived compose metadata."))?;
+    Engine::compose_lifecycle(compose_cmd, &derived_compose, action)?;
+    Path::new(&derived_compose)
+        .parent()
+        .map_or(Ok(()), |run_dir| set_run_stopped(run_dir, action == "stop"))
+}
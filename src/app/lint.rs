@@ -0,0 +1,51 @@
+use std::fs;
+use std::io::{self, Write};
+
+use serde_yaml::Value;
+
+use crate::infra::derive::run_compose_config;
+use crate::infra::lint::{lint_compose, Severity};
+use crate::support::settings::{compose_file, process_env};
+
+use super::output::{self, OutputFormat};
+use super::resolve_compose_file;
+
+pub fn run_lint(
+    compose_cmd: &[String],
+    args: &[String],
+    format: OutputFormat,
+) -> Result<i32, String> {
+    let (compose_file, _) = resolve_compose_file(&compose_file(args, &process_env))?;
+    let contents = fs::read_to_string(&compose_file)
+        .map_err(|err| format!("failed to read {compose_file}: {err}"))?;
+    let raw: Value = serde_yaml::from_str(&contents)
+        .map_err(|err| format!("failed to parse {compose_file}: {err}"))?;
+    let mut cmd = compose_cmd.to_vec();
+    cmd.extend(["-f".to_string(), compose_file, "config".to_string()]);
+    let resolved = run_compose_config(&cmd)?;
+
+    let findings = lint_compose(&raw, &resolved);
+    let failed = findings
+        .iter()
+        .any(|finding| finding.severity == Severity::Error);
+    if format.is_json() {
+        output::print_json(&serde_json::json!({ "findings": findings }));
+    } else if findings.is_empty() {
+        let _ = writeln!(io::stdout(), "No problems found.");
+    } else {
+        let mut stdout = io::stdout();
+        for finding in &findings {
+            let severity = match finding.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+            let service = finding.service.as_deref().unwrap_or("-");
+            let _ = writeln!(
+                stdout,
+                "{severity:<7}  {service}  [{}] {}",
+                finding.code, finding.message
+            );
+        }
+    }
+    Ok(i32::from(failed))
+}
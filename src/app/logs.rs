@@ -0,0 +1,235 @@
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::domain::{OutputStream, Scope};
+use crate::infra::engine::{Engine, RunContainers};
+use crate::infra::ui::{UiBind, UiServer};
+use crate::support::args::{parse_duration, take_flag, take_options};
+use crate::support::constants::HISTORY_LIMIT;
+use crate::support::log_files::{LogFileOptions, LogFileSink};
+use crate::support::log_filter::{LogFilter, LogLevel};
+use crate::support::log_sink::LogSinkTarget;
+use crate::support::logging::LogHub;
+use crate::support::run::project_name_from_run_id;
+use crate::support::skew::ClockSkewPolicy;
+use crate::support::traffic::TrafficHub;
+
+use super::{
+    load_run_containers, open_log_file_sink, open_log_sink, resolve_run_id,
+    run_metadata_from_containers, run_services_from_containers, run_tap_dir, runner,
+    session_ui_run, setup_signals, start_session_ui, RunMetadata, RunServices,
+};
+
+pub struct LogsArgs {
+    pub run_id: Option<String>,
+    pub traffic: bool,
+    no_ui: bool,
+    services: Vec<String>,
+    grep: Vec<String>,
+    level: Option<String>,
+    since: Option<String>,
+    stream: Option<String>,
+}
+
+pub struct LogsOptions {
+    pub ui_bind: UiBind,
+    pub log_files: Option<LogFileOptions>,
+    pub log_sink: Option<LogSinkTarget>,
+    pub log_reorder: Duration,
+    pub log_dedup: Duration,
+    pub log_skew: ClockSkewPolicy,
+}
+
+pub fn parse_logs_args(args: &[String]) -> LogsArgs {
+    let (args, no_ui) = take_flag(args, "--no-ui");
+    let (args, services) = take_options(&args, "--service");
+    let (args, grep) = take_options(&args, "--grep");
+    let (args, mut level) = take_options(&args, "--level");
+    let (args, mut since) = take_options(&args, "--since");
+    let (args, mut stream) = take_options(&args, "--stream");
+    LogsArgs {
+        run_id: args.into_iter().next(),
+        traffic: false,
+        no_ui,
+        services,
+        grep,
+        level: level.pop(),
+        since: since.pop(),
+        stream: stream.pop(),
+    }
+}
+
+fn build_filter(args: &LogsArgs) -> Result<(LogFilter, Option<i64>), String> {
+    let min_level = args
+        .level
+        .as_deref()
+        .map(|value| {
+            LogLevel::parse(value).ok_or_else(|| {
+                format!("Invalid --level '{value}'. Use trace, debug, info, warn, error or fatal.")
+            })
+        })
+        .transpose()?;
+    let since = args
+        .since
+        .as_deref()
+        .map(|value| {
+            parse_since(value).ok_or_else(|| {
+                format!("Invalid --since '{value}'. Use e.g. 10m, 2h or an RFC3339 timestamp.")
+            })
+        })
+        .transpose()?;
+    let stream = args
+        .stream
+        .as_deref()
+        .map(|value| {
+            OutputStream::parse(value)
+                .ok_or_else(|| format!("Invalid --stream '{value}'. Use stdout or stderr."))
+        })
+        .transpose()?;
+    let filter = LogFilter {
+        services: args.services.clone(),
+        grep: args.grep.iter().map(|token| token.to_lowercase()).collect(),
+        min_level,
+        stream,
+    };
+    Ok((filter, since))
+}
+
+fn parse_since(value: &str) -> Option<i64> {
+    if let Ok(parsed) = OffsetDateTime::parse(value, &Rfc3339) {
+        return Some(parsed.unix_timestamp());
+    }
+    let ago = i64::try_from(parse_duration(value)?.as_secs()).ok()?;
+    Some(OffsetDateTime::now_utc().unix_timestamp() - ago)
+}
+
+pub fn run_logs(engine: &Engine, args: &LogsArgs, mut options: LogsOptions) -> Result<i32, String> {
+    let run_id: &str = &resolve_run_id(engine, "logs", args.run_id.clone())?;
+    let (filter, since) = build_filter(args)?;
+    let containers = load_run_containers(engine, run_id, Scope::Running)?;
+    let metadata = run_metadata_from_containers(run_id, &containers);
+    let services = run_services_from_containers(&containers);
+    let file_sink = open_log_file_sink(options.log_files.take(), run_id)?;
+    let log_sink = open_log_sink(options.log_sink.take(), run_id)?;
+    let session = LogSession::install();
+    let log_hub = session_log_hub(&options, file_sink.clone());
+
+    let mut follower = runner::LogFollower::new(
+        engine.clone(),
+        run_id.to_string(),
+        run_project_name(&metadata),
+        session.stop_event.clone(),
+        Some(log_hub.clone()),
+        session.handles.clone(),
+        services.proxy_services.clone(),
+        services.service_aliases.clone(),
+    );
+    follower.set_file_sink(file_sink);
+    follower.set_log_sink(log_sink);
+    follower.set_filter(filter, since);
+    let traffic_hub = args.traffic.then(|| Arc::new(TrafficHub::new()));
+    let traffic_thread = traffic_hub.clone().map(|hub| {
+        let mut traffic = traffic_follower(engine, &metadata, services, &session, hub);
+        traffic.set_container_watch(follower.container_watch());
+        thread::spawn(move || traffic.follow())
+    });
+    let ui_server = if args.no_ui {
+        None
+    } else {
+        let run = session_ui_run(
+            &metadata,
+            log_hub,
+            traffic_hub,
+            Arc::new(RunContainers::new(engine.clone(), run_id.to_string())),
+        );
+        start_session_ui(run, &session.stop_event, &options.ui_bind)
+    };
+    let exit = follower.follow_logs(true, &mut Vec::new());
+    Ok(session.finish(exit, traffic_thread, ui_server))
+}
+
+struct LogSession {
+    stop_event: Arc<AtomicBool>,
+    exit_code: Arc<AtomicI32>,
+    handles: Arc<runner::ProcessHandles>,
+}
+
+impl LogSession {
+    fn install() -> Self {
+        let session = Self {
+            stop_event: Arc::new(AtomicBool::new(false)),
+            exit_code: Arc::new(AtomicI32::new(0)),
+            handles: Arc::new(runner::ProcessHandles::new()),
+        };
+        setup_signals(runner::SignalContext::new(
+            session.stop_event.clone(),
+            Arc::new(AtomicBool::new(false)),
+            session.exit_code.clone(),
+            session.handles.clone(),
+        ));
+        session
+    }
+
+    fn finish(
+        &self,
+        exit: i32,
+        traffic_thread: Option<JoinHandle<i32>>,
+        mut ui_server: Option<UiServer>,
+    ) -> i32 {
+        self.stop_event.store(true, Ordering::SeqCst);
+        self.handles.stop_log_procs();
+        if let Some(thread) = traffic_thread {
+            let _ = thread.join();
+        }
+        if let Some(server) = ui_server.as_mut() {
+            server.stop();
+        }
+        let signal_exit = self.exit_code.load(Ordering::SeqCst);
+        if signal_exit != 0 {
+            signal_exit
+        } else {
+            exit
+        }
+    }
+}
+
+fn session_log_hub(options: &LogsOptions, file_sink: Option<Arc<LogFileSink>>) -> Arc<LogHub> {
+    let log_hub = LogHub::with_reorder(HISTORY_LIMIT, options.log_reorder);
+    log_hub.set_dedup(options.log_dedup);
+    log_hub.set_clock_skew(options.log_skew);
+    log_hub.set_annotation_sink(file_sink);
+    log_hub
+}
+
+fn run_project_name(metadata: &RunMetadata) -> String {
+    metadata
+        .project_name
+        .clone()
+        .unwrap_or_else(|| project_name_from_run_id(&metadata.run_id))
+}
+
+fn traffic_follower(
+    engine: &Engine,
+    metadata: &RunMetadata,
+    services: RunServices,
+    session: &LogSession,
+    hub: Arc<TrafficHub>,
+) -> runner::TrafficFollower {
+    runner::TrafficFollower::new(
+        engine.clone(),
+        metadata.run_id.clone(),
+        run_project_name(metadata),
+        session.stop_event.clone(),
+        session.handles.clone(),
+        hub,
+        services.proxy_services,
+        services.service_aliases,
+        services.egress_proxy,
+        run_tap_dir(metadata),
+    )
+}
@@ -1,37 +1,96 @@
+mod alerts;
+mod bench;
+mod chaos;
+mod completions;
+mod config;
+mod crash;
+mod daemon;
+mod derive;
+mod engines;
+mod envoy_image;
+mod forward;
+mod gc;
+mod hooks;
+mod inspect;
+mod lifecycle;
+mod lint;
+mod logs;
+mod mute;
+mod oneoff;
+mod open;
+mod output;
+mod platform;
+mod ps;
+mod record;
+mod render;
+mod run_lock;
 mod runner;
+mod stall;
+mod top;
+mod traffic_logs;
+mod verify;
+mod wait;
 mod watchdog;
 
 use signal_hook::consts::signal::{SIGINT, SIGTERM};
 use signal_hook::iterator::Signals;
 use std::collections::{HashMap, HashSet};
 use std::env;
-use std::fs;
-use std::io::{self, Write};
-use std::path::Path;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
+use serde::Serialize;
 use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
 
-use crate::infra::compose::detect_compose_cmd;
-use crate::infra::engine::{CleanupContext, ContainerInfo, Engine};
-use crate::infra::ui::{open_browser, UiServer};
+use crate::domain::traffic::{versioned, TrafficCall, NETWORK_TAG};
+use crate::infra::compose::{detect_compose_cmd, ComposeProvider, ComposeSelection};
+use crate::infra::derive::{buffer_stdin_compose, run_dir, EnvOverride, ResourceLimit};
+use crate::infra::effective::load_effective_config;
+use crate::infra::engine::{CleanupContext, ContainerInfo, Engine, RunContainers};
+use crate::infra::run_lock::RunLock;
+use crate::infra::ui::{open_browser, UiBind, UiRun, UiServer};
+use crate::infra::verify::{load_expectations, Expectations, Verifier};
 use crate::support::args::{
-    extract_compose_file_arg, extract_engine_arg, extract_subcommand, extract_traffic_arg,
-    first_compose_file, strip_project_name_args,
+    extract_engine_arg, extract_subcommand, extract_traffic_arg, is_env_truthy, is_truthy,
+    parse_byte_size, parse_duration, replace_compose_file_arg, strip_project_name_args, take_flag,
+    take_option, take_repeated_option, STDIN_COMPOSE,
 };
+use crate::support::cleanup::CleanupResult;
 use crate::support::constants::{
-    COMPOSE_FILE_LABEL, DERIVED_COMPOSE_LABEL, PROJECT_NAME_LABEL, PROXY_EGRESS_LABEL, PROXY_LABEL,
-    RUN_ID_LABEL, SERVICE_LABEL, STARTED_AT_LABEL,
+    COMPOSE_FILE_LABEL, DEFAULT_MAX_BODY_BYTES, DERIVED_COMPOSE_LABEL, KEEP_VOLUMES_ENV,
+    PROJECT_NAME_LABEL, PROXY_EGRESS_LABEL, PROXY_LABEL, RUN_ID_LABEL, SERVICE_LABEL,
+    STARTED_AT_LABEL, TAG_LABEL,
 };
-use crate::support::logging::LogHub;
-use crate::support::run::{new_run_id, project_name_from_run_id, run_started_at};
-use crate::support::services::build_service_info;
+use crate::support::crash::{remove_run_dir, CRASH_DIR};
+use crate::support::env::mask_env;
+use crate::support::log_files::{
+    LogFileOptions, LogFileSink, DEFAULT_LOG_KEEP, DEFAULT_LOG_MAX_BYTES,
+};
+use crate::support::log_sink::{LogSink, LogSinkTarget};
+use crate::support::logging::{LogHub, DEFAULT_LOG_REORDER};
+use crate::support::platform::Platform;
+use crate::support::run::{
+    merge_engine_runs, new_run_id, project_name_from_run_id, resolve_run_ref, run_started_at,
+    validate_tag, RunRef, LAST_RUN_ALIAS,
+};
+use crate::support::sampling::TrafficSampling;
+use crate::support::services::{build_dependency_graph, build_service_info};
+use crate::support::settings::{process_env, resolve_args, ResolvedArgs, Setting, Source};
+use crate::support::share::ShareTarget;
+use crate::support::shutdown::{shutdown_tiers, ShutdownOrder};
+use crate::support::skew::{ClockSkewPolicy, DEFAULT_SKEW_THRESHOLD};
 use crate::support::traffic::TrafficHub;
+use crate::support::traffic_logs::TrafficLogs;
+use crate::support::wait::{WaitOptions, WaitTarget, DEFAULT_WAIT_TIMEOUT};
+
+use self::output::OutputFormat;
+use self::stall::{StallAction, StallPolicy};
 
 pub fn run() -> ExitCode {
     match run_inner() {
@@ -59,9 +118,60 @@ impl AppError {
 
 enum SessionCommand {
     List,
-    Logs { run_id: Option<String> },
-    Traffic { run_id: Option<String> },
-    Down { run_id: Option<String> },
+    Logs(logs::LogsArgs),
+    Traffic {
+        run_id: Option<String>,
+        trace: Option<String>,
+        network: Option<String>,
+        json: bool,
+    },
+    Down {
+        run_id: Option<String>,
+        all: bool,
+    },
+    Stop {
+        run_id: Option<String>,
+    },
+    Start(lifecycle::StartArgs),
+    Gc {
+        ttl: Option<String>,
+        dry_run: bool,
+    },
+    Env {
+        run_id: Option<String>,
+        service: Option<String>,
+    },
+    Top {
+        run_id: Option<String>,
+    },
+    Mute(mute::MuteArgs),
+    Inspect(inspect::InspectArgs),
+    Bench(bench::BenchArgs),
+    Chaos(chaos::ChaosArgs),
+    Forward(forward::ForwardArgs),
+    OneOff(oneoff::OneOffArgs),
+    Open(open::OpenArgs),
+    Ps(ps::PsArgs),
+    Replay(record::ReplayArgs),
+    ConfigShow,
+    Lint {
+        args: Vec<String>,
+    },
+    Ui,
+}
+
+impl SessionCommand {
+    const fn spans_engines(&self) -> bool {
+        matches!(self, Self::List | Self::Logs(_) | Self::Down { .. })
+    }
+
+    fn run_ref(&self) -> Option<&str> {
+        match self {
+            Self::Logs(args) => args.run_id.as_deref(),
+            Self::Down { run_id, all: false } => run_id.as_deref(),
+            _ => None,
+        }
+    }
 }
 
 fn run_inner() -> Result<i32, AppError> {
@@ -70,59 +180,575 @@ fn run_inner() -> Result<i32, AppError> {
         return Ok(0);
     }
 
-    let (args, engine_preference) =
-        extract_engine_arg(&args).map_err(|err| AppError::new(err, 2))?;
-    let (args, traffic_override) = extract_traffic_arg(&args);
+    let (args, mut options) = extract_global_options(&args).map_err(|err| AppError::new(err, 2))?;
     let args = strip_project_name_args(&args);
+    if let Some(result) = completions::handle_completions(&args, options.compose_provider.engine) {
+        return result.map_err(|err| AppError::new(err, 2));
+    }
     if let Some(command) = extract_session_command(&args) {
-        let selection =
-            detect_compose_cmd(engine_preference).map_err(|err| AppError::new(err, 1))?;
-        let engine = Engine::new(selection.engine, &selection.compose_cmd);
-        let exit_code = match command {
-            SessionCommand::List => Ok(run_list(&engine)),
-            SessionCommand::Logs { run_id } => match require_run_id("logs", run_id) {
-                Ok(run_id) => run_logs(&engine, &run_id),
-                Err(err) => Err(err),
-            },
-            SessionCommand::Traffic { run_id } => match require_run_id("traffic", run_id) {
-                Ok(run_id) => run_traffic(&engine, &run_id),
-                Err(err) => Err(err),
-            },
-            SessionCommand::Down { run_id } => match require_run_id("down", run_id) {
-                Ok(run_id) => run_down(&engine, &selection.compose_cmd, &run_id),
-                Err(err) => Err(err),
-            },
-        }
-        .map_err(|err| AppError::new(err, 2))?;
-        return Ok(exit_code);
+        return run_session(command, options);
     }
 
-    let (compose_file, compose_file_from_args) =
-        resolve_compose_file(&args).map_err(|err| AppError::new(err, 2))?;
     let run_id = new_run_id();
     let project_name = project_name_from_run_id(&run_id);
+    let run_root =
+        resolve_run_root(&options.resolved.run_dir).map_err(|err| AppError::new(err, 2))?;
+    let mut compose = resolve_compose_input(
+        &options.resolved.compose_file,
+        args,
+        &project_name,
+        run_root.as_deref(),
+    )
+    .map_err(|err| AppError::new(err, 2))?;
     let started_at = run_started_at();
-    let selection = detect_compose_cmd(engine_preference).map_err(|err| AppError::new(err, 1))?;
+    let selection = select_compose(&options)?;
     let engine = Engine::new(selection.engine, &selection.compose_cmd);
 
-    if extract_subcommand(&args).as_deref() == Some("up") {
-        let _ = writeln!(std::io::stdout(), "Run ID: {run_id}");
-    }
+    let subcommand = extract_subcommand(&compose.args);
+    let derive_args = (subcommand.as_deref() == Some("derive"))
+        .then(|| derive::take_derive_args(&mut compose.args));
+    let run_lock = (subcommand.as_deref() == Some("up"))
+        .then(|| start_up(&engine, &mut compose, &run_id, &project_name, &options))
+        .transpose()?
+        .flatten();
+    let output = options.output;
+    let file_sink = open_log_file_sink(options.log_files.take(), &run_id)
+        .map_err(|err| AppError::new(err, 2))?;
+    let log_sink =
+        open_log_sink(options.log_sink.take(), &run_id).map_err(|err| AppError::new(err, 2))?;
 
     let mut runner = runner::ComposeRunner::new(runner::ComposeRunnerConfig {
         compose_cmd: selection.compose_cmd,
         engine,
-        compose_file,
+        compose_file: compose.file,
         run_id,
         project_name,
         run_started_at: started_at,
-        args,
+        args: compose.args,
     });
-    runner.set_compose_file_from_args(compose_file_from_args);
-    runner.set_traffic_enabled(traffic_enabled(traffic_override));
+    runner.set_legacy_compose(selection.legacy);
+    runner.set_compose_file_from_args(compose.from_args);
+    runner.set_compose_base_dir(compose.base_dir);
+    runner.set_run_root(run_root);
+    runner.set_file_sink(file_sink);
+    runner.set_log_sink(log_sink);
+    configure_runner(&mut runner, options);
+    if let Some(args) = derive_args {
+        return derive::run_derive(&mut runner, &args, output).map_err(|err| AppError::new(err, 1));
+    }
     setup_signals(runner.signal_context());
 
-    Ok(run_with_cleanup(&mut runner))
+    Ok(run_locked(&mut runner, run_lock))
+}
+
+fn run_locked(runner: &mut runner::ComposeRunner, run_lock: Option<RunLock>) -> i32 {
+    let code = run_with_cleanup(runner);
+    if let Some(lock) = run_lock {
+        lock.finish(code == 0);
+    }
+    code
+}
+
+fn start_up(
+    engine: &Engine,
+    compose: &mut ComposeInput,
+    run_id: &str,
+    project_name: &str,
+    options: &GlobalOptions,
+) -> Result<Option<RunLock>, AppError> {
+    let lock = run_lock::lock_compose_file(engine, compose, run_id)
+        .map_err(|err| AppError::new(err, 1))?;
+    print_run_banner(run_id, project_name, options);
+    Ok(lock)
+}
+
+fn run_session(command: SessionCommand, options: GlobalOptions) -> Result<i32, AppError> {
+    let selection = select_compose(&options)?;
+    let engines = engines::SessionEngines::detect(
+        selection,
+        options.compose_provider,
+        command.spans_engines(),
+    );
+    let output = options.output;
+    run_session_command(command, &engines, options).map_err(|err| {
+        if output.is_json() {
+            output::print_json(&serde_json::json!({ "error": err }));
+        }
+        AppError::new(err, 2)
+    })
+}
+
+fn select_compose(options: &GlobalOptions) -> Result<ComposeSelection, AppError> {
+    let selection =
+        detect_compose_cmd(options.compose_provider).map_err(|err| AppError::new(err, 1))?;
+    if selection.legacy {
+        eprintln!(
+            "[compose] using legacy provider `{}` (best-effort, --allow-legacy-compose)",
+            selection.compose_cmd.join(" ")
+        );
+    }
+    Ok(selection)
+}
+
+fn configure_runner(runner: &mut runner::ComposeRunner, options: GlobalOptions) {
+    runner.set_traffic_enabled(traffic_enabled(options.traffic_override));
+    runner.set_ui_bind(options.ui_bind);
+    runner.set_log_reorder(options.log_reorder);
+    runner.set_log_dedup(options.log_dedup);
+    runner.set_log_skew(options.log_skew);
+    runner.set_traffic_sampling(options.traffic_sampling);
+    runner.set_traffic_logs(options.traffic_logs);
+    runner.set_max_body_bytes(options.max_body_bytes);
+    runner.set_skip_missing_secrets(options.skip_missing_secrets);
+    runner.set_keep_volumes(options.down.keep_volumes);
+    runner.set_shutdown_order(options.down.shutdown_order);
+    runner.set_tag(options.tag);
+    runner.set_verifier(options.expectations.map(Verifier::new));
+    runner.set_hook(options.hook);
+    runner.set_record(options.record);
+    runner.set_wait(options.wait);
+    runner.set_env_overrides(options.env_overrides);
+    runner.set_resource_limits(options.resource_limits);
+    runner.set_stall(options.stall);
+    runner.set_platform(options.platform);
+}
+
+fn print_run_banner(run_id: &str, project_name: &str, options: &GlobalOptions) {
+    let _ = writeln!(std::io::stdout(), "Run ID: {run_id}");
+    if let Some(tag) = options.tag.as_deref() {
+        let _ = writeln!(std::io::stdout(), "Tag: {tag}");
+    }
+    if let Some(requested) = &options.resolved.project_name {
+        eprintln!(
+            "[compose] ignoring project name '{}' from {}: the run uses project {project_name}",
+            requested.value.as_deref().unwrap_or_default(),
+            requested.source.label()
+        );
+    }
+}
+
+struct GlobalOptions {
+    compose_provider: ComposeProvider,
+    traffic_override: Option<bool>,
+    ui_bind: UiBind,
+    log_files: Option<LogFileOptions>,
+    log_sink: Option<LogSinkTarget>,
+    log_reorder: Duration,
+    log_dedup: Duration,
+    log_skew: ClockSkewPolicy,
+    traffic_sampling: TrafficSampling,
+    traffic_logs: Option<TrafficLogs>,
+    max_body_bytes: usize,
+    skip_missing_secrets: bool,
+    down: DownOptions,
+    output: OutputFormat,
+    tag: Option<String>,
+    expectations: Option<Expectations>,
+    hook: Option<String>,
+    record: Option<PathBuf>,
+    wait: WaitOptions,
+    resolved: ResolvedArgs,
+    env_overrides: Vec<EnvOverride>,
+    resource_limits: Vec<ResourceLimit>,
+    stall: StallPolicy,
+    platform: Option<Platform>,
+}
+
+fn extract_global_options(args: &[String]) -> Result<(Vec<String>, GlobalOptions), String> {
+    let resolved = resolve_args(args, &process_env);
+    let (args, _) = take_option(args, "--run-dir");
+    let (args, compose_provider) = extract_compose_provider(&args)?;
+    let (args, traffic_override) = extract_traffic_arg(&args);
+    let (args, ui_bind) = extract_ui_bind(&args)?;
+    let (args, log_files) = extract_log_file_options(&args)?;
+    let (args, log_sink) = extract_log_sink(&args)?;
+    let (args, log_reorder) = extract_duration(
+        &args,
+        "--log-reorder",
+        DEFAULT_LOG_REORDER,
+        "0, 500ms or 2s",
+    )?;
+    let (args, log_dedup) = extract_duration(&args, "--log-dedup", Duration::ZERO, "0, 5s or 1m")?;
+    let (args, log_skew) = extract_log_skew(&args)?;
+    let (args, traffic_sampling) = extract_traffic_sampling(&args)?;
+    let (args, traffic_logs) = extract_traffic_logs(&args)?;
+    let (args, max_body_bytes) = extract_max_body_bytes(&args)?;
+    let (args, skip_missing_secrets) = take_flag(&args, "--skip-missing-secrets");
+    let (args, down) = extract_down_options(&args)?;
+    let (args, output) = extract_output(&args)?;
+    let (args, tag) = take_option(&args, "--tag");
+    let tag = tag.map(|tag| validate_tag(&tag)).transpose()?;
+    let (args, expectations) = extract_expectations(&args)?;
+    let (args, hook) = take_option(&args, "--hook");
+    let (args, record) = take_option(&args, "--record");
+    let (args, env_overrides) = extract_env_overrides(&args)?;
+    let (args, resource_limits) = extract_resource_limits(&args)?;
+    let (args, wait) = extract_wait_options(&args)?;
+    let (args, stall) = extract_stall_policy(&args)?;
+    let (args, platform) = extract_platform(&args)?;
+    Ok((
+        args,
+        GlobalOptions {
+            compose_provider,
+            traffic_override,
+            ui_bind,
+            log_files,
+            log_sink,
+            log_reorder,
+            log_dedup,
+            log_skew,
+            traffic_sampling,
+            traffic_logs,
+            max_body_bytes,
+            skip_missing_secrets,
+            down,
+            output,
+            tag,
+            expectations,
+            hook: hook.filter(|command| !command.trim().is_empty()),
+            record: record.map(PathBuf::from),
+            wait,
+            resolved,
+            env_overrides,
+            resource_limits,
+            stall,
+            platform,
+        },
+    ))
+}
+
+#[derive(Clone, Copy)]
+struct DownOptions {
+    keep_volumes: bool,
+    shutdown_order: ShutdownOrder,
+}
+
+fn extract_down_options(args: &[String]) -> Result<(Vec<String>, DownOptions), String> {
+    let (args, keep_volumes) = take_flag(args, "--keep-volumes");
+    let (args, order) = take_option(&args, "--shutdown-order");
+    let shutdown_order = order
+        .map(|value| ShutdownOrder::parse(&value))
+        .transpose()?
+        .unwrap_or_default();
+    Ok((
+        args,
+        DownOptions {
+            keep_volumes: keep_volumes || is_env_truthy(KEEP_VOLUMES_ENV),
+            shutdown_order,
+        },
+    ))
+}
+
+fn extract_compose_provider(args: &[String]) -> Result<(Vec<String>, ComposeProvider), String> {
+    let (args, engine) = extract_engine_arg(args)?;
+    let (args, allow_legacy) = take_flag(&args, "--allow-legacy-compose");
+    Ok((
+        args,
+        ComposeProvider {
+            engine,
+            allow_legacy,
+        },
+    ))
+}
+
+fn extract_duration(
+    args: &[String],
+    name: &str,
+    default: Duration,
+    examples: &str,
+) -> Result<(Vec<String>, Duration), String> {
+    let (args, value) = take_option(args, name);
+    let duration = match value {
+        Some(value) => parse_duration(&value)
+            .ok_or_else(|| format!("Invalid {name} '{value}'. Use e.g. {examples}."))?,
+        None => default,
+    };
+    Ok((args, duration))
+}
+
+fn extract_log_skew(args: &[String]) -> Result<(Vec<String>, ClockSkewPolicy), String> {
+    let (args, threshold) = extract_duration(args, "--log-skew", Duration::ZERO, "500ms or 2s")?;
+    let (args, correct) = take_flag(&args, "--log-skew-correct");
+    let threshold = if correct && threshold.is_zero() {
+        DEFAULT_SKEW_THRESHOLD
+    } else {
+        threshold
+    };
+    Ok((args, ClockSkewPolicy { threshold, correct }))
+}
+
+fn extract_traffic_sampling(args: &[String]) -> Result<(Vec<String>, TrafficSampling), String> {
+    let (args, sampling) = take_option(args, "--traffic-sample");
+    let sampling = match sampling {
+        Some(value) => TrafficSampling::parse(&value)
+            .map_err(|err| format!("Invalid --traffic-sample '{value}': {err}."))?,
+        None => TrafficSampling::default(),
+    };
+    Ok((args, sampling))
+}
+
+fn extract_traffic_logs(args: &[String]) -> Result<(Vec<String>, Option<TrafficLogs>), String> {
+    let (args, logs) = take_option(args, "--traffic-logs");
+    let logs = logs
+        .map(|value| {
+            TrafficLogs::parse(&value)
+                .map_err(|err| format!("Invalid --traffic-logs '{value}': {err}."))
+        })
+        .transpose()?;
+    Ok((args, logs))
+}
+
+fn extract_env_overrides(args: &[String]) -> Result<(Vec<String>, Vec<EnvOverride>), String> {
+    let (args, specs) = take_repeated_option(args, "--set-env");
+    let overrides = specs
+        .iter()
+        .map(|spec| EnvOverride::parse(spec))
+        .collect::<Result<_, _>>()?;
+    Ok((args, overrides))
+}
+
+fn extract_resource_limits(args: &[String]) -> Result<(Vec<String>, Vec<ResourceLimit>), String> {
+    let (args, specs) = take_repeated_option(args, "--limit");
+    let limits = specs
+        .iter()
+        .map(|spec| ResourceLimit::parse(spec))
+        .collect::<Result<_, _>>()?;
+    Ok((args, limits))
+}
+
+fn extract_expectations(args: &[String]) -> Result<(Vec<String>, Option<Expectations>), String> {
+    let (args, verify) = take_option(args, "--verify");
+    let expectations = verify
+        .map(|path| load_expectations(Path::new(&path)))
+        .transpose()?;
+    Ok((args, expectations))
+}
+
+fn extract_output(args: &[String]) -> Result<(Vec<String>, OutputFormat), String> {
+    let (args, output) = take_option(args, "--output");
+    let output = output
+        .as_deref()
+        .map_or(Ok(OutputFormat::Text), OutputFormat::parse)?;
+    Ok((args, output))
+}
+
+fn extract_platform(args: &[String]) -> Result<(Vec<String>, Option<Platform>), String> {
+    let (args, platform) = take_option(args, "--platform");
+    let platform = platform.as_deref().map(Platform::parse).transpose()?;
+    Ok((args, platform))
+}
+
+fn extract_stall_policy(args: &[String]) -> Result<(Vec<String>, StallPolicy), String> {
+    let defaults = StallPolicy::default();
+    let (args, timeout) =
+        extract_duration(args, "--stall-timeout", defaults.timeout, "0, 90s or 5m")?;
+    let (args, action) = take_option(&args, "--on-stall");
+    let action = action
+        .as_deref()
+        .map_or(Ok(defaults.action), StallAction::parse)?;
+    Ok((args, StallPolicy { timeout, action }))
+}
+
+fn extract_wait_options(args: &[String]) -> Result<(Vec<String>, WaitOptions), String> {
+    let (args, specs) = take_repeated_option(args, "--wait-for");
+    let targets = specs
+        .iter()
+        .map(|spec| WaitTarget::parse(spec))
+        .collect::<Result<_, _>>()?;
+    let (args, timeout) = extract_duration(
+        &args,
+        "--wait-timeout",
+        DEFAULT_WAIT_TIMEOUT,
+        "30s, 2m or 5m",
+    )?;
+    Ok((args, WaitOptions { targets, timeout }))
+}
+
+fn extract_max_body_bytes(args: &[String]) -> Result<(Vec<String>, usize), String> {
+    let (args, value) = take_option(args, "--max-body-bytes");
+    let Some(value) = value else {
+        return Ok((args, DEFAULT_MAX_BODY_BYTES));
+    };
+    let bytes = parse_byte_size(&value)
+        .and_then(|bytes| usize::try_from(bytes).ok())
+        .filter(|bytes| *bytes > 0)
+        .ok_or_else(|| format!("Invalid --max-body-bytes '{value}'. Use e.g. 64K, 1M or 10M."))?;
+    Ok((args, bytes))
+}
+
+fn extract_log_sink(args: &[String]) -> Result<(Vec<String>, Option<LogSinkTarget>), String> {
+    let (args, target) = take_option(args, "--log-sink");
+    let target = target.as_deref().map(LogSinkTarget::parse).transpose()?;
+    Ok((args, target))
+}
+
+fn extract_log_file_options(
+    args: &[String],
+) -> Result<(Vec<String>, Option<LogFileOptions>), String> {
+    let (args, dir) = take_option(args, "--log-dir");
+    let (args, max_size) = take_option(&args, "--log-max-size");
+    let (args, keep) = take_option(&args, "--log-keep");
+    let (args, gzip) = take_flag(&args, "--log-gzip");
+    let Some(dir) = dir else {
+        return Ok((args, None));
+    };
+    let max_bytes = match max_size {
+        Some(value) => parse_byte_size(&value).ok_or_else(|| {
+            format!("Invalid --log-max-size '{value}'. Use e.g. 512K, 10M or 1G.")
+        })?,
+        None => DEFAULT_LOG_MAX_BYTES,
+    };
+    let keep = match keep {
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("Invalid --log-keep '{value}'. Use a number of files."))?,
+        None => DEFAULT_LOG_KEEP,
+    };
+    Ok((
+        args,
+        Some(LogFileOptions {
+            dir: PathBuf::from(dir),
+            max_bytes,
+            keep,
+            gzip,
+        }),
+    ))
+}
+
+fn open_log_file_sink(
+    options: Option<LogFileOptions>,
+    run_id: &str,
+) -> Result<Option<Arc<LogFileSink>>, String> {
+    options
+        .map(|options| LogFileSink::new(options, run_id).map(Arc::new))
+        .transpose()
+}
+
+fn open_log_sink(
+    target: Option<LogSinkTarget>,
+    run_id: &str,
+) -> Result<Option<Arc<LogSink>>, String> {
+    target
+        .map(|target| LogSink::open(&target, run_id).map(Arc::new))
+        .transpose()
+}
+
+impl GlobalOptions {
+    fn take_logs_options(&mut self) -> logs::LogsOptions {
+        logs::LogsOptions {
+            ui_bind: self.ui_bind.clone(),
+            log_files: self.log_files.take(),
+            log_sink: self.log_sink.take(),
+            log_reorder: self.log_reorder,
+            log_dedup: self.log_dedup,
+            log_skew: self.log_skew,
+        }
+    }
+}
+
+fn run_session_command(
+    command: SessionCommand,
+    engines: &engines::SessionEngines,
+    mut options: GlobalOptions,
+) -> Result<i32, String> {
+    let target = engines.route(command.run_ref())?;
+    let engine = &target.engine;
+    let compose_cmd = target.compose_cmd.as_slice();
+    let logs_options = options.take_logs_options();
+    let output = options.output;
+    let down = options.down;
+    match command {
+        SessionCommand::List => Ok(run_list(engines, output)),
+        SessionCommand::Logs(args) => logs::run_logs(engine, &args, logs_options),
+        SessionCommand::Traffic {
+            run_id,
+            trace,
+            network,
+            json,
+        } => {
+            let calls = CallStream {
+                network,
+                json: json || output.is_json(),
+            };
+            run_traffic(
+                engine,
+                run_id,
+                trace.as_deref(),
+                options.traffic_sampling,
+                &calls,
+            )
+        }
+        SessionCommand::Top { run_id } => top::run_top(engine, run_id, options.traffic_sampling),
+        SessionCommand::Mute(args) => mute::run_mute(engine, args, output),
+        SessionCommand::Inspect(args) => inspect::run_inspect(
+            engine,
+            args,
+            resolve_run_root(&options.resolved.run_dir)?.as_deref(),
+            output,
+        ),
+        SessionCommand::Bench(args) => bench::run_bench(engine, args, output),
+        SessionCommand::Chaos(args) => chaos::run_chaos(engine, args, output),
+        SessionCommand::Forward(args) => forward::run_forward(engine, args, output),
+        SessionCommand::OneOff(args) => oneoff::run_oneoff(engine, args),
+        SessionCommand::Open(args) => open::run_open(engine, args, output),
+        SessionCommand::Ps(args) => ps::run_ps(engine, args, output),
+        SessionCommand::Replay(args) => record::run_replay(engine, args, &options.ui_bind),
+        SessionCommand::ConfigShow => Ok(run_config_show(target, &options)),
+        SessionCommand::Lint { args } => lint::run_lint(compose_cmd, &args, output),
+        SessionCommand::Down { all: true, .. } => {
+            Ok(gc::run_down_all(&engines.all(), down.keep_volumes, output))
+        }
+        SessionCommand::Down { run_id, .. } => {
+            let run_id = resolve_run_id(engine, "down", run_id)?;
+            run_down_command(engine, compose_cmd, &run_id, down, output)
+        }
+        SessionCommand::Stop { run_id } => lifecycle::run_stop(engine, compose_cmd, run_id, output),
+        SessionCommand::Start(args) => {
+            lifecycle::run_start(engine, compose_cmd, args, logs_options)
+        }
+        SessionCommand::Gc { ttl, dry_run } => run_gc_command(target, ttl, dry_run, &options),
+        SessionCommand::Env { run_id, service } => run_env(engine, run_id, service, output),
+        SessionCommand::Ui => daemon::run_ui_daemon(engine, &daemon_ui_bind(options.ui_bind)),
+    }
+}
+
+const fn daemon_ui_bind(mut bind: UiBind) -> UiBind {
+    if bind.port == 0 {
+        bind.port = daemon::UI_DAEMON_PORT;
+    }
+    bind
+}
+
+fn run_config_show(target: &engines::EngineTarget, options: &GlobalOptions) -> i32 {
+    let show = config::ConfigShow::new(
+        &options.resolved,
+        options.compose_provider.engine,
+        options.output,
+    );
+    show.run(&target.engine, &target.compose_cmd)
+}
+
+fn run_gc_command(
+    target: &engines::EngineTarget,
+    ttl: Option<String>,
+    dry_run: bool,
+    options: &GlobalOptions,
+) -> Result<i32, String> {
+    let gc_options = gc::GcOptions {
+        ttl: parse_gc_ttl(ttl)?,
+        dry_run,
+        keep_volumes: options.down.keep_volumes,
+        output: options.output,
+        run_root: resolve_run_root(&options.resolved.run_dir)?,
+    };
+    Ok(gc::run_gc(&target.engine, &target.compose_cmd, &gc_options))
+}
+
+fn parse_gc_ttl(ttl: Option<String>) -> Result<Duration, String> {
+    ttl.map_or(Ok(gc::DEFAULT_GC_TTL), |value| {
+        parse_duration(&value)
+            .ok_or_else(|| format!("Invalid --ttl '{value}'. Use e.g. 30m, 1h or 2h."))
+    })
 }
 
 fn handle_version(args: &[String]) -> bool {
@@ -154,26 +780,125 @@ fn handle_watchdog(args: &[String]) -> bool {
     true
 }
 
-fn resolve_compose_file(args: &[String]) -> Result<(String, bool), String> {
-    let compose_file_arg = extract_compose_file_arg(args);
-    let compose_file_env = env::var("COMPOSE_FILE").ok();
-    let compose_file_from_args = compose_file_arg.is_some() || compose_file_env.is_some();
-    let compose_file = if let Some(path) = compose_file_arg {
-        path
-    } else if let Some(value) = compose_file_env.as_deref() {
-        first_compose_file(value).ok_or_else(|| "COMPOSE_FILE is set but empty.".to_string())?
-    } else {
-        return Err("Compose file is required. Pass -f/--file or set COMPOSE_FILE.".to_string());
+struct ComposeInput {
+    file: String,
+    from_args: bool,
+    base_dir: Option<PathBuf>,
+    args: Vec<String>,
+}
+
+fn resolve_run_root(setting: &Setting) -> Result<Option<PathBuf>, String> {
+    let Some(dir) = setting.value.as_deref() else {
+        return Ok(None);
+    };
+    if dir.trim().is_empty() {
+        return Err("--run-dir needs a directory.".to_string());
+    }
+    let dir = PathBuf::from(dir);
+    if dir.is_absolute() {
+        return Ok(Some(dir));
+    }
+    env::current_dir()
+        .map(|cwd| Some(cwd.join(dir)))
+        .map_err(|err| format!("failed to resolve working directory: {err}"))
+}
+
+fn resolve_compose_input(
+    setting: &Setting,
+    args: Vec<String>,
+    project_name: &str,
+    run_root: Option<&Path>,
+) -> Result<ComposeInput, String> {
+    let (file, from_args) = resolve_compose_file(setting)?;
+    if file != STDIN_COMPOSE {
+        return Ok(ComposeInput {
+            file,
+            from_args,
+            base_dir: None,
+            args,
+        });
+    }
+    let base_dir =
+        env::current_dir().map_err(|err| format!("failed to resolve working directory: {err}"))?;
+    let file = buffer_stdin_compose(&run_dir(run_root, &base_dir, project_name))?
+        .to_string_lossy()
+        .into_owned();
+    Ok(ComposeInput {
+        args: replace_compose_file_arg(&args, STDIN_COMPOSE, &file),
+        file,
+        from_args,
+        base_dir: Some(base_dir),
+    })
+}
+
+fn resolve_compose_file(setting: &Setting) -> Result<(String, bool), String> {
+    match (&setting.value, setting.source) {
+        (Some(path), _) => Ok((path.clone(), true)),
+        (None, Source::Env) => Err("COMPOSE_FILE is set but empty.".to_string()),
+        (None, _) => {
+            Err("Compose file is required. Pass -f/--file or set COMPOSE_FILE.".to_string())
+        }
+    }
+}
+
+fn extract_ui_bind(args: &[String]) -> Result<(Vec<String>, UiBind), String> {
+    let (args, host) = take_option(args, "--ui-bind");
+    let (args, port) = take_option(&args, "--ui-port");
+    let (args, fallback) = take_flag(&args, "--ui-port-auto-fallback");
+    let (args, assets) = take_option(&args, "--ui-assets");
+    let (args, share) = take_option(&args, "--share");
+    let share = share.as_deref().map(ShareTarget::parse).transpose()?;
+    let assets = assets.map(PathBuf::from);
+    if let Some(dir) = assets.as_ref().filter(|dir| !dir.is_dir()) {
+        return Err(format!(
+            "--ui-assets '{}' is not a directory.",
+            dir.display()
+        ));
+    }
+    let mut bind = UiBind {
+        fallback,
+        assets,
+        share,
+        ..UiBind::default()
     };
-    Ok((compose_file, compose_file_from_args))
+    if let Some(host) = host {
+        bind.host = host;
+    }
+    if let Some(port) = port {
+        bind.port = port.parse().map_err(|_| {
+            format!("Invalid --ui-port '{port}'. Use a number between 0 and 65535.")
+        })?;
+    }
+    Ok((args, bind))
 }
 
 fn traffic_enabled(traffic_override: Option<bool>) -> bool {
     traffic_override.unwrap_or(true)
 }
 
-fn require_run_id(command: &str, run_id: Option<String>) -> Result<String, String> {
-    run_id.ok_or_else(|| format!("Usage: sanelens {command} <run_id>"))
+fn resolve_run_id(
+    engine: &Engine,
+    command: &str,
+    run_ref: Option<String>,
+) -> Result<String, String> {
+    let run_ref = run_ref.ok_or_else(|| format!("Usage: sanelens {command} <run_id|tag>"))?;
+    if run_ref != LAST_RUN_ALIAS
+        && !engine
+            .collect_run_container_ids(&run_ref, crate::domain::Scope::All)
+            .is_empty()
+    {
+        return Ok(run_ref);
+    }
+    let runs = collect_runs(engine, crate::domain::Scope::All);
+    let refs: Vec<RunRef<'_>> = runs
+        .iter()
+        .map(|run| RunRef {
+            run_id: &run.run_id,
+            tag: run.tag.as_deref(),
+            started_at: run.started_at_ts,
+        })
+        .collect();
+    resolve_run_ref(&run_ref, &refs)
 }
 
 fn extract_session_command(args: &[String]) -> Option<SessionCommand> {
@@ -181,7 +906,7 @@ fn extract_session_command(args: &[String]) -> Option<SessionCommand> {
     while let Some(arg) = iter.next() {
         if arg == "--" {
             if let Some(cmd) = iter.next() {
-                return parse_session_command(cmd.as_str(), &mut iter);
+                return parse_session_command(cmd.as_str(), &iter.cloned().collect::<Vec<_>>());
             }
             return None;
         }
@@ -194,26 +919,69 @@ fn extract_session_command(args: &[String]) -> Option<SessionCommand> {
             }
             continue;
         }
-        return parse_session_command(arg.as_str(), &mut iter);
+        return parse_session_command(arg.as_str(), &iter.cloned().collect::<Vec<_>>());
     }
     None
 }
 
-fn parse_session_command<'a>(
-    command: &str,
-    iter: &mut impl Iterator<Item = &'a String>,
-) -> Option<SessionCommand> {
+fn parse_session_command(command: &str, rest: &[String]) -> Option<SessionCommand> {
     match command {
         "list" => Some(SessionCommand::List),
-        "logs" => Some(SessionCommand::Logs {
-            run_id: iter.next().cloned(),
+        "logs" => Some(SessionCommand::Logs(logs::parse_logs_args(rest))),
+        "traffic" => {
+            let (rest, trace) = take_option(rest, "--trace");
+            let (rest, network) = take_option(&rest, "--network");
+            let (rest, json) = take_flag(&rest, "--json");
+            Some(SessionCommand::Traffic {
+                run_id: rest.into_iter().next(),
+                trace,
+                network,
+                json,
+            })
+        }
+        "down" => {
+            let (rest, all) = take_flag(rest, "--all");
+            Some(SessionCommand::Down {
+                run_id: rest.into_iter().next(),
+                all,
+            })
+        }
+        "stop" => Some(SessionCommand::Stop {
+            run_id: rest.first().cloned(),
         }),
-        "traffic" => Some(SessionCommand::Traffic {
-            run_id: iter.next().cloned(),
+        "start" => Some(SessionCommand::Start(lifecycle::parse_start_args(rest))),
+        "gc" => {
+            let (rest, ttl) = take_option(rest, "--ttl");
+            let (_, dry_run) = take_flag(&rest, "--dry-run");
+            Some(SessionCommand::Gc { ttl, dry_run })
+        }
+        "env" => Some(SessionCommand::Env {
+            run_id: rest.first().cloned(),
+            service: rest.get(1).cloned(),
+        }),
+        "top" => Some(SessionCommand::Top {
+            run_id: rest.first().cloned(),
         }),
-        "down" => Some(SessionCommand::Down {
-            run_id: iter.next().cloned(),
+        "mute" | "unmute" => Some(SessionCommand::Mute(mute::MuteArgs {
+            run_id: rest.first().cloned(),
+            service: rest.get(1).cloned(),
+            mute: command == "mute",
+        })),
+        "inspect" => Some(SessionCommand::Inspect(inspect::parse_inspect_args(rest))),
+        "config" => {
+            (rest.first().map(String::as_str) == Some("show")).then_some(SessionCommand::ConfigShow)
+        }
+        "bench" => Some(SessionCommand::Bench(bench::parse_bench_args(rest))),
+        "chaos" => Some(SessionCommand::Chaos(chaos::parse_chaos_args(rest))),
+        "forward" => Some(SessionCommand::Forward(forward::parse_forward_args(rest))),
+        "run" => oneoff::parse_oneoff_args(rest).map(SessionCommand::OneOff),
+        "open" => Some(SessionCommand::Open(open::parse_open_args(rest))),
+        "ps" => ps::parse_ps_args(rest).map(SessionCommand::Ps),
+        "replay" => Some(SessionCommand::Replay(record::parse_replay_args(rest))),
+        "lint" => Some(SessionCommand::Lint {
+            args: rest.to_vec(),
         }),
+        "ui" => Some(SessionCommand::Ui),
         _ => None,
     }
 }
@@ -236,131 +1004,176 @@ fn option_takes_value(arg: &str) -> bool {
     )
 }
 
-fn run_list(engine: &Engine) -> i32 {
-    let mut runs = collect_active_runs(engine);
+fn run_list(engines: &engines::SessionEngines, output: OutputFormat) -> i32 {
+    let targets = engines.all();
+    let mut runs: Vec<RunMetadata> = merge_engine_runs(
+        targets
+            .iter()
+            .map(|target| collect_active_runs(&target.engine)),
+        |run| run.run_id.as_str(),
+    )
+    .into_iter()
+    .map(|(idx, mut run)| {
+        run.engine = targets.get(idx).map(|target| target.name());
+        run
+    })
+    .collect();
+    runs.sort_by_key(|run| std::cmp::Reverse(run.started_at_ts));
+    if output.is_json() {
+        output::print_json(&serde_json::json!({ "runs": runs }));
+        return 0;
+    }
     if runs.is_empty() {
         let mut stdout = io::stdout();
         let _ = writeln!(stdout, "No active runs.");
         return 0;
     }
-    runs.sort_by(|a, b| b.started_at_ts.cmp(&a.started_at_ts));
-
-    let mut run_id_width = "RUN_ID".len();
-    let mut started_width = "STARTED".len();
-    let mut duration_width = "DURATION".len();
-    let mut compose_width = "COMPOSE_FILE".len();
 
     let now_ts = OffsetDateTime::now_utc().unix_timestamp();
-    let rows: Vec<_> = runs
+    let rows: Vec<[String; 6]> = runs
         .into_iter()
         .map(|run| {
-            let started = run.started_at_raw.unwrap_or_else(|| "-".to_string());
             let duration = run
                 .started_at_ts
                 .map_or_else(|| "-".to_string(), |ts| format_duration(now_ts - ts));
-            let compose_file = run.compose_file.unwrap_or_else(|| "-".to_string());
-            run_id_width = run_id_width.max(run.run_id.len());
-            started_width = started_width.max(started.len());
-            duration_width = duration_width.max(duration.len());
-            compose_width = compose_width.max(compose_file.len());
-            (run.run_id, started, duration, compose_file)
+            [
+                run.run_id,
+                run.engine.unwrap_or("-").to_string(),
+                run.tag.unwrap_or_else(|| "-".to_string()),
+                run.started_at_raw.unwrap_or_else(|| "-".to_string()),
+                duration,
+                run.compose_file.unwrap_or_else(|| "-".to_string()),
+            ]
         })
         .collect();
+    print_table(
+        [
+            "RUN_ID",
+            "ENGINE",
+            "TAG",
+            "STARTED",
+            "DURATION",
+            "COMPOSE_FILE",
+        ],
+        &rows,
+    );
+    0
+}
 
+fn print_table<const N: usize>(headers: [&str; N], rows: &[[String; N]]) {
+    let mut widths = headers.map(str::len);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
     let mut stdout = io::stdout();
-    let run_id = "RUN_ID";
-    let started = "STARTED";
-    let duration = "DURATION";
-    let compose = "COMPOSE_FILE";
-    let _ = writeln!(
-        stdout,
-        "{run_id:<run_id_width$}  {started:<started_width$}  {duration:<duration_width$}  {compose:<compose_width$}"
-    );
-    for (run_id, started, duration, compose_file) in rows {
+    let format_row = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+    let _ = writeln!(stdout, "{}", format_row(headers.to_vec()));
+    for row in rows {
         let _ = writeln!(
             stdout,
-            "{run_id:<run_id_width$}  {started:<started_width$}  {duration:<duration_width$}  {compose_file:<compose_width$}"
+            "{}",
+            format_row(row.iter().map(String::as_str).collect())
         );
     }
-
-    0
 }
 
-fn run_logs(engine: &Engine, run_id: &str) -> Result<i32, String> {
-    let containers = load_run_containers(engine, run_id, crate::domain::Scope::Running)?;
-    let metadata = run_metadata_from_containers(run_id, &containers);
-    let services = run_services_from_containers(&containers);
-    let project_name = metadata
-        .project_name
-        .unwrap_or_else(|| project_name_from_run_id(run_id));
-    let stop_event = Arc::new(AtomicBool::new(false));
-    let signal_handled = Arc::new(AtomicBool::new(false));
-    let exit_code = Arc::new(AtomicI32::new(0));
-    let handles = Arc::new(runner::ProcessHandles::new());
-    setup_signals(runner::SignalContext::new(
-        stop_event.clone(),
-        signal_handled,
-        exit_code.clone(),
-        handles.clone(),
-    ));
+fn run_env(
+    engine: &Engine,
+    run_id: Option<String>,
+    service: Option<String>,
+    output: OutputFormat,
+) -> Result<i32, String> {
+    let run_id = resolve_run_id(engine, "env", run_id)?;
+    let service =
+        service.ok_or_else(|| "Usage: sanelens env <run_id|tag> <service>".to_string())?;
+    let containers = RunContainers::new(engine.clone(), run_id.clone());
+    let env = containers
+        .service_env(&service)
+        .ok_or_else(|| format!("Service {service} not found in run {run_id}."))?;
+    let vars = mask_env(&env);
+    if output.is_json() {
+        output::print_json(&serde_json::json!({ "service": service, "env": vars }));
+        return Ok(0);
+    }
+    let mut stdout = io::stdout();
+    for var in vars {
+        let _ = writeln!(stdout, "{}={}", var.key, var.value);
+    }
+    Ok(0)
+}
 
-    let log_hub = Arc::new(LogHub::new(crate::support::constants::HISTORY_LIMIT));
-    let service_info = metadata
-        .compose_file
+fn session_ui_run(
+    metadata: &RunMetadata,
+    log_hub: Arc<LogHub>,
+    traffic_hub: Option<Arc<TrafficHub>>,
+    containers: Arc<RunContainers>,
+) -> UiRun {
+    let compose_file = metadata.compose_file.as_deref();
+    let run_dir = metadata
+        .derived_compose
         .as_deref()
-        .map(build_service_info)
-        .unwrap_or_default();
+        .and_then(|path| Path::new(path).parent());
+    UiRun {
+        log_hub,
+        service_info: Arc::new(
+            compose_file
+                .map(|file| build_service_info(file, containers.endpoint_host()))
+                .unwrap_or_default(),
+        ),
+        endpoint_probes: Some(Arc::default()),
+        forwards: Arc::default(),
+        service_graph: Arc::new(compose_file.map(build_dependency_graph).unwrap_or_default()),
+        service_config: Arc::new(load_effective_config(run_dir)),
+        alert_hub: None,
+        traffic_hub,
+        containers,
+    }
+}
 
-    let mut ui_server = None;
-    match UiServer::start(log_hub.clone(), service_info, None, stop_event.clone()) {
+fn start_session_ui(
+    run: UiRun,
+    stop_event: &Arc<AtomicBool>,
+    ui_bind: &UiBind,
+) -> Option<UiServer> {
+    match UiServer::start(run, stop_event.clone(), ui_bind) {
         Ok(server) => {
-            let port = server.port();
-            let url = format!("http://127.0.0.1:{port}/");
+            let url = server.url();
             let _ = writeln!(std::io::stdout(), "[compose] log UI: {url}");
             open_browser(&url);
-            ui_server = Some(server);
+            Some(server)
         }
         Err(err) => {
             eprintln!("[compose] log UI failed: {err}");
+            None
         }
     }
-
-    let follower = runner::LogFollower::new(
-        engine.clone(),
-        run_id.to_string(),
-        project_name,
-        stop_event,
-        Some(log_hub),
-        handles.clone(),
-        services.proxy_services,
-        services.service_aliases,
-    );
-    let mut log_threads = Vec::new();
-    let exit = follower.follow_logs(true, &mut log_threads);
-
-    handles.stop_log_procs();
-    if let Some(server) = ui_server.as_mut() {
-        server.stop();
-    }
-    let signal_exit = exit_code.load(Ordering::SeqCst);
-    if signal_exit != 0 {
-        return Ok(signal_exit);
-    }
-    Ok(exit)
 }
 
-fn run_traffic(engine: &Engine, run_id: &str) -> Result<i32, String> {
+fn run_traffic(
+    engine: &Engine,
+    run_id: Option<String>,
+    trace: Option<&str>,
+    sampling: TrafficSampling,
+    calls: &CallStream,
+) -> Result<i32, String> {
+    let run_id = resolve_run_id(engine, "traffic", run_id)?;
+    let run_id = run_id.as_str();
     let containers = load_run_containers(engine, run_id, crate::domain::Scope::Running)?;
     let metadata = run_metadata_from_containers(run_id, &containers);
     let services = run_services_from_containers(&containers);
+    let tap_dir = run_tap_dir(&metadata);
     let project_name = metadata
         .project_name
         .unwrap_or_else(|| project_name_from_run_id(run_id));
-    let tap_dir = metadata
-        .derived_compose
-        .as_ref()
-        .and_then(|path| Path::new(path).parent().map(|dir| dir.join("tap")))
-        .filter(|dir| dir.exists());
 
     let stop_event = Arc::new(AtomicBool::new(false));
     let signal_handled = Arc::new(AtomicBool::new(false));
@@ -374,6 +1187,7 @@ fn run_traffic(engine: &Engine, run_id: &str) -> Result<i32, String> {
     ));
 
     let hub = Arc::new(TrafficHub::new());
+    hub.set_sampling(sampling);
     let follower = runner::TrafficFollower::new(
         engine.clone(),
         run_id.to_string(),
@@ -388,35 +1202,107 @@ fn run_traffic(engine: &Engine, run_id: &str) -> Result<i32, String> {
     );
 
     let handle = thread::spawn(move || follower.follow());
+    let trace_exit = trace.map(|request_id| print_trace(&hub, &stop_event, request_id));
+    if trace_exit.is_none() {
+        stream_calls(&hub, &stop_event, calls);
+    }
+
+    stop_event.store(true, Ordering::SeqCst);
+    handles.stop_log_procs();
+    let follower_exit = handle.join().map_or(1, |code| code);
+    let signal_exit = exit_code.load(Ordering::SeqCst);
+    if signal_exit != 0 {
+        return Ok(signal_exit);
+    }
+    if let Some(code) = trace_exit {
+        return Ok(code);
+    }
+    Ok(follower_exit)
+}
+
+struct CallStream {
+    network: Option<String>,
+    json: bool,
+}
+
+impl CallStream {
+    fn shows(&self, call: &TrafficCall) -> bool {
+        self.network
+            .as_ref()
+            .is_none_or(|network| call.attrs.tags.get(NETWORK_TAG) == Some(network))
+    }
+}
+
+fn stream_calls(hub: &TrafficHub, stop_event: &AtomicBool, calls: &CallStream) {
     let (receiver, snapshot) = hub.register_call_client();
     let mut stdout = io::stdout();
-    for call in snapshot {
-        let line = serde_json::to_string(&call).unwrap_or_default();
-        let _ = writeln!(stdout, "{line}");
+    let json = calls.json;
+    let color = !json && stdout.is_terminal() && env::var_os("NO_COLOR").is_none();
+    let format = |call: &TrafficCall| {
+        if json {
+            serde_json::to_string(&versioned(call)).unwrap_or_default()
+        } else {
+            render::call_row(call, color)
+        }
+    };
+    for call in snapshot.iter().filter(|call| calls.shows(call)) {
+        let _ = writeln!(stdout, "{}", format(call));
         let _ = stdout.flush();
     }
     while !stop_event.load(Ordering::SeqCst) {
         match receiver.recv_timeout(Duration::from_secs(1)) {
-            Ok(call) => {
-                let line = serde_json::to_string(&call).unwrap_or_default();
-                let _ = writeln!(stdout, "{line}");
+            Ok(call) if calls.shows(&call) => {
+                let _ = writeln!(stdout, "{}", format(&call));
                 let _ = stdout.flush();
             }
-            Err(crossbeam_channel::RecvTimeoutError::Timeout) => {}
+            Ok(_) | Err(crossbeam_channel::RecvTimeoutError::Timeout) => {}
             Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
         }
     }
+}
 
-    handles.stop_log_procs();
-    let follower_exit = handle.join().map_or(1, |code| code);
-    let signal_exit = exit_code.load(Ordering::SeqCst);
-    if signal_exit != 0 {
-        return Ok(signal_exit);
+fn print_trace(hub: &TrafficHub, stop_event: &AtomicBool, request_id: &str) -> i32 {
+    let (receiver, _) = hub.register_call_client();
+    while !stop_event.load(Ordering::SeqCst) {
+        if receiver.recv_timeout(TRACE_SETTLE).is_err() {
+            break;
+        }
     }
-    Ok(follower_exit)
+    let Some(trace) = hub.trace(request_id) else {
+        eprintln!("[compose] no calls found for request id {request_id}");
+        return 1;
+    };
+    let mut stdout = io::stdout();
+    for line in render::render_trace(&trace) {
+        let _ = writeln!(stdout, "{line}");
+    }
+    0
+}
+
+const TRACE_SETTLE: Duration = Duration::from_secs(2);
+
+fn run_down_command(
+    engine: &Engine,
+    compose_cmd: &[String],
+    run_id: &str,
+    down: DownOptions,
+    output: OutputFormat,
+) -> Result<i32, String> {
+    let outcome = run_down(engine, compose_cmd, run_id, down);
+    if output.is_json() {
+        let exit_code = i32::from(outcome.is_err());
+        output::print_json(&CleanupResult::new(run_id, outcome));
+        return Ok(exit_code);
+    }
+    outcome.map(|()| 0)
 }
 
-fn run_down(engine: &Engine, compose_cmd: &[String], run_id: &str) -> Result<i32, String> {
+fn run_down(
+    engine: &Engine,
+    compose_cmd: &[String],
+    run_id: &str,
+    down: DownOptions,
+) -> Result<(), String> {
     let containers = load_run_containers(engine, run_id, crate::domain::Scope::All)?;
     let metadata = run_metadata_from_containers(run_id, &containers);
     let derived_compose = metadata
@@ -426,24 +1312,59 @@ fn run_down(engine: &Engine, compose_cmd: &[String], run_id: &str) -> Result<i32
         .project_name
         .unwrap_or_else(|| project_name_from_run_id(run_id));
 
+    if let Some(dir) = Path::new(&derived_compose).parent() {
+        inspect::write_snapshot(engine, run_id, dir, "down");
+    }
+    if down.shutdown_order == ShutdownOrder::Graph {
+        let compose_file = metadata
+            .compose_file
+            .filter(|path| Path::new(path).exists())
+            .unwrap_or_else(|| derived_compose.clone());
+        engine.stop_in_order(
+            run_id,
+            &shutdown_tiers(&build_dependency_graph(&compose_file)),
+        );
+    }
     let project_args: Vec<String> = Vec::new();
-    engine.cleanup_project(&CleanupContext {
+    let cleanup = engine.cleanup_project(&CleanupContext {
         compose_cmd,
         compose_file: &derived_compose,
+        run_id,
         project_name: &project_name,
         project_args: &project_args,
+        keep_volumes: down.keep_volumes,
     });
 
-    if let Some(dir) = Path::new(&derived_compose).parent() {
-        if let Err(err) = fs::remove_dir_all(dir) {
-            eprintln!("[compose] cleanup failed: {err}");
-        }
+    // Keep the run dir on failure so `down` can be retried.
+    cleanup?;
+    let Some(dir) = Path::new(&derived_compose).parent() else {
+        return Ok(());
+    };
+    let kept = remove_run_dir(dir)
+        .map_err(|err| format!("failed to remove run dir {}: {err}", dir.display()))?;
+    if kept {
+        eprintln!(
+            "[compose] kept crash evidence in {}",
+            dir.join(CRASH_DIR).display()
+        );
     }
-    Ok(0)
+    Ok(())
+}
+
+fn run_tap_dir(metadata: &RunMetadata) -> Option<PathBuf> {
+    metadata
+        .derived_compose
+        .as_ref()
+        .and_then(|path| Path::new(path).parent().map(|dir| dir.join("tap")))
+        .filter(|dir| dir.exists())
 }
 
 fn collect_active_runs(engine: &Engine) -> Vec<RunMetadata> {
-    let ids = engine.collect_container_ids_with_label(RUN_ID_LABEL, crate::domain::Scope::Running);
+    collect_runs(engine, crate::domain::Scope::Running)
+}
+
+fn collect_runs(engine: &Engine, scope: crate::domain::Scope) -> Vec<RunMetadata> {
+    let ids = engine.collect_container_ids_with_label(RUN_ID_LABEL, scope);
     if ids.is_empty() {
         return Vec::new();
     }
@@ -457,6 +1378,16 @@ fn collect_active_runs(engine: &Engine) -> Vec<RunMetadata> {
             .entry(run_id.clone())
             .or_insert_with(|| RunMetadata::new(run_id.clone()));
         entry.apply_labels(&container.labels);
+        let state = container.display_state();
+        entry.containers.push(RunContainerStatus {
+            id: container.id,
+            service: container
+                .labels
+                .get(SERVICE_LABEL)
+                .cloned()
+                .or(container.service),
+            state,
+        });
     }
     runs.into_values().collect()
 }
@@ -497,13 +1428,13 @@ fn run_services_from_containers(containers: &[ContainerInfo]) -> RunServices {
         if container
             .labels
             .get(PROXY_LABEL)
-            .is_some_and(|value| label_is_truthy(value))
+            .is_some_and(|value| is_truthy(value))
         {
             proxy_services.insert(service_name.clone());
             if container
                 .labels
                 .get(PROXY_EGRESS_LABEL)
-                .is_some_and(|value| label_is_truthy(value))
+                .is_some_and(|value| is_truthy(value))
             {
                 egress_proxy = Some(service_name.clone());
             }
@@ -516,10 +1447,6 @@ fn run_services_from_containers(containers: &[ContainerInfo]) -> RunServices {
     }
 }
 
-fn label_is_truthy(value: &str) -> bool {
-    matches!(value.to_lowercase().as_str(), "1" | "true" | "yes")
-}
-
 fn parse_started_at(value: &str) -> Option<i64> {
     OffsetDateTime::parse(value, &Rfc3339)
         .ok()
@@ -544,13 +1471,27 @@ fn format_duration(secs: i64) -> String {
     }
 }
 
+#[derive(Serialize)]
 struct RunMetadata {
     run_id: String,
     compose_file: Option<String>,
     derived_compose: Option<String>,
     project_name: Option<String>,
+    #[serde(rename = "started_at")]
     started_at_raw: Option<String>,
+    #[serde(skip)]
     started_at_ts: Option<i64>,
+    tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    engine: Option<&'static str>,
+    containers: Vec<RunContainerStatus>,
+}
+
+#[derive(Serialize)]
+struct RunContainerStatus {
+    id: String,
+    service: Option<String>,
+    state: Option<String>,
 }
 
 impl RunMetadata {
@@ -563,6 +1504,9 @@ impl RunMetadata {
             project_name: None,
             started_at_raw: None,
             started_at_ts: None,
+            tag: None,
+            engine: None,
+            containers: Vec::new(),
         }
     }
 
@@ -582,6 +1526,11 @@ impl RunMetadata {
                 self.project_name = Some(value.clone());
             }
         }
+        if self.tag.is_none() {
+            if let Some(value) = labels.get(TAG_LABEL) {
+                self.tag = Some(value.clone());
+            }
+        }
         if self.started_at_raw.is_none() {
             if let Some(value) = labels.get(STARTED_AT_LABEL) {
                 self.started_at_raw = Some(value.clone());
@@ -610,10 +1559,14 @@ fn setup_signals(context: runner::SignalContext) {
 fn run_with_cleanup(runner: &mut runner::ComposeRunner) -> i32 {
     let mut exit_code = runner.run();
     runner.cleanup_once();
+    if runner.verification_failed() && exit_code == 0 {
+        exit_code = 1;
+    }
     let signal_exit = runner.signal_exit_code();
     if signal_exit != 0 {
         exit_code = signal_exit;
     }
+    runner.finish_hooks(exit_code);
     exit_code
 }
 
@@ -0,0 +1,49 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::domain::Scope;
+use crate::infra::engine::Engine;
+use crate::infra::ui::{UiEndpoint, LOG_FILTERS_PATH};
+use crate::infra::ui_daemon::post_run_api;
+use crate::support::constants::UI_FILE;
+
+use super::output::{self, OutputFormat};
+use super::{load_run_containers, resolve_run_id, run_metadata_from_containers};
+
+pub struct MuteArgs {
+    pub run_id: Option<String>,
+    pub service: Option<String>,
+    pub mute: bool,
+}
+
+pub fn run_mute(engine: &Engine, args: MuteArgs, output: OutputFormat) -> Result<i32, String> {
+    let command = if args.mute { "mute" } else { "unmute" };
+    let run_id = resolve_run_id(engine, command, args.run_id)?;
+    let service = args
+        .service
+        .ok_or_else(|| format!("Usage: sanelens {command} <run_id|tag> <service>"))?;
+    let endpoint = run_ui_endpoint(engine, &run_id)?;
+    let body = serde_json::json!({ command: [service] }).to_string();
+    let response = post_run_api(&endpoint, LOG_FILTERS_PATH, &body)?;
+    if output.is_json() {
+        let filter = serde_json::from_str::<serde_json::Value>(&response).unwrap_or_default();
+        output::print_json(&serde_json::json!({ "run_id": run_id, "filters": filter }));
+        return Ok(0);
+    }
+    let _ = writeln!(io::stdout(), "[compose] {command}d {service} in {run_id}");
+    Ok(0)
+}
+
+pub fn run_ui_endpoint(engine: &Engine, run_id: &str) -> Result<UiEndpoint, String> {
+    let containers = load_run_containers(engine, run_id, Scope::All)?;
+    let metadata = run_metadata_from_containers(run_id, &containers);
+    metadata
+        .derived_compose
+        .as_deref()
+        .and_then(|path| Path::new(path).parent())
+        .map(|dir| dir.join(UI_FILE))
+        .and_then(|path| fs::read(path).ok())
+        .and_then(|data| serde_json::from_slice::<UiEndpoint>(&data).ok())
+        .ok_or_else(|| format!("Run {run_id} has no log UI to update."))
+}
@@ -0,0 +1,63 @@
+use std::io::{self, IsTerminal};
+
+use crate::domain::Scope;
+use crate::infra::engine::Engine;
+use crate::support::args::take_option;
+use crate::support::oneoff::{attachable_networks, forwarded_env, run_args, OneOffSpec};
+use crate::support::run::project_name_from_run_id;
+
+use super::{load_run_containers, resolve_run_id, run_metadata_from_containers};
+
+const DEFAULT_NAME: &str = "oneoff";
+
+pub struct OneOffArgs {
+    run_id: Option<String>,
+    image: String,
+    name: Option<String>,
+    command: Vec<String>,
+}
+
+pub fn parse_oneoff_args(args: &[String]) -> Option<OneOffArgs> {
+    let (args, image) = take_option(args, "--image");
+    let image = image?;
+    let (args, name) = take_option(&args, "--name");
+    let split = args
+        .iter()
+        .position(|arg| arg == "--")
+        .unwrap_or(args.len());
+    let (head, command) = args.split_at(split);
+    Some(OneOffArgs {
+        run_id: head.first().cloned(),
+        image,
+        name,
+        command: command.iter().skip(1).cloned().collect(),
+    })
+}
+
+pub fn run_oneoff(engine: &Engine, args: OneOffArgs) -> Result<i32, String> {
+    let run_id = resolve_run_id(engine, "run", args.run_id)?;
+    let containers = load_run_containers(engine, &run_id, Scope::Running)?;
+    let metadata = run_metadata_from_containers(&run_id, &containers);
+    let networks = attachable_networks(containers.iter().flat_map(|info| &info.networks));
+    if networks.is_empty() {
+        return Err(format!("Run {run_id} has no networks to attach to."));
+    }
+    let env = containers
+        .iter()
+        .find(|info| !info.is_proxy())
+        .map(|info| forwarded_env(&info.env))
+        .unwrap_or_default();
+    let spec = OneOffSpec {
+        project_name: metadata
+            .project_name
+            .unwrap_or_else(|| project_name_from_run_id(&run_id)),
+        run_id,
+        name: args.name.unwrap_or_else(|| DEFAULT_NAME.to_string()),
+        image: args.image,
+        networks,
+        env,
+        command: args.command,
+        tty: io::stdin().is_terminal() && io::stdout().is_terminal(),
+    };
+    engine.run_container(&run_args(&spec))
+}
@@ -0,0 +1,76 @@
+use std::io::{self, Write};
+
+use crate::domain::{Scope, ServiceInfo};
+use crate::infra::engine::Engine;
+use crate::infra::probe::EndpointProbes;
+use crate::infra::ui::open_browser;
+use crate::support::args::take_flag;
+use crate::support::services::{build_service_info, endpoint_rows, service_url};
+
+use super::output::{self, OutputFormat};
+use super::{load_run_containers, print_table, resolve_run_id, run_metadata_from_containers};
+
+pub struct OpenArgs {
+    run_id: Option<String>,
+    service: Option<String>,
+    print: bool,
+}
+
+pub fn parse_open_args(args: &[String]) -> OpenArgs {
+    let (args, print) = take_flag(args, "--print");
+    let mut args = args.into_iter();
+    OpenArgs {
+        run_id: args.next(),
+        service: args.next(),
+        print,
+    }
+}
+
+pub fn run_open(engine: &Engine, args: OpenArgs, output: OutputFormat) -> Result<i32, String> {
+    let run_id = resolve_run_id(engine, "open", args.run_id)?;
+    let containers = load_run_containers(engine, &run_id, Scope::All)?;
+    let compose_file = run_metadata_from_containers(&run_id, &containers)
+        .compose_file
+        .ok_or_else(|| format!("Run {run_id} is missing compose file metadata."))?;
+    let services = build_service_info(&compose_file, engine.endpoint_host());
+    let probes = EndpointProbes::default();
+    probes.refresh(&services);
+    let services: Vec<ServiceInfo> = probes
+        .annotate(&services)
+        .into_iter()
+        .filter(|service| service.exposed)
+        .collect();
+    let Some(name) = args.service else {
+        print_services(&services, output);
+        return Ok(0);
+    };
+    let url = service_url(&services, &name)
+        .ok_or_else(|| format!("Service {name} has no published endpoint in run {run_id}."))?;
+    if output.is_json() {
+        output::print_json(&serde_json::json!({ "service": name, "url": url }));
+    } else if args.print {
+        let _ = writeln!(io::stdout(), "{url}");
+    } else {
+        eprintln!("[compose] opening {url}");
+        open_browser(&url);
+    }
+    Ok(0)
+}
+
+fn print_services(services: &[ServiceInfo], output: OutputFormat) {
+    if output.is_json() {
+        let services: Vec<_> = services
+            .iter()
+            .map(|service| {
+                serde_json::json!({
+                    "service": service.name,
+                    "url": service.endpoint,
+                    "endpoints": service.endpoints,
+                })
+            })
+            .collect();
+        output::print_json(&serde_json::json!({ "services": services }));
+        return;
+    }
+    print_table(["SERVICE", "URL"], &endpoint_rows(services));
+}
@@ -0,0 +1,29 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl OutputFormat {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("Invalid --output '{value}'. Use text or json.")),
+        }
+    }
+
+    pub const fn is_json(self) -> bool {
+        matches!(self, Self::Json)
+    }
+}
+
+pub fn print_json<T: Serialize>(value: &T) {
+    let payload = serde_json::to_string(value).unwrap_or_default();
+    let _ = writeln!(io::stdout(), "{payload}");
+}
@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::infra::engine::Engine;
+use crate::support::platform::{emulation, normalize_arch, Emulation, Platform, BINFMT_DIR};
+
+pub fn check_platform(
+    engine: &Engine,
+    platform: &Platform,
+    envoy_image: Option<&str>,
+) -> Result<(), String> {
+    let Some(arch) = engine.architecture() else {
+        eprintln!("[compose] could not read the engine architecture; not checking emulation for {platform}");
+        return Ok(());
+    };
+    match emulation(&arch, platform, Path::new(BINFMT_DIR)) {
+        Emulation::Native => {}
+        Emulation::Available => {
+            eprintln!("[compose] {platform} runs emulated on the {arch} engine");
+        }
+        Emulation::Unknown => eprintln!(
+            "[compose] cannot check emulation for {platform} on the {arch} engine (no local binfmt_misc); a missing emulator fails with `exec format error`"
+        ),
+        Emulation::Missing => {
+            return Err(format!(
+                "the engine is {arch} and has no binfmt handler for {platform}; install one (e.g. `docker run --privileged --rm tonistiigi/binfmt --install {}`) or drop --platform",
+                platform.arch
+            ));
+        }
+    }
+    if let Some(image) = envoy_image {
+        warn_envoy_platform(engine, image, platform);
+    }
+    Ok(())
+}
+
+fn warn_envoy_platform(engine: &Engine, image: &str, platform: &Platform) {
+    let inspected = engine.inspect_images(&[image.to_string()]);
+    let Some(arch) = inspected
+        .first()
+        .and_then(|item| item.get("Architecture"))
+        .and_then(Value::as_str)
+        .map(normalize_arch)
+    else {
+        return;
+    };
+    if arch != platform.arch {
+        eprintln!(
+            "[compose] warning: envoy image {image} is {arch}, not {platform}; the traffic sidecars keep running as {arch} next to emulated services"
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+
+use crate::domain::Scope;
+use crate::infra::engine::Engine;
+use crate::infra::image::{image_metadata, ImageMetadata};
+use crate::support::args::take_flag;
+
+use super::output::{self, OutputFormat};
+use super::{load_run_containers, print_table, resolve_run_id};
+
+const SHORT_ID: usize = 12;
+
+#[derive(Serialize)]
+struct PsRow {
+    service: Option<String>,
+    container: String,
+    state: Option<String>,
+    #[serde(flatten)]
+    image: ImageMetadata,
+}
+
+pub struct PsArgs {
+    run_id: Option<String>,
+}
+
+pub fn parse_ps_args(args: &[String]) -> Option<PsArgs> {
+    let (args, wide) = take_flag(args, "--wide");
+    wide.then(|| PsArgs {
+        run_id: args.into_iter().next(),
+    })
+}
+
+pub fn run_ps(engine: &Engine, args: PsArgs, output: OutputFormat) -> Result<i32, String> {
+    let run_id = resolve_run_id(engine, "ps", args.run_id)?;
+    let containers = load_run_containers(engine, &run_id, Scope::All)?;
+    let image_ids: Vec<String> = containers
+        .iter()
+        .filter_map(|info| info.image_id.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let images = engine.inspect_images(&image_ids);
+    let mut rows: Vec<PsRow> = containers
+        .iter()
+        .filter(|info| !info.is_proxy())
+        .map(|info| PsRow {
+            service: info.service.clone(),
+            container: info.id.clone(),
+            state: info.display_state(),
+            image: image_metadata(info, &images),
+        })
+        .collect();
+    rows.sort_by(|left, right| left.service.cmp(&right.service));
+    if output.is_json() {
+        output::print_json(&serde_json::json!({ "containers": rows }));
+        return Ok(0);
+    }
+    let table: Vec<[String; 7]> = rows.into_iter().map(table_row).collect();
+    print_table(
+        [
+            "SERVICE",
+            "CONTAINER",
+            "STATE",
+            "IMAGE",
+            "DIGEST",
+            "CREATED",
+            "SIZE",
+        ],
+        &table,
+    );
+    Ok(0)
+}
+
+fn table_row(row: PsRow) -> [String; 7] {
+    let dash = || "-".to_string();
+    let digest = row
+        .image
+        .digest
+        .as_deref()
+        .map(|digest| digest.split_once('@').map_or(digest, |(_, hash)| hash))
+        .map_or_else(dash, |hash| short(hash.trim_start_matches("sha256:")));
+    [
+        row.service.unwrap_or_else(dash),
+        short(&row.container),
+        row.state.unwrap_or_else(dash),
+        row.image.image.unwrap_or_else(dash),
+        digest,
+        row.image
+            .created
+            .map_or_else(dash, |created| created.chars().take(19).collect()),
+        row.image.size_bytes.map_or_else(dash, format_size),
+    ]
+}
+
+fn short(id: &str) -> String {
+    id.chars().take(SHORT_ID).collect()
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1000.0 && unit + 1 < UNITS.len() {
+        value /= 1000.0;
+        unit += 1;
+    }
+    let suffix = UNITS.get(unit).copied().unwrap_or_default();
+    if unit == 0 {
+        format!("{bytes}{suffix}")
+    } else {
+        format!("{value:.1}{suffix}")
+    }
+}
@@ -0,0 +1,214 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{never, select, Receiver};
+
+use crate::domain::traffic::{Observation, ObservationSink};
+use crate::domain::LogEvent;
+use crate::infra::engine::{Engine, RunContainers};
+use crate::infra::ui::{open_browser, UiBind, UiRun, UiServer};
+use crate::support::args::take_option;
+use crate::support::clock::now_ms;
+use crate::support::constants::{BIN_NAME, HISTORY_LIMIT};
+use crate::support::events::{EventKind, LifecycleEvent};
+use crate::support::logging::LogHub;
+use crate::support::record::{
+    parse_speed, read_entries, read_header, replay_offset, RecordHeader, RecordItem, RecordWriter,
+};
+use crate::support::traffic::TrafficHub;
+
+use super::{runner, setup_signals};
+
+const STOP_POLL: Duration = Duration::from_millis(100);
+
+pub struct RecordSources {
+    pub log_hub: Arc<LogHub>,
+    pub traffic_hub: Option<Arc<TrafficHub>>,
+    pub events: Receiver<LifecycleEvent>,
+}
+
+pub fn start_recorder(
+    path: &Path,
+    header: &RecordHeader,
+    sources: RecordSources,
+) -> Result<thread::JoinHandle<()>, String> {
+    let writer = File::create(path)
+        .and_then(|file| RecordWriter::new(BufWriter::new(file), header))
+        .map_err(|err| format!("failed to create {}: {err}", path.display()))?;
+    let (logs, history) = sources.log_hub.register_lossless_client();
+    let observations = sources
+        .traffic_hub
+        .map_or_else(never, |hub| hub.register_observation_client());
+    let path = path.to_path_buf();
+    Ok(thread::spawn(move || {
+        match record(writer, history, &logs, &observations, &sources.events) {
+            Ok(()) => eprintln!("[record] wrote {}", path.display()),
+            Err(err) => eprintln!("[record] recording to {} failed: {err}", path.display()),
+        }
+    }))
+}
+
+fn record<W: Write>(
+    mut writer: RecordWriter<W>,
+    history: Vec<LogEvent>,
+    logs: &Receiver<LogEvent>,
+    observations: &Receiver<Observation>,
+    events: &Receiver<LifecycleEvent>,
+) -> io::Result<()> {
+    for event in history {
+        writer.write(now_ms(), RecordItem::Log { event })?;
+    }
+    loop {
+        let item = select! {
+            recv(logs) -> event => event.ok().map(|event| RecordItem::Log { event }),
+            recv(observations) -> observation => observation
+                .ok()
+                .map(|observation| RecordItem::Observation { observation }),
+            recv(events) -> event => match event {
+                Ok(event) if matches!(event.kind, EventKind::CallObserved { .. }) => None,
+                Ok(event) => Some(RecordItem::Lifecycle { event }),
+                Err(_) => break,
+            },
+        };
+        if let Some(item) = item {
+            writer.write(now_ms(), item)?;
+        }
+    }
+    for event in logs.try_iter() {
+        writer.write(now_ms(), RecordItem::Log { event })?;
+    }
+    writer.flush()
+}
+
+pub struct ReplayArgs {
+    path: Option<PathBuf>,
+    speed: Option<String>,
+}
+
+pub fn parse_replay_args(args: &[String]) -> ReplayArgs {
+    let (args, speed) = take_option(args, "--speed");
+    ReplayArgs {
+        path: args.into_iter().next().map(PathBuf::from),
+        speed,
+    }
+}
+
+pub fn run_replay(engine: &Engine, args: ReplayArgs, ui_bind: &UiBind) -> Result<i32, String> {
+    let path = args
+        .path
+        .ok_or_else(|| format!("Usage: {BIN_NAME} replay <recording.slr> [--speed N]"))?;
+    let speed = args.speed.as_deref().map_or(Ok(1.0), parse_speed)?;
+    let file =
+        File::open(&path).map_err(|err| format!("failed to open {}: {err}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let header = read_header(&mut reader)?;
+    let stop_event = Arc::new(AtomicBool::new(false));
+    let exit_code = Arc::new(AtomicI32::new(0));
+    setup_signals(runner::SignalContext::new(
+        stop_event.clone(),
+        Arc::new(AtomicBool::new(false)),
+        exit_code.clone(),
+        Arc::new(runner::ProcessHandles::new()),
+    ));
+
+    let log_hub = LogHub::with_reorder(HISTORY_LIMIT, Duration::ZERO);
+    let traffic_hub = Arc::new(TrafficHub::new());
+    let run = UiRun {
+        log_hub: log_hub.clone(),
+        service_info: Arc::new(header.services),
+        endpoint_probes: None,
+        forwards: Arc::default(),
+        service_graph: Arc::new(header.graph),
+        service_config: Arc::default(),
+        alert_hub: None,
+        traffic_hub: Some(traffic_hub.clone()),
+        containers: Arc::new(RunContainers::new(engine.clone(), header.run_id.clone())),
+    };
+    let mut server = UiServer::start(run, stop_event.clone(), ui_bind)
+        .map_err(|err| format!("log UI failed: {err}"))?;
+    let url = server.url();
+    let _ = writeln!(
+        io::stdout(),
+        "[replay] {} ({}) at {speed}x: {url}",
+        header.run_id,
+        header.project
+    );
+    open_browser(&url);
+
+    match replay(reader, &log_hub, &traffic_hub, speed, &stop_event) {
+        Ok(true) => {
+            let _ = writeln!(io::stdout(), "[replay] finished; press Ctrl-C to exit");
+            while !stop_event.load(Ordering::SeqCst) {
+                thread::sleep(STOP_POLL);
+            }
+        }
+        Ok(false) => {}
+        Err(err) => eprintln!("[replay] {err}"),
+    }
+    server.stop();
+    Ok(exit_code.load(Ordering::SeqCst))
+}
+
+fn replay(
+    reader: impl BufRead,
+    log_hub: &LogHub,
+    traffic_hub: &TrafficHub,
+    speed: f64,
+    stop_event: &AtomicBool,
+) -> Result<bool, String> {
+    let started = Instant::now();
+    for entry in read_entries(reader) {
+        let entry = entry?;
+        if !wait_until(started + replay_offset(entry.offset_ms, speed), stop_event) {
+            return Ok(false);
+        }
+        apply(entry.item, log_hub, traffic_hub);
+    }
+    Ok(true)
+}
+
+fn wait_until(deadline: Instant, stop_event: &AtomicBool) -> bool {
+    loop {
+        if stop_event.load(Ordering::SeqCst) {
+            return false;
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return true;
+        }
+        thread::sleep(remaining.min(STOP_POLL));
+    }
+}
+
+fn apply(item: RecordItem, log_hub: &LogHub, traffic_hub: &TrafficHub) {
+    match item {
+        RecordItem::Log { event } => log_hub.replay(event),
+        RecordItem::Observation { observation } => traffic_hub.emit(observation),
+        RecordItem::Lifecycle { event } => {
+            let ts_ms = i64::try_from(event.at_ms).ok();
+            match event.kind {
+                EventKind::RunStarted { project, .. } => {
+                    log_hub.annotate(None, &format!("run started ({project})"), ts_ms);
+                }
+                EventKind::ServiceHealthy { service, .. } => {
+                    log_hub.annotate(Some(&service), "healthy", ts_ms);
+                }
+                EventKind::CrashLoop {
+                    service, restarts, ..
+                } => {
+                    let text = format!("crash loop ({restarts} restarts)");
+                    log_hub.annotate(Some(&service), &text, ts_ms);
+                }
+                EventKind::RunStopped { exit_code } => {
+                    log_hub.annotate(None, &format!("run stopped (exit {exit_code})"), ts_ms);
+                }
+                EventKind::CallObserved { .. } => {}
+            }
+        }
+    }
+}
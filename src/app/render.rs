@@ -0,0 +1,202 @@
+use crate::domain::traffic::{EntityId, Trace, TraceNode, TrafficCall};
+use crate::support::bench::BenchReport;
+use crate::support::chaos::CHAOS_TAG;
+use crate::support::summary::RunSummary;
+use crate::support::text::truncate;
+
+pub fn entity_label(entity: Option<&EntityId>) -> String {
+    match entity {
+        Some(EntityId::Workload {
+            name,
+            instance: Some(instance),
+        }) => format!("{name}[{instance}]"),
+        Some(EntityId::Workload { name, .. } | EntityId::Host { name }) => name.clone(),
+        Some(entity @ EntityId::External { .. }) => {
+            entity.external_host().unwrap_or_else(|| "?".to_string())
+        }
+        Some(EntityId::Unknown) | None => "?".to_string(),
+    }
+}
+
+pub fn call_summary(call: &TrafficCall) -> String {
+    let src = entity_label(call.peer.src.as_ref());
+    let dst = entity_label(call.peer.dst.as_ref());
+    let method = call.method.as_deref().unwrap_or("-");
+    let path = call.path.as_deref().unwrap_or("-");
+    let status = call
+        .status
+        .map_or_else(|| "-".to_string(), |status| status.to_string());
+    let duration = call
+        .duration_ms
+        .map_or_else(|| "-".to_string(), |ms| format!("{ms}ms"));
+    format!("{src} -> {dst} {method} {path} {status} {duration}")
+}
+
+pub fn call_log_line(call: &TrafficCall) -> String {
+    let src = entity_label(call.peer.src.as_ref());
+    let dst = entity_label(call.peer.dst.as_ref());
+    let method = call.method.as_deref().unwrap_or("-");
+    let path = call.path.as_deref().unwrap_or("-");
+    let status = call
+        .status
+        .map_or_else(|| "-".to_string(), |status| status.to_string());
+    let duration = call
+        .duration_ms
+        .map_or_else(|| "-".to_string(), |ms| format!("{ms}ms"));
+    let chaos = call
+        .attrs
+        .tags
+        .get(CHAOS_TAG)
+        .map(|kind| format!(" [chaos: {kind}]"))
+        .unwrap_or_default();
+    format!("{dst} \u{2190} {src} {method} {path} {status} {duration}{chaos}")
+}
+
+pub fn render_trace(trace: &Trace) -> Vec<String> {
+    let duration = trace
+        .duration_ms
+        .map_or_else(|| "-".to_string(), |ms| format!("{ms}ms"));
+    let mut lines = vec![format!(
+        "trace {} ({} calls, {duration})",
+        trace.request_id, trace.call_count
+    )];
+    for root in &trace.roots {
+        render_trace_node(root, 1, &mut lines);
+    }
+    lines
+}
+
+fn render_trace_node(node: &TraceNode, depth: usize, lines: &mut Vec<String>) {
+    lines.push(format!(
+        "{}{}",
+        "  ".repeat(depth),
+        call_summary(&node.call)
+    ));
+    for child in &node.children {
+        render_trace_node(child, depth + 1, lines);
+    }
+}
+
+const FLOW_WIDTH: usize = 32;
+const METHOD_WIDTH: usize = 7;
+const PATH_WIDTH: usize = 40;
+
+pub fn call_row(call: &TrafficCall, color: bool) -> String {
+    let flow = format!(
+        "{}\u{2192}{}",
+        entity_label(call.peer.src.as_ref()),
+        entity_label(call.peer.dst.as_ref())
+    );
+    let method = call.method.as_deref().unwrap_or("-");
+    let path = call.path.as_deref().unwrap_or("-");
+    let status = call
+        .status
+        .map_or_else(|| "-".to_string(), |status| status.to_string());
+    let duration = call
+        .duration_ms
+        .map_or_else(|| "-".to_string(), |ms| format!("{ms}ms"));
+    let chaos = call
+        .attrs
+        .tags
+        .get(CHAOS_TAG)
+        .map(|kind| format!(" [chaos: {kind}]"))
+        .unwrap_or_default();
+    let row = format!(
+        "{} {flow:<FLOW_WIDTH$} {method:<METHOD_WIDTH$} {:<PATH_WIDTH$} {status:>3} {duration:>7}{chaos}",
+        clock(call.at_ms),
+        truncate(path, PATH_WIDTH),
+    );
+    match status_color(call.status).filter(|_| color) {
+        Some(code) => format!("\u{1b}[{code}m{row}\u{1b}[0m"),
+        None => row,
+    }
+}
+
+const fn status_color(status: Option<u16>) -> Option<u8> {
+    match status {
+        Some(200..=299) => Some(32),
+        Some(300..=399) => Some(36),
+        Some(400..=499) => Some(33),
+        Some(500..=599) => Some(31),
+        _ => None,
+    }
+}
+
+fn clock(at_ms: u64) -> String {
+    let millis = at_ms % 1_000;
+    let secs = (at_ms / 1_000) % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}.{millis:03}",
+        secs / 3_600,
+        (secs / 60) % 60,
+        secs % 60
+    )
+}
+
+pub fn render_summary(summary: &RunSummary) -> Vec<String> {
+    let mut lines = vec![format!("run {} summary", summary.run_id)];
+    for service in &summary.services {
+        let state = service.state.as_deref().unwrap_or("-");
+        let exit = service
+            .exit_code
+            .map_or_else(|| "-".to_string(), |code| code.to_string());
+        lines.push(format!(
+            "  {:<24} {state:<10} exit {exit:<4} restarts {:<3} log lines {}",
+            service.service, service.restarts, service.log_lines
+        ));
+    }
+    if !summary.requests.is_empty() {
+        let classes: Vec<String> = summary
+            .requests
+            .iter()
+            .map(|(class, count)| format!("{class} {count}"))
+            .collect();
+        lines.push(format!("  requests: {}", classes.join(", ")));
+    }
+    if !summary.suppressed.is_empty() {
+        let services: Vec<String> = summary
+            .suppressed
+            .iter()
+            .map(|(service, count)| format!("{service} {count}"))
+            .collect();
+        lines.push(format!("  ignored calls: {}", services.join(", ")));
+    }
+    if !summary.slowest.is_empty() {
+        lines.push("  slowest endpoints (p95):".to_string());
+        for endpoint in &summary.slowest {
+            lines.push(format!(
+                "    {:>6}ms {} {} {} ({} calls)",
+                endpoint.p95_ms, endpoint.service, endpoint.method, endpoint.route, endpoint.count
+            ));
+        }
+    }
+    if !summary.external_hosts.is_empty() {
+        lines.push(format!(
+            "  external hosts: {}",
+            summary.external_hosts.join(", ")
+        ));
+    }
+    lines
+}
+
+pub fn render_bench(report: &BenchReport) -> Vec<String> {
+    let mut lines = vec![format!(
+        "bench {}: {} requests in {}ms ({} req/s), {} errors",
+        report.target, report.requests, report.duration_ms, report.achieved_rps, report.errors
+    )];
+    if let Some(latency) = &report.latency {
+        lines.push(format!(
+            "  latency: p50 {}ms, p90 {}ms, p99 {}ms, max {}ms",
+            latency.p50_ms, latency.p90_ms, latency.p99_ms, latency.max_ms
+        ));
+    }
+    if !report.statuses.is_empty() {
+        let statuses: Vec<String> = report
+            .statuses
+            .iter()
+            .map(|(status, count)| format!("{status} {count}"))
+            .collect();
+        lines.push(format!("  statuses: {}", statuses.join(", ")));
+    }
+    lines
+}
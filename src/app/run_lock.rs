@@ -0,0 +1,55 @@
+use std::fs;
+
+use crate::domain::Scope;
+use crate::infra::engine::Engine;
+use crate::infra::process::pid_alive;
+use crate::infra::run_lock::{LockHolder, LockOutcome, RunLock};
+use crate::support::args::{has_flag, take_flag};
+use crate::support::run::{run_started_at, state_dir};
+
+use super::ComposeInput;
+
+const LOCK_DIR: &str = "locks";
+
+pub fn lock_compose_file(
+    engine: &Engine,
+    compose: &mut ComposeInput,
+    run_id: &str,
+) -> Result<Option<RunLock>, String> {
+    let (args, force_new) = take_flag(&compose.args, "--force-new");
+    compose.args = args;
+    let Some(dir) = state_dir().map(|dir| dir.join(LOCK_DIR)) else {
+        return Ok(None);
+    };
+    if force_new || compose.base_dir.is_some() {
+        return Ok(None);
+    }
+    let compose_file = fs::canonicalize(&compose.file).map_or_else(
+        |_| compose.file.clone(),
+        |path| path.to_string_lossy().into_owned(),
+    );
+    let holder = LockHolder {
+        compose_file,
+        run_id: run_id.to_string(),
+        pid: std::process::id(),
+        started_at: run_started_at(),
+        detached: has_flag(&compose.args, &["-d", "--detach"]),
+    };
+    let is_live = |held: &LockHolder| {
+        i32::try_from(held.pid).is_ok_and(pid_alive)
+            || !engine
+                .collect_run_container_ids(&held.run_id, Scope::Running)
+                .is_empty()
+    };
+    match RunLock::acquire(&dir, &holder, is_live) {
+        Ok(LockOutcome::Acquired(lock)) => Ok(Some(lock)),
+        Ok(LockOutcome::Held(held)) => Err(format!(
+            "[compose] {} is already running as {} (started {}).\n[compose] Attach with `sanelens logs {}` or `sanelens open {}`, or pass --force-new to start a separate run.",
+            held.compose_file, held.run_id, held.started_at, held.run_id, held.run_id
+        )),
+        Err(err) => {
+            eprintln!("[compose] run lock unavailable: {err}");
+            Ok(None)
+        }
+    }
+}
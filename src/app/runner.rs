@@ -1,34 +1,105 @@
 use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
-use std::io::{BufRead, BufReader, IsTerminal, Read, Write};
-use std::path::PathBuf;
+use std::io::{self, BufRead, BufReader, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command, ExitStatus, Stdio};
-use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-
-use crate::domain::traffic::ObservationSink;
-use crate::domain::{Scope, ServiceInfo};
-use crate::infra::derive::{derive_compose, DeriveConfig, DerivedCompose};
-use crate::infra::engine::{CleanupContext, Engine};
-use crate::infra::process::{spawn_process_group, terminate_process};
-use crate::infra::resolver::RuntimeResolver;
-use crate::infra::traffic::{observation_from_envoy, observation_from_tap, parse_envoy_log_line};
-use crate::infra::ui::{open_browser, UiServer};
+use std::time::{Duration, Instant};
+
+use crate::domain::traffic::{Observation, ObservationSink};
+use crate::domain::{instance_identity, OutputStream, Scope, ServiceInfo};
+use crate::infra::backend::StreamHandle;
+use crate::infra::compose::legacy_project_args;
+use crate::infra::container_watch::ContainerWatch;
+use crate::infra::derive::{
+    derive_compose, DeriveConfig, DerivedCompose, EnvOverride, ResourceLimit,
+};
+use crate::infra::effective::load_effective_config;
+use crate::infra::engine::{scaled_instances, CleanupContext, Engine, RunContainers};
+use crate::infra::probe::EndpointProbes;
+use crate::infra::process::{
+    kill_process_group, run_output, spawn_process_group, terminate_process,
+};
+use crate::infra::resolver::{DnsCache, RuntimeResolver};
+use crate::infra::traffic::{
+    observation_from_envoy, observation_from_tap, parse_envoy_log_line, EnvoyAccessLog,
+    ObservationContext, SocketTap,
+};
+use crate::infra::ui::{open_browser, UiBind, UiEndpoint, UiRun, UiServer};
+use crate::infra::ui_daemon::register_with_daemon;
+use crate::infra::verify::Verifier;
+use crate::infra::watch::{load_develop_watch_services, load_watch_targets, Watcher};
+use crate::support::alerts::{alert_rules_from_doc, AlertHub};
 use crate::support::args::{
-    extract_subcommand, has_flag, insert_after, is_env_false, is_env_truthy,
+    extract_subcommand, has_flag, insert_after, is_env_false, is_env_truthy, parse_duration,
     strip_compose_file_args, take_flag,
 };
-use crate::support::constants::{BIN_NAME, HISTORY_LIMIT};
-use crate::support::logging::{log_worker, LogHub, LogWorkerConfig};
-use crate::support::services::build_service_info;
+use crate::support::chaos::{FaultMarks, CHAOS_TAG};
+use crate::support::clock::now_ms;
+use crate::support::compose_errors::{diagnose, OutputTail, TeeReader};
+use crate::support::constants::{
+    BIN_NAME, DEFAULT_MAX_BODY_BYTES, HISTORY_LIMIT, KEEP_VOLUMES_ENV, MAX_BODY_BYTES_LABEL,
+    ROOTLESS_COMPAT_ENV, TRAFFIC_IGNORE_LABEL, UI_FILE,
+};
+use crate::support::crash::{remove_run_dir, CrashLoopPolicy, CRASH_DIR};
+use crate::support::events::{EventBus, EventKind};
+use crate::support::log_files::LogFileSink;
+use crate::support::log_filter::LogFilter;
+use crate::support::log_sink::LogSink;
+use crate::support::logging::{
+    log_worker, LogHub, LogWorkerConfig, StdoutFormat, DEFAULT_LOG_REORDER,
+};
+use crate::support::platform::Platform;
+use crate::support::record::{RecordHeader, RECORD_VERSION};
+use crate::support::sampling::{TrafficIgnore, TrafficSampling};
+use crate::support::services::{build_dependency_graph, build_pane, build_service_info};
+use crate::support::settings::{
+    env_or_default, BROWSER_READY_TIMEOUT_ENV, CRASH_LOOP_ENV, EGRESS_INIT_IMAGE_ENV,
+    ENVOY_IMAGE_ENV,
+};
+use crate::support::shutdown::{shutdown_tiers, Interrupt, InterruptTracker, ShutdownOrder};
+use crate::support::skew::ClockSkewPolicy;
+use crate::support::summary::{
+    external_hosts, slowest_endpoints, RunSummary, ServiceSummary, SLOWEST_ENDPOINT_LIMIT,
+};
+use crate::support::tap_failover::{
+    is_tap_write_error, CaptureMode, TapFailover, CAPTURE_TAG, HEADERS_ONLY,
+};
 use crate::support::traffic::TrafficHub;
+use crate::support::traffic_logs::TrafficLogs;
+use crate::support::wait::WaitOptions;
+
+use super::alerts::start_alerts;
+use super::crash::CrashCapture;
+use super::envoy_image::ensure_envoy_image;
+use super::hooks::{publish, start_hooks, watch_events, EventContext};
+use super::inspect;
+use super::platform::check_platform;
+use super::record::{start_recorder, RecordSources};
+use super::render::render_summary;
+use super::stall::{
+    StallAction, StallPolicy, StallTarget, StallWatch, STALL_EXIT_CODE, STALL_RETRIES,
+};
+use super::traffic_logs::start_traffic_logs;
+use super::verify::VerifyTask;
+use super::wait::wait_for_services;
 
 pub struct ProcessHandles {
     compose_proc: Mutex<Option<Child>>,
-    log_procs: Mutex<Vec<Child>>,
+    log_procs: Mutex<Vec<StreamHandle>>,
+    compose_pid: AtomicU32,
+    log_pids: Mutex<Vec<u32>>,
+    forced: AtomicBool,
+    shutdown_plan: Mutex<Option<ShutdownPlan>>,
+}
+
+struct ShutdownPlan {
+    engine: Engine,
+    run_id: String,
+    tiers: Vec<Vec<String>>,
 }
 
 impl ProcessHandles {
@@ -36,10 +107,47 @@ impl ProcessHandles {
         Self {
             compose_proc: Mutex::new(None),
             log_procs: Mutex::new(Vec::new()),
+            compose_pid: AtomicU32::new(0),
+            log_pids: Mutex::new(Vec::new()),
+            forced: AtomicBool::new(false),
+            shutdown_plan: Mutex::new(None),
+        }
+    }
+
+    fn log_pids(&self) -> MutexGuard<'_, Vec<u32>> {
+        self.log_pids
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn add_log_proc(&self, handle: StreamHandle) {
+        if let Some(pid) = handle.pid() {
+            self.log_pids().push(pid);
         }
+        self.log_procs().push(handle);
     }
 
-    fn log_procs(&self) -> MutexGuard<'_, Vec<Child>> {
+    fn set_compose_proc(&self, child: Child) {
+        self.compose_pid.store(child.id(), Ordering::SeqCst);
+        *self.compose_proc() = Some(child);
+    }
+
+    pub fn force_kill(&self) {
+        self.forced.store(true, Ordering::SeqCst);
+        let compose_pid = self.compose_pid.swap(0, Ordering::SeqCst);
+        if compose_pid != 0 {
+            kill_process_group(compose_pid);
+        }
+        for pid in self.log_pids().drain(..) {
+            kill_process_group(pid);
+        }
+    }
+
+    pub fn was_forced(&self) -> bool {
+        self.forced.load(Ordering::SeqCst)
+    }
+
+    fn log_procs(&self) -> MutexGuard<'_, Vec<StreamHandle>> {
         self.log_procs
             .lock()
             .unwrap_or_else(std::sync::PoisonError::into_inner)
@@ -54,17 +162,42 @@ impl ProcessHandles {
     pub fn stop_log_procs(&self) {
         let mut procs = self.log_procs();
         for proc in procs.iter_mut() {
-            terminate_process(proc, Duration::from_secs(5));
+            proc.stop();
         }
         procs.clear();
+        drop(procs);
+        self.log_pids().clear();
+    }
+
+    fn prune_log_procs(&self) {
+        let mut procs = self.log_procs();
+        procs.retain_mut(StreamHandle::is_running);
+        *self.log_pids() = procs.iter().filter_map(StreamHandle::pid).collect();
+    }
+
+    fn set_shutdown_plan(&self, plan: ShutdownPlan) {
+        *self
+            .shutdown_plan
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(plan);
     }
 
     pub fn stop_compose_proc(&self) {
+        let plan = self
+            .shutdown_plan
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .take();
+        if let Some(plan) = plan.filter(|_| !self.was_forced()) {
+            plan.engine.stop_in_order(&plan.run_id, &plan.tiers);
+        }
         let mut proc = self.compose_proc();
         if let Some(child) = proc.as_mut() {
             terminate_process(child, Duration::from_secs(10));
         }
         *proc = None;
+        drop(proc);
+        self.compose_pid.store(0, Ordering::SeqCst);
     }
 }
 
@@ -84,32 +217,137 @@ pub struct ComposeRunner {
     original_compose_file: String,
     compose_file: String,
     compose_file_from_args: bool,
+    compose_dirs: ComposeDirs,
     run_id: String,
     project_name: String,
     run_started_at: String,
     compose_args: Vec<String>,
     engine: Engine,
     stop_event: Arc<AtomicBool>,
+    container_watch: Arc<ContainerWatch>,
     cleanup_enabled: bool,
     cleanup_done: bool,
     signal_handled: Arc<AtomicBool>,
     exit_code: Arc<AtomicI32>,
     handles: Arc<ProcessHandles>,
     project_args: Vec<String>,
+    legacy_compose: bool,
     log_hub: Option<Arc<LogHub>>,
     ui_server: Option<UiServer>,
+    ui_bind: UiBind,
+    file_sink: Option<Arc<LogFileSink>>,
+    log_sink: Option<Arc<LogSink>>,
+    compose_stderr: Arc<OutputTail>,
+    log_tuning: LogTuning,
     service_info: Vec<ServiceInfo>,
-    log_follow_thread: Option<thread::JoinHandle<i32>>,
-    log_threads: Vec<thread::JoinHandle<()>>,
+    endpoint_probes: Arc<EndpointProbes>,
     traffic_enabled: bool,
     traffic_hub: Option<Arc<TrafficHub>>,
-    traffic_threads: Vec<thread::JoinHandle<()>>,
+    traffic_sampling: TrafficSampling,
+    traffic_logs: Option<TrafficLogs>,
+    max_body_bytes: usize,
+    skip_missing_secrets: bool,
     proxy_services: HashSet<String>,
     service_aliases: HashMap<String, String>,
     egress_proxy: Option<String>,
     watchdog_proc: Option<Child>,
     derived_dir: Option<PathBuf>,
     retain_run_dir: bool,
+    watch_enabled: bool,
+    keep_volumes: bool,
+    shutdown_order: ShutdownOrder,
+    tag: Option<String>,
+    wait: WaitOptions,
+    service_overrides: ServiceOverrides,
+    stall: StallState,
+    threads: RunnerThreads,
+    hooks: RunHooks,
+    report: RunReport,
+}
+
+#[derive(Default)]
+struct RunnerThreads {
+    log_follow: Option<thread::JoinHandle<i32>>,
+    logs: Vec<thread::JoinHandle<()>>,
+    traffic: Vec<thread::JoinHandle<()>>,
+    watch: Option<thread::JoinHandle<()>>,
+    alert: Option<thread::JoinHandle<()>>,
+    traffic_log: Option<thread::JoinHandle<()>>,
+    events: Vec<thread::JoinHandle<()>>,
+}
+
+impl RunnerThreads {
+    fn join(&mut self) {
+        if let Some(handle) = self.log_follow.take() {
+            let _ = handle.join();
+        }
+        if let Some(handle) = self.watch.take() {
+            let _ = handle.join();
+        }
+        for handle in self.logs.drain(..) {
+            let _ = handle.join();
+        }
+        for handle in self.traffic.drain(..) {
+            let _ = handle.join();
+        }
+        if let Some(handle) = self.alert.take() {
+            let _ = handle.join();
+        }
+        if let Some(handle) = self.traffic_log.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[derive(Default)]
+struct RunHooks {
+    command: Option<String>,
+    record_path: Option<PathBuf>,
+    event_bus: Option<Arc<EventBus>>,
+    alert_hub: Option<Arc<AlertHub>>,
+}
+
+#[derive(Default)]
+struct RunReport {
+    verifier: Option<Verifier>,
+    verify_task: Option<VerifyTask>,
+    verification_failed: bool,
+    summary_enabled: bool,
+}
+
+#[derive(Default)]
+struct StallState {
+    policy: StallPolicy,
+    watch: Option<StallWatch>,
+}
+
+struct LogTuning {
+    reorder: Duration,
+    dedup: Duration,
+    skew: ClockSkewPolicy,
+}
+
+impl Default for LogTuning {
+    fn default() -> Self {
+        Self {
+            reorder: DEFAULT_LOG_REORDER,
+            dedup: Duration::ZERO,
+            skew: ClockSkewPolicy::default(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ServiceOverrides {
+    env: Vec<EnvOverride>,
+    limits: Vec<ResourceLimit>,
+    platform: Option<Platform>,
+}
+
+#[derive(Default)]
+struct ComposeDirs {
+    base_dir: Option<PathBuf>,
+    run_root: Option<PathBuf>,
 }
 
 #[allow(clippy::struct_excessive_bools)]
@@ -131,42 +369,76 @@ struct LogThreadOptions {
     emit_stdout: bool,
     color_enabled: bool,
     timestamps_enabled: bool,
+    max_len: usize,
+}
+
+struct WatchRebuilder {
+    watcher: Watcher,
+    develop: Vec<String>,
+    compose: Vec<String>,
+    stop_event: Arc<AtomicBool>,
 }
 
 impl ComposeRunner {
     pub fn new(config: ComposeRunnerConfig) -> Self {
-        let service_info = build_service_info(&config.compose_file);
+        let service_info = build_service_info(&config.compose_file, config.engine.endpoint_host());
+        let stop_event = Arc::new(AtomicBool::new(false));
+        let container_watch = ContainerWatch::new(
+            config.engine.clone(),
+            config.run_id.clone(),
+            stop_event.clone(),
+        );
         Self {
             compose_cmd: config.compose_cmd,
             original_compose_file: config.compose_file.clone(),
             compose_file: config.compose_file,
             compose_file_from_args: false,
+            compose_dirs: ComposeDirs::default(),
             run_id: config.run_id,
             project_name: config.project_name,
             run_started_at: config.run_started_at,
             compose_args: config.args,
             engine: config.engine,
-            stop_event: Arc::new(AtomicBool::new(false)),
+            stop_event,
+            container_watch,
             cleanup_enabled: false,
             cleanup_done: false,
             signal_handled: Arc::new(AtomicBool::new(false)),
             exit_code: Arc::new(AtomicI32::new(0)),
             handles: Arc::new(ProcessHandles::new()),
             project_args: Vec::new(),
+            legacy_compose: false,
             log_hub: None,
             ui_server: None,
+            ui_bind: UiBind::default(),
+            file_sink: None,
+            log_sink: None,
+            compose_stderr: OutputTail::new(),
+            log_tuning: LogTuning::default(),
             service_info,
-            log_follow_thread: None,
-            log_threads: Vec::new(),
+            endpoint_probes: Arc::default(),
             traffic_enabled: false,
             traffic_hub: None,
-            traffic_threads: Vec::new(),
+            traffic_sampling: TrafficSampling::default(),
+            traffic_logs: None,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            skip_missing_secrets: false,
             proxy_services: HashSet::new(),
             service_aliases: HashMap::new(),
             egress_proxy: None,
             watchdog_proc: None,
             derived_dir: None,
             retain_run_dir: false,
+            watch_enabled: false,
+            keep_volumes: false,
+            shutdown_order: ShutdownOrder::default(),
+            tag: None,
+            wait: WaitOptions::default(),
+            service_overrides: ServiceOverrides::default(),
+            stall: StallState::default(),
+            threads: RunnerThreads::default(),
+            hooks: RunHooks::default(),
+            report: RunReport::default(),
         }
     }
 
@@ -174,10 +446,278 @@ impl ComposeRunner {
         self.compose_file_from_args = from_args;
     }
 
+    pub fn set_compose_base_dir(&mut self, dir: Option<PathBuf>) {
+        self.compose_dirs.base_dir = dir;
+    }
+
+    pub fn set_run_root(&mut self, dir: Option<PathBuf>) {
+        self.compose_dirs.run_root = dir;
+    }
+
     pub const fn set_traffic_enabled(&mut self, enabled: bool) {
         self.traffic_enabled = enabled;
     }
 
+    pub const fn set_traffic_sampling(&mut self, sampling: TrafficSampling) {
+        self.traffic_sampling = sampling;
+    }
+
+    pub fn set_traffic_logs(&mut self, logs: Option<TrafficLogs>) {
+        self.traffic_logs = logs;
+    }
+
+    pub const fn set_max_body_bytes(&mut self, max_body_bytes: usize) {
+        self.max_body_bytes = max_body_bytes;
+    }
+
+    pub const fn set_skip_missing_secrets(&mut self, skip: bool) {
+        self.skip_missing_secrets = skip;
+    }
+
+    pub fn set_ui_bind(&mut self, bind: UiBind) {
+        self.ui_bind = bind;
+    }
+
+    pub fn set_file_sink(&mut self, sink: Option<Arc<LogFileSink>>) {
+        self.file_sink = sink;
+    }
+
+    pub fn set_log_sink(&mut self, sink: Option<Arc<LogSink>>) {
+        self.log_sink = sink;
+    }
+
+    pub const fn set_log_reorder(&mut self, window: Duration) {
+        self.log_tuning.reorder = window;
+    }
+
+    pub const fn set_log_dedup(&mut self, window: Duration) {
+        self.log_tuning.dedup = window;
+    }
+
+    pub const fn set_log_skew(&mut self, policy: ClockSkewPolicy) {
+        self.log_tuning.skew = policy;
+    }
+
+    pub const fn set_legacy_compose(&mut self, legacy: bool) {
+        self.legacy_compose = legacy;
+    }
+
+    pub const fn set_keep_volumes(&mut self, keep: bool) {
+        self.keep_volumes = keep;
+    }
+
+    pub const fn set_shutdown_order(&mut self, order: ShutdownOrder) {
+        self.shutdown_order = order;
+    }
+
+    pub fn set_tag(&mut self, tag: Option<String>) {
+        self.tag = tag;
+    }
+
+    pub fn set_verifier(&mut self, verifier: Option<Verifier>) {
+        self.report.verifier = verifier;
+    }
+
+    pub fn set_env_overrides(&mut self, overrides: Vec<EnvOverride>) {
+        self.service_overrides.env = overrides;
+    }
+
+    pub fn set_resource_limits(&mut self, limits: Vec<ResourceLimit>) {
+        self.service_overrides.limits = limits;
+    }
+
+    pub fn set_platform(&mut self, platform: Option<Platform>) {
+        self.service_overrides.platform = platform;
+    }
+
+    pub const fn set_stall(&mut self, stall: StallPolicy) {
+        self.stall.policy = stall;
+    }
+
+    pub fn set_hook(&mut self, command: Option<String>) {
+        self.hooks.command = command;
+    }
+
+    pub fn set_record(&mut self, path: Option<PathBuf>) {
+        self.hooks.record_path = path;
+    }
+
+    pub fn set_wait(&mut self, wait: WaitOptions) {
+        self.wait = wait;
+    }
+
+    fn take_wait_options(&mut self, subcommand: &str) -> Option<WaitOptions> {
+        let wait = std::mem::take(&mut self.wait);
+        if wait.targets.is_empty() {
+            return None;
+        }
+        if subcommand != "up" || !has_flag(&self.compose_args, &["-d", "--detach"]) {
+            eprintln!("[wait] --wait-for needs `up -d`; not waiting.");
+            return None;
+        }
+        Some(wait)
+    }
+
+    pub const fn verification_failed(&self) -> bool {
+        self.report.verification_failed
+    }
+
+    fn start_verifier(&mut self, subcommand: &str) {
+        let Some(verifier) = self.report.verifier.take() else {
+            return;
+        };
+        if subcommand != "up" || has_flag(&self.compose_args, &["-d", "--detach"]) {
+            eprintln!("[verify] --verify needs a foreground `up`; skipping verification.");
+            return;
+        }
+        let Some(hub) = self.ensure_traffic_hub() else {
+            eprintln!("[verify] traffic capture is disabled; cannot verify expectations.");
+            self.report.verification_failed = true;
+            return;
+        };
+        self.report.verify_task = Some(VerifyTask::start(verifier, hub));
+    }
+
+    fn start_alerts(&mut self, subcommand: &str) {
+        if subcommand != "up" || has_flag(&self.compose_args, &["-d", "--detach"]) {
+            return;
+        }
+        let rules = fs::read_to_string(&self.compose_file)
+            .map_err(|err| err.to_string())
+            .and_then(|contents| serde_yaml::from_str(&contents).map_err(|err| err.to_string()))
+            .and_then(|doc| alert_rules_from_doc(&doc));
+        let rules = match rules {
+            Ok(rules) if rules.is_empty() => return,
+            Ok(rules) => rules,
+            Err(err) => {
+                eprintln!("[alert] alerts disabled: {err}");
+                return;
+            }
+        };
+        let hub = Arc::new(AlertHub::new(rules));
+        let log_hub = self.ensure_log_hub();
+        let traffic_hub = self.ensure_traffic_hub();
+        self.threads.alert = Some(start_alerts(
+            hub.clone(),
+            &log_hub,
+            traffic_hub.as_deref(),
+            self.stop_event.clone(),
+        ));
+        self.hooks.alert_hub = Some(hub);
+    }
+
+    fn start_traffic_logs(&mut self, subcommand: &str) {
+        let Some(logs) = self.traffic_logs.take() else {
+            return;
+        };
+        if subcommand != "up" || has_flag(&self.compose_args, &["-d", "--detach"]) {
+            eprintln!("[compose] --traffic-logs needs a foreground `up`; not mirroring calls.");
+            return;
+        }
+        let Some(traffic_hub) = self.ensure_traffic_hub() else {
+            eprintln!("[compose] --traffic-logs needs traffic capture; not mirroring calls.");
+            return;
+        };
+        let log_hub = self.ensure_log_hub();
+        self.threads.traffic_log = Some(start_traffic_logs(
+            logs,
+            log_hub,
+            &traffic_hub,
+            self.stop_event.clone(),
+        ));
+    }
+
+    fn start_events(&mut self, subcommand: &str) {
+        let command = self.hooks.command.take();
+        let record_path = self.hooks.record_path.take();
+        if subcommand != "up" || has_flag(&self.compose_args, &["-d", "--detach"]) {
+            if command.is_some() {
+                eprintln!("[hook] --hook needs a foreground `up`; skipping hooks.");
+            }
+            if record_path.is_some() {
+                eprintln!("[record] --record needs a foreground `up`; not recording.");
+            }
+            return;
+        }
+        let crash = self.crash_capture();
+        if command.is_none() && record_path.is_none() && crash.is_none() {
+            return;
+        }
+        let bus = Arc::new(EventBus::new());
+        if let Some(command) = command {
+            self.threads.events.push(start_hooks(command, &bus));
+        }
+        if let Some(path) = record_path {
+            self.start_recorder(&path, &bus);
+        }
+        let traffic_hub = self.ensure_traffic_hub();
+        self.threads.events.push(watch_events(
+            EventContext {
+                bus: bus.clone(),
+                engine: self.engine.clone(),
+                run_id: self.run_id.clone(),
+                stop_event: self.stop_event.clone(),
+                crash,
+            },
+            traffic_hub.as_deref(),
+        ));
+        let kind = EventKind::RunStarted {
+            project: self.project_name.clone(),
+            tag: self.tag.clone(),
+        };
+        publish(&bus, &self.run_id, kind);
+        self.hooks.event_bus = Some(bus);
+    }
+
+    fn crash_capture(&mut self) -> Option<CrashCapture> {
+        let policy =
+            env_or_default(CRASH_LOOP_ENV).map_or(Ok(None), |value| CrashLoopPolicy::parse(&value));
+        let policy = match policy {
+            Ok(policy) => policy?,
+            Err(err) => {
+                eprintln!("[alert] {CRASH_LOOP_ENV}: {err} Crash-loop detection is off.");
+                return None;
+            }
+        };
+        Some(CrashCapture {
+            policy,
+            log_hub: self.ensure_log_hub(),
+            alert_hub: self.hooks.alert_hub.clone(),
+            run_dir: self.derived_dir.clone(),
+        })
+    }
+
+    fn start_recorder(&mut self, path: &Path, bus: &EventBus) {
+        let header = RecordHeader {
+            version: RECORD_VERSION,
+            run_id: self.run_id.clone(),
+            project: self.project_name.clone(),
+            started_at_ms: now_ms(),
+            services: self.service_info.clone(),
+            graph: build_dependency_graph(&self.original_compose_file),
+        };
+        let sources = RecordSources {
+            log_hub: self.ensure_log_hub(),
+            traffic_hub: self.ensure_traffic_hub(),
+            events: bus.subscribe_lossless(),
+        };
+        match start_recorder(path, &header, sources) {
+            Ok(handle) => self.threads.events.push(handle),
+            Err(err) => eprintln!("[record] {err}"),
+        }
+    }
+
+    pub fn finish_hooks(&mut self, exit_code: i32) {
+        let Some(bus) = self.hooks.event_bus.take() else {
+            return;
+        };
+        publish(&bus, &self.run_id, EventKind::RunStopped { exit_code });
+        bus.close();
+        for handle in self.threads.events.drain(..) {
+            let _ = handle.join();
+        }
+    }
+
     pub fn set_derived_dir(&mut self, dir: Option<PathBuf>) {
         self.derived_dir = dir;
     }
@@ -186,29 +726,73 @@ impl ComposeRunner {
         self.cleanup_enabled = true;
     }
 
-    fn prepare_derived_compose(&mut self) -> Result<(), String> {
-        let envoy_image = if self.traffic_enabled {
-            env::var("SANELENS_ENVOY_IMAGE")
-                .unwrap_or_else(|_| "envoyproxy/envoy:v1.30-latest".to_string())
-        } else {
-            "envoyproxy/envoy:v1.30-latest".to_string()
-        };
+    fn prepare_envoy_image(&mut self, subcommand: &str) -> Result<String, String> {
+        let mut envoy_image = env_or_default(ENVOY_IMAGE_ENV).unwrap_or_default();
+        if self.traffic_enabled
+            && self.engine.is_remote()
+            && matches!(subcommand, "up" | "run" | "create")
+        {
+            eprintln!(
+                "[compose] traffic disabled: the engine runs on {}, which cannot mount the local proxy configs and tap directories",
+                self.engine.endpoint_host()
+            );
+            self.traffic_enabled = false;
+        }
+        if self.traffic_enabled && matches!(subcommand, "up" | "run" | "create") {
+            match ensure_envoy_image(&self.engine, &envoy_image) {
+                Ok(image) => envoy_image = image,
+                Err(err) => {
+                    eprintln!("[compose] traffic disabled: {err}");
+                    self.traffic_enabled = false;
+                }
+            }
+        }
+        if let Some(platform) = self
+            .service_overrides
+            .platform
+            .as_ref()
+            .filter(|_| matches!(subcommand, "up" | "run" | "create"))
+        {
+            let envoy = self.traffic_enabled.then_some(envoy_image.as_str());
+            check_platform(&self.engine, platform, envoy)?;
+        }
+        Ok(envoy_image)
+    }
+
+    fn prepare_derived_compose(&mut self, subcommand: &str) -> Result<Vec<String>, String> {
+        let envoy_image = self.prepare_envoy_image(subcommand)?;
+        let egress_transparent = env::var("SANELENS_EGRESS_MODE")
+            .is_ok_and(|mode| mode.trim().eq_ignore_ascii_case("transparent"));
         let mut config = DeriveConfig {
             run_id: self.run_id.clone(),
             run_started_at: self.run_started_at.clone(),
+            tag: self.tag.clone(),
             envoy_image,
             enable_traffic: self.traffic_enabled,
-            enable_egress: self.traffic_enabled && is_env_truthy("SANELENS_EGRESS_PROXY"),
+            enable_egress: self.traffic_enabled
+                && (is_env_truthy("SANELENS_EGRESS_PROXY") || egress_transparent),
+            egress_transparent,
+            egress_init_image: env_or_default(EGRESS_INIT_IMAGE_ENV).unwrap_or_default(),
             compose_cmd: self.compose_cmd.clone(),
             compose_args: self.compose_args.clone(),
             compose_file_from_args: self.compose_file_from_args,
             disable_pods: self.engine.is_podman(),
+            max_body_bytes: self.max_body_bytes,
+            skip_missing_secrets: self.skip_missing_secrets,
+            rootless_compat: self.traffic_enabled && self.rootless_compat(),
+            env_overrides: self.service_overrides.env.clone(),
+            resource_limits: self.service_overrides.limits.clone(),
+            platform: self
+                .service_overrides
+                .platform
+                .as_ref()
+                .map(ToString::to_string),
+            base_dir: self.compose_dirs.base_dir.clone(),
+            run_root: self.compose_dirs.run_root.clone(),
+            explain: subcommand == "derive",
         };
         match derive_compose(&self.original_compose_file, &self.project_name, &config) {
-            Ok(derived) => {
-                self.apply_derived_compose(derived);
-                Ok(())
-            }
+            Ok(derived) => Ok(self.apply_derived_compose(derived)),
             Err(err) => {
                 if !self.traffic_enabled {
                     return Err(err);
@@ -219,13 +803,32 @@ impl ComposeRunner {
                 config.enable_egress = false;
                 let derived =
                     derive_compose(&self.original_compose_file, &self.project_name, &config)?;
-                self.apply_derived_compose(derived);
-                Ok(())
+                Ok(self.apply_derived_compose(derived))
             }
         }
     }
 
-    fn apply_derived_compose(&mut self, derived: DerivedCompose) {
+    pub fn derive_only(&mut self) -> Result<(PathBuf, Vec<String>), String> {
+        let rewrites = self.prepare_derived_compose("derive")?;
+        Ok((PathBuf::from(&self.compose_file), rewrites))
+    }
+
+    fn rootless_compat(&self) -> bool {
+        if is_env_false(ROOTLESS_COMPAT_ENV) {
+            return false;
+        }
+        let network = self.engine.rootless_network();
+        if network.is_none() && !is_env_truthy(ROOTLESS_COMPAT_ENV) {
+            return false;
+        }
+        let network = network.unwrap_or_else(|| "forced".to_string());
+        eprintln!(
+            "[compose] rootless podman ({network}): proxies listen on IPv4 and IPv6 and services get host-gateway aliases; set {ROOTLESS_COMPAT_ENV}=0 to turn this off"
+        );
+        true
+    }
+
+    fn apply_derived_compose(&mut self, derived: DerivedCompose) -> Vec<String> {
         self.compose_file = derived.path.to_string_lossy().into_owned();
         self.derived_dir = Some(derived.run_dir);
         self.proxy_services = derived.proxy_services;
@@ -233,25 +836,45 @@ impl ComposeRunner {
         self.egress_proxy = derived.egress_proxy;
         self.compose_args = strip_compose_file_args(&self.compose_args);
         self.compose_file_from_args = false;
+        derived.rewrites
     }
 
     fn ensure_traffic_hub(&mut self) -> Option<Arc<TrafficHub>> {
         if !self.traffic_enabled {
             return None;
         }
-        let hub = self
-            .traffic_hub
-            .get_or_insert_with(|| Arc::new(TrafficHub::new()));
+        let sampling = &self.traffic_sampling;
+        let hub = self.traffic_hub.get_or_insert_with(|| {
+            let hub = TrafficHub::new();
+            hub.set_sampling(sampling.clone());
+            Arc::new(hub)
+        });
         Some(hub.clone())
     }
 
+    fn ensure_log_hub(&mut self) -> Arc<LogHub> {
+        let window = self.log_tuning.reorder;
+        let dedup = self.log_tuning.dedup;
+        let skew = self.log_tuning.skew;
+        let file_sink = self.file_sink.clone();
+        self.log_hub
+            .get_or_insert_with(|| {
+                let hub = LogHub::with_reorder(HISTORY_LIMIT, window);
+                hub.set_dedup(dedup);
+                hub.set_clock_skew(skew);
+                hub.set_annotation_sink(file_sink);
+                hub
+            })
+            .clone()
+    }
+
     pub fn signal_context(&self) -> SignalContext {
-        SignalContext {
-            stop_event: self.stop_event.clone(),
-            signal_handled: self.signal_handled.clone(),
-            exit_code: self.exit_code.clone(),
-            handles: self.handles.clone(),
-        }
+        SignalContext::new(
+            self.stop_event.clone(),
+            self.signal_handled.clone(),
+            self.exit_code.clone(),
+            self.handles.clone(),
+        )
     }
 
     pub fn signal_exit_code(&self) -> i32 {
@@ -264,59 +887,162 @@ impl ComposeRunner {
         }
         self.cleanup_done = true;
         self.stop_event.store(true, Ordering::SeqCst);
+        self.container_watch.stop();
         self.handles.stop_log_procs();
         self.handles.stop_compose_proc();
-        if let Some(handle) = self.log_follow_thread.take() {
-            let _ = handle.join();
+        self.threads.join();
+        if let Some(task) = self.report.verify_task.take() {
+            self.report.verification_failed = !task.finish();
         }
-        for handle in self.log_threads.drain(..) {
-            let _ = handle.join();
-        }
-        for handle in self.traffic_threads.drain(..) {
-            let _ = handle.join();
+        if self.report.summary_enabled && !self.handles.was_forced() {
+            self.report_summary();
         }
         if let Some(server) = self.ui_server.as_mut() {
             server.stop();
         }
         self.ui_server = None;
+        if self.handles.was_forced() {
+            self.report_skipped_cleanup();
+            return;
+        }
         if self.cleanup_enabled {
-            self.engine.cleanup_project(&CleanupContext {
+            self.snapshot_containers();
+            let cleanup = self.engine.cleanup_project(&CleanupContext {
                 compose_cmd: &self.compose_cmd,
                 compose_file: &self.compose_file,
+                run_id: &self.run_id,
                 project_name: &self.project_name,
                 project_args: &self.project_args,
+                keep_volumes: self.keep_volumes,
             });
+            if let Err(err) = cleanup {
+                eprintln!("[compose] cleanup failed: {err}");
+            }
         }
         if let Some(dir) = self.derived_dir.take().filter(|_| !self.retain_run_dir) {
-            if let Err(err) = fs::remove_dir_all(&dir) {
-                eprintln!("[compose] cleanup failed: {err}");
+            match remove_run_dir(&dir) {
+                Ok(true) => eprintln!(
+                    "[compose] kept crash evidence in {}",
+                    dir.join(CRASH_DIR).display()
+                ),
+                Ok(false) => {}
+                Err(err) => eprintln!("[compose] cleanup failed: {err}"),
             }
         }
     }
 
+    fn report_summary(&self) {
+        let ids = self
+            .engine
+            .collect_run_container_ids(&self.run_id, Scope::All);
+        let log_lines = self
+            .log_hub
+            .as_ref()
+            .map(|hub| hub.line_counts())
+            .unwrap_or_default();
+        let containers = self.engine.inspect_containers(&ids);
+        let instances = scaled_instances(&containers, &self.service_aliases);
+        let mut services: Vec<ServiceSummary> = containers
+            .into_iter()
+            .filter(|info| !info.is_proxy())
+            .filter_map(|info| {
+                let service = info.service.clone()?;
+                let service = self.service_aliases.get(&service).unwrap_or(&service);
+                let service = instance_identity(service, instances.get(&info.id).copied());
+                Some(ServiceSummary {
+                    log_lines: log_lines.get(&service).copied().unwrap_or(0),
+                    state: info.display_state(),
+                    exit_code: info
+                        .exit_code
+                        .filter(|_| info.state.as_deref() == Some("exited")),
+                    restarts: info.restart_count,
+                    service,
+                })
+            })
+            .collect();
+        services.sort_by(|a, b| a.service.cmp(&b.service));
+        let hub = self.traffic_hub.as_deref();
+        let edges = hub.map(TrafficHub::edges).unwrap_or_default();
+        let summary = RunSummary {
+            run_id: self.run_id.clone(),
+            services,
+            requests: hub.map(TrafficHub::status_classes).unwrap_or_default(),
+            suppressed: hub.map(TrafficHub::suppressed).unwrap_or_default(),
+            slowest: slowest_endpoints(&edges, SLOWEST_ENDPOINT_LIMIT),
+            external_hosts: external_hosts(&edges),
+        };
+        for line in render_summary(&summary) {
+            eprintln!("{line}");
+        }
+        if let Some(sink) = self.file_sink.as_ref() {
+            let path = sink.run_dir().join("summary.json");
+            let written = serde_json::to_vec_pretty(&summary)
+                .map_err(|err| err.to_string())
+                .and_then(|payload| fs::write(&path, payload).map_err(|err| err.to_string()));
+            if let Err(err) = written {
+                eprintln!("[compose] failed to write {}: {err}", path.display());
+            }
+        }
+    }
+
+    fn report_skipped_cleanup(&self) {
+        if self.cleanup_enabled {
+            eprintln!(
+                "[compose] force quit: skipped compose down; containers of run {} may still be running (stop them with `{BIN_NAME} down {}`)",
+                self.run_id, self.run_id
+            );
+        }
+        if let Some(dir) = self.derived_dir.as_ref().filter(|_| !self.retain_run_dir) {
+            eprintln!("[compose] force quit: kept run dir {}", dir.display());
+        }
+    }
+
     pub fn run(&mut self) -> i32 {
         let subcommand_plan = match self.prepare_subcommand() {
             Ok(values) => values,
             Err(code) => return code,
         };
 
-        if let Err(err) = self.prepare_derived_compose() {
+        if let Err(err) = self.prepare_derived_compose(&subcommand_plan.name) {
             eprintln!("[compose] derive failed: {err}");
             return 1;
         }
         self.apply_defaults(&subcommand_plan);
-        let follow_plan = self.prepare_follow_plan(&subcommand_plan.name);
+        self.start_verifier(&subcommand_plan.name);
+        self.start_alerts(&subcommand_plan.name);
+        self.start_traffic_logs(&subcommand_plan.name);
+        self.start_events(&subcommand_plan.name);
+        let wait = self.take_wait_options(&subcommand_plan.name);
+        self.report.summary_enabled =
+            subcommand_plan.name == "up" && !has_flag(&self.compose_args, &["-d", "--detach"]);
+        let follow_plan = match self.prepare_follow_plan(&subcommand_plan.name) {
+            Ok(plan) => plan,
+            Err(err) => {
+                eprintln!("[compose] {err}");
+                return 2;
+            }
+        };
+        self.start_watch_thread();
         self.maybe_cleanup_before_up(&subcommand_plan.name);
 
-        if let Some(exit_code) = self.run_no_cache_build(&subcommand_plan) {
+        if let Some(exit_code) = self.run_build_panes(&subcommand_plan) {
             return exit_code;
         }
 
-        let exit_code = self.run_compose(&self.compose_args);
+        let exit_code = self.run_watched_compose(&subcommand_plan.name, &follow_plan);
         if exit_code != 0 {
             eprintln!("[compose] command failed with exit code {exit_code}");
+            if subcommand_plan.name == "up" && exit_code != STALL_EXIT_CODE {
+                self.report_compose_failure();
+            }
             return exit_code;
         }
+        if let Some(wait) = wait {
+            let exit_code = wait_for_services(&self.engine, &self.run_id, &wait, &self.stop_event);
+            if exit_code != 0 {
+                return exit_code;
+            }
+        }
 
         if let Some(exit_code) = self.maybe_follow_logs(&follow_plan, &subcommand_plan.name) {
             return exit_code;
@@ -339,6 +1065,13 @@ impl ComposeRunner {
             let (updated, force_recreate_requested) =
                 take_flag(&self.compose_args, "--force-recreate");
             self.compose_args = updated;
+            let (updated, watch_requested) = take_flag(&self.compose_args, "--watch");
+            self.compose_args = updated;
+            if watch_requested && has_flag(&self.compose_args, &["-d", "--detach"]) {
+                eprintln!("[compose] --watch needs a foreground run; ignoring it with --detach.");
+            } else {
+                self.watch_enabled = watch_requested;
+            }
             (no_cache_requested, force_recreate_requested)
         } else {
             (false, false)
@@ -351,7 +1084,8 @@ impl ComposeRunner {
     }
 
     fn apply_defaults(&mut self, plan: &SubcommandPlan) {
-        self.project_args.clear();
+        self.project_args =
+            legacy_project_args(&self.compose_cmd, self.legacy_compose, &self.compose_args);
 
         if plan.name == "up" {
             if !plan.no_cache_requested
@@ -385,7 +1119,7 @@ impl ComposeRunner {
         }
     }
 
-    fn prepare_follow_plan(&mut self, subcommand: &str) -> FollowPlan {
+    fn prepare_follow_plan(&mut self, subcommand: &str) -> Result<FollowPlan, String> {
         let user_no_start_requested = has_flag(&self.compose_args, &["--no-start"]);
 
         let detach_requested = has_flag(&self.compose_args, &["-d", "--detach"]);
@@ -393,7 +1127,7 @@ impl ComposeRunner {
             && !detach_requested
             && (!is_env_false("COMPOSE_LOG_UI") || self.traffic_enabled);
         if ui_enabled {
-            self.start_ui();
+            self.start_ui()?;
         }
 
         let manual_log_follow = self.engine.manual_log_follow(subcommand, detach_requested);
@@ -413,6 +1147,7 @@ impl ComposeRunner {
         if self.cleanup_enabled && self.engine.supports_watchdog() {
             self.start_watchdog();
         }
+        self.plan_shutdown(subcommand);
 
         let mut follow_in_thread = false;
         if log_follow_enabled && subcommand == "up" {
@@ -431,11 +1166,11 @@ impl ComposeRunner {
             self.start_traffic_follow_thread();
         }
 
-        FollowPlan {
+        Ok(FollowPlan {
             log_follow_enabled,
             emit_stdout,
             follow_in_thread,
-        }
+        })
     }
 
     fn maybe_cleanup_before_up(&self, subcommand: &str) {
@@ -445,16 +1180,29 @@ impl ComposeRunner {
         let running_ids = self
             .engine
             .collect_run_container_ids(&self.run_id, Scope::Running);
+        if !running_ids.is_empty() {
+            return;
+        }
         let all_ids = self
             .engine
             .collect_run_container_ids(&self.run_id, Scope::All);
-        if running_ids.is_empty() && !all_ids.is_empty() {
-            self.engine.cleanup_project(&CleanupContext {
+        let leftover = self
+            .engine
+            .inspect_containers(&all_ids)
+            .iter()
+            .any(|info| !info.is_completed_job());
+        if leftover {
+            let cleanup = self.engine.cleanup_project(&CleanupContext {
                 compose_cmd: &self.compose_cmd,
                 compose_file: &self.compose_file,
+                run_id: &self.run_id,
                 project_name: &self.project_name,
                 project_args: &self.project_args,
+                keep_volumes: self.keep_volumes,
             });
+            if let Err(err) = cleanup {
+                eprintln!("[compose] cleanup failed: {err}");
+            }
         }
     }
 
@@ -471,39 +1219,174 @@ impl ComposeRunner {
 
     fn maybe_follow_logs(&mut self, plan: &FollowPlan, subcommand: &str) -> Option<i32> {
         if plan.log_follow_enabled && subcommand == "up" && !plan.follow_in_thread {
+            if plan.emit_stdout
+                && !(self.log_tuning.reorder.is_zero() && self.log_tuning.dedup.is_zero())
+            {
+                self.ensure_log_hub();
+            }
             let follower = self.log_follower();
-            return Some(follower.follow_logs(plan.emit_stdout, &mut self.log_threads));
+            return Some(follower.follow_logs(plan.emit_stdout, &mut self.threads.logs));
         }
         None
     }
 
+    fn plan_shutdown(&self, subcommand: &str) {
+        if subcommand != "up" || !self.cleanup_enabled || self.shutdown_order == ShutdownOrder::None
+        {
+            return;
+        }
+        self.handles.set_shutdown_plan(ShutdownPlan {
+            engine: self.engine.clone(),
+            run_id: self.run_id.clone(),
+            tiers: shutdown_tiers(&build_dependency_graph(&self.original_compose_file)),
+        });
+    }
+
+    fn snapshot_containers(&self) {
+        if let Some(dir) = self.derived_dir.as_deref() {
+            inspect::write_snapshot(&self.engine, &self.run_id, dir, "down");
+        }
+    }
+
     fn maybe_cleanup_after(&self, subcommand: &str) {
         if subcommand == "down" || subcommand == "stop" {
-            self.engine.cleanup_project(&CleanupContext {
+            self.snapshot_containers();
+            let cleanup = self.engine.cleanup_project(&CleanupContext {
                 compose_cmd: &self.compose_cmd,
                 compose_file: &self.compose_file,
+                run_id: &self.run_id,
                 project_name: &self.project_name,
                 project_args: &self.project_args,
+                keep_volumes: self.keep_volumes,
             });
+            if let Err(err) = cleanup {
+                eprintln!("[compose] cleanup failed: {err}");
+            }
+        }
+    }
+
+    fn compose_command(&self, args: &[String]) -> Option<Command> {
+        let Some((compose_bin, compose_args)) = self.compose_cmd.split_first() else {
+            eprintln!("[compose] compose command is empty");
+            return None;
+        };
+        let mut cmd = Command::new(compose_bin);
+        cmd.args(compose_args);
+        if !self.compose_file_from_args {
+            cmd.arg("-f").arg(&self.compose_file);
+        }
+        cmd.args(&self.project_args).args(args);
+        cmd.env_remove("COMPOSE_PROJECT_NAME");
+        Some(cmd)
+    }
+
+    fn run_watched_compose(&mut self, subcommand: &str, follow_plan: &FollowPlan) -> i32 {
+        if subcommand != "up" {
+            return self.run_main_compose(subcommand, follow_plan);
+        }
+        let mut attempt = 0;
+        loop {
+            let target = StallTarget {
+                engine: &self.engine,
+                run_id: &self.run_id,
+                services: self.service_info.len(),
+                log_hub: self.log_hub.clone(),
+            };
+            self.stall.watch = StallWatch::start(self.stall.policy, target);
+            let exit_code = self.run_main_compose(subcommand, follow_plan);
+            if !self.stall.watch.take().is_some_and(|watch| watch.stalled()) {
+                return exit_code;
+            }
+            if self.stall.policy.action != StallAction::Retry || attempt >= STALL_RETRIES {
+                eprintln!("[compose] aborted the stalled `up`");
+                return STALL_EXIT_CODE;
+            }
+            attempt += 1;
+            eprintln!("[compose] retrying the stalled `up` ({attempt}/{STALL_RETRIES})");
+        }
+    }
+
+    fn run_main_compose(&self, subcommand: &str, follow_plan: &FollowPlan) -> i32 {
+        let up = subcommand == "up";
+        // On a terminal compose keeps its own TTY progress display and prompts.
+        let logged = up && !follow_plan.follow_in_thread && !io::stdout().is_terminal();
+        if up {
+            self.compose_stderr.clear();
+        }
+        if logged {
+            self.run_logged_compose(&self.compose_args)
+        } else if up {
+            self.run_captured_compose(&self.compose_args)
+        } else {
+            self.run_compose(&self.compose_args)
+        }
+    }
+
+    fn run_compose(&self, args: &[String]) -> i32 {
+        let Some(mut cmd) = self.compose_command(args) else {
+            return 1;
+        };
+        cmd.stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit());
+        let child = match spawn_process_group(&mut cmd) {
+            Ok(child) => child,
+            Err(err) => {
+                eprintln!("[compose] failed to start compose: {err}");
+                return 1;
+            }
+        };
+        self.wait_compose(child)
+    }
+
+    fn run_captured_compose(&self, args: &[String]) -> i32 {
+        let Some(mut cmd) = self.compose_command(args) else {
+            return 1;
+        };
+        cmd.stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::piped());
+        let mut child = match spawn_process_group(&mut cmd) {
+            Ok(child) => child,
+            Err(err) => {
+                eprintln!("[compose] failed to start compose: {err}");
+                return 1;
+            }
+        };
+        let tee = child.stderr.take().map(|stderr| {
+            let mut reader = TeeReader::new(stderr, self.compose_stderr.clone());
+            thread::spawn(move || io::copy(&mut reader, &mut io::stderr()))
+        });
+        let exit_code = self.wait_compose(child);
+        if let Some(tee) = tee {
+            let _ = tee.join();
+        }
+        exit_code
+    }
+
+    fn report_compose_failure(&self) {
+        let failures = diagnose(
+            &self.compose_stderr.lines(),
+            &self.project_name,
+            &self.service_aliases,
+        );
+        if failures.is_empty() {
+            return;
+        }
+        eprintln!("[compose] up failed:");
+        for failure in failures {
+            eprintln!("[compose]   {}", failure.summary());
         }
     }
 
-    fn run_compose(&self, args: &[String]) -> i32 {
-        let Some((compose_bin, compose_args)) = self.compose_cmd.split_first() else {
-            eprintln!("[compose] compose command is empty");
+    fn run_logged_compose(&self, args: &[String]) -> i32 {
+        let Some(mut cmd) = self.compose_command(args) else {
             return 1;
         };
-        let mut cmd = Command::new(compose_bin);
-        cmd.args(compose_args);
-        if !self.compose_file_from_args {
-            cmd.arg("-f").arg(&self.compose_file);
-        }
-        cmd.args(&self.project_args)
-            .args(args)
-            .stdin(Stdio::inherit())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit());
-        cmd.env_remove("COMPOSE_PROJECT_NAME");
+        // A prompt would never show up in piped output, so do not wait on one.
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
         let child = match spawn_process_group(&mut cmd) {
             Ok(child) => child,
             Err(err) => {
@@ -511,10 +1394,70 @@ impl ComposeRunner {
                 return 1;
             }
         };
-        {
-            let mut proc = self.handles.compose_proc();
-            *proc = Some(child);
-        }
+        let (color_enabled, _) = LogFollower::log_settings(true);
+        let (color_prefix, color_reset) = if color_enabled {
+            (
+                format!("\u{1b}[{COMPOSE_PANE_COLOR}m"),
+                "\u{1b}[0m".to_string(),
+            )
+        } else {
+            (String::new(), String::new())
+        };
+        let pane = StdoutFormat {
+            prefix: COMPOSE_PANE.to_string(),
+            color_prefix,
+            color_reset,
+        };
+        self.wait_logged_compose(child, self.log_hub.as_ref(), &pane)
+    }
+
+    fn wait_logged_compose(
+        &self,
+        mut child: Child,
+        hub: Option<&Arc<LogHub>>,
+        pane: &StdoutFormat,
+    ) -> i32 {
+        let readers: Vec<(OutputStream, Box<dyn Read + Send>)> = [
+            child
+                .stdout
+                .take()
+                .map(|out| (OutputStream::Stdout, Box::new(out) as Box<dyn Read + Send>)),
+            child.stderr.take().map(|err| {
+                let err = TeeReader::new(err, self.compose_stderr.clone());
+                (OutputStream::Stderr, Box::new(err) as Box<dyn Read + Send>)
+            }),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let workers: Vec<_> = readers
+            .into_iter()
+            .map(|(stream, reader)| {
+                let hub = hub.cloned();
+                let stop_event = self.stop_event.clone();
+                let config = LogWorkerConfig {
+                    service: pane.prefix.clone(),
+                    stream,
+                    prefix: pane.prefix.clone(),
+                    color_prefix: pane.color_prefix.clone(),
+                    color_reset: pane.color_reset.clone(),
+                    emit_stdout: true,
+                    file_sink: self.file_sink.clone(),
+                    log_sink: self.log_sink.clone(),
+                    filter: None,
+                };
+                thread::spawn(move || log_worker(reader, hub.as_ref(), &stop_event, config))
+            })
+            .collect();
+        let exit_code = self.wait_compose(child);
+        for worker in workers {
+            let _ = worker.join();
+        }
+        exit_code
+    }
+
+    fn wait_compose(&self, child: Child) -> i32 {
+        self.handles.set_compose_proc(child);
         loop {
             let Ok(finished) = self.try_wait_compose() else {
                 return 1;
@@ -525,10 +1468,73 @@ impl ComposeRunner {
             if self.stop_event.load(Ordering::SeqCst) {
                 return 1;
             }
+            if self.stall.watch.as_ref().is_some_and(StallWatch::check) {
+                self.stop_compose();
+                return STALL_EXIT_CODE;
+            }
             thread::sleep(Duration::from_millis(100));
         }
     }
 
+    fn stop_compose(&self) {
+        if let Some(child) = self.handles.compose_proc().as_mut() {
+            terminate_process(child, Duration::from_secs(5));
+        }
+    }
+
+    fn run_build_panes(&mut self, plan: &SubcommandPlan) -> Option<i32> {
+        let build_requested = plan.no_cache_requested || has_flag(&self.compose_args, &["--build"]);
+        let hub = self
+            .log_hub
+            .clone()
+            .filter(|_| plan.name == "up" && build_requested);
+        let Some(hub) = hub else {
+            return self.run_no_cache_build(plan);
+        };
+        let targets = match load_watch_targets(Path::new(&self.compose_file)) {
+            Ok(targets) => targets,
+            Err(err) => {
+                eprintln!("[compose] build panes unavailable: {err}");
+                return self.run_no_cache_build(plan);
+            }
+        };
+        for target in targets {
+            let exit_code = self.run_service_build(&hub, &target.service, plan.no_cache_requested);
+            if exit_code != 0 {
+                eprintln!(
+                    "[compose] build of {} failed with exit code {exit_code}",
+                    target.service
+                );
+                return Some(exit_code);
+            }
+        }
+        self.compose_args = take_flag(&self.compose_args, "--build").0;
+        None
+    }
+
+    fn run_service_build(&self, hub: &Arc<LogHub>, service: &str, no_cache: bool) -> i32 {
+        let (args, pane) = build_pane(service, &self.service_aliases, no_cache);
+        let Some(mut cmd) = self.compose_command(&args) else {
+            return 1;
+        };
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let child = match spawn_process_group(&mut cmd) {
+            Ok(child) => child,
+            Err(err) => {
+                eprintln!("[compose] failed to start build for {service}: {err}");
+                return 1;
+            }
+        };
+        let pane = StdoutFormat {
+            prefix: pane,
+            color_prefix: String::new(),
+            color_reset: String::new(),
+        };
+        self.wait_logged_compose(child, Some(hub), &pane)
+    }
+
     fn try_wait_compose(&self) -> Result<Option<ExitStatus>, ()> {
         let status = self
             .handles
@@ -541,32 +1547,72 @@ impl ComposeRunner {
         Ok(status)
     }
 
-    fn start_ui(&mut self) {
+    fn start_ui(&mut self) -> Result<(), String> {
+        if let Some(endpoint) = register_with_daemon(&self.run_id) {
+            if self.ui_bind.share.is_some() {
+                eprintln!("[compose] --share is ignored while `sanelens ui` serves this run");
+            }
+            self.announce_ui(&endpoint);
+            return Ok(());
+        }
         let traffic_hub = self.ensure_traffic_hub();
-        let log_hub = self
-            .log_hub
-            .get_or_insert_with(|| Arc::new(LogHub::new(HISTORY_LIMIT)));
-        match UiServer::start(
-            log_hub.clone(),
-            self.service_info.clone(),
+        let run = UiRun {
+            log_hub: self.ensure_log_hub(),
+            service_info: Arc::new(self.service_info.clone()),
+            endpoint_probes: Some(self.endpoint_probes.clone()),
+            forwards: Arc::default(),
+            service_graph: Arc::new(build_dependency_graph(&self.original_compose_file)),
+            service_config: Arc::new(load_effective_config(self.derived_dir.as_deref())),
+            alert_hub: self.hooks.alert_hub.clone(),
             traffic_hub,
-            self.stop_event.clone(),
-        ) {
+            containers: Arc::new(RunContainers::new(self.engine.clone(), self.run_id.clone())),
+        };
+        match UiServer::start(run, self.stop_event.clone(), &self.ui_bind) {
             Ok(server) => {
-                let port = server.port();
+                self.announce_ui(&server.endpoint());
                 self.ui_server = Some(server);
-                let url = format!("http://127.0.0.1:{port}/");
-                let _ = writeln!(std::io::stdout(), "[compose] log UI: {url}");
-                open_browser(&url);
+            }
+            Err(err) if err.kind() == io::ErrorKind::AddrInUse => {
+                return Err(format!("log UI failed: {err}"));
             }
             Err(err) => {
                 eprintln!("[compose] log UI failed: {err}");
             }
         }
+        Ok(())
+    }
+
+    fn announce_ui(&self, endpoint: &UiEndpoint) {
+        let _ = writeln!(std::io::stdout(), "[compose] log UI: {}", endpoint.url);
+        if let Some(dir) = self.derived_dir.as_ref() {
+            let path = dir.join(UI_FILE);
+            let payload = serde_json::to_vec_pretty(endpoint).unwrap_or_default();
+            if let Err(err) = fs::write(&path, payload) {
+                eprintln!("[compose] failed to write {}: {err}", path.display());
+            }
+        }
+        self.open_browser_when_ready(endpoint.url.clone());
+    }
+
+    fn open_browser_when_ready(&self, url: String) {
+        let engine = self.engine.clone();
+        let run_id = self.run_id.clone();
+        let stop_event = self.stop_event.clone();
+        let timeout = env_or_default(BROWSER_READY_TIMEOUT_ENV)
+            .and_then(|value| parse_duration(&value))
+            .unwrap_or(BROWSER_READY_TIMEOUT);
+        let probes = self.endpoint_probes.clone();
+        let services = self.service_info.clone();
+        thread::spawn(move || {
+            open_browser_after_readiness(&engine, &run_id, &stop_event, &url, timeout);
+            if !stop_event.load(Ordering::SeqCst) {
+                probes.refresh(&services);
+            }
+        });
     }
 
     fn start_log_follow_thread(&mut self, emit_stdout: bool) {
-        if self.log_follow_thread.is_some() {
+        if self.threads.log_follow.is_some() {
             return;
         }
         let follower = self.log_follower();
@@ -574,11 +1620,47 @@ impl ComposeRunner {
             let mut log_threads = Vec::new();
             follower.follow_logs(emit_stdout, &mut log_threads)
         });
-        self.log_follow_thread = Some(handle);
+        self.threads.log_follow = Some(handle);
+    }
+
+    fn start_watch_thread(&mut self) {
+        if !self.watch_enabled || self.threads.watch.is_some() {
+            return;
+        }
+        let targets = match load_watch_targets(Path::new(&self.compose_file)) {
+            Ok(targets) => targets,
+            Err(err) => {
+                eprintln!("[compose] watch disabled: {err}");
+                return;
+            }
+        };
+        let develop =
+            load_develop_watch_services(Path::new(&self.compose_file)).unwrap_or_default();
+        let watcher = Watcher::new(
+            targets
+                .into_iter()
+                .filter(|target| !develop.contains(&target.service))
+                .collect(),
+        );
+        if watcher.is_empty() && develop.is_empty() {
+            eprintln!("[compose] watch disabled: no services with a local build context.");
+            return;
+        }
+        let mut compose = self.compose_cmd.clone();
+        compose.push("-f".to_string());
+        compose.push(self.compose_file.clone());
+        compose.extend(self.project_args.iter().cloned());
+        let rebuilder = WatchRebuilder {
+            watcher,
+            develop,
+            compose,
+            stop_event: self.stop_event.clone(),
+        };
+        self.threads.watch = Some(thread::spawn(move || rebuilder.run()));
     }
 
     fn start_traffic_follow_thread(&mut self) {
-        if !self.traffic_enabled || !self.traffic_threads.is_empty() {
+        if !self.traffic_enabled || !self.threads.traffic.is_empty() {
             return;
         }
         let Some(follower) = self.traffic_follower() else {
@@ -587,7 +1669,7 @@ impl ComposeRunner {
         let handle = thread::spawn(move || {
             let _ = follower.follow();
         });
-        self.traffic_threads.push(handle);
+        self.threads.traffic.push(handle);
     }
 
     fn log_follower(&self) -> LogFollower {
@@ -598,8 +1680,15 @@ impl ComposeRunner {
             stop_event: self.stop_event.clone(),
             log_hub: self.log_hub.clone(),
             handles: self.handles.clone(),
+            container_watch: self.container_watch.clone(),
             proxy_services: self.proxy_services.clone(),
             service_aliases: self.service_aliases.clone(),
+            file_sink: self.file_sink.clone(),
+            log_sink: self.log_sink.clone(),
+            filter: None,
+            since: None,
+            // Containers that join later (replicas, `sanelens run` one-offs) get followed too.
+            reattach: true,
         }
     }
 
@@ -619,11 +1708,13 @@ impl ComposeRunner {
             project_name: self.project_name.clone(),
             stop_event: self.stop_event.clone(),
             handles: self.handles.clone(),
+            container_watch: self.container_watch.clone(),
             hub,
             proxy_services: self.proxy_services.clone(),
             service_aliases: self.service_aliases.clone(),
             egress_proxy: self.egress_proxy.clone(),
             tap_dir,
+            dns_cache: Arc::default(),
         })
     }
 
@@ -645,6 +1736,9 @@ impl ComposeRunner {
         if let Some(conn) = self.engine.connection() {
             cmd.arg(conn);
         }
+        if self.keep_volumes {
+            cmd.env(KEEP_VOLUMES_ENV, "1");
+        }
         if let Ok(child) = spawn_process_group(&mut cmd) {
             self.watchdog_proc = Some(child);
         }
@@ -658,8 +1752,14 @@ pub struct LogFollower {
     stop_event: Arc<AtomicBool>,
     log_hub: Option<Arc<LogHub>>,
     handles: Arc<ProcessHandles>,
+    container_watch: Arc<ContainerWatch>,
     proxy_services: HashSet<String>,
     service_aliases: HashMap<String, String>,
+    file_sink: Option<Arc<LogFileSink>>,
+    log_sink: Option<Arc<LogSink>>,
+    filter: Option<Arc<LogFilter>>,
+    since: Option<i64>,
+    reattach: bool,
 }
 
 impl LogFollower {
@@ -674,6 +1774,8 @@ impl LogFollower {
         proxy_services: HashSet<String>,
         service_aliases: HashMap<String, String>,
     ) -> Self {
+        let container_watch =
+            ContainerWatch::new(engine.clone(), run_id.clone(), stop_event.clone());
         Self {
             engine,
             run_id,
@@ -681,11 +1783,38 @@ impl LogFollower {
             stop_event,
             log_hub,
             handles,
+            container_watch,
             proxy_services,
             service_aliases,
+            file_sink: None,
+            log_sink: None,
+            filter: None,
+            since: None,
+            reattach: false,
         }
     }
 
+    pub fn set_file_sink(&mut self, sink: Option<Arc<LogFileSink>>) {
+        self.file_sink = sink;
+    }
+
+    pub fn set_log_sink(&mut self, sink: Option<Arc<LogSink>>) {
+        self.log_sink = sink;
+    }
+
+    pub fn container_watch(&self) -> Arc<ContainerWatch> {
+        self.container_watch.clone()
+    }
+
+    pub fn set_container_watch(&mut self, watch: Arc<ContainerWatch>) {
+        self.container_watch = watch;
+    }
+
+    pub fn set_filter(&mut self, filter: LogFilter, since: Option<i64>) {
+        self.filter = Some(Arc::new(filter));
+        self.since = since;
+    }
+
     pub fn follow_logs(
         &self,
         emit_stdout: bool,
@@ -701,20 +1830,29 @@ impl LogFollower {
             emit_stdout,
             color_enabled,
             timestamps_enabled,
+            max_len,
         };
-        self.spawn_log_threads(services, max_len, options, log_threads);
+        let mut service_colors = HashMap::new();
+        self.spawn_log_threads(services, options, &mut service_colors, log_threads);
+        if self.reattach {
+            self.reattach_logs(ids, options, &mut service_colors, log_threads);
+        }
 
         for handle in log_threads.drain(..) {
             let _ = handle.join();
         }
+        if let Some(hub) = self.log_hub.as_ref() {
+            hub.flush();
+        }
         0
     }
 
     fn collect_services(&self, ids: &[String]) -> (Vec<(String, String)>, usize) {
         let mut services = Vec::new();
         let mut max_len = 0;
-        for cid in ids {
-            let service = self.engine.resolve_service_name(&self.project_name, cid);
+        let names = self.engine.resolve_service_names(&self.project_name, ids);
+        let instances = self.replica_instances();
+        for (cid, service) in ids.iter().zip(names) {
             if self.proxy_services.contains(&service) {
                 continue;
             }
@@ -723,12 +1861,31 @@ impl LogFollower {
                 .get(&service)
                 .cloned()
                 .unwrap_or(service);
+            if !self
+                .filter
+                .as_ref()
+                .is_none_or(|filter| filter.includes_service(&service))
+            {
+                continue;
+            }
+            let instance = instances
+                .iter()
+                .find(|(id, _)| id.starts_with(cid.as_str()))
+                .map(|(_, instance)| *instance);
+            let service = instance_identity(&service, instance);
             max_len = max_len.max(service.len());
             services.push((cid.clone(), service));
         }
         (services, max_len)
     }
 
+    fn replica_instances(&self) -> HashMap<String, u32> {
+        let ids = self
+            .engine
+            .collect_run_container_ids(&self.run_id, Scope::All);
+        scaled_instances(&self.engine.inspect_containers(&ids), &self.service_aliases)
+    }
+
     fn log_settings(emit_stdout: bool) -> (bool, bool) {
         let mut color_enabled = emit_stdout;
         let mut timestamps_enabled = true;
@@ -744,24 +1901,54 @@ impl LogFollower {
         (color_enabled, timestamps_enabled)
     }
 
+    fn reattach_logs(
+        &self,
+        ids: Vec<String>,
+        options: LogThreadOptions,
+        service_colors: &mut HashMap<String, u8>,
+        log_threads: &mut Vec<thread::JoinHandle<()>>,
+    ) {
+        let mut seen: HashSet<String> = ids.into_iter().collect();
+        let mut generation = self.container_watch.generation();
+        while !self.stop_event.load(Ordering::SeqCst) {
+            generation = self
+                .container_watch
+                .wait(generation, Duration::from_secs(1));
+            self.handles.prune_log_procs();
+            prune_finished_threads(log_threads);
+            let running = self
+                .engine
+                .collect_run_container_ids(&self.run_id, Scope::Running);
+            if running.is_empty() && log_threads.is_empty() {
+                break;
+            }
+            let fresh: Vec<String> = running
+                .into_iter()
+                .filter(|id| seen.insert(id.clone()))
+                .collect();
+            if !fresh.is_empty() {
+                let (services, _) = self.collect_services(&fresh);
+                self.spawn_log_threads(services, options, service_colors, log_threads);
+            }
+        }
+    }
+
     fn spawn_log_threads(
         &self,
         services: Vec<(String, String)>,
-        max_len: usize,
         options: LogThreadOptions,
+        service_colors: &mut HashMap<String, u8>,
         log_threads: &mut Vec<thread::JoinHandle<()>>,
     ) {
         let colors = [31, 32, 33, 34, 35, 36, 91, 92, 93, 94, 95, 96];
-        let mut service_colors = HashMap::new();
-        let mut color_index = 0;
+        let max_len = options.max_len;
         for (cid, service) in services {
+            let color_index = service_colors.len();
             let color_code = *service_colors.entry(service.clone()).or_insert_with(|| {
-                let code = colors
+                colors
                     .get(color_index % colors.len())
                     .copied()
-                    .unwrap_or(37);
-                color_index += 1;
-                code
+                    .unwrap_or(37)
             });
             let prefix = format!("{service:<max_len$}");
             let (color_prefix, color_reset) = if options.color_enabled {
@@ -769,39 +1956,37 @@ impl LogFollower {
             } else {
                 (String::new(), String::new())
             };
-            let log_cmd = self.engine.logs_cmd(&cid, options.timestamps_enabled);
-            let Some((log_bin, log_args)) = log_cmd.split_first() else {
+            let Ok(stream) = self
+                .engine
+                .logs(&cid, options.timestamps_enabled, self.since)
+            else {
                 continue;
             };
-            let mut command = Command::new(log_bin);
-            command
-                .args(log_args)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped());
-            let Ok(mut child) = spawn_process_group(&mut command) else {
-                continue;
+            self.handles.add_log_proc(stream.handle);
+
+            let config = LogWorkerConfig {
+                service: service.clone(),
+                stream: OutputStream::Stdout,
+                prefix: prefix.clone(),
+                color_prefix: color_prefix.clone(),
+                color_reset: color_reset.clone(),
+                emit_stdout: options.emit_stdout,
+                file_sink: self.file_sink.clone(),
+                log_sink: self.log_sink.clone(),
+                filter: self.filter.clone(),
             };
-            let stdout = child.stdout.take();
-            let stderr = child.stderr.take();
-            self.handles.log_procs().push(child);
-
-            if let Some(stdout) = stdout {
-                let config = LogWorkerConfig {
-                    service: service.clone(),
-                    prefix: prefix.clone(),
-                    color_prefix: color_prefix.clone(),
-                    color_reset: color_reset.clone(),
-                    emit_stdout: options.emit_stdout,
-                };
-                self.spawn_log_worker(stdout, config, log_threads);
-            }
-            if let Some(stderr) = stderr {
+            self.spawn_log_worker(stream.stdout, config, log_threads);
+            if let Some(stderr) = stream.stderr {
                 let config = LogWorkerConfig {
                     service: service.clone(),
+                    stream: OutputStream::Stderr,
                     prefix: prefix.clone(),
                     color_prefix: color_prefix.clone(),
                     color_reset: color_reset.clone(),
                     emit_stdout: options.emit_stdout,
+                    file_sink: self.file_sink.clone(),
+                    log_sink: self.log_sink.clone(),
+                    filter: self.filter.clone(),
                 };
                 self.spawn_log_worker(stderr, config, log_threads);
             }
@@ -823,6 +2008,7 @@ impl LogFollower {
     }
 
     fn wait_for_container_ids(&self) -> Vec<String> {
+        let mut generation = self.container_watch.generation();
         while !self.stop_event.load(Ordering::SeqCst) {
             let ids = self
                 .engine
@@ -830,7 +2016,9 @@ impl LogFollower {
             if !ids.is_empty() {
                 return ids;
             }
-            thread::sleep(Duration::from_millis(500));
+            generation = self
+                .container_watch
+                .wait(generation, Duration::from_millis(500));
         }
         Vec::new()
     }
@@ -842,11 +2030,22 @@ pub struct TrafficFollower {
     project_name: String,
     stop_event: Arc<AtomicBool>,
     handles: Arc<ProcessHandles>,
+    container_watch: Arc<ContainerWatch>,
     hub: Arc<TrafficHub>,
     proxy_services: HashSet<String>,
     service_aliases: HashMap<String, String>,
     egress_proxy: Option<String>,
     tap_dir: Option<PathBuf>,
+    dns_cache: Arc<DnsCache>,
+}
+
+type HeldAccessLog = (EnvoyAccessLog, u64);
+type SharedFailover = Arc<TapCapture>;
+
+#[derive(Default)]
+struct TapCapture {
+    failover: Mutex<TapFailover<HeldAccessLog>>,
+    faults: FaultMarks,
 }
 
 #[derive(Clone)]
@@ -856,7 +2055,9 @@ struct TrafficWorkerContext {
     stop_event: Arc<AtomicBool>,
     service_name: String,
     is_egress: bool,
-    tap_enabled: bool,
+    tap: Option<SharedFailover>,
+    max_body_bytes: usize,
+    ignore: Arc<TrafficIgnore>,
 }
 
 #[derive(Clone)]
@@ -867,6 +2068,9 @@ struct TapWorkerContext {
     service_name: String,
     is_egress: bool,
     tap_dir: PathBuf,
+    failover: SharedFailover,
+    max_body_bytes: usize,
+    ignore: Arc<TrafficIgnore>,
 }
 
 impl TrafficFollower {
@@ -883,27 +2087,36 @@ impl TrafficFollower {
         egress_proxy: Option<String>,
         tap_dir: Option<PathBuf>,
     ) -> Self {
+        let container_watch =
+            ContainerWatch::new(engine.clone(), run_id.clone(), stop_event.clone());
         Self {
             engine,
             run_id,
             project_name,
             stop_event,
             handles,
+            container_watch,
             hub,
             proxy_services,
             service_aliases,
             egress_proxy,
             tap_dir,
+            dns_cache: Arc::default(),
         }
     }
 
+    pub fn set_container_watch(&mut self, watch: Arc<ContainerWatch>) {
+        self.container_watch = watch;
+    }
+
     pub fn follow(&self) -> i32 {
         if self.proxy_services.is_empty() {
             return 0;
         }
         let mut workers = Vec::new();
         let mut seen = HashSet::new();
-        let mut tap_seen = HashSet::new();
+        let mut failovers = HashMap::new();
+        let mut generation = self.container_watch.generation();
 
         while !self.stop_event.load(Ordering::SeqCst) {
             let ids = self
@@ -918,13 +2131,16 @@ impl TrafficFollower {
                     &self.engine,
                     &self.run_id,
                     &self.service_aliases,
+                    self.dns_cache.clone(),
                 ));
-                workers.extend(self.spawn_workers(&new_ids, &resolver, &mut tap_seen));
+                workers.extend(self.spawn_workers(&new_ids, &resolver, &mut failovers));
             }
-            self.prune_finished_log_procs();
-            Self::prune_finished_workers(&mut workers);
+            self.handles.prune_log_procs();
+            prune_finished_threads(&mut workers);
             if !self.stop_event.load(Ordering::SeqCst) {
-                thread::sleep(Duration::from_millis(250));
+                generation = self
+                    .container_watch
+                    .wait(generation, Duration::from_millis(250));
             }
         }
         for handle in workers {
@@ -933,66 +2149,52 @@ impl TrafficFollower {
         0
     }
 
-    fn prune_finished_log_procs(&self) {
-        let mut procs = self.handles.log_procs();
-        procs.retain_mut(|child| child.try_wait().ok().flatten().is_none());
-        drop(procs);
-    }
-
-    fn prune_finished_workers(workers: &mut Vec<thread::JoinHandle<()>>) {
-        let mut remaining = Vec::with_capacity(workers.len());
-        for handle in workers.drain(..) {
-            if handle.is_finished() {
-                let _ = handle.join();
-            } else {
-                remaining.push(handle);
-            }
-        }
-        *workers = remaining;
-    }
-
     fn spawn_workers(
         &self,
         ids: &[String],
         resolver: &Arc<RuntimeResolver>,
-        tap_seen: &mut HashSet<String>,
+        failovers: &mut HashMap<String, SharedFailover>,
     ) -> Vec<thread::JoinHandle<()>> {
         let mut workers = Vec::new();
-        for cid in ids {
-            let service = self.engine.resolve_service_name(&self.project_name, cid);
+        let names = self.engine.resolve_service_names(&self.project_name, ids);
+        let infos = self.engine.inspect_containers(ids);
+        for (cid, service) in ids.iter().zip(names) {
             let is_egress = self.egress_proxy.as_deref() == Some(&service);
-            let log_cmd = self.engine.logs_cmd(cid, false);
-            let Some((log_bin, log_args)) = log_cmd.split_first() else {
+            let labels = infos
+                .iter()
+                .find(|info| info.id.starts_with(cid.as_str()) || cid.starts_with(&info.id))
+                .map(|info| &info.labels);
+            let max_body_bytes = labels
+                .and_then(|labels| labels.get(MAX_BODY_BYTES_LABEL)?.parse().ok())
+                .unwrap_or(DEFAULT_MAX_BODY_BYTES);
+            let ignore = Arc::new(
+                labels
+                    .and_then(|labels| labels.get(TRAFFIC_IGNORE_LABEL))
+                    .map(|spec| TrafficIgnore::parse(spec))
+                    .unwrap_or_default(),
+            );
+            let Ok(stream) = self.engine.logs(cid, false, None) else {
                 continue;
             };
-            let mut command = Command::new(log_bin);
-            command
-                .args(log_args)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped());
-            let Ok(mut child) = spawn_process_group(&mut command) else {
-                continue;
-            };
-            let stdout = child.stdout.take();
-            let stderr = child.stderr.take();
-            self.handles.log_procs().push(child);
+            self.handles.add_log_proc(stream.handle);
+            let (tap, tap_dir) = self.tap_failover(&service, failovers).unzip();
             let context = TrafficWorkerContext {
                 hub: self.hub.clone(),
                 resolver: resolver.clone(),
                 stop_event: self.stop_event.clone(),
                 service_name: service.clone(),
                 is_egress,
-                tap_enabled: self.tap_dir.is_some(),
+                tap: tap.clone(),
+                max_body_bytes,
+                ignore: ignore.clone(),
             };
 
-            if let Some(stdout) = stdout {
-                Self::spawn_traffic_worker(stdout, context.clone(), &mut workers);
-            }
-            if let Some(stderr) = stderr {
+            Self::spawn_traffic_worker(stream.stdout, context.clone(), &mut workers);
+            if let Some(stderr) = stream.stderr {
                 Self::spawn_traffic_worker(stderr, context, &mut workers);
             }
 
-            if let Some(tap_dir) = self.tap_dir_for_service(&service, tap_seen) {
+            if let Some((tap_dir, failover)) = tap_dir.flatten().zip(tap) {
                 let tap_context = TapWorkerContext {
                     hub: self.hub.clone(),
                     resolver: resolver.clone(),
@@ -1000,6 +2202,9 @@ impl TrafficFollower {
                     service_name: service.clone(),
                     is_egress,
                     tap_dir,
+                    failover,
+                    max_body_bytes,
+                    ignore,
                 };
                 Self::spawn_tap_worker(tap_context, &mut workers);
             }
@@ -1018,16 +2223,18 @@ impl TrafficFollower {
         workers.push(thread);
     }
 
-    fn tap_dir_for_service(
+    fn tap_failover(
         &self,
         service_name: &str,
-        tap_seen: &mut HashSet<String>,
-    ) -> Option<PathBuf> {
+        failovers: &mut HashMap<String, SharedFailover>,
+    ) -> Option<(SharedFailover, Option<PathBuf>)> {
         let tap_root = self.tap_dir.as_ref()?;
-        if !tap_seen.insert(service_name.to_string()) {
-            return None;
+        if let Some(failover) = failovers.get(service_name) {
+            return Some((failover.clone(), None));
         }
-        Some(tap_root.join(service_name))
+        let failover = SharedFailover::default();
+        failovers.insert(service_name.to_string(), failover.clone());
+        Some((failover, Some(tap_root.join(service_name))))
     }
 
     fn spawn_tap_worker(context: TapWorkerContext, workers: &mut Vec<thread::JoinHandle<()>>) {
@@ -1038,6 +2245,164 @@ impl TrafficFollower {
     }
 }
 
+impl WatchRebuilder {
+    fn run(mut self) {
+        let mut develop = None;
+        let mut develop_pending = !self.develop.is_empty();
+        while !self.stop_event.load(Ordering::SeqCst) {
+            thread::sleep(Duration::from_secs(1));
+            if develop_pending && self.develop_containers_running() {
+                develop_pending = false;
+                develop = self.start_develop_watch();
+            }
+            if let Some(status) = develop
+                .as_mut()
+                .and_then(|child| child.try_wait().ok().flatten())
+            {
+                eprintln!("[compose] compose watch exited ({status}); develop.watch rules are no longer applied");
+                develop = None;
+            }
+            let mut changed = self.watcher.poll();
+            if changed.is_empty() {
+                continue;
+            }
+            thread::sleep(Duration::from_millis(500));
+            changed.extend(self.watcher.poll());
+            changed.sort();
+            changed.dedup();
+            for service in changed
+                .iter()
+                .take_while(|_| !self.stop_event.load(Ordering::SeqCst))
+            {
+                self.rebuild(service);
+            }
+        }
+        if let Some(mut child) = develop {
+            terminate_process(&mut child, Duration::from_secs(5));
+        }
+    }
+
+    fn develop_containers_running(&self) -> bool {
+        let mut cmd = self.compose.clone();
+        cmd.extend(["ps", "-q", "--status", "running"].map(String::from));
+        cmd.extend(self.develop.iter().cloned());
+        run_output(&cmd)
+            .is_ok_and(|output| output.status.success() && !output.stdout.trim_ascii().is_empty())
+    }
+
+    fn start_develop_watch(&self) -> Option<Child> {
+        let (compose_bin, compose_args) = self.compose.split_first()?;
+        let _ = writeln!(
+            std::io::stdout(),
+            "[compose] applying develop.watch rules for {}",
+            self.develop.join(", ")
+        );
+        let mut cmd = Command::new(compose_bin);
+        cmd.args(compose_args)
+            .args(["watch", "--no-up"])
+            .args(&self.develop)
+            .stdin(Stdio::null())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit());
+        cmd.env_remove("COMPOSE_PROJECT_NAME");
+        match spawn_process_group(&mut cmd) {
+            Ok(child) => Some(child),
+            Err(err) => {
+                eprintln!("[compose] compose watch failed to start: {err}");
+                None
+            }
+        }
+    }
+
+    fn rebuild(&self, service: &str) {
+        let _ = writeln!(
+            std::io::stdout(),
+            "[compose] change detected in {service}; rebuilding"
+        );
+        let recreated = self.compose(&["build", service])
+            && self.compose(&["up", "-d", "--no-deps", "--force-recreate", service]);
+        if !recreated {
+            eprintln!("[compose] rebuild of {service} failed; keeping the running container");
+        }
+    }
+
+    fn compose(&self, args: &[&str]) -> bool {
+        let Some((compose_bin, compose_args)) = self.compose.split_first() else {
+            return false;
+        };
+        let mut cmd = Command::new(compose_bin);
+        cmd.args(compose_args)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit());
+        cmd.env_remove("COMPOSE_PROJECT_NAME");
+        let Ok(mut child) = spawn_process_group(&mut cmd) else {
+            return false;
+        };
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) => return status.success(),
+                Ok(None) => {}
+                Err(_) => return false,
+            }
+            if self.stop_event.load(Ordering::SeqCst) {
+                terminate_process(&mut child, Duration::from_secs(5));
+                return false;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+const COMPOSE_PANE: &str = "compose";
+const COMPOSE_PANE_COLOR: u8 = 90;
+const BROWSER_READY_TIMEOUT: Duration = Duration::from_secs(30);
+const BROWSER_READY_POLL: Duration = Duration::from_millis(500);
+const TAP_FILE_SETTLE: Duration = Duration::from_millis(500);
+
+fn open_browser_after_readiness(
+    engine: &Engine,
+    run_id: &str,
+    stop_event: &AtomicBool,
+    url: &str,
+    timeout: Duration,
+) {
+    let deadline = Instant::now() + timeout;
+    while !stop_event.load(Ordering::SeqCst) {
+        if run_has_ready_service(engine, run_id) {
+            open_browser(url);
+            return;
+        }
+        if Instant::now() >= deadline {
+            let separator = if url.contains('?') { '&' } else { '?' };
+            open_browser(&format!("{url}{separator}starting=1"));
+            return;
+        }
+        thread::sleep(BROWSER_READY_POLL);
+    }
+}
+
+fn run_has_ready_service(engine: &Engine, run_id: &str) -> bool {
+    let ids = engine.collect_run_container_ids(run_id, Scope::Running);
+    engine
+        .inspect_containers(&ids)
+        .iter()
+        .any(|info| !info.is_proxy() && info.is_ready())
+}
+
+fn prune_finished_threads(threads: &mut Vec<thread::JoinHandle<()>>) {
+    let mut remaining = Vec::with_capacity(threads.len());
+    for handle in threads.drain(..) {
+        if handle.is_finished() {
+            let _ = handle.join();
+        } else {
+            remaining.push(handle);
+        }
+    }
+    *threads = remaining;
+}
+
 fn traffic_log_worker<R: Read>(reader: R, context: TrafficWorkerContext) {
     let TrafficWorkerContext {
         hub,
@@ -1045,8 +2410,16 @@ fn traffic_log_worker<R: Read>(reader: R, context: TrafficWorkerContext) {
         stop_event,
         service_name,
         is_egress,
-        tap_enabled,
+        tap,
+        max_body_bytes,
+        ignore,
     } = context;
+    let context = ObservationContext {
+        service_name: &service_name,
+        resolver: resolver.as_ref(),
+        is_egress,
+        max_body_bytes,
+    };
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
     while !stop_event.load(Ordering::SeqCst) {
@@ -1062,18 +2435,97 @@ fn traffic_log_worker<R: Read>(reader: R, context: TrafficWorkerContext) {
             continue;
         }
         let Some(log) = parse_envoy_log_line(trimmed) else {
+            if let Some(tap) = tap.as_ref().filter(|_| is_tap_write_error(trimmed)) {
+                let reason = format!("tap write failed ({trimmed})");
+                fail_over(tap, &hub, &ignore, &context, &reason);
+            }
             continue;
         };
-        if tap_enabled && (log.method.is_some() || log.path.is_some() || log.authority.is_some()) {
+        let Some((log, at_ms, headers_only)) = capture_access_log(tap.as_ref(), log) else {
             continue;
-        }
-        let now_ms = current_time_ms();
-        if let Some(obs) =
-            observation_from_envoy(log, &service_name, resolver.as_ref(), is_egress, now_ms)
-        {
-            hub.emit(obs);
-        }
+        };
+        emit_access_log(&hub, &ignore, &context, (log, at_ms), headers_only);
+    }
+}
+
+fn capture_access_log(
+    tap: Option<&SharedFailover>,
+    log: EnvoyAccessLog,
+) -> Option<(EnvoyAccessLog, u64, bool)> {
+    let now_ms = now_ms();
+    let Some(tap) = tap.filter(|_| {
+        !log.is_upgrade() && (log.method.is_some() || log.path.is_some() || log.authority.is_some())
+    }) else {
+        return Some((log, now_ms, false));
+    };
+    if let (Some(id), Some(flags)) = (log.request_id.as_deref(), log.response_flags.as_deref()) {
+        tap.faults.record(id, flags);
+    }
+    let completed = log.response_code.is_some_and(|code| code > 0);
+    lock_failover(tap)
+        .access_log((log, now_ms), completed.then_some(now_ms))
+        .map(|(log, at_ms)| (log, at_ms, true))
+}
+
+fn emit_access_log(
+    hub: &TrafficHub,
+    ignore: &TrafficIgnore,
+    context: &ObservationContext<'_>,
+    (log, at_ms): HeldAccessLog,
+    headers_only: bool,
+) {
+    let Some(mut obs) = observation_from_envoy(log, context, at_ms) else {
+        return;
+    };
+    if headers_only {
+        let attrs = match &mut obs {
+            Observation::Http(http) => &mut http.attrs,
+            Observation::Upgrade(upgrade) => &mut upgrade.http.attrs,
+            Observation::Flow(flow) => &mut flow.attrs,
+            Observation::Db(db) => &mut db.attrs,
+        };
+        attrs
+            .tags
+            .insert(CAPTURE_TAG.to_string(), HEADERS_ONLY.to_string());
     }
+    emit_unless_ignored(hub, ignore, context.service_name, obs);
+}
+
+fn fail_over(
+    failover: &SharedFailover,
+    hub: &TrafficHub,
+    ignore: &TrafficIgnore,
+    context: &ObservationContext<'_>,
+    reason: &str,
+) {
+    let Some(pending) = lock_failover(failover).fail() else {
+        return;
+    };
+    switch_to_headers_only(pending, hub, ignore, context, reason);
+}
+
+fn switch_to_headers_only(
+    pending: Vec<HeldAccessLog>,
+    hub: &TrafficHub,
+    ignore: &TrafficIgnore,
+    context: &ObservationContext<'_>,
+    reason: &str,
+) {
+    eprintln!(
+        "[compose] {}: {reason}; capturing from access logs only (headers, no bodies)",
+        context.service_name
+    );
+    hub.set_capture_mode(context.service_name, CaptureMode::HeadersOnly);
+    for held in pending {
+        emit_access_log(hub, ignore, context, held, true);
+    }
+}
+
+fn lock_failover(failover: &SharedFailover) -> MutexGuard<'_, TapFailover<HeldAccessLog>> {
+    failover
+        .failover
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
 }
 
 fn tap_file_worker(context: TapWorkerContext) {
@@ -1084,8 +2536,22 @@ fn tap_file_worker(context: TapWorkerContext) {
         service_name,
         is_egress,
         tap_dir,
+        failover,
+        max_body_bytes,
+        ignore,
     } = context;
-    let _ = fs::create_dir_all(&tap_dir);
+    let context = ObservationContext {
+        service_name: &service_name,
+        resolver: resolver.as_ref(),
+        is_egress,
+        max_body_bytes,
+    };
+    if let Err(err) = fs::create_dir_all(&tap_dir).and_then(|()| fs::read_dir(&tap_dir)) {
+        let reason = format!("tap directory {} unavailable ({err})", tap_dir.display());
+        fail_over(&failover, &hub, &ignore, &context, &reason);
+        return;
+    }
+    let mut sockets = HashMap::new();
     while !stop_event.load(Ordering::SeqCst) {
         let Ok(entries) = fs::read_dir(&tap_dir) else {
             thread::sleep(Duration::from_millis(250));
@@ -1093,34 +2559,126 @@ fn tap_file_worker(context: TapWorkerContext) {
         };
         for entry in entries.flatten() {
             let path = entry.path();
-            if !path.is_file() {
-                continue;
-            }
-            let Ok(payload) = fs::read_to_string(&path) else {
+            if is_socket_tap(&path) {
+                read_socket_tap(&path, &mut sockets, &hub, &ignore, &context);
                 continue;
-            };
-            let now_ms = current_time_ms();
-            if let Some(obs) = observation_from_tap(
-                &payload,
-                &service_name,
-                resolver.as_ref(),
-                is_egress,
-                now_ms,
-            ) {
-                hub.emit(obs);
-                let _ = fs::remove_file(&path);
             }
+            read_tap_file(&path, &failover, &hub, &ignore, &context);
+        }
+        let pending = lock_failover(&failover).tick(now_ms());
+        if let Some(pending) = pending {
+            let reason = "no tap files arrived for captured requests";
+            switch_to_headers_only(pending, &hub, &ignore, &context, reason);
         }
         thread::sleep(Duration::from_millis(250));
     }
 }
 
-fn current_time_ms() -> u64 {
-    let millis = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis();
-    u64::try_from(millis).unwrap_or(u64::MAX)
+fn read_tap_file(
+    path: &Path,
+    failover: &SharedFailover,
+    hub: &TrafficHub,
+    ignore: &TrafficIgnore,
+    context: &ObservationContext<'_>,
+) {
+    if !tap_file_settled(path) {
+        return;
+    }
+    let Some(mut obs) = fs::read_to_string(path)
+        .ok()
+        .and_then(|payload| observation_from_tap(&payload, context, now_ms()))
+    else {
+        return;
+    };
+    let _ = fs::remove_file(path);
+    tag_tap_fault(&mut obs, &failover.faults);
+    let mut state = lock_failover(failover);
+    let previous = state.mode();
+    let switched = state.tap_seen();
+    drop(state);
+    emit_unless_ignored(hub, ignore, context.service_name, obs);
+    if !switched {
+        return;
+    }
+    if previous == CaptureMode::HeadersOnly {
+        eprintln!(
+            "[compose] {}: tap files arrived; capturing bodies again",
+            context.service_name
+        );
+    }
+    hub.set_capture_mode(context.service_name, CaptureMode::Tap);
+}
+
+fn tap_file_settled(path: &Path) -> bool {
+    fs::metadata(path)
+        .ok()
+        .filter(fs::Metadata::is_file)
+        .and_then(|meta| meta.modified().ok())
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age >= TAP_FILE_SETTLE)
+}
+
+fn tag_tap_fault(obs: &mut Observation, faults: &FaultMarks) {
+    let Observation::Http(http) = obs else {
+        return;
+    };
+    let Some(kind) = http
+        .correlation
+        .request_id
+        .as_deref()
+        .and_then(|id| faults.take(id))
+    else {
+        return;
+    };
+    http.attrs
+        .tags
+        .insert(CHAOS_TAG.to_string(), kind.to_string());
+}
+
+#[derive(Default)]
+struct SocketTapFile {
+    offset: u64,
+    tap: SocketTap,
+}
+
+fn is_socket_tap(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("socket_"))
+}
+
+fn read_socket_tap(
+    path: &Path,
+    sockets: &mut HashMap<PathBuf, SocketTapFile>,
+    hub: &TrafficHub,
+    ignore: &TrafficIgnore,
+    context: &ObservationContext<'_>,
+) {
+    let stream = sockets.entry(path.to_path_buf()).or_default();
+    let mut bytes = Vec::new();
+    let read = fs::File::open(path).and_then(|mut file| {
+        file.seek(SeekFrom::Start(stream.offset))?;
+        file.read_to_end(&mut bytes)
+    });
+    let Ok(read) = read else {
+        return;
+    };
+    stream.offset += u64::try_from(read).unwrap_or(0);
+    for obs in stream.tap.feed(&bytes, context, now_ms()) {
+        emit_unless_ignored(hub, ignore, context.service_name, obs);
+    }
+    if stream.tap.closed() {
+        let _ = fs::remove_file(path);
+        sockets.remove(path);
+    }
+}
+
+fn emit_unless_ignored(hub: &TrafficHub, ignore: &TrafficIgnore, service: &str, obs: Observation) {
+    if ignore.matches(&obs) {
+        hub.suppress(service);
+    } else {
+        hub.emit(obs);
+    }
 }
 
 pub struct SignalContext {
@@ -1128,6 +2686,7 @@ pub struct SignalContext {
     signal_handled: Arc<AtomicBool>,
     exit_code: Arc<AtomicI32>,
     handles: Arc<ProcessHandles>,
+    interrupts: InterruptTracker,
 }
 
 impl SignalContext {
@@ -1143,16 +2702,37 @@ impl SignalContext {
             signal_handled,
             exit_code,
             handles,
+            interrupts: InterruptTracker::default(),
         }
     }
 
     pub fn handle_signal(&self) {
-        if self.signal_handled.swap(true, Ordering::SeqCst) {
+        let stopping = self.signal_handled.swap(true, Ordering::SeqCst);
+        if stopping && self.handles.was_forced() {
             return;
         }
+        match self.interrupts.press(now_ms(), stopping) {
+            Interrupt::Stop => {}
+            Interrupt::ForceKill => {
+                eprintln!("[compose] force quit: killing compose and log followers");
+                self.stop_event.store(true, Ordering::SeqCst);
+                self.handles.force_kill();
+                return;
+            }
+            Interrupt::StillStopping => {
+                eprintln!("[compose] still stopping; press Ctrl-C again quickly to force quit");
+                return;
+            }
+        }
+        eprintln!("[compose] stopping; press Ctrl-C again to force quit");
         self.exit_code.store(130, Ordering::SeqCst);
-        self.stop_event.store(true, Ordering::SeqCst);
-        self.handles.stop_log_procs();
-        self.handles.stop_compose_proc();
+        let stop_event = self.stop_event.clone();
+        let handles = self.handles.clone();
+        // Services stop first so the log followers still capture what they print on the way down.
+        thread::spawn(move || {
+            handles.stop_compose_proc();
+            stop_event.store(true, Ordering::SeqCst);
+            handles.stop_log_procs();
+        });
     }
 }
@@ -0,0 +1,180 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::domain::Scope;
+use crate::infra::engine::{run_settled, Engine};
+use crate::support::logging::LogHub;
+use crate::support::stall::stall_idle;
+
+pub const STALL_EXIT_CODE: i32 = 124;
+pub const STALL_RETRIES: u32 = 2;
+const DEFAULT_STALL_TIMEOUT: Duration = Duration::from_mins(5);
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+const DIAGNOSTIC_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StallAction {
+    Warn,
+    Retry,
+    Abort,
+}
+
+impl StallAction {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "warn" => Ok(Self::Warn),
+            "retry" => Ok(Self::Retry),
+            "abort" => Ok(Self::Abort),
+            _ => Err(format!(
+                "Invalid --on-stall value {value:?}. Use warn, retry or abort."
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct StallPolicy {
+    pub timeout: Duration,
+    pub action: StallAction,
+}
+
+impl Default for StallPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_STALL_TIMEOUT,
+            action: StallAction::Warn,
+        }
+    }
+}
+
+pub struct StallTarget<'a> {
+    pub engine: &'a Engine,
+    pub run_id: &'a str,
+    pub services: usize,
+    pub log_hub: Option<Arc<LogHub>>,
+}
+
+pub struct StallWatch {
+    policy: StallPolicy,
+    engine: Engine,
+    progress: Arc<Mutex<Instant>>,
+    settled: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    warned_at: Mutex<Option<Instant>>,
+    stalled: AtomicBool,
+}
+
+impl StallWatch {
+    pub fn start(policy: StallPolicy, target: StallTarget<'_>) -> Option<Self> {
+        if policy.timeout.is_zero() {
+            return None;
+        }
+        let watch = Self {
+            policy,
+            engine: target.engine.clone(),
+            progress: Arc::new(Mutex::new(Instant::now())),
+            settled: Arc::new(AtomicBool::new(false)),
+            stop: Arc::new(AtomicBool::new(false)),
+            warned_at: Mutex::new(None),
+            stalled: AtomicBool::new(false),
+        };
+        let sampler = Sampler {
+            engine: target.engine.clone(),
+            run_id: target.run_id.to_string(),
+            services: target.services,
+            log_hub: target.log_hub,
+            progress: watch.progress.clone(),
+            settled: watch.settled.clone(),
+            stop: watch.stop.clone(),
+        };
+        thread::spawn(move || sampler.run());
+        Some(watch)
+    }
+
+    pub fn check(&self) -> bool {
+        if self.settled.load(Ordering::SeqCst) {
+            return false;
+        }
+        let progress = *lock(&self.progress);
+        let warned_at = *lock(&self.warned_at);
+        let Some(idle) = stall_idle(progress, warned_at, self.policy.timeout, Instant::now())
+        else {
+            return false;
+        };
+        eprintln!(
+            "[compose] `up` made no progress for {}s: no new containers, state changes or log lines",
+            idle.as_secs()
+        );
+        for line in self.engine.diagnostics(DIAGNOSTIC_TIMEOUT) {
+            eprintln!("[compose]   {line}");
+        }
+        if self.policy.action == StallAction::Warn {
+            eprintln!(
+                "[compose] still waiting; pass --on-stall retry or --on-stall abort to act on it"
+            );
+            *lock(&self.warned_at) = Some(Instant::now());
+            return false;
+        }
+        self.stalled.store(true, Ordering::SeqCst);
+        true
+    }
+
+    pub fn stalled(&self) -> bool {
+        self.stalled.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for StallWatch {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+struct Sampler {
+    engine: Engine,
+    run_id: String,
+    services: usize,
+    log_hub: Option<Arc<LogHub>>,
+    progress: Arc<Mutex<Instant>>,
+    settled: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Sampler {
+    fn run(&self) {
+        let mut last = None;
+        while !self.stop.load(Ordering::SeqCst) {
+            let ids = self
+                .engine
+                .collect_run_container_ids(&self.run_id, Scope::All);
+            let containers = self.engine.inspect_containers(&ids);
+            if run_settled(self.services, &containers) {
+                self.settled.store(true, Ordering::SeqCst);
+                return;
+            }
+            let mut states: Vec<_> = containers
+                .into_iter()
+                .map(|container| (container.id, container.state))
+                .collect();
+            states.sort();
+            let lines: u64 = self
+                .log_hub
+                .as_ref()
+                .map_or(0, |hub| hub.line_counts().values().sum());
+            let sample = Some((states, lines));
+            if sample != last {
+                *lock(&self.progress) = Instant::now();
+                last = sample;
+            }
+            thread::sleep(SAMPLE_INTERVAL);
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
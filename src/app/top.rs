@@ -0,0 +1,289 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::Receiver;
+use time::OffsetDateTime;
+
+use crate::domain::traffic::{EntityId, TrafficCall};
+use crate::domain::{LogEvent, Scope};
+use crate::infra::container_watch::ContainerWatch;
+use crate::infra::engine::{ContainerInfo, Engine};
+use crate::support::constants::SERVICE_LABEL;
+use crate::support::log_filter::LogFilter;
+use crate::support::logging::LogHub;
+use crate::support::run::project_name_from_run_id;
+use crate::support::sampling::TrafficSampling;
+use crate::support::text::truncate;
+use crate::support::traffic::TrafficHub;
+
+use super::{
+    load_run_containers, print_table, resolve_run_id, run_metadata_from_containers,
+    run_services_from_containers, run_tap_dir, runner, setup_signals, RunServices,
+};
+
+const REFRESH: Duration = Duration::from_secs(1);
+const RATE_WINDOW: usize = 10;
+const LAST_LINE_WIDTH: usize = 60;
+const TOP_HISTORY: usize = 256;
+const LOG_LOOKBACK_SECS: i64 = 60;
+
+#[derive(Clone, Copy, Default)]
+struct CallCounts {
+    calls: u64,
+    errors: u64,
+}
+
+struct TopState {
+    last_lines: HashMap<String, String>,
+    buckets: VecDeque<(Instant, HashMap<String, CallCounts>)>,
+    window_start: Instant,
+}
+
+impl TopState {
+    fn new() -> Self {
+        Self {
+            last_lines: HashMap::new(),
+            buckets: VecDeque::new(),
+            window_start: Instant::now(),
+        }
+    }
+
+    fn absorb_logs(&mut self, receiver: &Receiver<LogEvent>) {
+        for event in receiver.try_iter() {
+            if let Some(line) = event.line.lines().next() {
+                self.last_lines.insert(event.service, line.to_string());
+            }
+        }
+    }
+
+    fn absorb_calls(&mut self, receiver: Option<&Receiver<TrafficCall>>) {
+        let mut bucket: HashMap<String, CallCounts> = HashMap::new();
+        for call in receiver.into_iter().flat_map(Receiver::try_iter) {
+            let Some(EntityId::Workload { name, .. }) = call.peer.dst else {
+                continue;
+            };
+            let counts = bucket.entry(name).or_default();
+            counts.calls += 1;
+            if call.status.is_some_and(|status| status >= 400) {
+                counts.errors += 1;
+            }
+        }
+        self.buckets.push_back((Instant::now(), bucket));
+        while self.buckets.len() > RATE_WINDOW {
+            if let Some((closed_at, _)) = self.buckets.pop_front() {
+                self.window_start = closed_at;
+            }
+        }
+    }
+
+    fn rates(&self, service: &str) -> (String, String) {
+        let total = self
+            .buckets
+            .iter()
+            .filter_map(|(_, bucket)| bucket.get(service).copied())
+            .fold(CallCounts::default(), |acc, counts| CallCounts {
+                calls: acc.calls + counts.calls,
+                errors: acc.errors + counts.errors,
+            });
+        if total.calls == 0 {
+            return ("-".to_string(), "-".to_string());
+        }
+        // Ticks overrun REFRESH under load, so rate over the measured span.
+        let window_ms = self
+            .buckets
+            .back()
+            .map(|(closed_at, _)| closed_at.duration_since(self.window_start).as_millis())
+            .and_then(|ms| u64::try_from(ms).ok())
+            .unwrap_or(0)
+            .max(1);
+        let tenths = total.calls * 10_000 / window_ms;
+        let errors = total.errors * 100 / total.calls;
+        (
+            format!("{}.{}", tenths / 10, tenths % 10),
+            format!("{errors}%"),
+        )
+    }
+}
+
+struct TopContext {
+    engine: Engine,
+    run_id: String,
+    project_name: String,
+    stop_event: Arc<AtomicBool>,
+    handles: Arc<runner::ProcessHandles>,
+    container_watch: Arc<ContainerWatch>,
+}
+
+impl TopContext {
+    fn follow_logs(&self, services: &RunServices) -> (Receiver<LogEvent>, thread::JoinHandle<()>) {
+        let log_hub = LogHub::with_reorder(TOP_HISTORY, Duration::ZERO);
+        let (receiver, _) = log_hub.register_client();
+        let mut follower = runner::LogFollower::new(
+            self.engine.clone(),
+            self.run_id.clone(),
+            self.project_name.clone(),
+            self.stop_event.clone(),
+            Some(log_hub),
+            self.handles.clone(),
+            services.proxy_services.clone(),
+            services.service_aliases.clone(),
+        );
+        follower.set_container_watch(self.container_watch.clone());
+        follower.set_filter(
+            LogFilter::default(),
+            Some(OffsetDateTime::now_utc().unix_timestamp() - LOG_LOOKBACK_SECS),
+        );
+        let handle = thread::spawn(move || {
+            follower.follow_logs(false, &mut Vec::new());
+        });
+        (receiver, handle)
+    }
+
+    fn follow_traffic(
+        &self,
+        services: RunServices,
+        tap_dir: Option<PathBuf>,
+        sampling: TrafficSampling,
+    ) -> Option<(Receiver<TrafficCall>, thread::JoinHandle<()>)> {
+        if services.proxy_services.is_empty() {
+            return None;
+        }
+        let hub = Arc::new(TrafficHub::new());
+        hub.set_sampling(sampling);
+        let mut follower = runner::TrafficFollower::new(
+            self.engine.clone(),
+            self.run_id.clone(),
+            self.project_name.clone(),
+            self.stop_event.clone(),
+            self.handles.clone(),
+            hub.clone(),
+            services.proxy_services,
+            services.service_aliases,
+            services.egress_proxy,
+            tap_dir,
+        );
+        follower.set_container_watch(self.container_watch.clone());
+        let handle = thread::spawn(move || {
+            follower.follow();
+        });
+        Some((hub.register_call_client().0, handle))
+    }
+}
+
+pub fn run_top(
+    engine: &Engine,
+    run_id: Option<String>,
+    sampling: TrafficSampling,
+) -> Result<i32, String> {
+    let run_id = resolve_run_id(engine, "top", run_id)?;
+    let run_id = run_id.as_str();
+    let containers = load_run_containers(engine, run_id, Scope::All)?;
+    let metadata = run_metadata_from_containers(run_id, &containers);
+    let services = run_services_from_containers(&containers);
+    let tap_dir = run_tap_dir(&metadata);
+    let exit_code = Arc::new(AtomicI32::new(0));
+    let stop_event = Arc::new(AtomicBool::new(false));
+    let context = TopContext {
+        engine: engine.clone(),
+        run_id: run_id.to_string(),
+        project_name: metadata
+            .project_name
+            .unwrap_or_else(|| project_name_from_run_id(run_id)),
+        handles: Arc::new(runner::ProcessHandles::new()),
+        container_watch: ContainerWatch::new(
+            engine.clone(),
+            run_id.to_string(),
+            stop_event.clone(),
+        ),
+        stop_event,
+    };
+    setup_signals(runner::SignalContext::new(
+        context.stop_event.clone(),
+        Arc::new(AtomicBool::new(false)),
+        exit_code.clone(),
+        context.handles.clone(),
+    ));
+
+    let (log_receiver, log_handle) = context.follow_logs(&services);
+    let (call_receiver, traffic_handle) =
+        context.follow_traffic(services, tap_dir, sampling).unzip();
+
+    let mut state = TopState::new();
+    while !context.stop_event.load(Ordering::SeqCst) {
+        let tick = Instant::now();
+        state.absorb_logs(&log_receiver);
+        state.absorb_calls(call_receiver.as_ref());
+        render(engine, run_id, &state);
+        thread::sleep(REFRESH.saturating_sub(tick.elapsed()));
+    }
+
+    context.handles.stop_log_procs();
+    for handle in std::iter::once(log_handle).chain(traffic_handle) {
+        let _ = handle.join();
+    }
+    Ok(exit_code.load(Ordering::SeqCst))
+}
+
+fn render(engine: &Engine, run_id: &str, state: &TopState) {
+    let ids = engine.collect_run_container_ids(run_id, Scope::All);
+    let mut containers: Vec<ContainerInfo> = engine
+        .inspect_containers(&ids)
+        .into_iter()
+        .filter(|info| !info.is_proxy())
+        .collect();
+    containers.sort_by_key(service_name);
+    let running: Vec<String> = containers
+        .iter()
+        .filter(|info| info.state.as_deref() == Some("running"))
+        .map(|info| info.id.clone())
+        .collect();
+    let usage = engine.container_stats(&running);
+    let rows: Vec<[String; 8]> = containers
+        .iter()
+        .map(|info| {
+            let service = service_name(info);
+            let sample = usage.get(&info.id).cloned().unwrap_or_default();
+            let (rate, errors) = state.rates(&service);
+            let last_line = state
+                .last_lines
+                .get(&service)
+                .map_or_else(String::new, |line| truncate(line, LAST_LINE_WIDTH));
+            [
+                service,
+                info.display_state().unwrap_or_else(|| "-".to_string()),
+                sample
+                    .cpu_percent
+                    .map_or_else(|| "-".to_string(), |cpu| format!("{cpu:.1}%")),
+                sample.memory.unwrap_or_else(|| "-".to_string()),
+                info.restart_count.to_string(),
+                rate,
+                errors,
+                last_line,
+            ]
+        })
+        .collect();
+
+    let mut stdout = io::stdout();
+    let _ = write!(stdout, "\x1b[H\x1b[2J");
+    let _ = writeln!(stdout, "sanelens top  run {run_id}  (Ctrl-C to quit)\n");
+    print_table(
+        [
+            "SERVICE", "STATE", "CPU", "MEM", "RESTARTS", "REQ/S", "ERR", "LAST LOG",
+        ],
+        &rows,
+    );
+    let _ = stdout.flush();
+}
+
+fn service_name(info: &ContainerInfo) -> String {
+    info.labels
+        .get(SERVICE_LABEL)
+        .or(info.service.as_ref())
+        .cloned()
+        .unwrap_or_else(|| info.id.chars().take(12).collect())
+}
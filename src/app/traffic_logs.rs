@@ -0,0 +1,72 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{select, Receiver};
+
+use crate::domain::traffic::TrafficCall;
+use crate::domain::OutputStream;
+use crate::support::clock::now_ms;
+use crate::support::logging::LogHub;
+use crate::support::multiline::format_epoch_millis_rfc3339;
+use crate::support::traffic::TrafficHub;
+use crate::support::traffic_logs::{TrafficLogLimiter, TrafficLogs, TRAFFIC_LOG_SERVICE};
+
+use super::render::call_log_line;
+
+const TICK: Duration = Duration::from_secs(1);
+
+pub fn start_traffic_logs(
+    logs: TrafficLogs,
+    log_hub: Arc<LogHub>,
+    traffic_hub: &TrafficHub,
+    stop_event: Arc<AtomicBool>,
+) -> thread::JoinHandle<()> {
+    let (calls, _) = traffic_hub.register_call_client();
+    thread::spawn(move || mirror_calls(&logs, &log_hub, &calls, &stop_event))
+}
+
+fn mirror_calls(
+    logs: &TrafficLogs,
+    log_hub: &LogHub,
+    calls: &Receiver<TrafficCall>,
+    stop_event: &AtomicBool,
+) {
+    let mut limiter = TrafficLogLimiter::new(logs.max_per_sec);
+    while !stop_event.load(Ordering::SeqCst) {
+        let call = select! {
+            recv(calls) -> call => match call {
+                Ok(call) => Some(call),
+                Err(_) => break,
+            },
+            default(TICK) => None,
+        };
+        let now = now_ms();
+        if let Some(skipped) = limiter.take_skipped(now) {
+            log_hub.annotate(
+                Some(TRAFFIC_LOG_SERVICE),
+                &format!(
+                    "{skipped} calls not mirrored (limit {}/s)",
+                    logs.max_per_sec
+                ),
+                None,
+            );
+        }
+        let Some(call) = call.filter(|call| logs.matches(call)) else {
+            continue;
+        };
+        if !limiter.admit(now) {
+            continue;
+        }
+        let timestamp = i64::try_from(call.at_ms)
+            .ok()
+            .and_then(format_epoch_millis_rfc3339);
+        log_hub.publish(
+            TRAFFIC_LOG_SERVICE,
+            OutputStream::Stdout,
+            &call_log_line(&call),
+            timestamp.as_deref(),
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+
+use crate::domain::traffic::TrafficCall;
+use crate::infra::verify::Verifier;
+use crate::support::traffic::TrafficHub;
+
+const POLL: Duration = Duration::from_millis(200);
+
+pub struct VerifyTask {
+    hub: Arc<TrafficHub>,
+    done: Arc<AtomicBool>,
+    handle: thread::JoinHandle<Verifier>,
+}
+
+impl VerifyTask {
+    pub fn start(mut verifier: Verifier, hub: Arc<TrafficHub>) -> Self {
+        let (receiver, history) = hub.register_call_client();
+        for call in &history {
+            verifier.observe(call);
+        }
+        let done = Arc::new(AtomicBool::new(false));
+        let thread_done = done.clone();
+        let handle = thread::spawn(move || {
+            observe_calls(&mut verifier, &receiver, &thread_done);
+            verifier
+        });
+        Self { hub, done, handle }
+    }
+
+    pub fn finish(self) -> bool {
+        self.done.store(true, Ordering::SeqCst);
+        let Ok(verifier) = self.handle.join() else {
+            eprintln!("[verify] verification thread failed");
+            return false;
+        };
+        let violations = verifier.finish(&self.hub.edges());
+        for violation in &violations {
+            eprintln!("[verify] FAIL {}: {}", violation.rule, violation.message);
+        }
+        if violations.is_empty() {
+            eprintln!("[verify] all expectations met");
+        } else {
+            let plural = if violations.len() == 1 { "" } else { "s" };
+            eprintln!("[verify] {} violation{plural}", violations.len());
+        }
+        violations.is_empty()
+    }
+}
+
+fn observe_calls(verifier: &mut Verifier, receiver: &Receiver<TrafficCall>, done: &AtomicBool) {
+    loop {
+        match receiver.recv_timeout(POLL) {
+            Ok(call) => verifier.observe(&call),
+            Err(RecvTimeoutError::Timeout) if done.load(Ordering::SeqCst) => break,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+    for call in receiver.try_iter() {
+        verifier.observe(&call);
+    }
+}
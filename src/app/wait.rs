@@ -0,0 +1,82 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::domain::Scope;
+use crate::infra::engine::{ContainerInfo, Engine};
+use crate::support::wait::{target_progress, ContainerState, WaitOptions, WaitProgress};
+
+const WAIT_POLL: Duration = Duration::from_secs(1);
+
+pub fn wait_for_services(
+    engine: &Engine,
+    run_id: &str,
+    options: &WaitOptions,
+    stop_event: &AtomicBool,
+) -> i32 {
+    let deadline = Instant::now() + options.timeout;
+    loop {
+        let ids = engine.collect_run_container_ids(run_id, Scope::All);
+        let pending = match pending_targets(options, &engine.inspect_containers(&ids)) {
+            Ok(pending) => pending,
+            Err(err) => {
+                eprintln!("[wait] {err}");
+                return 1;
+            }
+        };
+        if pending.is_empty() {
+            let _ = writeln!(io::stdout(), "[wait] all services ready");
+            return 0;
+        }
+        if stop_event.load(Ordering::SeqCst) {
+            return 130;
+        }
+        if Instant::now() >= deadline {
+            eprintln!(
+                "[wait] timed out after {}s; still waiting for {}",
+                options.timeout.as_secs(),
+                pending.join(", ")
+            );
+            return 1;
+        }
+        thread::sleep(WAIT_POLL);
+    }
+}
+
+fn pending_targets(
+    options: &WaitOptions,
+    containers: &[ContainerInfo],
+) -> Result<Vec<String>, String> {
+    let states: Vec<ContainerState<'_>> = containers
+        .iter()
+        .filter(|info| !info.is_proxy())
+        .filter_map(|info| {
+            Some(ContainerState {
+                service: info.service.as_deref()?,
+                state: info.state.as_deref(),
+                health: info.health.as_deref(),
+            })
+        })
+        .collect();
+    let mut pending = Vec::new();
+    for target in &options.targets {
+        let condition = target.condition.label();
+        match target_progress(target, &states) {
+            WaitProgress::Met => {}
+            WaitProgress::Waiting(reason) => {
+                pending.push(format!(
+                    "{} ({condition}, currently {reason})",
+                    target.service
+                ));
+            }
+            WaitProgress::Failed(reason) => {
+                return Err(format!(
+                    "{} can never become {condition}: {reason}",
+                    target.service
+                ));
+            }
+        }
+    }
+    Ok(pending)
+}
@@ -3,9 +3,11 @@ use std::time::Duration;
 
 use crate::app::runner::{ComposeRunner, ComposeRunnerConfig};
 use crate::domain::EngineKind;
-use crate::infra::compose::detect_compose_cmd;
+use crate::infra::compose::{detect_compose_cmd, ComposeProvider};
 use crate::infra::engine::Engine;
 use crate::infra::process::{command_exists, pid_alive};
+use crate::support::args::is_env_truthy;
+use crate::support::constants::KEEP_VOLUMES_ENV;
 use crate::support::run::run_started_at;
 
 pub fn run_watchdog(
@@ -27,7 +29,10 @@ pub fn run_watchdog(
             EngineKind::Podman,
         )
     } else {
-        let selection = match detect_compose_cmd(None) {
+        let selection = match detect_compose_cmd(ComposeProvider {
+            engine: None,
+            allow_legacy: true,
+        }) {
             Ok(selection) => selection,
             Err(err) => {
                 eprintln!("{err}");
@@ -50,6 +55,7 @@ pub fn run_watchdog(
         .parent()
         .map(std::path::Path::to_path_buf);
     runner.set_derived_dir(derived_dir);
+    runner.set_keep_volumes(is_env_truthy(KEEP_VOLUMES_ENV));
     runner.enable_cleanup();
     runner.cleanup_once();
 }
@@ -1,21 +1,172 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 pub mod traffic;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ServiceInfo {
     pub name: String,
     pub endpoints: Vec<String>,
     pub endpoint: Option<String>,
     pub exposed: bool,
+    #[serde(default)]
+    pub published: Vec<PublishedEndpoint>,
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublishedEndpoint {
+    pub host: String,
+    pub port: u16,
+    pub url: String,
+    pub protocol: Option<EndpointProtocol>,
+    #[serde(default)]
+    pub forwarded: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointProtocol {
+    Http,
+    Https,
+    Postgres,
+    Mysql,
+    Redis,
+    Mongodb,
+    Amqp,
+    Tcp,
+}
+
+impl EndpointProtocol {
+    pub const fn scheme(self) -> &'static str {
+        match self {
+            Self::Http => "http",
+            Self::Https => "https",
+            Self::Postgres => "postgres",
+            Self::Mysql => "mysql",
+            Self::Redis => "redis",
+            Self::Mongodb => "mongodb",
+            Self::Amqp => "amqp",
+            Self::Tcp => "tcp",
+        }
+    }
+
+    pub const fn is_web(self) -> bool {
+        matches!(self, Self::Http | Self::Https)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyKind {
+    DependsOn,
+    Link,
+    NetworkMode,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Dependency {
+    pub from: String,
+    pub to: String,
+    pub kind: DependencyKind,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ServiceGraph {
+    pub services: Vec<String>,
+    pub dependencies: Vec<Dependency>,
+    pub networks: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeStatus {
+    Declared,
+    Confirmed,
+    Undeclared,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    pub declared: Vec<DependencyKind>,
+    pub same_network: bool,
+    pub status: EdgeStatus,
+    pub calls: u64,
+    pub errors: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GraphView {
+    pub services: Vec<String>,
+    pub networks: BTreeMap<String, Vec<String>>,
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct LogEvent {
     pub seq: u64,
     pub service: String,
     pub container_ts: Option<String>,
+    pub ts_ms: Option<i64>,
     pub line: String,
+    pub stream: OutputStream,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub annotation: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<LogKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exception: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogKind {
+    Error,
+}
+
+pub fn instance_identity(service: &str, instance: Option<u32>) -> String {
+    instance.map_or_else(
+        || service.to_string(),
+        |instance| format!("{service}[{instance}]"),
+    )
+}
+
+pub fn split_identity(identity: &str) -> (&str, Option<u32>) {
+    identity
+        .strip_suffix(']')
+        .and_then(|rest| rest.rsplit_once('['))
+        .and_then(|(service, instance)| Some((service, Some(instance.parse().ok()?))))
+        .unwrap_or((identity, None))
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    #[default]
+    Stdout,
+    Stderr,
+}
+
+impl OutputStream {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "stdout" | "out" => Some(Self::Stdout),
+            "stderr" | "err" => Some(Self::Stderr),
+            _ => None,
+        }
+    }
+
+    pub const fn marker(self) -> char {
+        match self {
+            Self::Stdout => '|',
+            Self::Stderr => '!',
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -1,8 +1,78 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::net::IpAddr;
 
-#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize)]
+pub const NETWORK_TAG: &str = "network";
+pub const ORIGIN_TAG: &str = "origin";
+pub const HOST_ENTITY: &str = "host";
+pub const OBSERVATION_SCHEMA: &str = "sanelens.observation.v2";
+const OBSERVATION_SCHEMA_PREFIX: &str = "sanelens.observation.v";
+const OBSERVATION_SCHEMA_VERSION: u32 = 2;
+
+#[derive(Serialize)]
+pub struct Versioned<'a, T> {
+    pub schema: &'static str,
+    #[serde(flatten)]
+    pub payload: &'a T,
+}
+
+pub const fn versioned<T>(payload: &T) -> Versioned<'_, T> {
+    Versioned {
+        schema: OBSERVATION_SCHEMA,
+        payload,
+    }
+}
+
+pub fn versioned_all<T>(payloads: &[T]) -> Vec<Versioned<'_, T>> {
+    payloads.iter().map(versioned).collect()
+}
+
+pub fn upgrade_schema(mut value: serde_json::Value) -> Result<serde_json::Value, String> {
+    let version = match value.get("schema").and_then(serde_json::Value::as_str) {
+        None => 0,
+        Some(schema) => schema
+            .strip_prefix(OBSERVATION_SCHEMA_PREFIX)
+            .and_then(|version| version.parse().ok())
+            .ok_or_else(|| format!("unknown schema '{schema}'"))?,
+    };
+    if version > OBSERVATION_SCHEMA_VERSION {
+        return Err(format!(
+            "schema v{version} is newer than this build supports (v{OBSERVATION_SCHEMA_VERSION})"
+        ));
+    }
+    // Unversioned payloads predate the schema field and are otherwise v1.
+    if version < 2 {
+        upgrade_external_ips(&mut value);
+    }
+    if let Some(object) = value.as_object_mut() {
+        object.insert(
+            "schema".to_string(),
+            serde_json::Value::from(OBSERVATION_SCHEMA),
+        );
+    }
+    Ok(value)
+}
+
+// v2 replaced the single `ip` of external entities with every resolved `ips`.
+fn upgrade_external_ips(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(object) => {
+            if object.get("kind").and_then(serde_json::Value::as_str) == Some("external") {
+                let ips: Vec<serde_json::Value> = object
+                    .remove("ip")
+                    .into_iter()
+                    .filter(|ip| ip.as_str() != Some("0.0.0.0"))
+                    .collect();
+                object.insert("ips".to_string(), serde_json::Value::Array(ips));
+            }
+            object.values_mut().for_each(upgrade_external_ips);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(upgrade_external_ips),
+        _ => {}
+    }
+}
+
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "kind", rename_all = "snake_case")]
 pub enum EntityId {
     Workload {
@@ -10,23 +80,40 @@ pub enum EntityId {
         instance: Option<String>,
     },
     External {
-        ip: IpAddr,
+        ips: Vec<IpAddr>,
         dns_name: Option<String>,
     },
-    #[allow(dead_code)]
     Host {
         name: String,
     },
     Unknown,
 }
 
-#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize)]
+impl EntityId {
+    pub fn workload_name(&self) -> Option<&str> {
+        match self {
+            Self::Workload { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn external_host(&self) -> Option<String> {
+        match self {
+            Self::External { ips, dns_name } => dns_name
+                .clone()
+                .or_else(|| ips.first().map(ToString::to_string)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Socket {
     pub ip: IpAddr,
     pub port: u16,
 }
 
-#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "kind", rename_all = "lowercase")]
 pub enum Transport {
     Tcp,
@@ -38,14 +125,14 @@ pub enum Transport {
     },
 }
 
-#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct FlowKey {
     pub src: Socket,
     pub dst: Socket,
     pub transport: Transport,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FlowMetrics {
     pub bytes_in: Option<u64>,
     pub bytes_out: Option<u64>,
@@ -53,21 +140,21 @@ pub struct FlowMetrics {
     pub duration_ms: Option<u64>,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Peer {
     pub src: Option<EntityId>,
     pub dst: Option<EntityId>,
     pub raw: Option<FlowKey>,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ObservationAttrs {
     pub visibility: Visibility,
     pub confidence: Confidence,
     pub tags: BTreeMap<String, String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Visibility {
     L4Flow,
@@ -85,7 +172,7 @@ impl Visibility {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Confidence {
     Exact,
@@ -95,14 +182,14 @@ pub enum Confidence {
 }
 
 #[allow(clippy::struct_field_names)]
-#[derive(Clone, Debug, Default, Serialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Correlation {
     pub request_id: Option<String>,
     pub trace_id: Option<String>,
     pub span_id: Option<String>,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct HttpObservation {
     pub at_ms: u64,
     pub peer: Peer,
@@ -120,7 +207,7 @@ pub struct HttpObservation {
     pub attrs: ObservationAttrs,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FlowObservation {
     pub at_ms: u64,
     pub flow: FlowKey,
@@ -129,14 +216,41 @@ pub struct FlowObservation {
     pub attrs: ObservationAttrs,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UpgradeInfo {
+    pub protocol: String,
+    pub messages_in: Option<u64>,
+    pub messages_out: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UpgradeObservation {
+    pub http: HttpObservation,
+    pub upgrade: UpgradeInfo,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DbObservation {
+    pub at_ms: u64,
+    pub peer: Peer,
+    pub protocol: String,
+    pub command: String,
+    pub statement: String,
+    pub duration_ms: Option<u64>,
+    pub attrs: ObservationAttrs,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(tag = "kind", rename_all = "snake_case")]
 pub enum Observation {
     Flow(FlowObservation),
     Http(HttpObservation),
+    Upgrade(UpgradeObservation),
+    Db(DbObservation),
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TrafficCall {
     pub seq: u64,
     pub at_ms: u64,
@@ -153,16 +267,23 @@ pub struct TrafficCall {
     pub response_body: Option<String>,
     pub correlation: Correlation,
     pub attrs: ObservationAttrs,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upgrade: Option<UpgradeInfo>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TraceNode {
+    pub call: TrafficCall,
+    pub children: Vec<Self>,
 }
 
-#[allow(clippy::struct_excessive_bools)]
-#[derive(Clone, Debug, Default, Serialize)]
-pub struct Capabilities {
-    pub l4_flows: bool,
-    pub http: bool,
-    pub grpc: bool,
-    pub can_see_bodies: bool,
-    pub can_correlate: bool,
+#[derive(Clone, Debug, Serialize)]
+pub struct Trace {
+    pub request_id: String,
+    pub started_at_ms: u64,
+    pub duration_ms: Option<u64>,
+    pub call_count: usize,
+    pub roots: Vec<TraceNode>,
 }
 
 pub trait ObservationSink: Send + Sync {
@@ -171,6 +292,18 @@ pub trait ObservationSink: Send + Sync {
 
 pub trait Resolver: Send + Sync {
     fn resolve_entity(&self, socket: &Socket) -> Option<EntityId>;
+
+    fn record_dns(&self, _host: &str, ip: Option<IpAddr>) -> Vec<IpAddr> {
+        ip.into_iter().collect()
+    }
+
+    fn network_name(&self, _ip: &IpAddr) -> Option<String> {
+        None
+    }
+
+    fn dns_name(&self, _ip: &IpAddr) -> Option<String> {
+        None
+    }
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize)]
@@ -188,6 +321,13 @@ pub enum EdgeKey {
         method: String,
         route: String,
     },
+    Db {
+        from: EntityId,
+        to: EntityId,
+        protocol: String,
+        command: String,
+        statement: String,
+    },
     #[allow(dead_code)]
     Grpc {
         from: EntityId,
@@ -197,6 +337,27 @@ pub enum EdgeKey {
     },
 }
 
+impl EdgeKey {
+    // Named external peers gain addresses as new DNS answers arrive; they stay the same edge.
+    pub fn without_addresses(&self) -> Self {
+        let mut key = self.clone();
+        let (Self::Flow { from, to, .. }
+        | Self::Http { from, to, .. }
+        | Self::Db { from, to, .. }
+        | Self::Grpc { from, to, .. }) = &mut key;
+        for entity in [from, to] {
+            if let EntityId::External {
+                ips,
+                dns_name: Some(_),
+            } = entity
+            {
+                ips.clear();
+            }
+        }
+        key
+    }
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct EdgeStats {
     pub count: u64,
@@ -205,7 +366,10 @@ pub struct EdgeStats {
     pub errors: u64,
     pub p50_ms: Option<u64>,
     pub p95_ms: Option<u64>,
+    pub p99_ms: Option<u64>,
     pub visibility: Visibility,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub networks: Vec<String>,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -0,0 +1,311 @@
+use std::io::{self, Read};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::domain::{EngineKind, Scope};
+use crate::infra::compose::{
+    collect_docker_container_ids_by_label, collect_docker_container_ids_by_label_key,
+    collect_docker_container_ids_by_labels, collect_podman_container_ids_by_label,
+    collect_podman_container_ids_by_label_key, collect_podman_container_ids_by_labels,
+    strip_service_suffix,
+};
+use crate::infra::log_poll::{LogCapabilities, LogPoller};
+use crate::infra::process::{run_output, spawn_process_group, terminate_process};
+use crate::support::constants::SERVICE_LABEL;
+
+pub type LabelFilter<'a> = [(&'a str, Option<&'a str>)];
+
+pub const CONTAINER_EVENTS: &[&str] = &["create", "start", "die"];
+
+pub trait EngineBackend: Send + Sync {
+    fn container_ids(&self, labels: &LabelFilter<'_>, scope: Scope) -> Vec<String>;
+    fn inspect(&self, ids: &[String]) -> Vec<Value>;
+    fn inspect_images(&self, ids: &[String]) -> Vec<Value>;
+    fn service_name(&self, project_name: &str, cid: &str) -> String;
+    fn logs(&self, cid: &str, timestamps: bool, since: Option<i64>) -> io::Result<LogStream>;
+    fn events(&self, labels: &LabelFilter<'_>) -> io::Result<LogStream>;
+}
+
+pub struct LogStream {
+    pub stdout: Box<dyn Read + Send>,
+    pub stderr: Option<Box<dyn Read + Send>>,
+    pub handle: StreamHandle,
+}
+
+pub enum StreamHandle {
+    Process(Child),
+    Socket(UnixStream, Arc<AtomicBool>),
+    Poll(Arc<AtomicBool>),
+}
+
+impl StreamHandle {
+    pub fn stop(&mut self) {
+        match self {
+            Self::Process(child) => terminate_process(child, Duration::from_secs(5)),
+            Self::Socket(stream, _) => {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+            Self::Poll(stop) => stop.store(true, Ordering::SeqCst),
+        }
+    }
+
+    pub fn pid(&self) -> Option<u32> {
+        match self {
+            Self::Process(child) => Some(child.id()),
+            Self::Socket(..) | Self::Poll(_) => None,
+        }
+    }
+
+    pub fn is_running(&mut self) -> bool {
+        match self {
+            Self::Process(child) => child.try_wait().ok().flatten().is_none(),
+            Self::Socket(_, finished) | Self::Poll(finished) => !finished.load(Ordering::SeqCst),
+        }
+    }
+}
+
+pub struct EofReader<R> {
+    inner: R,
+    finished: Arc<AtomicBool>,
+}
+
+impl<R: Read> EofReader<R> {
+    pub const fn new(inner: R, finished: Arc<AtomicBool>) -> Self {
+        Self { inner, finished }
+    }
+}
+
+impl<R: Read> Read for EofReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.inner.read(buf);
+        let ended = match &result {
+            Ok(read) => *read == 0 && !buf.is_empty(),
+            Err(err) => err.kind() != io::ErrorKind::Interrupted,
+        };
+        if ended {
+            self.finished.store(true, Ordering::SeqCst);
+        }
+        result
+    }
+}
+
+pub struct CliBackend {
+    kind: EngineKind,
+    cmd: Vec<String>,
+    log_capabilities: OnceLock<LogCapabilities>,
+}
+
+impl CliBackend {
+    pub const fn new(kind: EngineKind, cmd: Vec<String>) -> Self {
+        Self {
+            kind,
+            cmd,
+            log_capabilities: OnceLock::new(),
+        }
+    }
+
+    fn log_capabilities(&self) -> LogCapabilities {
+        *self.log_capabilities.get_or_init(|| {
+            let capabilities = LogCapabilities::probe(&self.cmd);
+            if !capabilities.follow {
+                eprintln!(
+                    "[compose] engine does not support `logs --follow`; polling logs instead"
+                );
+            }
+            capabilities
+        })
+    }
+
+    fn poll_stream(
+        &self,
+        cid: &str,
+        timestamps: bool,
+        since: Option<i64>,
+    ) -> io::Result<LogStream> {
+        let (stdout, stdout_writer) = io::pipe()?;
+        let (stderr, stderr_writer) = io::pipe()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let poller = LogPoller {
+            cmd: self.cmd.clone(),
+            cid: cid.to_string(),
+            stop: stop.clone(),
+            capabilities: self.log_capabilities(),
+            timestamps,
+            since,
+        };
+        poller.spawn(stdout_writer, stderr_writer);
+        Ok(LogStream {
+            stdout: Box::new(stdout),
+            stderr: Some(Box::new(stderr)),
+            handle: StreamHandle::Poll(stop),
+        })
+    }
+
+    fn spawn_stream(&self, args: &[String]) -> io::Result<LogStream> {
+        let mut command = Command::new(self.cmd.first().map_or("docker", String::as_str));
+        command
+            .args(self.cmd.iter().skip(1))
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = spawn_process_group(&mut command)?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| io::Error::other("missing stdout pipe"))?;
+        let stderr = child.stderr.take();
+        Ok(LogStream {
+            stdout: Box::new(stdout),
+            stderr: stderr.map(|stderr| Box::new(stderr) as Box<dyn Read + Send>),
+            handle: StreamHandle::Process(child),
+        })
+    }
+}
+
+impl EngineBackend for CliBackend {
+    fn container_ids(&self, labels: &LabelFilter<'_>, scope: Scope) -> Vec<String> {
+        let podman = matches!(self.kind, EngineKind::Podman);
+        match labels {
+            [(key, None)] if podman => {
+                collect_podman_container_ids_by_label_key(&self.cmd, key, scope)
+            }
+            [(key, None)] => collect_docker_container_ids_by_label_key(&self.cmd, key, scope),
+            [(key, Some(value))] if podman => {
+                collect_podman_container_ids_by_label(&self.cmd, key, value, scope)
+            }
+            [(key, Some(value))] => {
+                collect_docker_container_ids_by_label(&self.cmd, key, value, scope)
+            }
+            _ => {
+                let pairs: Vec<(&str, &str)> = labels
+                    .iter()
+                    .filter_map(|(key, value)| value.map(|value| (*key, value)))
+                    .collect();
+                if podman {
+                    collect_podman_container_ids_by_labels(&self.cmd, &pairs, scope)
+                } else {
+                    collect_docker_container_ids_by_labels(&self.cmd, &pairs, scope)
+                }
+            }
+        }
+    }
+
+    fn inspect(&self, ids: &[String]) -> Vec<Value> {
+        if ids.is_empty() {
+            return Vec::new();
+        }
+        let mut cmd = self.cmd.clone();
+        cmd.push("inspect".to_string());
+        cmd.extend(ids.iter().cloned());
+        let Ok(output) = run_output(&cmd) else {
+            return Vec::new();
+        };
+        match serde_json::from_slice(&output.stdout) {
+            Ok(Value::Array(list)) => list,
+            _ => Vec::new(),
+        }
+    }
+
+    fn inspect_images(&self, ids: &[String]) -> Vec<Value> {
+        if ids.is_empty() {
+            return Vec::new();
+        }
+        let mut cmd = self.cmd.clone();
+        cmd.extend(["image".to_string(), "inspect".to_string()]);
+        cmd.extend(ids.iter().cloned());
+        let Ok(output) = run_output(&cmd) else {
+            return Vec::new();
+        };
+        match serde_json::from_slice(&output.stdout) {
+            Ok(Value::Array(list)) => list,
+            _ => Vec::new(),
+        }
+    }
+
+    fn service_name(&self, project_name: &str, cid: &str) -> String {
+        self.inspect(&[cid.to_string()])
+            .first()
+            .and_then(|item| service_name_from_inspect(item, self.kind, project_name))
+            .unwrap_or_else(|| cid.to_string())
+    }
+
+    fn logs(&self, cid: &str, timestamps: bool, since: Option<i64>) -> io::Result<LogStream> {
+        let capabilities = self.log_capabilities();
+        if !capabilities.follow {
+            return self.poll_stream(cid, timestamps, since);
+        }
+        let mut args = Vec::new();
+        if timestamps && capabilities.timestamps {
+            args.push("--timestamps".to_string());
+        }
+        if let Some(since) = since.filter(|_| capabilities.since) {
+            args.push("--since".to_string());
+            args.push(since.to_string());
+        }
+        let mut follow = vec!["logs".to_string(), "--follow".to_string()];
+        follow.extend(args);
+        follow.push(cid.to_string());
+        self.spawn_stream(&follow)
+    }
+
+    fn events(&self, labels: &LabelFilter<'_>) -> io::Result<LogStream> {
+        let (format, events) = match self.kind {
+            EngineKind::Podman => ("json", &[][..]),
+            EngineKind::Docker => ("{{json .}}", CONTAINER_EVENTS),
+        };
+        let filters = std::iter::once("type=container".to_string())
+            .chain(events.iter().map(|event| format!("event={event}")))
+            .chain(labels.iter().map(|(key, value)| {
+                value.map_or_else(
+                    || format!("label={key}"),
+                    |value| format!("label={key}={value}"),
+                )
+            }));
+        let mut args = vec!["events".to_string()];
+        for filter in filters {
+            args.push("--filter".to_string());
+            args.push(filter);
+        }
+        args.push("--format".to_string());
+        args.push(format.to_string());
+        self.spawn_stream(&args)
+    }
+}
+
+pub fn service_name_from_inspect(
+    item: &Value,
+    kind: EngineKind,
+    project_name: &str,
+) -> Option<String> {
+    let labels = item.get("Config").and_then(|config| config.get("Labels"));
+    let label_keys: &[&str] = match kind {
+        EngineKind::Podman => &["io.podman.compose.service", "com.docker.compose.service"],
+        EngineKind::Docker => &["com.docker.compose.service"],
+    };
+    if let Some(service) = label_keys
+        .iter()
+        .filter_map(|key| labels.and_then(|labels| labels.get(*key)))
+        .filter_map(Value::as_str)
+        .chain(
+            labels
+                .and_then(|labels| labels.get(SERVICE_LABEL))
+                .and_then(Value::as_str),
+        )
+        .find(|value| !value.is_empty())
+    {
+        return Some(service.to_string());
+    }
+    let name = item
+        .get("Name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .trim_start_matches('/');
+    let name = strip_service_suffix(name, project_name);
+    (!name.is_empty()).then_some(name)
+}
@@ -7,57 +7,99 @@ use crate::infra::process::{command_exists, run_output, run_status};
 pub struct ComposeSelection {
     pub compose_cmd: Vec<String>,
     pub engine: EngineKind,
+    pub legacy: bool,
 }
 
-pub fn detect_compose_cmd(
-    preferred_engine: Option<EngineKind>,
-) -> Result<ComposeSelection, String> {
-    if let Some(selection) = selection_from_env(preferred_engine)? {
+#[derive(Clone, Copy, Default)]
+pub struct ComposeProvider {
+    pub engine: Option<EngineKind>,
+    pub allow_legacy: bool,
+}
+
+pub fn detect_compose_cmd(provider: ComposeProvider) -> Result<ComposeSelection, String> {
+    let ComposeProvider {
+        engine: preferred_engine,
+        allow_legacy,
+    } = provider;
+    if let Some(selection) = selection_from_env(preferred_engine, allow_legacy)? {
         return Ok(selection);
     }
 
     match preferred_engine {
-        Some(EngineKind::Podman) => detect_podman_compose_cmd()
-            .map(|cmd| ComposeSelection {
-                compose_cmd: cmd,
-                engine: EngineKind::Podman,
-            })
+        Some(EngineKind::Podman) => detect_for_engine(EngineKind::Podman, allow_legacy)
             .ok_or_else(|| "Podman compose tool not found in PATH.".to_string()),
-        Some(EngineKind::Docker) => detect_docker_compose_cmd()
-            .map(|cmd| ComposeSelection {
-                compose_cmd: cmd,
-                engine: EngineKind::Docker,
-            })
+        Some(EngineKind::Docker) => detect_for_engine(EngineKind::Docker, allow_legacy)
             .ok_or_else(|| "Docker compose tool not found in PATH.".to_string()),
-        None => {
-            if let Some(cmd) = detect_podman_compose_cmd() {
-                return Ok(ComposeSelection {
-                    compose_cmd: cmd,
-                    engine: EngineKind::Podman,
-                });
-            }
-            if let Some(cmd) = detect_docker_compose_cmd() {
-                return Ok(ComposeSelection {
-                    compose_cmd: cmd,
-                    engine: EngineKind::Docker,
-                });
-            }
-            Err("No compose tool found in PATH.".to_string())
-        }
+        None => detect_for_engine(EngineKind::Podman, false)
+            .or_else(|| detect_for_engine(EngineKind::Docker, false))
+            .or_else(|| detect_legacy(EngineKind::Podman, allow_legacy))
+            .or_else(|| detect_legacy(EngineKind::Docker, allow_legacy))
+            .ok_or_else(|| "No compose tool found in PATH.".to_string()),
+    }
+}
+
+pub fn detect_other_engine(
+    provider: ComposeProvider,
+    primary: EngineKind,
+) -> Option<ComposeSelection> {
+    if provider.engine.is_some() || env::var_os("COMPOSE_CMD").is_some() {
+        return None;
     }
+    let other = match primary {
+        EngineKind::Podman => EngineKind::Docker,
+        EngineKind::Docker => EngineKind::Podman,
+    };
+    detect_for_engine(other, provider.allow_legacy)
+}
+
+fn detect_for_engine(engine: EngineKind, allow_legacy: bool) -> Option<ComposeSelection> {
+    let compose_cmd = match engine {
+        EngineKind::Podman => detect_podman_compose_cmd(),
+        EngineKind::Docker => detect_docker_compose_cmd(),
+    };
+    compose_cmd
+        .map(|compose_cmd| ComposeSelection {
+            compose_cmd,
+            engine,
+            legacy: false,
+        })
+        .or_else(|| detect_legacy(engine, allow_legacy))
+}
+
+fn detect_legacy(engine: EngineKind, allow_legacy: bool) -> Option<ComposeSelection> {
+    legacy_selection(engine, allow_legacy, command_exists)
+}
+
+fn legacy_selection(
+    engine: EngineKind,
+    allow_legacy: bool,
+    exists: fn(&str) -> bool,
+) -> Option<ComposeSelection> {
+    let tool = match engine {
+        EngineKind::Podman => "podman-compose",
+        EngineKind::Docker => "docker-compose",
+    };
+    (allow_legacy && exists(tool)).then(|| ComposeSelection {
+        compose_cmd: vec![tool.to_string()],
+        engine,
+        legacy: true,
+    })
 }
 
 fn selection_from_env(
     preferred_engine: Option<EngineKind>,
+    allow_legacy: bool,
 ) -> Result<Option<ComposeSelection>, String> {
     let Ok(env_cmd) = env::var("COMPOSE_CMD") else {
         return Ok(None);
     };
     match shell_words::split(&env_cmd) {
         Ok(cmd) if !cmd.is_empty() => {
-            if is_legacy_compose_cmd(&cmd) {
+            let legacy = is_legacy_compose_cmd(&cmd);
+            if legacy && !allow_legacy {
                 return Err(
-                    "COMPOSE_CMD must use `podman compose` or `docker compose`.".to_string()
+                    "COMPOSE_CMD must use `podman compose` or `docker compose` (or pass --allow-legacy-compose)."
+                        .to_string(),
                 );
             }
             let inferred = infer_engine_kind(&cmd);
@@ -72,6 +114,7 @@ fn selection_from_env(
             Ok(Some(ComposeSelection {
                 compose_cmd: cmd,
                 engine: preferred_engine.unwrap_or(inferred),
+                legacy,
             }))
         }
         _ => Err("COMPOSE_CMD is set but empty or invalid.".to_string()),
@@ -92,7 +135,27 @@ fn is_legacy_compose_cmd(cmd: &[String]) -> bool {
         .is_some_and(|value| value.contains("podman-compose") || value.contains("docker-compose"))
 }
 
-const fn display_engine(kind: EngineKind) -> &'static str {
+pub fn is_podman_compose_cmd(cmd: &[String]) -> bool {
+    cmd.first()
+        .is_some_and(|value| value.contains("podman-compose"))
+}
+
+pub fn legacy_project_args(
+    compose_cmd: &[String],
+    legacy: bool,
+    compose_args: &[String],
+) -> Vec<String> {
+    let in_pod_requested = compose_args
+        .iter()
+        .any(|arg| arg == "--in-pod" || arg.starts_with("--in-pod="));
+    if legacy && is_podman_compose_cmd(compose_cmd) && !in_pod_requested {
+        vec!["--in-pod".to_string(), "false".to_string()]
+    } else {
+        Vec::new()
+    }
+}
+
+pub const fn display_engine(kind: EngineKind) -> &'static str {
     match kind {
         EngineKind::Podman => "podman",
         EngineKind::Docker => "docker",
@@ -164,7 +227,7 @@ pub fn collect_podman_container_ids(
     for label in &labels {
         let mut cmd = base.clone();
         cmd.push("--filter".to_string());
-        cmd.push(label.to_string());
+        cmd.push(label.clone());
         cmd.push("-q".to_string());
         if let Ok(output) = run_output(&cmd) {
             let stdout = String::from_utf8_lossy(&output.stdout);
@@ -397,73 +460,7 @@ pub fn remove_project_pods(podman_cmd: &[String], project_name: &str) {
     let _ = run_output(&rm_cmd);
 }
 
-pub fn resolve_service_name_podman(podman_cmd: &[String], project_name: &str, cid: &str) -> String {
-    let label_keys = ["io.podman.compose.service", "com.docker.compose.service"];
-    for label in &label_keys {
-        let mut command = podman_cmd.to_vec();
-        command.push("inspect".to_string());
-        command.push("--format".to_string());
-        command.push(format!("{{{{ index .Config.Labels \"{label}\" }}}}"));
-        command.push(cid.to_string());
-        if let Ok(output) = run_output(&command) {
-            let candidate = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if !candidate.is_empty() && candidate != "<no value>" {
-                return candidate;
-            }
-        }
-    }
-    let mut command = podman_cmd.to_vec();
-    command.push("inspect".to_string());
-    command.push("--format".to_string());
-    command.push("{{ .Name }}".to_string());
-    command.push(cid.to_string());
-    if let Ok(output) = run_output(&command) {
-        let mut name = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        if name.starts_with('/') {
-            name.remove(0);
-        }
-        name = strip_service_suffix(&name, project_name);
-        if !name.is_empty() {
-            return name;
-        }
-    }
-    cid.to_string()
-}
-
-pub fn resolve_service_name_docker(docker_cmd: &[String], project_name: &str, cid: &str) -> String {
-    let label_keys = ["com.docker.compose.service"];
-    for label in &label_keys {
-        let mut command = docker_cmd.to_vec();
-        command.push("inspect".to_string());
-        command.push("--format".to_string());
-        command.push(format!("{{{{ index .Config.Labels \"{label}\" }}}}"));
-        command.push(cid.to_string());
-        if let Ok(output) = run_output(&command) {
-            let candidate = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if !candidate.is_empty() && candidate != "<no value>" {
-                return candidate;
-            }
-        }
-    }
-    let mut command = docker_cmd.to_vec();
-    command.push("inspect".to_string());
-    command.push("--format".to_string());
-    command.push("{{ .Name }}".to_string());
-    command.push(cid.to_string());
-    if let Ok(output) = run_output(&command) {
-        let mut name = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        if name.starts_with('/') {
-            name.remove(0);
-        }
-        name = strip_service_suffix(&name, project_name);
-        if !name.is_empty() {
-            return name;
-        }
-    }
-    cid.to_string()
-}
-
-fn strip_service_suffix(name: &str, project_name: &str) -> String {
+pub fn strip_service_suffix(name: &str, project_name: &str) -> String {
     let mut result = name.to_string();
     let prefix = format!("{project_name}_");
     if result.starts_with(&prefix) {
@@ -483,3 +480,39 @@ fn strip_service_suffix(name: &str, project_name: &str) -> String {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{legacy_project_args, legacy_selection};
+    use crate::domain::EngineKind;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn legacy_tools_are_only_used_when_allowed_and_installed() {
+        let installed = |tool: &str| tool == "podman-compose";
+        let selection = legacy_selection(EngineKind::Podman, true, installed);
+        assert!(selection.is_some_and(|selection| {
+            selection.legacy
+                && selection.engine == EngineKind::Podman
+                && selection.compose_cmd == strings(&["podman-compose"])
+        }));
+        assert!(legacy_selection(EngineKind::Podman, false, installed).is_none());
+        assert!(legacy_selection(EngineKind::Docker, true, installed).is_none());
+    }
+
+    #[test]
+    fn legacy_podman_compose_runs_outside_pods_unless_asked() {
+        let podman = strings(&["podman-compose"]);
+        let up = strings(&["up", "--no-start"]);
+        assert_eq!(
+            legacy_project_args(&podman, true, &up),
+            strings(&["--in-pod", "false"])
+        );
+        assert!(legacy_project_args(&podman, true, &strings(&["--in-pod=true", "up"])).is_empty());
+        assert!(legacy_project_args(&podman, false, &up).is_empty());
+        assert!(legacy_project_args(&strings(&["docker-compose"]), true, &up).is_empty());
+    }
+}
@@ -0,0 +1,258 @@
+use std::io::{self, BufRead, BufReader, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Once};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::infra::backend::{LogStream, StreamHandle, CONTAINER_EVENTS};
+use crate::infra::engine::Engine;
+
+const STOP_CHECK: Duration = Duration::from_millis(250);
+const RESYNC_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WatchMode {
+    Idle,
+    Events,
+    Polling,
+}
+
+struct WatchState {
+    generation: u64,
+    mode: WatchMode,
+}
+
+struct Shared {
+    state: Mutex<WatchState>,
+    changed: Condvar,
+    handle: Mutex<Option<StreamHandle>>,
+}
+
+impl Shared {
+    const fn new() -> Self {
+        Self {
+            state: Mutex::new(WatchState {
+                generation: 0,
+                mode: WatchMode::Idle,
+            }),
+            changed: Condvar::new(),
+            handle: Mutex::new(None),
+        }
+    }
+
+    fn bump(&self, mode: Option<WatchMode>) {
+        let mut state = lock(&self.state);
+        state.generation += 1;
+        if let Some(mode) = mode {
+            state.mode = mode;
+        }
+        drop(state);
+        self.changed.notify_all();
+    }
+
+    fn read_events(&self, stdout: Box<dyn Read + Send>) {
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if parse_container_event(&line).is_some() {
+                self.bump(None);
+            }
+        }
+        self.bump(Some(WatchMode::Polling));
+    }
+
+    fn wait(&self, seen: u64, poll: Duration, stop_event: &AtomicBool) -> u64 {
+        let mut state = lock(&self.state);
+        let timeout = if state.mode == WatchMode::Events {
+            RESYNC_INTERVAL.max(poll)
+        } else {
+            poll
+        };
+        let deadline = Instant::now() + timeout;
+        while state.generation == seen && !stop_event.load(Ordering::SeqCst) {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                break;
+            }
+            state = self
+                .changed
+                .wait_timeout(state, left.min(STOP_CHECK))
+                .map_or_else(|err| err.into_inner().0, |(guard, _)| guard);
+        }
+        state.generation
+    }
+}
+
+pub struct ContainerWatch {
+    engine: Engine,
+    run_id: String,
+    stop_event: Arc<AtomicBool>,
+    shared: Arc<Shared>,
+    started: Once,
+}
+
+impl ContainerWatch {
+    pub fn new(engine: Engine, run_id: String, stop_event: Arc<AtomicBool>) -> Arc<Self> {
+        Arc::new(Self {
+            engine,
+            run_id,
+            stop_event,
+            shared: Arc::new(Shared::new()),
+            started: Once::new(),
+        })
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.start();
+        lock(&self.shared.state).generation
+    }
+
+    pub fn wait(&self, seen: u64, poll: Duration) -> u64 {
+        self.start();
+        let generation = self.shared.wait(seen, poll, &self.stop_event);
+        if self.stop_event.load(Ordering::SeqCst) {
+            self.stop();
+        }
+        generation
+    }
+
+    pub fn stop(&self) {
+        let handle = lock(&self.shared.handle).take();
+        if let Some(mut handle) = handle {
+            handle.stop();
+        }
+    }
+
+    fn start(&self) {
+        self.started.call_once(|| {
+            let Ok(LogStream {
+                stdout,
+                stderr,
+                handle,
+            }) = self.engine.container_events(&self.run_id)
+            else {
+                self.shared.bump(Some(WatchMode::Polling));
+                return;
+            };
+            *lock(&self.shared.handle) = Some(handle);
+            self.shared.bump(Some(WatchMode::Events));
+            if let Some(mut stderr) = stderr {
+                thread::spawn(move || io::copy(&mut stderr, &mut io::sink()));
+            }
+            let shared = self.shared.clone();
+            thread::spawn(move || shared.read_events(stdout));
+        });
+    }
+}
+
+impl Drop for ContainerWatch {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ContainerEvent {
+    pub id: String,
+    pub action: String,
+}
+
+pub fn parse_container_event(line: &str) -> Option<ContainerEvent> {
+    let value: Value = serde_json::from_str(line.trim()).ok()?;
+    let field = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| value.get(*key).and_then(Value::as_str))
+            .map(str::to_string)
+    };
+    if field(&["Type", "type"]).as_deref() != Some("container") {
+        return None;
+    }
+    let action = field(&["Action", "status", "Status"])?;
+    let action = match action.split(':').next().unwrap_or_default().trim() {
+        "died" => "die",
+        action => action,
+    };
+    if !CONTAINER_EVENTS.contains(&action) {
+        return None;
+    }
+    let id = field(&["id", "ID"]).or_else(|| {
+        value
+            .get("Actor")
+            .and_then(|actor| actor.get("ID"))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    })?;
+    Some(ContainerEvent {
+        id,
+        action: action.to_string(),
+    })
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_container_event, ContainerEvent, Shared, WatchMode};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn parses_docker_and_podman_container_events() {
+        let docker = r#"{"status":"start","id":"abc123","Type":"container","Action":"start","Actor":{"ID":"abc123","Attributes":{"sanelens.run_id":"r1"}}}"#;
+        let podman =
+            r#"{"ID":"def456","Image":"nginx","Name":"web","Status":"died","Type":"container"}"#;
+        let podman_create = r#"{"ID":"def456","Status":"create","Type":"container"}"#;
+        let exec = r#"{"Type":"container","Action":"exec_start: sh","id":"abc123"}"#;
+        let health = r#"{"Type":"container","Action":"health_status: healthy","id":"abc123"}"#;
+        let network = r#"{"Type":"network","Action":"create","Actor":{"ID":"net1"}}"#;
+        assert_eq!(
+            parse_container_event(docker),
+            Some(ContainerEvent {
+                id: "abc123".to_string(),
+                action: "start".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_container_event(podman_create).map(|event| event.id),
+            Some("def456".to_string())
+        );
+        assert_eq!(
+            parse_container_event(podman).map(|event| event.action),
+            Some("die".to_string())
+        );
+        assert_eq!(parse_container_event(exec), None);
+        assert_eq!(parse_container_event(health), None);
+        assert_eq!(parse_container_event(network), None);
+        assert_eq!(parse_container_event("not json"), None);
+    }
+
+    #[test]
+    fn waiters_wake_on_events_and_time_out_when_polling() {
+        let shared = Arc::new(Shared::new());
+        let stop = AtomicBool::new(false);
+        shared.bump(Some(WatchMode::Events));
+        let notifier = shared.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            notifier.bump(None);
+        });
+        let started = Instant::now();
+        assert_eq!(shared.wait(1, Duration::from_millis(10), &stop), 2);
+        assert!(started.elapsed() < Duration::from_secs(2));
+        let _ = handle.join();
+
+        shared.bump(Some(WatchMode::Polling));
+        let started = Instant::now();
+        assert_eq!(shared.wait(3, Duration::from_millis(20), &stop), 3);
+        assert!(started.elapsed() >= Duration::from_millis(20));
+    }
+}
@@ -1,17 +1,26 @@
 use std::collections::{HashMap, HashSet};
 use std::env;
+use std::fmt::Write as _;
 use std::fs;
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
 use serde_yaml::{Mapping, Value};
 
-use crate::support::args::extract_compose_global_args;
+use crate::infra::effective::{write_effective_config, EFFECTIVE_CONFIG_FILE};
+use crate::infra::envoy::{apply_envoy_overrides, read_envoy_overrides};
+use crate::infra::explain::explain_rewrites;
+use crate::infra::proxy::ProxyPolicy;
+use crate::support::args::{extract_compose_global_args, parse_byte_size};
 use crate::support::constants::{
-    COMPOSE_FILE_LABEL, DERIVED_COMPOSE_LABEL, PROJECT_NAME_LABEL, RUN_ID_LABEL, SERVICE_LABEL,
-    STARTED_AT_LABEL,
+    COMPOSE_FILE_LABEL, DERIVED_COMPOSE_LABEL, ENVOY_ADMIN_PORT, MAX_BODY_BYTES_LABEL,
+    PROJECT_NAME_LABEL, PROXY_NAME_LABEL, PROXY_PORTS_LABEL, PROXY_PROTOCOLS_LABEL, RUN_ID_LABEL,
+    SERVICE_LABEL, STARTED_AT_LABEL, TAG_LABEL, TRAFFIC_IGNORE_LABEL, UPSTREAM_LABEL,
 };
 
+const STDIN_COMPOSE_FILE: &str = "compose.stdin.yaml";
+
 #[derive(Clone)]
 pub struct DerivedCompose {
     pub path: PathBuf,
@@ -19,6 +28,7 @@ pub struct DerivedCompose {
     pub proxy_services: HashSet<String>,
     pub app_service_map: HashMap<String, String>,
     pub egress_proxy: Option<String>,
+    pub rewrites: Vec<String>,
 }
 
 #[derive(Clone)]
@@ -26,19 +36,148 @@ pub struct DerivedCompose {
 pub struct DeriveConfig {
     pub run_id: String,
     pub run_started_at: String,
+    pub tag: Option<String>,
     pub envoy_image: String,
     pub enable_traffic: bool,
     pub enable_egress: bool,
+    pub egress_transparent: bool,
+    pub egress_init_image: String,
     pub compose_cmd: Vec<String>,
     pub compose_args: Vec<String>,
     pub compose_file_from_args: bool,
     pub disable_pods: bool,
+    pub max_body_bytes: usize,
+    pub skip_missing_secrets: bool,
+    pub rootless_compat: bool,
+    pub env_overrides: Vec<EnvOverride>,
+    pub resource_limits: Vec<ResourceLimit>,
+    pub platform: Option<String>,
+    pub base_dir: Option<PathBuf>,
+    pub run_root: Option<PathBuf>,
+    pub explain: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnvOverride {
+    pub service: String,
+    pub key: String,
+    pub value: String,
+}
+
+impl EnvOverride {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid --set-env '{spec}'. Use service:KEY=VALUE.");
+        let (service, assignment) = spec.split_once(':').ok_or_else(invalid)?;
+        let (key, value) = assignment.split_once('=').ok_or_else(invalid)?;
+        if service.trim().is_empty() || key.trim().is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            service: service.trim().to_string(),
+            key: key.trim().to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+pub const ALL_SERVICES: &str = "*";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourceLimit {
+    pub service: String,
+    pub cpus: Option<f64>,
+    pub memory: Option<u64>,
+}
+
+impl ResourceLimit {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "Invalid --limit '{spec}'. Use service=cpu:0.5,mem:512m (or *= for every service)."
+            )
+        };
+        let (service, values) = spec.split_once('=').ok_or_else(invalid)?;
+        let mut limit = Self {
+            service: service.trim().to_string(),
+            cpus: None,
+            memory: None,
+        };
+        if limit.service.is_empty() {
+            return Err(invalid());
+        }
+        for pair in values
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+        {
+            let (key, value) = pair.split_once(':').ok_or_else(invalid)?;
+            match key.trim().to_ascii_lowercase().as_str() {
+                "cpu" | "cpus" => {
+                    let cpus = value.trim().parse::<f64>().ok();
+                    limit.cpus = Some(cpus.filter(|cpus| *cpus > 0.0).ok_or_else(invalid)?);
+                }
+                "mem" | "memory" => {
+                    let memory = parse_byte_size(value).filter(|bytes| *bytes > 0);
+                    limit.memory = Some(memory.ok_or_else(invalid)?);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        if limit.cpus.is_none() && limit.memory.is_none() {
+            return Err(invalid());
+        }
+        Ok(limit)
+    }
+}
+
+struct EnvoyTuning<'a> {
+    overrides: Option<&'a Mapping>,
+    max_body_bytes: usize,
+    dual_stack: bool,
+    upstream: UpstreamProtocol,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum UpstreamProtocol {
+    Auto,
+    Http1,
+    H2c,
+}
+
+impl UpstreamProtocol {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "http1" | "http/1.1" => Some(Self::Http1),
+            "h2c" | "http2" | "grpc" => Some(Self::H2c),
+            _ => None,
+        }
+    }
+
+    const fn http_config(self) -> &'static str {
+        match self {
+            Self::Auto => "use_downstream_protocol_config:\n          http_protocol_options: {}\n          http2_protocol_options: {}\n",
+            Self::Http1 => "explicit_http_config:\n          http_protocol_options: {}\n",
+            Self::H2c => "explicit_http_config:\n          http2_protocol_options: {}\n",
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum ProxyProtocol {
     Http,
     Tcp,
+    Sniff,
+}
+
+impl ProxyProtocol {
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Http => "http",
+            Self::Tcp => "tcp",
+            Self::Sniff => "sniff",
+        }
+    }
 }
 
 struct RunLabelContext<'a> {
@@ -47,6 +186,7 @@ struct RunLabelContext<'a> {
     derived_compose: &'a str,
     started_at: &'a str,
     project_name: &'a str,
+    tag: Option<&'a str>,
 }
 
 #[allow(clippy::cognitive_complexity, clippy::too_many_lines)]
@@ -58,10 +198,16 @@ pub fn derive_compose(
     let compose_path = to_absolute_path(compose_file)
         .map_err(|err| format!("failed to resolve compose path: {err}"))?;
     let mut doc = load_compose_doc(&compose_path, project_name, config)?;
+    let original = config.explain.then(|| doc.clone());
     set_compose_name(&mut doc, project_name);
-    let compose_dir = compose_path.parent().unwrap_or_else(|| Path::new("."));
-    let out_dir = compose_dir.join(".sanelens").join(project_name);
+    let compose_dir = config
+        .base_dir
+        .as_deref()
+        .or_else(|| compose_path.parent())
+        .unwrap_or_else(|| Path::new("."));
+    let out_dir = run_dir(config.run_root.as_deref(), compose_dir, project_name);
     fs::create_dir_all(&out_dir).map_err(|err| format!("failed to create derived dir: {err}"))?;
+    write_effective_config(&out_dir.join(EFFECTIVE_CONFIG_FILE), &compose_path, &doc);
     let compose_file_label = compose_path.to_string_lossy().into_owned();
     let derived_path = out_dir.join("compose.derived.yaml");
     let derived_compose_label = derived_path.to_string_lossy().into_owned();
@@ -71,9 +217,11 @@ pub fn derive_compose(
         derived_compose: &derived_compose_label,
         started_at: &config.run_started_at,
         project_name,
+        tag: config.tag.as_deref(),
     };
 
     rewrite_top_level_paths(&mut doc, compose_dir);
+    check_file_entries(&mut doc, config.skip_missing_secrets)?;
     if config.disable_pods {
         disable_podman_pods(&mut doc);
     }
@@ -89,9 +237,13 @@ pub fn derive_compose(
         Vec::new()
     };
 
+    let proxy_policy = ProxyPolicy::from_doc(&doc)?;
     let Some(Value::Mapping(services)) = doc.get_mut("services") else {
         return Err("compose file missing services".to_string());
     };
+    apply_env_overrides(services, &config.env_overrides)?;
+    apply_resource_limits(services, &config.resource_limits)?;
+    apply_platform(services, config.platform.as_deref());
 
     if !config.enable_traffic {
         for (name, service_value) in services.iter_mut() {
@@ -104,6 +256,10 @@ pub fn derive_compose(
             rewrite_service_paths(service, compose_dir);
             add_run_labels(service, service_name, &run_labels);
         }
+        label_run_resources(&mut doc, &config.run_id);
+        let rewrites = original
+            .map(|original| explain_rewrites(&original, &doc, &HashMap::new()))
+            .unwrap_or_default();
         let payload = serde_yaml::to_string(&doc)
             .map_err(|err| format!("serialize compose failed: {err}"))?;
         fs::write(&derived_path, payload)
@@ -114,6 +270,7 @@ pub fn derive_compose(
             proxy_services: HashSet::new(),
             app_service_map: HashMap::new(),
             egress_proxy: None,
+            rewrites,
         });
     }
 
@@ -170,7 +327,7 @@ pub fn derive_compose(
             new_services.insert(key, Value::Mapping(service));
             continue;
         }
-        let protocol_override = read_proxy_protocol(&service);
+        let protocol_override = proxy_policy.resolve(&name, read_proxy_protocol(&service));
         if protocol_override == Some("off".to_string()) {
             if config.enable_egress {
                 ensure_env_var(
@@ -190,12 +347,13 @@ pub fn derive_compose(
             continue;
         }
 
+        let proxied = select_proxied_ports(&name, &service, &ports);
         let mut port_modes = Vec::new();
-        for port in &ports {
+        for port in &proxied {
             let mode = match protocol_override.as_deref() {
                 Some("http") => ProxyProtocol::Http,
                 Some("tcp") => ProxyProtocol::Tcp,
-                Some("auto" | "true") | None => guess_protocol(*port),
+                Some("auto" | "true" | "on") | None => guess_protocol(*port),
                 Some(other) => {
                     eprintln!("[compose] unknown sanelens.proxy value '{other}' on {name}");
                     guess_protocol(*port)
@@ -203,6 +361,12 @@ pub fn derive_compose(
             };
             port_modes.push((*port, mode));
         }
+        let protocols: Vec<String> = port_modes
+            .iter()
+            .map(|(port, mode)| format!("{port}/{}", mode.label()))
+            .collect();
+        let envoy_overrides = read_envoy_overrides(&name, &service, compose_dir)?;
+        let max_body_bytes = read_max_body_bytes(&name, &service, config.max_body_bytes);
 
         let app_name = format!("{name}-app");
         app_service_map.insert(app_name.clone(), name.clone());
@@ -211,9 +375,20 @@ pub fn derive_compose(
         let original_ports = service.remove(Value::String("ports".to_string()));
         let original_expose = service.remove(Value::String("expose".to_string()));
         let original_container_name = service.remove(Value::String("container_name".to_string()));
+        let (proxy_ports, direct_ports) =
+            partition_port_entries(original_ports.clone(), &proxied, port_entry_port);
+        let (proxy_expose, _) =
+            partition_port_entries(original_expose.clone(), &proxied, value_to_u16);
 
         let mut app_service = service.clone();
+        if config.rootless_compat {
+            warn_privileged_ports(&name, original_ports.as_ref());
+            ensure_host_gateway(&mut app_service);
+        }
         ensure_expose_ports(&mut app_service, &ports, original_expose.as_ref());
+        if let Some(direct_ports) = direct_ports {
+            app_service.insert(Value::String("ports".to_string()), direct_ports);
+        }
         add_label(&mut app_service, "sanelens.app", "true");
         add_label(&mut app_service, "sanelens.app.name", &name);
         add_run_labels(&mut app_service, &name, &run_labels);
@@ -239,6 +414,9 @@ pub fn derive_compose(
         if config.disable_pods {
             add_envoy_entrypoint(&mut proxy_service);
         }
+        if config.rootless_compat {
+            ensure_host_gateway(&mut proxy_service);
+        }
         if let Some(restart) = service.get(Value::String("restart".to_string())) {
             proxy_service.insert(Value::String("restart".to_string()), restart.clone());
         }
@@ -247,13 +425,14 @@ pub fn derive_compose(
         }
         let depends = build_proxy_depends_on(&app_name);
         proxy_service.insert(Value::String("depends_on".to_string()), depends);
-        if let Some(ports_value) = original_ports.clone() {
-            proxy_service.insert(Value::String("ports".to_string()), ports_value);
-        }
+        proxy_service.insert(
+            Value::String("ports".to_string()),
+            with_admin_port(proxy_ports),
+        );
         if let Some(container_name) = original_container_name {
             proxy_service.insert(Value::String("container_name".to_string()), container_name);
         }
-        let expose_value = build_expose_value(&ports, original_expose.as_ref());
+        let expose_value = build_expose_value(&proxied, proxy_expose.as_ref());
         if let Some(expose) = expose_value {
             proxy_service.insert(Value::String("expose".to_string()), expose);
         }
@@ -269,11 +448,35 @@ pub fn derive_compose(
         ]);
         proxy_service.insert(Value::String("volumes".to_string()), volumes_value);
         add_label(&mut proxy_service, "sanelens.proxy", "true");
-        add_label(&mut proxy_service, "sanelens.proxy.name", &name);
+        add_label(&mut proxy_service, PROXY_NAME_LABEL, &name);
+        add_label(
+            &mut proxy_service,
+            PROXY_PROTOCOLS_LABEL,
+            &protocols.join(","),
+        );
+        add_label(
+            &mut proxy_service,
+            MAX_BODY_BYTES_LABEL,
+            &max_body_bytes.to_string(),
+        );
+        if let Some(ignore) = read_label(&service, TRAFFIC_IGNORE_LABEL) {
+            add_label(&mut proxy_service, TRAFFIC_IGNORE_LABEL, &ignore);
+        }
         add_run_labels(&mut proxy_service, &name, &run_labels);
 
-        write_envoy_config(&envoy_dir, &name, &app_name, &port_modes)
-            .map_err(|err| format!("failed to write envoy config: {err}"))?;
+        write_envoy_config(
+            &envoy_dir,
+            &name,
+            &app_name,
+            &port_modes,
+            &EnvoyTuning {
+                overrides: envoy_overrides.as_ref(),
+                max_body_bytes,
+                dual_stack: config.rootless_compat,
+                upstream: read_upstream_protocol(&name, &service),
+            },
+        )
+        .map_err(|err| format!("failed to write envoy config: {err}"))?;
 
         new_services.insert(Value::String(name.clone()), Value::Mapping(proxy_service));
         new_services.insert(Value::String(app_name), Value::Mapping(app_service));
@@ -293,13 +496,30 @@ pub fn derive_compose(
             Some(&tap_service_dir),
         );
         if let Value::Mapping(map) = &mut egress_config {
+            add_label(
+                map,
+                MAX_BODY_BYTES_LABEL,
+                &config.max_body_bytes.to_string(),
+            );
             add_run_labels(map, &egress_name, &run_labels);
         }
         let egress_envoy = envoy_dir.join("egress.yaml");
-        write_egress_envoy_config(&egress_envoy)
-            .map_err(|err| format!("failed to write egress envoy config: {err}"))?;
+        write_egress_envoy_config(
+            &egress_envoy,
+            config.egress_transparent,
+            config.max_body_bytes,
+        )
+        .map_err(|err| format!("failed to write egress envoy config: {err}"))?;
         new_services.insert(Value::String(egress_name.clone()), egress_config);
         proxy_services.insert(egress_name);
+        if config.egress_transparent {
+            add_egress_init_services(
+                &mut new_services,
+                &proxy_services,
+                &config.egress_init_image,
+                &run_labels,
+            );
+        }
     }
 
     for (_, value) in &mut new_services {
@@ -309,7 +529,12 @@ pub fn derive_compose(
         rewrite_depends_on_for_proxies(service, &proxy_app_map);
     }
 
+    check_port_collisions(&new_services)?;
     *services = new_services;
+    label_run_resources(&mut doc, &config.run_id);
+    let rewrites = original
+        .map(|original| explain_rewrites(&original, &doc, &app_service_map))
+        .unwrap_or_default();
 
     let payload =
         serde_yaml::to_string(&doc).map_err(|err| format!("serialize compose failed: {err}"))?;
@@ -326,6 +551,7 @@ pub fn derive_compose(
         } else {
             None
         },
+        rewrites,
     })
 }
 
@@ -345,10 +571,20 @@ fn load_compose_doc(
         args.push("-f".to_string());
         args.push(compose_path.to_string_lossy().into_owned());
     }
+    let has_project_dir = args
+        .iter()
+        .any(|arg| arg == "--project-directory" || arg.starts_with("--project-directory="));
+    if let Some(base_dir) = config.base_dir.as_ref().filter(|_| !has_project_dir) {
+        args.push("--project-directory".to_string());
+        args.push(base_dir.to_string_lossy().into_owned());
+    }
     cmd.extend(args);
     cmd.push("config".to_string());
+    run_compose_config(&cmd)
+}
 
-    let output = run_compose_output(&cmd).map_err(|err| format!("compose config failed: {err}"))?;
+pub fn run_compose_config(cmd: &[String]) -> Result<Value, String> {
+    let output = run_compose_output(cmd).map_err(|err| format!("compose config failed: {err}"))?;
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         let stderr = stderr.trim();
@@ -425,6 +661,15 @@ fn is_service_healthy_condition(value: &Value) -> bool {
         .is_some_and(|condition| condition == "service_healthy")
 }
 
+fn with_admin_port(ports: Option<Value>) -> Value {
+    let mut entries = match ports {
+        Some(Value::Sequence(entries)) => entries,
+        _ => Vec::new(),
+    };
+    entries.push(Value::String(format!("127.0.0.1::{ENVOY_ADMIN_PORT}")));
+    Value::Sequence(entries)
+}
+
 fn build_expose_value(ports: &[u16], original: Option<&Value>) -> Option<Value> {
     let mut items: Vec<Value> = Vec::new();
     for port in ports {
@@ -450,22 +695,42 @@ fn ensure_expose_ports(service: &mut Mapping, ports: &[u16], original_expose: Op
 }
 
 fn read_proxy_protocol(service: &Mapping) -> Option<String> {
+    read_label(service, "sanelens.proxy").map(|value| value.to_lowercase())
+}
+
+fn read_max_body_bytes(name: &str, service: &Mapping, default: usize) -> usize {
+    let Some(value) = read_label(service, MAX_BODY_BYTES_LABEL) else {
+        return default;
+    };
+    match parse_byte_size(&value).and_then(|bytes| usize::try_from(bytes).ok()) {
+        Some(bytes) if bytes > 0 => bytes,
+        _ => {
+            eprintln!("[compose] invalid {MAX_BODY_BYTES_LABEL} value '{value}' on {name}");
+            default
+        }
+    }
+}
+
+fn read_upstream_protocol(name: &str, service: &Mapping) -> UpstreamProtocol {
+    let Some(value) = read_label(service, UPSTREAM_LABEL) else {
+        return UpstreamProtocol::Auto;
+    };
+    UpstreamProtocol::parse(&value).unwrap_or_else(|| {
+        eprintln!("[compose] unknown {UPSTREAM_LABEL} value '{value}' on {name}; using auto");
+        UpstreamProtocol::Auto
+    })
+}
+
+fn read_label(service: &Mapping, key: &str) -> Option<String> {
     let labels = service.get(Value::String("labels".to_string()));
-    let key = "sanelens.proxy";
     match labels {
-        Some(Value::Sequence(list)) => {
-            list.iter()
-                .filter_map(|entry| entry.as_str())
-                .find_map(|entry| {
-                    entry
-                        .strip_prefix(&format!("{key}="))
-                        .map(str::to_lowercase)
-                })
-        }
+        Some(Value::Sequence(list)) => list
+            .iter()
+            .filter_map(|entry| entry.as_str())
+            .find_map(|entry| entry.strip_prefix(&format!("{key}=")).map(str::to_string)),
         Some(Value::Mapping(map)) => map
             .get(Value::String(key.to_string()))
-            .and_then(|value| value.as_str())
-            .map(str::to_lowercase),
+            .map(label_value_string),
         _ => None,
     }
 }
@@ -537,6 +802,9 @@ fn add_run_labels(service: &mut Mapping, service_name: &str, labels: &RunLabelCo
     add_label(service, DERIVED_COMPOSE_LABEL, labels.derived_compose);
     add_label(service, STARTED_AT_LABEL, labels.started_at);
     add_label(service, PROJECT_NAME_LABEL, labels.project_name);
+    if let Some(tag) = labels.tag {
+        add_label(service, TAG_LABEL, tag);
+    }
 }
 
 fn ensure_env_var(service: &mut Mapping, key: &str, value: &str) {
@@ -567,6 +835,112 @@ fn ensure_env_var(service: &mut Mapping, key: &str, value: &str) {
     }
 }
 
+fn apply_env_overrides(services: &mut Mapping, overrides: &[EnvOverride]) -> Result<(), String> {
+    for entry in overrides {
+        let Some(Value::Mapping(service)) = services.get_mut(entry.service.as_str()) else {
+            return Err(format!(
+                "--set-env targets unknown service '{}'",
+                entry.service
+            ));
+        };
+        set_env_var(service, &entry.key, &entry.value);
+    }
+    Ok(())
+}
+
+fn apply_resource_limits(services: &mut Mapping, limits: &[ResourceLimit]) -> Result<(), String> {
+    if let Some(unknown) = limits.iter().find(|limit| {
+        limit.service != ALL_SERVICES && !services.contains_key(limit.service.as_str())
+    }) {
+        return Err(format!(
+            "--limit targets unknown service '{}'",
+            unknown.service
+        ));
+    }
+    let defaults = limits.iter().filter(|limit| limit.service == ALL_SERVICES);
+    for (name, service) in services.iter_mut() {
+        let Value::Mapping(service) = service else {
+            continue;
+        };
+        let own = limits
+            .iter()
+            .filter(|limit| name.as_str() == Some(limit.service.as_str()));
+        for limit in defaults.clone().chain(own) {
+            set_resource_limit(service, limit);
+        }
+    }
+    Ok(())
+}
+
+fn apply_platform(services: &mut Mapping, platform: Option<&str>) {
+    let Some(platform) = platform else {
+        return;
+    };
+    let key = Value::String("platform".to_string());
+    for service in services.values_mut().filter_map(Value::as_mapping_mut) {
+        if !service.contains_key(&key) {
+            service.insert(key.clone(), Value::String(platform.to_string()));
+        }
+    }
+}
+
+fn set_resource_limit(service: &mut Mapping, limit: &ResourceLimit) {
+    let mut resolved = Vec::new();
+    if let Some(cpus) = limit.cpus {
+        resolved.push(("cpus", "cpus", Value::String(cpus.to_string())));
+    }
+    if let Some(memory) = limit.memory {
+        resolved.push(("memory", "mem_limit", Value::Number(memory.into())));
+    }
+    let mut deploy = take_mapping(service, "deploy");
+    let mut resources = take_mapping(&mut deploy, "resources");
+    let mut limits = take_mapping(&mut resources, "limits");
+    for (key, _, value) in &resolved {
+        limits.insert(Value::String((*key).to_string()), value.clone());
+    }
+    resources.insert(Value::String("limits".to_string()), Value::Mapping(limits));
+    deploy.insert(
+        Value::String("resources".to_string()),
+        Value::Mapping(resources),
+    );
+    service.insert(Value::String("deploy".to_string()), Value::Mapping(deploy));
+    for (_, legacy, value) in resolved {
+        if let Some(existing) = service.get_mut(legacy) {
+            *existing = value;
+        }
+    }
+}
+
+fn take_mapping(map: &mut Mapping, key: &str) -> Mapping {
+    match map.remove(key) {
+        Some(Value::Mapping(entry)) => entry,
+        _ => Mapping::new(),
+    }
+}
+
+fn set_env_var(service: &mut Mapping, key: &str, value: &str) {
+    let env_key = Value::String("environment".to_string());
+    if let Some(Value::Sequence(list)) = service.get_mut(&env_key) {
+        let prefix = format!("{key}=");
+        list.retain(|entry| {
+            entry
+                .as_str()
+                .is_none_or(|item| item != key && !item.starts_with(&prefix))
+        });
+        list.push(Value::String(format!("{key}={value}")));
+        return;
+    }
+    if !matches!(service.get(&env_key), Some(Value::Mapping(_))) {
+        service.insert(env_key.clone(), Value::Mapping(Mapping::new()));
+    }
+    if let Some(Value::Mapping(map)) = service.get_mut(&env_key) {
+        map.insert(
+            Value::String(key.to_string()),
+            Value::String(value.to_string()),
+        );
+    }
+}
+
 fn merge_env_var(service: &mut Mapping, key: &str, value: &str) {
     let env_key = Value::String("environment".to_string());
     match service.get_mut(&env_key) {
@@ -620,18 +994,7 @@ fn get_string(map: &Mapping, key: &str) -> Option<String> {
 fn extract_ports(service: &Mapping) -> Vec<u16> {
     let mut ports = Vec::new();
     if let Some(Value::Sequence(entries)) = service.get(Value::String("ports".to_string())) {
-        for entry in entries {
-            let port = match entry {
-                Value::String(value) => parse_container_port(value),
-                Value::Mapping(map) => map
-                    .get(Value::String("target".to_string()))
-                    .and_then(value_to_u16),
-                _ => None,
-            };
-            if let Some(port) = port {
-                ports.push(port);
-            }
-        }
+        ports.extend(entries.iter().filter_map(port_entry_port));
     }
     if let Some(Value::Sequence(entries)) = service.get(Value::String("expose".to_string())) {
         for entry in entries {
@@ -645,7 +1008,72 @@ fn extract_ports(service: &Mapping) -> Vec<u16> {
     ports
 }
 
-fn value_to_u16(value: &Value) -> Option<u16> {
+fn port_entry_port(entry: &Value) -> Option<u16> {
+    match entry {
+        Value::String(value) => parse_container_port(value),
+        Value::Mapping(map) => map
+            .get(Value::String("target".to_string()))
+            .and_then(value_to_u16),
+        _ => None,
+    }
+}
+
+pub fn parse_proxy_ports(value: &str) -> Result<Vec<u16>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            parse_port_token(entry)
+                .ok_or_else(|| format!("{PROXY_PORTS_LABEL} entry '{entry}' is not a port number"))
+        })
+        .collect()
+}
+
+fn select_proxied_ports(name: &str, service: &Mapping, ports: &[u16]) -> Vec<u16> {
+    let Some(value) = read_label(service, PROXY_PORTS_LABEL) else {
+        return ports.to_vec();
+    };
+    let listed = match parse_proxy_ports(&value) {
+        Ok(listed) => listed,
+        Err(err) => {
+            eprintln!("[compose] {err} on {name}; proxying every port");
+            return ports.to_vec();
+        }
+    };
+    for port in listed.iter().filter(|port| !ports.contains(port)) {
+        eprintln!("[compose] {PROXY_PORTS_LABEL} lists port {port}, which {name} does not expose");
+    }
+    let proxied: Vec<u16> = ports
+        .iter()
+        .copied()
+        .filter(|port| listed.contains(port))
+        .collect();
+    if proxied.is_empty() {
+        eprintln!(
+            "[compose] {PROXY_PORTS_LABEL} on {name} matches no exposed port; proxying every port"
+        );
+        return ports.to_vec();
+    }
+    proxied
+}
+
+fn partition_port_entries(
+    value: Option<Value>,
+    proxied: &[u16],
+    port_of: fn(&Value) -> Option<u16>,
+) -> (Option<Value>, Option<Value>) {
+    let Some(Value::Sequence(entries)) = value else {
+        return (value, None);
+    };
+    let (direct, proxy): (Vec<Value>, Vec<Value>) = entries
+        .into_iter()
+        .partition(|entry| port_of(entry).is_some_and(|port| !proxied.contains(&port)));
+    let sequence = |items: Vec<Value>| (!items.is_empty()).then_some(Value::Sequence(items));
+    (sequence(proxy), sequence(direct))
+}
+
+pub fn value_to_u16(value: &Value) -> Option<u16> {
     match value {
         Value::Number(num) => num.as_u64().and_then(|v| u16::try_from(v).ok()),
         Value::String(value) => {
@@ -656,7 +1084,7 @@ fn value_to_u16(value: &Value) -> Option<u16> {
     }
 }
 
-fn parse_container_port(entry: &str) -> Option<u16> {
+pub fn parse_container_port(entry: &str) -> Option<u16> {
     let entry = entry.split('/').next().unwrap_or(entry).trim();
     if entry.is_empty() {
         return None;
@@ -726,17 +1154,299 @@ fn guess_protocol(port: u16) -> ProxyProtocol {
     const HTTP_PORTS: [u16; 12] = [
         80, 443, 3000, 3001, 3002, 5173, 8000, 8080, 8100, 9000, 10000, 15672,
     ];
+    const SERVER_FIRST_PORTS: [u16; 10] = [21, 22, 23, 25, 110, 143, 587, 2525, 3306, 5900];
     if HTTP_PORTS.contains(&port) {
         ProxyProtocol::Http
-    } else {
+    } else if SERVER_FIRST_PORTS.contains(&port) {
         ProxyProtocol::Tcp
+    } else {
+        ProxyProtocol::Sniff
     }
 }
 
 #[cfg(test)]
 #[allow(clippy::literal_string_with_formatting_args)]
 mod tests {
-    use super::parse_container_port;
+    use std::collections::HashSet;
+
+    use super::{
+        add_egress_init_services, apply_env_overrides, apply_platform, apply_resource_limits,
+        check_file_entries, check_port_collisions, cluster_block, dual_stack_listeners,
+        ensure_host_gateway, guess_protocol, http_listener_block, label_run_resources,
+        parse_container_port, partition_port_entries, port_entry_port, select_proxied_ports,
+        sniff_listener_block, transparent_egress_config, with_admin_port, EnvOverride,
+        ProxyProtocol, ResourceLimit, RunLabelContext, UpstreamProtocol,
+    };
+    use crate::support::constants::DEFAULT_MAX_BODY_BYTES;
+    use serde_yaml::Value;
+
+    #[test]
+    fn server_first_ports_skip_the_sniffer() {
+        assert!(matches!(guess_protocol(8080), ProxyProtocol::Http));
+        assert!(matches!(guess_protocol(3306), ProxyProtocol::Tcp));
+        assert!(matches!(guess_protocol(22), ProxyProtocol::Tcp));
+        assert!(matches!(guess_protocol(587), ProxyProtocol::Tcp));
+        assert!(matches!(guess_protocol(7345), ProxyProtocol::Sniff));
+    }
+
+    #[test]
+    fn egress_init_sidecars_join_each_workload_network() {
+        let mut services: serde_yaml::Mapping = serde_yaml::from_str(
+            "api: {}\nworker:\n  network_mode: host\napi-proxy: {}\nsanelens-egress-proxy: {}\n",
+        )
+        .unwrap_or_default();
+        let proxies: HashSet<String> = ["api-proxy", "sanelens-egress-proxy"]
+            .into_iter()
+            .map(ToString::to_string)
+            .collect();
+        let labels = RunLabelContext {
+            run_id: "run-1",
+            compose_file: "compose.yaml",
+            derived_compose: "derived.yaml",
+            started_at: "now",
+            project_name: "sanelens-run-1",
+            tag: None,
+        };
+        add_egress_init_services(&mut services, &proxies, "alpine:3.20", &labels);
+        let inits: Vec<&str> = services
+            .iter()
+            .filter_map(|(name, _)| name.as_str())
+            .filter(|name| name.ends_with("-egress-init"))
+            .collect();
+        assert_eq!(inits, vec!["api-egress-init"]);
+        let init = services.get("api-egress-init");
+        let field = |key: &str| init.and_then(|init| init.get(key)).cloned();
+        assert_eq!(
+            field("network_mode").as_ref().and_then(Value::as_str),
+            Some("service:api")
+        );
+        let expected = |yaml: &str| serde_yaml::from_str::<Value>(yaml).ok();
+        assert_eq!(field("cap_add"), expected("[NET_ADMIN, NET_RAW]"));
+        assert_eq!(
+            field("depends_on"),
+            expected("[api, sanelens-egress-proxy]")
+        );
+        let labels = field("labels");
+        let has_label = |label: &str| {
+            labels
+                .as_ref()
+                .and_then(Value::as_sequence)
+                .is_some_and(|labels| labels.iter().any(|value| value.as_str() == Some(label)))
+        };
+        assert!(has_label("sanelens.egress.init=true"));
+        assert!(has_label("sanelens.run_id=run-1"));
+    }
+
+    #[test]
+    fn labels_run_networks_and_volumes() {
+        let mut doc: Value = serde_yaml::from_str(
+            "services: {}\nnetworks:\n  shared:\n    external: true\nvolumes:\n  data:\n",
+        )
+        .unwrap_or_default();
+        label_run_resources(&mut doc, "run-1");
+        let label = |section: &str, name: &str| {
+            doc.get(section)
+                .and_then(|entries| entries.get(name))
+                .and_then(|entry| entry.get("labels"))
+                .and_then(|labels| labels.get("sanelens.run_id"))
+                .and_then(Value::as_str)
+                .map(ToString::to_string)
+        };
+        assert_eq!(label("volumes", "data").as_deref(), Some("run-1"));
+        assert_eq!(label("networks", "default").as_deref(), Some("run-1"));
+        assert_eq!(label("networks", "shared"), None);
+    }
+
+    #[test]
+    fn proxy_ports_label_keeps_other_ports_on_the_app() {
+        let service: serde_yaml::Mapping = serde_yaml::from_str(
+            "ports: ['8080:8080', {target: 9090, published: '9090'}]\nlabels:\n  sanelens.proxy.ports: '8080'\n",
+        )
+        .unwrap_or_default();
+        let proxied = select_proxied_ports("api", &service, &[8080, 9090]);
+        assert_eq!(proxied, vec![8080]);
+        let (proxy, direct) =
+            partition_port_entries(service.get("ports").cloned(), &proxied, port_entry_port);
+        let proxy_expected: Option<Value> = serde_yaml::from_str("['8080:8080']").ok();
+        let direct_expected: Option<Value> =
+            serde_yaml::from_str("[{target: 9090, published: '9090'}]").ok();
+        assert_eq!((proxy.clone(), direct), (proxy_expected, direct_expected));
+        let published: Option<Value> =
+            serde_yaml::from_str("['8080:8080', '127.0.0.1::9901']").ok();
+        assert_eq!(Some(with_admin_port(proxy)), published);
+        let unlabeled = serde_yaml::Mapping::new();
+        assert_eq!(
+            select_proxied_ports("api", &unlabeled, &[8080, 9090]),
+            vec![8080, 9090]
+        );
+    }
+
+    #[test]
+    fn platform_fills_services_without_one() {
+        let mut services: serde_yaml::Mapping =
+            serde_yaml::from_str("api: {}\ndb:\n  platform: linux/arm64\n").unwrap_or_default();
+        apply_platform(&mut services, Some("linux/amd64"));
+        let platform = |service: &str| {
+            services
+                .get(service)
+                .and_then(|service| service.get("platform"))
+                .and_then(Value::as_str)
+                .map(ToString::to_string)
+        };
+        assert_eq!(platform("api").as_deref(), Some("linux/amd64"));
+        assert_eq!(platform("db").as_deref(), Some("linux/arm64"));
+    }
+
+    #[test]
+    fn resource_limits_apply_defaults_then_service_overrides() {
+        let mut services: serde_yaml::Mapping =
+            serde_yaml::from_str("api:\n  mem_limit: 1073741824\nworker: {}\n").unwrap_or_default();
+        let limits: Vec<ResourceLimit> = ["*=cpu:1,mem:256m", "api=mem:512m"]
+            .iter()
+            .filter_map(|spec| ResourceLimit::parse(spec).ok())
+            .collect();
+        assert!(apply_resource_limits(&mut services, &limits).is_ok());
+        let limit = |service: &str, key: &str| {
+            services
+                .get(service)
+                .and_then(|service| service.get("deploy"))
+                .and_then(|deploy| deploy.get("resources"))
+                .and_then(|resources| resources.get("limits"))
+                .and_then(|limits| limits.get(key))
+                .cloned()
+        };
+        assert_eq!(
+            limit("api", "memory").and_then(|value| value.as_u64()),
+            Some(536_870_912)
+        );
+        assert_eq!(
+            limit("worker", "memory").and_then(|value| value.as_u64()),
+            Some(268_435_456)
+        );
+        assert_eq!(
+            limit("api", "cpus").as_ref().and_then(Value::as_str),
+            Some("1")
+        );
+        let legacy = services.get("api").and_then(|api| api.get("mem_limit"));
+        assert_eq!(legacy.and_then(Value::as_u64), Some(536_870_912));
+        assert!(services
+            .get("worker")
+            .and_then(|worker| worker.get("mem_limit"))
+            .is_none());
+        let unknown = ResourceLimit::parse("db=cpu:0.5").map(|limit| vec![limit]);
+        assert!(unknown.is_ok_and(|limits| apply_resource_limits(&mut services, &limits).is_err()));
+        assert!(ResourceLimit::parse("api=cpu:0").is_err());
+        assert!(ResourceLimit::parse("api=gpu:1").is_err());
+    }
+
+    #[test]
+    fn env_overrides_replace_service_variables() {
+        let mut services: serde_yaml::Mapping = serde_yaml::from_str(
+            "api:\n  environment: [LOG_LEVEL=info, PORT=8080]\nweb:\n  environment:\n    LOG_LEVEL: info\nworker: {}\n",
+        )
+        .unwrap_or_default();
+        let overrides: Vec<EnvOverride> =
+            ["api:LOG_LEVEL=debug", "web:LOG_LEVEL=trace", "worker:A=b=c"]
+                .iter()
+                .filter_map(|spec| EnvOverride::parse(spec).ok())
+                .collect();
+        assert!(apply_env_overrides(&mut services, &overrides).is_ok());
+        let env = |service: &str| {
+            services
+                .get(service)
+                .and_then(|service| service.get("environment"))
+                .cloned()
+        };
+        let api: Value = serde_yaml::from_str("[PORT=8080, LOG_LEVEL=debug]").unwrap_or_default();
+        assert_eq!(env("api"), Some(api));
+        let web = env("web").and_then(|env| env.get("LOG_LEVEL").cloned());
+        assert_eq!(web.as_ref().and_then(Value::as_str), Some("trace"));
+        let worker = env("worker").and_then(|env| env.get("A").cloned());
+        assert_eq!(worker.as_ref().and_then(Value::as_str), Some("b=c"));
+        let unknown = EnvOverride::parse("db:X=1").map(|entry| vec![entry]);
+        assert!(unknown.is_ok_and(|entries| apply_env_overrides(&mut services, &entries).is_err()));
+        assert!(EnvOverride::parse("LOG_LEVEL=debug").is_err());
+    }
+
+    #[test]
+    fn reports_and_skips_missing_secret_files() {
+        let present = std::env::temp_dir().join(format!("sanelens-secret-{}", std::process::id()));
+        let _ = std::fs::write(&present, "hunter2");
+        let yaml = format!(
+            "services:\n  api:\n    secrets: [token, {{source: db_password, target: db}}]\n    configs: [nginx]\nsecrets:\n  token:\n    file: {}\n  db_password:\n    file: /nonexistent/db_password.txt\n  vault:\n    external: true\nconfigs:\n  nginx:\n    file: /nonexistent/nginx.conf\n",
+            present.display()
+        );
+        let mut doc: Value = serde_yaml::from_str(&yaml).unwrap_or_default();
+        let err = check_file_entries(&mut doc.clone(), false)
+            .err()
+            .unwrap_or_default();
+        assert!(err.contains("secret db_password: /nonexistent/db_password.txt"));
+        assert!(err.contains("config nginx: /nonexistent/nginx.conf"));
+        assert!(!err.contains("token"));
+
+        assert!(check_file_entries(&mut doc, true).is_ok());
+        let _ = std::fs::remove_file(&present);
+        let secrets = doc.get("secrets");
+        assert!(secrets.and_then(|map| map.get("db_password")).is_none());
+        assert!(secrets.and_then(|map| map.get("vault")).is_some());
+        let refs = |section: &str| {
+            doc.get("services")
+                .and_then(|services| services.get("api"))
+                .and_then(|api| api.get(section))
+                .and_then(Value::as_sequence)
+                .map(Vec::len)
+        };
+        assert_eq!(refs("secrets"), Some(1));
+        assert_eq!(refs("configs"), Some(0));
+    }
+
+    #[test]
+    fn reports_port_collisions_by_service() {
+        let services: serde_yaml::Mapping = serde_yaml::from_str(
+            "api:\n  labels: {sanelens.proxy.name: api}\n  ports: [{target: 8080, published: '8080'}, {target: 9901}]\nweb:\n  ports: ['127.0.0.1:8080:80', '9000:90/udp']\nadmin:\n  ports: ['127.0.0.2:9000:90']\nsidecar:\n  network_mode: service:api\n  expose: ['8080']\n",
+        )
+        .unwrap_or_default();
+        let err = check_port_collisions(&services).err().unwrap_or_default();
+        assert!(err.contains("host port 8080/tcp is published by both api and web"));
+        assert!(err.contains("api uses port 9901"));
+        assert!(err.contains("sidecar shares the network namespace of api"));
+        assert!(!err.contains("9000"));
+    }
+
+    #[test]
+    fn rootless_compat_listens_dual_stack_and_adds_host_gateway() {
+        let config = dual_stack_listeners(&format!(
+            "static_resources:\n  listeners:\n{}",
+            http_listener_block("api", "api-app", 8080, DEFAULT_MAX_BODY_BYTES)
+        ));
+        let doc: Value = serde_yaml::from_str(&config).unwrap_or_default();
+        let address = doc
+            .get("static_resources")
+            .and_then(|resources| resources.get("listeners"))
+            .and_then(|listeners| listeners.get(0))
+            .and_then(|listener| listener.get("address"))
+            .and_then(|address| address.get("socket_address"));
+        assert_eq!(
+            address.and_then(|address| address.get("address")),
+            Some(&Value::String("::".to_string()))
+        );
+        assert_eq!(
+            address.and_then(|address| address.get("ipv4_compat")),
+            Some(&Value::Bool(true))
+        );
+
+        let mut service: serde_yaml::Mapping =
+            serde_yaml::from_str("extra_hosts: [\"host.docker.internal:10.0.0.1\"]\n")
+                .unwrap_or_default();
+        ensure_host_gateway(&mut service);
+        assert_eq!(
+            service.get("extra_hosts"),
+            Some(&serde_yaml::from_str(
+                "[\"host.docker.internal:10.0.0.1\", \"host.containers.internal:host-gateway\"]"
+            )
+            .unwrap_or_default())
+        );
+    }
 
     #[test]
     fn parse_container_port_plain() {
@@ -768,6 +1478,105 @@ mod tests {
             Some(80)
         );
     }
+
+    #[test]
+    fn sniff_listener_falls_back_to_tcp_proxy() {
+        let config = format!(
+            "static_resources:\n  listeners:\n{}",
+            sniff_listener_block("api", "api-app", 7345, 65536)
+        );
+        assert!(config.contains("max_buffered_rx_bytes: 65536"));
+        let doc: Value = serde_yaml::from_str(&config).unwrap_or_default();
+        let listener = doc
+            .get("static_resources")
+            .and_then(|resources| resources.get("listeners"))
+            .and_then(|listeners| listeners.get(0));
+        let inspector = listener
+            .and_then(|listener| listener.get("listener_filters"))
+            .and_then(|filters| filters.get(0))
+            .and_then(|filter| filter.get("name"))
+            .and_then(Value::as_str);
+        assert_eq!(inspector, Some("envoy.filters.listener.http_inspector"));
+        let chains: Vec<&str> = listener
+            .and_then(|listener| listener.get("filter_chains"))
+            .and_then(Value::as_sequence)
+            .map(|chains| {
+                chains
+                    .iter()
+                    .filter_map(|chain| chain.get("filters")?.get(0)?.get("name")?.as_str())
+                    .collect()
+            })
+            .unwrap_or_default();
+        assert_eq!(
+            chains,
+            vec![
+                "envoy.filters.network.http_connection_manager",
+                "envoy.filters.network.tcp_proxy"
+            ]
+        );
+        let tcp_tap = listener
+            .and_then(|listener| listener.get("filter_chains"))
+            .and_then(|chains| chains.get(1))
+            .and_then(|chain| chain.get("transport_socket")?.get("name"))
+            .and_then(Value::as_str);
+        assert_eq!(tcp_tap, Some("envoy.transport_sockets.tap"));
+    }
+
+    #[test]
+    fn clusters_carry_upstream_http_protocol_options() {
+        let options = |upstream| {
+            let config = format!("clusters:\n{}", cluster_block("api-app", 50051, upstream));
+            let doc: Value = serde_yaml::from_str(&config).unwrap_or_default();
+            doc.get("clusters")
+                .and_then(|clusters| clusters.get(0))
+                .and_then(|cluster| cluster.get("typed_extension_protocol_options"))
+                .and_then(|options| {
+                    options.get("envoy.extensions.upstreams.http.v3.HttpProtocolOptions")
+                })
+                .cloned()
+        };
+        let h2c = options(Some(UpstreamProtocol::H2c));
+        assert!(h2c
+            .as_ref()
+            .and_then(|options| options.get("explicit_http_config"))
+            .and_then(|config| config.get("http2_protocol_options"))
+            .is_some());
+        let auto = options(UpstreamProtocol::parse("auto"));
+        assert!(auto
+            .as_ref()
+            .and_then(|options| options.get("use_downstream_protocol_config"))
+            .is_some());
+        assert!(options(None).is_none());
+        assert_eq!(UpstreamProtocol::parse("gRPC"), Some(UpstreamProtocol::H2c));
+    }
+
+    #[test]
+    fn transparent_egress_adds_redirect_listeners() {
+        let config = transparent_egress_config(DEFAULT_MAX_BODY_BYTES).unwrap_or_default();
+        let doc: Value = serde_yaml::from_str(&config).unwrap_or_default();
+        let listeners: Vec<(String, u64)> = doc
+            .get("static_resources")
+            .and_then(|resources| resources.get("listeners"))
+            .and_then(Value::as_sequence)
+            .map(|listeners| {
+                listeners
+                    .iter()
+                    .filter_map(|listener| {
+                        let name = listener.get("name")?.as_str()?.to_string();
+                        let port = listener
+                            .get("address")?
+                            .get("socket_address")?
+                            .get("port_value")?
+                            .as_u64()?;
+                        Some((name, port))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        assert_eq!(listeners.len(), 3);
+        assert!(listeners.contains(&("egress_transparent_http".to_string(), 15002)));
+        assert!(listeners.contains(&("egress_transparent_tls".to_string(), 15003)));
+    }
 }
 
 fn build_egress_service(
@@ -835,6 +1644,40 @@ fn collect_network_names(doc: &Value) -> Vec<String> {
     names
 }
 
+fn label_run_resources(doc: &mut Value, run_id: &str) {
+    let Value::Mapping(root) = doc else {
+        return;
+    };
+    for section in ["networks", "volumes"] {
+        let key = Value::String(section.to_string());
+        if !matches!(root.get(&key), Some(Value::Mapping(_))) {
+            root.insert(key.clone(), Value::Mapping(Mapping::new()));
+        }
+        let Some(Value::Mapping(entries)) = root.get_mut(&key) else {
+            continue;
+        };
+        if section == "networks" {
+            entries
+                .entry(Value::String("default".to_string()))
+                .or_insert(Value::Null);
+        }
+        for (_, entry) in entries.iter_mut() {
+            if entry.is_null() {
+                *entry = Value::Mapping(Mapping::new());
+            }
+            let Value::Mapping(definition) = entry else {
+                continue;
+            };
+            let external = definition
+                .get("external")
+                .is_some_and(|value| !matches!(value, Value::Bool(false) | Value::Null));
+            if !external {
+                add_label(definition, RUN_ID_LABEL, run_id);
+            }
+        }
+    }
+}
+
 fn collect_service_names(doc: &Value) -> Result<Vec<String>, String> {
     let Some(Value::Mapping(services)) = doc.get("services") else {
         return Err("compose file missing services".to_string());
@@ -866,6 +1709,95 @@ fn rewrite_top_level_paths(doc: &mut Value, base_dir: &Path) {
     }
 }
 
+struct MissingFile {
+    section: &'static str,
+    name: String,
+    path: String,
+    reason: String,
+}
+
+impl MissingFile {
+    fn describe(&self) -> String {
+        let kind = self.section.trim_end_matches('s');
+        format!("{kind} {}: {} ({})", self.name, self.path, self.reason)
+    }
+}
+
+fn check_file_entries(doc: &mut Value, skip_missing: bool) -> Result<(), String> {
+    let missing = missing_file_entries(doc);
+    if missing.is_empty() {
+        return Ok(());
+    }
+    if !skip_missing {
+        let lines: Vec<String> = missing.iter().map(MissingFile::describe).collect();
+        return Err(format!(
+            "missing secret/config files:\n  {}\nfix the paths or pass --skip-missing-secrets to leave them out",
+            lines.join("\n  ")
+        ));
+    }
+    for entry in &missing {
+        eprintln!("[compose] skipping {}", entry.describe());
+        drop_file_entry(doc, entry);
+    }
+    Ok(())
+}
+
+fn missing_file_entries(doc: &Value) -> Vec<MissingFile> {
+    let mut missing = Vec::new();
+    for section in ["secrets", "configs"] {
+        let Some(Value::Mapping(map)) = doc.get(section) else {
+            continue;
+        };
+        for (name, entry) in map {
+            let (Some(name), Some(path)) =
+                (name.as_str(), entry.get("file").and_then(Value::as_str))
+            else {
+                continue;
+            };
+            if let Some(reason) = unreadable_reason(Path::new(path)) {
+                missing.push(MissingFile {
+                    section,
+                    name: name.to_string(),
+                    path: path.to_string(),
+                    reason,
+                });
+            }
+        }
+    }
+    missing
+}
+
+fn unreadable_reason(path: &Path) -> Option<String> {
+    let readable = fs::metadata(path).and_then(|meta| {
+        if meta.is_dir() {
+            fs::read_dir(path).map(|_| ())
+        } else {
+            fs::File::open(path).map(|_| ())
+        }
+    });
+    readable.err().map(|err| err.to_string())
+}
+
+fn drop_file_entry(doc: &mut Value, entry: &MissingFile) {
+    if let Some(Value::Mapping(map)) = doc.get_mut(entry.section) {
+        map.remove(entry.name.as_str());
+    }
+    let Some(Value::Mapping(services)) = doc.get_mut("services") else {
+        return;
+    };
+    for (_, service) in services.iter_mut() {
+        let Some(Value::Sequence(refs)) = service.get_mut(entry.section) else {
+            continue;
+        };
+        refs.retain(|reference| {
+            let source = reference
+                .as_str()
+                .or_else(|| reference.get("source").and_then(Value::as_str));
+            source != Some(entry.name.as_str())
+        });
+    }
+}
+
 fn disable_podman_pods(doc: &mut Value) {
     let Value::Mapping(map) = doc else {
         return;
@@ -908,6 +1840,21 @@ fn rewrite_service_paths(service: &mut Mapping, base_dir: &Path) {
     rewrite_env_files(service, base_dir);
     rewrite_volumes(service, base_dir);
     rewrite_extends(service, base_dir);
+    rewrite_develop_watch(service, base_dir);
+}
+
+fn rewrite_develop_watch(service: &mut Mapping, base_dir: &Path) {
+    let Some(Value::Sequence(rules)) = service
+        .get_mut(Value::String("develop".to_string()))
+        .and_then(|develop| develop.get_mut("watch"))
+    else {
+        return;
+    };
+    for rule in rules {
+        if let Some(Value::String(path)) = rule.get_mut("path") {
+            rewrite_string_value(path, base_dir, PathKind::Dir);
+        }
+    }
 }
 
 fn rewrite_build(service: &mut Mapping, base_dir: &Path) {
@@ -1130,6 +2077,26 @@ fn is_probably_path(value: &str) -> bool {
         || value.contains('\\')
 }
 
+pub fn run_dir(run_root: Option<&Path>, base_dir: &Path, project_name: &str) -> PathBuf {
+    run_root
+        .map_or_else(|| base_dir.join(".sanelens"), Path::to_path_buf)
+        .join(project_name)
+}
+
+pub fn buffer_stdin_compose(dir: &Path) -> Result<PathBuf, String> {
+    let mut contents = Vec::new();
+    io::stdin()
+        .read_to_end(&mut contents)
+        .map_err(|err| format!("failed to read compose file from stdin: {err}"))?;
+    if contents.iter().all(u8::is_ascii_whitespace) {
+        return Err("Compose file from stdin (-f -) is empty.".to_string());
+    }
+    fs::create_dir_all(dir).map_err(|err| format!("failed to create derived dir: {err}"))?;
+    let path = dir.join(STDIN_COMPOSE_FILE);
+    fs::write(&path, contents).map_err(|err| format!("failed to buffer stdin compose: {err}"))?;
+    Ok(path)
+}
+
 fn to_absolute_path(path: &str) -> Result<PathBuf, String> {
     let candidate = PathBuf::from(path);
     if candidate.is_absolute() {
@@ -1149,29 +2116,214 @@ fn write_envoy_config(
     service_name: &str,
     app_name: &str,
     ports: &[(u16, ProxyProtocol)],
+    tuning: &EnvoyTuning<'_>,
 ) -> Result<(), String> {
     let mut body = String::new();
     body.push_str("static_resources:\n  listeners:\n");
     for (port, mode) in ports {
         match mode {
             ProxyProtocol::Http => {
-                body.push_str(&http_listener_block(service_name, app_name, *port));
+                body.push_str(&http_listener_block(
+                    service_name,
+                    app_name,
+                    *port,
+                    tuning.max_body_bytes,
+                ));
             }
             ProxyProtocol::Tcp => {
                 body.push_str(&tcp_listener_block(service_name, app_name, *port));
             }
+            ProxyProtocol::Sniff => {
+                body.push_str(&sniff_listener_block(
+                    service_name,
+                    app_name,
+                    *port,
+                    tuning.max_body_bytes,
+                ));
+            }
         }
     }
     body.push_str("  clusters:\n");
-    for (port, _) in ports {
-        body.push_str(&cluster_block(app_name, *port));
+    for (port, mode) in ports {
+        let upstream = (*mode != ProxyProtocol::Tcp).then_some(tuning.upstream);
+        body.push_str(&cluster_block(app_name, *port, upstream));
+    }
+    let _ = write!(
+        body,
+        "admin:\n  access_log_path: /tmp/envoy_admin.log\n  address:\n    socket_address:\n      address: 0.0.0.0\n      port_value: {ENVOY_ADMIN_PORT}\nlayered_runtime:\n  layers:\n  - name: static\n    static_layer: {{}}\n  - name: admin\n    admin_layer: {{}}\n"
+    );
+    if tuning.dual_stack {
+        body = dual_stack_listeners(&body);
+    }
+    if let Some(overrides) = tuning.overrides {
+        body = apply_envoy_overrides(&body, overrides)?;
     }
-    body.push_str("admin:\n  access_log_path: /tmp/envoy_admin.log\n  address:\n    socket_address:\n      address: 0.0.0.0\n      port_value: 9901\n");
 
     let path = envoy_dir.join(format!("{service_name}.yaml"));
     fs::write(path, body).map_err(|err| err.to_string())
 }
 
+fn dual_stack_listeners(config: &str) -> String {
+    config.replace(
+        "      socket_address:\n        address: 0.0.0.0\n",
+        "      socket_address:\n        address: \"::\"\n        ipv4_compat: true\n",
+    )
+}
+
+const HOST_GATEWAY_ALIASES: [&str; 2] = ["host.docker.internal", "host.containers.internal"];
+
+fn ensure_host_gateway(service: &mut Mapping) {
+    let key = Value::String("extra_hosts".to_string());
+    let entries = service
+        .entry(key)
+        .or_insert_with(|| Value::Sequence(Vec::new()));
+    match entries {
+        Value::Sequence(list) => {
+            for alias in HOST_GATEWAY_ALIASES {
+                let present = list.iter().any(|entry| {
+                    entry
+                        .as_str()
+                        .is_some_and(|entry| entry.split([':', '=']).next() == Some(alias))
+                });
+                if !present {
+                    list.push(Value::String(format!("{alias}:host-gateway")));
+                }
+            }
+        }
+        Value::Mapping(map) => {
+            for alias in HOST_GATEWAY_ALIASES {
+                map.entry(Value::String(alias.to_string()))
+                    .or_insert_with(|| Value::String("host-gateway".to_string()));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn warn_privileged_ports(service: &str, ports: Option<&Value>) {
+    let Some(Value::Sequence(entries)) = ports else {
+        return;
+    };
+    for entry in entries {
+        if let Some(port) = published_binding(entry)
+            .map(|binding| binding.port)
+            .filter(|port| *port < 1024)
+        {
+            eprintln!(
+                "[compose] {service}: host port {port} is below 1024; rootless podman can only publish it if net.ipv4.ip_unprivileged_port_start allows it"
+            );
+        }
+    }
+}
+
+struct PortBinding {
+    host_ip: String,
+    port: u16,
+    protocol: String,
+}
+
+impl PortBinding {
+    fn overlaps(&self, other: &Self) -> bool {
+        let wildcard = |ip: &str| ip.is_empty() || ip == "0.0.0.0" || ip == "::";
+        self.port == other.port
+            && self.protocol == other.protocol
+            && (self.host_ip == other.host_ip
+                || wildcard(&self.host_ip)
+                || wildcard(&other.host_ip))
+    }
+}
+
+fn published_binding(entry: &Value) -> Option<PortBinding> {
+    match entry {
+        Value::Mapping(map) => Some(PortBinding {
+            host_ip: map
+                .get("host_ip")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            port: map.get("published").and_then(value_to_u16)?,
+            protocol: map
+                .get("protocol")
+                .and_then(Value::as_str)
+                .unwrap_or("tcp")
+                .to_string(),
+        }),
+        Value::String(value) => {
+            let (mapping, protocol) = value.split_once('/').unwrap_or((value, "tcp"));
+            let host = mapping.get(..find_container_port_separator(mapping)?)?;
+            let (host_ip, port) = host.rsplit_once(':').unwrap_or(("", host));
+            Some(PortBinding {
+                host_ip: host_ip.trim_matches(['[', ']']).to_string(),
+                port: parse_port_token(port)?,
+                protocol: protocol.to_string(),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn check_port_collisions(services: &Mapping) -> Result<(), String> {
+    let mut problems = Vec::new();
+    let mut published: Vec<(&str, PortBinding)> = Vec::new();
+    for (name, service) in services {
+        let (Some(name), Some(service)) = (name.as_str(), service.as_mapping()) else {
+            continue;
+        };
+        let entries = service.get("ports").and_then(Value::as_sequence);
+        for binding in entries.into_iter().flatten().filter_map(published_binding) {
+            match published.iter().find(|(_, other)| other.overlaps(&binding)) {
+                Some((owner, _)) if *owner != name => problems.push(format!(
+                    "host port {}/{} is published by both {owner} and {name}",
+                    binding.port, binding.protocol
+                )),
+                Some(_) => {}
+                None => published.push((name, binding)),
+            }
+        }
+        if read_label(service, PROXY_NAME_LABEL).is_some()
+            && extract_ports(service).contains(&ENVOY_ADMIN_PORT)
+        {
+            problems.push(format!(
+                "{name} uses port {ENVOY_ADMIN_PORT}, which its sanelens proxy reserves for the Envoy admin interface; move the port or set sanelens.proxy=off"
+            ));
+        }
+        problems.extend(shared_namespace_collisions(services, name, service));
+    }
+    if problems.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "port collisions in the derived compose file:\n  - {}",
+        problems.join("\n  - ")
+    ))
+}
+
+fn shared_namespace_collisions(services: &Mapping, name: &str, service: &Mapping) -> Vec<String> {
+    let Some(owner) = get_string(service, "network_mode")
+        .and_then(|mode| mode.strip_prefix("service:").map(str::to_string))
+    else {
+        return Vec::new();
+    };
+    let Some(proxy) = services
+        .get(owner.as_str())
+        .and_then(Value::as_mapping)
+        .filter(|proxy| read_label(proxy, PROXY_NAME_LABEL).is_some())
+    else {
+        return Vec::new();
+    };
+    let mut listeners = extract_ports(proxy);
+    listeners.push(ENVOY_ADMIN_PORT);
+    extract_ports(service)
+        .into_iter()
+        .filter(|port| listeners.contains(port))
+        .map(|port| {
+            format!(
+                "{name} shares the network namespace of {owner}, whose sanelens proxy already listens on port {port}"
+            )
+        })
+        .collect()
+}
+
 const EGRESS_ENVOY_CONFIG: &str = r#"static_resources:
   listeners:
   - name: egress_listener
@@ -1185,6 +2337,8 @@ const EGRESS_ENVOY_CONFIG: &str = r#"static_resources:
         typed_config:
           "@type": type.googleapis.com/envoy.extensions.filters.network.http_connection_manager.v3.HttpConnectionManager
           stat_prefix: egress_http
+          upgrade_configs:
+          - upgrade_type: websocket
           route_config:
             name: egress_route
             virtual_hosts:
@@ -1211,8 +2365,8 @@ const EGRESS_ENVOY_CONFIG: &str = r#"static_resources:
                   match_config:
                     any_match: true
                   output_config:
-                    max_buffered_rx_bytes: 10485760
-                    max_buffered_tx_bytes: 10485760
+                    max_buffered_rx_bytes: __MAX_BODY_BYTES__
+                    max_buffered_tx_bytes: __MAX_BODY_BYTES__
                     sinks:
                     - format: JSON_BODY_AS_STRING
                       file_per_tap:
@@ -1244,8 +2398,10 @@ const EGRESS_ENVOY_CONFIG: &str = r#"static_resources:
                   duration_ms: "%DURATION%"
                   downstream_remote_address: "%DOWNSTREAM_REMOTE_ADDRESS%"
                   upstream_host: "%UPSTREAM_HOST%"
+                  requested_server_name: "%REQUESTED_SERVER_NAME%"
                   bytes_received: "%BYTES_RECEIVED%"
                   bytes_sent: "%BYTES_SENT%"
+                  upgrade: "%REQ(UPGRADE)%"
   clusters:
   - name: egress_cluster
     connect_timeout: 5s
@@ -1264,25 +2420,190 @@ admin:
       address: 0.0.0.0
       port_value: 9901
 "#;
-fn write_egress_envoy_config(path: &Path) -> Result<(), String> {
-    fs::write(path, EGRESS_ENVOY_CONFIG).map_err(|err| err.to_string())
+fn write_egress_envoy_config(
+    path: &Path,
+    transparent: bool,
+    max_body_bytes: usize,
+) -> Result<(), String> {
+    let body = if transparent {
+        transparent_egress_config(max_body_bytes)?
+    } else {
+        egress_envoy_config(max_body_bytes)
+    };
+    fs::write(path, body).map_err(|err| err.to_string())
+}
+
+const TRANSPARENT_HTTP_PORT: u16 = 15002;
+
+const TRANSPARENT_TLS_LISTENER: &str = r#"name: egress_transparent_tls
+address:
+  socket_address:
+    address: 0.0.0.0
+    port_value: 15003
+listener_filters:
+- name: envoy.filters.listener.tls_inspector
+  typed_config:
+    "@type": type.googleapis.com/envoy.extensions.filters.listener.tls_inspector.v3.TlsInspector
+filter_chains:
+- filters:
+  - name: envoy.filters.network.sni_dynamic_forward_proxy
+    typed_config:
+      "@type": type.googleapis.com/envoy.extensions.filters.network.sni_dynamic_forward_proxy.v3.FilterConfig
+      port_value: 443
+      dns_cache_config:
+        name: egress_cache
+        dns_lookup_family: V4_ONLY
+  - name: envoy.filters.network.tcp_proxy
+    typed_config:
+      "@type": type.googleapis.com/envoy.extensions.filters.network.tcp_proxy.v3.TcpProxy
+      stat_prefix: egress_transparent_tls
+      cluster: egress_cluster
+      access_log:
+      - name: envoy.access_loggers.stdout
+        typed_config:
+          "@type": type.googleapis.com/envoy.extensions.access_loggers.stream.v3.StdoutAccessLog
+          log_format:
+            json_format:
+              timestamp: "%START_TIME%"
+              duration_ms: "%DURATION%"
+              downstream_remote_address: "%DOWNSTREAM_REMOTE_ADDRESS%"
+              upstream_host: "%UPSTREAM_HOST%"
+              requested_server_name: "%REQUESTED_SERVER_NAME%"
+              bytes_received: "%BYTES_RECEIVED%"
+              bytes_sent: "%BYTES_SENT%"
+"#;
+
+const EGRESS_INIT_SCRIPT: &str = "set -e
+command -v iptables >/dev/null 2>&1 || apk add --no-cache iptables >/dev/null
+egress=$$(getent hosts sanelens-egress-proxy | awk '{ print $$1 }')
+[ -n \"$$egress\" ] || { echo 'sanelens-egress-proxy not resolvable' >&2; exit 1; }
+iptables -t nat -N SANELENS_EGRESS
+for net in 127.0.0.0/8 10.0.0.0/8 172.16.0.0/12 192.168.0.0/16; do
+  iptables -t nat -A SANELENS_EGRESS -d $$net -j RETURN
+done
+iptables -t nat -A SANELENS_EGRESS -p tcp --dport 80 -j DNAT --to-destination $$egress:15002
+iptables -t nat -A SANELENS_EGRESS -p tcp --dport 443 -j DNAT --to-destination $$egress:15003
+iptables -t nat -A OUTPUT -p tcp -j SANELENS_EGRESS
+";
+
+fn egress_envoy_config(max_body_bytes: usize) -> String {
+    EGRESS_ENVOY_CONFIG.replace("__MAX_BODY_BYTES__", &max_body_bytes.to_string())
+}
+
+fn transparent_egress_config(max_body_bytes: usize) -> Result<String, String> {
+    let mut doc: Value = serde_yaml::from_str(&egress_envoy_config(max_body_bytes))
+        .map_err(|err| format!("egress envoy config is invalid: {err}"))?;
+    let tls_listener: Value = serde_yaml::from_str(TRANSPARENT_TLS_LISTENER)
+        .map_err(|err| format!("transparent egress listener is invalid: {err}"))?;
+    let Some(Value::Sequence(listeners)) = doc
+        .get_mut("static_resources")
+        .and_then(|resources| resources.get_mut("listeners"))
+    else {
+        return Err("egress envoy config has no listeners".to_string());
+    };
+    let mut http_listener = listeners
+        .first()
+        .cloned()
+        .ok_or_else(|| "egress envoy config has no listeners".to_string())?;
+    if let Value::Mapping(listener) = &mut http_listener {
+        listener.insert(
+            Value::String("name".to_string()),
+            Value::String("egress_transparent_http".to_string()),
+        );
+    }
+    if let Some(address) = http_listener
+        .get_mut("address")
+        .and_then(|address| address.get_mut("socket_address"))
+        .and_then(Value::as_mapping_mut)
+    {
+        address.insert(
+            Value::String("port_value".to_string()),
+            Value::Number(TRANSPARENT_HTTP_PORT.into()),
+        );
+    }
+    listeners.push(http_listener);
+    listeners.push(tls_listener);
+    serde_yaml::to_string(&doc).map_err(|err| format!("serialize egress config failed: {err}"))
+}
+
+fn add_egress_init_services(
+    services: &mut Mapping,
+    proxy_services: &HashSet<String>,
+    image: &str,
+    run_labels: &RunLabelContext<'_>,
+) {
+    let workloads: Vec<String> = services
+        .iter()
+        .filter(|(_, service)| service.get("network_mode").is_none())
+        .filter_map(|(name, _)| name.as_str())
+        .filter(|name| !proxy_services.contains(*name))
+        .map(ToString::to_string)
+        .collect();
+    for workload in workloads {
+        let init_name = format!("{workload}-egress-init");
+        let mut init = Mapping::new();
+        let mut set = |key: &str, value: Value| {
+            init.insert(Value::String(key.to_string()), value);
+        };
+        let strings = |values: &[&str]| {
+            Value::Sequence(
+                values
+                    .iter()
+                    .map(|value| Value::String((*value).to_string()))
+                    .collect(),
+            )
+        };
+        set("image", Value::String(image.to_string()));
+        set("network_mode", Value::String(format!("service:{workload}")));
+        set("cap_add", strings(&["NET_ADMIN", "NET_RAW"]));
+        // Runs once: a restarted workload gets a fresh netns without the rules.
+        set("restart", Value::String("no".to_string()));
+        set(
+            "depends_on",
+            strings(&[workload.as_str(), "sanelens-egress-proxy"]),
+        );
+        set("entrypoint", strings(&["sh", "-c"]));
+        set("command", strings(&[EGRESS_INIT_SCRIPT]));
+        add_label(&mut init, "sanelens.egress.init", "true");
+        add_run_labels(&mut init, &init_name, run_labels);
+        services.insert(Value::String(init_name), Value::Mapping(init));
+    }
 }
 
-#[allow(clippy::too_many_lines)]
-fn http_listener_block(service_name: &str, app_name: &str, port: u16) -> String {
+fn http_listener_block(
+    service_name: &str,
+    app_name: &str,
+    port: u16,
+    max_body_bytes: usize,
+) -> String {
     format!(
-        r#"  - name: {service_name}_listener_{port}
-    address:
-      socket_address:
-        address: 0.0.0.0
-        port_value: {port}
-    filter_chains:
-    - filters:
-      - name: envoy.filters.network.http_connection_manager
+        "  - name: {service_name}_listener_{port}\n    address:\n      socket_address:\n        address: 0.0.0.0\n        port_value: {port}\n    filter_chains:\n    - filters:\n{}",
+        http_manager_filter(app_name, port, max_body_bytes),
+    )
+}
+
+fn sniff_listener_block(
+    service_name: &str,
+    app_name: &str,
+    port: u16,
+    max_body_bytes: usize,
+) -> String {
+    format!(
+        "  - name: {service_name}_sniff_listener_{port}\n    address:\n      socket_address:\n        address: 0.0.0.0\n        port_value: {port}\n    listener_filters_timeout: 1s\n    continue_on_listener_filters_timeout: true\n    listener_filters:\n    - name: envoy.filters.listener.http_inspector\n      typed_config:\n        \"@type\": type.googleapis.com/envoy.extensions.filters.listener.http_inspector.v3.HttpInspector\n    filter_chains:\n    - filter_chain_match:\n        application_protocols: [\"http/1.0\", \"http/1.1\", \"h2c\"]\n      filters:\n{}    - {SOCKET_TAP}      filters:\n{}",
+        http_manager_filter(app_name, port, max_body_bytes),
+        tcp_proxy_filter(app_name, port),
+    )
+}
+
+fn http_manager_filter(app_name: &str, port: u16, max_body_bytes: usize) -> String {
+    format!(
+        r#"      - name: envoy.filters.network.http_connection_manager
         typed_config:
           "@type": type.googleapis.com/envoy.extensions.filters.network.http_connection_manager.v3.HttpConnectionManager
           stat_prefix: ingress_http_{port}
           codec_type: AUTO
+          upgrade_configs:
+          - upgrade_type: websocket
           route_config:
             name: route_{port}
             virtual_hosts:
@@ -1291,9 +2612,24 @@ fn http_listener_block(service_name: &str, app_name: &str, port: u16) -> String
               routes:
               - match:
                   prefix: "/"
+                  headers:
+                  - name: upgrade
+                    present_match: true
                 route:
                   cluster: {app_name}_{port}
-          http_filters:
+                  timeout: 0s
+              - match:
+                  prefix: "/"
+                route:
+                  cluster: {app_name}_{port}
+{}{HTTP_ACCESS_LOG}"#,
+        http_filters(max_body_bytes),
+    )
+}
+
+fn http_filters(max_body_bytes: usize) -> String {
+    format!(
+        r#"          http_filters:
           - name: envoy.filters.http.tap
             typed_config:
               "@type": type.googleapis.com/envoy.extensions.filters.http.tap.v3.Tap
@@ -1302,16 +2638,33 @@ fn http_listener_block(service_name: &str, app_name: &str, port: u16) -> String
                   match_config:
                     any_match: true
                   output_config:
-                    max_buffered_rx_bytes: 10485760
-                    max_buffered_tx_bytes: 10485760
+                    max_buffered_rx_bytes: {max_body_bytes}
+                    max_buffered_tx_bytes: {max_body_bytes}
                     sinks:
                     - format: JSON_BODY_AS_STRING
                       file_per_tap:
                         path_prefix: /sanelens/tap/trace
+          - name: envoy.filters.http.fault
+            typed_config:
+              "@type": type.googleapis.com/envoy.extensions.filters.http.fault.v3.HTTPFault
+              delay:
+                fixed_delay: 0.001s
+                percentage:
+                  numerator: 0
+                  denominator: HUNDRED
+              abort:
+                http_status: 503
+                percentage:
+                  numerator: 0
+                  denominator: HUNDRED
           - name: envoy.filters.http.router
             typed_config:
               "@type": type.googleapis.com/envoy.extensions.filters.http.router.v3.Router
-          access_log:
+"#,
+    )
+}
+
+const HTTP_ACCESS_LOG: &str = r#"          access_log:
           - name: envoy.access_loggers.stdout
             typed_config:
               "@type": type.googleapis.com/envoy.extensions.access_loggers.stream.v3.StdoutAccessLog
@@ -1322,6 +2675,7 @@ fn http_listener_block(service_name: &str, app_name: &str, port: u16) -> String
                   path: "%REQ(X-ENVOY-ORIGINAL-PATH?:PATH)%"
                   protocol: "%PROTOCOL%"
                   response_code: "%RESPONSE_CODE%"
+                  response_flags: "%RESPONSE_FLAGS%"
                   duration_ms: "%DURATION%"
                   downstream_remote_address: "%DOWNSTREAM_REMOTE_ADDRESS%"
                   upstream_host: "%UPSTREAM_HOST%"
@@ -1337,18 +2691,52 @@ fn http_listener_block(service_name: &str, app_name: &str, port: u16) -> String
                   response_content_type: "%RESP(CONTENT-TYPE)%"
                   response_content_length: "%RESP(CONTENT-LENGTH)%"
                   response_body: "%DYNAMIC_METADATA(sanelens:response_body)%"
-"#,
-    )
-}
+                  upgrade: "%REQ(UPGRADE)%"
+"#;
 
 fn tcp_listener_block(service_name: &str, app_name: &str, port: u16) -> String {
     format!(
-        "  - name: {service_name}_tcp_listener_{port}\n    address:\n      socket_address:\n        address: 0.0.0.0\n        port_value: {port}\n    filter_chains:\n    - filters:\n      - name: envoy.filters.network.tcp_proxy\n        typed_config:\n          \"@type\": type.googleapis.com/envoy.extensions.filters.network.tcp_proxy.v3.TcpProxy\n          stat_prefix: tcp_{port}\n          cluster: {app_name}_{port}\n          access_log:\n          - name: envoy.access_loggers.stdout\n            typed_config:\n              \"@type\": type.googleapis.com/envoy.extensions.access_loggers.stream.v3.StdoutAccessLog\n              log_format:\n                json_format:\n                  timestamp: \"%START_TIME%\"\n                  duration_ms: \"%DURATION%\"\n                  downstream_remote_address: \"%DOWNSTREAM_REMOTE_ADDRESS%\"\n                  upstream_host: \"%UPSTREAM_HOST%\"\n                  bytes_received: \"%BYTES_RECEIVED%\"\n                  bytes_sent: \"%BYTES_SENT%\"\n",
+        "  - name: {service_name}_tcp_listener_{port}\n    address:\n      socket_address:\n        address: 0.0.0.0\n        port_value: {port}\n    filter_chains:\n    - {SOCKET_TAP}      filters:\n{}",
+        tcp_proxy_filter(app_name, port),
     )
 }
 
-fn cluster_block(app_name: &str, port: u16) -> String {
+const SOCKET_TAP: &str = r#"transport_socket:
+        name: envoy.transport_sockets.tap
+        typed_config:
+          "@type": type.googleapis.com/envoy.extensions.transport_sockets.tap.v3.Tap
+          common_config:
+            static_config:
+              match_config:
+                any_match: true
+              output_config:
+                streaming: true
+                sinks:
+                - format: JSON_BODY_AS_BYTES
+                  file_per_tap:
+                    path_prefix: /sanelens/tap/socket
+          transport_socket:
+            name: envoy.transport_sockets.raw_buffer
+            typed_config:
+              "@type": type.googleapis.com/envoy.extensions.transport_sockets.raw_buffer.v3.RawBuffer
+"#;
+
+fn tcp_proxy_filter(app_name: &str, port: u16) -> String {
     format!(
-        "  - name: {app_name}_{port}\n    connect_timeout: 2s\n    type: STRICT_DNS\n    lb_policy: ROUND_ROBIN\n    load_assignment:\n      cluster_name: {app_name}_{port}\n      endpoints:\n      - lb_endpoints:\n        - endpoint:\n            address:\n              socket_address:\n                address: {app_name}\n                port_value: {port}\n",
+        "      - name: envoy.filters.network.tcp_proxy\n        typed_config:\n          \"@type\": type.googleapis.com/envoy.extensions.filters.network.tcp_proxy.v3.TcpProxy\n          stat_prefix: tcp_{port}\n          cluster: {app_name}_{port}\n          access_log:\n          - name: envoy.access_loggers.stdout\n            typed_config:\n              \"@type\": type.googleapis.com/envoy.extensions.access_loggers.stream.v3.StdoutAccessLog\n              log_format:\n                json_format:\n                  timestamp: \"%START_TIME%\"\n                  duration_ms: \"%DURATION%\"\n                  downstream_remote_address: \"%DOWNSTREAM_REMOTE_ADDRESS%\"\n                  upstream_host: \"%UPSTREAM_HOST%\"\n                  bytes_received: \"%BYTES_RECEIVED%\"\n                  bytes_sent: \"%BYTES_SENT%\"\n",
     )
 }
+
+fn cluster_block(app_name: &str, port: u16, upstream: Option<UpstreamProtocol>) -> String {
+    let mut block = format!(
+        "  - name: {app_name}_{port}\n    connect_timeout: 2s\n    type: STRICT_DNS\n    lb_policy: ROUND_ROBIN\n    load_assignment:\n      cluster_name: {app_name}_{port}\n      endpoints:\n      - lb_endpoints:\n        - endpoint:\n            address:\n              socket_address:\n                address: {app_name}\n                port_value: {port}\n",
+    );
+    if let Some(upstream) = upstream {
+        let _ = write!(
+            block,
+            "    typed_extension_protocol_options:\n      envoy.extensions.upstreams.http.v3.HttpProtocolOptions:\n        \"@type\": type.googleapis.com/envoy.extensions.upstreams.http.v3.HttpProtocolOptions\n        {}",
+            upstream.http_config()
+        );
+    }
+    block
+}
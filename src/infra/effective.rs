@@ -0,0 +1,299 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+pub const EFFECTIVE_CONFIG_FILE: &str = "effective-config.json";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceConfig {
+    pub environment: Vec<String>,
+    pub env_files: Vec<String>,
+    pub fallbacks: Vec<Fallback>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fallback {
+    pub variable: String,
+    pub default: String,
+    pub empty: bool,
+}
+
+pub fn write_effective_config(path: &Path, compose_path: &Path, resolved: &Value) {
+    let raw = fs::read_to_string(compose_path)
+        .ok()
+        .and_then(|contents| serde_yaml::from_str::<Value>(&contents).ok())
+        .unwrap_or_default();
+    let dotenv = compose_path
+        .parent()
+        .and_then(|dir| fs::read_to_string(dir.join(".env")).ok())
+        .map(|contents| parse_dotenv(&contents))
+        .unwrap_or_default();
+    let lookup = |name: &str| env::var(name).ok().or_else(|| dotenv.get(name).cloned());
+    let config = effective_config(&raw, resolved, &lookup);
+    match serde_json::to_vec_pretty(&config) {
+        Ok(payload) => {
+            if let Err(err) = fs::write(path, payload) {
+                eprintln!("[compose] failed to write effective config: {err}");
+            }
+        }
+        Err(err) => eprintln!("[compose] failed to serialize effective config: {err}"),
+    }
+}
+
+pub fn load_effective_config(run_dir: Option<&Path>) -> BTreeMap<String, ServiceConfig> {
+    run_dir
+        .and_then(|dir| fs::read(dir.join(EFFECTIVE_CONFIG_FILE)).ok())
+        .and_then(|payload| serde_json::from_slice(&payload).ok())
+        .unwrap_or_default()
+}
+
+pub fn effective_config(
+    raw: &Value,
+    resolved: &Value,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> BTreeMap<String, ServiceConfig> {
+    let Some(services) = resolved.get("services").and_then(Value::as_mapping) else {
+        return BTreeMap::new();
+    };
+    services
+        .iter()
+        .filter_map(|(name, service)| {
+            let name = name.as_str()?;
+            let raw_service = raw.get("services").and_then(|services| services.get(name));
+            let mut fallbacks = Vec::new();
+            if let Some(raw_service) = raw_service {
+                collect_fallbacks(raw_service, lookup, &mut fallbacks);
+            }
+            fallbacks.sort_by(|a, b| a.variable.cmp(&b.variable));
+            fallbacks.dedup();
+            Some((
+                name.to_string(),
+                ServiceConfig {
+                    environment: environment_entries(service.get("environment")),
+                    env_files: env_file_paths(raw_service.and_then(|raw| raw.get("env_file"))),
+                    fallbacks,
+                },
+            ))
+        })
+        .collect()
+}
+
+fn environment_entries(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Mapping(map)) => map
+            .iter()
+            .filter_map(|(key, value)| {
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    Value::Null => String::new(),
+                    other => serde_yaml::to_string(other)
+                        .map(|text| text.trim().to_string())
+                        .unwrap_or_default(),
+                };
+                Some(format!("{}={value}", key.as_str()?))
+            })
+            .collect(),
+        Some(Value::Sequence(list)) => list
+            .iter()
+            .filter_map(Value::as_str)
+            .map(ToString::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn env_file_paths(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::String(path)) => vec![path.clone()],
+        Some(Value::Sequence(list)) => list
+            .iter()
+            .filter_map(|entry| {
+                entry
+                    .as_str()
+                    .or_else(|| entry.get("path").and_then(Value::as_str))
+            })
+            .map(ToString::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn collect_fallbacks(
+    value: &Value,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    fallbacks: &mut Vec<Fallback>,
+) {
+    match value {
+        Value::String(text) => fallbacks.extend(
+            references(text)
+                .into_iter()
+                .filter_map(|reference| reference.fallback(lookup)),
+        ),
+        Value::Sequence(list) => {
+            for item in list {
+                collect_fallbacks(item, lookup, fallbacks);
+            }
+        }
+        Value::Mapping(map) => {
+            for (key, item) in map {
+                collect_fallbacks(key, lookup, fallbacks);
+                collect_fallbacks(item, lookup, fallbacks);
+            }
+        }
+        _ => {}
+    }
+}
+
+struct Reference {
+    name: String,
+    operator: Option<&'static str>,
+    default: String,
+}
+
+impl Reference {
+    fn fallback(self, lookup: &dyn Fn(&str) -> Option<String>) -> Option<Fallback> {
+        let value = lookup(&self.name);
+        let default = match self.operator {
+            None => String::new(),
+            Some(":-") => self.default,
+            Some("-") if value.is_none() => self.default,
+            Some(_) => return None,
+        };
+        let fell_back = match self.operator {
+            Some(":-") => value.as_deref().is_none_or(str::is_empty),
+            _ => value.is_none(),
+        };
+        fell_back.then_some(Fallback {
+            variable: self.name,
+            empty: default.is_empty(),
+            default,
+        })
+    }
+}
+
+fn references(text: &str) -> Vec<Reference> {
+    let mut found = Vec::new();
+    let mut rest = text;
+    while let Some(idx) = rest.find('$') {
+        let after = rest.get(idx + 1..).unwrap_or_default();
+        if let Some(escaped) = after.strip_prefix('$') {
+            rest = escaped;
+            continue;
+        }
+        if let Some(inner) = after.strip_prefix('{') {
+            let Some(end) = inner.find('}') else {
+                break;
+            };
+            let body = inner.get(..end).unwrap_or_default();
+            found.extend(parse_braced(body));
+            rest = inner.get(end + 1..).unwrap_or_default();
+            continue;
+        }
+        let name: String = after
+            .chars()
+            .take_while(|ch| ch.is_ascii_alphanumeric() || *ch == '_')
+            .collect();
+        rest = after.get(name.len()..).unwrap_or_default();
+        if !name.is_empty() {
+            found.push(Reference {
+                name,
+                operator: None,
+                default: String::new(),
+            });
+        }
+    }
+    found
+}
+
+fn parse_braced(body: &str) -> Option<Reference> {
+    const OPERATORS: [&str; 6] = [":-", ":?", ":+", "-", "?", "+"];
+    let split = body
+        .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+        .unwrap_or(body.len());
+    let name = body.get(..split)?;
+    if name.is_empty() {
+        return None;
+    }
+    let tail = body.get(split..).unwrap_or_default();
+    if tail.is_empty() {
+        return Some(Reference {
+            name: name.to_string(),
+            operator: None,
+            default: String::new(),
+        });
+    }
+    let operator = OPERATORS.into_iter().find(|op| tail.starts_with(op))?;
+    Some(Reference {
+        name: name.to_string(),
+        operator: Some(operator),
+        default: tail.get(operator.len()..).unwrap_or_default().to_string(),
+    })
+}
+
+fn parse_dotenv(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .or_else(|| {
+                    value
+                        .strip_prefix('\'')
+                        .and_then(|value| value.strip_suffix('\''))
+                })
+                .unwrap_or(value);
+            Some((key.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{effective_config, Fallback};
+
+    #[test]
+    #[allow(clippy::literal_string_with_formatting_args)]
+    fn flags_variables_that_fell_back_to_defaults() {
+        let raw = serde_yaml::from_str(
+            "services:\n  api:\n    image: \"api:${TAG:-latest}\"\n    env_file: [.env.api]\n    environment:\n      DATABASE_URL: ${DATABASE_URL}\n      LOG_LEVEL: ${LOG_LEVEL-info}\n      REGION: ${REGION:-}\n      PRICE: $$5\n      HOME_DIR: $HOME_DIR\n",
+        )
+        .unwrap_or_default();
+        let resolved = serde_yaml::from_str(
+            "services:\n  api:\n    image: api:latest\n    environment:\n      DATABASE_URL: \"\"\n      LOG_LEVEL: debug\n      REGION: \"\"\n      PRICE: $5\n      HOME_DIR: /home/app\n",
+        )
+        .unwrap_or_default();
+        let lookup = |name: &str| match name {
+            "LOG_LEVEL" => Some("debug".to_string()),
+            "HOME_DIR" => Some("/home/app".to_string()),
+            "REGION" => Some(String::new()),
+            _ => None,
+        };
+        let config = effective_config(&raw, &resolved, &lookup);
+        let api = config.get("api").cloned().unwrap_or_default();
+        assert_eq!(api.env_files, vec![".env.api".to_string()]);
+        assert!(api.environment.contains(&"PRICE=$5".to_string()));
+        let fallback = |variable: &str, default: &str| Fallback {
+            variable: variable.to_string(),
+            default: default.to_string(),
+            empty: default.is_empty(),
+        };
+        assert_eq!(
+            api.fallbacks,
+            vec![
+                fallback("DATABASE_URL", ""),
+                fallback("REGION", ""),
+                fallback("TAG", "latest"),
+            ]
+        );
+    }
+}
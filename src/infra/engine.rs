@@ -1,39 +1,112 @@
 use std::collections::HashMap;
 use std::env;
-use std::net::IpAddr;
-use std::process::{Command, Stdio};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::process::{Command, Output, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::Value;
 
 use crate::domain::{EngineKind, Scope};
+use crate::infra::backend::{service_name_from_inspect, CliBackend, EngineBackend, LogStream};
 use crate::infra::compose::{
-    collect_docker_container_ids_by_label, collect_docker_container_ids_by_label_key,
-    collect_docker_container_ids_by_labels, collect_podman_container_ids,
-    collect_podman_container_ids_by_label, collect_podman_container_ids_by_label_key,
-    collect_podman_container_ids_by_labels, collect_podman_container_ids_by_name,
-    remove_project_pods, resolve_service_name_docker, resolve_service_name_podman,
+    collect_podman_container_ids, collect_podman_container_ids_by_name, remove_project_pods,
+};
+use crate::infra::engine_api::ApiBackend;
+use crate::infra::image::{image_metadata, ImageMetadata};
+use crate::infra::process::{run_output, run_output_timeout};
+use crate::support::args::is_truthy;
+use crate::support::constants::{
+    ENVOY_ADMIN_PORT, ONEOFF_LABEL, PROXY_LABEL, PROXY_NAME_LABEL, RUN_ID_LABEL, SERVICE_LABEL,
 };
-use crate::infra::process::run_output;
-use crate::support::constants::{PROXY_LABEL, RUN_ID_LABEL};
+
+const INSPECT_WORKERS: usize = 8;
+const CONTAINER_NUMBER_LABEL: &str = "com.docker.compose.container-number";
+const LOCAL_HOST: &str = "localhost";
+const SHUTDOWN_STOP_TIMEOUT: &str = "10";
 
 pub struct ContainerInfo {
     pub id: String,
     pub service: Option<String>,
+    pub image: Option<String>,
+    pub image_id: Option<String>,
     pub ips: Vec<IpAddr>,
+    pub network_ips: Vec<(String, IpAddr)>,
+    pub gateways: Vec<IpAddr>,
+    pub networks: Vec<String>,
     pub labels: HashMap<String, String>,
+    pub env: Vec<String>,
+    pub state: Option<String>,
+    pub health: Option<String>,
+    pub restart_count: u64,
+    pub exit_code: Option<i64>,
+    pub restart_policy: Option<String>,
+    pub instance: Option<u32>,
+}
+
+impl ContainerInfo {
+    pub fn is_completed_job(&self) -> bool {
+        self.state.as_deref() == Some("exited")
+            && self.exit_code == Some(0)
+            && self
+                .restart_policy
+                .as_deref()
+                .is_none_or(|policy| policy.is_empty() || policy == "no")
+    }
+
+    pub fn display_state(&self) -> Option<String> {
+        if self.is_completed_job() {
+            return Some("completed".to_string());
+        }
+        self.state.clone()
+    }
+
+    pub fn is_proxy(&self) -> bool {
+        self.labels
+            .get(PROXY_LABEL)
+            .is_some_and(|value| is_truthy(value))
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.state.as_deref() == Some("running")
+            && self
+                .health
+                .as_deref()
+                .is_none_or(|health| health == "healthy")
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ContainerStats {
+    pub cpu_percent: Option<f64>,
+    pub memory: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct RunContainers {
+    engine: Engine,
+    run_id: String,
 }
 
 #[derive(Clone)]
 pub struct Engine {
     kind: EngineKind,
     connection: Option<String>,
+    endpoint_host: String,
     podman_cmd: Vec<String>,
     docker_cmd: Vec<String>,
+    backend: Arc<dyn EngineBackend>,
 }
 
 pub struct CleanupContext<'a> {
     pub compose_cmd: &'a [String],
     pub compose_file: &'a str,
+    pub run_id: &'a str,
     pub project_name: &'a str,
     pub project_args: &'a [String],
+    pub keep_volumes: bool,
 }
 
 impl Engine {
@@ -45,32 +118,71 @@ impl Engine {
         } else {
             None
         };
+        let remote_url = remote_engine_url(kind);
         let mut podman_cmd = vec!["podman".to_string()];
         if let Some(ref conn) = connection {
             podman_cmd.push("--connection".to_string());
-            podman_cmd.push(conn.to_string());
+            podman_cmd.push(conn.clone());
+        } else if remote_url.is_some() {
+            podman_cmd.push("--remote".to_string());
         }
         let docker_cmd = vec!["docker".to_string()];
+        let remote = connection.is_some() || remote_url.is_some();
+        let backend = select_backend(kind, remote, &podman_cmd, &docker_cmd);
+        let endpoint_host = remote_url
+            .as_deref()
+            .and_then(host_from_engine_url)
+            .or_else(|| connection.as_deref().and_then(podman_connection_host))
+            .unwrap_or_else(|| LOCAL_HOST.to_string());
         Self {
             kind,
             connection,
+            endpoint_host,
             podman_cmd,
             docker_cmd,
+            backend,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_backend(kind: EngineKind, backend: Arc<dyn EngineBackend>) -> Self {
+        Self {
+            kind,
+            connection: None,
+            endpoint_host: LOCAL_HOST.to_string(),
+            podman_cmd: vec!["podman".to_string()],
+            docker_cmd: vec!["docker".to_string()],
+            backend,
         }
     }
 
     pub fn with_connection(mut self, connection: Option<String>) -> Self {
         if let Some(conn) = connection {
+            self.endpoint_host =
+                podman_connection_host(&conn).unwrap_or_else(|| LOCAL_HOST.to_string());
             self.connection = Some(conn.clone());
             self.podman_cmd = vec!["podman".to_string(), "--connection".to_string(), conn];
+            self.backend = Arc::new(CliBackend::new(self.kind, self.podman_cmd.clone()));
         }
         self
     }
 
+    pub const fn kind(&self) -> EngineKind {
+        self.kind
+    }
+
     pub fn connection(&self) -> Option<String> {
         self.connection.clone()
     }
 
+    pub fn endpoint_host(&self) -> &str {
+        &self.endpoint_host
+    }
+
+    pub fn is_remote(&self) -> bool {
+        self.endpoint_host != LOCAL_HOST
+    }
+
     pub const fn supports_watchdog(&self) -> bool {
         matches!(self.kind, EngineKind::Podman)
     }
@@ -92,69 +204,127 @@ impl Engine {
     }
 
     pub fn collect_run_container_ids(&self, run_id: &str, scope: Scope) -> Vec<String> {
-        match self.kind {
-            EngineKind::Podman => {
-                collect_podman_container_ids_by_label(&self.podman_cmd, RUN_ID_LABEL, run_id, scope)
-            }
-            EngineKind::Docker => {
-                collect_docker_container_ids_by_label(&self.docker_cmd, RUN_ID_LABEL, run_id, scope)
-            }
-        }
+        self.backend
+            .container_ids(&[(RUN_ID_LABEL, Some(run_id))], scope)
     }
 
     pub fn collect_run_proxy_container_ids(&self, run_id: &str, scope: Scope) -> Vec<String> {
-        let labels = [(RUN_ID_LABEL, run_id), (PROXY_LABEL, "true")];
-        match self.kind {
-            EngineKind::Podman => {
-                collect_podman_container_ids_by_labels(&self.podman_cmd, &labels, scope)
-            }
-            EngineKind::Docker => {
-                collect_docker_container_ids_by_labels(&self.docker_cmd, &labels, scope)
-            }
-        }
+        let labels = [(RUN_ID_LABEL, Some(run_id)), (PROXY_LABEL, Some("true"))];
+        self.backend.container_ids(&labels, scope)
     }
 
     pub fn collect_container_ids_with_label(&self, label_key: &str, scope: Scope) -> Vec<String> {
-        match self.kind {
-            EngineKind::Podman => {
-                collect_podman_container_ids_by_label_key(&self.podman_cmd, label_key, scope)
-            }
-            EngineKind::Docker => {
-                collect_docker_container_ids_by_label_key(&self.docker_cmd, label_key, scope)
-            }
-        }
+        self.backend.container_ids(&[(label_key, None)], scope)
     }
 
-    pub fn resolve_service_name(&self, project_name: &str, cid: &str) -> String {
-        match self.kind {
-            EngineKind::Podman => resolve_service_name_podman(&self.podman_cmd, project_name, cid),
-            EngineKind::Docker => resolve_service_name_docker(&self.docker_cmd, project_name, cid),
-        }
+    pub fn resolve_service_names(&self, project_name: &str, ids: &[String]) -> Vec<String> {
+        let items = self.backend.inspect(ids);
+        let names: Vec<Option<String>> = ids
+            .iter()
+            .map(|cid| {
+                items
+                    .iter()
+                    .find(|item| {
+                        item.get("Id")
+                            .and_then(Value::as_str)
+                            .is_some_and(|id| id.starts_with(cid.as_str()))
+                    })
+                    .and_then(|item| service_name_from_inspect(item, self.kind, project_name))
+            })
+            .collect();
+        let missing: Vec<&String> = ids
+            .iter()
+            .zip(&names)
+            .filter(|(_, name)| name.is_none())
+            .map(|(cid, _)| cid)
+            .collect();
+        let mut resolved =
+            parallel_map(&missing, |cid| self.backend.service_name(project_name, cid)).into_iter();
+        names
+            .into_iter()
+            .zip(ids)
+            .map(|(name, cid)| {
+                name.or_else(|| resolved.next().flatten())
+                    .unwrap_or_else(|| cid.clone())
+            })
+            .collect()
     }
 
-    pub fn logs_cmd(&self, cid: &str, timestamps_enabled: bool) -> Vec<String> {
-        let mut command = match self.kind {
-            EngineKind::Podman => self.podman_cmd.clone(),
-            EngineKind::Docker => self.docker_cmd.clone(),
+    pub fn logs(
+        &self,
+        cid: &str,
+        timestamps_enabled: bool,
+        since: Option<i64>,
+    ) -> io::Result<LogStream> {
+        self.backend.logs(cid, timestamps_enabled, since)
+    }
+
+    pub fn container_events(&self, run_id: &str) -> io::Result<LogStream> {
+        self.backend.events(&[(RUN_ID_LABEL, Some(run_id))])
+    }
+
+    pub fn collect_oneoff_container_ids(&self, run_id: &str) -> Vec<String> {
+        let labels = [(RUN_ID_LABEL, Some(run_id)), (ONEOFF_LABEL, Some("true"))];
+        self.backend.container_ids(&labels, Scope::All)
+    }
+
+    pub fn cleanup_project(&self, context: &CleanupContext<'_>) -> Result<(), String> {
+        let oneoffs = self.collect_oneoff_container_ids(context.run_id);
+        let mut errors: Vec<String> = self.remove_containers(&oneoffs).err().into_iter().collect();
+        Self::compose_down(context);
+        if matches!(self.kind, EngineKind::Podman) {
+            self.cleanup_podman_project(context);
+        }
+        errors.extend(self.remove_labeled_resources(context.run_id, context.keep_volumes));
+        // `compose down` failures only matter when they leave containers behind.
+        let leftover = if context.run_id.is_empty() {
+            0
+        } else {
+            self.collect_run_container_ids(context.run_id, Scope::All)
+                .len()
         };
-        command.push("logs".to_string());
-        command.push("--follow".to_string());
-        if timestamps_enabled {
-            command.push("--timestamps".to_string());
+        if leftover > 0 {
+            errors.push(format!(
+                "{leftover} container(s) of {} were not removed",
+                context.run_id
+            ));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
         }
-        command.push(cid.to_string());
-        command
     }
 
-    pub fn cleanup_project(&self, context: &CleanupContext<'_>) {
-        Self::compose_down(
-            context.compose_cmd,
-            context.compose_file,
-            context.project_args,
-        );
-        if !matches!(self.kind, EngineKind::Podman) {
+    pub fn stop_in_order(&self, run_id: &str, tiers: &[Vec<String>]) {
+        if tiers.len() < 2 {
             return;
         }
+        let ids = self.collect_run_container_ids(run_id, Scope::Running);
+        let containers = self.inspect_containers(&ids);
+        for tier in tiers {
+            let ids: Vec<String> = containers
+                .iter()
+                .filter(|info| {
+                    info.labels
+                        .get(SERVICE_LABEL)
+                        .or(info.service.as_ref())
+                        .is_some_and(|service| tier.contains(service))
+                })
+                .map(|info| info.id.clone())
+                .collect();
+            if ids.is_empty() {
+                continue;
+            }
+            eprintln!("[compose] stopping {}", tier.join(", "));
+            let mut cmd = self.cli_cmd();
+            cmd.extend(["stop", "-t", SHUTDOWN_STOP_TIMEOUT].map(ToString::to_string));
+            cmd.extend(ids);
+            let _ = run_output(&cmd);
+        }
+    }
+
+    fn cleanup_podman_project(&self, context: &CleanupContext<'_>) {
         remove_project_pods(&self.podman_cmd, context.project_name);
         let mut ids =
             collect_podman_container_ids(&self.podman_cmd, context.project_name, Scope::All);
@@ -168,71 +338,676 @@ impl Engine {
             let mut cmd = self.podman_cmd.clone();
             cmd.push("rm".to_string());
             cmd.push("-f".to_string());
+            if !context.keep_volumes {
+                cmd.push("-v".to_string());
+            }
             cmd.extend(ids);
             let _ = run_output(&cmd);
         }
     }
 
-    fn compose_down(compose_cmd: &[String], compose_file: &str, project_args: &[String]) {
-        let Some((compose_bin, compose_args)) = compose_cmd.split_first() else {
+    fn remove_labeled_resources(&self, run_id: &str, keep_volumes: bool) -> Vec<String> {
+        let mut errors = Vec::new();
+        if run_id.is_empty() {
+            return errors;
+        }
+        let kinds: &[&str] = if keep_volumes {
+            &["network"]
+        } else {
+            &["network", "volume"]
+        };
+        let filter = format!("label={RUN_ID_LABEL}={run_id}");
+        for kind in kinds {
+            let mut list = self.cli_cmd();
+            list.extend([kind.to_string(), "ls".to_string(), "-q".to_string()]);
+            list.extend(["--filter".to_string(), filter.clone()]);
+            let Ok(output) = run_output(&list) else {
+                continue;
+            };
+            let names: Vec<String> = String::from_utf8_lossy(&output.stdout)
+                .split_whitespace()
+                .map(ToString::to_string)
+                .collect();
+            if names.is_empty() {
+                continue;
+            }
+            let mut remove = self.cli_cmd();
+            remove.extend([kind.to_string(), "rm".to_string()]);
+            remove.extend(names);
+            match run_output(&remove) {
+                Ok(output) if output.status.success() => {}
+                Ok(output) => errors.push(format!(
+                    "failed to remove {kind}s: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )),
+                Err(err) => errors.push(format!("failed to remove {kind}s: {err}")),
+            }
+        }
+        errors
+    }
+
+    fn cli_cmd(&self) -> Vec<String> {
+        match self.kind {
+            EngineKind::Podman => self.podman_cmd.clone(),
+            EngineKind::Docker => self.docker_cmd.clone(),
+        }
+    }
+
+    pub fn remove_containers(&self, ids: &[String]) -> Result<(), String> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let mut cmd = self.cli_cmd();
+        cmd.push("rm".to_string());
+        cmd.push("-f".to_string());
+        cmd.extend(ids.iter().cloned());
+        match run_output(&cmd) {
+            Ok(output) if output.status.success() => Ok(()),
+            Ok(output) => Err(format!(
+                "failed to remove containers: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+            Err(err) => Err(format!("failed to remove containers: {err}")),
+        }
+    }
+
+    pub fn image_exists(&self, image: &str) -> bool {
+        let mut cmd = self.cli_cmd();
+        cmd.extend([
+            "image".to_string(),
+            "inspect".to_string(),
+            image.to_string(),
+        ]);
+        run_output(&cmd).is_ok_and(|output| output.status.success())
+    }
+
+    pub fn pull_image(&self, image: &str) -> Result<(), String> {
+        let mut cmd = self.cli_cmd();
+        cmd.extend(["pull".to_string(), image.to_string()]);
+        let Some((program, args)) = cmd.split_first() else {
+            return Err("engine command is empty".to_string());
+        };
+        let output = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::from(io::stderr()))
+            .stderr(Stdio::piped())
+            .output()
+            .map_err(|err| err.to_string())?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.trim().lines().last().unwrap_or_default().to_string();
+        Err(if message.is_empty() {
+            format!("pull exited with {}", output.status)
+        } else {
+            message
+        })
+    }
+
+    pub fn run_container(&self, args: &[String]) -> Result<i32, String> {
+        let mut cmd = self.cli_cmd();
+        cmd.push("run".to_string());
+        cmd.extend(args.iter().cloned());
+        let Some((program, args)) = cmd.split_first() else {
+            return Err("engine command is empty".to_string());
+        };
+        let status = Command::new(program)
+            .args(args)
+            .status()
+            .map_err(|err| format!("failed to start container: {err}"))?;
+        Ok(status.code().unwrap_or(1))
+    }
+
+    pub fn start_container(&self, args: &[String]) -> Result<String, String> {
+        let mut cmd = self.cli_cmd();
+        cmd.push("run".to_string());
+        cmd.extend(args.iter().cloned());
+        match run_output(&cmd) {
+            Ok(output) if output.status.success() => {
+                Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+            }
+            Ok(output) => Err(format!(
+                "failed to start container: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+            Err(err) => Err(format!("failed to start container: {err}")),
+        }
+    }
+
+    pub fn published_port(&self, id: &str, port: u16) -> Option<SocketAddr> {
+        self.inspect_raw(&[id.to_string()])
+            .first()
+            .and_then(|raw| published_address(raw, port))
+    }
+
+    pub fn rootless_network(&self) -> Option<String> {
+        if !self.is_podman() {
+            return None;
+        }
+        let query = |template: &str| {
+            let mut cmd = self.podman_cmd.clone();
+            cmd.extend([
+                "info".to_string(),
+                "--format".to_string(),
+                template.to_string(),
+            ]);
+            run_output(&cmd)
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        };
+        if query("{{.Host.Security.Rootless}}")? != "true" {
+            return None;
+        }
+        Some(rootless_network_name(
+            query("{{.Host.RootlessNetworkCmd}}").as_deref(),
+        ))
+    }
+
+    pub fn architecture(&self) -> Option<String> {
+        let template = if self.is_podman() {
+            "{{.Host.Arch}}"
+        } else {
+            "{{.Architecture}}"
+        };
+        let mut cmd = self.cli_cmd();
+        cmd.extend([
+            "info".to_string(),
+            "--format".to_string(),
+            template.to_string(),
+        ]);
+        run_output(&cmd)
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .filter(|arch| !arch.is_empty())
+    }
+
+    pub fn diagnostics(&self, timeout: Duration) -> Vec<String> {
+        let query = |args: &[&str]| {
+            let mut cmd = self.cli_cmd();
+            cmd.extend(args.iter().map(ToString::to_string));
+            run_output_timeout(&cmd, timeout).map_or_else(
+                || format!("no answer within {}s", timeout.as_secs()),
+                |output| summarize_output(&output),
+            )
+        };
+        if !self.is_podman() {
+            return vec![format!(
+                "engine: {}",
+                query(&["version", "--format", "{{.Server.Version}}"])
+            )];
+        }
+        vec![
+            format!(
+                "engine: {}",
+                query(&["info", "--format", "{{.Version.Version}}"])
+            ),
+            format!(
+                "machine: {}",
+                query(&[
+                    "machine",
+                    "list",
+                    "--format",
+                    "{{.Name}} running={{.Running}} last_up={{.LastUp}}",
+                ])
+            ),
+        ]
+    }
+
+    pub fn local_image_tags(&self, repository: &str) -> Vec<String> {
+        let mut cmd = self.cli_cmd();
+        cmd.extend([
+            "images".to_string(),
+            repository.to_string(),
+            "--format".to_string(),
+            "{{.Repository}}:{{.Tag}}".to_string(),
+        ]);
+        let Ok(output) = run_output(&cmd) else {
+            return Vec::new();
+        };
+        if !output.status.success() {
+            return Vec::new();
+        }
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.ends_with(":<none>"))
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    fn compose_down(context: &CleanupContext<'_>) {
+        let Some((compose_bin, compose_args)) = context.compose_cmd.split_first() else {
             return;
         };
         let mut command = Command::new(compose_bin);
         command
             .args(compose_args)
             .arg("-f")
-            .arg(compose_file)
-            .args(project_args)
+            .arg(context.compose_file)
+            .args(context.project_args)
             .arg("down")
-            .arg("--remove-orphans")
-            .arg("--volumes")
+            .arg("--remove-orphans");
+        if !context.keep_volumes {
+            command.arg("--volumes");
+        }
+        command
             .env_remove("COMPOSE_PROJECT_NAME")
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
         let _ = command.output();
     }
 
+    pub fn compose_lifecycle(
+        compose_cmd: &[String],
+        compose_file: &str,
+        action: &str,
+    ) -> Result<(), String> {
+        let Some((compose_bin, compose_args)) = compose_cmd.split_first() else {
+            return Err("compose command is empty".to_string());
+        };
+        let output = Command::new(compose_bin)
+            .args(compose_args)
+            .arg("-f")
+            .arg(compose_file)
+            .arg(action)
+            .env_remove("COMPOSE_PROJECT_NAME")
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|err| format!("compose {action} failed: {err}"))?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!(
+            "compose {action} failed: {}",
+            stderr.trim().lines().last().unwrap_or_default()
+        ))
+    }
+
     pub fn inspect_containers(&self, ids: &[String]) -> Vec<ContainerInfo> {
+        self.backend
+            .inspect(ids)
+            .iter()
+            .map(container_info)
+            .collect()
+    }
+
+    pub fn inspect_raw(&self, ids: &[String]) -> Vec<Value> {
+        self.backend.inspect(ids)
+    }
+
+    pub fn inspect_images(&self, ids: &[String]) -> Vec<Value> {
+        self.backend.inspect_images(ids)
+    }
+
+    pub fn container_stats(&self, ids: &[String]) -> HashMap<String, ContainerStats> {
         if ids.is_empty() {
-            return Vec::new();
+            return HashMap::new();
         }
-        let mut cmd = match self.kind {
-            EngineKind::Podman => self.podman_cmd.clone(),
-            EngineKind::Docker => self.docker_cmd.clone(),
-        };
-        cmd.push("inspect".to_string());
+        let mut cmd = self.cli_cmd();
+        cmd.extend([
+            "stats".to_string(),
+            "--no-stream".to_string(),
+            "--format".to_string(),
+            "{{.ID}}\t{{.CPUPerc}}\t{{.MemUsage}}".to_string(),
+        ]);
         cmd.extend(ids.iter().cloned());
         let Ok(output) = run_output(&cmd) else {
-            return Vec::new();
+            return HashMap::new();
         };
-        let value: serde_json::Value = match serde_json::from_slice(&output.stdout) {
-            Ok(value) => value,
-            Err(_) => return Vec::new(),
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(parse_stats_line)
+            .filter_map(|(short_id, stats)| {
+                ids.iter()
+                    .find(|id| !short_id.is_empty() && id.starts_with(&short_id))
+                    .map(|id| (id.clone(), stats))
+            })
+            .collect()
+    }
+}
+
+impl RunContainers {
+    pub const fn new(engine: Engine, run_id: String) -> Self {
+        Self { engine, run_id }
+    }
+
+    pub fn endpoint_host(&self) -> &str {
+        self.engine.endpoint_host()
+    }
+
+    pub fn service_env(&self, service: &str) -> Option<Vec<String>> {
+        self.service_container(service).map(|info| info.env)
+    }
+
+    pub fn service_image(&self, service: &str) -> Option<ImageMetadata> {
+        let info = self.service_container(service)?;
+        let images = self.engine.inspect_images(info.image_id.as_slice());
+        Some(image_metadata(&info, &images))
+    }
+
+    fn service_container(&self, service: &str) -> Option<ContainerInfo> {
+        let ids = self
+            .engine
+            .collect_run_container_ids(&self.run_id, Scope::Running);
+        self.engine
+            .inspect_containers(&ids)
+            .into_iter()
+            .filter(|info| !info.is_proxy())
+            .find(|info| {
+                info.labels
+                    .get(SERVICE_LABEL)
+                    .or(info.service.as_ref())
+                    .is_some_and(|name| name == service)
+            })
+    }
+
+    fn proxy_container(&self, service: &str) -> Option<Value> {
+        let ids = self
+            .engine
+            .collect_run_proxy_container_ids(&self.run_id, Scope::Running);
+        self.engine.inspect_raw(&ids).into_iter().find(|raw| {
+            extract_labels_map(raw)
+                .get(PROXY_NAME_LABEL)
+                .is_some_and(|name| name == service)
+        })
+    }
+
+    pub fn proxy_admin_addr(&self, service: &str) -> Option<SocketAddr> {
+        self.proxy_container(service)
+            .and_then(|raw| published_address(&raw, ENVOY_ADMIN_PORT))
+    }
+
+    pub fn service_networks(&self, service: &str) -> Vec<String> {
+        let mut networks: Vec<String> = self
+            .proxy_container(service)
+            .map(|raw| container_info(&raw).networks)
+            .unwrap_or_default();
+        networks.extend(
+            self.service_container(service)
+                .map(|info| info.networks)
+                .unwrap_or_default(),
+        );
+        networks
+    }
+
+    pub fn service_ips(&self, service: &str) -> Vec<IpAddr> {
+        let mut ips: Vec<IpAddr> = self
+            .proxy_container(service)
+            .map(|raw| container_info(&raw).ips)
+            .unwrap_or_default();
+        ips.extend(
+            self.service_container(service)
+                .map(|info| info.ips)
+                .unwrap_or_default(),
+        );
+        ips.dedup();
+        ips
+    }
+}
+
+fn summarize_output(output: &Output) -> String {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    if output.status.success() {
+        return if lines.is_empty() {
+            "none".to_string()
+        } else {
+            lines.join("; ")
         };
-        let Some(list) = value.as_array() else {
-            return Vec::new();
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    stderr
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .map_or_else(
+            || format!("failed ({})", output.status),
+            ToString::to_string,
+        )
+}
+
+pub fn scaled_instances(
+    containers: &[ContainerInfo],
+    service_aliases: &HashMap<String, String>,
+) -> HashMap<String, u32> {
+    let mut replicas: HashMap<&str, Vec<(&str, u32)>> = HashMap::new();
+    for container in containers.iter().filter(|container| !container.is_proxy()) {
+        let (Some(service), Some(instance)) = (container.service.as_deref(), container.instance)
+        else {
+            continue;
         };
-        let mut info = Vec::new();
-        for item in list {
-            let id = item
-                .get("Id")
-                .and_then(|value| value.as_str())
-                .unwrap_or("")
-                .to_string();
-            let labels_map = extract_labels_map(item);
-            let service = labels_map
-                .get("com.docker.compose.service")
-                .or_else(|| labels_map.get("io.podman.compose.service"))
-                .cloned();
-            let ip_addresses = extract_ips(item);
-            info.push(ContainerInfo {
-                id,
-                service,
-                ips: ip_addresses,
-                labels: labels_map,
-            });
-        }
-        info
+        let service = service_aliases.get(service).map_or(service, String::as_str);
+        replicas
+            .entry(service)
+            .or_default()
+            .push((container.id.as_str(), instance));
+    }
+    replicas
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .flatten()
+        .map(|(id, instance)| (id.to_string(), instance))
+        .collect()
+}
+
+pub fn run_settled(services: usize, containers: &[ContainerInfo]) -> bool {
+    let settled = containers
+        .iter()
+        .filter(|container| {
+            container.state.as_deref() == Some("running") || container.is_completed_job()
+        })
+        .count();
+    services > 0 && settled >= services
+}
+
+pub fn parallel_map<T, R, F>(items: &[T], map: F) -> Vec<Option<R>>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    if items.len() <= 1 {
+        return items.iter().map(|item| Some(map(item))).collect();
+    }
+    let chunk_size = items.len().div_ceil(INSPECT_WORKERS);
+    let map = &map;
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let handle = scope.spawn(move || chunk.iter().map(map).collect::<Vec<_>>());
+                (chunk.len(), handle)
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|(len, handle)| {
+                handle.join().map_or_else(
+                    |_| std::iter::repeat_with(|| None).take(len).collect(),
+                    |results| results.into_iter().map(Some).collect::<Vec<_>>(),
+                )
+            })
+            .collect()
+    })
+}
+
+fn published_address(item: &Value, port: u16) -> Option<SocketAddr> {
+    item.get("NetworkSettings")
+        .and_then(|settings| settings.get("Ports"))
+        .and_then(|ports| ports.get(format!("{port}/tcp")))
+        .and_then(Value::as_array)?
+        .iter()
+        .find_map(|binding| {
+            let host_port = binding
+                .get("HostPort")
+                .and_then(Value::as_str)
+                .and_then(|value| value.parse().ok())?;
+            let ip = binding
+                .get("HostIp")
+                .and_then(Value::as_str)
+                .and_then(|value| value.trim_matches(['[', ']']).parse::<IpAddr>().ok())
+                .filter(|ip| !ip.is_unspecified())
+                .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+            Some(SocketAddr::new(ip, host_port))
+        })
+}
+
+fn container_info(item: &serde_json::Value) -> ContainerInfo {
+    let id = item
+        .get("Id")
+        .and_then(|value| value.as_str())
+        .unwrap_or("")
+        .to_string();
+    let labels_map = extract_labels_map(item);
+    let service = labels_map
+        .get("com.docker.compose.service")
+        .or_else(|| labels_map.get("io.podman.compose.service"))
+        .cloned();
+    let instance = labels_map
+        .get(CONTAINER_NUMBER_LABEL)
+        .and_then(|number| number.parse().ok());
+    let env = item
+        .get("Config")
+        .and_then(|config| config.get("Env"))
+        .and_then(|value| value.as_array())
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|entry| entry.as_str().map(ToString::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    let health = item
+        .get("State")
+        .and_then(|state| state.get("Health").or_else(|| state.get("Healthcheck")))
+        .and_then(|health| health.get("Status"))
+        .and_then(|value| value.as_str())
+        .filter(|status| !status.is_empty())
+        .map(ToString::to_string);
+    let network_ips = extract_network_ips(item);
+    let restart_count = item
+        .get("RestartCount")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(0);
+    let exit_code = item
+        .pointer("/State/ExitCode")
+        .and_then(serde_json::Value::as_i64);
+    ContainerInfo {
+        id,
+        service,
+        image: string_at(item, "/Config/Image"),
+        image_id: string_at(item, "/Image"),
+        ips: network_ips.iter().map(|(_, ip)| *ip).collect(),
+        network_ips,
+        gateways: extract_gateways(item),
+        networks: extract_networks(item),
+        labels: labels_map,
+        env,
+        state: string_at(item, "/State/Status"),
+        health,
+        restart_count,
+        exit_code,
+        restart_policy: string_at(item, "/HostConfig/RestartPolicy/Name"),
+        instance,
+    }
+}
+
+fn string_at(item: &Value, pointer: &str) -> Option<String> {
+    item.pointer(pointer)
+        .and_then(Value::as_str)
+        .map(ToString::to_string)
+}
+
+fn parse_stats_line(line: &str) -> Option<(String, ContainerStats)> {
+    let mut fields = line.split('\t').map(str::trim);
+    let id = fields.next()?.to_string();
+    let cpu_percent = fields
+        .next()
+        .and_then(|cpu| cpu.trim_end_matches('%').parse().ok());
+    let memory = fields
+        .next()
+        .filter(|memory| !memory.is_empty() && *memory != "--")
+        .map(ToString::to_string);
+    Some((
+        id,
+        ContainerStats {
+            cpu_percent,
+            memory,
+        },
+    ))
+}
+
+fn select_backend(
+    kind: EngineKind,
+    remote: bool,
+    podman_cmd: &[String],
+    docker_cmd: &[String],
+) -> Arc<dyn EngineBackend> {
+    if !remote {
+        if let Some(api) = ApiBackend::connect(kind) {
+            return Arc::new(api);
+        }
+    }
+    let cmd = match kind {
+        EngineKind::Podman => podman_cmd.to_vec(),
+        EngineKind::Docker => docker_cmd.to_vec(),
+    };
+    Arc::new(CliBackend::new(kind, cmd))
+}
+
+fn remote_engine_url(kind: EngineKind) -> Option<String> {
+    let host_var = match kind {
+        EngineKind::Docker => "DOCKER_HOST",
+        EngineKind::Podman => "CONTAINER_HOST",
+    };
+    env::var(host_var)
+        .ok()
+        .filter(|url| !url.is_empty() && !url.starts_with("unix://"))
+}
+
+fn podman_connection_host(connection: &str) -> Option<String> {
+    let cmd = [
+        "podman".to_string(),
+        "system".to_string(),
+        "connection".to_string(),
+        "list".to_string(),
+        "--format".to_string(),
+        "{{.Name}}\t{{.URI}}".to_string(),
+    ];
+    let output = run_output(&cmd).ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .find(|(name, _)| name.trim() == connection)
+        .and_then(|(_, uri)| host_from_engine_url(uri.trim()))
+}
+
+fn host_from_engine_url(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://")?;
+    if !matches!(scheme, "ssh" | "tcp" | "http" | "https") {
+        return None;
+    }
+    let authority = rest.split('/').next().unwrap_or_default();
+    let host_port = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = host_port.strip_prefix('[').map_or_else(
+        || host_port.split(':').next(),
+        |bracketed| bracketed.split(']').next(),
+    );
+    let host = host.unwrap_or_default();
+    match host {
+        "" | "127.0.0.1" | "::1" | LOCAL_HOST => None,
+        host => Some(host.to_string()),
     }
 }
 
@@ -263,15 +1038,33 @@ fn extract_labels_map(container: &serde_json::Value) -> HashMap<String, String>
         return HashMap::new();
     };
     map.iter()
-        .filter_map(|(key, value)| {
-            value
-                .as_str()
-                .map(|value| (key.to_string(), value.to_string()))
-        })
+        .filter_map(|(key, value)| value.as_str().map(|value| (key.clone(), value.to_string())))
+        .collect()
+}
+
+fn extract_networks(container: &serde_json::Value) -> Vec<String> {
+    container
+        .pointer("/NetworkSettings/Networks")
+        .and_then(|value| value.as_object())
+        .map(|networks| networks.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn extract_gateways(container: &serde_json::Value) -> Vec<IpAddr> {
+    let Some(networks) = container
+        .pointer("/NetworkSettings/Networks")
+        .and_then(|value| value.as_object())
+    else {
+        return Vec::new();
+    };
+    networks
+        .values()
+        .flat_map(|network| ["Gateway", "IPv6Gateway"].map(|key| network.get(key)))
+        .filter_map(|value| value?.as_str()?.parse::<IpAddr>().ok())
         .collect()
 }
 
-fn extract_ips(container: &serde_json::Value) -> Vec<IpAddr> {
+fn extract_network_ips(container: &serde_json::Value) -> Vec<(String, IpAddr)> {
     let mut ips = Vec::new();
     let Some(networks) = container
         .get("NetworkSettings")
@@ -280,7 +1073,7 @@ fn extract_ips(container: &serde_json::Value) -> Vec<IpAddr> {
     else {
         return ips;
     };
-    for network in networks.values() {
+    for (name, network) in networks {
         let parsed = ["IPAddress", "IpAddress", "ip_address", "ipAddress"]
             .iter()
             .filter_map(|key| {
@@ -288,8 +1081,292 @@ fn extract_ips(container: &serde_json::Value) -> Vec<IpAddr> {
                     .get(*key)
                     .and_then(|value| value.as_str())
                     .and_then(|ip| ip.parse::<IpAddr>().ok())
-            });
+            })
+            .map(|ip| (name.clone(), ip));
         ips.extend(parsed);
     }
     ips
 }
+
+fn rootless_network_name(reported: Option<&str>) -> String {
+    reported
+        .filter(|name| !name.is_empty() && !name.starts_with('<'))
+        .unwrap_or("slirp4netns")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io;
+    use std::sync::Arc;
+
+    use serde_json::Value;
+
+    use crate::domain::{EngineKind, Scope};
+    use crate::infra::backend::{EngineBackend, LabelFilter, LogStream};
+    use crate::support::oneoff::{run_args, OneOffSpec};
+
+    use super::{
+        container_info, host_from_engine_url, parallel_map, parse_stats_line, published_address,
+        rootless_network_name, run_settled, scaled_instances, ContainerStats, Engine,
+    };
+
+    struct FakeBackend {
+        containers: Vec<(String, HashMap<String, String>)>,
+    }
+
+    impl EngineBackend for FakeBackend {
+        fn container_ids(&self, labels: &LabelFilter<'_>, _scope: Scope) -> Vec<String> {
+            self.containers
+                .iter()
+                .filter(|(_, own)| {
+                    labels
+                        .iter()
+                        .all(|(key, value)| has_label(own, key, *value))
+                })
+                .map(|(id, _)| id.clone())
+                .collect()
+        }
+
+        fn inspect(&self, _ids: &[String]) -> Vec<Value> {
+            Vec::new()
+        }
+
+        fn inspect_images(&self, _ids: &[String]) -> Vec<Value> {
+            Vec::new()
+        }
+
+        fn service_name(&self, _project_name: &str, cid: &str) -> String {
+            cid.to_string()
+        }
+
+        fn logs(
+            &self,
+            _cid: &str,
+            _timestamps: bool,
+            _since: Option<i64>,
+        ) -> io::Result<LogStream> {
+            Err(io::Error::other("no logs"))
+        }
+
+        fn events(&self, _labels: &LabelFilter<'_>) -> io::Result<LogStream> {
+            Err(io::Error::other("no events"))
+        }
+    }
+
+    fn has_label(labels: &HashMap<String, String>, key: &str, value: Option<&str>) -> bool {
+        labels
+            .get(key)
+            .is_some_and(|own| value.is_none_or(|value| own == value))
+    }
+
+    fn labels_from_run_args(args: &[String]) -> HashMap<String, String> {
+        args.windows(2)
+            .filter(|pair| pair.first().is_some_and(|flag| flag == "--label"))
+            .filter_map(|pair| pair.get(1)?.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn numbers_only_services_with_several_replicas() {
+        let inspect = |id: &str, service: &str, proxy: &str| {
+            container_info(&serde_json::json!({
+                "Id": id,
+                "Config": {"Labels": {
+                    "com.docker.compose.service": service,
+                    "com.docker.compose.container-number": id.get(1..),
+                    "sanelens.proxy": proxy,
+                }},
+            }))
+        };
+        let containers = vec![
+            inspect("a1", "api-app", "http"),
+            inspect("a2", "api-app", "http"),
+            inspect("p1", "api", "true"),
+            inspect("d1", "db", "off"),
+        ];
+        let aliases = HashMap::from([("api-app".to_string(), "api".to_string())]);
+        let instances = scaled_instances(&containers, &aliases);
+        assert_eq!(instances.len(), 2);
+        assert_eq!(instances.get("a2"), Some(&2));
+        assert_eq!(instances.get("d1"), None);
+    }
+
+    #[test]
+    fn rootless_network_defaults_to_slirp4netns_on_older_podman() {
+        assert_eq!(rootless_network_name(Some("pasta")), "pasta");
+        assert_eq!(rootless_network_name(Some("<no value>")), "slirp4netns");
+        assert_eq!(rootless_network_name(None), "slirp4netns");
+    }
+
+    #[test]
+    fn parallel_map_keeps_input_order() {
+        let ids: Vec<u64> = (0..37).collect();
+        let doubled = parallel_map(&ids, |id| id * 2);
+        assert_eq!(
+            doubled,
+            ids.iter().map(|id| Some(id * 2)).collect::<Vec<_>>()
+        );
+        assert!(parallel_map(&[] as &[u64], |id| *id).is_empty());
+    }
+
+    #[test]
+    fn reads_published_ports_from_inspect() {
+        let raw = serde_json::json!({
+            "NetworkSettings": {"Ports": {
+                "9901/tcp": [{"HostIp": "127.0.0.1", "HostPort": "49153"}],
+                "8080/tcp": [{"HostIp": "0.0.0.0", "HostPort": "8080"}, {"HostIp": "::", "HostPort": "8080"}],
+                "5432/tcp": null,
+            }}
+        });
+        let addr = |port| published_address(&raw, port).map(|addr| addr.to_string());
+        assert_eq!(addr(9901).as_deref(), Some("127.0.0.1:49153"));
+        assert_eq!(addr(8080).as_deref(), Some("127.0.0.1:8080"));
+        assert_eq!(addr(5432), None);
+    }
+
+    #[test]
+    fn extracts_remote_engine_hosts() {
+        assert_eq!(
+            host_from_engine_url("ssh://dev@devbox.lan:2222").as_deref(),
+            Some("devbox.lan")
+        );
+        assert_eq!(
+            host_from_engine_url("ssh://core@10.0.0.7/run/user/1000/podman/podman.sock").as_deref(),
+            Some("10.0.0.7")
+        );
+        assert_eq!(
+            host_from_engine_url("tcp://[fd00::2]:2376").as_deref(),
+            Some("fd00::2")
+        );
+        assert_eq!(host_from_engine_url("tcp://127.0.0.1:2375"), None);
+        assert_eq!(host_from_engine_url("unix:///var/run/docker.sock"), None);
+    }
+
+    #[test]
+    fn parses_stats_lines() {
+        assert_eq!(
+            parse_stats_line("3f2a9c1d0b7e\t12.50%\t48.2MiB / 7.6GiB"),
+            Some((
+                "3f2a9c1d0b7e".to_string(),
+                ContainerStats {
+                    cpu_percent: Some(12.5),
+                    memory: Some("48.2MiB / 7.6GiB".to_string()),
+                }
+            ))
+        );
+        assert_eq!(
+            parse_stats_line("abc\t--\t--").map(|(_, stats)| stats),
+            Some(ContainerStats::default())
+        );
+    }
+
+    #[test]
+    fn only_running_healthy_workloads_count_as_ready() {
+        let inspect = |proxy: &str, status: &str, health: Option<&str>| {
+            container_info(&serde_json::json!({
+                "Id": "abc",
+                "Config": {"Labels": {"sanelens.proxy": proxy}},
+                "State": {"Status": status, "Health": health.map(|h| serde_json::json!({"Status": h}))},
+            }))
+        };
+        let ready_workload = |info: &super::ContainerInfo| !info.is_proxy() && info.is_ready();
+        assert!(inspect("True", "running", None).is_proxy());
+        assert!(inspect("1", "running", None).is_proxy());
+        assert!(!inspect("false", "running", None).is_proxy());
+        assert!(ready_workload(&inspect("no", "running", Some("healthy"))));
+        assert!(!ready_workload(&inspect("yes", "running", None)));
+        assert!(!ready_workload(&inspect("", "running", Some("starting"))));
+        assert!(!ready_workload(&inspect("", "exited", None)));
+    }
+
+    #[test]
+    fn collects_network_gateways_from_inspect() {
+        let info = container_info(&serde_json::json!({
+            "Id": "abc",
+            "NetworkSettings": {"Networks": {
+                "demo_default": {"IPAddress": "172.18.0.5", "Gateway": "172.18.0.1", "IPv6Gateway": ""},
+                "demo_v6": {"Gateway": "", "IPv6Gateway": "fd00::1"},
+            }},
+        }));
+        let mut gateways: Vec<String> = info.gateways.iter().map(ToString::to_string).collect();
+        gateways.sort();
+        assert_eq!(gateways, vec!["172.18.0.1", "fd00::1"]);
+    }
+
+    #[test]
+    fn marks_one_shot_jobs_that_exited_cleanly_as_completed() {
+        let inspect = |code: i64, policy: &str| {
+            container_info(&serde_json::json!({
+                "Id": "abc",
+                "State": {"Status": "exited", "ExitCode": code},
+                "HostConfig": {"RestartPolicy": {"Name": policy}},
+            }))
+        };
+        assert_eq!(
+            inspect(0, "no").display_state().as_deref(),
+            Some("completed")
+        );
+        assert_eq!(inspect(0, "").display_state().as_deref(), Some("completed"));
+        assert_eq!(inspect(1, "no").display_state().as_deref(), Some("exited"));
+        assert!(!inspect(0, "unless-stopped").is_completed_job());
+    }
+
+    #[test]
+    fn runs_settle_once_every_service_is_running_or_completed() {
+        let inspect = |status: &str, code: i64| {
+            container_info(&serde_json::json!({
+                "Id": "abc",
+                "State": {"Status": status, "ExitCode": code},
+            }))
+        };
+        assert!(run_settled(
+            2,
+            &[inspect("exited", 0), inspect("running", 0)]
+        ));
+        assert!(!run_settled(
+            2,
+            &[inspect("exited", 0), inspect("created", 0)]
+        ));
+        assert!(!run_settled(
+            2,
+            &[inspect("exited", 1), inspect("running", 0)]
+        ));
+        assert!(!run_settled(0, &[inspect("running", 0)]));
+    }
+
+    #[test]
+    fn teardown_finds_the_run_one_off_containers() {
+        let spec = |run_id: &str| OneOffSpec {
+            run_id: run_id.to_string(),
+            project_name: format!("sanelens_{run_id}"),
+            name: "probe".to_string(),
+            image: "curlimages/curl".to_string(),
+            networks: Vec::new(),
+            env: Vec::new(),
+            command: Vec::new(),
+            tty: false,
+        };
+        let workload = HashMap::from([("sanelens.run_id".to_string(), "run_a1".to_string())]);
+        let backend = FakeBackend {
+            containers: vec![
+                ("api".to_string(), workload),
+                (
+                    "probe".to_string(),
+                    labels_from_run_args(&run_args(&spec("run_a1"))),
+                ),
+                (
+                    "other".to_string(),
+                    labels_from_run_args(&run_args(&spec("run_b2"))),
+                ),
+            ],
+        };
+        let engine = Engine::with_backend(EngineKind::Docker, Arc::new(backend));
+        assert_eq!(
+            engine.collect_oneoff_container_ids("run_a1"),
+            vec!["probe".to_string()]
+        );
+    }
+}
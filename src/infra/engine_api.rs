@@ -0,0 +1,399 @@
+use std::env;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::domain::{EngineKind, Scope};
+use crate::infra::backend::{
+    service_name_from_inspect, EngineBackend, EofReader, LabelFilter, LogStream, StreamHandle,
+    CONTAINER_EVENTS,
+};
+use crate::infra::engine::parallel_map;
+
+const PING_TIMEOUT: Duration = Duration::from_secs(1);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct ApiBackend {
+    socket: PathBuf,
+    kind: EngineKind,
+}
+
+struct Response {
+    status: u16,
+    body: Box<dyn Read + Send>,
+    stream: UnixStream,
+}
+
+impl ApiBackend {
+    pub fn connect(kind: EngineKind) -> Option<Self> {
+        if env::var("SANELENS_ENGINE_API").is_ok_and(|value| {
+            matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "0" | "false" | "no"
+            )
+        }) {
+            return None;
+        }
+        socket_candidates(kind)
+            .into_iter()
+            .filter(|path| path.exists())
+            .map(|socket| Self { socket, kind })
+            .find(Self::ping)
+    }
+
+    fn ping(&self) -> bool {
+        self.request("/_ping", Some(PING_TIMEOUT))
+            .is_ok_and(|response| response.status == 200)
+    }
+
+    fn request(&self, path: &str, timeout: Option<Duration>) -> io::Result<Response> {
+        let mut stream = UnixStream::connect(&self.socket)?;
+        stream.set_read_timeout(timeout)?;
+        write!(
+            stream,
+            "GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n"
+        )?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let status = line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| io::Error::other("malformed engine API response"))?;
+        let mut chunked = false;
+        let mut content_length = None;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("transfer-encoding") {
+                chunked = value.eq_ignore_ascii_case("chunked");
+            } else if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse::<u64>().ok();
+            }
+        }
+        let body: Box<dyn Read + Send> = if chunked {
+            Box::new(ChunkedReader::new(reader))
+        } else if let Some(length) = content_length {
+            Box::new(reader.take(length))
+        } else {
+            Box::new(reader)
+        };
+        Ok(Response {
+            status,
+            body,
+            stream,
+        })
+    }
+
+    fn get_json(&self, path: &str) -> Option<Value> {
+        let mut response = self.request(path, Some(REQUEST_TIMEOUT)).ok()?;
+        if response.status != 200 {
+            return None;
+        }
+        let mut payload = Vec::new();
+        response.body.read_to_end(&mut payload).ok()?;
+        serde_json::from_slice(&payload).ok()
+    }
+
+    fn stream(&self, path: &str, demux: bool) -> io::Result<LogStream> {
+        let response = self.request(path, None)?;
+        if response.status != 200 {
+            return Err(io::Error::other(format!(
+                "engine API returned {} for {path}",
+                response.status
+            )));
+        }
+        let finished = Arc::new(AtomicBool::new(false));
+        let body: Box<dyn Read + Send> = Box::new(EofReader::new(response.body, finished.clone()));
+        let handle = StreamHandle::Socket(response.stream, finished);
+        if !demux {
+            return Ok(LogStream {
+                stdout: body,
+                stderr: None,
+                handle,
+            });
+        }
+        let (stdout, stdout_writer) = io::pipe()?;
+        let (stderr, stderr_writer) = io::pipe()?;
+        thread::spawn(move || demux_stream(body, stdout_writer, stderr_writer));
+        Ok(LogStream {
+            stdout: Box::new(stdout),
+            stderr: Some(Box::new(stderr)),
+            handle,
+        })
+    }
+}
+
+impl EngineBackend for ApiBackend {
+    fn container_ids(&self, labels: &LabelFilter<'_>, scope: Scope) -> Vec<String> {
+        let all = u8::from(matches!(scope, Scope::All));
+        let filters = label_filters(labels);
+        let path = format!("/containers/json?all={all}&filters={}", encode(&filters));
+        let Some(Value::Array(list)) = self.get_json(&path) else {
+            return Vec::new();
+        };
+        let mut ids: Vec<String> = list
+            .iter()
+            .filter_map(|item| item.get("Id").and_then(Value::as_str))
+            .map(ToString::to_string)
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    fn inspect(&self, ids: &[String]) -> Vec<Value> {
+        parallel_map(ids, |id| {
+            self.get_json(&format!("/containers/{}/json", encode(id)))
+        })
+        .into_iter()
+        .flatten()
+        .flatten()
+        .collect()
+    }
+
+    fn inspect_images(&self, ids: &[String]) -> Vec<Value> {
+        parallel_map(ids, |id| {
+            self.get_json(&format!("/images/{}/json", encode(id)))
+        })
+        .into_iter()
+        .flatten()
+        .flatten()
+        .collect()
+    }
+
+    fn service_name(&self, project_name: &str, cid: &str) -> String {
+        self.inspect(&[cid.to_string()])
+            .first()
+            .and_then(|item| service_name_from_inspect(item, self.kind, project_name))
+            .unwrap_or_else(|| cid.to_string())
+    }
+
+    fn logs(&self, cid: &str, timestamps: bool, since: Option<i64>) -> io::Result<LogStream> {
+        let timestamps = u8::from(timestamps);
+        let mut path = format!(
+            "/containers/{}/logs?follow=1&stdout=1&stderr=1&timestamps={timestamps}",
+            encode(cid)
+        );
+        if let Some(since) = since {
+            let _ = write!(path, "&since={since}");
+        }
+        self.stream(&path, true)
+    }
+
+    fn events(&self, labels: &LabelFilter<'_>) -> io::Result<LogStream> {
+        let filters = serde_json::json!({
+            "label": label_values(labels),
+            "type": ["container"],
+            "event": CONTAINER_EVENTS,
+        })
+        .to_string();
+        self.stream(&format!("/events?filters={}", encode(&filters)), false)
+    }
+}
+
+fn socket_candidates(kind: EngineKind) -> Vec<PathBuf> {
+    let host_var = match kind {
+        EngineKind::Docker => "DOCKER_HOST",
+        EngineKind::Podman => "CONTAINER_HOST",
+    };
+    if let Ok(host) = env::var(host_var) {
+        return host
+            .strip_prefix("unix://")
+            .map(|path| vec![PathBuf::from(path)])
+            .unwrap_or_default();
+    }
+    match kind {
+        EngineKind::Docker => vec![PathBuf::from("/var/run/docker.sock")],
+        EngineKind::Podman => {
+            let mut candidates = Vec::new();
+            if let Ok(runtime_dir) = env::var("XDG_RUNTIME_DIR") {
+                candidates.push(Path::new(&runtime_dir).join("podman/podman.sock"));
+            }
+            candidates.push(PathBuf::from("/run/podman/podman.sock"));
+            candidates
+        }
+    }
+}
+
+fn label_filters(labels: &LabelFilter<'_>) -> String {
+    serde_json::json!({ "label": label_values(labels) }).to_string()
+}
+
+fn label_values(labels: &LabelFilter<'_>) -> Vec<String> {
+    labels
+        .iter()
+        .map(|(key, value)| {
+            value.map_or_else(|| (*key).to_string(), |value| format!("{key}={value}"))
+        })
+        .collect()
+}
+
+pub fn encode(value: &str) -> String {
+    value.bytes().fold(String::new(), |mut out, byte| {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            out.push(char::from(byte));
+        } else {
+            let _ = write!(out, "%{byte:02X}");
+        }
+        out
+    })
+}
+
+fn demux_stream(
+    mut body: Box<dyn Read + Send>,
+    mut stdout: io::PipeWriter,
+    mut stderr: io::PipeWriter,
+) {
+    let mut header = [0_u8; 8];
+    loop {
+        if body.read_exact(&mut header).is_err() {
+            return;
+        }
+        let [kind, pad_a, pad_b, pad_c, size_a, size_b, size_c, size_d] = header;
+        if kind > 2 || pad_a != 0 || pad_b != 0 || pad_c != 0 {
+            let _ = stdout.write_all(&header);
+            let _ = io::copy(&mut body, &mut stdout);
+            return;
+        }
+        let size = u64::from(u32::from_be_bytes([size_a, size_b, size_c, size_d]));
+        let target = if kind == 2 { &mut stderr } else { &mut stdout };
+        let mut frame = (&mut body).take(size);
+        if io::copy(&mut frame, target).is_err() {
+            return;
+        }
+    }
+}
+
+pub struct ChunkedReader<R> {
+    inner: R,
+    remaining: u64,
+    done: bool,
+}
+
+impl<R: BufRead> ChunkedReader<R> {
+    pub const fn new(inner: R) -> Self {
+        Self {
+            inner,
+            remaining: 0,
+            done: false,
+        }
+    }
+
+    fn next_chunk(&mut self) -> io::Result<()> {
+        let mut line = String::new();
+        if self.inner.read_line(&mut line)? == 0 {
+            self.done = true;
+            return Ok(());
+        }
+        if line.trim().is_empty() && self.inner.read_line(&mut line)? == 0 {
+            self.done = true;
+            return Ok(());
+        }
+        let size = line.trim().split(';').next().unwrap_or_default();
+        self.remaining = u64::from_str_radix(size, 16)
+            .map_err(|_| io::Error::other(format!("invalid chunk size '{size}'")))?;
+        if self.remaining == 0 {
+            self.done = true;
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for ChunkedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.remaining == 0 {
+            if self.done {
+                return Ok(0);
+            }
+            self.next_chunk()?;
+        }
+        let limit = usize::try_from(self.remaining)
+            .unwrap_or(usize::MAX)
+            .min(buf.len());
+        let read = self.inner.read(buf.get_mut(..limit).unwrap_or_default())?;
+        if read == 0 {
+            self.done = true;
+        }
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{demux_stream, encode, ChunkedReader};
+    use crate::infra::backend::{EofReader, StreamHandle};
+    use std::io::{self, Read, Write};
+    use std::os::unix::net::UnixStream;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    #[test]
+    fn socket_handles_stop_running_at_end_of_stream() {
+        let pair = UnixStream::pair().and_then(|(local, remote)| {
+            let reader = local.try_clone()?;
+            Ok((local, remote, reader))
+        });
+        assert!(pair.is_ok());
+        let Ok((local, mut remote, reader)) = pair else {
+            return;
+        };
+        let finished = Arc::new(AtomicBool::new(false));
+        let mut body = EofReader::new(reader, finished.clone());
+        let mut handle = StreamHandle::Socket(local, finished);
+        assert!(handle.is_running());
+        assert!(remote.write_all(b"line\n").is_ok());
+        drop(remote);
+        let mut read = String::new();
+        assert!(body.read_to_string(&mut read).is_ok());
+        assert_eq!(read, "line\n");
+        assert!(!handle.is_running());
+    }
+
+    #[test]
+    fn decodes_chunked_bodies() {
+        let raw = b"5\r\nhello\r\n7\r\n world!\r\n0\r\n\r\n";
+        let mut body = String::new();
+        let _ = ChunkedReader::new(&raw[..]).read_to_string(&mut body);
+        assert_eq!(body, "hello world!");
+        assert_eq!(
+            encode("{\"label\":[\"a=b\"]}"),
+            "%7B%22label%22%3A%5B%22a%3Db%22%5D%7D"
+        );
+    }
+
+    #[test]
+    fn demultiplexes_stdout_and_stderr_frames() {
+        let mut raw = vec![1, 0, 0, 0, 0, 0, 0, 4];
+        raw.extend_from_slice(b"out\n");
+        raw.extend_from_slice(&[2, 0, 0, 0, 0, 0, 0, 4]);
+        raw.extend_from_slice(b"err\n");
+        let (Ok((mut stdout, stdout_writer)), Ok((mut stderr, stderr_writer))) =
+            (io::pipe(), io::pipe())
+        else {
+            return;
+        };
+        demux_stream(Box::new(io::Cursor::new(raw)), stdout_writer, stderr_writer);
+        let mut out = String::new();
+        let mut err = String::new();
+        let _ = stdout.read_to_string(&mut out);
+        let _ = stderr.read_to_string(&mut err);
+        assert_eq!(out, "out\n");
+        assert_eq!(err, "err\n");
+    }
+}
@@ -0,0 +1,310 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::Path;
+use std::time::Duration;
+
+use serde_yaml::{Mapping, Value};
+
+use crate::support::constants::ENVOY_CONFIG_LABEL;
+
+const ADMIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+const EXTENSION_KEY: &str = "x-sanelens";
+const ENVOY_KEY: &str = "envoy";
+const SECTIONS: [&str; 6] = [
+    "listener",
+    "http_connection_manager",
+    "tcp_proxy",
+    "virtual_host",
+    "route",
+    "cluster",
+];
+
+pub fn read_envoy_overrides(
+    service_name: &str,
+    service: &Mapping,
+    base_dir: &Path,
+) -> Result<Option<Mapping>, String> {
+    let mut overrides = match read_label(service, ENVOY_CONFIG_LABEL) {
+        Some(file) => load_overrides_file(service_name, &base_dir.join(file))?,
+        None => Mapping::new(),
+    };
+    if let Some(inline) = service
+        .get(Value::String(EXTENSION_KEY.to_string()))
+        .and_then(|value| value.get(ENVOY_KEY))
+    {
+        let Value::Mapping(inline) = inline else {
+            return Err(format!(
+                "{EXTENSION_KEY}.{ENVOY_KEY} on {service_name} must be a mapping"
+            ));
+        };
+        merge_mapping(&mut overrides, inline);
+    }
+    validate_sections(service_name, &overrides)?;
+    Ok(if overrides.is_empty() {
+        None
+    } else {
+        Some(overrides)
+    })
+}
+
+pub fn apply_envoy_overrides(config: &str, overrides: &Mapping) -> Result<String, String> {
+    let mut doc: Value = serde_yaml::from_str(config)
+        .map_err(|err| format!("generated envoy config is invalid: {err}"))?;
+    let section = |name: &str| {
+        overrides
+            .get(Value::String(name.to_string()))
+            .and_then(Value::as_mapping)
+    };
+    if let Some(resources) = doc.get_mut("static_resources") {
+        if let Some(Value::Sequence(listeners)) = resources.get_mut("listeners") {
+            for listener in listeners.iter_mut() {
+                apply_listener_overrides(listener, &section);
+            }
+        }
+        if let (Some(Value::Sequence(clusters)), Some(cluster)) =
+            (resources.get_mut("clusters"), section("cluster"))
+        {
+            for entry in clusters.iter_mut() {
+                merge_value(entry, cluster);
+            }
+        }
+    }
+    serde_yaml::to_string(&doc).map_err(|err| format!("serialize envoy config failed: {err}"))
+}
+
+pub fn post_admin(addr: SocketAddr, path: &str) -> Result<String, String> {
+    let mut stream = TcpStream::connect_timeout(&addr, ADMIN_TIMEOUT)
+        .map_err(|err| format!("Envoy admin at {addr} is unreachable: {err}"))?;
+    stream
+        .set_read_timeout(Some(ADMIN_TIMEOUT))
+        .map_err(|err| err.to_string())?;
+    write!(
+        stream,
+        "POST {path} HTTP/1.0\r\nHost: {addr}\r\nContent-Length: 0\r\n\r\n"
+    )
+    .map_err(|err| err.to_string())?;
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .map_err(|err| err.to_string())?;
+    let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+    match head.split_whitespace().nth(1) {
+        Some("200") => Ok(body.to_string()),
+        Some(status) => Err(format!(
+            "Envoy admin at {addr} answered {status}: {}",
+            body.trim()
+        )),
+        None => Err(format!("Envoy admin at {addr} sent an invalid response")),
+    }
+}
+
+fn apply_listener_overrides<'a>(
+    listener: &mut Value,
+    section: &impl Fn(&str) -> Option<&'a Mapping>,
+) {
+    if let Some(overrides) = section("listener") {
+        merge_value(listener, overrides);
+    }
+    let Some(Value::Sequence(chains)) = listener.get_mut("filter_chains") else {
+        return;
+    };
+    for chain in chains.iter_mut() {
+        let Some(Value::Sequence(filters)) = chain.get_mut("filters") else {
+            continue;
+        };
+        for filter in filters.iter_mut() {
+            let name = filter
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            let Some(typed_config) = filter.get_mut("typed_config") else {
+                continue;
+            };
+            if name.ends_with("http_connection_manager") {
+                apply_http_manager_overrides(typed_config, section);
+            } else if let Some(overrides) =
+                section("tcp_proxy").filter(|_| name.ends_with("tcp_proxy"))
+            {
+                merge_value(typed_config, overrides);
+            }
+        }
+    }
+}
+
+fn apply_http_manager_overrides<'a>(
+    typed_config: &mut Value,
+    section: &impl Fn(&str) -> Option<&'a Mapping>,
+) {
+    if let Some(overrides) = section("http_connection_manager") {
+        merge_value(typed_config, overrides);
+    }
+    let Some(Value::Sequence(hosts)) = typed_config
+        .get_mut("route_config")
+        .and_then(|value| value.get_mut("virtual_hosts"))
+    else {
+        return;
+    };
+    for host in hosts.iter_mut() {
+        if let Some(overrides) = section("virtual_host") {
+            merge_value(host, overrides);
+        }
+        let (Some(Value::Sequence(routes)), Some(overrides)) =
+            (host.get_mut("routes"), section("route"))
+        else {
+            continue;
+        };
+        for route in routes.iter_mut() {
+            if let Some(target) = route.get_mut("route") {
+                merge_value(target, overrides);
+            }
+        }
+    }
+}
+
+fn merge_value(target: &mut Value, overrides: &Mapping) {
+    if let Value::Mapping(map) = target {
+        merge_mapping(map, overrides);
+    }
+}
+
+fn merge_mapping(target: &mut Mapping, overrides: &Mapping) {
+    for (key, value) in overrides {
+        match (target.get_mut(key), value) {
+            (Some(Value::Mapping(existing)), Value::Mapping(incoming)) => {
+                merge_mapping(existing, incoming);
+            }
+            _ => {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+fn load_overrides_file(service_name: &str, path: &Path) -> Result<Mapping, String> {
+    let display = path.display();
+    let contents = fs::read_to_string(path).map_err(|err| {
+        format!("failed to read envoy overrides for {service_name} ({display}): {err}")
+    })?;
+    match serde_yaml::from_str::<Value>(&contents) {
+        Ok(Value::Mapping(map)) => Ok(map),
+        Ok(Value::Null) => Ok(Mapping::new()),
+        Ok(_) => Err(format!("envoy overrides in {display} must be a mapping")),
+        Err(err) => Err(format!("invalid envoy overrides in {display}: {err}")),
+    }
+}
+
+fn validate_sections(service_name: &str, overrides: &Mapping) -> Result<(), String> {
+    for (key, value) in overrides {
+        let Some(name) = key.as_str().filter(|name| SECTIONS.contains(name)) else {
+            let key = serde_yaml::to_string(key).unwrap_or_default();
+            let expected = SECTIONS.join(", ");
+            return Err(format!(
+                "unknown envoy override section '{}' on {service_name} (expected one of: {expected})",
+                key.trim()
+            ));
+        };
+        if !value.is_mapping() {
+            return Err(format!(
+                "envoy override section '{name}' on {service_name} must be a mapping"
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn read_label(service: &Mapping, key: &str) -> Option<String> {
+    match service.get(Value::String("labels".to_string())) {
+        Some(Value::Sequence(list)) => list
+            .iter()
+            .filter_map(Value::as_str)
+            .find_map(|entry| entry.strip_prefix(&format!("{key}=")))
+            .map(ToString::to_string),
+        Some(Value::Mapping(map)) => map
+            .get(Value::String(key.to_string()))
+            .and_then(Value::as_str)
+            .map(ToString::to_string),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_envoy_overrides, merge_mapping};
+    use serde_yaml::{Mapping, Value};
+
+    const CONFIG: &str = "static_resources:
+  listeners:
+  - name: api_listener_8080
+    filter_chains:
+    - filters:
+      - name: envoy.filters.network.http_connection_manager
+        typed_config:
+          stat_prefix: ingress_http_8080
+          route_config:
+            virtual_hosts:
+            - name: backend
+              routes:
+              - match:
+                  prefix: /
+                route:
+                  cluster: api-app_8080
+  clusters:
+  - name: api-app_8080
+    connect_timeout: 2s
+";
+
+    fn mapping(yaml: &str) -> Mapping {
+        serde_yaml::from_str(yaml).unwrap_or_default()
+    }
+
+    fn lookup<'a>(doc: &'a Value, path: &[&str]) -> Option<&'a Value> {
+        path.iter().try_fold(doc, |value, key| {
+            key.parse::<usize>()
+                .map_or_else(|_| value.get(*key), |idx| value.get(idx))
+        })
+    }
+
+    #[test]
+    fn merge_is_deep_for_mappings() {
+        let mut target = mapping("a: {b: 1, c: 2}\nd: 3");
+        merge_mapping(&mut target, &mapping("a: {c: 4}\nd: [5]"));
+        assert_eq!(target, mapping("a: {b: 1, c: 4}\nd: [5]"));
+    }
+
+    #[test]
+    fn overrides_reach_routes_and_clusters() {
+        let overrides = mapping(
+            "route: {timeout: 30s, retry_policy: {num_retries: 2}}\ncluster: {connect_timeout: 5s}\nvirtual_host: {request_headers_to_add: []}",
+        );
+        let rendered = apply_envoy_overrides(CONFIG, &overrides).unwrap_or_default();
+        let doc: Value = serde_yaml::from_str(&rendered).unwrap_or(Value::Null);
+        let listener = ["static_resources", "listeners", "0", "filter_chains", "0"];
+        let host = [
+            &listener[..],
+            &[
+                "filters",
+                "0",
+                "typed_config",
+                "route_config",
+                "virtual_hosts",
+                "0",
+            ],
+        ]
+        .concat();
+        let route = [&host[..], &["routes", "0", "route"]].concat();
+        let timeout = lookup(&doc, &[&route[..], &["timeout"]].concat());
+        let cluster = lookup(&doc, &[&route[..], &["cluster"]].concat());
+        let headers = lookup(&doc, &[&host[..], &["request_headers_to_add"]].concat());
+        let connect = lookup(
+            &doc,
+            &["static_resources", "clusters", "0", "connect_timeout"],
+        );
+        assert_eq!(timeout.and_then(Value::as_str), Some("30s"));
+        assert_eq!(cluster.and_then(Value::as_str), Some("api-app_8080"));
+        assert!(headers.is_some_and(Value::is_sequence));
+        assert_eq!(connect.and_then(Value::as_str), Some("5s"));
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use serde_yaml::{Mapping, Value};
+
+use crate::support::constants::{PROXY_NAME_LABEL, PROXY_PROTOCOLS_LABEL};
+
+const KEYED_FIELDS: [&str; 2] = ["labels", "environment"];
+
+pub fn explain_rewrites(
+    original: &Value,
+    derived: &Value,
+    app_service_map: &HashMap<String, String>,
+) -> Vec<String> {
+    let mut notes = Vec::new();
+    let empty = Mapping::new();
+    let original_services = services(original).unwrap_or(&empty);
+    let derived_services = services(derived).unwrap_or(&empty);
+    let app_names: HashMap<&str, &str> = app_service_map
+        .iter()
+        .map(|(app, name)| (name.as_str(), app.as_str()))
+        .collect();
+    for (name, derived_service) in derived_services {
+        let Some(name) = name.as_str() else {
+            continue;
+        };
+        let source = app_service_map.get(name).map_or(name, String::as_str);
+        let Some(original_service) = original_services.get(source) else {
+            notes.push(proxy_label(derived_service, PROXY_NAME_LABEL).map_or_else(
+                || format!("{name}: added by sanelens"),
+                |target| format!("{name}: added, proxy for {target}"),
+            ));
+            continue;
+        };
+        if let Some(app) = app_names.get(name) {
+            let protocols = proxy_label(derived_service, PROXY_PROTOCOLS_LABEL).unwrap_or_default();
+            notes.push(format!(
+                "{name}: proxy inserted ({protocols}); the service now runs as {app}, and ports and container_name stay on the proxy"
+            ));
+            continue;
+        }
+        explain_mapping(name, original_service, derived_service, &mut notes);
+    }
+    if let (Value::Mapping(original), Value::Mapping(derived)) = (original, derived) {
+        for (key, value) in derived {
+            let Some(key) = key.as_str().filter(|key| *key != "services") else {
+                continue;
+            };
+            diff_value(
+                "compose",
+                key,
+                original.get(key).unwrap_or(&Value::Null),
+                value,
+                &mut notes,
+            );
+        }
+    }
+    notes
+}
+
+fn services(doc: &Value) -> Option<&Mapping> {
+    doc.get("services").and_then(Value::as_mapping)
+}
+
+fn proxy_label(service: &Value, key: &str) -> Option<String> {
+    service
+        .get("labels")
+        .and_then(|labels| labels.get(key))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+fn explain_mapping(name: &str, original: &Value, derived: &Value, notes: &mut Vec<String>) {
+    let Value::Mapping(derived) = derived else {
+        return;
+    };
+    for (key, value) in derived {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        let before = original.get(key).unwrap_or(&Value::Null);
+        if KEYED_FIELDS.contains(&key) {
+            explain_keys(name, key, before, value, notes);
+        } else {
+            diff_value(name, key, before, value, notes);
+        }
+    }
+}
+
+fn explain_keys(
+    name: &str,
+    field: &str,
+    original: &Value,
+    derived: &Value,
+    notes: &mut Vec<String>,
+) {
+    let Value::Mapping(derived) = derived else {
+        diff_value(name, field, original, derived, notes);
+        return;
+    };
+    let changed: Vec<String> = derived
+        .iter()
+        .filter(|(key, value)| original.get(*key) != Some(*value))
+        .filter_map(|(key, _)| key.as_str())
+        .map(|key| {
+            let sign = if original.get(key).is_some() {
+                '~'
+            } else {
+                '+'
+            };
+            format!("{sign}{key}")
+        })
+        .collect();
+    if !changed.is_empty() {
+        notes.push(format!("{name}: {field} {}", changed.join(" ")));
+    }
+}
+
+fn diff_value(name: &str, path: &str, original: &Value, derived: &Value, notes: &mut Vec<String>) {
+    if original == derived {
+        return;
+    }
+    match (original, derived) {
+        (Value::Mapping(before), Value::Mapping(after)) => {
+            for (key, value) in after {
+                let Some(key) = key.as_str() else {
+                    continue;
+                };
+                let before = before.get(key).unwrap_or(&Value::Null);
+                diff_value(name, &format!("{path}.{key}"), before, value, notes);
+            }
+        }
+        (Value::Sequence(before), Value::Sequence(after)) if before.len() == after.len() => {
+            for (index, (before, after)) in before.iter().zip(after).enumerate() {
+                diff_value(name, &format!("{path}[{index}]"), before, after, notes);
+            }
+        }
+        (Value::String(before), Value::String(after)) => {
+            let kind = if after.starts_with('/') || after.contains(":/") {
+                "path"
+            } else {
+                "set"
+            };
+            notes.push(format!("{name}: {kind} {path} {before:?} -> {after:?}"));
+        }
+        (Value::Null, after) => notes.push(format!("{name}: set {path} = {}", inline(after))),
+        (before, after) => notes.push(format!(
+            "{name}: set {path} {} -> {}",
+            inline(before),
+            inline(after)
+        )),
+    }
+}
+
+fn inline(value: &Value) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explains_proxy_paths_labels_and_env() {
+        let original: Value = serde_yaml::from_str(
+            "services:\n  api:\n    image: api\n    ports: ['8080:8080']\n  worker:\n    image: worker\n    volumes: ['./data:/data']\n    environment:\n      MODE: batch\n",
+        )
+        .unwrap_or_default();
+        let derived: Value = serde_yaml::from_str(
+            "name: sanelens-1\nservices:\n  api:\n    image: envoy\n    labels:\n      sanelens.proxy.name: api\n      sanelens.proxy.protocols: 8080/http\n  api-app:\n    image: api\n  worker:\n    image: worker\n    volumes: ['/src/data:/data']\n    environment:\n      MODE: batch\n      HTTP_PROXY: http://sanelens-egress-proxy:15001\n    labels:\n      sanelens.run_id: r1\n  sanelens-egress-proxy:\n    image: envoy\n",
+        )
+        .unwrap_or_default();
+        let apps = HashMap::from([("api-app".to_string(), "api".to_string())]);
+        assert_eq!(
+            explain_rewrites(&original, &derived, &apps),
+            vec![
+                "api: proxy inserted (8080/http); the service now runs as api-app, and ports and container_name stay on the proxy".to_string(),
+                "worker: path volumes[0] \"./data:/data\" -> \"/src/data:/data\"".to_string(),
+                "worker: environment +HTTP_PROXY".to_string(),
+                "worker: labels +sanelens.run_id".to_string(),
+                "sanelens-egress-proxy: added by sanelens".to_string(),
+                "compose: set name = \"sanelens-1\"".to_string(),
+            ]
+        );
+    }
+}
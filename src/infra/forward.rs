@@ -0,0 +1,144 @@
+use std::io;
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const ACCEPT_POLL: Duration = Duration::from_millis(100);
+
+pub struct PortForward {
+    local: SocketAddr,
+    stop: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl PortForward {
+    pub fn open(local_port: u16, target: SocketAddr) -> Result<Self, String> {
+        let listener = TcpListener::bind(("127.0.0.1", local_port))
+            .map_err(|err| format!("failed to listen on 127.0.0.1:{local_port}: {err}"))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|err| err.to_string())?;
+        let local = listener.local_addr().map_err(|err| err.to_string())?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = stop.clone();
+        let handle = thread::spawn(move || accept_loop(&listener, target, &flag));
+        Ok(Self {
+            local,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    pub const fn local_addr(&self) -> SocketAddr {
+        self.local
+    }
+
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for PortForward {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+pub fn first_reachable(targets: &[SocketAddr]) -> Option<SocketAddr> {
+    targets
+        .iter()
+        .copied()
+        .find(|target| TcpStream::connect_timeout(target, CONNECT_TIMEOUT).is_ok())
+}
+
+fn accept_loop(listener: &TcpListener, target: SocketAddr, stop: &AtomicBool) {
+    while !stop.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((client, _)) => {
+                thread::spawn(move || relay(client, target));
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL),
+            Err(err) => {
+                eprintln!("[compose] forward: accept failed: {err}");
+                thread::sleep(ACCEPT_POLL);
+            }
+        }
+    }
+}
+
+fn relay(client: TcpStream, target: SocketAddr) {
+    let upstream = match TcpStream::connect_timeout(&target, CONNECT_TIMEOUT) {
+        Ok(upstream) => upstream,
+        Err(err) => {
+            eprintln!("[compose] forward: {target} unreachable: {err}");
+            return;
+        }
+    };
+    let _ = bridge(client, upstream);
+}
+
+fn bridge(client: TcpStream, upstream: TcpStream) -> io::Result<()> {
+    client.set_nonblocking(false)?;
+    let mut client_reader = client.try_clone()?;
+    let mut upstream_writer = upstream.try_clone()?;
+    let outbound = thread::spawn(move || {
+        let _ = io::copy(&mut client_reader, &mut upstream_writer);
+        let _ = upstream_writer.shutdown(Shutdown::Write);
+    });
+    let mut upstream_reader = upstream;
+    let mut client_writer = client;
+    let _ = io::copy(&mut upstream_reader, &mut client_writer);
+    let _ = client_writer.shutdown(Shutdown::Both);
+    let _ = outbound.join();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpListener, TcpStream};
+    use std::thread;
+
+    use super::{first_reachable, PortForward};
+
+    #[test]
+    fn relays_bytes_to_the_target_and_back() {
+        let Ok(upstream) = TcpListener::bind("127.0.0.1:0") else {
+            return;
+        };
+        let Ok(target) = upstream.local_addr() else {
+            return;
+        };
+        let echo = thread::spawn(move || {
+            for mut stream in upstream.incoming().take(2).flatten() {
+                let mut buffer = [0_u8; 4];
+                let _ = stream
+                    .read_exact(&mut buffer)
+                    .and_then(|()| stream.write_all(&buffer));
+            }
+        });
+        let unused = SocketAddr::from(([127, 0, 0, 1], 1));
+        assert_eq!(first_reachable(&[unused, target]), Some(target));
+
+        let forward = PortForward::open(0, target);
+        assert!(forward.is_ok());
+        let Ok(mut forward) = forward else {
+            return;
+        };
+        let mut reply = [0_u8; 4];
+        let echoed = TcpStream::connect(forward.local_addr()).and_then(|mut client| {
+            client.write_all(b"ping")?;
+            client.read_exact(&mut reply)
+        });
+        assert!(echoed.is_ok());
+        assert_eq!(&reply, b"ping");
+        forward.stop();
+        let _ = echo.join();
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::infra::engine::ContainerInfo;
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ImageMetadata {
+    pub image: Option<String>,
+    pub id: Option<String>,
+    pub digest: Option<String>,
+    pub created: Option<String>,
+    pub size_bytes: Option<u64>,
+    pub exposed_ports: Vec<String>,
+    pub entrypoint: Vec<String>,
+    pub labels: BTreeMap<String, String>,
+}
+
+pub fn image_metadata(container: &ContainerInfo, images: &[Value]) -> ImageMetadata {
+    let inspected = container.image_id.as_deref().and_then(|id| {
+        images
+            .iter()
+            .find(|item| item.get("Id").and_then(Value::as_str) == Some(id))
+    });
+    let mut metadata = inspected.map(parse_image).unwrap_or_default();
+    metadata.image.clone_from(&container.image);
+    metadata.id.clone_from(&container.image_id);
+    metadata
+}
+
+fn parse_image(item: &Value) -> ImageMetadata {
+    let config = item.get("Config");
+    let mut exposed_ports: Vec<String> = config
+        .and_then(|config| config.get("ExposedPorts"))
+        .and_then(Value::as_object)
+        .map(|ports| ports.keys().cloned().collect())
+        .unwrap_or_default();
+    exposed_ports.sort();
+    ImageMetadata {
+        digest: item
+            .get("RepoDigests")
+            .and_then(Value::as_array)
+            .and_then(|digests| digests.first())
+            .and_then(Value::as_str)
+            .map(ToString::to_string),
+        created: item
+            .get("Created")
+            .and_then(Value::as_str)
+            .map(ToString::to_string),
+        size_bytes: item.get("Size").and_then(Value::as_u64),
+        exposed_ports,
+        entrypoint: string_list(config.and_then(|config| config.get("Entrypoint"))),
+        labels: config
+            .and_then(|config| config.get("Labels"))
+            .and_then(Value::as_object)
+            .map(|labels| {
+                labels
+                    .iter()
+                    .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        ..ImageMetadata::default()
+    }
+}
+
+fn string_list(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(Value::as_str)
+                .map(ToString::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::parse_image;
+
+    #[test]
+    fn reads_digest_ports_entrypoint_and_labels() {
+        let item = json!({
+            "Id": "sha256:abc",
+            "RepoDigests": ["registry.local/api@sha256:def"],
+            "Created": "2024-05-01T10:00:00Z",
+            "Size": 123_456,
+            "Config": {
+                "ExposedPorts": {"9090/tcp": {}, "8080/tcp": {}},
+                "Entrypoint": ["/app/api", "--serve"],
+                "Labels": {"org.opencontainers.image.revision": "4f2e1c"}
+            }
+        });
+        let metadata = parse_image(&item);
+        assert_eq!(
+            metadata.digest.as_deref(),
+            Some("registry.local/api@sha256:def")
+        );
+        assert_eq!(metadata.size_bytes, Some(123_456));
+        assert_eq!(metadata.exposed_ports, ["8080/tcp", "9090/tcp"]);
+        assert_eq!(metadata.entrypoint, ["/app/api", "--serve"]);
+        assert_eq!(
+            metadata
+                .labels
+                .get("org.opencontainers.image.revision")
+                .map(String::as_str),
+            Some("4f2e1c")
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+
+use crate::infra::derive::{parse_container_port, parse_proxy_ports, value_to_u16};
+use crate::infra::proxy::ProxyPolicy;
+use crate::support::constants::{
+    COMPOSE_FILE_LABEL, DERIVED_COMPOSE_LABEL, ENVOY_CONFIG_LABEL, MAX_BODY_BYTES_LABEL,
+    PROJECT_NAME_LABEL, PROXY_EGRESS_LABEL, PROXY_LABEL, PROXY_PORTS_LABEL, PROXY_PROTOCOLS_LABEL,
+    RUN_ID_LABEL, SERVICE_LABEL, STARTED_AT_LABEL, TAG_LABEL, TRAFFIC_IGNORE_LABEL, UPSTREAM_LABEL,
+};
+
+const USER_LABELS: &[&str] = &[
+    PROXY_LABEL,
+    PROXY_PORTS_LABEL,
+    ENVOY_CONFIG_LABEL,
+    MAX_BODY_BYTES_LABEL,
+    TRAFFIC_IGNORE_LABEL,
+    UPSTREAM_LABEL,
+];
+const RESERVED_LABELS: &[&str] = &[
+    RUN_ID_LABEL,
+    SERVICE_LABEL,
+    PROXY_EGRESS_LABEL,
+    PROXY_PROTOCOLS_LABEL,
+    COMPOSE_FILE_LABEL,
+    DERIVED_COMPOSE_LABEL,
+    STARTED_AT_LABEL,
+    PROJECT_NAME_LABEL,
+    TAG_LABEL,
+];
+const PROXY_MODES: &[&str] = &["auto", "http", "tcp", "off", "true", "false", "on"];
+const LABEL_TYPO_DISTANCE: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    pub code: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    pub message: String,
+}
+
+impl Finding {
+    fn new(severity: Severity, code: &'static str, service: &str, message: String) -> Self {
+        Self {
+            severity,
+            code,
+            service: Some(service.to_string()),
+            message,
+        }
+    }
+}
+
+pub fn lint_compose(raw: &Value, resolved: &Value) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    if let Err(err) = ProxyPolicy::from_doc(resolved) {
+        findings.push(Finding {
+            severity: Severity::Error,
+            code: "invalid-proxy-policy",
+            service: None,
+            message: err,
+        });
+    }
+    let mut container_names: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (name, service) in services(resolved) {
+        lint_network_mode(name, service, &mut findings);
+        lint_ports(name, service, &mut findings);
+        lint_labels(name, service, &mut findings);
+        if let Some(container_name) = service.get("container_name").and_then(Value::as_str) {
+            container_names
+                .entry(container_name.to_string())
+                .or_default()
+                .push(name.to_string());
+        }
+    }
+    for (name, service) in services(raw) {
+        lint_raw_ports(name, service, &mut findings);
+    }
+    for (container_name, owners) in container_names {
+        if let [first, _, ..] = owners.as_slice() {
+            findings.push(Finding::new(
+                Severity::Error,
+                "duplicate-container-name",
+                first,
+                format!(
+                    "container_name '{container_name}' is used by {}",
+                    owners.join(", ")
+                ),
+            ));
+        }
+    }
+    findings.sort_by(|a, b| (a.severity, &a.service).cmp(&(b.severity, &b.service)));
+    findings
+}
+
+fn services(doc: &Value) -> Vec<(&str, &Mapping)> {
+    doc.get("services")
+        .and_then(Value::as_mapping)
+        .map(|services| {
+            services
+                .iter()
+                .filter_map(|(name, service)| Some((name.as_str()?, service.as_mapping()?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn lint_network_mode(name: &str, service: &Mapping, findings: &mut Vec<Finding>) {
+    let Some(mode) = service.get("network_mode").and_then(Value::as_str) else {
+        return;
+    };
+    let finding = match mode {
+        "bridge" | "default" => return,
+        "host" | "none" => Finding::new(
+            Severity::Warning,
+            "network-mode-unproxied",
+            name,
+            format!("network_mode '{mode}' is left unproxied; its traffic is not captured"),
+        ),
+        other => Finding::new(
+            Severity::Warning,
+            "unsupported-network-mode",
+            name,
+            format!(
+                "network_mode '{other}' is not supported; proxies and egress capture may not work"
+            ),
+        ),
+    };
+    findings.push(finding);
+}
+
+fn lint_ports(name: &str, service: &Mapping, findings: &mut Vec<Finding>) {
+    let Some(Value::Sequence(ports)) = service.get("ports") else {
+        return;
+    };
+    for port in ports.iter().filter_map(Value::as_mapping) {
+        let target = port.get("target").and_then(value_to_u16);
+        let protocol = port
+            .get("protocol")
+            .and_then(Value::as_str)
+            .unwrap_or("tcp");
+        match target {
+            Some(target) if protocol.eq_ignore_ascii_case("udp") => {
+                findings.push(Finding::new(
+                    Severity::Warning,
+                    "unproxyable-port",
+                    name,
+                    format!(
+                        "UDP port {target} cannot be proxied; set {PROXY_LABEL}=off on this service"
+                    ),
+                ));
+            }
+            Some(_) => {}
+            None => findings.push(Finding::new(
+                Severity::Warning,
+                "unproxyable-port",
+                name,
+                "port entry without a numeric target is skipped by the proxy".to_string(),
+            )),
+        }
+    }
+}
+
+fn lint_raw_ports(name: &str, service: &Mapping, findings: &mut Vec<Finding>) {
+    let entries = ["ports", "expose"]
+        .iter()
+        .filter_map(|key| service.get(*key).and_then(Value::as_sequence))
+        .flatten()
+        .filter_map(Value::as_str);
+    for entry in entries {
+        let parsed = parse_container_port(entry).or_else(|| value_to_u16(&entry.into()));
+        if parsed.is_some() {
+            continue;
+        }
+        let message = if entry.contains('$') {
+            format!("cannot resolve a container port from '{entry}'; give the variable a default like ${{PORT:-8080}}")
+        } else {
+            format!("cannot parse a container port from '{entry}'")
+        };
+        findings.push(Finding::new(
+            Severity::Warning,
+            "unparsed-port",
+            name,
+            message,
+        ));
+    }
+}
+
+fn lint_labels(name: &str, service: &Mapping, findings: &mut Vec<Finding>) {
+    for (key, value) in labels(service) {
+        if key == PROXY_LABEL {
+            let mode = value.trim().to_lowercase();
+            if !PROXY_MODES.contains(&mode.as_str()) {
+                findings.push(Finding::new(
+                    Severity::Error,
+                    "invalid-label-value",
+                    name,
+                    format!("{PROXY_LABEL}={value} is not one of auto, http, tcp or off"),
+                ));
+            }
+            continue;
+        }
+        if key == PROXY_PORTS_LABEL {
+            if let Err(err) = parse_proxy_ports(&value) {
+                findings.push(Finding::new(
+                    Severity::Error,
+                    "invalid-label-value",
+                    name,
+                    err,
+                ));
+            }
+            continue;
+        }
+        if USER_LABELS.contains(&key.as_str()) {
+            continue;
+        }
+        if RESERVED_LABELS.contains(&key.as_str()) {
+            findings.push(Finding::new(
+                Severity::Warning,
+                "reserved-label",
+                name,
+                format!("{key} is set by sanelens and will be overwritten"),
+            ));
+            continue;
+        }
+        let suggestion = USER_LABELS
+            .iter()
+            .map(|known| (edit_distance(&key, known), *known))
+            .min()
+            .filter(|(distance, _)| *distance <= LABEL_TYPO_DISTANCE);
+        let message = match suggestion {
+            Some((_, known)) => format!("unknown label {key}; did you mean {known}?"),
+            None if key.starts_with("sanelens") => format!("unknown label {key}"),
+            None => continue,
+        };
+        findings.push(Finding::new(
+            Severity::Warning,
+            "unknown-label",
+            name,
+            message,
+        ));
+    }
+}
+
+fn labels(service: &Mapping) -> Vec<(String, String)> {
+    match service.get("labels") {
+        Some(Value::Mapping(map)) => map
+            .iter()
+            .filter_map(|(key, value)| {
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    Value::Bool(value) => value.to_string(),
+                    Value::Number(value) => value.to_string(),
+                    _ => String::new(),
+                };
+                Some((key.as_str()?.to_string(), value))
+            })
+            .collect(),
+        Some(Value::Sequence(list)) => list
+            .iter()
+            .filter_map(Value::as_str)
+            .map(|entry| {
+                let (key, value) = entry.split_once('=').unwrap_or((entry, ""));
+                (key.to_string(), value.to_string())
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = previous.get(j).copied().unwrap_or(0) + usize::from(ca != *cb);
+            let delete = previous.get(j + 1).copied().unwrap_or(0) + 1;
+            let insert = current.get(j).copied().unwrap_or(0) + 1;
+            current.push(substitute.min(delete).min(insert));
+        }
+        previous = current;
+    }
+    previous.last().copied().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint_compose, Severity};
+
+    #[test]
+    fn reports_sanelens_specific_problems() {
+        let raw = serde_yaml::from_str("services:\n  api:\n    ports:\n      - \"${API_PORT}\"\n")
+            .unwrap_or_default();
+        let resolved = serde_yaml::from_str(
+            "services:\n  api:\n    container_name: shared\n    labels:\n      sanelens.proxi: http\n    ports:\n      - target: 53\n        protocol: udp\n  web:\n    container_name: shared\n    network_mode: host\n    labels:\n      sanelens.proxy: htp\n",
+        )
+        .unwrap_or_default();
+        let findings = lint_compose(&raw, &resolved);
+        let codes: Vec<(Severity, &str)> = findings
+            .iter()
+            .map(|finding| (finding.severity, finding.code))
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                (Severity::Error, "duplicate-container-name"),
+                (Severity::Error, "invalid-label-value"),
+                (Severity::Warning, "unproxyable-port"),
+                (Severity::Warning, "unknown-label"),
+                (Severity::Warning, "unparsed-port"),
+                (Severity::Warning, "network-mode-unproxied"),
+            ]
+        );
+        assert!(findings
+            .iter()
+            .any(|finding| finding.message.contains("did you mean sanelens.proxy")));
+    }
+}
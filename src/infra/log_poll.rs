@@ -0,0 +1,286 @@
+use std::cmp::Ordering as CmpOrdering;
+use std::io::{self, PipeWriter, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::infra::process::run_output;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const LIVE_STATES: [&str; 3] = ["running", "restarting", "paused"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct LogCapabilities {
+    pub follow: bool,
+    pub timestamps: bool,
+    pub since: bool,
+}
+
+impl LogCapabilities {
+    pub const ALL: Self = Self {
+        follow: true,
+        timestamps: true,
+        since: true,
+    };
+
+    pub fn probe(cmd: &[String]) -> Self {
+        let mut probe = cmd.to_vec();
+        probe.extend(["logs".to_string(), "--help".to_string()]);
+        let Ok(output) = run_output(&probe) else {
+            return Self::ALL;
+        };
+        let mut help = String::from_utf8_lossy(&output.stdout).into_owned();
+        help.push_str(&String::from_utf8_lossy(&output.stderr));
+        Self::from_help(&help)
+    }
+
+    pub fn from_help(help: &str) -> Self {
+        if !help.contains("--") {
+            return Self::ALL;
+        }
+        Self {
+            follow: help.contains("--follow"),
+            timestamps: help.contains("--timestamps"),
+            since: help.contains("--since"),
+        }
+    }
+}
+
+pub struct LogPoller {
+    pub cmd: Vec<String>,
+    pub cid: String,
+    pub stop: Arc<AtomicBool>,
+    pub capabilities: LogCapabilities,
+    pub timestamps: bool,
+    pub since: Option<i64>,
+}
+
+impl LogPoller {
+    pub fn spawn(self, stdout: PipeWriter, stderr: PipeWriter) {
+        thread::spawn(move || {
+            self.run(stdout, stderr);
+            self.stop.store(true, Ordering::SeqCst);
+        });
+    }
+
+    const fn incremental(&self) -> bool {
+        self.capabilities.since && self.capabilities.timestamps
+    }
+
+    fn run(&self, mut stdout: PipeWriter, mut stderr: PipeWriter) {
+        let (mut seen_out, mut seen_err) = (0, 0);
+        let (mut out_cursor, mut err_cursor) = (LogCursor::default(), LogCursor::default());
+        while !self.stop.load(Ordering::SeqCst) {
+            let live = self.container_is_live();
+            let since = out_cursor.resume_point(&err_cursor);
+            let Ok(output) = run_output(&self.logs_command(since)) else {
+                return;
+            };
+            if !output.status.success() {
+                return;
+            }
+            let wrote = if self.incremental() {
+                let strip = !self.timestamps;
+                out_cursor
+                    .write_new(&mut stdout, &output.stdout, strip)
+                    .and_then(|()| err_cursor.write_new(&mut stderr, &output.stderr, strip))
+            } else {
+                write_new_lines(&mut stdout, &output.stdout, &mut seen_out)
+                    .and_then(|()| write_new_lines(&mut stderr, &output.stderr, &mut seen_err))
+            };
+            if wrote.is_err() || !live {
+                return;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn logs_command(&self, resume: Option<&str>) -> Vec<String> {
+        let mut logs = self.cmd.clone();
+        logs.push("logs".to_string());
+        if self.capabilities.timestamps && (self.timestamps || self.incremental()) {
+            logs.push("--timestamps".to_string());
+        }
+        let since = resume
+            .filter(|_| self.incremental())
+            .map(ToString::to_string)
+            .or_else(|| {
+                self.since
+                    .filter(|_| self.capabilities.since)
+                    .map(|since| since.to_string())
+            });
+        if let Some(since) = since {
+            logs.extend(["--since".to_string(), since]);
+        }
+        logs.push(self.cid.clone());
+        logs
+    }
+
+    fn container_is_live(&self) -> bool {
+        let mut inspect = self.cmd.clone();
+        inspect.extend([
+            "inspect".to_string(),
+            "--format".to_string(),
+            "{{.State.Status}}".to_string(),
+            self.cid.clone(),
+        ]);
+        run_output(&inspect).is_ok_and(|output| {
+            let state = String::from_utf8_lossy(&output.stdout);
+            LIVE_STATES.contains(&state.trim())
+        })
+    }
+}
+
+#[derive(Default)]
+struct LogCursor {
+    last: Option<(OffsetDateTime, String)>,
+    at_last: usize,
+}
+
+impl LogCursor {
+    fn resume_point<'a>(&'a self, other: &'a Self) -> Option<&'a str> {
+        let earliest = match (&self.last, &other.last) {
+            (Some(ours), Some(theirs)) => Some(if theirs.0 < ours.0 { theirs } else { ours }),
+            (ours, theirs) => ours.as_ref().or(theirs.as_ref()),
+        };
+        earliest.map(|(_, raw)| raw.as_str())
+    }
+
+    fn write_new(&mut self, writer: &mut impl Write, output: &[u8], strip: bool) -> io::Result<()> {
+        let mut repeated = 0;
+        for line in complete_lines(output) {
+            let Some((stamp, raw, rest)) = split_timestamp(line) else {
+                writer.write_all(line)?;
+                continue;
+            };
+            let shown = if strip { rest } else { line };
+            if self.admit(stamp, raw, &mut repeated) {
+                writer.write_all(shown)?;
+            }
+        }
+        writer.flush()
+    }
+
+    fn admit(&mut self, stamp: OffsetDateTime, raw: &str, repeated: &mut usize) -> bool {
+        match self.last.as_ref().map(|(last, _)| stamp.cmp(last)) {
+            Some(CmpOrdering::Less) => false,
+            Some(CmpOrdering::Equal) => {
+                *repeated += 1;
+                if *repeated <= self.at_last {
+                    return false;
+                }
+                self.at_last += 1;
+                true
+            }
+            _ => {
+                self.last = Some((stamp, raw.to_string()));
+                self.at_last = 1;
+                *repeated = 1;
+                true
+            }
+        }
+    }
+}
+
+fn split_timestamp(line: &[u8]) -> Option<(OffsetDateTime, &str, &[u8])> {
+    let space = line.iter().position(|byte| *byte == b' ')?;
+    let raw = std::str::from_utf8(line.get(..space)?).ok()?;
+    let stamp = OffsetDateTime::parse(raw, &Rfc3339).ok()?;
+    Some((stamp, raw, line.get(space + 1..)?))
+}
+
+fn write_new_lines(writer: &mut impl Write, output: &[u8], seen: &mut usize) -> io::Result<()> {
+    let lines: Vec<&[u8]> = complete_lines(output).collect();
+    for line in lines.iter().skip(*seen) {
+        writer.write_all(line)?;
+    }
+    *seen = (*seen).max(lines.len());
+    writer.flush()
+}
+
+fn complete_lines(output: &[u8]) -> impl Iterator<Item = &[u8]> {
+    output
+        .split_inclusive(|byte| *byte == b'\n')
+        .filter(|line| line.ends_with(b"\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    use super::{write_new_lines, LogCapabilities, LogCursor, LogPoller};
+
+    #[test]
+    fn reads_supported_flags_from_help() {
+        let help = "Usage: logs [OPTIONS] CONTAINER\n  -t, --timestamps  Show timestamps\n";
+        let caps = LogCapabilities::from_help(help);
+        assert!(!caps.follow);
+        assert!(caps.timestamps);
+        assert!(!caps.since);
+        assert_eq!(LogCapabilities::from_help(""), LogCapabilities::ALL);
+    }
+
+    #[test]
+    fn only_writes_lines_past_the_previous_poll() {
+        let mut out = Vec::new();
+        let mut seen = 0;
+        assert!(write_new_lines(&mut out, b"one\ntwo\npart", &mut seen).is_ok());
+        assert!(write_new_lines(&mut out, b"one\ntwo\npartial\nthree\n", &mut seen).is_ok());
+        assert_eq!(out, b"one\ntwo\npartial\nthree\n");
+        assert_eq!(seen, 4);
+    }
+
+    #[test]
+    fn resumes_from_the_last_timestamp_without_repeating_lines() {
+        let mut out = Vec::new();
+        let mut cursor = LogCursor::default();
+        let first = b"2024-01-01T00:00:01.5Z one\n2024-01-01T00:00:02Z two\n";
+        assert!(cursor.write_new(&mut out, first, true).is_ok());
+        assert_eq!(
+            cursor.resume_point(&LogCursor::default()),
+            Some("2024-01-01T00:00:02Z")
+        );
+        let second =
+            b"2024-01-01T00:00:02Z two\n2024-01-01T00:00:02Z again\n2024-01-01T00:00:03Z three\n";
+        assert!(cursor.write_new(&mut out, second, true).is_ok());
+        assert_eq!(out, b"one\ntwo\nagain\nthree\n");
+    }
+
+    #[test]
+    fn polls_since_the_resume_point_when_the_engine_allows_it() {
+        let poller = |capabilities| LogPoller {
+            cmd: vec!["docker".to_string()],
+            cid: "abc".to_string(),
+            stop: Arc::new(AtomicBool::new(false)),
+            capabilities,
+            timestamps: false,
+            since: Some(100),
+        };
+        assert_eq!(
+            poller(LogCapabilities::ALL).logs_command(Some("2024-01-01T00:00:02Z")),
+            [
+                "docker",
+                "logs",
+                "--timestamps",
+                "--since",
+                "2024-01-01T00:00:02Z",
+                "abc"
+            ]
+        );
+        let no_since = LogCapabilities {
+            follow: false,
+            timestamps: true,
+            since: false,
+        };
+        assert_eq!(
+            poller(no_since).logs_command(Some("2024-01-01T00:00:02Z")),
+            ["docker", "logs", "abc"]
+        );
+    }
+}
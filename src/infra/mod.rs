@@ -1,7 +1,24 @@
+pub mod backend;
 pub mod compose;
+pub mod container_watch;
 pub mod derive;
+pub mod effective;
 pub mod engine;
+pub mod engine_api;
+pub mod envoy;
+pub mod explain;
+pub mod forward;
+pub mod image;
+pub mod lint;
+pub mod log_poll;
+pub mod probe;
 pub mod process;
+pub mod proxy;
 pub mod resolver;
+pub mod run_lock;
 pub mod traffic;
+pub mod tunnel;
 pub mod ui;
+pub mod ui_daemon;
+pub mod verify;
+pub mod watch;
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::domain::{EndpointProtocol, ServiceInfo};
+use crate::infra::engine::parallel_map;
+use crate::support::probe::{
+    apply_protocols, classify_banner, classify_reply, http_probe_request, is_postgres_ssl_reply,
+    port_hint, POSTGRES_SSL_REQUEST,
+};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+const BANNER_TIMEOUT: Duration = Duration::from_millis(300);
+const REPLY_TIMEOUT: Duration = Duration::from_millis(800);
+const REPLY_BYTES: usize = 512;
+const FAILURE_TTL: Duration = Duration::from_secs(30);
+
+type ProbeKey = (String, u16);
+
+enum ProbeState {
+    Probing,
+    Found(EndpointProtocol),
+    Failed(Instant),
+}
+
+#[derive(Default)]
+pub struct EndpointProbes {
+    states: Arc<Mutex<HashMap<ProbeKey, ProbeState>>>,
+}
+
+impl EndpointProbes {
+    pub fn annotate(&self, services: &[ServiceInfo]) -> Vec<ServiceInfo> {
+        let due = self.claim_due(services, Instant::now());
+        if !due.is_empty() {
+            let states = self.states.clone();
+            thread::spawn(move || probe_into(&states, due));
+        }
+        let detected: HashMap<ProbeKey, EndpointProtocol> = lock_states(&self.states)
+            .iter()
+            .filter_map(|(key, state)| match state {
+                ProbeState::Found(protocol) => Some((key.clone(), *protocol)),
+                ProbeState::Probing | ProbeState::Failed(_) => None,
+            })
+            .collect();
+        services
+            .iter()
+            .cloned()
+            .map(|mut service| {
+                apply_protocols(&mut service, |host, port| {
+                    detected.get(&(host.to_string(), port)).copied()
+                });
+                service
+            })
+            .collect()
+    }
+
+    pub fn refresh(&self, services: &[ServiceInfo]) {
+        let due = self.claim_due(services, Instant::now());
+        probe_into(&self.states, due);
+    }
+
+    fn claim_due(&self, services: &[ServiceInfo], now: Instant) -> Vec<ProbeKey> {
+        let mut states = lock_states(&self.states);
+        let mut due: Vec<ProbeKey> = services
+            .iter()
+            .flat_map(|service| &service.published)
+            .map(|endpoint| (endpoint.host.clone(), endpoint.port))
+            .filter(|key| match states.get(key) {
+                None => true,
+                Some(ProbeState::Failed(at)) => now.duration_since(*at) >= FAILURE_TTL,
+                Some(ProbeState::Probing | ProbeState::Found(_)) => false,
+            })
+            .collect();
+        due.sort();
+        due.dedup();
+        for key in &due {
+            states.insert(key.clone(), ProbeState::Probing);
+        }
+        due
+    }
+}
+
+fn probe_into(states: &Mutex<HashMap<ProbeKey, ProbeState>>, due: Vec<ProbeKey>) {
+    if due.is_empty() {
+        return;
+    }
+    let found = parallel_map(&due, |(host, port)| probe_endpoint(host, *port));
+    let now = Instant::now();
+    let mut states = lock_states(states);
+    for (key, protocol) in due.into_iter().zip(found) {
+        let state = protocol
+            .flatten()
+            .map_or(ProbeState::Failed(now), ProbeState::Found);
+        states.insert(key, state);
+    }
+}
+
+fn lock_states(
+    states: &Mutex<HashMap<ProbeKey, ProbeState>>,
+) -> MutexGuard<'_, HashMap<ProbeKey, ProbeState>> {
+    states.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+pub fn probe_endpoint(host: &str, port: u16) -> Option<EndpointProtocol> {
+    let addr = (host, port).to_socket_addrs().ok()?.next()?;
+    let mut stream = connect(addr)?;
+    if let Some(banner) = read_reply(&mut stream, BANNER_TIMEOUT) {
+        return Some(classify_banner(&banner).unwrap_or_else(|| port_hint(port)));
+    }
+    let request = http_probe_request(host, port);
+    if stream.write_all(request.as_bytes()).is_ok() {
+        if let Some(protocol) = read_reply(&mut stream, REPLY_TIMEOUT)
+            .as_deref()
+            .and_then(classify_reply)
+        {
+            return Some(protocol);
+        }
+    }
+    let mut stream = connect(addr)?;
+    if stream.write_all(&POSTGRES_SSL_REQUEST).is_ok()
+        && read_reply(&mut stream, REPLY_TIMEOUT).is_some_and(|reply| is_postgres_ssl_reply(&reply))
+    {
+        return Some(EndpointProtocol::Postgres);
+    }
+    Some(port_hint(port))
+}
+
+fn connect(addr: SocketAddr) -> Option<TcpStream> {
+    TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).ok()
+}
+
+fn read_reply(stream: &mut TcpStream, timeout: Duration) -> Option<Vec<u8>> {
+    stream.set_read_timeout(Some(timeout)).ok()?;
+    let mut buf = vec![0_u8; REPLY_BYTES];
+    let read = stream.read(&mut buf).ok().filter(|read| *read > 0)?;
+    buf.truncate(read);
+    Some(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::PublishedEndpoint;
+    use std::net::TcpListener;
+
+    fn serve_once(reply: &'static [u8], wait_for_request: bool) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0");
+        assert!(listener.is_ok());
+        let Ok(listener) = listener else {
+            return 0;
+        };
+        let port = listener
+            .local_addr()
+            .map(|addr| addr.port())
+            .unwrap_or_default();
+        thread::spawn(move || {
+            let Ok((mut stream, _)) = listener.accept() else {
+                return;
+            };
+            if wait_for_request {
+                let _ = stream.read(&mut [0_u8; 256]);
+            }
+            let _ = stream.write_all(reply);
+        });
+        port
+    }
+
+    #[test]
+    fn detects_http_and_banner_protocols() {
+        let http = serve_once(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n", true);
+        assert_eq!(
+            probe_endpoint("127.0.0.1", http),
+            Some(EndpointProtocol::Http)
+        );
+        let mysql = serve_once(b"\x4a\x00\x00\x00\x0a8.0.36\x00", false);
+        assert_eq!(
+            probe_endpoint("127.0.0.1", mysql),
+            Some(EndpointProtocol::Mysql)
+        );
+    }
+
+    #[test]
+    fn caches_unreachable_endpoints_until_the_ttl_expires() {
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .map(|addr| addr.port())
+            .unwrap_or_default();
+        let services = vec![ServiceInfo {
+            name: "api".to_string(),
+            endpoints: Vec::new(),
+            endpoint: None,
+            exposed: true,
+            published: vec![PublishedEndpoint {
+                host: "127.0.0.1".to_string(),
+                port: closed,
+                url: String::new(),
+                protocol: None,
+                forwarded: false,
+            }],
+        }];
+        let probes = EndpointProbes::default();
+        let start = Instant::now();
+        probes.refresh(&services);
+        assert!(probes.claim_due(&services, start).is_empty());
+        assert_eq!(
+            probes.claim_due(&services, start + FAILURE_TTL + FAILURE_TTL),
+            vec![("127.0.0.1".to_string(), closed)]
+        );
+        let annotated = probes.annotate(&services);
+        assert_eq!(
+            annotated
+                .first()
+                .and_then(|service| service.published.first())
+                .and_then(|endpoint| endpoint.protocol),
+            None
+        );
+    }
+}
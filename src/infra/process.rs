@@ -1,5 +1,5 @@
 use std::env;
-use std::io;
+use std::io::{self, Read};
 use std::path::Path;
 use std::process::{Child, Command, Output, Stdio};
 use std::thread;
@@ -21,9 +21,7 @@ pub fn command_exists(cmd: &str) -> bool {
 }
 
 pub fn run_status(cmd: &[String]) -> bool {
-    run_output(cmd)
-        .map(|output| output.status.success())
-        .unwrap_or(false)
+    run_output(cmd).is_ok_and(|output| output.status.success())
 }
 
 pub fn run_output(cmd: &[String]) -> io::Result<Output> {
@@ -38,6 +36,40 @@ pub fn run_output(cmd: &[String]) -> io::Result<Output> {
     command.output()
 }
 
+pub fn run_output_timeout(cmd: &[String], timeout: Duration) -> Option<Output> {
+    let (program, args) = cmd.split_first()?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .ok()?;
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+    if !wait_child_timeout(&mut child, timeout) {
+        let _ = child.kill();
+        let _ = child.wait();
+        return None;
+    }
+    let status = child.wait().ok()?;
+    Some(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
 pub fn spawn_process_group(cmd: &mut Command) -> io::Result<Child> {
     #[cfg(unix)]
     {
@@ -54,6 +86,17 @@ pub fn spawn_process_group(cmd: &mut Command) -> io::Result<Child> {
     cmd.spawn()
 }
 
+pub fn kill_process_group(pid: u32) {
+    #[cfg(unix)]
+    if let Ok(pid) = i32::try_from(pid) {
+        unsafe {
+            libc::killpg(pid, libc::SIGKILL);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+}
+
 pub fn terminate_process(child: &mut Child, timeout: Duration) {
     if child.try_wait().ok().flatten().is_some() {
         return;
@@ -116,3 +159,17 @@ pub fn pid_alive(pid: i32) -> bool {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::run_output_timeout;
+
+    #[test]
+    fn drains_output_larger_than_the_pipe_buffer() {
+        let cmd = ["sh", "-c", "head -c 200000 /dev/zero"].map(ToString::to_string);
+        let output = run_output_timeout(&cmd, Duration::from_secs(5));
+        assert_eq!(output.map(|output| output.stdout.len()), Some(200_000));
+    }
+}
@@ -0,0 +1,94 @@
+use serde_yaml::{Mapping, Value};
+
+use crate::support::glob::glob_match;
+
+const EXTENSION_KEY: &str = "x-sanelens";
+const PROXY_KEY: &str = "proxy";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProxyPolicy {
+    default: Option<String>,
+    rules: Vec<(String, String)>,
+}
+
+impl ProxyPolicy {
+    pub fn from_doc(doc: &Value) -> Result<Self, String> {
+        let Some(section) = doc
+            .get(EXTENSION_KEY)
+            .and_then(|value| value.get(PROXY_KEY))
+        else {
+            return Ok(Self::default());
+        };
+        let Value::Mapping(section) = section else {
+            return Err(format!("{EXTENSION_KEY}.{PROXY_KEY} must be a mapping"));
+        };
+        let default = section.get("default").map(proxy_mode).transpose()?;
+        let rules = match section.get("services") {
+            Some(Value::Mapping(services)) => parse_rules(services)?,
+            Some(_) => {
+                return Err(format!(
+                    "{EXTENSION_KEY}.{PROXY_KEY}.services must be a mapping of patterns to modes"
+                ))
+            }
+            None => Vec::new(),
+        };
+        Ok(Self { default, rules })
+    }
+
+    pub fn resolve(&self, service_name: &str, label: Option<String>) -> Option<String> {
+        label
+            .or_else(|| {
+                self.rules
+                    .iter()
+                    .find(|(pattern, _)| glob_match(pattern, service_name))
+                    .map(|(_, mode)| mode.clone())
+            })
+            .or_else(|| self.default.clone())
+    }
+}
+
+fn parse_rules(services: &Mapping) -> Result<Vec<(String, String)>, String> {
+    services
+        .iter()
+        .map(|(pattern, mode)| {
+            let pattern = pattern.as_str().ok_or_else(|| {
+                format!("{EXTENSION_KEY}.{PROXY_KEY}.services keys must be service names or globs")
+            })?;
+            Ok((pattern.to_string(), proxy_mode(mode)?))
+        })
+        .collect()
+}
+
+fn proxy_mode(value: &Value) -> Result<String, String> {
+    match value {
+        Value::Bool(true) => Ok("true".to_string()),
+        Value::Bool(false) => Ok("off".to_string()),
+        Value::String(mode) => Ok(mode.trim().to_lowercase()),
+        _ => Err(format!(
+            "{EXTENSION_KEY}.{PROXY_KEY} modes must be one of auto, http, tcp or off"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProxyPolicy;
+
+    #[test]
+    fn resolves_labels_then_globs_then_default() {
+        let doc = serde_yaml::from_str(
+            "x-sanelens:\n  proxy:\n    default: off\n    services:\n      api-*: http\n      worker: true\n",
+        )
+        .unwrap_or_default();
+        let policy = ProxyPolicy::from_doc(&doc).unwrap_or_default();
+        assert_eq!(policy.resolve("api-users", None).as_deref(), Some("http"));
+        assert_eq!(policy.resolve("worker", None).as_deref(), Some("true"));
+        assert_eq!(policy.resolve("db", None).as_deref(), Some("off"));
+        assert_eq!(
+            policy
+                .resolve("api-users", Some("tcp".to_string()))
+                .as_deref(),
+            Some("tcp")
+        );
+    }
+}
@@ -1,12 +1,52 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::net::IpAddr;
+use std::sync::{Arc, Mutex, PoisonError};
 
-use crate::domain::traffic::{EntityId, Resolver, Socket};
+use crate::domain::traffic::{EntityId, Resolver, Socket, HOST_ENTITY};
 use crate::domain::Scope;
-use crate::infra::engine::{ContainerInfo, Engine};
+use crate::infra::engine::{scaled_instances, ContainerInfo, Engine};
+
+const COMPOSE_PROJECT_LABEL: &str = "com.docker.compose.project";
 
 pub struct RuntimeResolver {
     ip_map: HashMap<IpAddr, EntityId>,
+    networks: HashMap<IpAddr, String>,
+    gateways: HashSet<IpAddr>,
+    dns: Arc<DnsCache>,
+}
+
+#[derive(Default)]
+pub struct DnsCache {
+    entries: Mutex<DnsEntries>,
+}
+
+#[derive(Default)]
+struct DnsEntries {
+    by_ip: HashMap<IpAddr, String>,
+    by_host: HashMap<String, Vec<IpAddr>>,
+}
+
+impl DnsCache {
+    pub fn record(&self, host: &str, ip: Option<IpAddr>) -> Vec<IpAddr> {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(ip) = ip.filter(|ip| !ip.is_unspecified()) {
+            entries.by_ip.insert(ip, host.to_string());
+            let ips = entries.by_host.entry(host.to_string()).or_default();
+            if !ips.contains(&ip) {
+                ips.push(ip);
+            }
+        }
+        entries.by_host.get(host).cloned().unwrap_or_default()
+    }
+
+    pub fn host(&self, ip: &IpAddr) -> Option<String> {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .by_ip
+            .get(ip)
+            .cloned()
+    }
 }
 
 impl RuntimeResolver {
@@ -14,16 +54,28 @@ impl RuntimeResolver {
         engine: &Engine,
         run_id: &str,
         service_aliases: &HashMap<String, String>,
+        dns: Arc<DnsCache>,
     ) -> Self {
         let ids = engine.collect_run_container_ids(run_id, Scope::Running);
         let containers = engine.inspect_containers(&ids);
         Self {
+            networks: build_network_map(&containers),
+            gateways: containers
+                .iter()
+                .flat_map(|container| container.gateways.iter().copied())
+                .collect(),
             ip_map: build_ip_map(containers, service_aliases),
+            dns,
         }
     }
 
     pub fn resolve_ip(&self, ip: &IpAddr) -> Option<EntityId> {
-        self.ip_map.get(ip).cloned()
+        if let Some(entity) = self.ip_map.get(ip) {
+            return Some(entity.clone());
+        }
+        is_host_source(ip, &self.gateways).then(|| EntityId::Host {
+            name: HOST_ENTITY.to_string(),
+        })
     }
 }
 
@@ -31,6 +83,41 @@ impl Resolver for RuntimeResolver {
     fn resolve_entity(&self, socket: &Socket) -> Option<EntityId> {
         self.resolve_ip(&socket.ip)
     }
+
+    fn record_dns(&self, host: &str, ip: Option<IpAddr>) -> Vec<IpAddr> {
+        self.dns.record(host, ip)
+    }
+
+    fn network_name(&self, ip: &IpAddr) -> Option<String> {
+        self.networks.get(ip).cloned()
+    }
+
+    fn dns_name(&self, ip: &IpAddr) -> Option<String> {
+        self.dns.host(ip)
+    }
+}
+
+fn is_host_source(ip: &IpAddr, gateways: &HashSet<IpAddr>) -> bool {
+    let private = match ip {
+        IpAddr::V4(ip) => ip.is_private(),
+        IpAddr::V6(ip) => ip.is_unique_local(),
+    };
+    private && gateways.contains(ip)
+}
+
+fn build_network_map(containers: &[ContainerInfo]) -> HashMap<IpAddr, String> {
+    let mut map = HashMap::new();
+    for container in containers {
+        let project = container.labels.get(COMPOSE_PROJECT_LABEL);
+        for (network, ip) in &container.network_ips {
+            let name = project
+                .and_then(|project| network.strip_prefix(project.as_str()))
+                .and_then(|rest| rest.strip_prefix('_'))
+                .unwrap_or(network);
+            map.insert(*ip, name.to_string());
+        }
+    }
+    map
 }
 
 fn build_ip_map(
@@ -38,17 +125,14 @@ fn build_ip_map(
     service_aliases: &HashMap<String, String>,
 ) -> HashMap<IpAddr, EntityId> {
     let mut map = HashMap::new();
+    let instances = scaled_instances(&containers, service_aliases);
     for container in containers {
         let name = container
             .service
             .clone()
             .unwrap_or_else(|| "unknown".to_string());
         let name = service_aliases.get(&name).cloned().unwrap_or(name);
-        let instance = if container.id.is_empty() {
-            None
-        } else {
-            Some(container.id.chars().take(12).collect())
-        };
+        let instance = instances.get(&container.id).map(ToString::to_string);
         let entity = EntityId::Workload { name, instance };
         for ip in container.ips {
             map.insert(ip, entity.clone());
@@ -56,3 +140,50 @@ fn build_ip_map(
     }
     map
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use std::collections::HashSet;
+
+    use super::{is_host_source, DnsCache};
+
+    #[test]
+    fn keeps_every_address_per_host_and_names_each_one() {
+        let cache = DnsCache::default();
+        let first = IpAddr::V4(Ipv4Addr::new(140, 82, 112, 3));
+        let second = IpAddr::V4(Ipv4Addr::new(140, 82, 112, 4));
+        assert_eq!(cache.record("api.github.com", None), Vec::<IpAddr>::new());
+        assert_eq!(cache.record("api.github.com", Some(first)), vec![first]);
+        assert_eq!(
+            cache.record("api.github.com", Some(second)),
+            vec![first, second]
+        );
+        assert_eq!(
+            cache.record("api.github.com", Some(first)),
+            vec![first, second]
+        );
+        assert_eq!(cache.record("api.github.com", None), vec![first, second]);
+        assert_eq!(cache.host(&second).as_deref(), Some("api.github.com"));
+        assert_eq!(
+            cache.record("example.com", Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED))),
+            Vec::<IpAddr>::new()
+        );
+    }
+
+    #[test]
+    fn bridge_gateways_are_the_host() {
+        let gateway = IpAddr::V4(Ipv4Addr::new(172, 18, 0, 1));
+        let gateways = HashSet::from([gateway, IpAddr::V4(Ipv4Addr::new(8, 8, 8, 1))]);
+        assert!(is_host_source(&gateway, &gateways));
+        assert!(!is_host_source(
+            &IpAddr::V4(Ipv4Addr::new(172, 18, 0, 5)),
+            &gateways
+        ));
+        assert!(!is_host_source(
+            &IpAddr::V4(Ipv4Addr::new(8, 8, 8, 1)),
+            &gateways
+        ));
+    }
+}
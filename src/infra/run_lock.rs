@@ -0,0 +1,146 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockHolder {
+    pub compose_file: String,
+    pub run_id: String,
+    pub pid: u32,
+    pub started_at: String,
+    #[serde(default)]
+    pub detached: bool,
+}
+
+pub enum LockOutcome {
+    Acquired(RunLock),
+    Held(LockHolder),
+}
+
+pub struct RunLock {
+    path: PathBuf,
+    run_id: String,
+    detached: bool,
+}
+
+impl RunLock {
+    pub fn acquire(
+        dir: &Path,
+        holder: &LockHolder,
+        is_live: impl Fn(&LockHolder) -> bool,
+    ) -> Result<LockOutcome, String> {
+        fs::create_dir_all(dir)
+            .map_err(|err| format!("failed to create {}: {err}", dir.display()))?;
+        let path = dir.join(format!("{:016x}.json", fnv1a(&holder.compose_file)));
+        let staged = path.with_extension(format!("{}.tmp", holder.pid));
+        let payload = serde_json::to_vec(holder).map_err(|err| err.to_string())?;
+        fs::write(&staged, payload)
+            .map_err(|err| format!("failed to write {}: {err}", staged.display()))?;
+        let outcome = link_lock(&staged, &path, is_live);
+        let _ = fs::remove_file(&staged);
+        outcome.map(|held| {
+            held.map_or_else(
+                || {
+                    LockOutcome::Acquired(Self {
+                        path,
+                        run_id: holder.run_id.clone(),
+                        detached: holder.detached,
+                    })
+                },
+                LockOutcome::Held,
+            )
+        })
+    }
+
+    pub fn finish(self, succeeded: bool) {
+        if self.detached && succeeded {
+            std::mem::forget(self);
+        }
+    }
+}
+
+impl Drop for RunLock {
+    fn drop(&mut self) {
+        if read_holder(&self.path).is_some_and(|holder| holder.run_id == self.run_id) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn link_lock(
+    staged: &Path,
+    path: &Path,
+    is_live: impl Fn(&LockHolder) -> bool,
+) -> Result<Option<LockHolder>, String> {
+    for _ in 0..3 {
+        match fs::hard_link(staged, path) {
+            Ok(()) => return Ok(None),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+            Err(err) => return Err(format!("failed to lock {}: {err}", path.display())),
+        }
+        let current = read_holder(path);
+        if let Some(holder) = current.clone().filter(|holder| is_live(holder)) {
+            return Ok(Some(holder));
+        }
+        if read_holder(path) == current {
+            let _ = fs::remove_file(path);
+        }
+    }
+    Err(format!(
+        "failed to lock {}: it keeps changing",
+        path.display()
+    ))
+}
+
+fn read_holder(path: &Path) -> Option<LockHolder> {
+    let contents = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn fnv1a(value: &str) -> u64 {
+    value.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LockHolder, LockOutcome, RunLock};
+
+    fn holder(run_id: &str, pid: u32) -> LockHolder {
+        LockHolder {
+            compose_file: "/work/app/compose.yaml".to_string(),
+            run_id: run_id.to_string(),
+            pid,
+            started_at: "2026-01-01T00:00:00Z".to_string(),
+            detached: false,
+        }
+    }
+
+    #[test]
+    fn reports_a_live_holder_and_replaces_a_stale_one() {
+        let dir = std::env::temp_dir().join(format!("sanelens-lock-{}", std::process::id()));
+        let first = RunLock::acquire(&dir, &holder("run_aaaaaa", 1), |_| true);
+        assert!(matches!(first, Ok(LockOutcome::Acquired(_))));
+        let conflict = RunLock::acquire(&dir, &holder("run_bbbbbb", 2), |_| true);
+        assert!(matches!(
+            conflict,
+            Ok(LockOutcome::Held(ref existing)) if existing.run_id == "run_aaaaaa"
+        ));
+        let replaced = RunLock::acquire(&dir, &holder("run_cccccc", 3), |held| held.pid != 1);
+        assert!(matches!(replaced, Ok(LockOutcome::Acquired(_))));
+        drop(first);
+        let after = RunLock::acquire(&dir, &holder("run_dddddd", 4), |_| true);
+        assert!(matches!(
+            after,
+            Ok(LockOutcome::Held(ref existing)) if existing.run_id == "run_cccccc"
+        ));
+        drop(replaced);
+        let released = RunLock::acquire(&dir, &holder("run_eeeeee", 5), |_| true);
+        assert!(matches!(released, Ok(LockOutcome::Acquired(_))));
+        drop(released);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
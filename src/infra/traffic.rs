@@ -2,9 +2,12 @@ use std::collections::BTreeMap;
 use std::net::{IpAddr, SocketAddr};
 
 use crate::domain::traffic::{
-    Confidence, Correlation, EntityId, FlowKey, FlowMetrics, FlowObservation, HttpObservation,
-    Observation, ObservationAttrs, Peer, Resolver, Socket, Transport, Visibility,
+    Confidence, Correlation, DbObservation, EntityId, FlowKey, FlowMetrics, FlowObservation,
+    HttpObservation, Observation, ObservationAttrs, Peer, Resolver, Socket, Transport, UpgradeInfo,
+    UpgradeObservation, Visibility, NETWORK_TAG, ORIGIN_TAG,
 };
+use crate::support::chaos::{fault_kind, CHAOS_TAG};
+use crate::support::dbwire::{decode_base64, DbProtocol, DbStatement, DbStreamDecoder};
 use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
 
@@ -18,9 +21,11 @@ pub struct EnvoyAccessLog {
     #[allow(dead_code)]
     pub protocol: Option<String>,
     pub response_code: Option<u16>,
+    pub response_flags: Option<String>,
     pub duration_ms: Option<u64>,
     pub downstream_remote_address: Option<String>,
     pub upstream_host: Option<String>,
+    pub requested_server_name: Option<String>,
     pub bytes_received: Option<u64>,
     pub bytes_sent: Option<u64>,
     pub request_id: Option<String>,
@@ -33,12 +38,20 @@ pub struct EnvoyAccessLog {
     pub response_content_type: Option<String>,
     pub response_content_length: Option<String>,
     pub response_body: Option<String>,
+    pub upgrade: Option<String>,
 }
 
-struct EnvoyObservationContext<'a> {
-    service_name: &'a str,
-    resolver: &'a dyn Resolver,
-    is_egress: bool,
+impl EnvoyAccessLog {
+    pub fn is_upgrade(&self) -> bool {
+        self.upgrade.is_some() || self.response_code == Some(101)
+    }
+}
+
+pub struct ObservationContext<'a> {
+    pub service_name: &'a str,
+    pub resolver: &'a dyn Resolver,
+    pub is_egress: bool,
+    pub max_body_bytes: usize,
 }
 
 struct EnvoySockets {
@@ -58,6 +71,8 @@ struct HttpLogParts {
     response_headers: BTreeMap<String, String>,
     request_body: Option<String>,
     response_body: Option<String>,
+    request_body_truncated: u64,
+    response_body_truncated: u64,
 }
 
 struct RequestHeaderParts {
@@ -84,9 +99,11 @@ fn build_envoy_access_log(obj: &serde_json::Map<String, serde_json::Value>) -> E
         authority: string_field(obj, "authority"),
         protocol: string_field(obj, "protocol"),
         response_code: u16_field(obj, "response_code"),
+        response_flags: string_field(obj, "response_flags"),
         duration_ms: u64_field(obj, "duration_ms"),
         downstream_remote_address: string_field(obj, "downstream_remote_address"),
         upstream_host: string_field(obj, "upstream_host"),
+        requested_server_name: normalize_header_value(string_field(obj, "requested_server_name")),
         bytes_received: u64_field(obj, "bytes_received"),
         bytes_sent: u64_field(obj, "bytes_sent"),
         request_id: string_field(obj, "request_id"),
@@ -99,6 +116,7 @@ fn build_envoy_access_log(obj: &serde_json::Map<String, serde_json::Value>) -> E
         response_content_type: string_field(obj, "response_content_type"),
         response_content_length: string_field(obj, "response_content_length"),
         response_body: string_field(obj, "response_body"),
+        upgrade: normalize_header_value(string_field(obj, "upgrade")),
     }
 }
 
@@ -120,38 +138,22 @@ fn u16_field(obj: &serde_json::Map<String, serde_json::Value>, key: &str) -> Opt
 
 pub fn observation_from_envoy(
     log: EnvoyAccessLog,
-    service_name: &str,
-    resolver: &dyn Resolver,
-    is_egress: bool,
+    context: &ObservationContext<'_>,
     now_ms: u64,
 ) -> Option<Observation> {
     let sockets = parse_envoy_sockets(&log);
-    let context = EnvoyObservationContext {
-        service_name,
-        resolver,
-        is_egress,
-    };
-    let (peer, attrs) = resolve_peer_and_attrs(&log, &context, &sockets);
+    let (peer, attrs) = resolve_peer_and_attrs(&log, context, &sockets);
 
     if attrs.visibility == Visibility::L7Semantics {
-        return Some(build_http_observation(
-            log,
-            peer,
-            attrs,
-            now_ms,
-            context.is_egress,
-        ));
+        return Some(build_http_observation(log, peer, attrs, now_ms, context));
     }
 
     build_flow_observation(&log, peer, attrs, now_ms, &sockets)
 }
 
-#[allow(clippy::too_many_lines)]
 pub fn observation_from_tap(
     payload: &str,
-    service_name: &str,
-    resolver: &dyn Resolver,
-    is_egress: bool,
+    context: &ObservationContext<'_>,
     now_ms: u64,
 ) -> Option<Observation> {
     let value: serde_json::Value = serde_json::from_str(payload).ok()?;
@@ -177,47 +179,16 @@ pub fn observation_from_tap(
     let status = header_value(&response_headers, ":status")
         .or_else(|| header_value(&response_headers, "status"))
         .and_then(|value| value.parse::<u16>().ok());
+    if status == Some(101) {
+        return None;
+    }
 
-    let request_content_type = request_headers.get("content-type").cloned();
-    let response_content_type = response_headers.get("content-type").cloned();
-    let request_body_raw = parse_tap_body(tap_object(request, "body", "body"));
-    let response_body_raw = parse_tap_body(tap_object(response, "body", "body"));
-    let request_body = normalize_body(request_body_raw.clone(), request_content_type.as_deref());
-    let response_body = normalize_body(response_body_raw.clone(), response_content_type.as_deref());
-    let bytes_in = parse_content_length(&request_headers)
-        .or_else(|| request_body_raw.as_ref().map(|body| body.len() as u64));
-    let bytes_out = parse_content_length(&response_headers)
-        .or_else(|| response_body_raw.as_ref().map(|body| body.len() as u64));
-
+    let request_body = tap_body(request, &request_headers, context.max_body_bytes);
+    let response_body = tap_body(response, &response_headers, context.max_body_bytes);
     let (at_ms, duration_ms) = tap_timing(request, response, now_ms);
-    let downstream_socket =
-        parse_tap_connection(trace, "downstream_connection", "downstreamConnection");
-    let upstream_socket = parse_tap_connection(trace, "upstream_connection", "upstreamConnection");
-    let src_entity = downstream_socket
-        .as_ref()
-        .and_then(|socket| resolver.resolve_entity(socket));
-    let dst_entity = if is_egress {
-        parse_external_entity(authority.as_deref()).or_else(|| {
-            upstream_socket.as_ref().map(|socket| EntityId::External {
-                ip: socket.ip,
-                dns_name: None,
-            })
-        })
-    } else {
-        Some(EntityId::Workload {
-            name: service_name.to_string(),
-            instance: None,
-        })
-    };
-    let confidence = resolve_confidence(src_entity.as_ref(), dst_entity.as_ref());
-    let peer = build_peer(src_entity, dst_entity, downstream_socket, upstream_socket);
-    let attrs = ObservationAttrs {
-        visibility: Visibility::L7Semantics,
-        confidence,
-        tags: BTreeMap::default(),
-    };
-
-    let path = build_http_path_parts(path, authority.as_deref(), None, is_egress);
+    let (peer, mut attrs) = tap_peer_and_attrs(trace, context, authority.as_deref());
+    record_truncation(&mut attrs, request_body.dropped, response_body.dropped);
+    let path = build_http_path_parts(path, authority.as_deref(), None, context.is_egress);
 
     Some(Observation::Http(HttpObservation {
         at_ms,
@@ -226,12 +197,12 @@ pub fn observation_from_tap(
         path,
         status,
         duration_ms,
-        bytes_in,
-        bytes_out,
+        bytes_in: request_body.bytes,
+        bytes_out: response_body.bytes,
         request_headers,
         response_headers,
-        request_body,
-        response_body,
+        request_body: request_body.body,
+        response_body: response_body.body,
         correlation: Correlation {
             request_id,
             ..Default::default()
@@ -240,6 +211,61 @@ pub fn observation_from_tap(
     }))
 }
 
+struct TapBody {
+    body: Option<String>,
+    bytes: Option<u64>,
+    dropped: u64,
+}
+
+fn tap_body(
+    message: &serde_json::Map<String, serde_json::Value>,
+    headers: &BTreeMap<String, String>,
+    max_body_bytes: usize,
+) -> TapBody {
+    let length = parse_content_length(headers);
+    let (raw, cut) = parse_tap_body(tap_object(message, "body", "body"), length);
+    let bytes = length.or_else(|| raw.as_ref().map(|body| body.len() as u64));
+    let content_type = headers.get("content-type").map(String::as_str);
+    let (body, trimmed) = normalize_body(raw, content_type, max_body_bytes);
+    TapBody {
+        body,
+        bytes,
+        dropped: cut + trimmed,
+    }
+}
+
+fn tap_peer_and_attrs(
+    trace: &serde_json::Map<String, serde_json::Value>,
+    context: &ObservationContext<'_>,
+    authority: Option<&str>,
+) -> (Peer, ObservationAttrs) {
+    let resolver = context.resolver;
+    let downstream_socket =
+        parse_tap_connection(trace, "downstream_connection", "downstreamConnection");
+    let upstream_socket = parse_tap_connection(trace, "upstream_connection", "upstreamConnection");
+    let src_entity = downstream_socket
+        .as_ref()
+        .and_then(|socket| resolver.resolve_entity(socket));
+    let dst_entity = if context.is_egress {
+        external_entity(resolver, authority, upstream_socket.as_ref())
+    } else {
+        Some(service_entity(
+            resolver,
+            context.service_name,
+            upstream_socket.as_ref(),
+        ))
+    };
+    let confidence = resolve_confidence(src_entity.as_ref(), dst_entity.as_ref());
+    let peer = build_peer(src_entity, dst_entity, downstream_socket, upstream_socket);
+    let mut attrs = ObservationAttrs {
+        visibility: Visibility::L7Semantics,
+        confidence,
+        tags: BTreeMap::default(),
+    };
+    tag_peer(&mut attrs, resolver, &peer);
+    (peer, attrs)
+}
+
 fn parse_envoy_sockets(log: &EnvoyAccessLog) -> EnvoySockets {
     let downstream = log
         .downstream_remote_address
@@ -254,19 +280,14 @@ fn parse_envoy_sockets(log: &EnvoyAccessLog) -> EnvoySockets {
 
 fn resolve_peer_and_attrs(
     log: &EnvoyAccessLog,
-    context: &EnvoyObservationContext<'_>,
+    context: &ObservationContext<'_>,
     sockets: &EnvoySockets,
 ) -> (Peer, ObservationAttrs) {
     let src_entity = sockets
         .downstream
         .as_ref()
         .and_then(|socket| context.resolver.resolve_entity(socket));
-    let dst_entity = resolve_dst_entity(
-        log,
-        context.is_egress,
-        context.service_name,
-        sockets.upstream.as_ref(),
-    );
+    let dst_entity = resolve_dst_entity(log, context, sockets.upstream.as_ref());
     let confidence = resolve_confidence(src_entity.as_ref(), dst_entity.as_ref());
     let peer = build_peer(
         src_entity,
@@ -274,19 +295,34 @@ fn resolve_peer_and_attrs(
         sockets.downstream.clone(),
         sockets.upstream.clone(),
     );
-    let attrs = build_attrs(log, confidence);
+    let mut attrs = build_attrs(log, confidence);
+    tag_peer(&mut attrs, context.resolver, &peer);
     (peer, attrs)
 }
 
 fn build_http_observation(
     log: EnvoyAccessLog,
     peer: Peer,
-    attrs: ObservationAttrs,
+    mut attrs: ObservationAttrs,
     now_ms: u64,
-    is_egress: bool,
+    context: &ObservationContext<'_>,
 ) -> Observation {
-    let parts = build_http_parts(log, is_egress);
-    Observation::Http(HttpObservation {
+    let upgrade = log.is_upgrade().then(|| UpgradeInfo {
+        protocol: log
+            .upgrade
+            .as_deref()
+            .map_or_else(|| "websocket".to_string(), str::to_ascii_lowercase),
+        messages_in: None,
+        messages_out: None,
+    });
+    record_fault(&mut attrs, log.response_flags.as_deref());
+    let parts = build_http_parts(log, context);
+    record_truncation(
+        &mut attrs,
+        parts.request_body_truncated,
+        parts.response_body_truncated,
+    );
+    let http = HttpObservation {
         at_ms: now_ms,
         peer,
         method: parts.method,
@@ -304,10 +340,14 @@ fn build_http_observation(
             ..Default::default()
         },
         attrs,
-    })
+    };
+    match upgrade {
+        Some(upgrade) => Observation::Upgrade(UpgradeObservation { http, upgrade }),
+        None => Observation::Http(http),
+    }
 }
 
-fn build_http_parts(log: EnvoyAccessLog, is_egress: bool) -> HttpLogParts {
+fn build_http_parts(log: EnvoyAccessLog, context: &ObservationContext<'_>) -> HttpLogParts {
     let EnvoyAccessLog {
         method,
         path,
@@ -333,7 +373,7 @@ fn build_http_parts(log: EnvoyAccessLog, is_egress: bool) -> HttpLogParts {
         path,
         authority.as_deref(),
         upstream_host.as_deref(),
-        is_egress,
+        context.is_egress,
     );
     let request_headers = build_request_headers(RequestHeaderParts {
         authority,
@@ -346,8 +386,11 @@ fn build_http_parts(log: EnvoyAccessLog, is_egress: bool) -> HttpLogParts {
     });
     let response_headers =
         build_response_headers_from_parts(response_content_type.clone(), response_content_length);
-    let request_body = normalize_body(request_body, request_content_type.as_deref());
-    let response_body = normalize_body(response_body, response_content_type.as_deref());
+    let limit = context.max_body_bytes;
+    let (request_body, request_body_truncated) =
+        normalize_body(request_body, request_content_type.as_deref(), limit);
+    let (response_body, response_body_truncated) =
+        normalize_body(response_body, response_content_type.as_deref(), limit);
 
     HttpLogParts {
         method,
@@ -361,6 +404,29 @@ fn build_http_parts(log: EnvoyAccessLog, is_egress: bool) -> HttpLogParts {
         response_headers,
         request_body,
         response_body,
+        request_body_truncated,
+        response_body_truncated,
+    }
+}
+
+fn record_fault(attrs: &mut ObservationAttrs, flags: Option<&str>) {
+    if let Some(kind) = flags.and_then(fault_kind) {
+        attrs.tags.insert(CHAOS_TAG.to_string(), kind.to_string());
+    }
+}
+
+fn record_truncation(attrs: &mut ObservationAttrs, request: u64, response: u64) {
+    if request > 0 {
+        attrs.tags.insert(
+            "request_body_truncated_bytes".to_string(),
+            request.to_string(),
+        );
+    }
+    if response > 0 {
+        attrs.tags.insert(
+            "response_body_truncated_bytes".to_string(),
+            response.to_string(),
+        );
     }
 }
 
@@ -418,27 +484,68 @@ fn build_flow_observation(
 
 fn resolve_dst_entity(
     log: &EnvoyAccessLog,
-    is_egress: bool,
-    service_name: &str,
+    context: &ObservationContext<'_>,
     upstream: Option<&Socket>,
 ) -> Option<EntityId> {
-    if is_egress {
-        parse_external_entity(log.authority.as_deref().or(log.upstream_host.as_deref())).or_else(
-            || {
-                upstream.map(|socket| EntityId::External {
-                    ip: socket.ip,
-                    dns_name: None,
-                })
-            },
-        )
+    if context.is_egress {
+        let host = log
+            .authority
+            .as_deref()
+            .or(log.requested_server_name.as_deref())
+            .or(log.upstream_host.as_deref());
+        external_entity(context.resolver, host, upstream)
     } else {
-        Some(EntityId::Workload {
-            name: service_name.to_string(),
-            instance: None,
-        })
+        Some(service_entity(
+            context.resolver,
+            context.service_name,
+            upstream,
+        ))
+    }
+}
+
+fn service_entity(
+    resolver: &dyn Resolver,
+    service_name: &str,
+    upstream: Option<&Socket>,
+) -> EntityId {
+    let instance = upstream
+        .and_then(|socket| resolver.resolve_entity(socket))
+        .and_then(|entity| match entity {
+            EntityId::Workload { name, instance } if name == service_name => instance,
+            _ => None,
+        });
+    EntityId::Workload {
+        name: service_name.to_string(),
+        instance,
     }
 }
 
+fn external_entity(
+    resolver: &dyn Resolver,
+    host: Option<&str>,
+    upstream: Option<&Socket>,
+) -> Option<EntityId> {
+    let upstream_ip = upstream.map(|socket| socket.ip);
+    let ip = match parse_external_entity(host) {
+        Some(EntityId::External {
+            dns_name: Some(host),
+            ..
+        }) => {
+            return Some(EntityId::External {
+                ips: resolver.record_dns(&host, upstream_ip),
+                dns_name: Some(host),
+            });
+        }
+        Some(EntityId::External { ips, .. }) => ips.first().copied().or(upstream_ip)?,
+        _ => upstream_ip?,
+    };
+    let dns_name = resolver.dns_name(&ip);
+    let ips = dns_name
+        .as_deref()
+        .map_or_else(|| vec![ip], |host| resolver.record_dns(host, Some(ip)));
+    Some(EntityId::External { ips, dns_name })
+}
+
 const fn resolve_confidence(
     src_entity: Option<&EntityId>,
     dst_entity: Option<&EntityId>,
@@ -467,16 +574,43 @@ const fn build_peer(
     Peer { src, dst, raw }
 }
 
+fn tag_peer(attrs: &mut ObservationAttrs, resolver: &dyn Resolver, peer: &Peer) {
+    let network = peer.raw.as_ref().and_then(|flow| {
+        resolver
+            .network_name(&flow.src.ip)
+            .or_else(|| resolver.network_name(&flow.dst.ip))
+    });
+    if let Some(network) = network {
+        attrs.tags.insert(NETWORK_TAG.to_string(), network);
+    }
+    let origin = match &peer.src {
+        Some(EntityId::Host { .. }) => "user",
+        Some(EntityId::Workload { .. }) => "service",
+        _ => return,
+    };
+    attrs
+        .tags
+        .insert(ORIGIN_TAG.to_string(), origin.to_string());
+}
+
 fn build_attrs(log: &EnvoyAccessLog, confidence: Confidence) -> ObservationAttrs {
     let visibility = if log.method.is_some() || log.path.is_some() || log.authority.is_some() {
         Visibility::L7Semantics
     } else {
         Visibility::L4Flow
     };
+    let mut tags = BTreeMap::default();
+    if log
+        .request_content_type
+        .as_deref()
+        .is_some_and(|value| value.starts_with("application/grpc-web"))
+    {
+        tags.insert("protocol".to_string(), "grpc-web".to_string());
+    }
     ObservationAttrs {
         visibility,
         confidence,
-        tags: BTreeMap::default(),
+        tags,
     }
 }
 
@@ -561,18 +695,177 @@ fn parse_tap_headers(entries: Option<&Vec<serde_json::Value>>) -> BTreeMap<Strin
     headers
 }
 
-fn parse_tap_body(body: Option<&serde_json::Map<String, serde_json::Value>>) -> Option<String> {
-    let body = body?;
-    let value = tap_string(body, "as_string", "asString")?;
-    let trimmed = value.trim();
-    if trimmed.is_empty() {
-        return None;
+fn parse_tap_body(
+    body: Option<&serde_json::Map<String, serde_json::Value>>,
+    content_length: Option<u64>,
+) -> (Option<String>, u64) {
+    let Some(body) = body else {
+        return (None, 0);
+    };
+    let Some(value) = tap_string(body, "as_string", "asString") else {
+        return (None, 0);
+    };
+    if value.trim().is_empty() {
+        return (None, 0);
+    }
+    if !tap_bool(body, "truncated", "truncated").unwrap_or(false) {
+        return (Some(value.to_string()), 0);
     }
-    let truncated = tap_bool(body, "truncated", "truncated").unwrap_or(false);
-    if truncated {
-        Some(format!("{value}\n... (truncated by tap)"))
+    let missing = content_length.map_or(0, |length| length.saturating_sub(value.len() as u64));
+    let marker = if missing > 0 {
+        format!("{value}\n... (truncated by tap, {missing} more bytes)")
     } else {
-        Some(value.to_string())
+        format!("{value}\n... (truncated by tap)")
+    };
+    (Some(marker), missing)
+}
+
+#[derive(Default)]
+pub struct SocketTap {
+    pending: Vec<u8>,
+    client: Option<Socket>,
+    local: Option<Socket>,
+    decoder: Option<DbStreamDecoder>,
+    awaiting: Vec<(DbStatement, u64)>,
+    closed: bool,
+}
+
+impl SocketTap {
+    pub const fn closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn feed(
+        &mut self,
+        bytes: &[u8],
+        context: &ObservationContext<'_>,
+        now_ms: u64,
+    ) -> Vec<Observation> {
+        self.pending.extend_from_slice(bytes);
+        let mut segments = Vec::new();
+        let mut stream =
+            serde_json::Deserializer::from_slice(&self.pending).into_iter::<serde_json::Value>();
+        let consumed = loop {
+            match stream.next() {
+                Some(Ok(segment)) => segments.push(segment),
+                Some(Err(err)) if err.is_eof() => break stream.byte_offset(),
+                Some(Err(_)) => break self.pending.len(),
+                None => break stream.byte_offset(),
+            }
+        };
+        self.pending.drain(..consumed);
+        let mut completed = Vec::new();
+        for segment in &segments {
+            self.apply_segment(segment, now_ms, &mut completed);
+        }
+        if completed.is_empty() {
+            return Vec::new();
+        }
+        let protocol = self
+            .decoder
+            .as_ref()
+            .and_then(DbStreamDecoder::protocol)
+            .map_or("tcp", DbProtocol::as_str);
+        let (peer, attrs) = self.peer_and_attrs(context);
+        completed
+            .into_iter()
+            .map(|(statement, at_ms, duration_ms)| {
+                Observation::Db(DbObservation {
+                    at_ms,
+                    peer: peer.clone(),
+                    protocol: protocol.to_string(),
+                    command: statement.command,
+                    statement: statement.statement,
+                    duration_ms,
+                    attrs: attrs.clone(),
+                })
+            })
+            .collect()
+    }
+
+    fn apply_segment(
+        &mut self,
+        segment: &serde_json::Value,
+        now_ms: u64,
+        completed: &mut Vec<(DbStatement, u64, Option<u64>)>,
+    ) {
+        let Some(segment) = segment.as_object().and_then(|segment| {
+            tap_object(
+                segment,
+                "socket_streamed_trace_segment",
+                "socketStreamedTraceSegment",
+            )
+        }) else {
+            return;
+        };
+        if let Some(connection) = tap_object(segment, "connection", "connection") {
+            self.client = parse_tap_address(connection, "remote_address", "remoteAddress");
+            self.local = parse_tap_address(connection, "local_address", "localAddress");
+        }
+        let batched = tap_object(segment, "events", "events")
+            .and_then(|events| tap_array(events, "events", "events"));
+        let single = tap_value(segment, "event", "event");
+        for event in batched
+            .into_iter()
+            .flatten()
+            .chain(single)
+            .filter_map(serde_json::Value::as_object)
+        {
+            self.apply_event(event, now_ms, completed);
+        }
+    }
+
+    fn apply_event(
+        &mut self,
+        event: &serde_json::Map<String, serde_json::Value>,
+        now_ms: u64,
+        completed: &mut Vec<(DbStatement, u64, Option<u64>)>,
+    ) {
+        let at_ms = tap_timestamp_ms(event, "timestamp", "timestamp").unwrap_or(now_ms);
+        if tap_object(event, "write", "write").is_some() {
+            completed.extend(self.awaiting.drain(..).map(|(statement, started)| {
+                (statement, started, Some(at_ms.saturating_sub(started)))
+            }));
+        } else if tap_object(event, "closed", "closed").is_some() {
+            self.closed = true;
+            completed.extend(
+                self.awaiting
+                    .drain(..)
+                    .map(|(statement, started)| (statement, started, None)),
+            );
+        } else if let Some(bytes) = tap_object(event, "read", "read")
+            .and_then(|read| tap_object(read, "data", "data"))
+            .and_then(|body| tap_string(body, "as_bytes", "asBytes"))
+            .and_then(decode_base64)
+        {
+            let port = self.local.as_ref().map_or(0, |socket| socket.port);
+            let decoder = self
+                .decoder
+                .get_or_insert_with(|| DbStreamDecoder::new(port));
+            self.awaiting.extend(
+                decoder
+                    .push(&bytes)
+                    .into_iter()
+                    .map(|statement| (statement, at_ms)),
+            );
+        }
+    }
+
+    fn peer_and_attrs(&self, context: &ObservationContext<'_>) -> (Peer, ObservationAttrs) {
+        let src = self
+            .client
+            .as_ref()
+            .and_then(|socket| context.resolver.resolve_entity(socket));
+        let dst = Some(service_entity(context.resolver, context.service_name, None));
+        let confidence = resolve_confidence(src.as_ref(), dst.as_ref());
+        let peer = build_peer(src, dst, self.client.clone(), self.local.clone());
+        let mut attrs = ObservationAttrs {
+            visibility: Visibility::L7Semantics,
+            confidence,
+            tags: BTreeMap::default(),
+        };
+        tag_peer(&mut attrs, context.resolver, &peer);
+        (peer, attrs)
     }
 }
 
@@ -582,7 +875,15 @@ fn parse_tap_connection(
     camel: &str,
 ) -> Option<Socket> {
     let connection = tap_object(trace, snake, camel)?;
-    let remote = tap_object(connection, "remote_address", "remoteAddress")?;
+    parse_tap_address(connection, "remote_address", "remoteAddress")
+}
+
+fn parse_tap_address(
+    connection: &serde_json::Map<String, serde_json::Value>,
+    snake: &str,
+    camel: &str,
+) -> Option<Socket> {
+    let remote = tap_object(connection, snake, camel)?;
     let socket = tap_object(remote, "socket_address", "socketAddress")?;
     let address = tap_string(socket, "address", "address")?;
     let port =
@@ -726,10 +1027,13 @@ fn parse_external_entity(raw: Option<&str>) -> Option<EntityId> {
     let (host, _) = value.rsplit_once(':').unwrap_or((value, ""));
     let host = host.trim_matches(['[', ']']);
     if let Ok(ip) = host.parse::<IpAddr>() {
-        return Some(EntityId::External { ip, dns_name: None });
+        return Some(EntityId::External {
+            ips: vec![ip],
+            dns_name: None,
+        });
     }
     Some(EntityId::External {
-        ip: IpAddr::from([0, 0, 0, 0]),
+        ips: Vec::new(),
         dns_name: Some(host.to_string()),
     })
 }
@@ -753,11 +1057,17 @@ fn insert_header(headers: &mut BTreeMap<String, String>, key: &str, value: Optio
 
 const NON_JSON_BODY_PREVIEW_LIMIT: usize = 4096;
 
-fn normalize_body(body: Option<String>, content_type: Option<&str>) -> Option<String> {
-    let body = body?;
+fn normalize_body(
+    body: Option<String>,
+    content_type: Option<&str>,
+    max_bytes: usize,
+) -> (Option<String>, u64) {
+    let Some(body) = body else {
+        return (None, 0);
+    };
     let trimmed = body.trim();
     if trimmed.is_empty() || trimmed == "-" {
-        return None;
+        return (None, 0);
     }
     let content_type = content_type.and_then(|value| {
         let trimmed = value.trim();
@@ -774,15 +1084,20 @@ fn normalize_body(body: Option<String>, content_type: Option<&str>) -> Option<St
             )
         }
     });
-    if is_json_content_type(content_type.as_deref()) {
-        return Some(body);
-    }
-    let (snippet, truncated) = truncate_body(&body, NON_JSON_BODY_PREVIEW_LIMIT);
-    if truncated {
-        Some(format!("{snippet}\n... (cropped)"))
+    let limit = if is_json_content_type(content_type.as_deref()) {
+        max_bytes
     } else {
-        Some(snippet)
+        max_bytes.min(NON_JSON_BODY_PREVIEW_LIMIT)
+    };
+    let (snippet, truncated) = truncate_body(&body, limit);
+    if !truncated {
+        return (Some(snippet), 0);
     }
+    let dropped = body.len().saturating_sub(snippet.len()) as u64;
+    (
+        Some(format!("{snippet}\n... (cropped, {dropped} more bytes)")),
+        dropped,
+    )
 }
 
 fn is_json_content_type(content_type: Option<&str>) -> bool {
@@ -795,7 +1110,7 @@ fn truncate_body(body: &str, max_bytes: usize) -> (String, bool) {
     }
     let mut end = 0;
     for (idx, ch) in body.char_indices() {
-        if idx >= max_bytes {
+        if idx + ch.len_utf8() > max_bytes {
             break;
         }
         end = idx + ch.len_utf8();
@@ -813,3 +1128,132 @@ fn normalize_header_value(value: Option<String>) -> Option<String> {
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        normalize_body, record_truncation, tag_peer, ObservationContext, SocketTap,
+        NON_JSON_BODY_PREVIEW_LIMIT,
+    };
+    use crate::domain::traffic::{
+        Confidence, EntityId, Observation, ObservationAttrs, Peer, Resolver, Socket, Visibility,
+        HOST_ENTITY, ORIGIN_TAG,
+    };
+    use crate::support::traffic_fixtures::workload_peer;
+
+    struct NoResolver;
+
+    impl Resolver for NoResolver {
+        fn resolve_entity(&self, _socket: &Socket) -> Option<EntityId> {
+            None
+        }
+    }
+
+    #[test]
+    fn accounts_for_the_bytes_cut_from_captured_bodies() {
+        let json = Some("application/json; charset=utf-8");
+        assert_eq!(
+            normalize_body(Some("{\"id\":7}".to_string()), json, 64),
+            (Some("{\"id\":7}".to_string()), 0)
+        );
+        assert_eq!(normalize_body(Some(" - ".to_string()), json, 64), (None, 0));
+        assert_eq!(
+            normalize_body(Some("{\"a\":\"0123456789\"}".to_string()), json, 8),
+            (
+                Some("{\"a\":\"01\n... (cropped, 10 more bytes)".to_string()),
+                10
+            )
+        );
+        let text = "x".repeat(NON_JSON_BODY_PREVIEW_LIMIT + 100);
+        let (_, dropped) = normalize_body(Some(text), Some("text/plain"), 1 << 20);
+        assert_eq!(dropped, 100);
+        // A multibyte character straddling the limit is dropped whole.
+        assert_eq!(
+            normalize_body(Some("ééééé".to_string()), json, 5),
+            (Some("éé\n... (cropped, 6 more bytes)".to_string()), 6)
+        );
+
+        let mut attrs = ObservationAttrs {
+            visibility: Visibility::L7Semantics,
+            confidence: Confidence::Exact,
+            tags: std::collections::BTreeMap::new(),
+        };
+        record_truncation(&mut attrs, 0, 6);
+        let tags: Vec<(&str, &str)> = attrs
+            .tags
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        assert_eq!(tags, vec![("response_body_truncated_bytes", "6")]);
+    }
+
+    #[test]
+    fn tags_calls_from_the_host_as_user_traffic() {
+        let origin = |peer: &Peer| {
+            let mut attrs = ObservationAttrs {
+                visibility: Visibility::L7Envelope,
+                confidence: Confidence::Exact,
+                tags: std::collections::BTreeMap::new(),
+            };
+            tag_peer(&mut attrs, &NoResolver, peer);
+            attrs.tags.get(ORIGIN_TAG).cloned()
+        };
+        let from_host = Peer {
+            src: Some(EntityId::Host {
+                name: HOST_ENTITY.to_string(),
+            }),
+            ..workload_peer("web", "api")
+        };
+        let unresolved = Peer {
+            src: None,
+            ..workload_peer("web", "api")
+        };
+        assert_eq!(origin(&from_host).as_deref(), Some("user"));
+        assert_eq!(
+            origin(&workload_peer("web", "api")).as_deref(),
+            Some("service")
+        );
+        assert_eq!(origin(&unresolved), None);
+    }
+
+    fn segment(body: &str) -> String {
+        format!("{{\n  \"socket_streamed_trace_segment\": {{\n    {body}\n  }}\n}}\n")
+    }
+
+    #[test]
+    fn decodes_streamed_socket_segments_as_they_arrive() {
+        let context = ObservationContext {
+            service_name: "cache",
+            resolver: &NoResolver,
+            is_egress: false,
+            max_body_bytes: 1024,
+        };
+        let connection = segment(
+            r#""connection": {"local_address": {"socket_address": {"address": "10.0.0.2", "port_value": 6379}}}"#,
+        );
+        let read = segment(
+            r#""event": {"timestamp": "2024-01-01T00:00:00.100Z", "read": {"data": {"as_bytes": "UElORw0K"}}}"#,
+        );
+        let write = segment(r#""event": {"timestamp": "2024-01-01T00:00:00.104Z", "write": {}}"#);
+        let closed = segment(r#""event": {"closed": {}}"#);
+        let stream = format!("{connection}{read}{write}");
+        let (head, tail) = stream.split_at(connection.len() + 10);
+
+        let mut tap = SocketTap::default();
+        assert!(tap.feed(head.as_bytes(), &context, 0).is_empty());
+        let observations = tap.feed(tail.as_bytes(), &context, 0);
+        let calls: Vec<_> = observations
+            .iter()
+            .filter_map(|obs| match obs {
+                Observation::Db(db) => {
+                    Some((db.protocol.as_str(), db.statement.as_str(), db.duration_ms))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(calls, vec![("redis", "PING", Some(4))]);
+        assert!(!tap.closed());
+        assert!(tap.feed(closed.as_bytes(), &context, 0).is_empty());
+        assert!(tap.closed());
+    }
+}
@@ -0,0 +1,178 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use crate::infra::process::{spawn_process_group, terminate_process};
+use crate::support::share::{allocated_port, ShareTarget};
+
+const RELAY_IDLE_CONNECTIONS: usize = 4;
+const RELAY_RETRY: Duration = Duration::from_secs(2);
+const RELAY_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const STOP_CHECK: Duration = Duration::from_millis(250);
+const SSH_READY_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub struct ShareTunnel {
+    child: Option<Child>,
+    stop: Arc<AtomicBool>,
+}
+
+impl ShareTunnel {
+    pub fn open(target: &ShareTarget, local_port: u16) -> Result<(Self, Option<u16>), String> {
+        let stop = Arc::new(AtomicBool::new(false));
+        match target {
+            ShareTarget::Ssh { remote_port, .. } => {
+                let (child, port) = open_ssh(target, local_port, *remote_port, &stop)?;
+                Ok((
+                    Self {
+                        child: Some(child),
+                        stop,
+                    },
+                    Some(port),
+                ))
+            }
+            ShareTarget::Relay { address: relay, .. } => {
+                for _ in 0..RELAY_IDLE_CONNECTIONS {
+                    let relay = relay.clone();
+                    let stop = stop.clone();
+                    thread::spawn(move || relay_worker(&relay, local_port, &stop));
+                }
+                Ok((Self { child: None, stop }, None))
+            }
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(mut child) = self.child.take() {
+            terminate_process(&mut child, Duration::from_secs(2));
+        }
+    }
+}
+
+impl Drop for ShareTunnel {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn open_ssh(
+    target: &ShareTarget,
+    local_port: u16,
+    remote_port: u16,
+    stop: &Arc<AtomicBool>,
+) -> Result<(Child, u16), String> {
+    let mut cmd = Command::new("ssh");
+    cmd.args(target.ssh_args(local_port))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    let mut child =
+        spawn_process_group(&mut cmd).map_err(|err| format!("failed to start ssh: {err}"))?;
+    let (sender, receiver) = mpsc::channel();
+    if let Some(stderr) = child.stderr.take() {
+        let stop = stop.clone();
+        thread::spawn(move || read_ssh_output(stderr, &sender, &stop));
+    }
+    if remote_port != 0 {
+        return Ok((child, remote_port));
+    }
+    if let Ok(port) = receiver.recv_timeout(SSH_READY_TIMEOUT) {
+        return Ok((child, port));
+    }
+    terminate_process(&mut child, Duration::from_secs(2));
+    Err("ssh did not report an allocated remote port".to_string())
+}
+
+fn read_ssh_output(stderr: impl Read, sender: &mpsc::Sender<u16>, stop: &AtomicBool) {
+    for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+        match allocated_port(&line) {
+            Some(port) => {
+                let _ = sender.send(port);
+            }
+            None => eprintln!("[compose] share: {line}"),
+        }
+    }
+    if !stop.load(Ordering::SeqCst) {
+        eprintln!("[compose] share tunnel closed");
+    }
+}
+
+fn relay_worker(relay: &str, local_port: u16, stop: &AtomicBool) {
+    let mut reported = false;
+    while !stop.load(Ordering::SeqCst) {
+        let upstream = match connect(relay) {
+            Ok(upstream) => upstream,
+            Err(err) => {
+                if !reported {
+                    eprintln!("[compose] share relay {relay} unreachable: {err}");
+                }
+                reported = true;
+                sleep_unless_stopped(RELAY_RETRY, stop);
+                continue;
+            }
+        };
+        reported = false;
+        let Some(first) = wait_for_client(&upstream, stop) else {
+            continue;
+        };
+        thread::spawn(move || {
+            let _ = bridge(upstream, &first, local_port);
+        });
+    }
+}
+
+fn connect(address: &str) -> io::Result<TcpStream> {
+    let addr = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address"))?;
+    TcpStream::connect_timeout(&addr, RELAY_CONNECT_TIMEOUT)
+}
+
+fn wait_for_client(mut upstream: &TcpStream, stop: &AtomicBool) -> Option<Vec<u8>> {
+    upstream.set_read_timeout(Some(STOP_CHECK)).ok()?;
+    let mut buffer = [0_u8; 4096];
+    while !stop.load(Ordering::SeqCst) {
+        match upstream.read(&mut buffer) {
+            Ok(0) => return None,
+            Ok(read) => return buffer.get(..read).map(<[u8]>::to_vec),
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            Err(_) => return None,
+        }
+    }
+    None
+}
+
+fn bridge(upstream: TcpStream, first: &[u8], local_port: u16) -> io::Result<()> {
+    upstream.set_read_timeout(None)?;
+    let mut local = TcpStream::connect(("127.0.0.1", local_port))?;
+    local.write_all(first)?;
+    let mut upstream_reader = upstream.try_clone()?;
+    let mut local_writer = local.try_clone()?;
+    let inbound = thread::spawn(move || {
+        let _ = io::copy(&mut upstream_reader, &mut local_writer);
+        let _ = local_writer.shutdown(Shutdown::Write);
+    });
+    let mut upstream_writer = upstream;
+    let _ = io::copy(&mut local, &mut upstream_writer);
+    let _ = upstream_writer.shutdown(Shutdown::Both);
+    let _ = inbound.join();
+    Ok(())
+}
+
+fn sleep_unless_stopped(duration: Duration, stop: &AtomicBool) {
+    let mut left = duration;
+    while !left.is_zero() && !stop.load(Ordering::SeqCst) {
+        let slice = left.min(STOP_CHECK);
+        thread::sleep(slice);
+        left -= slice;
+    }
+}
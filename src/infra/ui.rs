@@ -1,65 +1,265 @@
-use std::io::{self, BufRead, BufReader, Write};
-use std::net::{TcpListener, TcpStream};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crate::domain::traffic::{TrafficCall, TrafficEdge};
-use crate::domain::{LogEvent, ServiceInfo};
-use crate::support::logging::LogHub;
-use crate::support::traffic::TrafficHub;
+use crossbeam_channel::Receiver;
+
+use crate::domain::traffic::{versioned, versioned_all, TrafficCall};
+use crate::domain::{LogEvent, ServiceGraph, ServiceInfo};
+use crate::infra::effective::ServiceConfig;
+use crate::infra::engine::RunContainers;
+use crate::infra::probe::EndpointProbes;
+use crate::infra::tunnel::ShareTunnel;
+use crate::infra::ui_daemon::{DaemonInfo, RunEntry, UiRegistry};
+use crate::support::alerts::AlertHub;
+use crate::support::clock::now_ms;
+use crate::support::correlate::correlate_call_logs;
+use crate::support::env::mask_env;
+use crate::support::forward::{ForwardRegistry, ForwardUpdate};
+use crate::support::gzip::{accepts_gzip, gzip};
+use crate::support::logging::{LogHub, LogSnapshot};
+use crate::support::run::new_ui_token;
+use crate::support::services::overlay_traffic;
+use crate::support::share::ShareTarget;
+use crate::support::timeline::{build_timeline, TimelineWindow};
+use crate::support::traffic::{call_summary, TrafficHub, TrafficSnapshot, TrafficUpdate};
 
 static INDEX_HTML: &str = include_str!(env!("SANELENS_INDEX_HTML"));
 static APP_JS: &str = include_str!(env!("SANELENS_APP_JS"));
 static STYLES_CSS: &str = include_str!(env!("SANELENS_STYLES_CSS"));
 
+const MAX_BODY_BYTES: usize = 64 * 1024;
+const MAX_ANNOTATION_CHARS: usize = 2000;
+pub const LOG_FILTERS_PATH: &str = "/api/logs/filters";
+pub const FORWARDS_PATH: &str = "/api/forwards";
+const TRAFFIC_KEYFRAME_INTERVAL: Duration = Duration::from_secs(30);
+const ADMIN_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const ADMIN_READ_TIMEOUT: Duration = Duration::from_secs(10);
+const EVENT_BATCH_WINDOW: Duration = Duration::from_millis(50);
+const EVENT_BATCH_LIMIT: usize = 500;
+const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_KEEP_ALIVE_REQUESTS: usize = 100;
+const STREAM_PATHS: &[&str] = &["events", "traffic", "traffic/calls", "api/alerts"];
+
+#[derive(Clone)]
+pub struct UiBind {
+    pub host: String,
+    pub port: u16,
+    pub fallback: bool,
+    pub assets: Option<PathBuf>,
+    pub share: Option<ShareTarget>,
+}
+
+impl Default for UiBind {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            fallback: false,
+            assets: None,
+            share: None,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct UiEndpoint {
+    pub url: String,
+    pub host: String,
+    pub port: u16,
+    pub token: Option<String>,
+}
+
+impl UiBind {
+    pub fn is_loopback(&self) -> bool {
+        self.host == "localhost" || self.host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+    }
+}
+
 pub struct UiServer {
     stop_event: Arc<AtomicBool>,
-    handle: Option<thread::JoinHandle<()>>,
+    handles: Vec<thread::JoinHandle<()>>,
+    host: String,
     port: u16,
+    token: Option<String>,
+    share: Option<ShareTunnel>,
+}
+
+#[derive(Clone)]
+pub struct UiRun {
+    pub log_hub: Arc<LogHub>,
+    pub service_info: Arc<Vec<ServiceInfo>>,
+    pub endpoint_probes: Option<Arc<EndpointProbes>>,
+    pub forwards: Arc<ForwardRegistry>,
+    pub service_graph: Arc<ServiceGraph>,
+    pub service_config: Arc<BTreeMap<String, ServiceConfig>>,
+    pub alert_hub: Option<Arc<AlertHub>>,
+    pub traffic_hub: Option<Arc<TrafficHub>>,
+    pub containers: Arc<RunContainers>,
+}
+
+#[derive(Clone)]
+enum UiMode {
+    Single(UiRun),
+    Multi(Arc<UiRegistry>),
+}
+
+struct UiState {
+    mode: UiMode,
+    token: Option<String>,
+    assets: Option<PathBuf>,
+    read_only: bool,
+    open_reads: bool,
 }
 
 impl UiServer {
-    pub fn start(
-        log_hub: Arc<LogHub>,
-        service_info: Vec<ServiceInfo>,
-        traffic_hub: Option<Arc<TrafficHub>>,
+    pub fn start(run: UiRun, stop_event: Arc<AtomicBool>, bind: &UiBind) -> io::Result<Self> {
+        Self::serve(UiMode::Single(run), stop_event, bind)
+    }
+
+    pub fn start_multi(
+        registry: Arc<UiRegistry>,
         stop_event: Arc<AtomicBool>,
+        bind: &UiBind,
     ) -> io::Result<Self> {
-        let listener = TcpListener::bind("127.0.0.1:0")?;
+        Self::serve(UiMode::Multi(registry), stop_event, bind)
+    }
+
+    fn serve(mode: UiMode, stop_event: Arc<AtomicBool>, bind: &UiBind) -> io::Result<Self> {
+        let listener = bind_listener(bind)?;
         listener.set_nonblocking(true)?;
         let port = listener.local_addr()?.port();
-        let services = Arc::new(service_info);
-        let stop_clone = stop_event.clone();
-        let handle = thread::spawn(move || {
-            run_listener(
-                &listener,
-                &log_hub,
-                &services,
-                traffic_hub.as_ref(),
-                &stop_clone,
-            );
-        });
-        Ok(Self {
+        let token = Some(new_ui_token().map_err(io::Error::other)?);
+        let state = UiState {
+            mode,
+            token: token.clone(),
+            assets: bind.assets.clone(),
+            read_only: false,
+            open_reads: bind.is_loopback(),
+        };
+        let mut server = Self {
             stop_event,
-            handle: Some(handle),
+            handles: Vec::new(),
+            host: bind.host.clone(),
             port,
-        })
+            token,
+            share: None,
+        };
+        if let Some(target) = bind.share.as_ref() {
+            match server.open_share(target, &state) {
+                Ok(link) => {
+                    let _ = writeln!(io::stdout(), "[compose] shared UI (read-only): {link}");
+                }
+                Err(err) => eprintln!("[compose] --share failed: {err}"),
+            }
+        }
+        server.spawn_listener(listener, state);
+        Ok(server)
+    }
+
+    fn spawn_listener(&mut self, listener: TcpListener, state: UiState) {
+        let state = Arc::new(state);
+        let stop_event = self.stop_event.clone();
+        self.handles.push(thread::spawn(move || {
+            run_listener(&listener, &state, &stop_event);
+        }));
     }
 
-    pub const fn port(&self) -> u16 {
-        self.port
+    fn open_share(&mut self, target: &ShareTarget, state: &UiState) -> Result<String, String> {
+        let listener = TcpListener::bind(("127.0.0.1", 0))
+            .and_then(|listener| listener.set_nonblocking(true).map(|()| listener))
+            .map_err(|err| format!("failed to bind the share listener: {err}"))?;
+        let local_port = listener
+            .local_addr()
+            .map_err(|err| format!("failed to bind the share listener: {err}"))?
+            .port();
+        let token = new_ui_token()?;
+        let (tunnel, public_port) = ShareTunnel::open(target, local_port)?;
+        self.share = Some(tunnel);
+        self.spawn_listener(
+            listener,
+            UiState {
+                mode: state.mode.clone(),
+                token: Some(token.clone()),
+                assets: state.assets.clone(),
+                read_only: true,
+                open_reads: false,
+            },
+        );
+        Ok(target.link(public_port.unwrap_or_default(), &token))
+    }
+
+    pub fn url(&self) -> String {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        let query = self
+            .token
+            .as_deref()
+            .map(|token| format!("?token={token}"))
+            .unwrap_or_default();
+        format!("http://{host}:{}/{query}", self.port)
+    }
+
+    pub fn endpoint(&self) -> UiEndpoint {
+        UiEndpoint {
+            url: self.url(),
+            host: self.host.clone(),
+            port: self.port,
+            token: self.token.clone(),
+        }
+    }
+
+    pub fn daemon_info(&self) -> DaemonInfo {
+        DaemonInfo {
+            host: self.host.clone(),
+            port: self.port,
+            token: self.token.clone(),
+            pid: std::process::id(),
+        }
     }
 
     pub fn stop(&mut self) {
         self.stop_event.store(true, Ordering::SeqCst);
-        if let Some(handle) = self.handle.take() {
+        if let Some(mut tunnel) = self.share.take() {
+            tunnel.stop();
+        }
+        for handle in self.handles.drain(..) {
             let _ = handle.join();
         }
     }
 }
 
+fn bind_listener(bind: &UiBind) -> io::Result<TcpListener> {
+    match TcpListener::bind((bind.host.as_str(), bind.port)) {
+        Err(err) if err.kind() == io::ErrorKind::AddrInUse && bind.port != 0 => {
+            if !bind.fallback {
+                return Err(io::Error::new(
+                    err.kind(),
+                    format!(
+                        "port {} is busy (pass --ui-port-auto-fallback to use a random port)",
+                        bind.port
+                    ),
+                ));
+            }
+            eprintln!(
+                "[compose] UI port {} is busy, using a random port",
+                bind.port
+            );
+            TcpListener::bind((bind.host.as_str(), 0))
+        }
+        result => result,
+    }
+}
+
 pub fn open_browser(url: &str) {
     let _ = webbrowser::open(url);
 }
@@ -78,43 +278,21 @@ fn accept_next(listener: &TcpListener) -> AcceptOutcome {
     }
 }
 
-fn run_listener(
-    listener: &TcpListener,
-    log_hub: &Arc<LogHub>,
-    services: &Arc<Vec<ServiceInfo>>,
-    traffic_hub: Option<&Arc<TrafficHub>>,
-    stop_event: &Arc<AtomicBool>,
-) {
+fn run_listener(listener: &TcpListener, state: &Arc<UiState>, stop_event: &Arc<AtomicBool>) {
     while !stop_event.load(Ordering::SeqCst) {
         match accept_next(listener) {
-            AcceptOutcome::Stream(stream) => spawn_connection_handler(
-                stream,
-                log_hub.clone(),
-                services.clone(),
-                traffic_hub.cloned(),
-                stop_event.clone(),
-            ),
+            AcceptOutcome::Stream(stream) => {
+                spawn_connection_handler(stream, state.clone(), stop_event.clone());
+            }
             AcceptOutcome::Wait => thread::sleep(Duration::from_millis(100)),
             AcceptOutcome::Stop => return,
         }
     }
 }
 
-fn spawn_connection_handler(
-    stream: TcpStream,
-    log_hub: Arc<LogHub>,
-    services: Arc<Vec<ServiceInfo>>,
-    traffic_hub: Option<Arc<TrafficHub>>,
-    stop_event: Arc<AtomicBool>,
-) {
+fn spawn_connection_handler(stream: TcpStream, state: Arc<UiState>, stop_event: Arc<AtomicBool>) {
     thread::spawn(move || {
-        if let Err(err) = handle_connection(
-            stream,
-            &log_hub,
-            &services,
-            traffic_hub.as_ref(),
-            &stop_event,
-        ) {
+        if let Err(err) = handle_connection(&stream, &state, &stop_event) {
             eprintln!("[compose] ui connection error: {err}");
         }
     });
@@ -123,103 +301,731 @@ fn spawn_connection_handler(
 struct UiRouteContext<'a> {
     log_hub: &'a Arc<LogHub>,
     service_info: &'a Arc<Vec<ServiceInfo>>,
+    endpoint_probes: Option<&'a Arc<EndpointProbes>>,
+    forwards: &'a ForwardRegistry,
+    service_graph: &'a ServiceGraph,
+    service_config: &'a BTreeMap<String, ServiceConfig>,
+    alert_hub: Option<&'a Arc<AlertHub>>,
     traffic_hub: Option<&'a Arc<TrafficHub>>,
+    containers: &'a Arc<RunContainers>,
+    last_event_id: Option<&'a str>,
+    query: Option<&'a str>,
     stop_event: &'a Arc<AtomicBool>,
 }
 
+struct ServeContext<'a> {
+    token: Option<&'a str>,
+    open_reads: bool,
+    assets: Option<&'a Path>,
+    stop_event: &'a Arc<AtomicBool>,
+}
+
+impl ServeContext<'_> {
+    fn opens_read(&self, request: &HttpRequest<'_>) -> bool {
+        self.open_reads && request.line.method == "GET"
+    }
+}
+
+struct RequestLine<'a> {
+    method: &'a str,
+    path: &'a str,
+    query: Option<&'a str>,
+    http11: bool,
+}
+
+#[derive(Default)]
+struct RequestHeaders {
+    authorization: Option<String>,
+    content_length: usize,
+    last_event_id: Option<String>,
+    if_none_match: Option<String>,
+    accept_gzip: bool,
+    close: bool,
+}
+
+struct HttpRequest<'a> {
+    line: RequestLine<'a>,
+    authorization: Option<String>,
+    last_event_id: Option<String>,
+    if_none_match: Option<String>,
+    accept_gzip: bool,
+    body: Vec<u8>,
+}
+
+struct EmbeddedAsset {
+    paths: &'static [&'static str],
+    content_type: &'static str,
+    body: &'static [u8],
+    gzip: Vec<u8>,
+    etag: String,
+}
+
 fn handle_connection(
-    stream: TcpStream,
-    log_hub: &Arc<LogHub>,
-    service_info: &Arc<Vec<ServiceInfo>>,
-    traffic_hub: Option<&Arc<TrafficHub>>,
+    stream: &TcpStream,
+    state: &UiState,
     stop_event: &Arc<AtomicBool>,
 ) -> io::Result<()> {
     let mut reader = BufReader::new(stream.try_clone()?);
-    let Some(request_line) = read_request_line(&mut reader)? else {
-        return Ok(());
-    };
-    let Some((method, path)) = parse_request_line(&request_line) else {
-        return Ok(());
+    for served in 0..MAX_KEEP_ALIVE_REQUESTS {
+        if served > 0 {
+            stream.set_read_timeout(Some(KEEP_ALIVE_TIMEOUT))?;
+        }
+        let Some(request_line) = read_request_line(&mut reader)? else {
+            return Ok(());
+        };
+        stream.set_read_timeout(None)?;
+        let Some(line) = parse_request_line(&request_line) else {
+            return Ok(());
+        };
+        let headers = read_headers(&mut reader)?;
+        let mut body = Vec::new();
+        if headers.content_length > 0 {
+            let limit = headers.content_length.min(MAX_BODY_BYTES);
+            (&mut reader).take(limit as u64).read_to_end(&mut body)?;
+        }
+        let keep_alive = line.http11
+            && !headers.close
+            && headers.content_length <= MAX_BODY_BYTES
+            && !holds_connection(line.path);
+        let request = HttpRequest {
+            line,
+            authorization: headers.authorization,
+            last_event_id: headers.last_event_id,
+            if_none_match: headers.if_none_match,
+            accept_gzip: headers.accept_gzip,
+            body,
+        };
+        serve_request(&request, state, stop_event, stream.try_clone()?)?;
+        if !keep_alive || stop_event.load(Ordering::SeqCst) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn allows_method(read_only: bool, method: &str) -> bool {
+    !read_only || method == "GET"
+}
+
+fn holds_connection(path: &str) -> bool {
+    let path = path.strip_prefix('/').unwrap_or(path);
+    let inner = path
+        .strip_prefix("runs/")
+        .and_then(|rest| rest.split_once('/'))
+        .map_or(path, |(_, inner)| inner);
+    STREAM_PATHS.contains(&inner) || inner.starts_with("admin/")
+}
+
+fn serve_request(
+    request: &HttpRequest<'_>,
+    state: &UiState,
+    stop_event: &Arc<AtomicBool>,
+    stream: TcpStream,
+) -> io::Result<()> {
+    if !allows_method(state.read_only, request.line.method) {
+        return write_response(stream, 403, "text/plain", b"Read-only share");
+    }
+    let serve = ServeContext {
+        token: state.token.as_deref(),
+        open_reads: state.open_reads,
+        assets: state.assets.as_deref(),
+        stop_event,
     };
-    drain_headers(&mut reader)?;
+    match &state.mode {
+        UiMode::Single(run) => route_run(request, request.line.path, run, &serve, stream),
+        UiMode::Multi(registry) => route_multi(request, registry, &serve, stream),
+    }
+}
 
-    if method != "GET" {
+fn route_run(
+    request: &HttpRequest<'_>,
+    path: &str,
+    run: &UiRun,
+    serve: &ServeContext<'_>,
+    stream: TcpStream,
+) -> io::Result<()> {
+    let annotations = path == "/api/annotations";
+    let filters = path == LOG_FILTERS_PATH;
+    let forwards = path == FORWARDS_PATH;
+    let allowed = match request.line.method {
+        "GET" => !annotations,
+        "POST" => annotations || filters || forwards,
+        _ => false,
+    };
+    if !allowed {
         return write_response(stream, 405, "text/plain", b"Method not allowed");
     }
-
+    let open = is_static_asset(path) || (serve.opens_read(request) && !path.starts_with("/admin/"));
+    if !is_authorized(serve.token, request, open) {
+        return write_response(stream, 401, "text/plain", b"Unauthorized");
+    }
+    if let Some(file) = serve.assets.and_then(|dir| overlay_asset(dir, path)) {
+        return write_overlay_asset(stream, &file);
+    }
+    if let Some(asset) = embedded_asset(path) {
+        return write_embedded_asset(stream, asset, request);
+    }
+    if annotations {
+        return write_annotation_response(stream, &run.log_hub, &request.body);
+    }
+    if filters {
+        let body = (request.line.method == "POST").then_some(request.body.as_slice());
+        return write_log_filters_response(stream, &run.log_hub, body);
+    }
+    if forwards {
+        let body = (request.line.method == "POST").then_some(request.body.as_slice());
+        return write_forwards_response(stream, &run.forwards, body);
+    }
+    if let Some(rest) = path.strip_prefix("/admin/") {
+        return proxy_admin(stream, &run.containers, rest, request.line.query);
+    }
     let context = UiRouteContext {
-        log_hub,
-        service_info,
-        traffic_hub,
-        stop_event,
+        log_hub: &run.log_hub,
+        service_info: &run.service_info,
+        endpoint_probes: run.endpoint_probes.as_ref(),
+        forwards: &run.forwards,
+        service_graph: &run.service_graph,
+        service_config: &run.service_config,
+        alert_hub: run.alert_hub.as_ref(),
+        traffic_hub: run.traffic_hub.as_ref(),
+        containers: &run.containers,
+        last_event_id: request.last_event_id.as_deref(),
+        query: request.line.query,
+        stop_event: serve.stop_event,
     };
     route_request(path, stream, &context)
 }
 
+fn route_multi(
+    request: &HttpRequest<'_>,
+    registry: &UiRegistry,
+    serve: &ServeContext<'_>,
+    stream: TcpStream,
+) -> io::Result<()> {
+    if let Some(rest) = request.line.path.strip_prefix("/runs/") {
+        let Some((run_id, inner)) = rest.split_once('/') else {
+            let query = request
+                .line
+                .query
+                .map(|query| format!("?{query}"))
+                .unwrap_or_default();
+            let location = format!("Location: /runs/{rest}/{query}");
+            return write_response_with_headers(stream, 302, "text/plain", b"", &[&location]);
+        };
+        let Some(run) = registry.get(run_id) else {
+            return write_response(stream, 404, "text/plain", b"Unknown run");
+        };
+        return route_run(request, &format!("/{inner}"), &run, serve, stream);
+    }
+    if !is_authorized(serve.token, request, serve.opens_read(request)) {
+        return write_response(stream, 401, "text/plain", b"Unauthorized");
+    }
+    match (request.line.method, request.line.path) {
+        ("GET", "/" | "/index.html") => {
+            let page = render_runs_index(&registry.entries(), request.line.query);
+            write_response(stream, 200, "text/html; charset=utf-8", page.as_bytes())
+        }
+        ("GET", "/api/runs") => {
+            let payload = serde_json::to_vec(&serde_json::json!({ "runs": registry.entries() }))
+                .unwrap_or_default();
+            write_response_with_headers(
+                stream,
+                200,
+                "application/json",
+                &payload,
+                &["Cache-Control: no-store"],
+            )
+        }
+        ("POST", "/api/runs/register") => write_register_response(stream, registry, &request.body),
+        (_, "/api/runs/register") => {
+            write_response(stream, 405, "text/plain", b"Method not allowed")
+        }
+        _ => write_response(stream, 404, "text/plain", b"Not found"),
+    }
+}
+
+fn write_register_response(
+    stream: TcpStream,
+    registry: &UiRegistry,
+    body: &[u8],
+) -> io::Result<()> {
+    let run_id = serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|value| value.get("run_id")?.as_str().map(ToString::to_string));
+    let Some(run_id) = run_id.filter(|id| is_valid_run_id(id)) else {
+        return write_response(stream, 400, "text/plain", b"Missing run_id");
+    };
+    registry.register(&run_id);
+    let payload = serde_json::json!({ "url": format!("/runs/{run_id}/") }).to_string();
+    write_response(stream, 200, "application/json", payload.as_bytes())
+}
+
+fn write_annotation_response(
+    stream: TcpStream,
+    log_hub: &Arc<LogHub>,
+    body: &[u8],
+) -> io::Result<()> {
+    let Ok(payload) = serde_json::from_slice::<serde_json::Value>(body) else {
+        return write_response(stream, 400, "text/plain", b"Invalid JSON body");
+    };
+    let text = payload
+        .get("text")
+        .and_then(serde_json::Value::as_str)
+        .map(str::trim)
+        .filter(|text| !text.is_empty() && text.chars().count() <= MAX_ANNOTATION_CHARS);
+    let Some(text) = text else {
+        return write_response(stream, 400, "text/plain", b"Missing or oversized text");
+    };
+    let service = payload
+        .get("service")
+        .and_then(serde_json::Value::as_str)
+        .filter(|service| !service.is_empty());
+    let ts_ms = payload.get("ts_ms").and_then(serde_json::Value::as_i64);
+    log_hub.annotate(service, text, ts_ms);
+    write_response(stream, 201, "application/json", b"{\"ok\":true}")
+}
+
+fn write_log_filters_response(
+    stream: TcpStream,
+    log_hub: &Arc<LogHub>,
+    body: Option<&[u8]>,
+) -> io::Result<()> {
+    let filter = match body {
+        None => log_hub.runtime_filter(),
+        Some(body) => {
+            let Ok(payload) = serde_json::from_slice::<serde_json::Value>(body) else {
+                return write_response(stream, 400, "text/plain", b"Invalid JSON body");
+            };
+            match log_hub.update_runtime_filter(&payload) {
+                Ok(filter) => filter,
+                Err(err) => return write_response(stream, 400, "text/plain", err.as_bytes()),
+            }
+        }
+    };
+    let payload = serde_json::to_vec(&filter).unwrap_or_default();
+    write_response_with_headers(
+        stream,
+        200,
+        "application/json",
+        &payload,
+        &["Cache-Control: no-store"],
+    )
+}
+
+fn write_forwards_response(
+    stream: TcpStream,
+    forwards: &ForwardRegistry,
+    body: Option<&[u8]>,
+) -> io::Result<()> {
+    let current = match body {
+        None => forwards.forwards(),
+        Some(body) => match serde_json::from_slice::<ForwardUpdate>(body) {
+            Ok(update) => forwards.update(update),
+            Err(err) => {
+                return write_response(stream, 400, "text/plain", err.to_string().as_bytes())
+            }
+        },
+    };
+    let payload = serde_json::to_vec(&current).unwrap_or_default();
+    write_response_with_headers(
+        stream,
+        200,
+        "application/json",
+        &payload,
+        &["Cache-Control: no-store"],
+    )
+}
+
+fn is_valid_run_id(run_id: &str) -> bool {
+    !run_id.is_empty()
+        && run_id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+}
+
+fn render_runs_index(entries: &[RunEntry], query: Option<&str>) -> String {
+    let query = query.map(|query| format!("?{query}")).unwrap_or_default();
+    let items = entries.iter().fold(String::new(), |mut items, entry| {
+        let _ = write!(
+            items,
+            "<li><a href=\"/runs/{id}/{query}\">{id}</a> <small>{status}</small></li>",
+            id = entry.run_id,
+            status = entry.status
+        );
+        items
+    });
+    let body = if items.is_empty() {
+        "<p>No active runs.</p>".to_string()
+    } else {
+        format!("<ul>{items}</ul>")
+    };
+    format!(
+        "<!doctype html><html><head><meta charset=\"utf-8\"><title>sanelens runs</title></head><body><h1>Active runs</h1>{body}</body></html>"
+    )
+}
+
+fn is_authorized(token: Option<&str>, request: &HttpRequest<'_>, open: bool) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+    if open {
+        return true;
+    }
+    let bearer = request
+        .authorization
+        .as_deref()
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let query = request
+        .line
+        .query
+        .and_then(|query| query_param(query, "token"));
+    [bearer, query]
+        .into_iter()
+        .flatten()
+        .any(|candidate| tokens_match(candidate.trim(), token))
+}
+
+fn is_static_asset(path: &str) -> bool {
+    matches!(path, "/" | "/index.html" | "/app.js" | "/styles.css")
+}
+
+fn embedded_asset(path: &str) -> Option<&'static EmbeddedAsset> {
+    static ASSETS: OnceLock<Vec<EmbeddedAsset>> = OnceLock::new();
+    let assets = ASSETS.get_or_init(|| {
+        [
+            (
+                &["/", "/index.html"][..],
+                "text/html; charset=utf-8",
+                INDEX_HTML,
+            ),
+            (
+                &["/app.js"][..],
+                "application/javascript; charset=utf-8",
+                APP_JS,
+            ),
+            (&["/styles.css"][..], "text/css; charset=utf-8", STYLES_CSS),
+        ]
+        .into_iter()
+        .map(|(paths, content_type, body)| EmbeddedAsset {
+            paths,
+            content_type,
+            body: body.as_bytes(),
+            gzip: gzip(body.as_bytes()),
+            etag: asset_etag(body.as_bytes()),
+        })
+        .collect()
+    });
+    assets.iter().find(|asset| asset.paths.contains(&path))
+}
+
+fn asset_etag(body: &[u8]) -> String {
+    let hash = body.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("\"{hash:016x}\"")
+}
+
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match
+        .split(',')
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+fn write_embedded_asset(
+    mut stream: TcpStream,
+    asset: &EmbeddedAsset,
+    request: &HttpRequest<'_>,
+) -> io::Result<()> {
+    let etag = format!("ETag: {}", asset.etag);
+    if request
+        .if_none_match
+        .as_deref()
+        .is_some_and(|tags| etag_matches(tags, &asset.etag))
+    {
+        write!(
+            stream,
+            "HTTP/1.1 304 Not Modified\r\n{etag}\r\nCache-Control: no-cache\r\nVary: Accept-Encoding\r\n\r\n"
+        )?;
+        return stream.flush();
+    }
+    let mut headers = vec![
+        etag.as_str(),
+        "Cache-Control: no-cache",
+        "Vary: Accept-Encoding",
+    ];
+    let body = if request.accept_gzip {
+        headers.push("Content-Encoding: gzip");
+        asset.gzip.as_slice()
+    } else {
+        asset.body
+    };
+    write_response_with_headers(stream, 200, asset.content_type, body, &headers)
+}
+
+fn overlay_asset(dir: &Path, path: &str) -> Option<PathBuf> {
+    if path.starts_with("/api/") || path.starts_with("/admin/") {
+        return None;
+    }
+    let relative = Path::new(match path {
+        "/" => "index.html",
+        _ => path.strip_prefix('/')?,
+    });
+    let plain = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    let file = dir.join(relative);
+    (plain && file.is_file()).then_some(file)
+}
+
+fn write_overlay_asset(stream: TcpStream, file: &Path) -> io::Result<()> {
+    let Ok(body) = std::fs::read(file) else {
+        return write_response(stream, 500, "text/plain", b"Failed to read UI asset");
+    };
+    write_response_with_headers(
+        stream,
+        200,
+        asset_content_type(file),
+        &body,
+        &["Cache-Control: no-cache"],
+    )
+}
+
+fn asset_content_type(file: &Path) -> &'static str {
+    let extension = file
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("js" | "mjs") => "application/javascript; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("woff2") => "font/woff2",
+        Some("woff") => "font/woff",
+        Some("txt") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+fn query_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find_map(|(key, value)| (key == name).then_some(value))
+}
+
+fn tokens_match(candidate: &str, token: &str) -> bool {
+    candidate.len() == token.len()
+        && candidate
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |acc, (left, right)| acc | (left ^ right))
+            == 0
+}
+
 fn read_request_line(reader: &mut BufReader<TcpStream>) -> io::Result<Option<String>> {
     let mut request_line = String::new();
-    if reader.read_line(&mut request_line)? == 0 {
-        return Ok(None);
+    match reader.read_line(&mut request_line) {
+        Ok(0) => Ok(None),
+        Ok(_) => Ok(Some(request_line)),
+        Err(err)
+            if matches!(
+                err.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+            ) =>
+        {
+            Ok(None)
+        }
+        Err(err) => Err(err),
     }
-    Ok(Some(request_line))
 }
 
-fn parse_request_line(line: &str) -> Option<(&str, &str)> {
+fn parse_request_line(line: &str) -> Option<RequestLine<'_>> {
     let mut parts = line.split_whitespace();
     let method = parts.next()?;
-    let path = parts.next().unwrap_or("/");
-    let path = path.split('?').next().unwrap_or(path);
-    Some((method, path))
+    let target = parts.next().unwrap_or("/");
+    let http11 = parts.next() == Some("HTTP/1.1");
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (target, None),
+    };
+    Some(RequestLine {
+        method,
+        path,
+        query,
+        http11,
+    })
 }
 
-fn drain_headers(reader: &mut BufReader<TcpStream>) -> io::Result<()> {
+fn read_headers(reader: &mut BufReader<TcpStream>) -> io::Result<RequestHeaders> {
+    let mut headers = RequestHeaders::default();
     loop {
         let mut line = String::new();
         let bytes = reader.read_line(&mut line)?;
         if bytes == 0 || line == "\r\n" {
             break;
         }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let name = name.trim();
+        if name.eq_ignore_ascii_case("authorization") {
+            headers.authorization = Some(value.trim().to_string());
+        } else if name.eq_ignore_ascii_case("content-length") {
+            headers.content_length = value.trim().parse().unwrap_or(0);
+        } else if name.eq_ignore_ascii_case("last-event-id") {
+            headers.last_event_id = Some(value.trim().to_string());
+        } else if name.eq_ignore_ascii_case("if-none-match") {
+            headers.if_none_match = Some(value.trim().to_string());
+        } else if name.eq_ignore_ascii_case("accept-encoding") {
+            headers.accept_gzip = accepts_gzip(value);
+        } else if name.eq_ignore_ascii_case("connection") {
+            headers.close = value.trim().eq_ignore_ascii_case("close");
+        }
     }
-    Ok(())
+    Ok(headers)
 }
 
 fn route_request(path: &str, stream: TcpStream, context: &UiRouteContext<'_>) -> io::Result<()> {
     match path {
-        "/" | "/index.html" => write_response(
-            stream,
-            200,
-            "text/html; charset=utf-8",
-            INDEX_HTML.as_bytes(),
-        ),
-        "/app.js" => write_response(
-            stream,
-            200,
-            "application/javascript; charset=utf-8",
-            APP_JS.as_bytes(),
-        ),
-        "/styles.css" => write_response(
-            stream,
-            200,
-            "text/css; charset=utf-8",
-            STYLES_CSS.as_bytes(),
-        ),
-        "/api/services" => write_services_response(stream, context.service_info),
-        "/events" => write_event_stream(stream, context.log_hub, context.stop_event),
-        "/traffic" => route_traffic_stream(stream, context.traffic_hub, context.stop_event),
+        "/api/services" => write_services_response(stream, context),
+        "/api/graph" => write_graph_response(stream, context),
+        "/events" => write_event_stream(stream, context),
+        "/traffic" => route_traffic_stream(stream, context),
+        "/api/alerts" => match context.alert_hub {
+            Some(hub) => write_alert_stream(stream, hub, context.stop_event),
+            None => write_response(stream, 404, "text/plain", b"No alert rules configured"),
+        },
         "/traffic/calls" => {
             route_traffic_calls_stream(stream, context.traffic_hub, context.stop_event)
         }
-        _ => write_response(stream, 404, "text/plain", b"Not found"),
+        "/api/traffic/suppressed" => write_suppressed_response(stream, context.traffic_hub),
+        "/api/traffic/capture" => write_capture_response(stream, context.traffic_hub),
+        "/api/timeline" | "/api/timeline/events" => write_timeline_response(path, stream, context),
+        _ => route_dynamic(path, stream, context),
+    }
+}
+
+fn route_dynamic(path: &str, stream: TcpStream, context: &UiRouteContext<'_>) -> io::Result<()> {
+    if let Some(seq) = path
+        .strip_prefix("/api/traffic/calls/")
+        .and_then(|rest| rest.strip_suffix("/logs"))
+    {
+        return write_call_logs_response(stream, context, seq);
+    }
+    if let Some(seq) = path.strip_prefix("/api/traffic/calls/") {
+        return write_call_detail_response(stream, context.traffic_hub, seq);
+    }
+    if let Some(request_id) = path.strip_prefix("/api/traffic/traces/") {
+        return write_trace_response(stream, context.traffic_hub, request_id);
+    }
+    if let Some(service) = path
+        .strip_prefix("/api/services/")
+        .and_then(|rest| rest.strip_suffix("/env"))
+    {
+        return write_env_response(stream, context.containers, service);
+    }
+    if let Some(service) = path
+        .strip_prefix("/api/services/")
+        .and_then(|rest| rest.strip_suffix("/image"))
+    {
+        return write_image_response(stream, context.containers, service);
+    }
+    if let Some(service) = path
+        .strip_prefix("/api/services/")
+        .and_then(|rest| rest.strip_suffix("/config"))
+    {
+        return write_config_response(stream, context.service_config, service);
+    }
+    write_response(stream, 404, "text/plain", b"Not found")
+}
+
+fn proxy_admin(
+    mut stream: TcpStream,
+    containers: &RunContainers,
+    rest: &str,
+    query: Option<&str>,
+) -> io::Result<()> {
+    let (service, admin_path) = rest.split_once('/').unwrap_or((rest, ""));
+    let Some(target) = containers.proxy_admin_addr(service) else {
+        return write_response(
+            stream,
+            404,
+            "text/plain",
+            b"No running proxy with a published admin port for that service",
+        );
+    };
+    let mut upstream = match TcpStream::connect_timeout(&target, ADMIN_CONNECT_TIMEOUT) {
+        Ok(upstream) => upstream,
+        Err(err) => {
+            let message = format!("Envoy admin of {service} is unreachable at {target}: {err}");
+            return write_response(stream, 502, "text/plain", message.as_bytes());
+        }
+    };
+    upstream.set_read_timeout(Some(ADMIN_READ_TIMEOUT))?;
+    let query = admin_query(query);
+    write!(
+        upstream,
+        "GET /{admin_path}{query} HTTP/1.0\r\nHost: {target}\r\nConnection: close\r\n\r\n"
+    )?;
+    io::copy(&mut upstream, &mut stream)?;
+    stream.flush()
+}
+
+fn admin_query(query: Option<&str>) -> String {
+    let pairs: Vec<&str> = query
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty() && pair.split('=').next() != Some("token"))
+        .collect();
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("?{}", pairs.join("&"))
     }
 }
 
-fn write_services_response(
+fn write_image_response(
     stream: TcpStream,
-    service_info: &Arc<Vec<ServiceInfo>>,
+    containers: &Arc<RunContainers>,
+    service: &str,
 ) -> io::Result<()> {
-    let payload = serde_json::to_vec(&ServicesResponse {
-        services: service_info.as_slice(),
-    })
+    let Some(image) = containers.service_image(service) else {
+        return write_response(stream, 404, "text/plain", b"Unknown service");
+    };
+    let payload = serde_json::to_vec(&serde_json::json!({
+        "service": service,
+        "image": image,
+    }))
+    .unwrap_or_default();
+    write_response_with_headers(
+        stream,
+        200,
+        "application/json",
+        &payload,
+        &["Cache-Control: no-store"],
+    )
+}
+
+fn write_env_response(
+    stream: TcpStream,
+    containers: &Arc<RunContainers>,
+    service: &str,
+) -> io::Result<()> {
+    let Some(env) = containers.service_env(service) else {
+        return write_response(stream, 404, "text/plain", b"Unknown service");
+    };
+    let payload = serde_json::to_vec(&serde_json::json!({
+        "service": service,
+        "env": mask_env(&env),
+    }))
     .unwrap_or_default();
     write_response_with_headers(
         stream,
@@ -230,13 +1036,209 @@ fn write_services_response(
     )
 }
 
-fn route_traffic_stream(
+fn write_config_response(
+    stream: TcpStream,
+    service_config: &BTreeMap<String, ServiceConfig>,
+    service: &str,
+) -> io::Result<()> {
+    let Some(config) = service_config.get(service) else {
+        return write_response(stream, 404, "text/plain", b"Unknown service");
+    };
+    let payload = serde_json::to_vec(&serde_json::json!({
+        "service": service,
+        "environment": mask_env(&config.environment),
+        "env_files": config.env_files,
+        "fallbacks": config.fallbacks,
+    }))
+    .unwrap_or_default();
+    write_response_with_headers(
+        stream,
+        200,
+        "application/json",
+        &payload,
+        &["Cache-Control: no-store"],
+    )
+}
+
+fn write_trace_response(
     stream: TcpStream,
     traffic_hub: Option<&Arc<TrafficHub>>,
-    stop_event: &Arc<AtomicBool>,
+    request_id: &str,
 ) -> io::Result<()> {
-    match traffic_hub {
-        Some(hub) => write_traffic_stream(stream, hub, stop_event),
+    let Some(trace) = traffic_hub.and_then(|hub| hub.trace(request_id)) else {
+        return write_response(stream, 404, "text/plain", b"Not found");
+    };
+    let payload = serde_json::to_vec(&trace).unwrap_or_default();
+    write_response_with_headers(
+        stream,
+        200,
+        "application/json",
+        &payload,
+        &["Cache-Control: no-store"],
+    )
+}
+
+fn write_call_logs_response(
+    stream: TcpStream,
+    context: &UiRouteContext<'_>,
+    seq: &str,
+) -> io::Result<()> {
+    let logs = seq.parse::<u64>().ok().and_then(|seq| {
+        let call = context.traffic_hub?.call(seq)?;
+        correlate_call_logs(&call, &context.log_hub.history())
+    });
+    let Some(logs) = logs else {
+        return write_response(stream, 404, "text/plain", b"Not found");
+    };
+    let payload = serde_json::to_vec(&logs).unwrap_or_default();
+    write_response_with_headers(
+        stream,
+        200,
+        "application/json",
+        &payload,
+        &["Cache-Control: no-store"],
+    )
+}
+
+fn write_call_detail_response(
+    stream: TcpStream,
+    traffic_hub: Option<&Arc<TrafficHub>>,
+    seq: &str,
+) -> io::Result<()> {
+    let detail = seq
+        .parse::<u64>()
+        .ok()
+        .and_then(|seq| traffic_hub?.call_detail(seq));
+    let Some(detail) = detail else {
+        return write_response(stream, 404, "text/plain", b"Not found");
+    };
+    let payload = serde_json::to_vec(&versioned(&detail)).unwrap_or_default();
+    write_response_with_headers(
+        stream,
+        200,
+        "application/json",
+        &payload,
+        &["Cache-Control: no-store"],
+    )
+}
+
+fn write_capture_response(
+    stream: TcpStream,
+    traffic_hub: Option<&Arc<TrafficHub>>,
+) -> io::Result<()> {
+    let services = traffic_hub
+        .map(|hub| hub.capture_modes())
+        .unwrap_or_default();
+    let payload =
+        serde_json::to_vec(&serde_json::json!({ "services": services })).unwrap_or_default();
+    write_response_with_headers(
+        stream,
+        200,
+        "application/json",
+        &payload,
+        &["Cache-Control: no-store"],
+    )
+}
+
+fn write_suppressed_response(
+    stream: TcpStream,
+    traffic_hub: Option<&Arc<TrafficHub>>,
+) -> io::Result<()> {
+    let services = traffic_hub.map(|hub| hub.suppressed()).unwrap_or_default();
+    let payload = serde_json::to_vec(&serde_json::json!({
+        "total": services.values().sum::<u64>(),
+        "services": services,
+    }))
+    .unwrap_or_default();
+    write_response_with_headers(
+        stream,
+        200,
+        "application/json",
+        &payload,
+        &["Cache-Control: no-store"],
+    )
+}
+
+fn write_timeline_response(
+    path: &str,
+    stream: TcpStream,
+    context: &UiRouteContext<'_>,
+) -> io::Result<()> {
+    let param = |name| context.query.and_then(|query| query_param(query, name));
+    let window = match TimelineWindow::parse(param("from"), param("to"), param("step"), now_ms()) {
+        Ok(window) => window,
+        Err(err) => return write_response(stream, 400, "text/plain", err.as_bytes()),
+    };
+    let payload = if path == "/api/timeline" {
+        let requests = context
+            .traffic_hub
+            .map(|hub| hub.timeline(&window))
+            .unwrap_or_default();
+        serde_json::to_vec(&build_timeline(
+            &window,
+            context.log_hub.timeline(&window),
+            requests,
+        ))
+    } else {
+        let (logs, logs_truncated) = context.log_hub.window(&window);
+        let (calls, calls_truncated) = context
+            .traffic_hub
+            .map(|hub| hub.calls_between(&window))
+            .unwrap_or_default();
+        serde_json::to_vec(&serde_json::json!({
+            "from_ms": window.from,
+            "to_ms": window.to,
+            "logs": logs,
+            "calls": calls,
+            "truncated": logs_truncated || calls_truncated,
+        }))
+    };
+    write_response_with_headers(
+        stream,
+        200,
+        "application/json",
+        &payload.unwrap_or_default(),
+        &["Cache-Control: no-store"],
+    )
+}
+
+fn write_graph_response(stream: TcpStream, context: &UiRouteContext<'_>) -> io::Result<()> {
+    let edges = context
+        .traffic_hub
+        .map(|hub| hub.edges())
+        .unwrap_or_default();
+    let payload =
+        serde_json::to_vec(&overlay_traffic(context.service_graph, &edges)).unwrap_or_default();
+    write_response_with_headers(
+        stream,
+        200,
+        "application/json",
+        &payload,
+        &["Cache-Control: no-store"],
+    )
+}
+
+fn write_services_response(stream: TcpStream, context: &UiRouteContext<'_>) -> io::Result<()> {
+    let services = context.forwards.apply(context.service_info);
+    let probed = context
+        .endpoint_probes
+        .map(|probes| probes.annotate(&services));
+    let payload = serde_json::to_vec(&ServicesResponse {
+        services: probed.as_deref().unwrap_or(services.as_slice()),
+    })
+    .unwrap_or_default();
+    write_response_with_headers(
+        stream,
+        200,
+        "application/json",
+        &payload,
+        &["Cache-Control: no-store"],
+    )
+}
+
+fn route_traffic_stream(stream: TcpStream, context: &UiRouteContext<'_>) -> io::Result<()> {
+    match context.traffic_hub {
+        Some(hub) => write_traffic_stream(stream, hub, context.last_event_id, context.stop_event),
         None => write_response(stream, 404, "text/plain", b"Not found"),
     }
 }
@@ -261,6 +1263,23 @@ fn write_response(
     write_response_with_headers(stream, status, content_type, body, &[])
 }
 
+const fn status_text(status: u16) -> &'static str {
+    match status {
+        201 => "Created",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        _ => "OK",
+    }
+}
+
 fn write_response_with_headers(
     mut stream: TcpStream,
     status: u16,
@@ -268,11 +1287,7 @@ fn write_response_with_headers(
     body: &[u8],
     headers: &[&str],
 ) -> io::Result<()> {
-    let status_text = match status {
-        404 => "Not Found",
-        405 => "Method Not Allowed",
-        _ => "OK",
-    };
+    let status_text = status_text(status);
     let content_len = body.len();
     let mut response = format!(
         "HTTP/1.1 {status} {status_text}\r\nContent-Type: {content_type}\r\nContent-Length: {content_len}\r\n"
@@ -288,11 +1303,7 @@ fn write_response_with_headers(
     Ok(())
 }
 
-fn write_event_stream(
-    mut stream: TcpStream,
-    log_hub: &Arc<LogHub>,
-    stop_event: &Arc<AtomicBool>,
-) -> io::Result<()> {
+fn write_event_stream(mut stream: TcpStream, context: &UiRouteContext<'_>) -> io::Result<()> {
     let headers = [
         "HTTP/1.1 200 OK",
         "Content-Type: text/event-stream",
@@ -304,15 +1315,27 @@ fn write_event_stream(
     stream.write_all(headers.as_bytes())?;
     stream.flush()?;
 
-    let (receiver, history) = log_hub.register_client();
-    if write_history(&mut stream, &history).is_err() {
+    let last_seq = context
+        .last_event_id
+        .and_then(|value| value.parse::<u64>().ok());
+    let (receiver, snapshot) = context.log_hub.register_client_since(last_seq);
+    if write_history(&mut stream, &snapshot).is_err() {
         return Ok(());
     }
+    let batched = context
+        .query
+        .and_then(|query| query_param(query, "batch"))
+        .is_some_and(|value| matches!(value, "1" | "true" | "yes"));
 
-    while !stop_event.load(Ordering::SeqCst) {
+    while !context.stop_event.load(Ordering::SeqCst) {
         match receiver.recv_timeout(Duration::from_secs(1)) {
             Ok(event) => {
-                if write_event(&mut stream, &event).is_err() {
+                let written = if batched {
+                    write_batch(&mut stream, &collect_batch(&receiver, event))
+                } else {
+                    write_event(&mut stream, &event)
+                };
+                if written.is_err() {
                     break;
                 }
             }
@@ -328,16 +1351,53 @@ fn write_event_stream(
     Ok(())
 }
 
-fn write_history(stream: &mut TcpStream, events: &[LogEvent]) -> io::Result<()> {
-    let payload = serde_json::to_string(events).unwrap_or_default();
-    stream.write_all(format!("event: history\ndata: {payload}\n\n").as_bytes())?;
+fn write_history(stream: &mut TcpStream, snapshot: &LogSnapshot) -> io::Result<()> {
+    let payload = serde_json::to_string(&snapshot.events).unwrap_or_default();
+    let event = if snapshot.resumed {
+        "resume"
+    } else {
+        "history"
+    };
+    let id = snapshot
+        .events
+        .last()
+        .map(|last| format!("id: {}\n", last.seq))
+        .unwrap_or_default();
+    stream.write_all(format!("{id}event: {event}\ndata: {payload}\n\n").as_bytes())?;
     stream.flush()?;
     Ok(())
 }
 
 fn write_event(stream: &mut TcpStream, event: &LogEvent) -> io::Result<()> {
     let payload = serde_json::to_string(event).unwrap_or_default();
-    stream.write_all(format!("data: {payload}\n\n").as_bytes())?;
+    let seq = event.seq;
+    stream.write_all(format!("id: {seq}\ndata: {payload}\n\n").as_bytes())?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn collect_batch(receiver: &Receiver<LogEvent>, first: LogEvent) -> Vec<LogEvent> {
+    let deadline = Instant::now() + EVENT_BATCH_WINDOW;
+    let mut batch = vec![first];
+    while batch.len() < EVENT_BATCH_LIMIT {
+        let Some(wait) = deadline.checked_duration_since(Instant::now()) else {
+            break;
+        };
+        match receiver.recv_timeout(wait) {
+            Ok(event) => batch.push(event),
+            Err(_) => break,
+        }
+    }
+    batch
+}
+
+fn write_batch(stream: &mut TcpStream, events: &[LogEvent]) -> io::Result<()> {
+    let Some(last) = events.last() else {
+        return Ok(());
+    };
+    let payload = serde_json::to_string(events).unwrap_or_default();
+    let seq = last.seq;
+    stream.write_all(format!("id: {seq}\nevent: batch\ndata: {payload}\n\n").as_bytes())?;
     stream.flush()?;
     Ok(())
 }
@@ -345,6 +1405,7 @@ fn write_event(stream: &mut TcpStream, event: &LogEvent) -> io::Result<()> {
 fn write_traffic_stream(
     mut stream: TcpStream,
     hub: &Arc<TrafficHub>,
+    last_event_id: Option<&str>,
     stop_event: &Arc<AtomicBool>,
 ) -> io::Result<()> {
     let headers = [
@@ -358,15 +1419,22 @@ fn write_traffic_stream(
     stream.write_all(headers.as_bytes())?;
     stream.flush()?;
 
-    let (receiver, snapshot) = hub.register_client();
-    if write_traffic_snapshot(&mut stream, &snapshot).is_err() {
+    let (receiver, snapshot) = hub.register_client(last_event_id);
+    if write_traffic_snapshot(&mut stream, hub, &snapshot).is_err() {
         return Ok(());
     }
 
+    let mut last_keyframe = Instant::now();
     while !stop_event.load(Ordering::SeqCst) {
+        if last_keyframe.elapsed() >= TRAFFIC_KEYFRAME_INTERVAL {
+            last_keyframe = Instant::now();
+            if write_traffic_snapshot(&mut stream, hub, &hub.keyframe()).is_err() {
+                break;
+            }
+        }
         match receiver.recv_timeout(Duration::from_secs(1)) {
-            Ok(event) => {
-                if write_traffic_event(&mut stream, &event).is_err() {
+            Ok(update) => {
+                if write_traffic_event(&mut stream, hub, &update).is_err() {
                     break;
                 }
             }
@@ -399,6 +1467,7 @@ fn write_traffic_calls_stream(
     stream.flush()?;
 
     let (receiver, snapshot) = hub.register_call_client();
+    let snapshot: Vec<TrafficCall> = snapshot.into_iter().map(call_summary).collect();
     if write_traffic_call_snapshot(&mut stream, &snapshot).is_err() {
         return Ok(());
     }
@@ -406,7 +1475,7 @@ fn write_traffic_calls_stream(
     while !stop_event.load(Ordering::SeqCst) {
         match receiver.recv_timeout(Duration::from_secs(1)) {
             Ok(event) => {
-                if write_traffic_call_event(&mut stream, &event).is_err() {
+                if write_traffic_call_event(&mut stream, &call_summary(event)).is_err() {
                     break;
                 }
             }
@@ -422,29 +1491,95 @@ fn write_traffic_calls_stream(
     Ok(())
 }
 
-fn write_traffic_snapshot(stream: &mut TcpStream, edges: &[TrafficEdge]) -> io::Result<()> {
-    let payload = serde_json::to_string(edges).unwrap_or_default();
-    stream.write_all(format!("event: snapshot\ndata: {payload}\n\n").as_bytes())?;
+fn write_traffic_snapshot(
+    stream: &mut TcpStream,
+    hub: &TrafficHub,
+    snapshot: &TrafficSnapshot,
+) -> io::Result<()> {
+    let payload = serde_json::to_string(&versioned_all(&snapshot.edges)).unwrap_or_default();
+    let event = if snapshot.resumed {
+        "resume"
+    } else {
+        "snapshot"
+    };
+    let id = hub.event_id(snapshot.revision);
+    stream.write_all(format!("id: {id}\nevent: {event}\ndata: {payload}\n\n").as_bytes())?;
     stream.flush()?;
     Ok(())
 }
 
-fn write_traffic_event(stream: &mut TcpStream, edge: &TrafficEdge) -> io::Result<()> {
-    let payload = serde_json::to_string(edge).unwrap_or_default();
-    stream.write_all(format!("data: {payload}\n\n").as_bytes())?;
+fn write_traffic_event(
+    stream: &mut TcpStream,
+    hub: &TrafficHub,
+    update: &TrafficUpdate,
+) -> io::Result<()> {
+    let id = hub.event_id(update.revision);
+    let message = if update.created {
+        let payload = serde_json::to_string(&versioned(&update.edge)).unwrap_or_default();
+        format!("id: {id}\ndata: {payload}\n\n")
+    } else {
+        let payload = serde_json::to_string(&versioned(&update.delta())).unwrap_or_default();
+        format!("id: {id}\nevent: delta\ndata: {payload}\n\n")
+    };
+    stream.write_all(message.as_bytes())?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn write_alert_stream(
+    mut stream: TcpStream,
+    hub: &Arc<AlertHub>,
+    stop_event: &Arc<AtomicBool>,
+) -> io::Result<()> {
+    let headers = [
+        "HTTP/1.1 200 OK",
+        "Content-Type: text/event-stream",
+        "Cache-Control: no-cache",
+        "Connection: keep-alive",
+        "\r\n",
+    ]
+    .join("\r\n");
+    stream.write_all(headers.as_bytes())?;
     stream.flush()?;
+
+    let (receiver, history) = hub.register_client();
+    let payload = serde_json::to_string(&history).unwrap_or_default();
+    stream.write_all(format!("event: history\ndata: {payload}\n\n").as_bytes())?;
+    stream.flush()?;
+
+    while !stop_event.load(Ordering::SeqCst) {
+        match receiver.recv_timeout(Duration::from_secs(1)) {
+            Ok(event) => {
+                let payload = serde_json::to_string(&event).unwrap_or_default();
+                if stream
+                    .write_all(format!("data: {payload}\n\n").as_bytes())
+                    .and_then(|()| stream.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+            Err(crossbeam_channel::RecvTimeoutError::Timeout) => {
+                if stream.write_all(b": ping\n\n").is_err() {
+                    break;
+                }
+                let _ = stream.flush();
+            }
+            Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
+        }
+    }
     Ok(())
 }
 
 fn write_traffic_call_snapshot(stream: &mut TcpStream, calls: &[TrafficCall]) -> io::Result<()> {
-    let payload = serde_json::to_string(calls).unwrap_or_default();
+    let payload = serde_json::to_string(&versioned_all(calls)).unwrap_or_default();
     stream.write_all(format!("event: snapshot\ndata: {payload}\n\n").as_bytes())?;
     stream.flush()?;
     Ok(())
 }
 
 fn write_traffic_call_event(stream: &mut TcpStream, call: &TrafficCall) -> io::Result<()> {
-    let payload = serde_json::to_string(call).unwrap_or_default();
+    let payload = serde_json::to_string(&versioned(call)).unwrap_or_default();
     stream.write_all(format!("data: {payload}\n\n").as_bytes())?;
     stream.flush()?;
     Ok(())
@@ -454,3 +1589,183 @@ fn write_traffic_call_event(stream: &mut TcpStream, call: &TrafficCall) -> io::R
 struct ServicesResponse<'a> {
     services: &'a [ServiceInfo],
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    use super::{
+        admin_query, allows_method, asset_content_type, bind_listener, collect_batch,
+        embedded_asset, etag_matches, holds_connection, is_authorized, is_static_asset,
+        overlay_asset, parse_request_line, status_text, HttpRequest, ServeContext, UiBind,
+        EVENT_BATCH_LIMIT,
+    };
+    use crate::domain::{LogEvent, OutputStream};
+    use std::path::Path;
+
+    fn event(seq: u64) -> LogEvent {
+        LogEvent {
+            seq,
+            service: "api".to_string(),
+            container_ts: None,
+            ts_ms: None,
+            line: format!("line {seq}"),
+            stream: OutputStream::Stdout,
+            repeat_count: None,
+            annotation: false,
+            instance: None,
+            kind: None,
+            exception: None,
+        }
+    }
+
+    fn request<'a>(line: &'a str, authorization: Option<&str>) -> Option<HttpRequest<'a>> {
+        parse_request_line(line).map(|line| HttpRequest {
+            line,
+            authorization: authorization.map(ToString::to_string),
+            last_event_id: None,
+            if_none_match: None,
+            accept_gzip: false,
+            body: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn token_is_required_outside_static_assets() {
+        let api = request("GET /api/services HTTP/1.1\r\n", None);
+        let query = request("GET /events?token=abc HTTP/1.1\r\n", None);
+        let bearer = request("GET /api/services HTTP/1.1\r\n", Some("Bearer abc"));
+        assert!(api.is_some_and(|req| !is_authorized(Some("abc"), &req, false)));
+        assert!(query.is_some_and(|req| is_authorized(Some("abc"), &req, false)));
+        assert!(bearer.is_some_and(|req| is_authorized(Some("abc"), &req, false)));
+        assert!(is_static_asset("/app.js"));
+        assert!(!is_static_asset("/events"));
+    }
+
+    #[test]
+    fn loopback_opens_reads_but_not_writes() {
+        let stop_event = Arc::new(AtomicBool::new(false));
+        let serve = ServeContext {
+            token: Some("abc"),
+            open_reads: true,
+            assets: None,
+            stop_event: &stop_event,
+        };
+        let read = request("GET /api/services HTTP/1.1\r\n", None);
+        let write = request("POST /api/annotations HTTP/1.1\r\n", None);
+        let signed = request("POST /api/annotations HTTP/1.1\r\n", Some("Bearer abc"));
+        assert!(read.is_some_and(|req| serve.opens_read(&req)));
+        assert!(write.is_some_and(|req| {
+            !serve.opens_read(&req) && !is_authorized(serve.token, &req, false)
+        }));
+        assert!(signed.is_some_and(|req| is_authorized(serve.token, &req, false)));
+    }
+
+    #[test]
+    fn embedded_assets_revalidate_and_streams_hold_the_connection() {
+        let index = embedded_asset("/");
+        let etag = index.map(|asset| asset.etag.clone()).unwrap_or_default();
+        assert_eq!(
+            embedded_asset("/index.html").map(|asset| &asset.etag),
+            Some(&etag)
+        );
+        assert!(embedded_asset("/api/services").is_none());
+        assert!(etag_matches(&format!("\"other\", W/{etag}"), &etag));
+        assert!(!etag_matches("\"other\"", &etag));
+        assert!(holds_connection("/events"));
+        assert!(holds_connection("/runs/abc/traffic/calls"));
+        assert!(holds_connection("/admin/api/stats"));
+        assert!(!holds_connection("/app.js"));
+        assert!(!holds_connection("/runs/abc/api/services"));
+    }
+
+    #[test]
+    fn overlay_assets_stay_inside_the_directory() {
+        let dir = std::env::temp_dir().join(format!("sanelens-ui-assets-{}", std::process::id()));
+        let _ = std::fs::create_dir_all(&dir);
+        let _ = std::fs::write(dir.join("index.html"), "<h1>docs</h1>");
+        let _ = std::fs::write(dir.join("logo.svg"), "<svg/>");
+        assert_eq!(overlay_asset(&dir, "/"), Some(dir.join("index.html")));
+        assert_eq!(overlay_asset(&dir, "/logo.svg"), Some(dir.join("logo.svg")));
+        assert_eq!(overlay_asset(&dir, "/app.js"), None);
+        assert_eq!(overlay_asset(&dir, "/../index.html"), None);
+        assert_eq!(asset_content_type(Path::new("logo.SVG")), "image/svg+xml");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn admin_queries_drop_the_ui_token() {
+        assert_eq!(admin_query(None), "");
+        assert_eq!(admin_query(Some("token=secret")), "");
+        assert_eq!(
+            admin_query(Some("token=secret&filter=cluster&format=json")),
+            "?filter=cluster&format=json"
+        );
+        assert_eq!(admin_query(Some("usedonly&token")), "?usedonly");
+    }
+
+    #[test]
+    fn shared_listeners_only_accept_reads() {
+        assert_eq!(status_text(403), "Forbidden");
+        assert_eq!(status_text(304), "Not Modified");
+        assert_eq!(status_text(409), "Conflict");
+        assert_eq!(status_text(500), "Internal Server Error");
+        assert!(allows_method(false, "POST"));
+        assert!(allows_method(true, "GET"));
+        assert!(!allows_method(true, "HEAD"));
+        assert!(!allows_method(true, "POST"));
+        assert!(!allows_method(true, "DELETE"));
+    }
+
+    #[test]
+    fn loopback_binds_are_detected() {
+        let bind = |host: &str| UiBind {
+            host: host.to_string(),
+            port: 0,
+            fallback: false,
+            assets: None,
+            share: None,
+        };
+        assert!(bind("127.0.0.1").is_loopback());
+        assert!(bind("::1").is_loopback());
+        assert!(!bind("0.0.0.0").is_loopback());
+    }
+
+    #[test]
+    fn busy_ports_fail_unless_fallback_is_allowed() {
+        let Ok(taken) = TcpListener::bind(("127.0.0.1", 0)) else {
+            return;
+        };
+        let Ok(addr) = taken.local_addr() else {
+            return;
+        };
+        let mut bind = UiBind {
+            host: "127.0.0.1".to_string(),
+            port: addr.port(),
+            fallback: false,
+            assets: None,
+            share: None,
+        };
+        assert!(bind_listener(&bind).is_err());
+        bind.fallback = true;
+        let fallback = bind_listener(&bind).and_then(|listener| listener.local_addr());
+        assert!(fallback.is_ok_and(|local| local.port() != addr.port()));
+    }
+
+    #[test]
+    fn batches_pending_events_up_to_the_limit() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        for seq in 1..=EVENT_BATCH_LIMIT as u64 + 5 {
+            let _ = sender.send(event(seq));
+        }
+        let first = collect_batch(&receiver, event(0));
+        assert_eq!(first.len(), EVENT_BATCH_LIMIT);
+        assert_eq!(first.first().map(|event| event.seq), Some(0));
+        let rest = receiver
+            .try_recv()
+            .map(|next| collect_batch(&receiver, next));
+        assert_eq!(rest.map(|batch| batch.len()).ok(), Some(6));
+    }
+}
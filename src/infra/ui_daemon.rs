@@ -0,0 +1,289 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::infra::ui::{UiEndpoint, UiRun};
+use crate::support::run::state_dir;
+
+const DAEMON_FILE: &str = "ui-daemon.json";
+const PENDING_TTL: Duration = Duration::from_mins(5);
+const REGISTER_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, Deserialize)]
+pub struct DaemonInfo {
+    pub host: String,
+    pub port: u16,
+    pub token: Option<String>,
+    pub pid: u32,
+}
+
+#[derive(Serialize)]
+pub struct RunEntry {
+    pub run_id: String,
+    pub status: &'static str,
+}
+
+pub struct UiRegistry {
+    runs: Mutex<BTreeMap<String, Arc<UiRun>>>,
+    pending: Mutex<HashMap<String, Instant>>,
+}
+
+impl UiRegistry {
+    pub fn new() -> Self {
+        Self {
+            runs: Mutex::new(BTreeMap::new()),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn insert(&self, run_id: &str, run: UiRun) {
+        self.pending().remove(run_id);
+        self.runs().insert(run_id.to_string(), Arc::new(run));
+    }
+
+    pub fn remove(&self, run_id: &str) {
+        self.runs().remove(run_id);
+    }
+
+    pub fn get(&self, run_id: &str) -> Option<Arc<UiRun>> {
+        self.runs().get(run_id).cloned()
+    }
+
+    pub fn contains(&self, run_id: &str) -> bool {
+        self.runs().contains_key(run_id)
+    }
+
+    pub fn run_ids(&self) -> Vec<String> {
+        self.runs().keys().cloned().collect()
+    }
+
+    pub fn register(&self, run_id: &str) {
+        if !self.contains(run_id) {
+            self.pending().insert(run_id.to_string(), Instant::now());
+        }
+    }
+
+    pub fn entries(&self) -> Vec<RunEntry> {
+        let mut entries: Vec<RunEntry> = self
+            .run_ids()
+            .into_iter()
+            .map(|run_id| RunEntry {
+                run_id,
+                status: "attached",
+            })
+            .collect();
+        let mut pending = self.pending();
+        pending.retain(|_, since| since.elapsed() < PENDING_TTL);
+        entries.extend(pending.keys().map(|run_id| RunEntry {
+            run_id: run_id.clone(),
+            status: "pending",
+        }));
+        drop(pending);
+        entries.sort_by(|left, right| left.run_id.cmp(&right.run_id));
+        entries
+    }
+
+    fn runs(&self) -> MutexGuard<'_, BTreeMap<String, Arc<UiRun>>> {
+        self.runs
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn pending(&self) -> MutexGuard<'_, HashMap<String, Instant>> {
+        self.pending
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+pub fn write_daemon_info(info: &DaemonInfo) -> Result<(), String> {
+    let path = daemon_info_path().ok_or_else(|| "no state directory available".to_string())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| format!("create {}: {err}", dir.display()))?;
+    }
+    let payload = serde_json::to_vec(info).unwrap_or_default();
+    fs::write(&path, payload).map_err(|err| format!("write {}: {err}", path.display()))
+}
+
+pub fn remove_daemon_info() {
+    if let Some(path) = daemon_info_path() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+pub fn register_with_daemon(run_id: &str) -> Option<UiEndpoint> {
+    let path = daemon_info_path()?;
+    let info: DaemonInfo = serde_json::from_slice(&fs::read(path).ok()?).ok()?;
+    let addr = (info.host.as_str(), info.port)
+        .to_socket_addrs()
+        .ok()?
+        .next()?;
+    let mut stream = TcpStream::connect_timeout(&addr, REGISTER_TIMEOUT).ok()?;
+    stream.set_read_timeout(Some(REGISTER_TIMEOUT)).ok()?;
+    let body = serde_json::json!({ "run_id": run_id }).to_string();
+    let auth = info
+        .token
+        .as_deref()
+        .map(|token| format!("Authorization: Bearer {token}\r\n"))
+        .unwrap_or_default();
+    let request = format!(
+        "POST /api/runs/register HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n{auth}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        info.host,
+        body.len()
+    );
+    stream.write_all(request.as_bytes()).ok()?;
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line).ok()?;
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        return None;
+    }
+    let host = if info.host.contains(':') {
+        format!("[{}]", info.host)
+    } else {
+        info.host.clone()
+    };
+    let query = info
+        .token
+        .as_deref()
+        .map(|token| format!("?token={token}"))
+        .unwrap_or_default();
+    Some(UiEndpoint {
+        url: format!("http://{host}:{}/runs/{run_id}/{query}", info.port),
+        host: info.host,
+        port: info.port,
+        token: info.token,
+    })
+}
+
+pub fn post_run_api(endpoint: &UiEndpoint, path: &str, body: &str) -> Result<String, String> {
+    let prefix = endpoint
+        .url
+        .split_once("://")
+        .and_then(|(_, rest)| rest.split_once('/'))
+        .and_then(|(_, rest)| rest.split('?').next())
+        .unwrap_or_default()
+        .trim_matches('/');
+    let target = if prefix.is_empty() {
+        path.to_string()
+    } else {
+        format!("/{prefix}{path}")
+    };
+    let addr = (endpoint.host.as_str(), endpoint.port)
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .ok_or_else(|| format!("cannot resolve {}", endpoint.host))?;
+    let mut stream = TcpStream::connect_timeout(&addr, REGISTER_TIMEOUT)
+        .map_err(|err| format!("log UI at {addr} is unreachable: {err}"))?;
+    stream
+        .set_read_timeout(Some(REGISTER_TIMEOUT))
+        .map_err(|err| err.to_string())?;
+    let auth = endpoint
+        .token
+        .as_deref()
+        .map(|token| format!("Authorization: Bearer {token}\r\n"))
+        .unwrap_or_default();
+    let request = format!(
+        "POST {target} HTTP/1.1\r\nHost: {addr}\r\nContent-Type: application/json\r\n{auth}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|err| err.to_string())?;
+    let mut response = String::new();
+    BufReader::new(stream)
+        .read_to_string(&mut response)
+        .map_err(|err| err.to_string())?;
+    let (head, payload) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+    match head.split_whitespace().nth(1) {
+        Some("200" | "201") => Ok(payload.to_string()),
+        Some(status) => Err(format!("log UI answered {status}: {}", payload.trim())),
+        None => Err("log UI sent an empty response".to_string()),
+    }
+}
+
+fn daemon_info_path() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join(DAEMON_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    use super::{post_run_api, UiRegistry};
+    use crate::infra::ui::UiEndpoint;
+
+    fn answer_once(stream: &mut TcpStream) -> Option<Vec<String>> {
+        let mut reader = BufReader::new(stream.try_clone().ok()?);
+        let mut head = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).ok()?;
+            if line.trim_end().is_empty() {
+                break;
+            }
+            head.push(line.trim_end().to_string());
+        }
+        stream
+            .write_all(b"HTTP/1.1 201 Created\r\nContent-Length: 2\r\n\r\nok")
+            .ok()?;
+        Some(head)
+    }
+
+    #[test]
+    fn lists_registered_runs_as_pending_until_they_attach() {
+        let registry = UiRegistry::new();
+        registry.register("run_b");
+        registry.register("run_a");
+        let entries: Vec<(String, &str)> = registry
+            .entries()
+            .into_iter()
+            .map(|entry| (entry.run_id, entry.status))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                ("run_a".to_string(), "pending"),
+                ("run_b".to_string(), "pending")
+            ]
+        );
+        assert!(!registry.contains("run_a"));
+        assert!(registry.get("run_a").is_none());
+    }
+
+    #[test]
+    fn posts_run_api_calls_under_the_run_prefix() {
+        let listener = TcpListener::bind("127.0.0.1:0");
+        assert!(listener.is_ok());
+        let Ok(listener) = listener else {
+            return;
+        };
+        let port = listener.local_addr().map_or(0, |addr| addr.port());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().ok()?;
+            answer_once(&mut stream)
+        });
+        let endpoint = UiEndpoint {
+            url: format!("http://127.0.0.1:{port}/runs/run_a/?token=t0k"),
+            host: "127.0.0.1".to_string(),
+            port,
+            token: Some("t0k".to_string()),
+        };
+        let response = post_run_api(&endpoint, "/api/log-filters", "{}");
+        assert_eq!(response, Ok("ok".to_string()));
+        let head = server.join().ok().flatten().unwrap_or_default();
+        assert_eq!(
+            head.first().map(String::as_str),
+            Some("POST /runs/run_a/api/log-filters HTTP/1.1")
+        );
+        assert!(head.iter().any(|line| line == "Authorization: Bearer t0k"));
+    }
+}
@@ -0,0 +1,300 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::traffic::{EdgeKey, EntityId, TrafficCall, TrafficEdge};
+use crate::support::glob::glob_match;
+
+const SAMPLE_STATUSES: usize = 5;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expectations {
+    #[serde(default)]
+    services: BTreeMap<String, ServiceExpectation>,
+    #[serde(default)]
+    egress: Option<EgressExpectation>,
+    #[serde(default)]
+    routes: Vec<RouteExpectation>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ServiceExpectation {
+    talks_to: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EgressExpectation {
+    #[serde(default)]
+    allow: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RouteExpectation {
+    path: String,
+    method: Option<String>,
+    service: Option<String>,
+    status: u16,
+}
+
+impl RouteExpectation {
+    fn describe(&self) -> String {
+        let method = self.method.as_deref().unwrap_or("*");
+        self.service.as_deref().map_or_else(
+            || format!("{method} {}", self.path),
+            |service| format!("{method} {service}{}", self.path),
+        )
+    }
+
+    fn matches(&self, call: &TrafficCall) -> bool {
+        let Some(path) = call.path.as_deref() else {
+            return false;
+        };
+        let path = path.split('?').next().unwrap_or(path);
+        glob_match(&self.path, path)
+            && self.method.as_deref().is_none_or(|method| {
+                call.method
+                    .as_deref()
+                    .is_some_and(|actual| actual.eq_ignore_ascii_case(method))
+            })
+            && self.service.as_deref().is_none_or(|service| {
+                call.peer.dst.as_ref().and_then(EntityId::workload_name) == Some(service)
+            })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    pub rule: &'static str,
+    pub message: String,
+}
+
+#[derive(Default)]
+struct RouteTally {
+    matched: u64,
+    mismatched: u64,
+    statuses: BTreeSet<String>,
+}
+
+impl RouteTally {
+    fn record(&mut self, status: Option<u16>, expected: u16) {
+        self.matched += 1;
+        if status == Some(expected) {
+            return;
+        }
+        self.mismatched += 1;
+        if self.statuses.len() < SAMPLE_STATUSES {
+            self.statuses
+                .insert(status.map_or_else(|| "none".to_string(), |status| status.to_string()));
+        }
+    }
+}
+
+pub struct Verifier {
+    expectations: Expectations,
+    routes: Vec<RouteTally>,
+}
+
+pub fn load_expectations(path: &Path) -> Result<Expectations, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+    serde_yaml::from_str(&contents)
+        .map_err(|err| format!("invalid expectations in {}: {err}", path.display()))
+}
+
+impl Verifier {
+    pub fn new(expectations: Expectations) -> Self {
+        let routes = expectations
+            .routes
+            .iter()
+            .map(|_| RouteTally::default())
+            .collect();
+        Self {
+            expectations,
+            routes,
+        }
+    }
+
+    pub fn observe(&mut self, call: &TrafficCall) {
+        for (rule, tally) in self.expectations.routes.iter().zip(self.routes.iter_mut()) {
+            if rule.matches(call) {
+                tally.record(call.status, rule.status);
+            }
+        }
+    }
+
+    pub fn finish(&self, edges: &[TrafficEdge]) -> Vec<Violation> {
+        let mut violations = self.peer_violations(edges);
+        violations.extend(self.route_violations());
+        violations
+    }
+
+    fn peer_violations(&self, edges: &[TrafficEdge]) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        let mut peers: BTreeMap<(String, String), u64> = BTreeMap::new();
+        let mut egress: BTreeMap<(String, String), u64> = BTreeMap::new();
+        for edge in edges {
+            let (EdgeKey::Flow { from, to, .. }
+            | EdgeKey::Http { from, to, .. }
+            | EdgeKey::Db { from, to, .. }
+            | EdgeKey::Grpc { from, to, .. }) = &edge.key;
+            let source = from.workload_name().unwrap_or("unknown").to_string();
+            match to {
+                EntityId::Workload { name, .. } => {
+                    *peers.entry((source, name.clone())).or_default() += edge.stats.count;
+                }
+                EntityId::External { ips, dns_name }
+                    if !self.egress_allowed(dns_name.as_deref(), ips) =>
+                {
+                    let host = to.external_host().unwrap_or_default();
+                    *egress.entry((source, host)).or_default() += edge.stats.count;
+                }
+                EntityId::External { .. } | EntityId::Host { .. } | EntityId::Unknown => {}
+            }
+        }
+        for ((from, to), count) in peers {
+            let allowed = self
+                .expectations
+                .services
+                .get(&from)
+                .and_then(|service| service.talks_to.as_ref());
+            if allowed.is_some_and(|allowed| !allowed.contains(&to)) {
+                violations.push(Violation {
+                    rule: "talks_to",
+                    message: format!("{from} called {to} ({count} calls), which is not allowed"),
+                });
+            }
+        }
+        if self.expectations.egress.is_some() {
+            violations.extend(egress.into_iter().map(|((from, host), count)| Violation {
+                rule: "egress",
+                message: format!("{from} reached external host {host} ({count} calls)"),
+            }));
+        }
+        violations
+    }
+
+    fn route_violations(&self) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for (rule, tally) in self.expectations.routes.iter().zip(&self.routes) {
+            if tally.matched == 0 {
+                violations.push(Violation {
+                    rule: "route",
+                    message: format!("no calls observed for {}", rule.describe()),
+                });
+            } else if tally.mismatched > 0 {
+                let statuses: Vec<&str> = tally.statuses.iter().map(String::as_str).collect();
+                violations.push(Violation {
+                    rule: "route",
+                    message: format!(
+                        "{} returned something other than {} in {} of {} calls (saw {})",
+                        rule.describe(),
+                        rule.status,
+                        tally.mismatched,
+                        tally.matched,
+                        statuses.join(", ")
+                    ),
+                });
+            }
+        }
+        violations
+    }
+
+    fn egress_allowed(&self, dns_name: Option<&str>, ips: &[IpAddr]) -> bool {
+        let ips: Vec<String> = ips.iter().map(ToString::to_string).collect();
+        self.expectations.egress.as_ref().is_none_or(|egress| {
+            egress.allow.iter().any(|pattern| {
+                ips.iter().any(|ip| glob_match(pattern, ip))
+                    || dns_name.is_some_and(|host| glob_match(pattern, host))
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use super::{Expectations, Verifier};
+    use crate::domain::traffic::{
+        EdgeKey, EdgeStats, EntityId, ObservationAttrs, TrafficCall, TrafficEdge, Transport,
+        Visibility,
+    };
+    use crate::support::traffic_fixtures::{http_call, workload, workload_peer};
+
+    fn edge(from: &str, to: EntityId) -> TrafficEdge {
+        TrafficEdge {
+            key: EdgeKey::Flow {
+                from: workload(from),
+                to,
+                transport: Transport::Tcp,
+                port: 443,
+            },
+            stats: EdgeStats {
+                count: 2,
+                bytes_in: 0,
+                bytes_out: 0,
+                errors: 0,
+                p50_ms: None,
+                p95_ms: None,
+                p99_ms: None,
+                visibility: Visibility::L4Flow,
+                networks: Vec::new(),
+            },
+            last_seen_ms: 0,
+        }
+    }
+
+    fn healthz(status: u16) -> TrafficCall {
+        TrafficCall {
+            peer: workload_peer("web", "api"),
+            path: Some("/healthz?probe=1".to_string()),
+            status: Some(status),
+            attrs: ObservationAttrs {
+                visibility: Visibility::L7Semantics,
+                ..http_call().attrs
+            },
+            ..http_call()
+        }
+    }
+
+    #[test]
+    fn reports_unexpected_peers_egress_and_statuses() {
+        let expectations: Expectations = serde_yaml::from_str(
+            "services:\n  web:\n    talks_to: [api]\negress:\n  allow: [\"*.github.com\"]\nroutes:\n  - path: /healthz\n    status: 200\n  - path: /ready\n    status: 200\n",
+        )
+        .unwrap_or_default();
+        let mut verifier = Verifier::new(expectations);
+        verifier.observe(&healthz(200));
+        verifier.observe(&healthz(503));
+        let external = |host: &str| EntityId::External {
+            ips: vec![IpAddr::V4(Ipv4Addr::new(140, 82, 112, 3))],
+            dns_name: Some(host.to_string()),
+        };
+        let violations = verifier.finish(&[
+            edge("web", workload("api")),
+            edge("web", workload("db")),
+            edge("api", external("api.github.com")),
+            edge("api", external("tracker.example.com")),
+        ]);
+        let rules: Vec<&str> = violations.iter().map(|violation| violation.rule).collect();
+        assert_eq!(rules, vec!["talks_to", "egress", "route", "route"]);
+        assert!(violations
+            .iter()
+            .any(|violation| violation.message.contains("1 of 2 calls (saw 503)")));
+        assert!(violations
+            .iter()
+            .any(|violation| violation.message == "no calls observed for * /ready"));
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(serde_yaml::from_str::<Expectations>("service:\n  web: {}\n").is_err());
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde_yaml::Value;
+
+const IGNORED_DIRS: &[&str] = &[".git", ".sanelens", "node_modules", "target"];
+const MAX_WATCHED_FILES: usize = 20_000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WatchTarget {
+    pub service: String,
+    pub paths: Vec<PathBuf>,
+}
+
+pub fn load_watch_targets(compose_file: &Path) -> Result<Vec<WatchTarget>, String> {
+    let contents = fs::read_to_string(compose_file)
+        .map_err(|err| format!("failed to read {}: {err}", compose_file.display()))?;
+    let doc: Value = serde_yaml::from_str(&contents)
+        .map_err(|err| format!("failed to parse {}: {err}", compose_file.display()))?;
+    Ok(watch_targets(&doc))
+}
+
+pub fn load_develop_watch_services(compose_file: &Path) -> Result<Vec<String>, String> {
+    let contents = fs::read_to_string(compose_file)
+        .map_err(|err| format!("failed to read {}: {err}", compose_file.display()))?;
+    let doc: Value = serde_yaml::from_str(&contents)
+        .map_err(|err| format!("failed to parse {}: {err}", compose_file.display()))?;
+    Ok(develop_watch_services(&doc))
+}
+
+pub fn develop_watch_services(doc: &Value) -> Vec<String> {
+    let Some(services) = doc.get("services").and_then(Value::as_mapping) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = services
+        .iter()
+        .filter(|(_, service)| {
+            service
+                .get("develop")
+                .and_then(|develop| develop.get("watch"))
+                .and_then(Value::as_sequence)
+                .is_some_and(|rules| !rules.is_empty())
+        })
+        .filter_map(|(name, _)| name.as_str().map(ToString::to_string))
+        .collect();
+    names.sort();
+    names
+}
+
+pub fn watch_targets(doc: &Value) -> Vec<WatchTarget> {
+    let Some(services) = doc.get("services").and_then(Value::as_mapping) else {
+        return Vec::new();
+    };
+    let mut targets: Vec<WatchTarget> = services
+        .iter()
+        .filter_map(|(name, service)| {
+            let paths = build_paths(service.get("build")?);
+            let service = name.as_str()?.to_string();
+            (!paths.is_empty()).then_some(WatchTarget { service, paths })
+        })
+        .collect();
+    targets.sort_by(|a, b| a.service.cmp(&b.service));
+    targets
+}
+
+fn build_paths(build: &Value) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    match build {
+        Value::String(context) => paths.push(PathBuf::from(context)),
+        Value::Mapping(map) => {
+            if let Some(context) = map.get("context").and_then(Value::as_str) {
+                paths.push(PathBuf::from(context));
+            }
+            if let Some(additional) = map.get("additional_contexts").and_then(Value::as_mapping) {
+                paths.extend(
+                    additional
+                        .values()
+                        .filter_map(Value::as_str)
+                        .map(PathBuf::from),
+                );
+            }
+        }
+        _ => {}
+    }
+    paths.retain(|path| path.is_dir());
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+pub struct Watcher {
+    targets: Vec<WatchTarget>,
+    fingerprints: Vec<u64>,
+}
+
+impl Watcher {
+    pub fn new(targets: Vec<WatchTarget>) -> Self {
+        let fingerprints = targets.iter().map(fingerprint).collect();
+        Self {
+            targets,
+            fingerprints,
+        }
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    pub fn poll(&mut self) -> Vec<String> {
+        let mut changed = Vec::new();
+        for (target, previous) in self.targets.iter().zip(self.fingerprints.iter_mut()) {
+            let current = fingerprint(target);
+            if current != *previous {
+                *previous = current;
+                changed.push(target.service.clone());
+            }
+        }
+        changed
+    }
+}
+
+fn fingerprint(target: &WatchTarget) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let mut budget = MAX_WATCHED_FILES;
+    for path in &target.paths {
+        hash_tree(path, &mut hasher, &mut budget);
+    }
+    hasher.finish()
+}
+
+fn hash_tree(root: &Path, hasher: &mut DefaultHasher, budget: &mut usize) {
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut entries: Vec<_> = entries.flatten().collect();
+        entries.sort_by_key(fs::DirEntry::file_name);
+        for entry in entries {
+            if *budget == 0 {
+                return;
+            }
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            let path = entry.path();
+            if meta.is_dir() {
+                pending.extend((!is_ignored(&entry)).then_some(path));
+                continue;
+            }
+            *budget -= 1;
+            path.hash(hasher);
+            meta.len().hash(hasher);
+            meta.modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .unwrap_or_default()
+                .hash(hasher);
+        }
+    }
+}
+
+fn is_ignored(entry: &fs::DirEntry) -> bool {
+    entry
+        .file_name()
+        .to_str()
+        .is_some_and(|name| IGNORED_DIRS.contains(&name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{develop_watch_services, watch_targets, Watcher};
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn reports_services_whose_build_context_changed() {
+        let root = env::temp_dir().join(format!("sanelens-watch-{}", std::process::id()));
+        let api = root.join("api");
+        let web = root.join("web");
+        let _ = fs::create_dir_all(api.join("node_modules"));
+        let _ = fs::create_dir_all(&web);
+        let _ = fs::write(api.join("main.rs"), "fn main() {}");
+
+        let yaml = format!(
+            "services:\n  api:\n    build: {}\n  web:\n    build:\n      context: {}\n  db:\n    image: postgres\n",
+            api.display(),
+            web.display()
+        );
+        let doc = serde_yaml::from_str(&yaml).unwrap_or_default();
+        let targets = watch_targets(&doc);
+        assert_eq!(
+            targets
+                .iter()
+                .map(|t| t.service.as_str())
+                .collect::<Vec<_>>(),
+            ["api", "web"]
+        );
+
+        let mut watcher = Watcher::new(targets);
+        assert!(watcher.poll().is_empty());
+        let _ = fs::write(api.join("node_modules").join("dep.js"), "ignored");
+        assert!(watcher.poll().is_empty());
+        let _ = fs::write(api.join("main.rs"), "fn main() { println!(); }");
+        assert_eq!(watcher.poll(), ["api".to_string()]);
+        assert!(watcher.poll().is_empty());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn finds_services_with_develop_watch_rules() {
+        let doc = serde_yaml::from_str(
+            "services:\n  web:\n    develop:\n      watch:\n        - action: sync\n          path: ./src\n          target: /app/src\n  api:\n    develop:\n      watch: []\n  db:\n    image: postgres\n",
+        )
+        .unwrap_or_default();
+        assert_eq!(develop_watch_services(&doc), ["web".to_string()]);
+    }
+}
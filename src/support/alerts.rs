@@ -0,0 +1,432 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+
+use crossbeam_channel::{bounded, Receiver, Sender, TrySendError};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+use crate::domain::traffic::EdgeKey;
+use crate::domain::{split_identity, LogEvent};
+use crate::support::args::parse_duration;
+use crate::support::glob::glob_match;
+use crate::support::traffic::TrafficUpdate;
+
+const EXTENSION_KEY: &str = "x-sanelens";
+const ALERTS_KEY: &str = "alerts";
+const DEFAULT_WINDOW_MS: u64 = 60_000;
+const DEFAULT_MIN_CALLS: u64 = 5;
+const ALERT_HISTORY_LIMIT: usize = 200;
+const ALERT_CLIENT_QUEUE_SIZE: usize = 256;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSpec {
+    name: Option<String>,
+    from: Option<String>,
+    service: Option<String>,
+    error_rate: Option<Value>,
+    p99: Option<String>,
+    log: Option<String>,
+    exception: Option<String>,
+    window: Option<String>,
+    min_calls: Option<u64>,
+    #[serde(default)]
+    notify: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Condition {
+    ErrorRate(f64),
+    P99(u64),
+    LogContains(String),
+    Exception(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlertRule {
+    pub name: String,
+    from: Option<String>,
+    service: Option<String>,
+    condition: Condition,
+    window_ms: u64,
+    min_calls: u64,
+    pub notify: bool,
+}
+
+impl AlertRule {
+    fn matches_edge(&self, key: &EdgeKey) -> bool {
+        let (EdgeKey::Flow { from, to, .. }
+        | EdgeKey::Http { from, to, .. }
+        | EdgeKey::Db { from, to, .. }
+        | EdgeKey::Grpc { from, to, .. }) = key;
+        let matches = |pattern: Option<&str>, name: Option<&str>| {
+            pattern.is_none_or(|pattern| name.is_some_and(|name| glob_match(pattern, name)))
+        };
+        matches(self.from.as_deref(), from.workload_name())
+            && matches(self.service.as_deref(), to.workload_name())
+    }
+
+    fn matches_service(&self, service: &str) -> bool {
+        let base = split_identity(service).0;
+        self.service
+            .as_deref()
+            .is_none_or(|pattern| glob_match(pattern, service) || glob_match(pattern, base))
+    }
+}
+
+pub fn alert_rules_from_doc(doc: &Value) -> Result<Vec<AlertRule>, String> {
+    let Some(section) = doc
+        .get(EXTENSION_KEY)
+        .and_then(|value| value.get(ALERTS_KEY))
+    else {
+        return Ok(Vec::new());
+    };
+    let specs: Vec<RuleSpec> = serde_yaml::from_value(section.clone())
+        .map_err(|err| format!("invalid {EXTENSION_KEY}.{ALERTS_KEY}: {err}"))?;
+    specs
+        .into_iter()
+        .enumerate()
+        .map(|(idx, spec)| parse_rule(idx, spec))
+        .collect()
+}
+
+fn parse_rule(idx: usize, spec: RuleSpec) -> Result<AlertRule, String> {
+    let name = spec.name.unwrap_or_else(|| format!("alert-{}", idx + 1));
+    let mut conditions = Vec::new();
+    if let Some(rate) = spec.error_rate {
+        conditions.push(Condition::ErrorRate(parse_rate(&rate).ok_or_else(
+            || {
+                format!(
+                    "alert {name}: error_rate must be a percentage like 5% or a ratio like 0.05"
+                )
+            },
+        )?));
+    }
+    if let Some(p99) = spec.p99 {
+        let threshold = parse_duration(&p99)
+            .ok_or_else(|| format!("alert {name}: invalid p99 threshold '{p99}'"))?;
+        conditions.push(Condition::P99(duration_ms(threshold)));
+    }
+    if let Some(pattern) = spec.log.filter(|pattern| !pattern.is_empty()) {
+        conditions.push(Condition::LogContains(pattern));
+    }
+    if let Some(pattern) = spec.exception.filter(|pattern| !pattern.is_empty()) {
+        conditions.push(Condition::Exception(pattern));
+    }
+    let condition = match conditions.as_slice() {
+        [condition] => condition.clone(),
+        _ => {
+            return Err(format!(
+                "alert {name}: set exactly one of error_rate, p99, log or exception"
+            ))
+        }
+    };
+    if spec.from.is_some()
+        && matches!(
+            condition,
+            Condition::LogContains(_) | Condition::Exception(_)
+        )
+    {
+        return Err(format!("alert {name}: from only applies to traffic rules"));
+    }
+    let window_ms = match spec.window {
+        Some(window) => parse_duration(&window)
+            .map(duration_ms)
+            .filter(|window| *window > 0)
+            .ok_or_else(|| format!("alert {name}: invalid window '{window}'"))?,
+        None => DEFAULT_WINDOW_MS,
+    };
+    Ok(AlertRule {
+        name,
+        from: spec.from,
+        service: spec.service,
+        condition,
+        window_ms,
+        min_calls: spec.min_calls.unwrap_or(DEFAULT_MIN_CALLS),
+        notify: spec.notify,
+    })
+}
+
+fn log_matches(condition: &Condition, event: &LogEvent) -> bool {
+    match condition {
+        Condition::LogContains(pattern) => event.line.contains(pattern.as_str()),
+        Condition::Exception(pattern) => event
+            .exception
+            .as_deref()
+            .is_some_and(|class| glob_match(pattern, class)),
+        Condition::ErrorRate(_) | Condition::P99(_) => false,
+    }
+}
+
+fn log_message(event: &LogEvent) -> String {
+    let Some(class) = &event.exception else {
+        return format!("{}: {}", event.service, event.line.trim());
+    };
+    let first = event.line.trim().lines().next().unwrap_or_default();
+    if first.contains(class.as_str()) {
+        format!("{}: {first}", event.service)
+    } else {
+        format!("{}: {class}: {first}", event.service)
+    }
+}
+
+fn parse_rate(value: &Value) -> Option<f64> {
+    let rate = match value {
+        Value::Number(number) => number.as_f64()?,
+        Value::String(text) => {
+            let text = text.trim();
+            match text.strip_suffix('%') {
+                Some(percent) => percent.trim().parse::<f64>().ok()? / 100.0,
+                None => text.parse().ok()?,
+            }
+        }
+        _ => return None,
+    };
+    (0.0..=1.0).contains(&rate).then_some(rate)
+}
+
+fn duration_ms(duration: std::time::Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertState {
+    Firing,
+    Resolved,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AlertEvent {
+    pub rule: String,
+    pub state: AlertState,
+    pub message: String,
+    pub at_ms: u64,
+    #[serde(skip)]
+    pub notify: bool,
+}
+
+#[derive(Default)]
+struct RuleState {
+    window: VecDeque<(u64, u64, u64)>,
+    latest_p99: HashMap<u64, (u64, u64)>,
+    firing: bool,
+    last_fired_ms: Option<u64>,
+}
+
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    states: Vec<RuleState>,
+    edge_totals: HashMap<u64, (u64, u64)>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        let states = rules.iter().map(|_| RuleState::default()).collect();
+        Self {
+            rules,
+            states,
+            edge_totals: HashMap::new(),
+        }
+    }
+
+    pub fn observe_traffic(&mut self, update: &TrafficUpdate) -> Vec<AlertEvent> {
+        let edge = &update.edge.edge;
+        let at_ms = edge.last_seen_ms;
+        let (count, errors) = (edge.stats.count, edge.stats.errors);
+        let (previous_count, previous_errors) = self
+            .edge_totals
+            .insert(update.edge.id, (count, errors))
+            .unwrap_or_default();
+        let calls = count.saturating_sub(previous_count);
+        let failed = errors.saturating_sub(previous_errors);
+        for (rule, state) in self.rules.iter().zip(self.states.iter_mut()) {
+            if !rule.matches_edge(&edge.key) {
+                continue;
+            }
+            match (&rule.condition, edge.stats.p99_ms) {
+                (Condition::ErrorRate(_), _) => state.window.push_back((at_ms, calls, failed)),
+                (Condition::P99(_), Some(p99)) => {
+                    state.latest_p99.insert(update.edge.id, (at_ms, p99));
+                }
+                _ => {}
+            }
+        }
+        self.evaluate(at_ms)
+    }
+
+    pub fn observe_log(&mut self, event: &LogEvent, at_ms: u64) -> Vec<AlertEvent> {
+        if event.annotation {
+            return Vec::new();
+        }
+        let mut events = Vec::new();
+        for (rule, state) in self.rules.iter().zip(self.states.iter_mut()) {
+            if !rule.matches_service(&event.service) || !log_matches(&rule.condition, event) {
+                continue;
+            }
+            let cooling = state
+                .last_fired_ms
+                .is_some_and(|last| at_ms.saturating_sub(last) < rule.window_ms);
+            if cooling {
+                continue;
+            }
+            state.last_fired_ms = Some(at_ms);
+            events.push(AlertEvent {
+                rule: rule.name.clone(),
+                state: AlertState::Firing,
+                message: log_message(event),
+                at_ms,
+                notify: rule.notify,
+            });
+        }
+        events
+    }
+
+    pub fn evaluate(&mut self, now_ms: u64) -> Vec<AlertEvent> {
+        let mut events = Vec::new();
+        for (rule, state) in self.rules.iter().zip(self.states.iter_mut()) {
+            let since = now_ms.saturating_sub(rule.window_ms);
+            let breach = match rule.condition {
+                Condition::ErrorRate(threshold) => {
+                    error_rate_breach(state, since, threshold, rule.min_calls)
+                }
+                Condition::P99(threshold) => p99_breach(state, since, threshold),
+                Condition::LogContains(_) | Condition::Exception(_) => continue,
+            };
+            let firing = breach.is_some();
+            if firing == state.firing {
+                continue;
+            }
+            state.firing = firing;
+            events.push(AlertEvent {
+                rule: rule.name.clone(),
+                state: if firing {
+                    AlertState::Firing
+                } else {
+                    AlertState::Resolved
+                },
+                message: breach.unwrap_or_else(|| "back within threshold".to_string()),
+                at_ms: now_ms,
+                notify: rule.notify,
+            });
+        }
+        events
+    }
+}
+
+fn error_rate_breach(
+    state: &mut RuleState,
+    since: u64,
+    threshold: f64,
+    min_calls: u64,
+) -> Option<String> {
+    while state.window.front().is_some_and(|(at, _, _)| *at < since) {
+        state.window.pop_front();
+    }
+    let (calls, failed) = state
+        .window
+        .iter()
+        .fold((0, 0), |(calls, failed), (_, c, f)| (calls + c, failed + f));
+    if calls == 0 || calls < min_calls {
+        return None;
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let rate = failed as f64 / calls as f64;
+    (rate > threshold).then(|| {
+        format!(
+            "error rate {:.1}% ({failed} of {calls} calls) is above {:.1}%",
+            rate * 100.0,
+            threshold * 100.0
+        )
+    })
+}
+
+fn p99_breach(state: &mut RuleState, since: u64, threshold: u64) -> Option<String> {
+    state.latest_p99.retain(|_, (at, _)| *at >= since);
+    let worst = state.latest_p99.values().map(|(_, p99)| *p99).max()?;
+    (worst > threshold).then(|| format!("p99 latency {worst}ms is above {threshold}ms"))
+}
+
+struct AlertHubState {
+    engine: AlertEngine,
+    history: VecDeque<AlertEvent>,
+    clients: Vec<(usize, Sender<AlertEvent>)>,
+    next_client_id: usize,
+}
+
+pub struct AlertHub {
+    state: Mutex<AlertHubState>,
+}
+
+impl AlertHub {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            state: Mutex::new(AlertHubState {
+                engine: AlertEngine::new(rules),
+                history: VecDeque::new(),
+                clients: Vec::new(),
+                next_client_id: 1,
+            }),
+        }
+    }
+
+    pub fn register_client(&self) -> (Receiver<AlertEvent>, Vec<AlertEvent>) {
+        let (sender, receiver) = bounded(ALERT_CLIENT_QUEUE_SIZE);
+        let mut state = self.state();
+        let id = state.next_client_id;
+        state.next_client_id += 1;
+        state.clients.push((id, sender));
+        let history = state.history.iter().cloned().collect();
+        drop(state);
+        (receiver, history)
+    }
+
+    pub fn observe_traffic(&self, update: &TrafficUpdate) -> Vec<AlertEvent> {
+        let events = self.state().engine.observe_traffic(update);
+        self.publish(&events);
+        events
+    }
+
+    pub fn observe_log(&self, event: &LogEvent, at_ms: u64) -> Vec<AlertEvent> {
+        let events = self.state().engine.observe_log(event, at_ms);
+        self.publish(&events);
+        events
+    }
+
+    pub fn evaluate(&self, now_ms: u64) -> Vec<AlertEvent> {
+        let events = self.state().engine.evaluate(now_ms);
+        self.publish(&events);
+        events
+    }
+
+    pub fn raise(&self, event: AlertEvent) {
+        self.publish(&[event]);
+    }
+
+    fn publish(&self, events: &[AlertEvent]) {
+        if events.is_empty() {
+            return;
+        }
+        let mut state = self.state();
+        for event in events {
+            state.history.push_back(event.clone());
+            while state.history.len() > ALERT_HISTORY_LIMIT {
+                state.history.pop_front();
+            }
+        }
+        state.clients.retain(|(_, sender)| {
+            events.iter().all(|event| {
+                !matches!(
+                    sender.try_send(event.clone()),
+                    Err(TrySendError::Disconnected(_))
+                )
+            })
+        });
+    }
+
+    fn state(&self) -> MutexGuard<'_, AlertHubState> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,147 @@
+use super::alerts::{alert_rules_from_doc, AlertEngine, AlertState};
+use super::traffic::{IndexedEdge, TrafficUpdate};
+use crate::domain::traffic::{EdgeKey, EdgeStats, EntityId, TrafficEdge, Transport, Visibility};
+use crate::domain::{LogEvent, LogKind, OutputStream};
+
+fn engine(yaml: &str) -> AlertEngine {
+    let doc = serde_yaml::from_str(yaml).unwrap_or_default();
+    AlertEngine::new(alert_rules_from_doc(&doc).unwrap_or_default())
+}
+
+fn update(to: &str, count: u64, errors: u64, p99_ms: Option<u64>, at_ms: u64) -> TrafficUpdate {
+    let workload = |name: &str| EntityId::Workload {
+        name: name.to_string(),
+        instance: None,
+    };
+    TrafficUpdate {
+        revision: count,
+        created: false,
+        edge: IndexedEdge {
+            id: if to == "db" { 2 } else { 1 },
+            edge: TrafficEdge {
+                key: EdgeKey::Flow {
+                    from: workload("web"),
+                    to: workload(to),
+                    transport: Transport::Tcp,
+                    port: 80,
+                },
+                stats: EdgeStats {
+                    count,
+                    bytes_in: 0,
+                    bytes_out: 0,
+                    errors,
+                    p50_ms: None,
+                    p95_ms: None,
+                    p99_ms,
+                    visibility: Visibility::L7Envelope,
+                    networks: Vec::new(),
+                },
+                last_seen_ms: at_ms,
+            },
+        },
+    }
+}
+
+fn log(service: &str, line: &str) -> LogEvent {
+    LogEvent {
+        seq: 1,
+        service: service.to_string(),
+        container_ts: None,
+        ts_ms: None,
+        line: line.to_string(),
+        stream: OutputStream::Stderr,
+        repeat_count: None,
+        annotation: false,
+        instance: None,
+        kind: None,
+        exception: None,
+    }
+}
+
+#[test]
+fn error_rate_fires_and_resolves_over_the_window() {
+    let mut engine = engine(
+        "x-sanelens:\n  alerts:\n    - name: api-errors\n      service: api\n      error_rate: 5%\n",
+    );
+    assert!(engine
+        .observe_traffic(&update("api", 10, 0, None, 1_000))
+        .is_empty());
+    assert!(engine
+        .observe_traffic(&update("db", 20, 20, None, 1_000))
+        .is_empty());
+    let fired = engine.observe_traffic(&update("api", 20, 2, None, 2_000));
+    let states: Vec<(&str, AlertState)> = fired
+        .iter()
+        .map(|event| (event.rule.as_str(), event.state))
+        .collect();
+    assert_eq!(states, vec![("api-errors", AlertState::Firing)]);
+    assert!(engine.evaluate(30_000).is_empty());
+    let resolved = engine.evaluate(62_001);
+    assert_eq!(
+        resolved.first().map(|event| event.state),
+        Some(AlertState::Resolved)
+    );
+}
+
+#[test]
+fn p99_and_log_rules_fire() {
+    let mut engine = engine(
+        "x-sanelens:\n  alerts:\n    - name: slow\n      p99: 2s\n    - name: fatal\n      log: FATAL\n      window: 10s\n",
+    );
+    assert!(engine
+        .observe_traffic(&update("api", 5, 0, Some(900), 1_000))
+        .is_empty());
+    let slow = engine.observe_traffic(&update("api", 6, 0, Some(2_500), 2_000));
+    assert_eq!(slow.first().map(|event| event.rule.as_str()), Some("slow"));
+
+    assert!(engine
+        .observe_log(&log("api", "all good"), 3_000)
+        .is_empty());
+    let fatal = engine.observe_log(&log("api", "FATAL: out of memory"), 3_000);
+    assert_eq!(
+        fatal.first().map(|event| event.message.as_str()),
+        Some("api: FATAL: out of memory")
+    );
+    assert!(engine
+        .observe_log(&log("api", "FATAL again"), 5_000)
+        .is_empty());
+    assert_eq!(
+        engine.observe_log(&log("api", "FATAL again"), 14_000).len(),
+        1
+    );
+}
+
+#[test]
+fn exception_rules_match_the_tagged_class() {
+    let mut engine = engine(
+        "x-sanelens:\n  alerts:\n    - name: npe\n      service: api\n      exception: java.lang.*\n",
+    );
+    let mut crash = log(
+        "api",
+        "Exception in thread \"main\" java.lang.NullPointerException\n\tat App.main(App.java:3)",
+    );
+    assert!(engine.observe_log(&crash, 1_000).is_empty());
+    crash.kind = Some(LogKind::Error);
+    crash.exception = Some("java.lang.NullPointerException".to_string());
+    let fired = engine.observe_log(&crash, 1_000);
+    assert_eq!(
+        fired.first().map(|event| event.message.as_str()),
+        Some("api: Exception in thread \"main\" java.lang.NullPointerException")
+    );
+    crash.service = "worker".to_string();
+    assert!(engine.observe_log(&crash, 90_000).is_empty());
+}
+
+#[test]
+fn rejects_invalid_rules() {
+    let parse = |yaml: &str| {
+        let doc = serde_yaml::from_str(yaml).unwrap_or_default();
+        alert_rules_from_doc(&doc)
+    };
+    assert!(parse("services: {}\n").is_ok_and(|rules| rules.is_empty()));
+    assert!(parse("x-sanelens:\n  alerts:\n    - error_rate: 150%\n").is_err());
+    assert!(parse("x-sanelens:\n  alerts:\n    - error_rate: 5%\n      p99: 2s\n").is_err());
+    assert!(parse("x-sanelens:\n  alerts:\n    - log: FATAL\n      from: web\n").is_err());
+    assert!(parse("x-sanelens:\n  alerts:\n    - log: FATAL\n      exception: '*'\n").is_err());
+    assert!(parse("x-sanelens:\n  alerts:\n    - log: FATAL\n      sevrity: high\n").is_err());
+}
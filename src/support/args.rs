@@ -1,4 +1,5 @@
 use std::env;
+use std::time::Duration;
 
 use crate::domain::EngineKind;
 
@@ -152,6 +153,111 @@ pub fn take_flag(args: &[String], name: &str) -> (Vec<String>, bool) {
     (updated, enabled)
 }
 
+pub fn take_option(args: &[String], name: &str) -> (Vec<String>, Option<String>) {
+    let mut updated = Vec::with_capacity(args.len());
+    let mut value = None;
+    let prefix = format!("{name}=");
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            updated.push(arg.clone());
+            updated.extend(iter.cloned());
+            break;
+        }
+        if arg == name {
+            value = iter.next().cloned();
+            continue;
+        }
+        if let Some(rest) = arg.strip_prefix(&prefix) {
+            value = Some(rest.to_string());
+            continue;
+        }
+        updated.push(arg.clone());
+    }
+    (updated, value)
+}
+
+pub fn take_options(args: &[String], name: &str) -> (Vec<String>, Vec<String>) {
+    let (rest, values) = take_repeated_option(args, name);
+    let values = values
+        .iter()
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(ToString::to_string)
+        .collect();
+    (rest, values)
+}
+
+pub fn take_repeated_option(args: &[String], name: &str) -> (Vec<String>, Vec<String>) {
+    let mut rest = args.to_vec();
+    let mut values = Vec::new();
+    loop {
+        let (updated, value) = take_first_option(&rest, name);
+        rest = updated;
+        let Some(value) = value else {
+            break;
+        };
+        values.push(value);
+    }
+    (rest, values)
+}
+
+fn take_first_option(args: &[String], name: &str) -> (Vec<String>, Option<String>) {
+    let prefix = format!("{name}=");
+    let Some(idx) = args
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .position(|arg| arg == name || arg.starts_with(&prefix))
+    else {
+        return (args.to_vec(), None);
+    };
+    let mut rest = args.to_vec();
+    let arg = rest.remove(idx);
+    let value = match arg.strip_prefix(&prefix) {
+        Some(value) => Some(value.to_string()),
+        None if idx < rest.len() => Some(rest.remove(idx)),
+        None => None,
+    };
+    (rest, value)
+}
+
+pub fn parse_byte_size(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let split = value
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (digits, unit) = value.split_at(split);
+    let base: u64 = digits.parse().ok()?;
+    let multiplier = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1024,
+        "m" | "mb" | "mib" => 1024 * 1024,
+        "g" | "gb" | "gib" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    base.checked_mul(multiplier)
+}
+
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (digits, unit) = value.split_at(split);
+    let base: u64 = digits.parse().ok()?;
+    let millis = match unit.trim().to_ascii_lowercase().as_str() {
+        "ms" => 1,
+        "" | "s" => 1000,
+        "m" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        _ => return None,
+    };
+    base.checked_mul(millis).map(Duration::from_millis)
+}
+
+pub const STDIN_COMPOSE: &str = "-";
+
 pub fn extract_compose_file_arg(args: &[String]) -> Option<String> {
     let mut found = None;
     let mut iter = args.iter();
@@ -203,6 +309,32 @@ pub fn extract_compose_global_args(args: &[String]) -> Vec<String> {
     extracted
 }
 
+pub fn replace_compose_file_arg(args: &[String], from: &str, to: &str) -> Vec<String> {
+    let mut updated = Vec::with_capacity(args.len());
+    let mut file_value = false;
+    for (index, arg) in args.iter().enumerate() {
+        if arg == "--" {
+            updated.extend(args.iter().skip(index).cloned());
+            break;
+        }
+        let value = if file_value {
+            Some(arg.as_str())
+        } else {
+            arg.strip_prefix("--file=")
+                .or_else(|| arg.strip_prefix("-f="))
+        };
+        let replaced = match value {
+            Some(value) if value == from => {
+                format!("{}{to}", &arg[..arg.len() - value.len()])
+            }
+            _ => arg.clone(),
+        };
+        file_value = !file_value && (arg == "-f" || arg == "--file");
+        updated.push(replaced);
+    }
+    updated
+}
+
 pub fn strip_compose_file_args(args: &[String]) -> Vec<String> {
     let mut updated = Vec::with_capacity(args.len());
     let mut iter = args.iter();
@@ -249,7 +381,11 @@ pub fn is_env_false(name: &str) -> bool {
 }
 
 pub fn is_env_truthy(name: &str) -> bool {
-    env::var(name).is_ok_and(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+    env::var(name).is_ok_and(|value| is_truthy(&value))
+}
+
+pub fn is_truthy(value: &str) -> bool {
+    matches!(value.to_lowercase().as_str(), "1" | "true" | "yes")
 }
 
 fn parse_engine_kind(value: Option<&str>) -> Result<EngineKind, String> {
@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::traffic::percentile;
+
+pub const FAILED_STATUS: &str = "failed";
+
+#[derive(Clone, Copy, Debug)]
+pub struct BenchSample {
+    pub latency_ms: u64,
+    pub status: Option<u16>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BenchReport {
+    pub target: String,
+    pub requests: u64,
+    pub errors: u64,
+    pub duration_ms: u64,
+    pub achieved_rps: u64,
+    pub latency: Option<LatencySummary>,
+    pub statuses: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[allow(clippy::struct_field_names)]
+pub struct LatencySummary {
+    pub p50_ms: u64,
+    pub p90_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+}
+
+pub fn summarize(target: &str, samples: &[BenchSample], elapsed: Duration) -> BenchReport {
+    let mut statuses = BTreeMap::new();
+    let mut errors = 0;
+    for sample in samples {
+        let key = sample
+            .status
+            .map_or_else(|| FAILED_STATUS.to_string(), |status| status.to_string());
+        *statuses.entry(key).or_insert(0) += 1;
+        if sample.status.is_none_or(|status| status >= 500) {
+            errors += 1;
+        }
+    }
+    let mut latencies: Vec<u64> = samples.iter().map(|sample| sample.latency_ms).collect();
+    latencies.sort_unstable();
+    let latency = latencies.last().map(|max| LatencySummary {
+        p50_ms: percentile(&latencies, 50),
+        p90_ms: percentile(&latencies, 90),
+        p99_ms: percentile(&latencies, 99),
+        max_ms: *max,
+    });
+    let requests = u64::try_from(samples.len()).unwrap_or(u64::MAX);
+    let duration_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+    BenchReport {
+        target: target.to_string(),
+        requests,
+        errors,
+        duration_ms,
+        achieved_rps: requests.saturating_mul(1000) / duration_ms.max(1),
+        latency,
+        statuses,
+    }
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use super::bench::{summarize, BenchSample, LatencySummary, FAILED_STATUS};
+
+const fn sample(latency_ms: u64, status: Option<u16>) -> BenchSample {
+    BenchSample { latency_ms, status }
+}
+
+#[test]
+fn summarizes_latency_and_errors() {
+    let mut samples: Vec<BenchSample> = (1..=98).map(|ms| sample(ms, Some(200))).collect();
+    samples.push(sample(250, Some(503)));
+    samples.push(sample(400, None));
+    let report = summarize(
+        "http://localhost:8080/health",
+        &samples,
+        Duration::from_secs(2),
+    );
+    assert_eq!(report.requests, 100);
+    assert_eq!(report.errors, 2);
+    assert_eq!(report.achieved_rps, 50);
+    assert_eq!(
+        report.latency,
+        Some(LatencySummary {
+            p50_ms: 50,
+            p90_ms: 90,
+            p99_ms: 250,
+            max_ms: 400,
+        })
+    );
+    assert_eq!(report.statuses.get("200"), Some(&98));
+    assert_eq!(report.statuses.get(FAILED_STATUS), Some(&1));
+}
+
+#[test]
+fn empty_runs_have_no_latency() {
+    let report = summarize("http://localhost:8080/", &[], Duration::ZERO);
+    assert_eq!(report.requests, 0);
+    assert_eq!(report.achieved_rps, 0);
+    assert!(report.latency.is_none());
+}
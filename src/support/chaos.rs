@@ -0,0 +1,148 @@
+use std::collections::VecDeque;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use super::args::parse_duration;
+
+pub const CHAOS_TAG: &str = "chaos";
+const DEFAULT_ABORT_STATUS: u16 = 503;
+const FAULT_MARK_LIMIT: usize = 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChaosPlan {
+    pub latency: Option<Duration>,
+    pub latency_percent: u8,
+    pub abort_percent: u8,
+    pub abort_status: u16,
+}
+
+impl ChaosPlan {
+    pub const fn clear() -> Self {
+        Self {
+            latency: None,
+            latency_percent: 0,
+            abort_percent: 0,
+            abort_status: DEFAULT_ABORT_STATUS,
+        }
+    }
+
+    pub fn parse(
+        latency: Option<&str>,
+        latency_rate: Option<&str>,
+        abort_rate: Option<&str>,
+        abort_status: Option<&str>,
+    ) -> Result<Self, String> {
+        let latency = latency
+            .map(|value| {
+                parse_duration(value)
+                    .filter(|duration| !duration.is_zero())
+                    .ok_or_else(|| format!("Invalid --latency '{value}'. Use e.g. 300ms or 2s."))
+            })
+            .transpose()?;
+        let latency_percent = match (latency, latency_rate) {
+            (None, Some(_)) => return Err("--latency-rate needs --latency.".to_string()),
+            (None, None) => 0,
+            (Some(_), rate) => rate.map_or(Ok(100), |rate| percent("--latency-rate", rate))?,
+        };
+        let abort_percent = abort_rate.map_or(Ok(0), |rate| percent("--abort-rate", rate))?;
+        let abort_status = match abort_status {
+            None => DEFAULT_ABORT_STATUS,
+            Some(value) => value
+                .parse()
+                .ok()
+                .filter(|status| (200..600).contains(status))
+                .ok_or_else(|| {
+                    format!("Invalid --abort-status '{value}'. Use an HTTP status like 503.")
+                })?,
+        };
+        if latency_percent == 0 && abort_percent == 0 {
+            return Err("Nothing to inject. Pass --latency, --abort-rate or --clear.".to_string());
+        }
+        Ok(Self {
+            latency,
+            latency_percent,
+            abort_percent,
+            abort_status,
+        })
+    }
+
+    pub fn runtime_query(&self) -> String {
+        let latency_ms = self.latency.map_or(0, |latency| latency.as_millis());
+        format!(
+            "fault.http.delay.fixed_delay_percent={}&fault.http.delay.fixed_duration_ms={latency_ms}&fault.http.abort.abort_percent={}&fault.http.abort.http_status={}",
+            self.latency_percent, self.abort_percent, self.abort_status
+        )
+    }
+
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(latency) = self.latency.filter(|_| self.latency_percent > 0) {
+            parts.push(format!(
+                "+{}ms latency on {}% of requests",
+                latency.as_millis(),
+                self.latency_percent
+            ));
+        }
+        if self.abort_percent > 0 {
+            parts.push(format!(
+                "{}% aborted with {}",
+                self.abort_percent, self.abort_status
+            ));
+        }
+        if parts.is_empty() {
+            "no faults".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+fn percent(flag: &str, value: &str) -> Result<u8, String> {
+    let invalid = || format!("Invalid {flag} '{value}'. Use a fraction between 0 and 1, e.g. 0.1.");
+    let rate: f64 = value.trim().parse().map_err(|_| invalid())?;
+    if !(0.0..=1.0).contains(&rate) {
+        return Err(invalid());
+    }
+    format!("{:.0}", rate * 100.0)
+        .parse()
+        .map_err(|_| invalid())
+}
+
+pub fn fault_kind(flags: &str) -> Option<&'static str> {
+    let mut delay = false;
+    let mut abort = false;
+    for flag in flags.split(',').map(str::trim) {
+        delay |= flag == "DI";
+        abort |= flag == "FI";
+    }
+    match (delay, abort) {
+        (true, true) => Some("delay+abort"),
+        (true, false) => Some("delay"),
+        (false, true) => Some("abort"),
+        (false, false) => None,
+    }
+}
+
+#[derive(Default)]
+pub struct FaultMarks {
+    marks: Mutex<VecDeque<(String, &'static str)>>,
+}
+
+impl FaultMarks {
+    pub fn record(&self, request_id: &str, flags: &str) {
+        let Some(kind) = fault_kind(flags) else {
+            return;
+        };
+        let mut marks = self.marks.lock().unwrap_or_else(PoisonError::into_inner);
+        if marks.len() >= FAULT_MARK_LIMIT {
+            marks.pop_front();
+        }
+        marks.push_back((request_id.to_string(), kind));
+    }
+
+    pub fn take(&self, request_id: &str) -> Option<&'static str> {
+        let mut marks = self.marks.lock().unwrap_or_else(PoisonError::into_inner);
+        let index = marks.iter().position(|(id, _)| id == request_id)?;
+        marks.remove(index).map(|(_, kind)| kind)
+    }
+}
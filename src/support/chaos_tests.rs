@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use super::chaos::{fault_kind, ChaosPlan, FaultMarks};
+
+#[test]
+fn parses_fault_plans_into_envoy_runtime_keys() {
+    let plan = ChaosPlan::parse(Some("300ms"), None, Some("0.1"), None);
+    assert_eq!(
+        plan,
+        Ok(ChaosPlan {
+            latency: Some(Duration::from_millis(300)),
+            latency_percent: 100,
+            abort_percent: 10,
+            abort_status: 503,
+        })
+    );
+    assert_eq!(
+        plan.as_ref().map(ChaosPlan::runtime_query),
+        Ok("fault.http.delay.fixed_delay_percent=100&fault.http.delay.fixed_duration_ms=300&fault.http.abort.abort_percent=10&fault.http.abort.http_status=503".to_string())
+    );
+    assert_eq!(
+        plan.map(|plan| plan.describe()),
+        Ok("+300ms latency on 100% of requests, 10% aborted with 503".to_string())
+    );
+    assert_eq!(
+        ChaosPlan::parse(Some("2s"), Some("0.25"), None, Some("502")).map(|plan| (
+            plan.latency_percent,
+            plan.abort_percent,
+            plan.abort_status
+        )),
+        Ok((25, 0, 502))
+    );
+    assert_eq!(ChaosPlan::clear().describe(), "no faults");
+    assert!(ChaosPlan::clear()
+        .runtime_query()
+        .contains("fixed_delay_percent=0&fault.http.delay.fixed_duration_ms=0"));
+    assert!(ChaosPlan::parse(None, None, None, None).is_err());
+    assert!(ChaosPlan::parse(None, Some("0.5"), None, None).is_err());
+    assert!(ChaosPlan::parse(None, None, Some("1.5"), None).is_err());
+    assert!(ChaosPlan::parse(None, None, Some("0.1"), Some("99")).is_err());
+    assert!(ChaosPlan::parse(Some("0ms"), None, None, None).is_err());
+}
+
+#[test]
+fn maps_envoy_response_flags_to_fault_kinds() {
+    assert_eq!(fault_kind("DI"), Some("delay"));
+    assert_eq!(fault_kind("FI"), Some("abort"));
+    assert_eq!(fault_kind("DI,FI"), Some("delay+abort"));
+    assert_eq!(fault_kind("UF,URX"), None);
+    assert_eq!(fault_kind("-"), None);
+}
+
+#[test]
+fn fault_marks_hand_out_the_access_log_kind_once() {
+    let marks = FaultMarks::default();
+    marks.record("req-1", "FI");
+    marks.record("req-2", "-");
+    marks.record("req-3", "DI");
+    assert_eq!(marks.take("req-1"), Some("abort"));
+    assert_eq!(marks.take("req-1"), None);
+    assert_eq!(marks.take("req-2"), None);
+    assert_eq!(marks.take("req-3"), Some("delay"));
+}
@@ -0,0 +1,28 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct CleanupResult {
+    pub target: String,
+    pub removed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CleanupResult {
+    pub fn new(target: impl Into<String>, outcome: Result<(), String>) -> Self {
+        let error = outcome.err();
+        Self {
+            target: target.into(),
+            removed: error.is_none(),
+            error,
+        }
+    }
+
+    pub fn planned(target: impl Into<String>) -> Self {
+        Self {
+            target: target.into(),
+            removed: false,
+            error: None,
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use serde_json::json;
+
+use super::cleanup::CleanupResult;
+
+#[test]
+fn cleanup_results_report_failures_in_json() {
+    let encode = |result: &CleanupResult| serde_json::to_value(result).unwrap_or_default();
+    assert_eq!(
+        encode(&CleanupResult::new("run_a1b2c3", Ok(()))),
+        json!({ "target": "run_a1b2c3", "removed": true })
+    );
+    assert_eq!(
+        encode(&CleanupResult::new(
+            "run_a1b2c3",
+            Err("1 container(s) of run_a1b2c3 were not removed".to_string())
+        )),
+        json!({
+            "target": "run_a1b2c3",
+            "removed": false,
+            "error": "1 container(s) of run_a1b2c3 were not removed"
+        })
+    );
+    assert_eq!(
+        encode(&CleanupResult::planned("run_a1b2c3")),
+        json!({ "target": "run_a1b2c3", "removed": false })
+    );
+}
@@ -0,0 +1,380 @@
+use std::fmt::Write;
+
+use crate::support::constants::BIN_NAME;
+
+pub const COMPLETE_RUNS_COMMAND: &str = "__complete-runs";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "bash" => Ok(Self::Bash),
+            "zsh" => Ok(Self::Zsh),
+            "fish" => Ok(Self::Fish),
+            _ => Err(format!(
+                "Unsupported shell '{value}'. Use bash, zsh or fish."
+            )),
+        }
+    }
+}
+
+enum Positional {
+    None,
+    Run,
+    Choices(&'static [&'static str]),
+}
+
+struct CommandSpec {
+    name: &'static str,
+    flags: &'static [&'static str],
+    options: &'static [&'static str],
+    positional: Positional,
+}
+
+const fn command(
+    name: &'static str,
+    flags: &'static [&'static str],
+    options: &'static [&'static str],
+    positional: Positional,
+) -> CommandSpec {
+    CommandSpec {
+        name,
+        flags,
+        options,
+        positional,
+    }
+}
+
+const COMMANDS: &[CommandSpec] = &[
+    command(
+        "up",
+        &[
+            "-d",
+            "--detach",
+            "--build",
+            "--no-cache",
+            "--force-recreate",
+            "--abort-on-container-exit",
+            "--force-new",
+        ],
+        &[],
+        Positional::None,
+    ),
+    command("list", &[], &[], Positional::None),
+    command(
+        "logs",
+        &["--no-ui"],
+        &["--service", "--grep", "--level", "--since", "--stream"],
+        Positional::Run,
+    ),
+    command(
+        "traffic",
+        &["--json"],
+        &["--trace", "--network"],
+        Positional::Run,
+    ),
+    command("top", &[], &[], Positional::Run),
+    command("ps", &["--wide"], &[], Positional::Run),
+    command(
+        "bench",
+        &[],
+        &["--path", "--rps", "--concurrency", "--duration"],
+        Positional::Run,
+    ),
+    command(
+        "chaos",
+        &["--clear"],
+        &[
+            "--latency",
+            "--latency-rate",
+            "--abort-rate",
+            "--abort-status",
+        ],
+        Positional::Run,
+    ),
+    command("forward", &[], &[], Positional::Run),
+    command("run", &[], &["--image", "--name"], Positional::Run),
+    command("env", &[], &[], Positional::Run),
+    command("open", &["--print"], &[], Positional::Run),
+    command("inspect", &["--snapshot"], &[], Positional::Run),
+    command("mute", &[], &[], Positional::Run),
+    command("unmute", &[], &[], Positional::Run),
+    command("down", &["--all"], &[], Positional::Run),
+    command("stop", &[], &[], Positional::Run),
+    command(
+        "start",
+        &["-d", "--detach", "--no-ui"],
+        &["--service", "--grep", "--level", "--since", "--stream"],
+        Positional::Run,
+    ),
+    command("gc", &["--dry-run"], &["--ttl"], Positional::None),
+    command("replay", &[], &["--speed"], Positional::None),
+    command("lint", &[], &[], Positional::None),
+    command(
+        "derive",
+        &["--print", "--explain"],
+        &["--out"],
+        Positional::None,
+    ),
+    command("config", &[], &[], Positional::Choices(&["show"])),
+    command("ui", &[], &[], Positional::None),
+    command(
+        "completions",
+        &[],
+        &[],
+        Positional::Choices(&["bash", "zsh", "fish"]),
+    ),
+];
+
+const GLOBAL_FLAGS: &[&str] = &[
+    "--version",
+    "--allow-legacy-compose",
+    "--traffic",
+    "--no-traffic",
+    "--ui-port-auto-fallback",
+    "--log-gzip",
+    "--log-skew-correct",
+    "--skip-missing-secrets",
+    "--keep-volumes",
+];
+
+const GLOBAL_OPTIONS: &[&str] = &[
+    "-f",
+    "--file",
+    "-p",
+    "--project-name",
+    "--engine",
+    "--ui-bind",
+    "--ui-port",
+    "--ui-assets",
+    "--share",
+    "--run-dir",
+    "--log-dir",
+    "--log-sink",
+    "--log-max-size",
+    "--log-keep",
+    "--log-reorder",
+    "--log-dedup",
+    "--log-skew",
+    "--traffic-sample",
+    "--traffic-logs",
+    "--shutdown-order",
+    "--max-body-bytes",
+    "--output",
+    "--tag",
+    "--verify",
+    "--hook",
+    "--record",
+    "--set-env",
+    "--limit",
+    "--wait-for",
+    "--wait-timeout",
+    "--stall-timeout",
+    "--on-stall",
+    "--platform",
+];
+
+const BASH_TEMPLATE: &str = r#"_@BIN@() {
+    local cur prev cmd="" args=0 runs=0 cmd_flags="" cmd_words="" i
+    cur="${COMP_WORDS[COMP_CWORD]}"
+    prev="${COMP_WORDS[COMP_CWORD-1]}"
+    case "$prev" in
+        @VALUE_PATTERN@) return 0 ;;
+    esac
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        case "${COMP_WORDS[i]}" in
+            @VALUE_PATTERN@) ((i++)) ;;
+            -*) ;;
+            *) if [[ -z "$cmd" ]]; then cmd="${COMP_WORDS[i]}"; else ((args++)); fi ;;
+        esac
+    done
+    case "$cmd" in
+@CASES@
+    esac
+    if [[ "$cur" == -* ]]; then
+        COMPREPLY=($(compgen -W "@GLOBAL@ $cmd_flags" -- "$cur"))
+    elif [[ -z "$cmd" ]]; then
+        COMPREPLY=($(compgen -W "@COMMANDS@" -- "$cur"))
+    elif [[ $args -eq 0 && $runs -eq 1 ]]; then
+        COMPREPLY=($(compgen -W "$(@BIN@ @COMPLETE_RUNS@ 2>/dev/null)" -- "$cur"))
+    elif [[ $args -eq 0 && -n "$cmd_words" ]]; then
+        COMPREPLY=($(compgen -W "$cmd_words" -- "$cur"))
+    fi
+}
+
+complete -o default -F _@BIN@ @BIN@
+"#;
+
+const ZSH_TEMPLATE: &str = r#"#compdef @BIN@
+
+_@BIN@() {
+    local cmd="" args=0 runs=0 cmd_flags="" cmd_words="" i
+    case "${words[CURRENT-1]}" in
+        @VALUE_PATTERN@) _files; return ;;
+    esac
+    for ((i = 2; i < CURRENT; i++)); do
+        case "${words[i]}" in
+            @VALUE_PATTERN@) ((i++)) ;;
+            -*) ;;
+            *) if [[ -z "$cmd" ]]; then cmd="${words[i]}"; else ((args++)); fi ;;
+        esac
+    done
+    case "$cmd" in
+@CASES@
+    esac
+    if [[ "$PREFIX" == -* ]]; then
+        compadd -- @GLOBAL@ ${=cmd_flags}
+    elif [[ -z "$cmd" ]]; then
+        compadd -- @COMMANDS@
+    elif [[ $args -eq 0 && $runs -eq 1 ]]; then
+        compadd -- ${(f)"$(@BIN@ @COMPLETE_RUNS@ 2>/dev/null)"}
+    elif [[ $args -eq 0 && -n "$cmd_words" ]]; then
+        compadd -- ${=cmd_words}
+    else
+        _files
+    fi
+}
+
+if [[ "${funcstack[1]}" == "_@BIN@" ]]; then
+    _@BIN@ "$@"
+else
+    compdef _@BIN@ @BIN@
+fi
+"#;
+
+const FISH_TEMPLATE: &str = r"function __@BIN@_words
+    set -l tokens (commandline -opc)
+    set -e tokens[1]
+    set -l skip 0
+    for token in $tokens
+        if test $skip -eq 1
+            set skip 0
+        else if string match -q -- '-*' $token
+            contains -- $token @VALUE_LIST@; and set skip 1
+        else
+            echo $token
+        end
+    end
+end
+
+function __@BIN@_using
+    set -l words (__@BIN@_words)
+    set -q words[1]; and contains -- $words[1] $argv
+end
+
+function __@BIN@_positional
+    set -l words (__@BIN@_words)
+    test (count $words) -eq 1; and contains -- $words[1] $argv
+end
+
+complete -c @BIN@ -f -n 'test (count (__@BIN@_words)) -eq 0' -a '@COMMANDS@'
+complete -c @BIN@ -f -n '__@BIN@_positional @RUN_COMMANDS@' -a '(@BIN@ @COMPLETE_RUNS@ 2>/dev/null)'
+@LINES@";
+
+pub fn script(shell: Shell) -> String {
+    let template = match shell {
+        Shell::Bash => BASH_TEMPLATE,
+        Shell::Zsh => ZSH_TEMPLATE,
+        Shell::Fish => FISH_TEMPLATE,
+    };
+    let commands: Vec<&str> = COMMANDS.iter().map(|spec| spec.name).collect();
+    let run_commands: Vec<&str> = COMMANDS
+        .iter()
+        .filter(|spec| matches!(spec.positional, Positional::Run))
+        .map(|spec| spec.name)
+        .collect();
+    let global: Vec<&str> = GLOBAL_FLAGS.iter().chain(GLOBAL_OPTIONS).copied().collect();
+    let values = value_options();
+    template
+        .replace("@VALUE_PATTERN@", &values.join("|"))
+        .replace("@VALUE_LIST@", &values.join(" "))
+        .replace("@CASES@", &case_arms())
+        .replace("@LINES@", &fish_lines())
+        .replace("@GLOBAL@", &global.join(" "))
+        .replace("@COMMANDS@", &commands.join(" "))
+        .replace("@RUN_COMMANDS@", &run_commands.join(" "))
+        .replace("@COMPLETE_RUNS@", COMPLETE_RUNS_COMMAND)
+        .replace("@BIN@", BIN_NAME)
+}
+
+fn value_options() -> Vec<&'static str> {
+    let mut values: Vec<&str> = GLOBAL_OPTIONS.to_vec();
+    for option in COMMANDS.iter().flat_map(|spec| spec.options) {
+        if !values.contains(option) {
+            values.push(option);
+        }
+    }
+    values
+}
+
+fn case_arms() -> String {
+    let mut arms = String::new();
+    for spec in COMMANDS {
+        let flags: Vec<&str> = spec.flags.iter().chain(spec.options).copied().collect();
+        let _ = write!(
+            arms,
+            "        {}) cmd_flags=\"{}\";",
+            spec.name,
+            flags.join(" ")
+        );
+        match spec.positional {
+            Positional::None => {}
+            Positional::Run => arms.push_str(" runs=1;"),
+            Positional::Choices(words) => {
+                let _ = write!(arms, " cmd_words=\"{}\";", words.join(" "));
+            }
+        }
+        arms.push_str(" ;;\n");
+    }
+    arms.truncate(arms.trim_end().len());
+    arms
+}
+
+fn fish_lines() -> String {
+    let mut lines = Vec::new();
+    for flag in GLOBAL_FLAGS {
+        lines.push(fish_flag(None, flag, false));
+    }
+    for option in GLOBAL_OPTIONS {
+        lines.push(fish_flag(None, option, true));
+    }
+    for spec in COMMANDS {
+        if let Positional::Choices(words) = spec.positional {
+            lines.push(format!(
+                "complete -c {BIN_NAME} -f -n '__{BIN_NAME}_positional {}' -a '{}'",
+                spec.name,
+                words.join(" ")
+            ));
+        }
+        for flag in spec.flags {
+            lines.push(fish_flag(Some(spec.name), flag, false));
+        }
+        for option in spec.options {
+            lines.push(fish_flag(Some(spec.name), option, true));
+        }
+    }
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
+fn fish_flag(command: Option<&str>, flag: &str, takes_value: bool) -> String {
+    let mut line = format!("complete -c {BIN_NAME}");
+    if let Some(command) = command {
+        let _ = write!(line, " -n '__{BIN_NAME}_using {command}'");
+    }
+    let _ = match flag.strip_prefix("--") {
+        Some(long) => write!(line, " -l {long}"),
+        None => write!(line, " -s {}", flag.trim_start_matches('-')),
+    };
+    if takes_value {
+        line.push_str(" -r");
+    }
+    line
+}
@@ -0,0 +1,34 @@
+use super::completions::{script, Shell};
+
+#[test]
+fn parses_supported_shells() {
+    assert_eq!(Shell::parse("bash"), Ok(Shell::Bash));
+    assert_eq!(Shell::parse("ZSH"), Ok(Shell::Zsh));
+    assert_eq!(Shell::parse("fish"), Ok(Shell::Fish));
+    assert!(Shell::parse("tcsh").is_err());
+}
+
+#[test]
+fn scripts_cover_commands_flags_and_run_ids() {
+    let bash = script(Shell::Bash);
+    assert!(bash.contains("complete -o default -F _sanelens sanelens"));
+    assert!(bash.contains(
+        "logs) cmd_flags=\"--no-ui --service --grep --level --since --stream\"; runs=1; ;;"
+    ));
+    assert!(bash.contains("config) cmd_flags=\"\"; cmd_words=\"show\"; ;;"));
+    assert!(bash.contains("|--wait-for|"));
+    assert!(bash.contains("sanelens __complete-runs 2>/dev/null"));
+    assert!(!bash.contains('@'));
+
+    let zsh = script(Shell::Zsh);
+    assert!(zsh.starts_with("#compdef sanelens\n"));
+    assert!(zsh.contains("compadd -- up list logs traffic"));
+
+    let fish = script(Shell::Fish);
+    assert!(fish.contains("complete -c sanelens -l wait-for -r\n"));
+    assert!(fish.contains("complete -c sanelens -s f -r\n"));
+    assert!(fish.contains("complete -c sanelens -n '__sanelens_using logs' -l no-ui\n"));
+    assert!(fish.contains(
+        "'__sanelens_positional logs traffic top ps bench chaos forward run env open inspect mute unmute down stop start'"
+    ));
+}
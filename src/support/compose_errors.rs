@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::logging::strip_ansi_codes;
+
+const TAIL_BYTES: usize = 64 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailureKind {
+    PortInUse,
+    MissingImage,
+    InvalidMount,
+    OutOfSpace,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComposeFailure {
+    pub service: Option<String>,
+    pub kind: FailureKind,
+    pub subject: Option<String>,
+}
+
+impl ComposeFailure {
+    pub fn summary(&self) -> String {
+        let service = self.service.as_deref().unwrap_or("compose");
+        let subject = self.subject.as_deref();
+        let message = match (self.kind, subject) {
+            (FailureKind::PortInUse, Some(port)) => format!(
+                "host port {port} is already in use; stop whatever listens on it (`lsof -i :{port}`) or change the published port"
+            ),
+            (FailureKind::PortInUse, None) => "a published host port is already in use; stop whatever listens on it or change the published port".to_string(),
+            (FailureKind::MissingImage, Some(image)) => format!(
+                "image {image} could not be pulled; check the name and tag, log in to the registry, or build it locally"
+            ),
+            (FailureKind::MissingImage, None) => "an image could not be pulled; check the name and tag, log in to the registry, or build it locally".to_string(),
+            (FailureKind::InvalidMount, Some(path)) => format!(
+                "mount {path} is invalid; create the host path or fix the volume entry (relative paths resolve against the compose file)"
+            ),
+            (FailureKind::InvalidMount, None) => "a volume mount is invalid; create the host path or fix the volume entry".to_string(),
+            (FailureKind::OutOfSpace, _) => "the engine ran out of disk space; prune unused images and volumes (`docker system prune`) or grow the VM disk".to_string(),
+        };
+        format!("{service}: {message}")
+    }
+}
+
+pub fn diagnose(
+    lines: &[String],
+    project: &str,
+    aliases: &HashMap<String, String>,
+) -> Vec<ComposeFailure> {
+    let mut failures: Vec<ComposeFailure> = Vec::new();
+    let mut last_service = None;
+    for line in lines {
+        let mentioned = line_service(line, project, aliases);
+        if mentioned.is_some() {
+            last_service.clone_from(&mentioned);
+        }
+        let Some((kind, subject)) = classify(line) else {
+            continue;
+        };
+        let service = mentioned.or_else(|| last_service.clone());
+        if failures
+            .iter()
+            .any(|failure| failure.kind == kind && failure.service == service)
+        {
+            continue;
+        }
+        failures.push(ComposeFailure {
+            service,
+            kind,
+            subject,
+        });
+    }
+    failures
+}
+
+fn classify(line: &str) -> Option<(FailureKind, Option<String>)> {
+    let lower = line.to_ascii_lowercase();
+    if lower.contains("no space left on device") {
+        return Some((FailureKind::OutOfSpace, None));
+    }
+    if lower.contains("port is already allocated") || lower.contains("address already in use") {
+        return Some((FailureKind::PortInUse, host_port(line)));
+    }
+    missing_image(line, &lower).or_else(|| invalid_mount(line, &lower))
+}
+
+fn host_port(line: &str) -> Option<String> {
+    line.split_whitespace().find_map(|token| {
+        let token = token.trim_end_matches([':', ',', ')']);
+        let (_, port) = token.rsplit_once(':')?;
+        (!port.is_empty() && port.bytes().all(|byte| byte.is_ascii_digit()))
+            .then(|| port.to_string())
+    })
+}
+
+fn missing_image(line: &str, lower: &str) -> Option<(FailureKind, Option<String>)> {
+    let after = |marker: &str| {
+        lower.find(marker).map(|index| {
+            line.get(index + marker.len()..)
+                .and_then(|rest| rest.split([' ', ',']).find(|token| !token.is_empty()))
+                .map(|image| image.trim_end_matches(':').to_string())
+        })
+    };
+    after("pull access denied for ")
+        .or_else(|| after("no such image: "))
+        .or_else(|| {
+            (lower.contains("manifest unknown") || lower.contains("not found"))
+                .then(|| after("manifest for ").or_else(|| after("docker://")))
+                .flatten()
+        })
+        .or_else(|| {
+            (lower.contains("manifest unknown") || lower.contains("repository does not exist"))
+                .then_some(None)
+        })
+        .map(|image| (FailureKind::MissingImage, image))
+}
+
+fn invalid_mount(line: &str, lower: &str) -> Option<(FailureKind, Option<String>)> {
+    let after = |marker: &str| {
+        lower.find(marker).map(|index| {
+            line.get(index + marker.len()..)
+                .map(|rest| rest.trim().trim_matches(['\'', '"']).to_string())
+                .filter(|rest| !rest.is_empty())
+        })
+    };
+    after("bind source path does not exist: ")
+        .or_else(|| after("invalid volume specification: "))
+        .or_else(|| {
+            (lower.contains("invalid mount config")
+                || lower.contains("error mounting")
+                || lower.contains("are you trying to mount a directory onto a file"))
+            .then(|| mount_source(line))
+        })
+        .map(|path| (FailureKind::InvalidMount, path))
+}
+
+fn mount_source(line: &str) -> Option<String> {
+    line.split_whitespace()
+        .map(|token| token.trim_matches(['"', '\'', ',', ':']))
+        .find(|token| token.starts_with('/') && token.len() > 1)
+        .map(str::to_string)
+}
+
+fn line_service(line: &str, project: &str, aliases: &HashMap<String, String>) -> Option<String> {
+    let resolve = |name: &str| {
+        aliases
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    };
+    let container = line
+        .split(|ch: char| ch.is_whitespace() || matches!(ch, '"' | '\'' | '(' | ')' | ','))
+        .find_map(|token| {
+            let rest = token
+                .strip_prefix(project)?
+                .strip_prefix(['-', '_'])?
+                .trim_end_matches(':');
+            let (name, index) = rest.rsplit_once(['-', '_'])?;
+            (!name.is_empty()
+                && !index.is_empty()
+                && index.bytes().all(|byte| byte.is_ascii_digit()))
+            .then(|| resolve(name))
+        });
+    container.or_else(|| {
+        let (_, rest) = line.split_once("service \"")?;
+        let (name, _) = rest.split_once('"')?;
+        Some(resolve(name))
+    })
+}
+
+pub struct OutputTail {
+    buffer: Mutex<Vec<u8>>,
+}
+
+impl OutputTail {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            buffer: Mutex::new(Vec::new()),
+        })
+    }
+
+    pub fn record(&self, bytes: &[u8]) {
+        let mut buffer = lock(&self.buffer);
+        buffer.extend_from_slice(bytes);
+        let excess = buffer.len().saturating_sub(TAIL_BYTES);
+        if excess > 0 {
+            buffer.drain(..excess);
+        }
+    }
+
+    pub fn clear(&self) {
+        lock(&self.buffer).clear();
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let buffer = lock(&self.buffer);
+        strip_ansi_codes(&buffer)
+            .split(['\n', '\r'])
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+pub struct TeeReader<R> {
+    inner: R,
+    tail: Arc<OutputTail>,
+}
+
+impl<R> TeeReader<R> {
+    pub const fn new(inner: R, tail: Arc<OutputTail>) -> Self {
+        Self { inner, tail }
+    }
+}
+
+impl<R: Read> Read for TeeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if let Some(bytes) = buf.get(..read) {
+            self.tail.record(bytes);
+        }
+        Ok(read)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use std::io::Read;
+
+use super::compose_errors::{diagnose, ComposeFailure, FailureKind, OutputTail, TeeReader};
+
+fn lines(text: &str) -> Vec<String> {
+    text.lines().map(str::to_string).collect()
+}
+
+#[test]
+fn detects_failure_signatures_with_the_failing_service() {
+    let aliases = HashMap::from([("api-app".to_string(), "api".to_string())]);
+    let output = lines(
+        " ✔ Container shop_r1-db-1  Started\n\
+         Error response from daemon: driver failed programming external connectivity on endpoint shop_r1-web-1 (3f2a): Bind for 0.0.0.0:8080 failed: port is already allocated\n\
+         ✘ Container shop_r1-api-app-1  Error\n\
+         Error response from daemon: invalid mount config for type \"bind\": bind source path does not exist: /home/dev/shop/certs\n\
+         Error response from daemon: pull access denied for acme/worker, repository does not exist or may require 'docker login'\n\
+         Error: writing blob: storing blob to file \"/var/tmp/storage\": write /var/tmp/storage: no space left on device",
+    );
+    let failures = diagnose(&output, "shop_r1", &aliases);
+    assert_eq!(
+        failures,
+        vec![
+            ComposeFailure {
+                service: Some("web".to_string()),
+                kind: FailureKind::PortInUse,
+                subject: Some("8080".to_string()),
+            },
+            ComposeFailure {
+                service: Some("api".to_string()),
+                kind: FailureKind::InvalidMount,
+                subject: Some("/home/dev/shop/certs".to_string()),
+            },
+            ComposeFailure {
+                service: Some("api".to_string()),
+                kind: FailureKind::MissingImage,
+                subject: Some("acme/worker".to_string()),
+            },
+            ComposeFailure {
+                service: Some("api".to_string()),
+                kind: FailureKind::OutOfSpace,
+                subject: None,
+            },
+        ]
+    );
+    assert!(failures.first().is_some_and(|failure| failure
+        .summary()
+        .starts_with("web: host port 8080 is already in use")));
+
+    let podman = lines(
+        "Error: rootlessport listen tcp 0.0.0.0:5432: bind: address already in use\n\
+         Error: initializing source docker://ghcr.io/acme/missing:v2: reading manifest v2 in ghcr.io/acme/missing: manifest unknown",
+    );
+    assert_eq!(
+        diagnose(&podman, "shop_r1", &aliases)
+            .iter()
+            .map(ComposeFailure::summary)
+            .collect::<Vec<_>>(),
+        vec![
+            "compose: host port 5432 is already in use; stop whatever listens on it (`lsof -i :5432`) or change the published port".to_string(),
+            "compose: image ghcr.io/acme/missing:v2 could not be pulled; check the name and tag, log in to the registry, or build it locally".to_string(),
+        ]
+    );
+    assert!(diagnose(
+        &lines("network shop_r1_default created\nsome other error"),
+        "shop_r1",
+        &aliases
+    )
+    .is_empty());
+}
+
+#[test]
+fn tee_reader_keeps_a_clean_tail_of_the_stream() {
+    let tail = OutputTail::new();
+    let mut reader = TeeReader::new(
+        "\u{1b}[31mError\u{1b}[0m: no space left on device\r\n ⠿ pulling\n".as_bytes(),
+        tail.clone(),
+    );
+    let mut copied = String::new();
+    assert!(reader.read_to_string(&mut copied).is_ok());
+    assert!(copied.contains("\u{1b}[31m"));
+    assert_eq!(
+        tail.lines(),
+        vec![
+            "Error: no space left on device".to_string(),
+            "⠿ pulling".to_string()
+        ]
+    );
+    tail.record(&vec![b'x'; 70 * 1024]);
+    assert!(tail.lines().iter().all(|line| !line.contains("Error")));
+    tail.clear();
+    assert!(tail.lines().is_empty());
+}
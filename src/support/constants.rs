@@ -7,8 +7,22 @@ pub const PROJECT_PREFIX: &str = "sanelens_";
 pub const RUN_ID_LABEL: &str = "sanelens.run_id";
 pub const SERVICE_LABEL: &str = "sanelens.service";
 pub const PROXY_LABEL: &str = "sanelens.proxy";
+pub const PROXY_NAME_LABEL: &str = "sanelens.proxy.name";
 pub const PROXY_EGRESS_LABEL: &str = "sanelens.proxy.egress";
+pub const PROXY_PROTOCOLS_LABEL: &str = "sanelens.proxy.protocols";
+pub const PROXY_PORTS_LABEL: &str = "sanelens.proxy.ports";
+pub const UPSTREAM_LABEL: &str = "sanelens.upstream";
+pub const ENVOY_ADMIN_PORT: u16 = 9901;
+pub const ENVOY_CONFIG_LABEL: &str = "sanelens.envoy.config";
 pub const COMPOSE_FILE_LABEL: &str = "sanelens.compose_file";
 pub const DERIVED_COMPOSE_LABEL: &str = "sanelens.derived_compose";
 pub const STARTED_AT_LABEL: &str = "sanelens.started_at";
 pub const PROJECT_NAME_LABEL: &str = "sanelens.project_name";
+pub const TAG_LABEL: &str = "sanelens.tag";
+pub const MAX_BODY_BYTES_LABEL: &str = "sanelens.max_body_bytes";
+pub const TRAFFIC_IGNORE_LABEL: &str = "sanelens.traffic.ignore";
+pub const ONEOFF_LABEL: &str = "sanelens.oneoff";
+pub const DEFAULT_MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+pub const KEEP_VOLUMES_ENV: &str = "SANELENS_KEEP_VOLUMES";
+pub const ROOTLESS_COMPAT_ENV: &str = "SANELENS_ROOTLESS_COMPAT";
+pub const UI_FILE: &str = "ui.json";
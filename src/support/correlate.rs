@@ -0,0 +1,118 @@
+use serde::Serialize;
+
+use crate::domain::traffic::TrafficCall;
+use crate::domain::{split_identity, LogEvent};
+
+pub const MIN_CALL_SLACK_MS: i64 = 1_000;
+const REQUEST_ID_KEYS: &[&str] = &[
+    "requestid",
+    "reqid",
+    "xrequestid",
+    "correlationid",
+    "traceid",
+];
+
+#[derive(Clone, Serialize)]
+pub struct CorrelatedLog {
+    #[serde(flatten)]
+    pub event: LogEvent,
+    pub matched: bool,
+}
+
+#[derive(Clone, Serialize)]
+pub struct CallLogs {
+    pub call: u64,
+    pub service: String,
+    pub request_id: Option<String>,
+    pub from_ms: i64,
+    pub to_ms: i64,
+    pub logs: Vec<CorrelatedLog>,
+}
+
+pub fn request_ids(line: &str) -> Vec<String> {
+    let trimmed = line.trim();
+    if trimmed.starts_with('{') {
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(trimmed) {
+            let mut ids = Vec::new();
+            collect_json_ids(&value, &mut ids);
+            return ids;
+        }
+    }
+    trimmed
+        .split_whitespace()
+        .filter_map(|pair| pair.split_once('='))
+        .filter(|(key, _)| is_request_id_key(key))
+        .map(|(_, value)| value.trim_matches(['"', '\'', ',']).to_string())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+fn collect_json_ids(value: &serde_json::Value, ids: &mut Vec<String>) {
+    let Some(object) = value.as_object() else {
+        return;
+    };
+    for (key, value) in object {
+        match value {
+            serde_json::Value::Object(_) => collect_json_ids(value, ids),
+            serde_json::Value::String(id) if is_request_id_key(key) && !id.is_empty() => {
+                ids.push(id.clone());
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_request_id_key(key: &str) -> bool {
+    let normalized: String = key
+        .trim_matches('"')
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|ch| ch.to_ascii_lowercase())
+        .collect();
+    REQUEST_ID_KEYS.contains(&normalized.as_str())
+}
+
+pub fn call_window(call: &TrafficCall) -> (i64, i64) {
+    let duration = i64::try_from(call.duration_ms.unwrap_or(0)).unwrap_or(i64::MAX);
+    let slack = duration.max(MIN_CALL_SLACK_MS);
+    let start = i64::try_from(call.at_ms).unwrap_or(i64::MAX);
+    (
+        start.saturating_sub(slack),
+        start.saturating_add(duration).saturating_add(slack),
+    )
+}
+
+pub fn correlate_call_logs(call: &TrafficCall, events: &[LogEvent]) -> Option<CallLogs> {
+    let service = call.peer.dst.as_ref()?.workload_name()?.to_string();
+    let request_id = call.correlation.request_id.clone();
+    let wanted: Vec<&String> = [&call.correlation.request_id, &call.correlation.trace_id]
+        .into_iter()
+        .flatten()
+        .collect();
+    let (from_ms, to_ms) = call_window(call);
+    let logs = events
+        .iter()
+        .filter(|event| !event.annotation && split_identity(&event.service).0 == service)
+        .filter(|event| {
+            event
+                .ts_ms
+                .is_some_and(|ts| (from_ms..=to_ms).contains(&ts))
+        })
+        .filter_map(|event| {
+            let ids = request_ids(&event.line);
+            let matched = ids.iter().any(|id| wanted.contains(&id));
+            (ids.is_empty() || matched || wanted.is_empty()).then(|| CorrelatedLog {
+                event: event.clone(),
+                matched,
+            })
+        })
+        .collect();
+    Some(CallLogs {
+        call: call.seq,
+        service,
+        request_id,
+        from_ms,
+        to_ms,
+        logs,
+    })
+}
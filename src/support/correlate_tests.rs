@@ -0,0 +1,81 @@
+use super::correlate::{correlate_call_logs, request_ids};
+use super::traffic_fixtures::{http_call, workload};
+use crate::domain::traffic::{Correlation, Peer, TrafficCall};
+use crate::domain::{LogEvent, OutputStream};
+
+fn call() -> TrafficCall {
+    TrafficCall {
+        seq: 7,
+        at_ms: 10_000,
+        peer: Peer {
+            src: None,
+            dst: Some(workload("api")),
+            raw: None,
+        },
+        path: Some("/orders".to_string()),
+        status: Some(500),
+        duration_ms: Some(2_000),
+        correlation: Correlation {
+            request_id: Some("req-1".to_string()),
+            trace_id: None,
+            span_id: None,
+        },
+        ..http_call()
+    }
+}
+
+fn event(service: &str, ts_ms: i64, line: &str) -> LogEvent {
+    LogEvent {
+        seq: 0,
+        service: service.to_string(),
+        container_ts: None,
+        ts_ms: Some(ts_ms),
+        line: line.to_string(),
+        stream: OutputStream::Stdout,
+        repeat_count: None,
+        annotation: false,
+        instance: None,
+        kind: None,
+        exception: None,
+    }
+}
+
+#[test]
+fn extracts_request_ids_from_structured_lines() {
+    assert_eq!(
+        request_ids(r#"{"level":"error","ctx":{"requestId":"req-1"},"msg":"boom"}"#),
+        vec!["req-1".to_string()]
+    );
+    assert_eq!(
+        request_ids(r#"level=info request_id="req-2" msg=ok"#),
+        vec!["req-2".to_string()]
+    );
+    assert!(request_ids("plain text line").is_empty());
+}
+
+#[test]
+fn keeps_target_lines_within_the_call_window() {
+    let events = vec![
+        event("api", 9_000, r#"{"request_id":"req-1","msg":"failed"}"#),
+        event("api[2]", 12_500, "panic: nil pointer"),
+        event("api", 11_000, r#"{"request_id":"req-9","msg":"other"}"#),
+        event("api", 20_000, "too late"),
+        event("db", 10_500, "unrelated service"),
+    ];
+    let logs = correlate_call_logs(&call(), &events);
+    let lines: Vec<(String, bool)> = logs
+        .map(|logs| {
+            logs.logs
+                .into_iter()
+                .map(|log| (log.event.line, log.matched))
+                .collect()
+        })
+        .unwrap_or_default();
+    assert_eq!(
+        lines,
+        vec![
+            (r#"{"request_id":"req-1","msg":"failed"}"#.to_string(), true),
+            ("panic: nil pointer".to_string(), false),
+        ]
+    );
+}
@@ -0,0 +1,151 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use time::OffsetDateTime;
+
+use crate::support::args::parse_duration;
+use crate::support::inspect::INSPECT_DIR;
+
+pub const CRASH_DIR: &str = "crash";
+pub const CRASH_LOG_LINES: usize = 500;
+const KEPT_DIRS: &[&str] = &[CRASH_DIR, INSPECT_DIR];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CrashLoopPolicy {
+    pub restarts: u64,
+    pub window: Duration,
+}
+
+impl CrashLoopPolicy {
+    pub fn parse(value: &str) -> Result<Option<Self>, String> {
+        let value = value.trim();
+        if matches!(
+            value.to_ascii_lowercase().as_str(),
+            "" | "0" | "off" | "false" | "no"
+        ) {
+            return Ok(None);
+        }
+        let invalid = || format!("Invalid crash-loop policy '{value}'. Use e.g. 3/5m or off.");
+        let (restarts, window) = value.split_once('/').ok_or_else(invalid)?;
+        let restarts = restarts
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .filter(|restarts| *restarts > 0)
+            .ok_or_else(invalid)?;
+        let window = parse_duration(window)
+            .filter(|window| !window.is_zero())
+            .ok_or_else(invalid)?;
+        Ok(Some(Self { restarts, window }))
+    }
+
+    pub fn window_ms(&self) -> u64 {
+        u64::try_from(self.window.as_millis()).unwrap_or(u64::MAX)
+    }
+}
+
+pub struct CrashLoopDetector {
+    policy: CrashLoopPolicy,
+    restart_counts: HashMap<String, u64>,
+    restarts: HashMap<String, VecDeque<u64>>,
+    fired_at: HashMap<String, u64>,
+}
+
+impl CrashLoopDetector {
+    pub fn new(policy: CrashLoopPolicy) -> Self {
+        Self {
+            policy,
+            restart_counts: HashMap::new(),
+            restarts: HashMap::new(),
+            fired_at: HashMap::new(),
+        }
+    }
+
+    pub fn observe(
+        &mut self,
+        service: &str,
+        container: &str,
+        restart_count: u64,
+        now_ms: u64,
+    ) -> Option<u64> {
+        let previous = self
+            .restart_counts
+            .insert(container.to_string(), restart_count)
+            .unwrap_or(restart_count);
+        let window_ms = self.policy.window_ms();
+        let times = self.restarts.entry(service.to_string()).or_default();
+        for _ in 0..restart_count.saturating_sub(previous) {
+            times.push_back(now_ms);
+        }
+        while times
+            .front()
+            .is_some_and(|at| now_ms.saturating_sub(*at) > window_ms)
+        {
+            times.pop_front();
+        }
+        let count = u64::try_from(times.len()).unwrap_or(u64::MAX);
+        if count < self.policy.restarts {
+            return None;
+        }
+        let cooling = self
+            .fired_at
+            .get(service)
+            .is_some_and(|at| now_ms.saturating_sub(*at) < window_ms);
+        if cooling {
+            return None;
+        }
+        self.fired_at.insert(service.to_string(), now_ms);
+        Some(count)
+    }
+}
+
+pub fn crash_dir_name(service: &str, at_ms: u64) -> String {
+    let nanos = i128::from(at_ms).saturating_mul(1_000_000);
+    let stamp = OffsetDateTime::from_unix_timestamp_nanos(nanos).map_or_else(
+        |_| at_ms.to_string(),
+        |at| {
+            format!(
+                "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+                at.year(),
+                u8::from(at.month()),
+                at.day(),
+                at.hour(),
+                at.minute(),
+                at.second()
+            )
+        },
+    );
+    let service: String = service
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{service}-{stamp}")
+}
+
+pub fn remove_run_dir(dir: &Path) -> io::Result<bool> {
+    if !KEPT_DIRS.iter().any(|name| dir.join(name).is_dir()) {
+        fs::remove_dir_all(dir)?;
+        return Ok(false);
+    }
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if KEPT_DIRS.iter().any(|name| entry.file_name() == *name) {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            fs::remove_dir_all(entry.path())?;
+        } else {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(dir.join(CRASH_DIR).is_dir())
+}
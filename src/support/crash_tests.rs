@@ -0,0 +1,60 @@
+use std::fs;
+use std::time::Duration;
+
+use super::crash::{crash_dir_name, remove_run_dir, CrashLoopDetector, CrashLoopPolicy, CRASH_DIR};
+
+#[test]
+fn parses_policies() {
+    assert_eq!(
+        CrashLoopPolicy::parse("3/5m"),
+        Ok(Some(CrashLoopPolicy {
+            restarts: 3,
+            window: Duration::from_mins(5),
+        }))
+    );
+    assert_eq!(CrashLoopPolicy::parse("off"), Ok(None));
+    assert!(CrashLoopPolicy::parse("3").is_err());
+    assert!(CrashLoopPolicy::parse("0/5m").is_err());
+}
+
+#[test]
+fn fires_once_per_window_when_restarts_pile_up() {
+    let mut detector = CrashLoopDetector::new(CrashLoopPolicy {
+        restarts: 3,
+        window: Duration::from_mins(1),
+    });
+    assert_eq!(detector.observe("api", "c1", 0, 0), None);
+    assert_eq!(detector.observe("api", "c1", 1, 5_000), None);
+    assert_eq!(detector.observe("api", "c1", 2, 10_000), None);
+    assert_eq!(detector.observe("api", "c1", 3, 20_000), Some(3));
+    assert_eq!(detector.observe("api", "c1", 4, 30_000), None);
+    assert_eq!(detector.observe("api", "c1", 4, 200_000), None);
+    assert_eq!(detector.observe("db", "c2", 0, 0), None);
+}
+
+#[test]
+fn first_sighting_sets_the_restart_baseline() {
+    let mut detector = CrashLoopDetector::new(CrashLoopPolicy {
+        restarts: 2,
+        window: Duration::from_mins(1),
+    });
+    assert_eq!(detector.observe("api", "c1", 7, 0), None);
+    assert_eq!(detector.observe("api", "c1", 8, 1_000), None);
+    assert_eq!(detector.observe("api", "c1", 9, 2_000), Some(2));
+}
+
+#[test]
+fn keeps_crash_evidence_when_removing_run_dir() {
+    assert_eq!(crash_dir_name("api/web", 0), "api_web-19700101T000000Z");
+    let dir = std::env::temp_dir().join(format!("sanelens-crash-{}", std::process::id()));
+    let evidence = dir.join(CRASH_DIR).join("api-19700101T000000Z");
+    assert!(fs::create_dir_all(&evidence).is_ok());
+    assert!(fs::write(dir.join("compose.yaml"), "services: {}").is_ok());
+    let kept = remove_run_dir(&dir);
+    let compose_gone = !dir.join("compose.yaml").exists();
+    let evidence_kept = evidence.is_dir();
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(kept.ok(), Some(true));
+    assert!(compose_gone);
+    assert!(evidence_kept);
+}
@@ -0,0 +1,446 @@
+use super::text::truncate;
+
+pub const MAX_STATEMENT_CHARS: usize = 256;
+const POSTGRES_PROTOCOL_V3: u32 = 196_608;
+const POSTGRES_SSL_REQUEST: u32 = 80_877_103;
+const POSTGRES_GSSENC_REQUEST: u32 = 80_877_104;
+const MYSQL_CLIENT_PROTOCOL_41: u32 = 0x0200;
+const MYSQL_CLIENT_SSL: u32 = 0x0800;
+const MYSQL_COM_QUERY: u8 = 0x03;
+const MYSQL_COM_STMT_PREPARE: u8 = 0x16;
+const REDIS_SECRET_COMMANDS: &[&str] = &["AUTH", "HELLO", "MIGRATE"];
+const DETECT_LIMIT: usize = 64 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DbProtocol {
+    Postgres,
+    Redis,
+    Mysql,
+}
+
+impl DbProtocol {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Postgres => "postgres",
+            Self::Redis => "redis",
+            Self::Mysql => "mysql",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DbStatement {
+    pub offset: usize,
+    pub command: String,
+    pub statement: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DecoderState {
+    Detecting,
+    Decoding(DbProtocol),
+    Opaque,
+}
+
+pub struct DbStreamDecoder {
+    port: u16,
+    state: DecoderState,
+    buffer: Vec<u8>,
+    base: usize,
+}
+
+impl DbStreamDecoder {
+    pub const fn new(port: u16) -> Self {
+        Self {
+            port,
+            state: DecoderState::Detecting,
+            buffer: Vec::new(),
+            base: 0,
+        }
+    }
+
+    pub const fn protocol(&self) -> Option<DbProtocol> {
+        match self.state {
+            DecoderState::Decoding(protocol) => Some(protocol),
+            DecoderState::Detecting | DecoderState::Opaque => None,
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<DbStatement> {
+        if self.state == DecoderState::Opaque {
+            return Vec::new();
+        }
+        self.buffer.extend_from_slice(bytes);
+        let decoded = match self.state {
+            DecoderState::Decoding(protocol) => decode_messages(protocol, &self.buffer),
+            DecoderState::Detecting | DecoderState::Opaque => {
+                let Some(decoded) = self.detect() else {
+                    return Vec::new();
+                };
+                decoded
+            }
+        };
+        let base = self.base;
+        let consumed = if self.state == DecoderState::Opaque {
+            self.buffer.len()
+        } else {
+            decoded.next.min(self.buffer.len())
+        };
+        self.buffer.drain(..consumed);
+        self.base += consumed;
+        decoded
+            .statements
+            .into_iter()
+            .map(|statement| DbStatement {
+                offset: base + statement.offset,
+                ..statement
+            })
+            .collect()
+    }
+
+    fn detect(&mut self) -> Option<Decoded> {
+        let Some((protocol, decoded)) = detect(&self.buffer, self.port) else {
+            if self.buffer.len() > DETECT_LIMIT {
+                self.state = DecoderState::Opaque;
+                self.buffer = Vec::new();
+            }
+            return None;
+        };
+        self.state = if decoded.encrypted {
+            DecoderState::Opaque
+        } else {
+            DecoderState::Decoding(protocol)
+        };
+        Some(decoded)
+    }
+}
+
+struct Decoded {
+    statements: Vec<DbStatement>,
+    next: usize,
+    encrypted: bool,
+}
+
+impl Decoded {
+    const fn encrypted(next: usize) -> Self {
+        Self {
+            statements: Vec::new(),
+            next,
+            encrypted: true,
+        }
+    }
+}
+
+fn detect(data: &[u8], port: u16) -> Option<(DbProtocol, Decoded)> {
+    if let Some(decoded) = decode_postgres(data) {
+        return Some((DbProtocol::Postgres, decoded));
+    }
+    if let Some(decoded) = decode_redis(data, port == 6379) {
+        return Some((DbProtocol::Redis, decoded));
+    }
+    decode_mysql(data).map(|decoded| (DbProtocol::Mysql, decoded))
+}
+
+fn decode_messages(protocol: DbProtocol, data: &[u8]) -> Decoded {
+    match protocol {
+        DbProtocol::Postgres => postgres_messages(data, 0),
+        DbProtocol::Redis => redis_commands(data, 0),
+        DbProtocol::Mysql => mysql_commands(data, 0),
+    }
+}
+
+fn decode_postgres(data: &[u8]) -> Option<Decoded> {
+    let mut offset = 0;
+    loop {
+        let len = usize::try_from(be_u32(data, offset)?).ok()?;
+        let code = be_u32(data, offset + 4)?;
+        if matches!(code, POSTGRES_SSL_REQUEST | POSTGRES_GSSENC_REQUEST) && len == 8 {
+            offset += 8;
+            continue;
+        }
+        if code != POSTGRES_PROTOCOL_V3 {
+            return (offset > 0).then_some(Decoded::encrypted(offset));
+        }
+        let params = data.get(offset + 8..offset + len)?;
+        let connect = DbStatement {
+            offset,
+            command: "CONNECT".to_string(),
+            statement: startup_summary(params),
+        };
+        let mut decoded = postgres_messages(data, offset + len);
+        decoded.statements.insert(0, connect);
+        return Some(decoded);
+    }
+}
+
+fn postgres_messages(data: &[u8], mut offset: usize) -> Decoded {
+    let mut statements = Vec::new();
+    while let Some(&tag) = data.get(offset) {
+        let Some(len) = be_u32(data, offset + 1).and_then(|len| usize::try_from(len).ok()) else {
+            break;
+        };
+        let Some(payload) = data.get(offset + 5..offset + 1 + len) else {
+            break;
+        };
+        let query = match tag {
+            b'Q' => cstrings(payload).next(),
+            b'P' => cstrings(payload).nth(1),
+            _ => None,
+        };
+        if let Some(statement) = query.as_deref().and_then(sql_statement) {
+            statements.push(DbStatement {
+                offset,
+                ..statement
+            });
+        }
+        offset += 1 + len;
+    }
+    Decoded {
+        statements,
+        next: offset,
+        encrypted: false,
+    }
+}
+
+fn startup_summary(params: &[u8]) -> String {
+    let values: Vec<String> = cstrings(params).collect();
+    let pairs: Vec<String> = values
+        .chunks(2)
+        .filter_map(|pair| match pair {
+            [key, value] if key == "user" || key == "database" => Some(format!("{key}={value}")),
+            _ => None,
+        })
+        .collect();
+    pairs.join(" ")
+}
+
+fn decode_redis(data: &[u8], inline: bool) -> Option<Decoded> {
+    let first = *data.first()?;
+    if first != b'*' && !(inline && first.is_ascii_alphabetic()) {
+        return None;
+    }
+    let decoded = redis_commands(data, 0);
+    (!decoded.statements.is_empty()).then_some(decoded)
+}
+
+fn redis_commands(data: &[u8], mut offset: usize) -> Decoded {
+    let mut statements = Vec::new();
+    while offset < data.len() {
+        let parsed = if data.get(offset) == Some(&b'*') {
+            resp_array(data, offset)
+        } else {
+            resp_inline(data, offset)
+        };
+        let Some((args, next)) = parsed else {
+            break;
+        };
+        if let Some(statement) = redis_statement(&args) {
+            statements.push(DbStatement {
+                offset,
+                ..statement
+            });
+        }
+        offset = next;
+    }
+    Decoded {
+        statements,
+        next: offset,
+        encrypted: false,
+    }
+}
+
+fn resp_array(data: &[u8], offset: usize) -> Option<(Vec<String>, usize)> {
+    let (header, mut next) = resp_line(data, offset + 1)?;
+    let count: usize = header.parse().ok()?;
+    let mut args = Vec::with_capacity(count.min(16));
+    for _ in 0..count {
+        if data.get(next) != Some(&b'$') {
+            return None;
+        }
+        let (len, body) = resp_line(data, next + 1)?;
+        let len: usize = len.parse().ok()?;
+        let value = data.get(body..body + len)?;
+        args.push(String::from_utf8_lossy(value).into_owned());
+        next = body + len + 2;
+    }
+    Some((args, next))
+}
+
+fn resp_inline(data: &[u8], offset: usize) -> Option<(Vec<String>, usize)> {
+    let (line, next) = resp_line(data, offset)?;
+    Some((line.split_whitespace().map(str::to_string).collect(), next))
+}
+
+fn resp_line(data: &[u8], offset: usize) -> Option<(String, usize)> {
+    let rest = data.get(offset..)?;
+    let end = rest.windows(2).position(|pair| pair == b"\r\n")?;
+    let line = String::from_utf8_lossy(rest.get(..end)?).into_owned();
+    Some((line, offset + end + 2))
+}
+
+fn redis_statement(args: &[String]) -> Option<DbStatement> {
+    let (command, rest) = args.split_first()?;
+    let command = command.to_ascii_uppercase();
+    let secret = REDIS_SECRET_COMMANDS.contains(&command.as_str());
+    let mut parts = vec![command.clone()];
+    for (index, arg) in rest.iter().enumerate() {
+        parts.push(if index == 0 && !secret {
+            truncate(arg, 64)
+        } else {
+            "?".to_string()
+        });
+    }
+    Some(DbStatement {
+        offset: 0,
+        command,
+        statement: truncate(&parts.join(" "), MAX_STATEMENT_CHARS),
+    })
+}
+
+fn decode_mysql(data: &[u8]) -> Option<Decoded> {
+    let (handshake, seq, offset) = mysql_packet(data, 0)?;
+    let capabilities = le_u32(handshake, 0)?;
+    if seq != 1 || capabilities & MYSQL_CLIENT_PROTOCOL_41 == 0 || handshake.len() < 32 {
+        return None;
+    }
+    if handshake.len() == 32 && capabilities & MYSQL_CLIENT_SSL != 0 {
+        return Some(Decoded::encrypted(offset));
+    }
+    let mut decoded = mysql_commands(data, offset);
+    if let Some(user) = handshake.get(32..).and_then(|rest| cstrings(rest).next()) {
+        decoded.statements.insert(
+            0,
+            DbStatement {
+                offset: 0,
+                command: "CONNECT".to_string(),
+                statement: format!("user={user}"),
+            },
+        );
+    }
+    Some(decoded)
+}
+
+fn mysql_commands(data: &[u8], mut offset: usize) -> Decoded {
+    let mut statements = Vec::new();
+    while let Some((payload, seq, next)) = mysql_packet(data, offset) {
+        let query = match payload.split_first() {
+            Some((&(MYSQL_COM_QUERY | MYSQL_COM_STMT_PREPARE), query)) if seq == 0 => {
+                Some(String::from_utf8_lossy(query).into_owned())
+            }
+            _ => None,
+        };
+        if let Some(statement) = query.as_deref().and_then(sql_statement) {
+            statements.push(DbStatement {
+                offset,
+                ..statement
+            });
+        }
+        offset = next;
+    }
+    Decoded {
+        statements,
+        next: offset,
+        encrypted: false,
+    }
+}
+
+fn mysql_packet(data: &[u8], offset: usize) -> Option<(&[u8], u8, usize)> {
+    let header = data.get(offset..offset + 4)?;
+    let len = usize::from(*header.first()?)
+        | usize::from(*header.get(1)?) << 8
+        | usize::from(*header.get(2)?) << 16;
+    let seq = *header.get(3)?;
+    let payload = data.get(offset + 4..offset + 4 + len)?;
+    Some((payload, seq, offset + 4 + len))
+}
+
+fn sql_statement(sql: &str) -> Option<DbStatement> {
+    let statement = redact_sql(sql);
+    let command = statement
+        .split(|ch: char| !ch.is_ascii_alphabetic())
+        .find(|word| !word.is_empty())?
+        .to_ascii_uppercase();
+    Some(DbStatement {
+        offset: 0,
+        command,
+        statement,
+    })
+}
+
+pub fn redact_sql(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len().min(MAX_STATEMENT_CHARS));
+    let mut chars = sql.chars().peekable();
+    let mut previous = ' ';
+    while let Some(ch) = chars.next() {
+        if ch == '\'' {
+            skip_quoted(&mut chars);
+            out.push('?');
+        } else if ch.is_ascii_digit() && !is_word_char(previous) {
+            while chars
+                .next_if(|next| next.is_ascii_alphanumeric() || *next == '.')
+                .is_some()
+            {}
+            out.push('?');
+        } else if ch.is_whitespace() {
+            if !out.ends_with(' ') {
+                out.push(' ');
+            }
+        } else {
+            out.push(ch);
+        }
+        previous = ch;
+    }
+    truncate(out.trim(), MAX_STATEMENT_CHARS)
+}
+
+fn skip_quoted(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    while let Some(ch) = chars.next() {
+        if ch == '\'' && chars.next_if_eq(&'\'').is_none() {
+            return;
+        }
+    }
+}
+
+const fn is_word_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || matches!(ch, '_' | '$' | '.')
+}
+
+fn cstrings(data: &[u8]) -> impl Iterator<Item = String> + '_ {
+    data.split(|byte| *byte == 0)
+        .filter(|part| !part.is_empty())
+        .map(|part| String::from_utf8_lossy(part).into_owned())
+}
+
+fn be_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+    Some(u32::from_be_bytes(bytes))
+}
+
+fn le_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+    Some(u32::from_le_bytes(bytes))
+}
+
+pub fn decode_base64(value: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(value.len() / 4 * 3);
+    let mut buffer = 0_u32;
+    let mut bits = 0;
+    for byte in value.bytes().filter(|byte| !byte.is_ascii_whitespace()) {
+        let sextet = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(sextet);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push(u8::try_from((buffer >> bits) & 0xff).ok()?);
+        }
+    }
+    Some(out)
+}
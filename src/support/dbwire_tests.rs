@@ -0,0 +1,145 @@
+use super::dbwire::{decode_base64, redact_sql, DbProtocol, DbStatement, DbStreamDecoder};
+
+fn decode_client_stream(data: &[u8], port: u16) -> Option<(DbProtocol, Vec<DbStatement>)> {
+    let mut decoder = DbStreamDecoder::new(port);
+    let statements = decoder.push(data);
+    decoder.protocol().map(|protocol| (protocol, statements))
+}
+
+fn postgres_message(tag: u8, payload: &[u8]) -> Vec<u8> {
+    let len = u32::try_from(payload.len() + 4).unwrap_or_default();
+    let mut message = vec![tag];
+    message.extend(len.to_be_bytes());
+    message.extend(payload);
+    message
+}
+
+#[test]
+fn decodes_postgres_startup_and_simple_queries() {
+    let params = b"user\0app\0database\0shop\0\0";
+    let len = u32::try_from(params.len() + 8).unwrap_or_default();
+    let mut data = len.to_be_bytes().to_vec();
+    data.extend(196_608_u32.to_be_bytes());
+    data.extend(params);
+    data.extend(postgres_message(b'p', b"secret\0"));
+    data.extend(postgres_message(
+        b'Q',
+        b"SELECT * FROM users WHERE email = 'a@b.c' AND id = 42\0",
+    ));
+    data.extend(postgres_message(b'X', b""));
+    let decoded = decode_client_stream(&data, 5432);
+    assert_eq!(
+        decoded.as_ref().map(|(protocol, _)| *protocol),
+        Some(DbProtocol::Postgres)
+    );
+    let statements = decoded
+        .map(|(_, statements)| statements)
+        .unwrap_or_default();
+    let summaries: Vec<(&str, &str)> = statements
+        .iter()
+        .map(|statement| (statement.command.as_str(), statement.statement.as_str()))
+        .collect();
+    assert_eq!(
+        summaries,
+        vec![
+            ("CONNECT", "user=app database=shop"),
+            ("SELECT", "SELECT * FROM users WHERE email = ? AND id = ?"),
+        ]
+    );
+}
+
+#[test]
+fn decodes_redis_commands_without_values() {
+    let data = b"*2\r\n$4\r\nAUTH\r\n$6\r\nhunter\r\n*3\r\n$3\r\nSET\r\n$6\r\nuser:1\r\n$5\r\nalice\r\nPING\r\n";
+    let decoded = decode_client_stream(data, 6379);
+    assert_eq!(
+        decoded.as_ref().map(|(protocol, _)| *protocol),
+        Some(DbProtocol::Redis)
+    );
+    let statements = decoded
+        .map(|(_, statements)| statements)
+        .unwrap_or_default();
+    let summaries: Vec<&str> = statements
+        .iter()
+        .map(|statement| statement.statement.as_str())
+        .collect();
+    assert_eq!(summaries, vec!["AUTH ?", "SET user:1 ?", "PING"]);
+    assert_eq!(
+        statements.get(1).map(|statement| statement.offset),
+        Some(26)
+    );
+}
+
+#[test]
+fn long_redis_keys_are_cut_to_a_fixed_width() {
+    let key = "k".repeat(100);
+    let data = format!("*2\r\n$3\r\nGET\r\n$100\r\n{key}\r\n");
+    let statements = decode_client_stream(data.as_bytes(), 6379)
+        .map(|(_, statements)| statements)
+        .unwrap_or_default();
+    let statement = statements
+        .first()
+        .map(|statement| statement.statement.as_str());
+    assert_eq!(statement, Some(format!("GET {}…", "k".repeat(63)).as_str()));
+}
+
+#[test]
+fn decodes_mysql_queries_after_the_handshake() {
+    let mut handshake = 0x0000_a285_u32.to_le_bytes().to_vec();
+    handshake.extend([0_u8; 28]);
+    handshake.extend(b"root\0");
+    let mut data = vec![u8::try_from(handshake.len()).unwrap_or_default(), 0, 0, 1];
+    data.extend(handshake);
+    let query = b"\x03UPDATE t SET name='x' WHERE id=7";
+    data.extend([u8::try_from(query.len()).unwrap_or_default(), 0, 0, 0]);
+    data.extend(query);
+    let decoded = decode_client_stream(&data, 3306);
+    assert_eq!(
+        decoded.as_ref().map(|(protocol, _)| *protocol),
+        Some(DbProtocol::Mysql)
+    );
+    let statements = decoded
+        .map(|(_, statements)| statements)
+        .unwrap_or_default();
+    let last = statements
+        .last()
+        .map(|statement| statement.statement.as_str());
+    assert_eq!(last, Some("UPDATE t SET name=? WHERE id=?"));
+    assert_eq!(decode_client_stream(b"\x16\x03\x01 tls", 443), None);
+}
+
+#[test]
+fn decodes_statements_as_the_stream_arrives() {
+    let params = b"user\0app\0\0";
+    let len = u32::try_from(params.len() + 8).unwrap_or_default();
+    let mut data = len.to_be_bytes().to_vec();
+    data.extend(196_608_u32.to_be_bytes());
+    data.extend(params);
+    data.extend(postgres_message(b'Q', b"SELECT 1\0"));
+    data.extend(postgres_message(b'Q', b"DELETE FROM t WHERE id = 9\0"));
+    let mut decoder = DbStreamDecoder::new(5432);
+    let mut seen = Vec::new();
+    for chunk in data.chunks(3) {
+        for statement in decoder.push(chunk) {
+            seen.push((statement.offset, statement.statement));
+        }
+    }
+    assert_eq!(decoder.protocol(), Some(DbProtocol::Postgres));
+    assert_eq!(
+        seen,
+        vec![
+            (0, "user=app".to_string()),
+            (18, "SELECT ?".to_string()),
+            (32, "DELETE FROM t WHERE id = ?".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn redacts_literals_but_keeps_identifiers_and_placeholders() {
+    assert_eq!(
+        redact_sql("select  t1.v from t1\n where s = 'it''s' and n > 3.5 and p = $1"),
+        "select t1.v from t1 where s = ? and n > ? and p = $1"
+    );
+    assert_eq!(decode_base64("U0VMRUNUIDE="), Some(b"SELECT 1".to_vec()));
+}
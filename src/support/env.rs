@@ -0,0 +1,45 @@
+use serde::Serialize;
+
+const SECRET_MARKERS: &[&str] = &[
+    "SECRET",
+    "TOKEN",
+    "PASSWORD",
+    "PASSWD",
+    "API_KEY",
+    "PRIVATE_KEY",
+    "CREDENTIAL",
+];
+const MASK: &str = "********";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct EnvVar {
+    pub key: String,
+    pub value: String,
+    pub masked: bool,
+}
+
+pub fn is_secret_key(key: &str) -> bool {
+    let upper = key.to_ascii_uppercase();
+    SECRET_MARKERS.iter().any(|marker| upper.contains(marker))
+}
+
+pub fn mask_env(entries: &[String]) -> Vec<EnvVar> {
+    let mut vars: Vec<EnvVar> = entries
+        .iter()
+        .map(|entry| {
+            let (key, value) = entry.split_once('=').unwrap_or((entry.as_str(), ""));
+            let masked = is_secret_key(key) && !value.is_empty();
+            EnvVar {
+                key: key.to_string(),
+                value: if masked {
+                    MASK.to_string()
+                } else {
+                    value.to_string()
+                },
+                masked,
+            }
+        })
+        .collect();
+    vars.sort_by(|a, b| a.key.cmp(&b.key));
+    vars
+}
@@ -0,0 +1,27 @@
+use super::env::{is_secret_key, mask_env};
+
+#[test]
+fn masks_secret_like_keys() {
+    let entries = vec![
+        "PATH=/usr/bin".to_string(),
+        "DB_PASSWORD=hunter2".to_string(),
+        "github_token=abc=def".to_string(),
+        "EMPTY_SECRET=".to_string(),
+    ];
+    let vars = mask_env(&entries);
+    let rendered: Vec<(&str, &str, bool)> = vars
+        .iter()
+        .map(|var| (var.key.as_str(), var.value.as_str(), var.masked))
+        .collect();
+    assert_eq!(
+        rendered,
+        [
+            ("DB_PASSWORD", "********", true),
+            ("EMPTY_SECRET", "", false),
+            ("PATH", "/usr/bin", false),
+            ("github_token", "********", true),
+        ]
+    );
+    assert!(is_secret_key("aws_secret_access_key"));
+    assert!(!is_secret_key("LOG_LEVEL"));
+}
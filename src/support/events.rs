@@ -0,0 +1,124 @@
+use std::sync::{Mutex, MutexGuard};
+
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender, TrySendError};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::traffic::TrafficCall;
+
+const EVENT_QUEUE_SIZE: usize = 256;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LifecycleEvent {
+    pub run_id: String,
+    pub at_ms: u64,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum EventKind {
+    RunStarted {
+        project: String,
+        tag: Option<String>,
+    },
+    ServiceHealthy {
+        service: String,
+        container: String,
+    },
+    CallObserved {
+        call: Box<TrafficCall>,
+    },
+    CrashLoop {
+        service: String,
+        container: String,
+        restarts: u64,
+        window_ms: u64,
+        snapshot: Option<String>,
+    },
+    RunStopped {
+        exit_code: i32,
+    },
+}
+
+impl EventKind {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::RunStarted { .. } => "run-started",
+            Self::ServiceHealthy { .. } => "service-healthy",
+            Self::CallObserved { .. } => "call-observed",
+            Self::CrashLoop { .. } => "crash-loop",
+            Self::RunStopped { .. } => "run-stopped",
+        }
+    }
+}
+
+pub struct EventBus {
+    subscribers: Mutex<Vec<Sender<LifecycleEvent>>>,
+}
+
+impl EventBus {
+    pub const fn new() -> Self {
+        Self {
+            subscribers: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn subscribe(&self) -> Receiver<LifecycleEvent> {
+        let (sender, receiver) = bounded(EVENT_QUEUE_SIZE);
+        self.subscribers().push(sender);
+        receiver
+    }
+
+    pub fn subscribe_lossless(&self) -> Receiver<LifecycleEvent> {
+        let (sender, receiver) = unbounded();
+        self.subscribers().push(sender);
+        receiver
+    }
+
+    pub fn publish(&self, event: &LifecycleEvent) {
+        self.subscribers()
+            .retain(|sender| match sender.try_send(event.clone()) {
+                Ok(()) | Err(TrySendError::Full(_)) => true,
+                Err(TrySendError::Disconnected(_)) => false,
+            });
+    }
+
+    pub fn close(&self) {
+        self.subscribers().clear();
+    }
+
+    fn subscribers(&self) -> MutexGuard<'_, Vec<Sender<LifecycleEvent>>> {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+pub struct CallSampler {
+    interval_ms: u64,
+    last_ms: Option<u64>,
+}
+
+impl CallSampler {
+    pub const fn new(interval_ms: u64) -> Self {
+        Self {
+            interval_ms,
+            last_ms: None,
+        }
+    }
+
+    pub fn admit(&mut self, call: &TrafficCall, now_ms: u64) -> bool {
+        let is_error = call.status.is_some_and(|status| status >= 500);
+        let due = self
+            .last_ms
+            .is_none_or(|last| now_ms.saturating_sub(last) >= self.interval_ms);
+        if !is_error && !due {
+            return false;
+        }
+        if due {
+            self.last_ms = Some(now_ms);
+        }
+        true
+    }
+}
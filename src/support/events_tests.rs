@@ -0,0 +1,42 @@
+use super::events::{CallSampler, EventBus, EventKind, LifecycleEvent};
+use super::traffic_fixtures::http_call;
+use crate::domain::traffic::TrafficCall;
+
+fn call(status: u16) -> TrafficCall {
+    TrafficCall {
+        status: Some(status),
+        ..http_call()
+    }
+}
+
+#[test]
+fn samples_one_call_per_interval_but_keeps_server_errors() {
+    let mut sampler = CallSampler::new(1_000);
+    let admitted: Vec<bool> = [(200, 0), (200, 400), (503, 500), (200, 999), (200, 1_000)]
+        .into_iter()
+        .map(|(status, now_ms)| sampler.admit(&call(status), now_ms))
+        .collect();
+    assert_eq!(admitted, vec![true, false, true, false, true]);
+}
+
+#[test]
+fn serializes_events_with_a_kebab_case_name_and_hangs_up_on_close() {
+    let bus = EventBus::new();
+    let receiver = bus.subscribe();
+    bus.publish(&LifecycleEvent {
+        run_id: "run_1".to_string(),
+        at_ms: 5,
+        kind: EventKind::RunStopped { exit_code: 130 },
+    });
+    bus.close();
+    let events: Vec<LifecycleEvent> = receiver.iter().collect();
+    assert_eq!(events.len(), 1);
+    let json = events
+        .first()
+        .and_then(|event| serde_json::to_value(event).ok())
+        .unwrap_or_default();
+    assert_eq!(
+        json,
+        serde_json::json!({"run_id": "run_1", "at_ms": 5, "event": "run-stopped", "exit_code": 130})
+    );
+}
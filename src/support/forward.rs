@@ -0,0 +1,131 @@
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{PublishedEndpoint, ServiceInfo};
+use crate::support::oneoff::{container_args, OneOffSpec};
+use crate::support::services::endpoint_url;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForwardTarget {
+    pub service: String,
+    pub port: u16,
+    pub local_port: u16,
+}
+
+impl ForwardTarget {
+    pub fn parse(target: &str, local_port: Option<&str>) -> Result<Self, String> {
+        let (service, port) = target
+            .rsplit_once(':')
+            .filter(|(service, _)| !service.is_empty())
+            .ok_or_else(|| format!("expected <service>:<port>, got '{target}'"))?;
+        let port = parse_port(port).and_then(|port| {
+            if port == 0 {
+                Err(format!("invalid port in '{target}'"))
+            } else {
+                Ok(port)
+            }
+        })?;
+        Ok(Self {
+            service: service.to_string(),
+            port,
+            local_port: local_port.map_or(Ok(0), parse_port)?,
+        })
+    }
+}
+
+pub fn relay_name(service: &str) -> String {
+    format!("forward-{service}")
+}
+
+pub fn relay_command(target: &ForwardTarget) -> Vec<String> {
+    vec![
+        format!("TCP-LISTEN:{},fork,reuseaddr", target.port),
+        format!("TCP:{}:{}", target.service, target.port),
+    ]
+}
+
+// Detached and published on loopback only; the engine picks the host port.
+pub fn relay_args(spec: &OneOffSpec, port: u16) -> Vec<String> {
+    let mut args = vec![
+        "--rm".to_string(),
+        "-d".to_string(),
+        "-p".to_string(),
+        format!("127.0.0.1::{port}"),
+    ];
+    args.extend(container_args(spec));
+    args
+}
+
+fn parse_port(value: &str) -> Result<u16, String> {
+    value.parse().map_err(|_| format!("invalid port '{value}'"))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Forward {
+    pub service: String,
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ForwardUpdate {
+    #[serde(default)]
+    pub add: Vec<Forward>,
+    #[serde(default)]
+    pub remove: Vec<Forward>,
+}
+
+#[derive(Default)]
+pub struct ForwardRegistry {
+    forwards: Mutex<Vec<Forward>>,
+}
+
+impl ForwardRegistry {
+    pub fn forwards(&self) -> Vec<Forward> {
+        self.forwards
+            .lock()
+            .map(|forwards| forwards.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn update(&self, update: ForwardUpdate) -> Vec<Forward> {
+        let Ok(mut forwards) = self.forwards.lock() else {
+            return Vec::new();
+        };
+        forwards.retain(|forward| !update.remove.contains(forward));
+        for forward in update.add {
+            if !forwards.contains(&forward) {
+                forwards.push(forward);
+            }
+        }
+        forwards.clone()
+    }
+
+    pub fn apply(&self, services: &[ServiceInfo]) -> Vec<ServiceInfo> {
+        let forwards = self.forwards();
+        services
+            .iter()
+            .cloned()
+            .map(|mut service| {
+                for forward in forwards
+                    .iter()
+                    .filter(|forward| forward.service == service.name)
+                {
+                    let url = endpoint_url("http", &forward.host, forward.port);
+                    service.endpoint.get_or_insert_with(|| url.clone());
+                    service.endpoints.push(url.clone());
+                    service.exposed = true;
+                    service.published.push(PublishedEndpoint {
+                        host: forward.host.clone(),
+                        port: forward.port,
+                        url,
+                        protocol: None,
+                        forwarded: true,
+                    });
+                }
+                service
+            })
+            .collect()
+    }
+}
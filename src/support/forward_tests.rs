@@ -0,0 +1,113 @@
+use super::forward::{
+    relay_args, relay_command, relay_name, Forward, ForwardRegistry, ForwardTarget, ForwardUpdate,
+};
+use super::oneoff::OneOffSpec;
+use crate::domain::ServiceInfo;
+
+#[test]
+fn parses_service_port_targets() {
+    assert_eq!(
+        ForwardTarget::parse("api:8080", None),
+        Ok(ForwardTarget {
+            service: "api".to_string(),
+            port: 8080,
+            local_port: 0,
+        })
+    );
+    assert_eq!(
+        ForwardTarget::parse("db:5432", Some("15432")).map(|target| target.local_port),
+        Ok(15432)
+    );
+    assert!(ForwardTarget::parse("api", None).is_err());
+    assert!(ForwardTarget::parse(":80", None).is_err());
+    assert!(ForwardTarget::parse("api:0", None).is_err());
+    assert!(ForwardTarget::parse("api:80", Some("http")).is_err());
+}
+
+#[test]
+fn lists_forwards_as_temporary_endpoints_until_removed() {
+    let registry = ForwardRegistry::default();
+    let forward = Forward {
+        service: "worker".to_string(),
+        host: "127.0.0.1".to_string(),
+        port: 40_123,
+    };
+    let added = registry.update(ForwardUpdate {
+        add: vec![forward.clone(), forward.clone()],
+        remove: Vec::new(),
+    });
+    assert_eq!(added, vec![forward.clone()]);
+
+    let services = vec![ServiceInfo {
+        name: "worker".to_string(),
+        endpoints: Vec::new(),
+        endpoint: None,
+        exposed: false,
+        published: Vec::new(),
+    }];
+    let applied = registry.apply(&services);
+    let worker = applied.first();
+    assert_eq!(
+        worker.and_then(|service| service.endpoint.as_deref()),
+        Some("http://127.0.0.1:40123")
+    );
+    assert!(worker.is_some_and(|service| service.exposed));
+    assert!(worker
+        .and_then(|service| service.published.first())
+        .is_some_and(|endpoint| endpoint.forwarded && endpoint.port == 40_123));
+
+    let removed = registry.update(ForwardUpdate {
+        add: Vec::new(),
+        remove: vec![forward],
+    });
+    assert!(removed.is_empty());
+    assert!(registry
+        .apply(&services)
+        .iter()
+        .all(|service| service.endpoint.is_none() && service.published.is_empty()));
+}
+
+#[test]
+fn relays_publish_the_target_port_on_loopback_inside_the_run() {
+    let target = ForwardTarget {
+        service: "db".to_string(),
+        port: 5432,
+        local_port: 0,
+    };
+    let spec = OneOffSpec {
+        run_id: "run_a1".to_string(),
+        project_name: "sanelens_run_a1".to_string(),
+        name: relay_name(&target.service),
+        image: "alpine/socat:latest".to_string(),
+        networks: vec!["sanelens_run_a1_default".to_string()],
+        env: Vec::new(),
+        command: relay_command(&target),
+        tty: false,
+    };
+    let args = relay_args(&spec, target.port);
+    let expected: Vec<String> = [
+        "--rm",
+        "-d",
+        "-p",
+        "127.0.0.1::5432",
+        "--label",
+        "sanelens.run_id=run_a1",
+        "--label",
+        "sanelens.service=forward-db",
+        "--label",
+        "sanelens.oneoff=true",
+        "--label",
+        "com.docker.compose.project=sanelens_run_a1",
+        "--label",
+        "com.docker.compose.oneoff=True",
+        "--network",
+        "sanelens_run_a1_default",
+        "alpine/socat:latest",
+        "TCP-LISTEN:5432,fork,reuseaddr",
+        "TCP:db:5432",
+    ]
+    .iter()
+    .map(ToString::to_string)
+    .collect();
+    assert_eq!(args, expected);
+}
@@ -0,0 +1,10 @@
+use super::glob::glob_match;
+
+#[test]
+fn matches_stars_and_single_characters() {
+    assert!(glob_match("*-svc?", "billing-svc1"));
+    assert!(glob_match("api-*", "api-"));
+    assert!(glob_match("*", ""));
+    assert!(!glob_match("api-*", "web"));
+    assert!(!glob_match("db?", "db"));
+}
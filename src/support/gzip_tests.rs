@@ -0,0 +1,31 @@
+use std::io::Read;
+
+use flate2::read::GzDecoder;
+
+use super::gzip::{accepts_gzip, gzip};
+
+#[test]
+fn checks_accept_encoding() {
+    assert!(accepts_gzip("gzip, deflate, br"));
+    assert!(accepts_gzip("br;q=1.0, GZIP;q=0.5"));
+    assert!(!accepts_gzip("gzip;q=0, br"));
+    assert!(!accepts_gzip("identity"));
+}
+
+#[test]
+fn gzip_output_round_trips_through_a_decoder() {
+    let text =
+        "<div class=\"row\">sanelens</div>\n".repeat(200) + "tail without repeats 0123456789";
+    let packed = gzip(text.as_bytes());
+    assert!(packed.len() < text.len() / 4);
+    let mut unpacked = String::new();
+    assert!(GzDecoder::new(packed.as_slice())
+        .read_to_string(&mut unpacked)
+        .is_ok());
+    assert_eq!(unpacked, text);
+    let mut empty = Vec::new();
+    assert!(GzDecoder::new(gzip(b"").as_slice())
+        .read_to_end(&mut empty)
+        .is_ok());
+    assert!(empty.is_empty());
+}
@@ -0,0 +1,52 @@
+use std::thread;
+use std::time::Duration;
+
+pub const PULL_ATTEMPTS: u32 = 3;
+
+pub trait ImageStore {
+    fn image_exists(&self, image: &str) -> bool;
+    fn pull_image(&self, image: &str) -> Result<(), String>;
+    fn local_image_tags(&self, repository: &str) -> Vec<String>;
+}
+
+pub fn ensure_image(
+    store: &impl ImageStore,
+    image: &str,
+    backoff: Duration,
+) -> Result<String, String> {
+    if store.image_exists(image) {
+        return Ok(image.to_string());
+    }
+    let mut last_error = String::new();
+    for attempt in 1..=PULL_ATTEMPTS {
+        eprintln!("[compose] pulling envoy image {image} (attempt {attempt}/{PULL_ATTEMPTS})");
+        match store.pull_image(image) {
+            Ok(()) => return Ok(image.to_string()),
+            Err(err) => {
+                eprintln!("[compose] pull of {image} failed: {err}");
+                last_error = err;
+            }
+        }
+        if attempt < PULL_ATTEMPTS {
+            let delay = backoff * 2_u32.pow(attempt - 1);
+            eprintln!("[compose] retrying in {}s", delay.as_secs());
+            thread::sleep(delay);
+        }
+    }
+    let repository = image_repository(image);
+    if let Some(cached) = store.local_image_tags(repository).into_iter().next() {
+        eprintln!("[compose] using locally cached envoy image {cached} instead of {image}");
+        return Ok(cached);
+    }
+    Err(format!(
+        "envoy image {image} is unavailable ({last_error}) and no cached {repository} image was found; pull it manually or set SANELENS_ENVOY_IMAGE"
+    ))
+}
+
+pub fn image_repository(image: &str) -> &str {
+    let image = image.split('@').next().unwrap_or(image);
+    match image.rsplit_once(':') {
+        Some((repository, tag)) if !tag.contains('/') => repository,
+        _ => image,
+    }
+}
@@ -0,0 +1,99 @@
+use std::cell::{Cell, RefCell};
+use std::time::Duration;
+
+use super::images::{ensure_image, image_repository, ImageStore, PULL_ATTEMPTS};
+
+#[derive(Default)]
+struct FakeStore {
+    present: bool,
+    failures: u32,
+    cached: Vec<String>,
+    pulls: Cell<u32>,
+    listed: RefCell<Vec<String>>,
+}
+
+impl ImageStore for FakeStore {
+    fn image_exists(&self, _image: &str) -> bool {
+        self.present
+    }
+
+    fn pull_image(&self, _image: &str) -> Result<(), String> {
+        self.pulls.set(self.pulls.get() + 1);
+        if self.pulls.get() > self.failures {
+            Ok(())
+        } else {
+            Err("registry unreachable".to_string())
+        }
+    }
+
+    fn local_image_tags(&self, repository: &str) -> Vec<String> {
+        self.listed.borrow_mut().push(repository.to_string());
+        self.cached.clone()
+    }
+}
+
+const IMAGE: &str = "envoyproxy/envoy:v1.30-latest";
+
+#[test]
+fn present_images_are_not_pulled() {
+    let store = FakeStore {
+        present: true,
+        ..FakeStore::default()
+    };
+    assert_eq!(
+        ensure_image(&store, IMAGE, Duration::ZERO),
+        Ok(IMAGE.to_string())
+    );
+    assert_eq!(store.pulls.get(), 0);
+}
+
+#[test]
+fn pulls_are_retried_until_one_succeeds() {
+    let store = FakeStore {
+        failures: PULL_ATTEMPTS - 1,
+        ..FakeStore::default()
+    };
+    assert_eq!(
+        ensure_image(&store, IMAGE, Duration::ZERO),
+        Ok(IMAGE.to_string())
+    );
+    assert_eq!(store.pulls.get(), PULL_ATTEMPTS);
+    assert!(store.listed.borrow().is_empty());
+}
+
+#[test]
+fn falls_back_to_a_cached_tag_of_the_same_repository() {
+    let store = FakeStore {
+        failures: PULL_ATTEMPTS,
+        cached: vec!["envoyproxy/envoy:v1.29-latest".to_string()],
+        ..FakeStore::default()
+    };
+    assert_eq!(
+        ensure_image(&store, IMAGE, Duration::ZERO),
+        Ok("envoyproxy/envoy:v1.29-latest".to_string())
+    );
+    assert_eq!(*store.listed.borrow(), vec!["envoyproxy/envoy"]);
+
+    let store = FakeStore {
+        failures: PULL_ATTEMPTS,
+        ..FakeStore::default()
+    };
+    let err = ensure_image(&store, IMAGE, Duration::ZERO)
+        .err()
+        .unwrap_or_default();
+    assert!(err.contains("registry unreachable"), "{err}");
+}
+
+#[test]
+fn strips_tags_and_digests_from_image_references() {
+    assert_eq!(image_repository(IMAGE), "envoyproxy/envoy");
+    assert_eq!(image_repository("envoy@sha256:abc"), "envoy");
+    assert_eq!(
+        image_repository("localhost:5000/envoy"),
+        "localhost:5000/envoy"
+    );
+    assert_eq!(
+        image_repository("localhost:5000/envoy:dev"),
+        "localhost:5000/envoy"
+    );
+}
@@ -0,0 +1,139 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::support::constants::{PROXY_LABEL, SERVICE_LABEL};
+use crate::support::env::{mask_env, EnvVar};
+
+pub const INSPECT_DIR: &str = "inspect";
+const COMPOSE_SERVICE_LABELS: &[&str] =
+    &["com.docker.compose.service", "io.podman.compose.service"];
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ContainerSnapshot {
+    pub service: Option<String>,
+    pub container: String,
+    pub status: Option<String>,
+    pub exit_code: Option<i64>,
+    pub restart_policy: Option<String>,
+    pub mounts: Vec<String>,
+    pub networks: Vec<String>,
+    pub env: Vec<EnvVar>,
+}
+
+pub fn container_service(value: &Value) -> Option<String> {
+    let labels = value.pointer("/Config/Labels")?;
+    std::iter::once(SERVICE_LABEL)
+        .chain(COMPOSE_SERVICE_LABELS.iter().copied())
+        .find_map(|key| labels.get(key).and_then(Value::as_str))
+        .map(str::to_string)
+}
+
+pub fn is_proxy_container(value: &Value) -> bool {
+    value
+        .pointer("/Config/Labels")
+        .and_then(|labels| labels.get(PROXY_LABEL))
+        .and_then(Value::as_str)
+        .is_some_and(|flag| matches!(flag.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+}
+
+pub fn summarize_container(value: &Value) -> ContainerSnapshot {
+    let text = |pointer: &str| {
+        value
+            .pointer(pointer)
+            .and_then(Value::as_str)
+            .filter(|text| !text.is_empty())
+            .map(str::to_string)
+    };
+    let restart_policy = text("/HostConfig/RestartPolicy/Name").map(|name| {
+        match value
+            .pointer("/HostConfig/RestartPolicy/MaximumRetryCount")
+            .and_then(Value::as_u64)
+            .filter(|retries| *retries > 0)
+        {
+            Some(retries) => format!("{name}:{retries}"),
+            None => name,
+        }
+    });
+    let env: Vec<String> = value
+        .pointer("/Config/Env")
+        .and_then(Value::as_array)
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|entry| entry.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    ContainerSnapshot {
+        service: container_service(value),
+        container: text("/Id").unwrap_or_default(),
+        status: text("/State/Status"),
+        exit_code: value.pointer("/State/ExitCode").and_then(Value::as_i64),
+        restart_policy,
+        mounts: mounts(value),
+        networks: networks(value),
+        env: mask_env(&env),
+    }
+}
+
+fn mounts(value: &Value) -> Vec<String> {
+    let Some(entries) = value.get("Mounts").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    entries
+        .iter()
+        .map(|mount| {
+            let field = |key: &str| mount.get(key).and_then(Value::as_str).unwrap_or_default();
+            let source = [field("Source"), field("Name")]
+                .into_iter()
+                .find(|source| !source.is_empty())
+                .unwrap_or("-");
+            let mode = if mount.get("RW").and_then(Value::as_bool) == Some(false) {
+                "ro"
+            } else {
+                "rw"
+            };
+            format!(
+                "{source} -> {} ({}, {mode})",
+                field("Destination"),
+                field("Type")
+            )
+        })
+        .collect()
+}
+
+fn networks(value: &Value) -> Vec<String> {
+    let Some(entries) = value
+        .pointer("/NetworkSettings/Networks")
+        .and_then(Value::as_object)
+    else {
+        return Vec::new();
+    };
+    entries
+        .iter()
+        .map(|(name, network)| {
+            network
+                .get("IPAddress")
+                .and_then(Value::as_str)
+                .filter(|ip| !ip.is_empty())
+                .map_or_else(|| name.clone(), |ip| format!("{name} {ip}"))
+        })
+        .collect()
+}
+
+pub fn latest_snapshot(run_dir: &Path) -> Option<PathBuf> {
+    fs::read_dir(run_dir.join(INSPECT_DIR))
+        .ok()?
+        .flatten()
+        .filter(|entry| {
+            entry
+                .path()
+                .extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .max_by_key(|entry| entry.metadata().and_then(|meta| meta.modified()).ok())
+        .map(|entry| entry.path())
+}
@@ -0,0 +1,67 @@
+use std::fs;
+
+use super::crash::remove_run_dir;
+use super::inspect::{
+    container_service, is_proxy_container, latest_snapshot, summarize_container, INSPECT_DIR,
+};
+
+#[test]
+fn summarizes_key_inspect_fields() {
+    let value = serde_json::json!({
+        "Id": "3f2a1b9c0d12",
+        "Config": {
+            "Env": ["LOG_LEVEL=debug", "DB_PASSWORD=hunter2"],
+            "Labels": {
+                "com.docker.compose.service": "api-1",
+                "sanelens.service": "api"
+            }
+        },
+        "State": { "Status": "exited", "ExitCode": 137 },
+        "HostConfig": { "RestartPolicy": { "Name": "on-failure", "MaximumRetryCount": 3 } },
+        "Mounts": [
+            { "Type": "bind", "Source": "/src", "Destination": "/app", "RW": false },
+            { "Type": "volume", "Name": "data", "Destination": "/var/lib/data", "RW": true }
+        ],
+        "NetworkSettings": { "Networks": { "default": { "IPAddress": "10.89.0.4" } } }
+    });
+    let snapshot = summarize_container(&value);
+    assert_eq!(snapshot.service.as_deref(), Some("api"));
+    assert_eq!(snapshot.status.as_deref(), Some("exited"));
+    assert_eq!(snapshot.exit_code, Some(137));
+    assert_eq!(snapshot.restart_policy.as_deref(), Some("on-failure:3"));
+    assert_eq!(
+        snapshot.mounts,
+        vec![
+            "/src -> /app (bind, ro)".to_string(),
+            "data -> /var/lib/data (volume, rw)".to_string()
+        ]
+    );
+    assert_eq!(snapshot.networks, vec!["default 10.89.0.4".to_string()]);
+    assert_eq!(
+        snapshot
+            .env
+            .iter()
+            .map(|var| (var.key.as_str(), var.masked))
+            .collect::<Vec<_>>(),
+        vec![("DB_PASSWORD", true), ("LOG_LEVEL", false)]
+    );
+    assert!(!is_proxy_container(&value));
+    let proxy = serde_json::json!({ "Config": { "Labels": { "sanelens.proxy": "true" } } });
+    assert!(is_proxy_container(&proxy));
+    assert_eq!(container_service(&proxy), None);
+}
+
+#[test]
+fn snapshots_survive_run_dir_removal() {
+    let dir = std::env::temp_dir().join(format!("sanelens-inspect-{}", std::process::id()));
+    assert!(fs::create_dir_all(dir.join(INSPECT_DIR)).is_ok());
+    assert!(fs::write(dir.join(INSPECT_DIR).join("down.json"), "[]").is_ok());
+    assert!(fs::write(dir.join("compose.yaml"), "services: {}").is_ok());
+    let kept = remove_run_dir(&dir);
+    let latest = latest_snapshot(&dir);
+    let compose_gone = !dir.join("compose.yaml").exists();
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(kept.ok(), Some(false));
+    assert_eq!(latest, Some(dir.join(INSPECT_DIR).join("down.json")));
+    assert!(compose_gone);
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+pub const DEFAULT_LOG_MAX_BYTES: u64 = 10 * 1024 * 1024;
+pub const DEFAULT_LOG_KEEP: usize = 5;
+
+#[derive(Clone)]
+pub struct LogFileOptions {
+    pub dir: PathBuf,
+    pub max_bytes: u64,
+    pub keep: usize,
+    pub gzip: bool,
+}
+
+struct OpenLog {
+    file: File,
+    written: u64,
+}
+
+pub struct LogFileSink {
+    dir: PathBuf,
+    options: LogFileOptions,
+    files: Mutex<HashMap<String, OpenLog>>,
+    compressor: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl LogFileSink {
+    pub fn new(options: LogFileOptions, run_id: &str) -> Result<Self, String> {
+        let dir = options.dir.join(run_id).join("logs");
+        fs::create_dir_all(&dir)
+            .map_err(|err| format!("failed to create log dir {}: {err}", dir.display()))?;
+        Ok(Self {
+            dir,
+            options,
+            files: Mutex::new(HashMap::new()),
+            compressor: Mutex::new(None),
+        })
+    }
+
+    pub fn run_dir(&self) -> &Path {
+        self.dir.parent().unwrap_or(&self.dir)
+    }
+
+    pub fn write(&self, service: &str, line: &str, container_ts: Option<&str>) {
+        let mut entry = String::with_capacity(line.len() + 40);
+        if let Some(ts) = container_ts {
+            entry.push_str(ts);
+            entry.push(' ');
+        }
+        entry.push_str(line);
+        entry.push('\n');
+
+        let path = self.path_for(service);
+        let mut files = self.files();
+        let needs_rotation = files.get(service).is_some_and(|open| {
+            open.written > 0 && open.written + entry.len() as u64 > self.options.max_bytes
+        });
+        if needs_rotation {
+            files.remove(service);
+            self.rotate(&path);
+        }
+        if !files.contains_key(service) {
+            let Ok(open) = open_log(&path) else {
+                return;
+            };
+            files.insert(service.to_string(), open);
+        }
+        if let Some(open) = files.get_mut(service) {
+            if open.file.write_all(entry.as_bytes()).is_ok() {
+                open.written += entry.len() as u64;
+            }
+        }
+    }
+
+    fn path_for(&self, service: &str) -> PathBuf {
+        let name: String = service
+            .chars()
+            .map(|ch| {
+                if ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.') {
+                    ch
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{name}.log"))
+    }
+
+    fn rotate(&self, path: &Path) {
+        let suffix = if self.options.gzip { ".gz" } else { "" };
+        let rotated = |index: usize| PathBuf::from(format!("{}.{index}{suffix}", path.display()));
+        let keep = self.options.keep.max(1);
+        // The previous rotation's `.1` must be compressed before it is shifted.
+        self.wait_for_compressor();
+        let _ = fs::remove_file(rotated(keep));
+        for index in (1..keep).rev() {
+            let _ = fs::rename(rotated(index), rotated(index + 1));
+        }
+        let first = PathBuf::from(format!("{}.1", path.display()));
+        if fs::rename(path, &first).is_err() {
+            return;
+        }
+        if self.options.gzip {
+            let handle = thread::spawn(move || {
+                let _ = compress(&first);
+            });
+            *lock(&self.compressor) = Some(handle);
+        }
+    }
+
+    fn wait_for_compressor(&self) {
+        let handle = lock(&self.compressor).take();
+        if let Some(handle) = handle {
+            let _ = handle.join();
+        }
+    }
+
+    fn files(&self) -> MutexGuard<'_, HashMap<String, OpenLog>> {
+        lock(&self.files)
+    }
+}
+
+impl Drop for LogFileSink {
+    fn drop(&mut self) {
+        self.wait_for_compressor();
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+fn compress(path: &Path) -> io::Result<()> {
+    let target = PathBuf::from(format!("{}.gz", path.display()));
+    let mut encoder = GzEncoder::new(File::create(target)?, Compression::default());
+    io::copy(&mut File::open(path)?, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    fs::remove_file(path)
+}
+
+fn open_log(path: &Path) -> std::io::Result<OpenLog> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let written = file.metadata().map_or(0, |meta| meta.len());
+    Ok(OpenLog { file, written })
+}
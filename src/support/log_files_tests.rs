@@ -0,0 +1,74 @@
+use std::fs;
+use std::io::Read;
+
+use flate2::read::GzDecoder;
+
+use super::args::parse_byte_size;
+use super::log_files::{LogFileOptions, LogFileSink};
+
+#[test]
+fn rotates_when_file_exceeds_max_size() {
+    let dir = std::env::temp_dir().join(format!("sanelens-log-files-{}", std::process::id()));
+    let options = LogFileOptions {
+        dir: dir.clone(),
+        max_bytes: 16,
+        keep: 2,
+        gzip: false,
+    };
+    let sink = LogFileSink::new(options, "run_test");
+    assert!(sink.is_ok());
+    if let Ok(sink) = sink {
+        for line in ["first line", "second line", "third line", "fourth line"] {
+            sink.write("api/web", line, None);
+        }
+    }
+    let logs = dir.join("run_test").join("logs");
+    let current = fs::read_to_string(logs.join("api_web.log")).unwrap_or_default();
+    let previous = fs::read_to_string(logs.join("api_web.log.1")).unwrap_or_default();
+    let oldest = fs::read_to_string(logs.join("api_web.log.2")).unwrap_or_default();
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(current, "fourth line\n");
+    assert_eq!(previous, "third line\n");
+    assert_eq!(oldest, "second line\n");
+}
+
+#[test]
+fn parses_byte_sizes() {
+    assert_eq!(parse_byte_size("512"), Some(512));
+    assert_eq!(parse_byte_size("10M"), Some(10 * 1024 * 1024));
+    assert_eq!(parse_byte_size("1gb"), Some(1024 * 1024 * 1024));
+    assert_eq!(parse_byte_size("ten"), None);
+}
+
+#[test]
+fn compresses_rotated_files_in_the_background() {
+    let dir = std::env::temp_dir().join(format!("sanelens-log-gzip-{}", std::process::id()));
+    let options = LogFileOptions {
+        dir: dir.clone(),
+        max_bytes: 16,
+        keep: 2,
+        gzip: true,
+    };
+    let sink = LogFileSink::new(options, "run_test");
+    assert!(sink.is_ok());
+    if let Ok(sink) = sink {
+        for line in ["first line", "second line", "third line"] {
+            sink.write("api", line, None);
+        }
+    }
+    let logs = dir.join("run_test").join("logs");
+    let unpack = |name: &str| {
+        let mut text = String::new();
+        fs::File::open(logs.join(name))
+            .and_then(|file| GzDecoder::new(file).read_to_string(&mut text))
+            .map(|_| text)
+            .unwrap_or_default()
+    };
+    let previous = unpack("api.log.1.gz");
+    let oldest = unpack("api.log.2.gz");
+    let leftover = logs.join("api.log.1").exists();
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(previous, "second line\n");
+    assert_eq!(oldest, "first line\n");
+    assert!(!leftover);
+}
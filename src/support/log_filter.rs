@@ -0,0 +1,194 @@
+use serde::Serialize;
+
+use crate::domain::{split_identity, OutputStream};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl LogLevel {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "trace" | "10" => Some(Self::Trace),
+            "debug" | "20" => Some(Self::Debug),
+            "info" | "notice" | "30" => Some(Self::Info),
+            "warn" | "warning" | "40" => Some(Self::Warn),
+            "error" | "err" | "50" => Some(Self::Error),
+            "fatal" | "critical" | "panic" | "60" => Some(Self::Fatal),
+            _ => None,
+        }
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Trace => "trace",
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+            Self::Fatal => "fatal",
+        }
+    }
+}
+
+const LEVEL_SCAN_TOKENS: usize = 6;
+
+pub fn detect_level(line: &str) -> Option<LogLevel> {
+    let first = line.lines().next().unwrap_or_default();
+    if let Some(level) = keyed_level(first) {
+        return Some(level);
+    }
+    first
+        .split(|ch: char| !ch.is_ascii_alphabetic())
+        .filter(|word| !word.is_empty())
+        .take(LEVEL_SCAN_TOKENS)
+        .find_map(LogLevel::parse)
+}
+
+fn keyed_level(line: &str) -> Option<LogLevel> {
+    let lower = line.to_ascii_lowercase();
+    ["\"level\":", "level=", "\"severity\":", "severity="]
+        .iter()
+        .find_map(|key| lower.find(key).map(|idx| idx + key.len()))
+        .and_then(|start| lower.get(start..))
+        .and_then(|rest| {
+            rest.trim_start_matches([' ', '"'])
+                .split(|ch: char| !ch.is_ascii_alphanumeric())
+                .next()
+        })
+        .and_then(LogLevel::parse)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LogFilter {
+    pub services: Vec<String>,
+    pub grep: Vec<String>,
+    pub min_level: Option<LogLevel>,
+    pub stream: Option<OutputStream>,
+}
+
+impl LogFilter {
+    pub fn includes_service(&self, service: &str) -> bool {
+        let base = split_identity(service).0;
+        self.services.is_empty()
+            || self
+                .services
+                .iter()
+                .any(|name| name == service || name == base)
+    }
+
+    pub fn includes_stream(&self, stream: OutputStream) -> bool {
+        self.stream.is_none_or(|wanted| wanted == stream)
+    }
+
+    pub fn matches(&self, service: &str, line: &str) -> bool {
+        if !self.includes_service(service) {
+            return false;
+        }
+        if !self.grep.is_empty() {
+            let lower = line.to_lowercase();
+            if !self.grep.iter().any(|token| lower.contains(token.as_str())) {
+                return false;
+            }
+        }
+        self.min_level
+            .is_none_or(|min| detect_level(line).is_some_and(|level| level >= min))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct RuntimeLogFilter {
+    pub muted: Vec<String>,
+    pub min_level: Option<LogLevel>,
+    pub grep: Vec<String>,
+}
+
+impl RuntimeLogFilter {
+    pub const fn is_empty(&self) -> bool {
+        self.muted.is_empty() && self.min_level.is_none() && self.grep.is_empty()
+    }
+
+    pub fn allows(&self, service: &str, line: &str) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        let base = split_identity(service).0;
+        if self
+            .muted
+            .iter()
+            .any(|name| name == service || name == base)
+        {
+            return false;
+        }
+        let filter = LogFilter {
+            grep: self.grep.clone(),
+            min_level: self.min_level,
+            ..LogFilter::default()
+        };
+        filter.matches(service, line)
+    }
+
+    pub fn update(&mut self, payload: &serde_json::Value) -> Result<(), String> {
+        let object = payload
+            .as_object()
+            .ok_or_else(|| "Filter update must be a JSON object.".to_string())?;
+        let mut next = self.clone();
+        if let Some(value) = object.get("muted") {
+            next.muted = string_list(value, "muted")?;
+        }
+        if let Some(value) = object.get("grep") {
+            next.grep = string_list(value, "grep")?
+                .iter()
+                .map(|token| token.to_lowercase())
+                .collect();
+        }
+        match object.get("min_level") {
+            None => {}
+            Some(serde_json::Value::Null) => next.min_level = None,
+            Some(value) => {
+                let level = value.as_str().and_then(LogLevel::parse);
+                next.min_level = Some(level.ok_or_else(|| {
+                    format!(
+                        "Invalid min_level {value}. Use trace, debug, info, warn, error or fatal."
+                    )
+                })?);
+            }
+        }
+        for service in string_list(
+            object.get("mute").unwrap_or(&serde_json::Value::Null),
+            "mute",
+        )? {
+            if !next.muted.contains(&service) {
+                next.muted.push(service);
+            }
+        }
+        let unmute = string_list(
+            object.get("unmute").unwrap_or(&serde_json::Value::Null),
+            "unmute",
+        )?;
+        next.muted.retain(|service| !unmute.contains(service));
+        *self = next;
+        Ok(())
+    }
+}
+
+fn string_list(value: &serde_json::Value, field: &str) -> Result<Vec<String>, String> {
+    let invalid = || format!("{field} must be a list of strings.");
+    match value {
+        serde_json::Value::Null => Ok(Vec::new()),
+        serde_json::Value::String(item) => Ok(vec![item.clone()]),
+        serde_json::Value::Array(items) => items
+            .iter()
+            .map(|item| item.as_str().map(str::to_string).ok_or_else(invalid))
+            .filter(|item| item.as_ref().map_or(true, |item| !item.trim().is_empty()))
+            .collect(),
+        _ => Err(invalid()),
+    }
+}
@@ -0,0 +1,86 @@
+use super::args::take_options;
+use super::log_filter::{detect_level, LogFilter, LogLevel, RuntimeLogFilter};
+use crate::domain::OutputStream;
+
+#[test]
+fn detects_levels_in_common_formats() {
+    assert_eq!(
+        detect_level("WARN  cache miss for key"),
+        Some(LogLevel::Warn)
+    );
+    assert_eq!(
+        detect_level("time=2026-01-08T01:14:41Z level=error msg=\"boom\""),
+        Some(LogLevel::Error)
+    );
+    assert_eq!(
+        detect_level("{\"level\":30,\"msg\":\"Request completed\"}"),
+        Some(LogLevel::Info)
+    );
+    assert_eq!(detect_level("listening on :8080"), None);
+}
+
+#[test]
+fn filter_applies_service_grep_and_level() {
+    let filter = LogFilter {
+        services: vec!["api".to_string()],
+        grep: vec!["timeout".to_string()],
+        min_level: Some(LogLevel::Warn),
+        stream: None,
+    };
+    assert!(filter.matches("api", "ERROR upstream Timeout after 5s"));
+    assert!(!filter.matches("db", "ERROR upstream timeout after 5s"));
+    assert!(!filter.matches("api", "INFO request timeout raised"));
+    assert!(!filter.matches("api", "ERROR connection refused"));
+}
+
+#[test]
+fn filter_selects_output_stream() {
+    let filter = LogFilter {
+        stream: OutputStream::parse("stderr"),
+        ..LogFilter::default()
+    };
+    assert!(filter.includes_stream(OutputStream::Stderr));
+    assert!(!filter.includes_stream(OutputStream::Stdout));
+    assert!(LogFilter::default().includes_stream(OutputStream::Stdout));
+}
+
+#[test]
+fn repeated_options_are_collected() {
+    let args: Vec<String> = [
+        "run-1",
+        "--service",
+        "api,web",
+        "--service=db",
+        "--grep",
+        "x",
+    ]
+    .iter()
+    .map(ToString::to_string)
+    .collect();
+    let (rest, services) = take_options(&args, "--service");
+    assert_eq!(services, vec!["api", "web", "db"]);
+    assert_eq!(rest, vec!["run-1", "--grep", "x"]);
+}
+
+#[test]
+fn runtime_filter_mutes_and_updates_incrementally() {
+    let mut filter = RuntimeLogFilter::default();
+    assert!(filter.allows("api", "anything"));
+    assert!(filter
+        .update(&serde_json::json!({ "mute": ["worker"], "min_level": "warn" }))
+        .is_ok());
+    assert!(!filter.allows("worker[2]", "ERROR boom"));
+    assert!(!filter.allows("api", "INFO ready"));
+    assert!(filter.allows("api", "ERROR boom"));
+
+    assert!(filter
+        .update(&serde_json::json!({ "unmute": "worker", "min_level": null, "grep": ["Boom"] }))
+        .is_ok());
+    assert_eq!(filter.muted, Vec::<String>::new());
+    assert!(filter.allows("worker", "ERROR boom"));
+    assert!(!filter.allows("worker", "ERROR other"));
+    assert!(filter
+        .update(&serde_json::json!({ "min_level": "loud" }))
+        .is_err());
+    assert_eq!(filter.grep, vec!["boom".to_string()]);
+}
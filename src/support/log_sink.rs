@@ -0,0 +1,244 @@
+use std::fs;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+
+use time::{OffsetDateTime, UtcOffset};
+
+use super::log_filter::{detect_level, LogLevel};
+
+pub const SYSLOG_SOCKET: &str = "/dev/log";
+pub const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+const SYSLOG_PORT: u16 = 514;
+const SYSLOG_FACILITY_USER: u8 = 1;
+const APP_NAME_MAX: usize = 48;
+const HOSTNAME_MAX: usize = 255;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LogSinkTarget {
+    SyslogUnix(PathBuf),
+    SyslogUdp(String),
+    Journald(PathBuf),
+}
+
+impl LogSinkTarget {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "Invalid --log-sink {value:?}. Use syslog:///dev/log, syslog://host[:port] or journald://."
+            )
+        };
+        let (scheme, rest) = value.trim().split_once("://").ok_or_else(invalid)?;
+        match scheme {
+            "syslog" if rest.is_empty() => Ok(Self::SyslogUnix(PathBuf::from(SYSLOG_SOCKET))),
+            "syslog" if rest.starts_with('/') => Ok(Self::SyslogUnix(PathBuf::from(rest))),
+            "syslog" => Ok(Self::SyslogUdp(with_default_port(rest))),
+            "journald" if rest.is_empty() => Ok(Self::Journald(PathBuf::from(JOURNALD_SOCKET))),
+            "journald" if rest.starts_with('/') => Ok(Self::Journald(PathBuf::from(rest))),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+fn with_default_port(host: &str) -> String {
+    let bracketed = host.starts_with('[');
+    let has_port = if bracketed {
+        host.contains("]:")
+    } else {
+        host.matches(':').count() == 1
+    };
+    if has_port {
+        host.to_string()
+    } else if !bracketed && host.contains(':') {
+        format!("[{host}]:{SYSLOG_PORT}")
+    } else {
+        format!("{host}:{SYSLOG_PORT}")
+    }
+}
+
+enum Transport {
+    Unix(UnixDatagram, PathBuf),
+    Udp(UdpSocket, SocketAddr),
+}
+
+impl Transport {
+    fn send(&self, payload: &[u8]) {
+        match self {
+            Self::Unix(socket, path) => {
+                if socket.send(payload).is_err() && socket.connect(path).is_ok() {
+                    let _ = socket.send(payload);
+                }
+            }
+            Self::Udp(socket, addr) => {
+                let _ = socket.send_to(payload, addr);
+            }
+        }
+    }
+}
+
+pub struct LogSink {
+    transport: Transport,
+    journald: bool,
+    hostname: String,
+    run_id: String,
+}
+
+impl LogSink {
+    pub fn open(target: &LogSinkTarget, run_id: &str) -> Result<Self, String> {
+        let (transport, journald) = match target {
+            LogSinkTarget::SyslogUnix(path) => (unix_transport(path)?, false),
+            LogSinkTarget::SyslogUdp(host) => (udp_transport(host)?, false),
+            LogSinkTarget::Journald(path) => (unix_transport(path)?, true),
+        };
+        Ok(Self {
+            transport,
+            journald,
+            hostname: local_hostname(),
+            run_id: run_id.to_string(),
+        })
+    }
+
+    pub fn write(&self, service: &str, line: &str, container_ts: Option<&str>) {
+        let severity = severity(detect_level(line));
+        let payload = if self.journald {
+            journald_message(service, severity, line, &self.run_id)
+        } else {
+            let timestamp = syslog_timestamp(container_ts);
+            syslog_message(&SyslogFields {
+                severity,
+                timestamp: &timestamp,
+                hostname: &self.hostname,
+                service,
+                run_id: &self.run_id,
+                line,
+            })
+            .into_bytes()
+        };
+        self.transport.send(&payload);
+    }
+}
+
+fn unix_transport(path: &Path) -> Result<Transport, String> {
+    let socket =
+        UnixDatagram::unbound().map_err(|err| format!("failed to open log sink socket: {err}"))?;
+    socket
+        .connect(path)
+        .map_err(|err| format!("failed to connect log sink {}: {err}", path.display()))?;
+    Ok(Transport::Unix(socket, path.to_path_buf()))
+}
+
+fn udp_transport(host: &str) -> Result<Transport, String> {
+    let addr = host
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .ok_or_else(|| format!("failed to resolve log sink host {host}"))?;
+    let bind = if addr.is_ipv6() {
+        "[::]:0"
+    } else {
+        "0.0.0.0:0"
+    };
+    let socket =
+        UdpSocket::bind(bind).map_err(|err| format!("failed to open log sink socket: {err}"))?;
+    Ok(Transport::Udp(socket, addr))
+}
+
+fn local_hostname() -> String {
+    let hostname = fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .unwrap_or_default();
+    let hostname = printable(hostname.trim(), HOSTNAME_MAX);
+    if hostname.is_empty() {
+        "-".to_string()
+    } else {
+        hostname
+    }
+}
+
+pub const fn severity(level: Option<LogLevel>) -> u8 {
+    match level {
+        Some(LogLevel::Fatal) => 2,
+        Some(LogLevel::Error) => 3,
+        Some(LogLevel::Warn) => 4,
+        Some(LogLevel::Info) | None => 6,
+        Some(LogLevel::Debug | LogLevel::Trace) => 7,
+    }
+}
+
+pub struct SyslogFields<'a> {
+    pub severity: u8,
+    pub timestamp: &'a str,
+    pub hostname: &'a str,
+    pub service: &'a str,
+    pub run_id: &'a str,
+    pub line: &'a str,
+}
+
+pub fn syslog_message(fields: &SyslogFields<'_>) -> String {
+    let priority = SYSLOG_FACILITY_USER * 8 + fields.severity;
+    let app = printable(fields.service, APP_NAME_MAX);
+    let app = if app.is_empty() { "-" } else { app.as_str() };
+    let run_id: String = fields
+        .run_id
+        .chars()
+        .flat_map(|ch| {
+            let escape = matches!(ch, '"' | '\\' | ']').then_some('\\');
+            escape.into_iter().chain(std::iter::once(ch))
+        })
+        .collect();
+    format!(
+        "<{priority}>1 {} {} {app} - - [sanelens@32473 run=\"{run_id}\"] {}",
+        fields.timestamp, fields.hostname, fields.line
+    )
+}
+
+pub fn syslog_timestamp(container_ts: Option<&str>) -> String {
+    let at = container_ts
+        .and_then(|ts| {
+            OffsetDateTime::parse(ts, &time::format_description::well_known::Rfc3339).ok()
+        })
+        .unwrap_or_else(OffsetDateTime::now_utc)
+        .to_offset(UtcOffset::UTC);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+        at.year(),
+        u8::from(at.month()),
+        at.day(),
+        at.hour(),
+        at.minute(),
+        at.second(),
+        at.microsecond()
+    )
+}
+
+pub fn journald_message(service: &str, severity: u8, line: &str, run_id: &str) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(line.len() + 96);
+    let priority = severity.to_string();
+    for (key, value) in [
+        ("MESSAGE", line),
+        ("PRIORITY", priority.as_str()),
+        ("SYSLOG_IDENTIFIER", service),
+        ("SANELENS_SERVICE", service),
+        ("SANELENS_RUN_ID", run_id),
+    ] {
+        payload.extend_from_slice(key.as_bytes());
+        if value.contains('\n') {
+            payload.push(b'\n');
+            payload.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            payload.push(b'=');
+        }
+        payload.extend_from_slice(value.as_bytes());
+        payload.push(b'\n');
+    }
+    payload
+}
+
+fn printable(value: &str, max: usize) -> String {
+    value
+        .chars()
+        .map(|ch| if ch.is_ascii_graphic() { ch } else { '_' })
+        .take(max)
+        .collect()
+}
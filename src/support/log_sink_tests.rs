@@ -0,0 +1,108 @@
+use std::net::UdpSocket;
+use std::os::unix::net::UnixDatagram;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::log_sink::{
+    journald_message, syslog_message, syslog_timestamp, LogSink, LogSinkTarget, SyslogFields,
+};
+
+#[test]
+fn parses_sink_targets() {
+    assert_eq!(
+        LogSinkTarget::parse("syslog://"),
+        Ok(LogSinkTarget::SyslogUnix(PathBuf::from("/dev/log")))
+    );
+    assert_eq!(
+        LogSinkTarget::parse("syslog:///var/run/log"),
+        Ok(LogSinkTarget::SyslogUnix(PathBuf::from("/var/run/log")))
+    );
+    assert_eq!(
+        LogSinkTarget::parse("syslog://logs.internal"),
+        Ok(LogSinkTarget::SyslogUdp("logs.internal:514".to_string()))
+    );
+    assert_eq!(
+        LogSinkTarget::parse("syslog://10.0.0.5:1514"),
+        Ok(LogSinkTarget::SyslogUdp("10.0.0.5:1514".to_string()))
+    );
+    assert_eq!(
+        LogSinkTarget::parse("syslog://::1"),
+        Ok(LogSinkTarget::SyslogUdp("[::1]:514".to_string()))
+    );
+    assert_eq!(
+        LogSinkTarget::parse("journald://"),
+        Ok(LogSinkTarget::Journald(PathBuf::from(
+            "/run/systemd/journal/socket"
+        )))
+    );
+    assert!(LogSinkTarget::parse("kafka://broker").is_err());
+    assert!(LogSinkTarget::parse("/dev/log").is_err());
+}
+
+#[test]
+fn formats_rfc5424_and_journald_payloads() {
+    assert_eq!(
+        syslog_timestamp(Some("2023-11-14T22:13:20.123456789Z")),
+        "2023-11-14T22:13:20.123456Z"
+    );
+    let message = syslog_message(&SyslogFields {
+        severity: 3,
+        timestamp: "2023-11-14T22:13:20.123456Z",
+        hostname: "devbox",
+        service: "api gateway",
+        run_id: "shop-1a2b",
+        line: "ERROR boom",
+    });
+    assert_eq!(
+        message,
+        "<11>1 2023-11-14T22:13:20.123456Z devbox api_gateway - - [sanelens@32473 run=\"shop-1a2b\"] ERROR boom"
+    );
+    let payload = journald_message("api", 4, "warn: slow\nat line 2", "shop-1a2b");
+    let mut expected = b"MESSAGE\n".to_vec();
+    expected.extend_from_slice(&20_u64.to_le_bytes());
+    expected.extend_from_slice(b"warn: slow\nat line 2\nPRIORITY=4\nSYSLOG_IDENTIFIER=api\n");
+    expected.extend_from_slice(b"SANELENS_SERVICE=api\nSANELENS_RUN_ID=shop-1a2b\n");
+    assert_eq!(payload, expected);
+}
+
+#[test]
+fn forwards_events_over_udp_and_unix_sockets() {
+    let Ok(server) = UdpSocket::bind("127.0.0.1:0") else {
+        return;
+    };
+    assert!(server
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .is_ok());
+    let target = server
+        .local_addr()
+        .map(|addr| LogSinkTarget::SyslogUdp(addr.to_string()));
+    let sink = target
+        .and_then(|target| LogSink::open(&target, "shop-1a2b").map_err(std::io::Error::other));
+    assert!(sink.is_ok());
+    if let Ok(sink) = &sink {
+        sink.write("db", "FATAL disk full", None);
+    }
+    let mut buffer = [0_u8; 512];
+    let received = server
+        .recv(&mut buffer)
+        .map(|len| String::from_utf8_lossy(buffer.get(..len).unwrap_or_default()).into_owned());
+    assert!(received
+        .is_ok_and(|text| text.starts_with("<10>1 ") && text.ends_with("] FATAL disk full")));
+
+    let path = std::env::temp_dir().join(format!("sanelens-journal-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let Ok(journal) = UnixDatagram::bind(&path) else {
+        return;
+    };
+    let sink = LogSink::open(&LogSinkTarget::Journald(path.clone()), "shop-1a2b");
+    if let Ok(sink) = &sink {
+        sink.write("api", "debug cache miss", None);
+    }
+    let received = journal
+        .recv(&mut buffer)
+        .map(|len| buffer.get(..len).unwrap_or_default().to_vec());
+    let _ = std::fs::remove_file(&path);
+    assert!(sink.is_ok());
+    assert!(received
+        .is_ok_and(|payload| payload.starts_with(b"MESSAGE=debug cache miss\nPRIORITY=7\n")));
+}
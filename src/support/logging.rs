@@ -1,60 +1,455 @@
-use crossbeam_channel::{bounded, Receiver, Sender, TrySendError};
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender, TrySendError};
 use std::borrow::Cow;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::io::{BufRead, BufReader, Read, Write};
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::{Arc, Mutex, MutexGuard};
-use std::time::{Duration, Instant};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use crate::domain::LogEvent;
+use crate::domain::{split_identity, LogEvent, LogKind, OutputStream};
 use crate::support::constants::CLIENT_QUEUE_SIZE;
-use crate::support::multiline::MultilineAggregator;
+use crate::support::log_files::LogFileSink;
+use crate::support::log_filter::{detect_level, LogFilter, LogLevel, RuntimeLogFilter};
+use crate::support::log_sink::LogSink;
+use crate::support::multiline::{
+    format_epoch_millis_rfc3339, parse_rfc3339_to_epoch_millis, AggregatedEvent,
+    MultilineAggregator,
+};
+use crate::support::skew::{format_skew, ClockSkewPolicy, ClockSkewTracker, SkewChange};
+use crate::support::timeline::{
+    ServiceCounts, TimelineIndex, TimelineWindow, TIMELINE_EVENT_LIMIT,
+};
+
+pub const DEFAULT_LOG_REORDER: Duration = Duration::from_secs(2);
+const REORDER_TICK: Duration = Duration::from_millis(200);
+pub const ANNOTATION_SERVICE: &str = "annotations";
+
+struct PendingEvent {
+    sort_ms: i64,
+    arrival: u64,
+    service: String,
+    stream: OutputStream,
+    container_ts: Option<String>,
+    ts_ms: Option<i64>,
+    line: String,
+    repeat_count: Option<u64>,
+    annotation: bool,
+    exception: Option<String>,
+}
+
+struct RepeatFold {
+    line: String,
+    stream: OutputStream,
+    last_ms: i64,
+    repeats: u64,
+    container_ts: Option<String>,
+    ts_ms: Option<i64>,
+}
+
+impl RepeatFold {
+    fn new(event: &PendingEvent) -> Self {
+        Self {
+            line: event.line.clone(),
+            stream: event.stream,
+            last_ms: event.sort_ms,
+            repeats: 0,
+            container_ts: event.container_ts.clone(),
+            ts_ms: event.ts_ms,
+        }
+    }
+
+    fn summary(self, service: &str) -> Option<PendingEvent> {
+        (self.repeats > 0).then(|| PendingEvent {
+            sort_ms: self.last_ms,
+            arrival: 0,
+            service: service.to_string(),
+            stream: self.stream,
+            container_ts: self.container_ts,
+            ts_ms: self.ts_ms,
+            line: self.line,
+            repeat_count: Some(self.repeats),
+            annotation: false,
+            exception: None,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct StdoutFormat {
+    pub prefix: String,
+    pub color_prefix: String,
+    pub color_reset: String,
+}
+
+pub struct LogSnapshot {
+    pub events: Vec<LogEvent>,
+    pub resumed: bool,
+}
 
 struct LogHubState {
     history: VecDeque<LogEvent>,
     clients: Vec<(usize, Sender<LogEvent>)>,
     next_client_id: usize,
+    pending: Vec<PendingEvent>,
+    next_arrival: u64,
+    stdout_formats: HashMap<String, StdoutFormat>,
+    folds: HashMap<String, RepeatFold>,
+    line_counts: BTreeMap<String, u64>,
+    timeline: TimelineIndex,
+}
+
+impl LogHubState {
+    fn fold_repeats(
+        &mut self,
+        due: Vec<PendingEvent>,
+        window_ms: i64,
+        expire_before: i64,
+    ) -> Vec<PendingEvent> {
+        let mut released = Vec::with_capacity(due.len());
+        for event in due {
+            if event.annotation {
+                released.push(event);
+                continue;
+            }
+            if let Some(fold) = self.folds.get_mut(&event.service).filter(|fold| {
+                fold.line == event.line
+                    && fold.stream == event.stream
+                    && event.sort_ms.saturating_sub(fold.last_ms) <= window_ms
+            }) {
+                fold.repeats += 1;
+                fold.last_ms = event.sort_ms;
+                fold.container_ts = event.container_ts;
+                fold.ts_ms = event.ts_ms;
+                continue;
+            }
+            let previous = self
+                .folds
+                .insert(event.service.clone(), RepeatFold::new(&event));
+            released.extend(previous.and_then(|fold| fold.summary(&event.service)));
+            released.push(event);
+        }
+        let expired: Vec<String> = self
+            .folds
+            .iter()
+            .filter(|(_, fold)| fold.last_ms.saturating_add(window_ms) < expire_before)
+            .map(|(service, _)| service.clone())
+            .collect();
+        for service in expired {
+            released.extend(
+                self.folds
+                    .remove(&service)
+                    .and_then(|fold| fold.summary(&service)),
+            );
+        }
+        released
+    }
 }
 
 pub struct LogHub {
     state: Mutex<LogHubState>,
+    release: Mutex<()>,
     seq: AtomicU64,
     history_size: usize,
+    reorder_window_ms: i64,
+    dedup_window_ms: AtomicI64,
+    annotation_sink: Mutex<Option<Arc<LogFileSink>>>,
+    skew: Mutex<ClockSkewTracker>,
+    runtime_filter: Mutex<RuntimeLogFilter>,
 }
 
 impl LogHub {
-    pub fn new(history_size: usize) -> Self {
+    pub fn with_reorder(history_size: usize, window: Duration) -> Arc<Self> {
+        let hub = Arc::new(Self::with_window(history_size, window));
+        if hub.reorders() {
+            let weak = Arc::downgrade(&hub);
+            thread::spawn(move || release_loop(&weak));
+        }
+        hub
+    }
+
+    fn with_window(history_size: usize, window: Duration) -> Self {
         Self {
             state: Mutex::new(LogHubState {
                 history: VecDeque::with_capacity(history_size),
                 clients: Vec::new(),
                 next_client_id: 1,
+                pending: Vec::new(),
+                next_arrival: 0,
+                stdout_formats: HashMap::new(),
+                folds: HashMap::new(),
+                line_counts: BTreeMap::new(),
+                timeline: TimelineIndex::default(),
             }),
+            release: Mutex::new(()),
             seq: AtomicU64::new(0),
             history_size,
+            reorder_window_ms: i64::try_from(window.as_millis()).unwrap_or(i64::MAX),
+            dedup_window_ms: AtomicI64::new(0),
+            annotation_sink: Mutex::new(None),
+            skew: Mutex::new(ClockSkewTracker::new(ClockSkewPolicy::default())),
+            runtime_filter: Mutex::new(RuntimeLogFilter::default()),
+        }
+    }
+
+    pub fn set_dedup(self: &Arc<Self>, window: Duration) {
+        let window_ms = i64::try_from(window.as_millis()).unwrap_or(i64::MAX);
+        let was_buffering = self.buffers();
+        self.dedup_window_ms.store(window_ms, Ordering::SeqCst);
+        if !was_buffering && window_ms > 0 {
+            let weak = Arc::downgrade(self);
+            thread::spawn(move || release_loop(&weak));
+        }
+    }
+
+    pub fn set_clock_skew(&self, policy: ClockSkewPolicy) {
+        *self.skew() = ClockSkewTracker::new(policy);
+    }
+
+    pub const fn reorders(&self) -> bool {
+        self.reorder_window_ms > 0
+    }
+
+    fn dedup_window_ms(&self) -> i64 {
+        self.dedup_window_ms.load(Ordering::SeqCst)
+    }
+
+    pub fn buffers(&self) -> bool {
+        self.reorders() || self.dedup_window_ms() > 0
+    }
+
+    pub fn set_stdout_format(&self, service: &str, format: StdoutFormat) {
+        self.state()
+            .stdout_formats
+            .insert(service_key(service), format);
+    }
+
+    pub fn publish(
+        &self,
+        service: &str,
+        stream: OutputStream,
+        line: &str,
+        container_ts: Option<&str>,
+    ) {
+        let now_ms = epoch_millis_now();
+        let mut event = pending_event(service, stream, line, container_ts, now_ms);
+        self.track_skew(&mut event, now_ms);
+        self.enqueue(event, now_ms);
+    }
+
+    pub fn publish_aggregated(&self, service: &str, stream: OutputStream, event: &AggregatedEvent) {
+        let now_ms = epoch_millis_now();
+        let mut pending = pending_event(
+            service,
+            stream,
+            &event.line,
+            event.container_ts.as_deref(),
+            now_ms,
+        );
+        pending.exception.clone_from(&event.exception);
+        self.track_skew(&mut pending, now_ms);
+        self.enqueue(pending, now_ms);
+    }
+
+    fn track_skew(&self, event: &mut PendingEvent, received_ms: i64) {
+        let Some(ts_ms) = event.ts_ms else {
+            return;
+        };
+        let mut skew = self.skew();
+        let change = skew.observe(&event.service, ts_ms, received_ms);
+        let correction = skew.correction(&event.service);
+        let policy = skew.policy();
+        drop(skew);
+        if correction != 0 {
+            event.ts_ms = Some(ts_ms.saturating_sub(correction));
+            event.sort_ms = ts_ms.saturating_sub(correction).min(received_ms);
+        }
+        let text = match change {
+            Some(SkewChange::Detected(skew_ms)) if policy.correct => format!(
+                "clock skew of {} against the host detected; correcting timestamps",
+                format_skew(skew_ms)
+            ),
+            Some(SkewChange::Detected(skew_ms)) => format!(
+                "clock skew of {} against the host detected; the merged timeline may be misordered",
+                format_skew(skew_ms)
+            ),
+            Some(SkewChange::Cleared) => "clock skew back within threshold".to_string(),
+            None => return,
+        };
+        self.annotate(Some(&event.service), &text, None);
+    }
+
+    fn skew(&self) -> MutexGuard<'_, ClockSkewTracker> {
+        self.skew
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    pub fn runtime_filter(&self) -> RuntimeLogFilter {
+        self.filter().clone()
+    }
+
+    pub fn update_runtime_filter(
+        &self,
+        payload: &serde_json::Value,
+    ) -> Result<RuntimeLogFilter, String> {
+        let mut filter = self.filter();
+        filter.update(payload)?;
+        Ok(filter.clone())
+    }
+
+    pub fn shows(&self, service: &str, line: &str) -> bool {
+        self.filter().allows(service, line)
+    }
+
+    fn shows_event(&self, event: &LogEvent) -> bool {
+        event.annotation || self.shows(&event.service, &event.line)
+    }
+
+    fn filter(&self) -> MutexGuard<'_, RuntimeLogFilter> {
+        self.runtime_filter
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    pub fn set_annotation_sink(&self, sink: Option<Arc<LogFileSink>>) {
+        *self
+            .annotation_sink
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = sink;
+    }
+
+    pub fn annotate(&self, service: Option<&str>, text: &str, ts_ms: Option<i64>) {
+        let now_ms = epoch_millis_now();
+        let container_ts = format_epoch_millis_rfc3339(ts_ms.unwrap_or(now_ms));
+        let service = service.unwrap_or(ANNOTATION_SERVICE);
+        let sink = self
+            .annotation_sink
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone();
+        if let Some(sink) = sink {
+            sink.write(
+                service,
+                &format!("[annotation] {text}"),
+                container_ts.as_deref(),
+            );
+        }
+        let mut event = pending_event(
+            service,
+            OutputStream::Stdout,
+            text,
+            container_ts.as_deref(),
+            now_ms,
+        );
+        event.annotation = true;
+        self.enqueue(event, now_ms);
+    }
+
+    #[cfg(test)]
+    pub fn publish_at(&self, service: &str, line: &str, container_ts: Option<&str>, now_ms: i64) {
+        let mut event = pending_event(service, OutputStream::Stdout, line, container_ts, now_ms);
+        self.track_skew(&mut event, now_ms);
+        self.enqueue(event, now_ms);
+    }
+
+    pub fn line_counts(&self) -> BTreeMap<String, u64> {
+        self.state().line_counts.clone()
+    }
+
+    fn enqueue(&self, mut event: PendingEvent, now_ms: i64) {
+        let mut state = self.state();
+        if !event.annotation {
+            *state.line_counts.entry(event.service.clone()).or_default() += 1;
+        }
+        event.arrival = state.next_arrival;
+        state.next_arrival += 1;
+        state.pending.push(event);
+        drop(state);
+        if !self.reorders() {
+            self.release(i64::MAX, now_ms);
         }
     }
 
-    pub fn publish(&self, service: &str, line: &str, container_ts: Option<&str>) {
+    pub fn release_due(&self, now_ms: i64) {
+        self.release(now_ms.saturating_sub(self.reorder_window_ms), now_ms);
+    }
+
+    fn release(&self, watermark: i64, now_ms: i64) {
+        let dedup_window_ms = self.dedup_window_ms();
+        let _release = self
+            .release
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let (due, formats) = {
+            let mut state = self.state();
+            if state.pending.is_empty() && state.folds.is_empty() {
+                return;
+            }
+            let (mut due, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut state.pending)
+                .into_iter()
+                .partition(|event| event.sort_ms <= watermark);
+            state.pending = rest;
+            due.sort_by_key(|event| (event.sort_ms, event.arrival));
+            if dedup_window_ms > 0 {
+                let expire_before = now_ms.saturating_sub(self.reorder_window_ms);
+                due = state.fold_repeats(due, dedup_window_ms, expire_before);
+            }
+            (due, state.stdout_formats.clone())
+        };
+        for pending in due {
+            let shown = pending.annotation || self.shows(&pending.service, &pending.line);
+            if let Some(format) = formats.get(&pending.service).filter(|_| shown) {
+                let line = pending.repeat_count.map_or_else(
+                    || Cow::Borrowed(pending.line.as_str()),
+                    |count| Cow::Owned(repeat_notice(count)),
+                );
+                emit_entries(format, pending.stream, &line);
+            }
+            self.dispatch(pending);
+        }
+    }
+
+    pub fn flush(&self) {
+        self.release(i64::MAX, i64::MAX);
+    }
+
+    pub fn replay(&self, mut event: LogEvent) {
+        event.seq = self.seq.fetch_add(1, Ordering::SeqCst) + 1;
+        self.broadcast(&event);
+    }
+
+    fn dispatch(&self, pending: PendingEvent) {
         let seq = self.seq.fetch_add(1, Ordering::SeqCst) + 1;
-        let event = LogEvent {
+        let instance = split_identity(&pending.service).1;
+        self.broadcast(&LogEvent {
             seq,
-            service: if service.is_empty() {
-                "unknown".to_string()
-            } else {
-                service.to_string()
-            },
-            container_ts: container_ts.map(ToString::to_string),
-            line: line.to_string(),
-        };
+            service: pending.service,
+            container_ts: pending.container_ts,
+            ts_ms: pending.ts_ms,
+            line: pending.line,
+            stream: pending.stream,
+            repeat_count: pending.repeat_count,
+            annotation: pending.annotation,
+            instance,
+            kind: pending.exception.is_some().then_some(LogKind::Error),
+            exception: pending.exception,
+        });
+    }
+
+    fn broadcast(&self, event: &LogEvent) {
         let clients = {
             let mut state = self.state();
+            record_timeline(&mut state.timeline, event);
             state.history.push_back(event.clone());
             while state.history.len() > self.history_size {
                 state.history.pop_front();
             }
             state.clients.clone()
         };
+        if !self.shows_event(event) {
+            return;
+        }
         let mut disconnected = HashSet::new();
         for (id, sender) in clients {
             match sender.try_send(event.clone()) {
@@ -70,15 +465,87 @@ impl LogHub {
         }
     }
 
+    pub fn recent(&self, service: &str, limit: usize) -> Vec<LogEvent> {
+        let state = self.state();
+        let mut events: Vec<LogEvent> = state
+            .history
+            .iter()
+            .rev()
+            .filter(|event| split_identity(&event.service).0 == service && !event.annotation)
+            .take(limit)
+            .cloned()
+            .collect();
+        drop(state);
+        events.reverse();
+        events
+    }
+
+    pub fn timeline(&self, window: &TimelineWindow) -> BTreeMap<u64, ServiceCounts> {
+        self.state().timeline.counts(window)
+    }
+
+    pub fn window(&self, window: &TimelineWindow) -> (Vec<LogEvent>, bool) {
+        let state = self.state();
+        let Some((first, last)) = state.timeline.seq_range(window) else {
+            return (Vec::new(), false);
+        };
+        let mut events: Vec<LogEvent> = state
+            .history
+            .iter()
+            .filter(|event| (first..=last).contains(&event.seq) && self.shows_event(event))
+            .take(TIMELINE_EVENT_LIMIT + 1)
+            .cloned()
+            .collect();
+        drop(state);
+        let truncated = events.len() > TIMELINE_EVENT_LIMIT;
+        events.truncate(TIMELINE_EVENT_LIMIT);
+        (events, truncated)
+    }
+
+    pub fn history(&self) -> Vec<LogEvent> {
+        self.state().history.iter().cloned().collect()
+    }
+
     pub fn register_client(&self) -> (Receiver<LogEvent>, Vec<LogEvent>) {
+        let (receiver, snapshot) = self.register_client_since(None);
+        (receiver, snapshot.events)
+    }
+
+    pub fn register_lossless_client(&self) -> (Receiver<LogEvent>, Vec<LogEvent>) {
+        let (sender, receiver) = unbounded();
+        (receiver, self.attach_client(sender, None).events)
+    }
+
+    pub fn register_client_since(
+        &self,
+        last_seq: Option<u64>,
+    ) -> (Receiver<LogEvent>, LogSnapshot) {
         let (sender, receiver) = bounded(CLIENT_QUEUE_SIZE);
+        (receiver, self.attach_client(sender, last_seq))
+    }
+
+    fn attach_client(&self, sender: Sender<LogEvent>, last_seq: Option<u64>) -> LogSnapshot {
         let mut state = self.state();
         let id = state.next_client_id;
         state.next_client_id += 1;
         state.clients.push((id, sender));
-        let history = state.history.iter().cloned().collect();
+        let oldest = state.history.front().map(|event| event.seq);
+        let resume_from = last_seq.filter(|seq| {
+            *seq <= self.seq.load(Ordering::SeqCst)
+                && oldest.is_none_or(|oldest| oldest <= seq.saturating_add(1))
+        });
+        let events = state
+            .history
+            .iter()
+            .filter(|event| resume_from.is_none_or(|seq| event.seq > seq))
+            .filter(|event| self.shows_event(event))
+            .cloned()
+            .collect();
         drop(state);
-        (receiver, history)
+        LogSnapshot {
+            events,
+            resumed: resume_from.is_some(),
+        }
     }
 
     fn state(&self) -> MutexGuard<'_, LogHubState> {
@@ -88,12 +555,78 @@ impl LogHub {
     }
 }
 
+fn record_timeline(timeline: &mut TimelineIndex, event: &LogEvent) {
+    let at_ms = u64::try_from(event.ts_ms.unwrap_or_else(epoch_millis_now)).unwrap_or(0);
+    timeline.mark(at_ms, event.seq);
+    if event.annotation {
+        return;
+    }
+    let level = detect_level(&event.line).map_or("other", LogLevel::as_str);
+    let service = split_identity(&event.service).0;
+    timeline.record(at_ms, service, level, event.repeat_count.unwrap_or(1));
+}
+
+fn release_loop(hub: &Weak<LogHub>) {
+    while let Some(hub) = hub.upgrade() {
+        hub.release_due(epoch_millis_now());
+        drop(hub);
+        thread::sleep(REORDER_TICK);
+    }
+}
+
+fn pending_event(
+    service: &str,
+    stream: OutputStream,
+    line: &str,
+    container_ts: Option<&str>,
+    now_ms: i64,
+) -> PendingEvent {
+    let ts_ms = container_ts.and_then(parse_rfc3339_to_epoch_millis);
+    PendingEvent {
+        sort_ms: ts_ms.map_or(now_ms, |ts| ts.min(now_ms)),
+        arrival: 0,
+        service: service_key(service),
+        stream,
+        container_ts: container_ts.map(ToString::to_string),
+        ts_ms,
+        line: line.to_string(),
+        repeat_count: None,
+        annotation: false,
+        exception: None,
+    }
+}
+
+fn repeat_notice(count: u64) -> String {
+    let plural = if count == 1 { "" } else { "s" };
+    format!("(last message repeated {count} more time{plural})")
+}
+
+fn service_key(service: &str) -> String {
+    if service.is_empty() {
+        "unknown".to_string()
+    } else {
+        service.to_string()
+    }
+}
+
+fn epoch_millis_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| {
+            i64::try_from(elapsed.as_millis()).unwrap_or(i64::MAX)
+        })
+}
+
 pub struct LogWorkerConfig {
     pub service: String,
+    pub stream: OutputStream,
     pub prefix: String,
     pub color_prefix: String,
     pub color_reset: String,
     pub emit_stdout: bool,
+    pub file_sink: Option<Arc<LogFileSink>>,
+    pub log_sink: Option<Arc<LogSink>>,
+    pub filter: Option<Arc<LogFilter>>,
 }
 
 pub fn log_worker<R: Read>(
@@ -104,11 +637,34 @@ pub fn log_worker<R: Read>(
 ) {
     let LogWorkerConfig {
         service,
+        stream,
         prefix,
         color_prefix,
         color_reset,
         emit_stdout,
+        file_sink,
+        log_sink,
+        filter,
     } = config;
+    let format = StdoutFormat {
+        prefix,
+        color_prefix,
+        color_reset,
+    };
+    let hub_prints = emit_stdout && log_hub.is_some_and(|hub| hub.buffers());
+    if let Some(hub) = log_hub.filter(|_| hub_prints) {
+        hub.set_stdout_format(&service, format.clone());
+    }
+    let stdout = (emit_stdout && !hub_prints).then_some(&format);
+    let outputs = LogOutputs {
+        service: &service,
+        stream,
+        log_hub,
+        file_sink: file_sink.as_ref(),
+        log_sink: log_sink.as_ref(),
+        filter: filter.as_deref(),
+        stdout,
+    };
     let mut reader = BufReader::new(reader);
     let mut buffer = Vec::new();
     let mut aggregator = MultilineAggregator::new(Duration::from_millis(1500));
@@ -131,30 +687,64 @@ pub fn log_worker<R: Read>(
         }
         let line = strip_ansi_codes(&buffer);
         let now = Instant::now();
-        let events = aggregator.push_line(line.as_ref(), now);
-        for event in events {
-            if let Some(hub) = log_hub {
-                hub.publish(&service, &event.line, event.container_ts.as_deref());
-            }
-            if emit_stdout {
-                emit_entries(&prefix, &color_prefix, &color_reset, &event.line);
-            }
+        for event in aggregator.push_line(line.as_ref(), now) {
+            outputs.deliver(&event);
         }
     }
     if let Some(event) = aggregator.flush() {
-        if let Some(hub) = log_hub {
-            hub.publish(&service, &event.line, event.container_ts.as_deref());
+        outputs.deliver(&event);
+    }
+}
+
+struct LogOutputs<'a> {
+    service: &'a str,
+    stream: OutputStream,
+    log_hub: Option<&'a Arc<LogHub>>,
+    file_sink: Option<&'a Arc<LogFileSink>>,
+    log_sink: Option<&'a Arc<LogSink>>,
+    filter: Option<&'a LogFilter>,
+    stdout: Option<&'a StdoutFormat>,
+}
+
+impl LogOutputs<'_> {
+    fn deliver(&self, event: &AggregatedEvent) {
+        if !self.filter.is_none_or(|filter| {
+            filter.includes_stream(self.stream) && filter.matches(self.service, &event.line)
+        }) {
+            return;
+        }
+        let container_ts = event.container_ts.as_deref();
+        if let Some(hub) = self.log_hub {
+            hub.publish_aggregated(self.service, self.stream, event);
+        }
+        if let Some(sink) = self.file_sink {
+            sink.write(self.service, &event.line, container_ts);
         }
-        if emit_stdout {
-            emit_entries(&prefix, &color_prefix, &color_reset, &event.line);
+        if let Some(sink) = self.log_sink {
+            sink.write(self.service, &event.line, container_ts);
+        }
+        if let Some(format) = self.stdout.filter(|_| {
+            self.log_hub
+                .is_none_or(|hub| hub.shows(self.service, &event.line))
+        }) {
+            emit_entries(format, self.stream, &event.line);
         }
     }
 }
 
-fn emit_entries(prefix: &str, color_prefix: &str, color_reset: &str, line: &str) {
+fn emit_entries(format: &StdoutFormat, stream: OutputStream, line: &str) {
+    let StdoutFormat {
+        prefix,
+        color_prefix,
+        color_reset,
+    } = format;
+    let marker = stream.marker();
     let mut stdout = std::io::stdout();
     for entry in line.split('\n') {
-        let _ = writeln!(stdout, "{color_prefix}{prefix}{color_reset} | {entry}");
+        let _ = writeln!(
+            stdout,
+            "{color_prefix}{prefix}{color_reset} {marker} {entry}"
+        );
     }
 }
 
@@ -166,7 +756,7 @@ pub fn strip_ansi_codes(input: &[u8]) -> Cow<'_, str> {
             Cow::Borrowed,
         );
     }
-    let stripped = if input.iter().any(|byte| *byte == 0x9b) {
+    let stripped = if input.contains(&0x9b) {
         let mut normalized = Vec::with_capacity(input.len() + 8);
         for &byte in input {
             if byte == 0x9b {
@@ -1,4 +1,14 @@
-use super::logging::strip_ansi_codes;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use super::logging::{log_worker, strip_ansi_codes, LogHub, LogWorkerConfig, ANNOTATION_SERVICE};
+use crate::domain::OutputStream;
+use crate::support::constants::CLIENT_QUEUE_SIZE;
+use crate::support::skew::ClockSkewPolicy;
 
 #[test]
 fn strips_sgr_sequences() {
@@ -25,3 +35,271 @@ fn strips_single_byte_csi_sequences() {
     ];
     assert_eq!(strip_ansi_codes(&input), "warn");
 }
+
+#[test]
+fn reorders_events_by_container_timestamp() {
+    let hub = LogHub::with_reorder(16, Duration::from_secs(2));
+    let (receiver, _) = hub.register_client();
+    let base = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| i64::try_from(elapsed.as_millis()).unwrap_or(0));
+    let ts = |ms: i64| {
+        OffsetDateTime::from_unix_timestamp_nanos(i128::from(ms) * 1_000_000)
+            .ok()
+            .and_then(|at| at.format(&Rfc3339).ok())
+    };
+    hub.publish_at("api", "second", ts(base + 1000).as_deref(), base + 1500);
+    hub.publish_at("db", "first", ts(base + 500).as_deref(), base + 1500);
+    hub.release_due(base + 2000);
+    assert!(receiver.try_recv().is_err());
+    hub.release_due(base + 5000);
+    let lines: Vec<String> = receiver.try_iter().map(|event| event.line).collect();
+    assert_eq!(lines, vec!["first".to_string(), "second".to_string()]);
+}
+
+#[test]
+fn clocks_running_ahead_are_held_for_the_reorder_window_only() {
+    let hub = LogHub::with_reorder(16, Duration::from_secs(2));
+    let (receiver, _) = hub.register_client();
+    let base = 1_700_000_000_000;
+    hub.publish_at(
+        "api",
+        "from the future",
+        Some("2023-11-14T22:23:20.000Z"),
+        base,
+    );
+    hub.release_due(base + 2000);
+    let lines: Vec<String> = receiver.try_iter().map(|event| event.line).collect();
+    assert_eq!(lines, vec!["from the future".to_string()]);
+}
+
+#[test]
+fn folds_identical_consecutive_events() {
+    let hub = LogHub::with_reorder(16, Duration::ZERO);
+    hub.set_dedup(Duration::from_secs(5));
+    let (receiver, _) = hub.register_client();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| i64::try_from(elapsed.as_millis()).unwrap_or(0));
+    for offset in 0..3 {
+        hub.publish_at("api", "panic: boom", None, now + offset);
+    }
+    hub.publish_at("db", "ready", None, now + 3);
+    hub.publish_at("api", "restarting", None, now + 4);
+    hub.flush();
+    let events: Vec<(String, Option<u64>)> = receiver
+        .try_iter()
+        .map(|event| (event.line, event.repeat_count))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            ("panic: boom".to_string(), None),
+            ("ready".to_string(), None),
+            ("panic: boom".to_string(), Some(2)),
+            ("restarting".to_string(), None),
+        ]
+    );
+}
+
+#[test]
+fn events_keep_their_output_stream() {
+    let hub = LogHub::with_reorder(16, Duration::ZERO);
+    let (receiver, _) = hub.register_client();
+    hub.publish("api", OutputStream::Stderr, "boom", None);
+    hub.publish("api", OutputStream::Stdout, "ok", None);
+    let streams: Vec<(String, OutputStream)> = receiver
+        .try_iter()
+        .map(|event| (event.line, event.stream))
+        .collect();
+    assert_eq!(
+        streams,
+        vec![
+            ("boom".to_string(), OutputStream::Stderr),
+            ("ok".to_string(), OutputStream::Stdout),
+        ]
+    );
+}
+
+#[test]
+fn annotations_are_merged_into_the_stream() {
+    let hub = LogHub::with_reorder(16, Duration::ZERO);
+    hub.set_dedup(Duration::from_secs(5));
+    let (receiver, _) = hub.register_client();
+    hub.publish("api", OutputStream::Stdout, "clicked", None);
+    hub.annotate(Some("api"), "clicked", Some(1_700_000_000_000));
+    hub.annotate(None, "checkout started", None);
+    hub.flush();
+    let events: Vec<(String, String, bool, Option<i64>)> = receiver
+        .try_iter()
+        .map(|event| (event.service, event.line, event.annotation, event.ts_ms))
+        .collect();
+    assert_eq!(events.len(), 3);
+    assert_eq!(
+        events.get(1),
+        Some(&(
+            "api".to_string(),
+            "clicked".to_string(),
+            true,
+            Some(1_700_000_000_000)
+        ))
+    );
+    assert!(events
+        .get(2)
+        .is_some_and(|(service, _, annotation, _)| service == ANNOTATION_SERVICE && *annotation));
+}
+
+#[test]
+fn scaled_replicas_carry_their_instance() {
+    let hub = LogHub::with_reorder(16, Duration::ZERO);
+    let (receiver, _) = hub.register_client();
+    hub.publish("api[2]", OutputStream::Stdout, "ready", None);
+    hub.publish("db", OutputStream::Stdout, "ready", None);
+    let events: Vec<(String, Option<u32>)> = receiver
+        .try_iter()
+        .map(|event| (event.service, event.instance))
+        .collect();
+    assert_eq!(
+        events,
+        vec![("api[2]".to_string(), Some(2)), ("db".to_string(), None)]
+    );
+}
+
+#[test]
+fn resumes_after_the_last_seen_sequence() {
+    let hub = LogHub::with_reorder(16, Duration::ZERO);
+    for line in ["one", "two", "three"] {
+        hub.publish("api", OutputStream::Stdout, line, None);
+    }
+    let (_, snapshot) = hub.register_client_since(Some(1));
+    let lines: Vec<&str> = snapshot
+        .events
+        .iter()
+        .map(|event| event.line.as_str())
+        .collect();
+    assert!(snapshot.resumed);
+    assert_eq!(lines, vec!["two", "three"]);
+
+    let (_, snapshot) = hub.register_client_since(Some(99));
+    assert!(!snapshot.resumed);
+    assert_eq!(snapshot.events.len(), 3);
+}
+
+#[test]
+fn sends_full_history_when_the_last_seen_sequence_was_evicted() {
+    let hub = LogHub::with_reorder(2, Duration::ZERO);
+    for line in ["one", "two", "three", "four"] {
+        hub.publish("api", OutputStream::Stdout, line, None);
+    }
+    let (_, snapshot) = hub.register_client_since(Some(1));
+    assert!(!snapshot.resumed);
+    assert_eq!(snapshot.events.len(), 2);
+
+    let (_, snapshot) = hub.register_client_since(Some(2));
+    assert!(snapshot.resumed);
+    assert_eq!(snapshot.events.len(), 2);
+}
+
+#[test]
+fn corrects_skewed_timestamps_and_annotates_the_service() {
+    let hub = LogHub::with_reorder(64, Duration::ZERO);
+    hub.set_clock_skew(ClockSkewPolicy {
+        threshold: Duration::from_secs(2),
+        correct: true,
+    });
+    let (receiver, _) = hub.register_client();
+    let base = 1_700_000_000_000;
+    for _ in 0..8 {
+        hub.publish_at("api", "tick", Some("2023-11-14T22:13:30.000Z"), base);
+    }
+    let events: Vec<_> = receiver.try_iter().collect();
+    assert!(events
+        .iter()
+        .any(|event| event.annotation && event.line.contains("+10.000s")));
+    assert_eq!(
+        events
+            .iter()
+            .rev()
+            .find(|event| !event.annotation)
+            .and_then(|event| event.ts_ms),
+        Some(base)
+    );
+}
+
+#[test]
+fn runtime_filter_hides_muted_services_from_clients() {
+    let hub = LogHub::with_reorder(16, Duration::ZERO);
+    let (live, _) = hub.register_client();
+    assert!(hub
+        .update_runtime_filter(&serde_json::json!({ "mute": ["noisy"] }))
+        .is_ok());
+    hub.publish("noisy", OutputStream::Stdout, "spam", None);
+    hub.publish("api", OutputStream::Stdout, "ready", None);
+    let received: Vec<String> = live.try_iter().map(|event| event.service).collect();
+    assert_eq!(received, vec!["api".to_string()]);
+    let (_, snapshot) = hub.register_client();
+    assert_eq!(snapshot.len(), 1);
+    assert_eq!(hub.recent("noisy", 10).len(), 1);
+}
+
+#[test]
+fn recent_lines_include_every_replica() {
+    let hub = LogHub::with_reorder(16, Duration::ZERO);
+    hub.publish("api[1]", OutputStream::Stdout, "one", None);
+    hub.publish("api[2]", OutputStream::Stderr, "two", None);
+    hub.publish("apis", OutputStream::Stdout, "other", None);
+    let lines: Vec<String> = hub
+        .recent("api", 10)
+        .into_iter()
+        .map(|event| event.line)
+        .collect();
+    assert_eq!(lines, vec!["one".to_string(), "two".to_string()]);
+}
+
+#[test]
+fn lossless_clients_keep_every_event() {
+    let hub = LogHub::with_reorder(16, Duration::ZERO);
+    let (receiver, _) = hub.register_client();
+    let (lossless, _) = hub.register_lossless_client();
+    for line in 0..=CLIENT_QUEUE_SIZE {
+        hub.publish("api", OutputStream::Stdout, &line.to_string(), None);
+    }
+    assert_eq!(receiver.try_iter().count(), CLIENT_QUEUE_SIZE);
+    assert_eq!(lossless.try_iter().count(), CLIENT_QUEUE_SIZE + 1);
+}
+
+#[test]
+fn compose_output_is_published_as_a_pseudo_service() {
+    let hub = LogHub::with_reorder(16, Duration::ZERO);
+    let (receiver, _) = hub.register_client();
+    let output = "Container demo-api-1  Creating\r\nContainer demo-api-1  Started\n";
+    let config = LogWorkerConfig {
+        service: "compose".to_string(),
+        stream: OutputStream::Stderr,
+        prefix: "compose".to_string(),
+        color_prefix: String::new(),
+        color_reset: String::new(),
+        emit_stdout: false,
+        file_sink: None,
+        log_sink: None,
+        filter: None,
+    };
+    log_worker(
+        output.as_bytes(),
+        Some(&hub),
+        &Arc::new(AtomicBool::new(false)),
+        config,
+    );
+    let events: Vec<_> = receiver.try_iter().collect();
+    assert!(events
+        .iter()
+        .all(|event| event.service == "compose" && event.stream == OutputStream::Stderr));
+    let lines: Vec<&str> = events.iter().flat_map(|event| event.line.lines()).collect();
+    assert_eq!(
+        lines,
+        vec![
+            "Container demo-api-1  Creating",
+            "Container demo-api-1  Started"
+        ]
+    );
+}
@@ -1,12 +1,126 @@
+pub mod alerts;
 pub mod args;
+pub mod bench;
+pub mod chaos;
+pub mod cleanup;
+pub mod clock;
+pub mod completions;
+pub mod compose_errors;
 pub mod constants;
+pub mod correlate;
+pub mod crash;
+pub mod dbwire;
+pub mod env;
+pub mod events;
+pub mod forward;
+pub mod glob;
+pub mod gzip;
+pub mod images;
+pub mod inspect;
+pub mod log_files;
+pub mod log_filter;
+pub mod log_sink;
 pub mod logging;
 pub mod multiline;
+pub mod oneoff;
+pub mod platform;
+pub mod probe;
+pub mod record;
 pub mod run;
+pub mod sampling;
 pub mod services;
+pub mod settings;
+pub mod share;
+pub mod shutdown;
+pub mod skew;
+pub mod stall;
+pub mod summary;
+pub mod tap_failover;
+pub mod text;
+pub mod timeline;
 pub mod traffic;
+pub mod traffic_logs;
+pub mod wait;
 
+#[cfg(test)]
+mod alerts_tests;
+#[cfg(test)]
+mod bench_tests;
+#[cfg(test)]
+mod chaos_tests;
+#[cfg(test)]
+mod cleanup_tests;
+#[cfg(test)]
+mod completions_tests;
+#[cfg(test)]
+mod compose_errors_tests;
+#[cfg(test)]
+mod correlate_tests;
+#[cfg(test)]
+mod crash_tests;
+#[cfg(test)]
+mod dbwire_tests;
+#[cfg(test)]
+mod env_tests;
+#[cfg(test)]
+mod events_tests;
+#[cfg(test)]
+mod forward_tests;
+#[cfg(test)]
+mod glob_tests;
+#[cfg(test)]
+mod gzip_tests;
+#[cfg(test)]
+mod images_tests;
+#[cfg(test)]
+mod inspect_tests;
+#[cfg(test)]
+mod log_files_tests;
+#[cfg(test)]
+mod log_filter_tests;
+#[cfg(test)]
+mod log_sink_tests;
 #[cfg(test)]
 mod logging_tests;
 #[cfg(test)]
 mod multiline_tests;
+#[cfg(test)]
+mod oneoff_tests;
+#[cfg(test)]
+mod platform_tests;
+#[cfg(test)]
+mod probe_tests;
+#[cfg(test)]
+mod record_tests;
+#[cfg(test)]
+mod run_tests;
+#[cfg(test)]
+mod sampling_tests;
+#[cfg(test)]
+mod services_tests;
+#[cfg(test)]
+mod settings_tests;
+#[cfg(test)]
+mod share_tests;
+#[cfg(test)]
+mod shutdown_tests;
+#[cfg(test)]
+mod skew_tests;
+#[cfg(test)]
+mod stall_tests;
+#[cfg(test)]
+mod summary_tests;
+#[cfg(test)]
+mod tap_failover_tests;
+#[cfg(test)]
+mod text_tests;
+#[cfg(test)]
+mod timeline_tests;
+#[cfg(test)]
+pub mod traffic_fixtures;
+#[cfg(test)]
+mod traffic_logs_tests;
+#[cfg(test)]
+mod traffic_tests;
+#[cfg(test)]
+mod wait_tests;
@@ -16,6 +16,7 @@ pub struct Ruling {
 pub struct AggregatedEvent {
     pub line: String,
     pub container_ts: Option<String>,
+    pub exception: Option<String>,
 }
 
 pub struct LineView<'a> {
@@ -40,6 +41,13 @@ impl Vote {
             complete,
         }
     }
+
+    const fn hold() -> Self {
+        Self {
+            decision: Decision::NoOpinion,
+            complete: false,
+        }
+    }
 }
 
 trait Classifier: Send + Sync {
@@ -53,7 +61,11 @@ pub struct Router {
 impl Router {
     pub fn new() -> Self {
         Self {
-            start_classifiers: vec![Box::new(JsonClassifier), Box::new(TokenSignalClassifier)],
+            start_classifiers: vec![
+                Box::new(JsonClassifier),
+                Box::new(ExceptionClassifier),
+                Box::new(TokenSignalClassifier),
+            ],
         }
     }
 
@@ -146,8 +158,10 @@ impl MultilineAggregator {
         if self.buffer.is_empty() {
             None
         } else {
+            let line = std::mem::take(&mut self.buffer);
             Some(AggregatedEvent {
-                line: std::mem::take(&mut self.buffer),
+                exception: detect_exception(&line),
+                line,
                 container_ts: self.current_container_ts.take(),
             })
         }
@@ -184,6 +198,20 @@ impl Classifier for JsonClassifier {
     }
 }
 
+struct ExceptionClassifier;
+
+impl Classifier for ExceptionClassifier {
+    fn classify(&self, view: &LineView) -> Option<Vote> {
+        if exception_class(view.content).is_some() {
+            return Some(Vote::start(false));
+        }
+        if is_stack_continuation(view.content) {
+            return Some(Vote::hold());
+        }
+        None
+    }
+}
+
 struct TokenSignalClassifier;
 
 impl Classifier for TokenSignalClassifier {
@@ -195,6 +223,55 @@ impl Classifier for TokenSignalClassifier {
     }
 }
 
+pub fn detect_exception(text: &str) -> Option<String> {
+    text.lines().find_map(exception_class)
+}
+
+fn exception_class(line: &str) -> Option<String> {
+    let line = line.trim_start();
+    if let Some(rest) = line.strip_prefix("Exception in thread ") {
+        let rest = rest
+            .strip_prefix('"')
+            .and_then(|quoted| quoted.split_once('"'))
+            .map_or(rest, |(_, after)| after);
+        return leading_identifier(rest.trim_start()).map(ToString::to_string);
+    }
+    if let Some((_, rest)) = line.split_once(NODE_REJECTION) {
+        let rest = rest.strip_prefix("Warning").unwrap_or(rest);
+        let inner = rest
+            .strip_prefix(": ")
+            .and_then(leading_identifier)
+            .filter(|name| name.ends_with("Error"));
+        return Some(inner.unwrap_or(NODE_REJECTION).to_string());
+    }
+    if let Some(rest) = line.strip_prefix("panic: ") {
+        let class = if rest.starts_with("runtime error:") {
+            "runtime error"
+        } else {
+            "panic"
+        };
+        return Some(class.to_string());
+    }
+    line.starts_with("fatal error: ")
+        .then(|| "fatal error".to_string())
+}
+
+fn leading_identifier(value: &str) -> Option<&str> {
+    let end = value
+        .find(|ch: char| !(ch.is_ascii_alphanumeric() || matches!(ch, '.' | '$' | '_')))
+        .unwrap_or(value.len());
+    value.get(..end).filter(|name| !name.is_empty())
+}
+
+fn is_stack_continuation(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    let indented = trimmed.len() < line.len();
+    (indented && (trimmed.starts_with("at ") || trimmed.starts_with("... ")))
+        || trimmed.starts_with("Caused by: ")
+        || trimmed.starts_with("created by ")
+        || (trimmed.starts_with("goroutine ") && trimmed.ends_with("]:"))
+}
+
 fn extract_json_candidate(value: &str) -> Option<&str> {
     let candidate = value.trim();
     let bytes = candidate.as_bytes();
@@ -363,16 +440,15 @@ fn match_time_at(bytes: &[u8], idx: usize) -> Option<usize> {
     if let Some(byte) = byte_at(bytes, end) {
         match byte {
             b'Z' | b'z' => end += 1,
-            b'+' | b'-' => {
+            b'+' | b'-'
                 if end + 5 < bytes.len()
                     && is_digit(bytes, end + 1)
                     && is_digit(bytes, end + 2)
                     && byte_at(bytes, end + 3)? == b':'
                     && is_digit(bytes, end + 4)
-                    && is_digit(bytes, end + 5)
-                {
-                    end += 6;
-                }
+                    && is_digit(bytes, end + 5) =>
+            {
+                end += 6;
             }
             _ => {}
         }
@@ -388,18 +464,27 @@ fn byte_at(bytes: &[u8], idx: usize) -> Option<u8> {
     bytes.get(idx).copied()
 }
 
-fn parse_rfc3339_to_epoch_millis(value: &str) -> Option<i64> {
+pub fn parse_rfc3339_to_epoch_millis(value: &str) -> Option<i64> {
     let parsed = OffsetDateTime::parse(value, &Rfc3339).ok()?;
     let seconds = parsed.unix_timestamp();
     let millis = i64::from(parsed.millisecond());
     Some(seconds.saturating_mul(1000).saturating_add(millis))
 }
 
+pub fn format_epoch_millis_rfc3339(millis: i64) -> Option<String> {
+    let nanos = i128::from(millis).saturating_mul(1_000_000);
+    OffsetDateTime::from_unix_timestamp_nanos(nanos)
+        .ok()?
+        .format(&Rfc3339)
+        .ok()
+}
+
 fn is_level(value: &str) -> bool {
     LEVELS.iter().any(|level| value.eq_ignore_ascii_case(level))
 }
 
 const LEADING_TOKEN_LIMIT: usize = 5;
+const NODE_REJECTION: &str = "UnhandledPromiseRejection";
 const LEVELS: [&str; 9] = [
     "TRACE", "DEBUG", "INFO", "WARN", "WARNING", "ERROR", "FATAL", "CRITICAL", "PANIC",
 ];
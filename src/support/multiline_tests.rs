@@ -255,3 +255,66 @@ fn bracketed_timestamp_level_groups_following_lines() {
     assert_event(&events, 1, line_at(&lines, 5), None);
     assert_event(&events, 2, line_at(&lines, 6), None);
 }
+
+fn exception_at(events: &[AggregatedEvent], idx: usize) -> Option<&str> {
+    events.get(idx).and_then(|event| event.exception.as_deref())
+}
+
+#[test]
+fn java_uncaught_exception_becomes_one_error_event() {
+    let lines = [
+        "2026-01-07 23:34:00.000 INFO Started App in 2.1 seconds",
+        "Exception in thread \"main\" java.lang.IllegalStateException: pool closed",
+        "\tat com.example.Pool.take(Pool.java:42)",
+        "\tat com.example.App.main(App.java:12)",
+        "Caused by: java.io.IOException: ERROR connection reset",
+        "\t... 2 more",
+    ];
+
+    let events = collect_events(&lines);
+
+    assert_eq!(events.len(), 2);
+    assert_event(&events, 0, line_at(&lines, 0), None);
+    assert_event(&events, 1, &lines[1..].join("\n"), None);
+    assert_eq!(exception_at(&events, 0), None);
+    assert_eq!(
+        exception_at(&events, 1),
+        Some("java.lang.IllegalStateException")
+    );
+}
+
+#[test]
+fn node_unhandled_rejection_extracts_the_error_name() {
+    let lines = [
+        "listening on 3000",
+        "(node:1) UnhandledPromiseRejectionWarning: TypeError: Cannot read properties of undefined",
+        "    at handler (/app/server.js:10:5)",
+        "[UnhandledPromiseRejection: This error originated either by throwing inside of an async function without a catch block.]",
+    ];
+
+    let events = collect_events(&lines);
+
+    assert_eq!(events.len(), 3);
+    assert_event(&events, 1, &lines[1..3].join("\n"), None);
+    assert_eq!(exception_at(&events, 1), Some("TypeError"));
+    assert_eq!(exception_at(&events, 2), Some("UnhandledPromiseRejection"));
+}
+
+#[test]
+fn go_panic_keeps_the_goroutine_dump() {
+    let lines = [
+        "2026/01/07 23:34:00 serving on :8080",
+        "panic: runtime error: index out of range [3] with length 2",
+        "",
+        "goroutine 1 [running]:",
+        "main.main()",
+        "\t/app/main.go:9 +0x1d",
+        "exit status 2",
+    ];
+
+    let events = collect_events(&lines);
+
+    assert_eq!(events.len(), 2);
+    assert_event(&events, 1, &lines[1..].join("\n"), None);
+    assert_eq!(exception_at(&events, 1), Some("runtime error"));
+}
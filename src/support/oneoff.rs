@@ -0,0 +1,78 @@
+use crate::support::constants::{ONEOFF_LABEL, RUN_ID_LABEL, SERVICE_LABEL};
+
+const FORWARDED_ENV: [&str; 6] = [
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "NO_PROXY",
+    "http_proxy",
+    "https_proxy",
+    "no_proxy",
+];
+const IGNORED_NETWORKS: [&str; 2] = ["host", "none"];
+
+pub struct OneOffSpec {
+    pub run_id: String,
+    pub project_name: String,
+    pub name: String,
+    pub image: String,
+    pub networks: Vec<String>,
+    pub env: Vec<String>,
+    pub command: Vec<String>,
+    pub tty: bool,
+}
+
+pub fn run_args(spec: &OneOffSpec) -> Vec<String> {
+    let mut args = vec!["--rm".to_string(), "-i".to_string()];
+    if spec.tty {
+        args.push("-t".to_string());
+    }
+    args.extend(container_args(spec));
+    args
+}
+
+pub fn container_args(spec: &OneOffSpec) -> Vec<String> {
+    let mut args = Vec::new();
+    let labels = [
+        (RUN_ID_LABEL, spec.run_id.as_str()),
+        (SERVICE_LABEL, spec.name.as_str()),
+        (ONEOFF_LABEL, "true"),
+        ("com.docker.compose.project", spec.project_name.as_str()),
+        ("com.docker.compose.oneoff", "True"),
+    ];
+    for (key, value) in labels {
+        args.push("--label".to_string());
+        args.push(format!("{key}={value}"));
+    }
+    for network in &spec.networks {
+        args.push("--network".to_string());
+        args.push(network.clone());
+    }
+    for entry in &spec.env {
+        args.push("-e".to_string());
+        args.push(entry.clone());
+    }
+    args.push(spec.image.clone());
+    args.extend(spec.command.iter().cloned());
+    args
+}
+
+pub fn forwarded_env(env: &[String]) -> Vec<String> {
+    env.iter()
+        .filter(|entry| {
+            entry
+                .split_once('=')
+                .is_some_and(|(key, _)| FORWARDED_ENV.contains(&key))
+        })
+        .cloned()
+        .collect()
+}
+
+pub fn attachable_networks<'a>(networks: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+    let mut attached: Vec<String> = Vec::new();
+    for network in networks {
+        if !IGNORED_NETWORKS.contains(&network.as_str()) && !attached.contains(network) {
+            attached.push(network.clone());
+        }
+    }
+    attached
+}
@@ -0,0 +1,43 @@
+use super::oneoff::{attachable_networks, forwarded_env, run_args, OneOffSpec};
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn labels_the_container_into_the_run_and_joins_its_networks() {
+    let networks = strings(&["sanelens_run_a1_default", "none", "sanelens_run_a1_default"]);
+    let spec = OneOffSpec {
+        run_id: "run_a1".to_string(),
+        project_name: "sanelens_run_a1".to_string(),
+        name: "probe".to_string(),
+        image: "curlimages/curl".to_string(),
+        networks: attachable_networks(&networks),
+        env: forwarded_env(&strings(&["PATH=/bin", "HTTP_PROXY=http://egress:3128"])),
+        command: strings(&["curl", "http://api:8080/x"]),
+        tty: false,
+    };
+    let args = run_args(&spec);
+    let expected = strings(&[
+        "--rm",
+        "-i",
+        "--label",
+        "sanelens.run_id=run_a1",
+        "--label",
+        "sanelens.service=probe",
+        "--label",
+        "sanelens.oneoff=true",
+        "--label",
+        "com.docker.compose.project=sanelens_run_a1",
+        "--label",
+        "com.docker.compose.oneoff=True",
+        "--network",
+        "sanelens_run_a1_default",
+        "-e",
+        "HTTP_PROXY=http://egress:3128",
+        "curlimages/curl",
+        "curl",
+        "http://api:8080/x",
+    ]);
+    assert_eq!(args, expected);
+}
@@ -0,0 +1,87 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+pub const BINFMT_DIR: &str = "/proc/sys/fs/binfmt_misc";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Platform {
+    pub os: String,
+    pub arch: String,
+    pub variant: Option<String>,
+}
+
+impl Platform {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut parts = value.trim().split('/').map(str::trim);
+        let os = parts.next().filter(|part| !part.is_empty());
+        let arch = parts.next().filter(|part| !part.is_empty());
+        let variant = parts.next().filter(|part| !part.is_empty());
+        match (os, arch, parts.next()) {
+            (Some(os), Some(arch), None) => Ok(Self {
+                os: os.to_ascii_lowercase(),
+                arch: normalize_arch(arch),
+                variant: variant.map(str::to_ascii_lowercase),
+            }),
+            _ => Err(format!(
+                "Invalid --platform {value:?}. Use os/arch[/variant], e.g. linux/amd64 or linux/arm64."
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.os, self.arch)?;
+        if let Some(variant) = &self.variant {
+            write!(f, "/{variant}")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn normalize_arch(arch: &str) -> String {
+    let arch = arch.trim().to_ascii_lowercase();
+    match arch.as_str() {
+        "x86_64" | "x86-64" | "x64" => "amd64".to_string(),
+        "aarch64" => "arm64".to_string(),
+        "armv7l" | "armv7" | "armhf" => "arm".to_string(),
+        "i386" | "i686" | "x86" => "386".to_string(),
+        _ => arch,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Emulation {
+    Native,
+    Available,
+    Missing,
+    Unknown,
+}
+
+pub fn emulation(engine_arch: &str, platform: &Platform, binfmt_dir: &Path) -> Emulation {
+    let engine_arch = normalize_arch(engine_arch);
+    if engine_arch == platform.arch || (engine_arch == "amd64" && platform.arch == "386") {
+        return Emulation::Native;
+    }
+    if !binfmt_dir.is_dir() {
+        return Emulation::Unknown;
+    }
+    let handler = binfmt_dir.join(format!("qemu-{}", qemu_arch(&platform.arch)));
+    let enabled = fs::read_to_string(handler)
+        .is_ok_and(|content| content.lines().next().map(str::trim) == Some("enabled"));
+    if enabled {
+        Emulation::Available
+    } else {
+        Emulation::Missing
+    }
+}
+
+fn qemu_arch(arch: &str) -> &str {
+    match arch {
+        "amd64" => "x86_64",
+        "arm64" => "aarch64",
+        "386" => "i386",
+        other => other,
+    }
+}
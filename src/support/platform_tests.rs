@@ -0,0 +1,56 @@
+use std::fs;
+
+use super::platform::{emulation, normalize_arch, Emulation, Platform};
+
+#[test]
+fn parses_and_normalizes_platforms() {
+    assert_eq!(
+        Platform::parse("Linux/x86_64"),
+        Ok(Platform {
+            os: "linux".to_string(),
+            arch: "amd64".to_string(),
+            variant: None,
+        })
+    );
+    assert_eq!(
+        Platform::parse("linux/arm/v7").map(|platform| platform.to_string()),
+        Ok("linux/arm/v7".to_string())
+    );
+    assert!(Platform::parse("amd64").is_err());
+    assert!(Platform::parse("linux/amd64/v2/extra").is_err());
+    assert_eq!(normalize_arch("aarch64"), "arm64");
+}
+
+#[test]
+fn checks_binfmt_handlers_for_foreign_architectures() {
+    let dir = std::env::temp_dir().join(format!("sanelens-binfmt-{}", std::process::id()));
+    assert!(fs::create_dir_all(&dir).is_ok());
+    assert!(fs::write(
+        dir.join("qemu-x86_64"),
+        "enabled\ninterpreter /usr/bin/qemu-x86_64\n"
+    )
+    .is_ok());
+    assert!(fs::write(dir.join("qemu-riscv64"), "disabled\n").is_ok());
+    let check = |platform: &str, engine: &str| {
+        Platform::parse(platform).map(|platform| emulation(engine, &platform, &dir))
+    };
+    let results = [
+        check("linux/arm64", "aarch64"),
+        check("linux/amd64", "arm64"),
+        check("linux/riscv64", "amd64"),
+        check("linux/s390x", "amd64"),
+    ];
+    let unknown = Platform::parse("linux/amd64")
+        .map(|platform| emulation("arm64", &platform, &dir.join("missing")));
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(
+        results,
+        [
+            Ok(Emulation::Native),
+            Ok(Emulation::Available),
+            Ok(Emulation::Missing),
+            Ok(Emulation::Missing),
+        ]
+    );
+    assert_eq!(unknown, Ok(Emulation::Unknown));
+}
@@ -0,0 +1,80 @@
+use crate::domain::{EndpointProtocol, ServiceInfo};
+use crate::support::services::endpoint_url;
+
+pub const POSTGRES_SSL_REQUEST: [u8; 8] = [0, 0, 0, 8, 0x04, 0xd2, 0x16, 0x2f];
+const MYSQL_PROTOCOL_V10: u8 = 0x0a;
+const TLS_ALERT: u8 = 0x15;
+
+pub fn http_probe_request(host: &str, port: u16) -> String {
+    format!("GET / HTTP/1.0\r\nHost: {host}:{port}\r\nUser-Agent: sanelens-probe\r\n\r\n")
+}
+
+pub fn classify_banner(banner: &[u8]) -> Option<EndpointProtocol> {
+    if banner.starts_with(b"AMQP") {
+        return Some(EndpointProtocol::Amqp);
+    }
+    (banner.len() > 5 && banner.get(4) == Some(&MYSQL_PROTOCOL_V10))
+        .then_some(EndpointProtocol::Mysql)
+}
+
+pub fn classify_reply(reply: &[u8]) -> Option<EndpointProtocol> {
+    if reply.starts_with(b"HTTP/") {
+        let text = String::from_utf8_lossy(reply);
+        if text.contains("MongoDB over HTTP") {
+            return Some(EndpointProtocol::Mongodb);
+        }
+        return Some(EndpointProtocol::Http);
+    }
+    if matches!(reply, [TLS_ALERT, 0x03, ..]) {
+        return Some(EndpointProtocol::Https);
+    }
+    if reply.starts_with(b"-ERR") || reply.starts_with(b"-NOAUTH") || reply.starts_with(b"-DENIED")
+    {
+        return Some(EndpointProtocol::Redis);
+    }
+    if reply.starts_with(b"AMQP") {
+        return Some(EndpointProtocol::Amqp);
+    }
+    None
+}
+
+pub fn is_postgres_ssl_reply(reply: &[u8]) -> bool {
+    matches!(reply, [b'S' | b'N'])
+}
+
+pub const fn port_hint(port: u16) -> EndpointProtocol {
+    match port {
+        443 | 8443 => EndpointProtocol::Https,
+        5432 => EndpointProtocol::Postgres,
+        3306 => EndpointProtocol::Mysql,
+        6379 => EndpointProtocol::Redis,
+        27017 => EndpointProtocol::Mongodb,
+        5672 => EndpointProtocol::Amqp,
+        _ => EndpointProtocol::Tcp,
+    }
+}
+
+pub fn apply_protocols(
+    service: &mut ServiceInfo,
+    mut protocol_for: impl FnMut(&str, u16) -> Option<EndpointProtocol>,
+) {
+    for endpoint in &mut service.published {
+        endpoint.protocol = protocol_for(&endpoint.host, endpoint.port);
+        let scheme = endpoint.protocol.map_or("http", EndpointProtocol::scheme);
+        endpoint.url = endpoint_url(scheme, &endpoint.host, endpoint.port);
+    }
+    if service.published.is_empty() {
+        return;
+    }
+    service.endpoints = service
+        .published
+        .iter()
+        .map(|endpoint| endpoint.url.clone())
+        .collect();
+    service.endpoint = service
+        .published
+        .iter()
+        .find(|endpoint| endpoint.protocol.is_none_or(EndpointProtocol::is_web))
+        .or_else(|| service.published.first())
+        .map(|endpoint| endpoint.url.clone());
+}
@@ -0,0 +1,65 @@
+use super::probe::{apply_protocols, classify_banner, classify_reply, port_hint};
+use crate::domain::{EndpointProtocol, PublishedEndpoint, ServiceInfo};
+
+#[test]
+fn classifies_banners_and_replies() {
+    assert_eq!(
+        classify_banner(b"\x4a\x00\x00\x00\x0a8.0.36\x00"),
+        Some(EndpointProtocol::Mysql)
+    );
+    assert_eq!(classify_banner(b"SSH-2.0-OpenSSH_9.6\r\n"), None);
+    assert_eq!(
+        classify_reply(b"HTTP/1.1 404 Not Found\r\n"),
+        Some(EndpointProtocol::Http)
+    );
+    assert_eq!(
+        classify_reply(
+            b"HTTP/1.0 200 OK\r\n\r\nIt looks like you are trying to access MongoDB over HTTP"
+        ),
+        Some(EndpointProtocol::Mongodb)
+    );
+    assert_eq!(
+        classify_reply(b"\x15\x03\x01\x00\x02\x02\x46"),
+        Some(EndpointProtocol::Https)
+    );
+    assert_eq!(
+        classify_reply(b"-ERR wrong number of arguments for 'get' command\r\n"),
+        Some(EndpointProtocol::Redis)
+    );
+    assert_eq!(classify_reply(b"garbage"), None);
+    assert_eq!(port_hint(5432), EndpointProtocol::Postgres);
+    assert_eq!(port_hint(9000), EndpointProtocol::Tcp);
+}
+
+#[test]
+fn rewrites_urls_and_prefers_web_endpoint() {
+    let published = |port| PublishedEndpoint {
+        host: "localhost".to_string(),
+        port,
+        url: format!("http://localhost:{port}"),
+        protocol: None,
+        forwarded: false,
+    };
+    let mut service = ServiceInfo {
+        name: "db".to_string(),
+        endpoints: vec![
+            "http://localhost:5432".to_string(),
+            "http://localhost:8080".to_string(),
+        ],
+        endpoint: Some("http://localhost:5432".to_string()),
+        exposed: true,
+        published: vec![published(5432), published(8080)],
+    };
+    apply_protocols(&mut service, |_, port| {
+        Some(if port == 5432 {
+            EndpointProtocol::Postgres
+        } else {
+            EndpointProtocol::Http
+        })
+    });
+    assert_eq!(
+        service.endpoints,
+        vec!["postgres://localhost:5432", "http://localhost:8080"]
+    );
+    assert_eq!(service.endpoint.as_deref(), Some("http://localhost:8080"));
+}
@@ -0,0 +1,134 @@
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::domain::traffic::{upgrade_schema, versioned, Observation};
+use crate::domain::{LogEvent, ServiceGraph, ServiceInfo};
+use crate::support::events::LifecycleEvent;
+
+pub const RECORD_VERSION: u32 = 1;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecordHeader {
+    pub version: u32,
+    pub run_id: String,
+    pub project: String,
+    pub started_at_ms: u64,
+    pub services: Vec<ServiceInfo>,
+    pub graph: ServiceGraph,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecordEntry {
+    pub offset_ms: u64,
+    #[serde(flatten)]
+    pub item: RecordItem,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecordItem {
+    Log {
+        event: LogEvent,
+    },
+    Observation {
+        #[serde(serialize_with = "serialize_versioned")]
+        observation: Observation,
+    },
+    Lifecycle {
+        event: LifecycleEvent,
+    },
+}
+
+pub struct RecordWriter<W: Write> {
+    out: W,
+    started_at_ms: u64,
+}
+
+impl<W: Write> RecordWriter<W> {
+    pub fn new(mut out: W, header: &RecordHeader) -> io::Result<Self> {
+        write_line(&mut out, header)?;
+        Ok(Self {
+            out,
+            started_at_ms: header.started_at_ms,
+        })
+    }
+
+    pub fn write(&mut self, at_ms: u64, item: RecordItem) -> io::Result<()> {
+        let entry = RecordEntry {
+            offset_ms: at_ms.saturating_sub(self.started_at_ms),
+            item,
+        };
+        write_line(&mut self.out, &entry)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+fn write_line<T: Serialize>(out: &mut impl Write, value: &T) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, value)?;
+    out.write_all(b"\n")
+}
+
+pub fn read_header(reader: &mut impl BufRead) -> Result<RecordHeader, String> {
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .map_err(|err| format!("failed to read recording: {err}"))?;
+    let header: RecordHeader =
+        serde_json::from_str(&line).map_err(|err| format!("not a sanelens recording: {err}"))?;
+    if header.version > RECORD_VERSION {
+        return Err(format!(
+            "recording version {} is newer than this build supports ({RECORD_VERSION})",
+            header.version
+        ));
+    }
+    Ok(header)
+}
+
+pub fn read_entries(reader: impl BufRead) -> impl Iterator<Item = Result<RecordEntry, String>> {
+    reader
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+        .map(|(index, line)| {
+            let line = line.map_err(|err| format!("failed to read recording: {err}"))?;
+            parse_entry(&line)
+                .map_err(|err| format!("invalid recording entry on line {}: {err}", index + 2))
+        })
+}
+
+fn serialize_versioned<S: Serializer>(
+    observation: &Observation,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    versioned(observation).serialize(serializer)
+}
+
+fn parse_entry(line: &str) -> Result<RecordEntry, String> {
+    let mut value: serde_json::Value = serde_json::from_str(line).map_err(|err| err.to_string())?;
+    if let Some(observation) = value.get_mut("observation") {
+        *observation = upgrade_schema(observation.take())?;
+    }
+    serde_json::from_value(value).map_err(|err| err.to_string())
+}
+
+pub fn parse_speed(value: &str) -> Result<f64, String> {
+    let trimmed = value.trim();
+    trimmed
+        .strip_suffix('x')
+        .unwrap_or(trimmed)
+        .parse::<f64>()
+        .ok()
+        .filter(|speed| speed.is_finite() && *speed > 0.0)
+        .ok_or_else(|| format!("Invalid --speed '{value}'. Use e.g. 1, 4 or 0.5x."))
+}
+
+#[allow(clippy::cast_precision_loss)]
+pub fn replay_offset(offset_ms: u64, speed: f64) -> Duration {
+    Duration::from_secs_f64(offset_ms as f64 / 1000.0 / speed)
+}
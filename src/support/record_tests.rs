@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+
+use super::events::{EventKind, LifecycleEvent};
+use super::record::{
+    parse_speed, read_entries, read_header, replay_offset, RecordHeader, RecordItem, RecordWriter,
+};
+use super::traffic::TrafficHub;
+use crate::domain::traffic::{
+    Confidence, EntityId, FlowKey, FlowMetrics, FlowObservation, Observation, ObservationAttrs,
+    ObservationSink, Peer, Socket, Transport, Visibility,
+};
+use crate::domain::{LogEvent, OutputStream, ServiceGraph};
+
+fn header() -> RecordHeader {
+    RecordHeader {
+        version: 1,
+        run_id: "run_a1".to_string(),
+        project: "sanelens_run_a1".to_string(),
+        started_at_ms: 10_000,
+        services: Vec::new(),
+        graph: ServiceGraph::default(),
+    }
+}
+
+fn flow(src: &str, dst: &str) -> Observation {
+    let socket = |port| Socket {
+        ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+        port,
+    };
+    let workload = |name: &str| EntityId::Workload {
+        name: name.to_string(),
+        instance: None,
+    };
+    Observation::Flow(FlowObservation {
+        at_ms: 10_500,
+        flow: FlowKey {
+            src: socket(40_000),
+            dst: socket(8080),
+            transport: Transport::Tcp,
+        },
+        metrics: FlowMetrics {
+            bytes_in: Some(10),
+            bytes_out: Some(20),
+            packets: None,
+            duration_ms: None,
+        },
+        peer: Peer {
+            src: Some(workload(src)),
+            dst: Some(workload(dst)),
+            raw: None,
+        },
+        attrs: ObservationAttrs {
+            visibility: Visibility::L4Flow,
+            confidence: Confidence::Exact,
+            tags: BTreeMap::new(),
+        },
+    })
+}
+
+#[test]
+fn recordings_round_trip_and_replay_into_a_fresh_hub() {
+    let mut buffer = Vec::new();
+    let log = LogEvent {
+        seq: 7,
+        service: "api".to_string(),
+        container_ts: None,
+        ts_ms: None,
+        line: "listening on :8080".to_string(),
+        stream: OutputStream::Stderr,
+        repeat_count: None,
+        annotation: false,
+        instance: None,
+        kind: None,
+        exception: None,
+    };
+    let stopped = LifecycleEvent {
+        run_id: "run_a1".to_string(),
+        at_ms: 12_000,
+        kind: EventKind::RunStopped { exit_code: 0 },
+    };
+    let items = [
+        (10_200, RecordItem::Log { event: log }),
+        (
+            10_500,
+            RecordItem::Observation {
+                observation: flow("web", "api"),
+            },
+        ),
+        (12_000, RecordItem::Lifecycle { event: stopped }),
+    ];
+    let written = RecordWriter::new(&mut buffer, &header()).and_then(|mut writer| {
+        items
+            .into_iter()
+            .try_for_each(|(at_ms, item)| writer.write(at_ms, item))
+    });
+    assert!(written.is_ok());
+
+    let mut reader = Cursor::new(buffer);
+    assert_eq!(
+        read_header(&mut reader).map(|header| header.run_id),
+        Ok("run_a1".to_string())
+    );
+    let entries: Result<Vec<_>, _> = read_entries(reader).collect();
+    assert!(entries.is_ok());
+    let entries = entries.unwrap_or_default();
+    let offsets: Vec<u64> = entries.iter().map(|entry| entry.offset_ms).collect();
+    assert_eq!(offsets, [200, 500, 2_000]);
+
+    let hub = TrafficHub::new();
+    for entry in entries {
+        match entry.item {
+            RecordItem::Log { event } => {
+                assert_eq!(event.line, "listening on :8080");
+                assert_eq!(event.stream, OutputStream::Stderr);
+            }
+            RecordItem::Observation { observation } => hub.emit(observation),
+            RecordItem::Lifecycle { event } => {
+                assert!(matches!(event.kind, EventKind::RunStopped { exit_code: 0 }));
+            }
+        }
+    }
+    assert_eq!(hub.edges().len(), 1);
+}
+
+#[test]
+fn speeds_scale_the_replay_schedule() {
+    assert_eq!(parse_speed("4x"), Ok(4.0));
+    assert_eq!(parse_speed("0.5"), Ok(0.5));
+    assert!(parse_speed("0").is_err());
+    assert!(parse_speed("fast").is_err());
+    assert_eq!(replay_offset(2_000, 4.0), Duration::from_millis(500));
+    assert_eq!(replay_offset(2_000, 1.0), Duration::from_secs(2));
+}
+
+#[test]
+fn observations_carry_the_schema_and_newer_schemas_are_rejected() {
+    let mut buffer = Vec::new();
+    let written = RecordWriter::new(&mut buffer, &header()).and_then(|mut writer| {
+        writer.write(
+            10_500,
+            RecordItem::Observation {
+                observation: flow("web", "api"),
+            },
+        )
+    });
+    assert!(written.is_ok());
+    let text = String::from_utf8(buffer).unwrap_or_default();
+    let entry = text.lines().nth(1).unwrap_or_default().to_string();
+    assert!(entry.contains(r#""schema":"sanelens.observation.v2""#));
+
+    let legacy = entry.replace(r#""schema":"sanelens.observation.v2","#, "");
+    let future = entry.replace("observation.v2", "observation.v3");
+    let read = |line: &str| read_entries(Cursor::new(line.to_string())).collect::<Vec<_>>();
+    assert!(!legacy.contains("schema"));
+    assert!(read(&legacy).iter().all(Result::is_ok));
+    assert!(read(&future)
+        .iter()
+        .all(|entry| entry.as_ref().is_err_and(|err| err.contains("newer"))));
+}
+
+#[test]
+fn v1_external_peers_are_read_with_every_address() {
+    let external = |dns_name: &str| EntityId::External {
+        ips: Vec::new(),
+        dns_name: Some(dns_name.to_string()),
+    };
+    let Observation::Flow(mut observation) = flow("api", "api") else {
+        return;
+    };
+    observation.peer.dst = Some(external("api.github.com"));
+    let mut buffer = Vec::new();
+    let written = RecordWriter::new(&mut buffer, &header()).and_then(|mut writer| {
+        writer.write(
+            10_500,
+            RecordItem::Observation {
+                observation: Observation::Flow(observation),
+            },
+        )
+    });
+    assert!(written.is_ok());
+    let text = String::from_utf8(buffer).unwrap_or_default();
+    let entry = text.lines().nth(1).unwrap_or_default();
+    let resolved = entry
+        .replace("observation.v2", "observation.v1")
+        .replace(r#""ips":[]"#, r#""ip":"140.82.112.3""#);
+    let unresolved = entry
+        .replace("observation.v2", "observation.v1")
+        .replace(r#""ips":[]"#, r#""ip":"0.0.0.0""#);
+    let peer_ips = |recording: &str| {
+        read_entries(Cursor::new(recording.to_string()))
+            .filter_map(Result::ok)
+            .find_map(|entry| match entry.item {
+                RecordItem::Observation {
+                    observation: Observation::Flow(flow),
+                } => match flow.peer.dst {
+                    Some(EntityId::External { ips, .. }) => Some(ips),
+                    _ => None,
+                },
+                _ => None,
+            })
+    };
+    assert_eq!(
+        peer_ips(&resolved),
+        Some(vec![IpAddr::V4(Ipv4Addr::new(140, 82, 112, 3))])
+    );
+    assert_eq!(peer_ips(&unresolved), Some(Vec::new()));
+}
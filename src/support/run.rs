@@ -1,9 +1,14 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::HashSet;
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
 
-use crate::support::constants::PROJECT_PREFIX;
+use crate::support::constants::{BIN_NAME, PROJECT_PREFIX};
 
 pub fn new_run_id() -> String {
     let mut bytes = [0u8; 3];
@@ -20,12 +25,207 @@ pub fn new_run_id() -> String {
     format!("run_{hex}")
 }
 
+pub fn new_ui_token() -> Result<String, String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|err| format!("cannot generate a UI access token: {err}"))?;
+    Ok(bytes
+        .iter()
+        .fold(String::with_capacity(32), |mut token, byte| {
+            let _ = write!(token, "{byte:02x}");
+            token
+        }))
+}
+
+pub fn state_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_STATE_HOME")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+    Some(base.join(BIN_NAME))
+}
+
 pub fn project_name_from_run_id(run_id: &str) -> String {
     format!("{PROJECT_PREFIX}{run_id}")
 }
 
+const STOPPED_MARKER: &str = "stopped";
+
+pub fn set_run_stopped(run_dir: &Path, stopped: bool) -> Result<(), String> {
+    let marker = run_dir.join(STOPPED_MARKER);
+    let result = if stopped {
+        fs::write(&marker, run_started_at())
+    } else {
+        match fs::remove_file(&marker) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            other => other,
+        }
+    };
+    result.map_err(|err| format!("failed to update {}: {err}", marker.display()))
+}
+
+pub fn is_run_stopped(run_dir: &Path) -> bool {
+    run_dir.join(STOPPED_MARKER).is_file()
+}
+
+/// Whether a run started at `started_at` (unix seconds) is at least `ttl` old.
+/// Runs without a start time count as stale.
+pub fn is_stale(started_at: Option<i64>, now: i64, ttl: Duration) -> bool {
+    let ttl_secs = i64::try_from(ttl.as_secs()).unwrap_or(i64::MAX);
+    started_at.is_none_or(|ts| now - ts >= ttl_secs)
+}
+
+/// Run dirs under `root` whose project is not live, that are not stopped and
+/// that have not been touched for `ttl`.
+pub fn orphaned_run_dirs(
+    root: &Path,
+    live_projects: &HashSet<String>,
+    ttl: Duration,
+) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            entry.file_name().to_str().is_some_and(|name| {
+                name.starts_with(PROJECT_PREFIX) && !live_projects.contains(name)
+            })
+        })
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir() && !is_run_stopped(path) && older_than(path, ttl))
+        .collect()
+}
+
+fn older_than(path: &Path, ttl: Duration) -> bool {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age >= ttl)
+}
+
 pub fn run_started_at() -> String {
     OffsetDateTime::now_utc()
         .format(&Rfc3339)
         .unwrap_or_else(|_| OffsetDateTime::now_utc().unix_timestamp().to_string())
 }
+
+pub const LAST_RUN_ALIAS: &str = "@last";
+
+pub struct RunRef<'a> {
+    pub run_id: &'a str,
+    pub tag: Option<&'a str>,
+    pub started_at: Option<i64>,
+}
+
+pub fn validate_tag(tag: &str) -> Result<String, String> {
+    let tag = tag.trim();
+    if tag.is_empty() || tag.chars().any(char::is_control) {
+        return Err(format!(
+            "Invalid --tag '{tag}'. Use a short, single-line label."
+        ));
+    }
+    Ok(tag.to_string())
+}
+
+pub fn resolve_run_ref(run_ref: &str, runs: &[RunRef<'_>]) -> Result<String, String> {
+    if run_ref == LAST_RUN_ALIAS {
+        return runs
+            .iter()
+            .max_by(|a, b| {
+                a.started_at
+                    .cmp(&b.started_at)
+                    .then_with(|| a.run_id.cmp(b.run_id))
+            })
+            .map(|run| run.run_id.to_string())
+            .ok_or_else(|| "No runs found for @last.".to_string());
+    }
+    if runs.iter().any(|run| run.run_id == run_ref) {
+        return Ok(run_ref.to_string());
+    }
+    let tagged = matching_ids(runs, |run| run.tag == Some(run_ref));
+    if !tagged.is_empty() {
+        return unique(&tagged, || format!("Tag '{run_ref}'"));
+    }
+    let bare = run_ref.strip_prefix("run_").unwrap_or(run_ref);
+    if bare.is_empty() {
+        return Ok(run_ref.to_string());
+    }
+    let prefixed = matching_ids(runs, |run| {
+        run.run_id
+            .strip_prefix("run_")
+            .unwrap_or(run.run_id)
+            .starts_with(bare)
+    });
+    if prefixed.is_empty() {
+        return Ok(run_ref.to_string());
+    }
+    unique(&prefixed, || format!("Run id prefix '{run_ref}'"))
+}
+
+fn matching_ids(runs: &[RunRef<'_>], predicate: impl Fn(&RunRef<'_>) -> bool) -> Vec<String> {
+    let mut ids: Vec<String> = runs
+        .iter()
+        .filter(|run| predicate(run))
+        .map(|run| run.run_id.to_string())
+        .collect();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+fn unique(ids: &[String], describe: impl Fn() -> String) -> Result<String, String> {
+    match ids {
+        [run_id] => Ok(run_id.clone()),
+        _ => Err(format!(
+            "{} matches {} runs ({}). Pass a longer run id instead.",
+            describe(),
+            ids.len(),
+            ids.join(", ")
+        )),
+    }
+}
+
+/// Resolves `run_ref` once against the runs of every engine and returns the index of
+/// the first engine that owns the resolved run.
+pub fn route_run_ref<T>(
+    run_ref: &str,
+    engines: impl IntoIterator<Item = Vec<T>>,
+    as_ref: impl Fn(&T) -> RunRef<'_>,
+) -> Result<Option<(usize, String)>, String> {
+    let engines: Vec<Vec<T>> = engines.into_iter().collect();
+    let as_ref = &as_ref;
+    let (owners, refs): (Vec<usize>, Vec<RunRef<'_>>) = engines
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, runs)| runs.iter().map(move |run| (idx, as_ref(run))))
+        .unzip();
+    if refs.is_empty() {
+        return Ok(None);
+    }
+    let run_id = resolve_run_ref(run_ref, &refs)?;
+    let owner = refs
+        .iter()
+        .position(|run| run.run_id == run_id)
+        .and_then(|pos| owners.get(pos).copied());
+    Ok(owner.map(|idx| (idx, run_id)))
+}
+
+/// Merges the runs seen by each engine, tagged with the engine index. A run seen by
+/// several engines (e.g. `docker` backed by podman-docker) is kept once, from the
+/// first engine that lists it.
+pub fn merge_engine_runs<T>(
+    engines: impl IntoIterator<Item = Vec<T>>,
+    run_id: impl Fn(&T) -> &str,
+) -> Vec<(usize, T)> {
+    let mut merged: Vec<(usize, T)> = Vec::new();
+    for (idx, runs) in engines.into_iter().enumerate() {
+        for run in runs {
+            if merged.iter().all(|(_, seen)| run_id(seen) != run_id(&run)) {
+                merged.push((idx, run));
+            }
+        }
+    }
+    merged
+}
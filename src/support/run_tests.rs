@@ -0,0 +1,173 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use super::run::{
+    is_run_stopped, is_stale, merge_engine_runs, new_ui_token, orphaned_run_dirs, resolve_run_ref,
+    route_run_ref, set_run_stopped, validate_tag, RunRef,
+};
+
+fn runs() -> Vec<RunRef<'static>> {
+    vec![
+        RunRef {
+            run_id: "run_a1b2c3",
+            tag: Some("repro"),
+            started_at: Some(100),
+        },
+        RunRef {
+            run_id: "run_a1ff00",
+            tag: None,
+            started_at: Some(300),
+        },
+        RunRef {
+            run_id: "run_7c0e11",
+            tag: Some("repro-2"),
+            started_at: Some(200),
+        },
+    ]
+}
+
+#[test]
+fn resolves_prefixes_tags_and_last() {
+    let runs = runs();
+    assert_eq!(
+        resolve_run_ref("run_7c0e11", &runs),
+        Ok("run_7c0e11".to_string())
+    );
+    assert_eq!(
+        resolve_run_ref("repro", &runs),
+        Ok("run_a1b2c3".to_string())
+    );
+    assert_eq!(resolve_run_ref("7c", &runs), Ok("run_7c0e11".to_string()));
+    assert_eq!(
+        resolve_run_ref("run_a1b", &runs),
+        Ok("run_a1b2c3".to_string())
+    );
+    assert_eq!(
+        resolve_run_ref("@last", &runs),
+        Ok("run_a1ff00".to_string())
+    );
+    assert_eq!(resolve_run_ref("zz", &runs), Ok("zz".to_string()));
+    assert_eq!(resolve_run_ref("@last", &[]).ok(), None);
+}
+
+#[test]
+fn validates_tags_and_rejects_ambiguous_ones() {
+    assert_eq!(validate_tag("  repro "), Ok("repro".to_string()));
+    assert!(validate_tag(" ").is_err());
+    assert!(validate_tag("two\nlines").is_err());
+    let mut runs = runs();
+    runs.push(RunRef {
+        run_id: "run_ffee00",
+        tag: Some("repro"),
+        started_at: None,
+    });
+    let err = resolve_run_ref("repro", &runs).err().unwrap_or_default();
+    assert!(err.starts_with("Tag 'repro' matches 2 runs"), "{err}");
+}
+
+#[test]
+fn rejects_ambiguous_prefixes() {
+    let err = resolve_run_ref("a1", &runs()).err().unwrap_or_default();
+    assert!(err.contains("run_a1b2c3, run_a1ff00"), "{err}");
+}
+
+#[test]
+fn ui_tokens_are_random_hex() {
+    let first = new_ui_token();
+    let second = new_ui_token();
+    assert!(
+        first.as_ref().is_ok_and(
+            |token| token.len() == 32 && token.bytes().all(|byte| byte.is_ascii_hexdigit())
+        )
+    );
+    assert_ne!(first, second);
+}
+
+#[test]
+fn stopped_runs_are_marked_until_started_again() {
+    let dir = std::env::temp_dir().join(format!("sanelens-stopped-{}", std::process::id()));
+    let _ = std::fs::create_dir_all(&dir);
+    assert!(!is_run_stopped(&dir));
+    assert_eq!(set_run_stopped(&dir, true), Ok(()));
+    assert!(is_run_stopped(&dir));
+    assert_eq!(set_run_stopped(&dir, false), Ok(()));
+    assert!(!is_run_stopped(&dir));
+    assert_eq!(set_run_stopped(&dir, false), Ok(()));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+type Listed = (&'static str, Option<&'static str>, i64);
+
+fn as_ref(run: &Listed) -> RunRef<'_> {
+    RunRef {
+        run_id: run.0,
+        tag: run.1,
+        started_at: Some(run.2),
+    }
+}
+
+#[test]
+fn routes_a_run_to_the_engine_that_owns_it() {
+    let podman: Vec<Listed> = vec![("run_a1b2c3", Some("repro"), 100)];
+    let docker: Vec<Listed> = vec![("run_7c0e11", Some("repro-2"), 300)];
+    let engines = || [podman.clone(), docker.clone()];
+
+    assert_eq!(
+        route_run_ref("repro", engines(), as_ref),
+        Ok(Some((0, "run_a1b2c3".to_string())))
+    );
+    assert_eq!(
+        route_run_ref("7c0", engines(), as_ref),
+        Ok(Some((1, "run_7c0e11".to_string())))
+    );
+    assert_eq!(
+        route_run_ref("@last", engines(), as_ref),
+        Ok(Some((1, "run_7c0e11".to_string())))
+    );
+    assert_eq!(route_run_ref("run_ffffff", engines(), as_ref), Ok(None));
+    let tagged_twice = [podman.clone(), vec![("run_7c0e11", Some("repro"), 300)]];
+    let err = route_run_ref("repro", tagged_twice, as_ref)
+        .err()
+        .unwrap_or_default();
+    assert!(err.contains("run_7c0e11, run_a1b2c3"), "{err}");
+}
+
+#[test]
+fn merges_runs_seen_through_several_engines_once() {
+    // `docker` is podman-docker here, so both engines list the podman run.
+    let podman: Vec<Listed> = vec![("run_a1b2c3", None, 1), ("run_a1ff00", None, 2)];
+    let docker: Vec<Listed> = vec![("run_a1ff00", None, 2), ("run_7c0e11", None, 3)];
+    let merged: Vec<(usize, &str)> = merge_engine_runs([podman, docker], |run| run.0)
+        .into_iter()
+        .map(|(idx, run)| (idx, run.0))
+        .collect();
+    assert_eq!(
+        merged,
+        vec![(0, "run_a1b2c3"), (0, "run_a1ff00"), (1, "run_7c0e11")]
+    );
+}
+
+#[test]
+fn gc_picks_stale_runs_and_orphaned_run_dirs() {
+    let day = Duration::from_hours(24);
+    assert!(is_stale(Some(0), 86_400, day));
+    assert!(!is_stale(Some(1), 86_400, day));
+    assert!(is_stale(None, 0, day));
+
+    let root = std::env::temp_dir().join(format!("sanelens-gc-{}", std::process::id()));
+    for name in [
+        "sanelens_run_live",
+        "sanelens_run_gone",
+        "sanelens_run_paused",
+        "other",
+    ] {
+        let _ = std::fs::create_dir_all(root.join(name));
+    }
+    let _ = set_run_stopped(&root.join("sanelens_run_paused"), true);
+    let live: HashSet<String> = HashSet::from(["sanelens_run_live".to_string()]);
+    let orphaned = orphaned_run_dirs(&root, &live, Duration::ZERO);
+    let fresh = orphaned_run_dirs(&root, &live, day);
+    let _ = std::fs::remove_dir_all(&root);
+    assert_eq!(orphaned, vec![root.join("sanelens_run_gone")]);
+    assert!(fresh.is_empty());
+}
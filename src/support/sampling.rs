@@ -0,0 +1,144 @@
+use crate::domain::traffic::{HttpObservation, Observation};
+use crate::support::args::parse_duration;
+use crate::support::glob::glob_match;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrafficSampling {
+    pub every: Option<u64>,
+    pub errors: bool,
+    pub slow_ms: Option<u64>,
+    pub max_per_sec: Option<u32>,
+}
+
+impl Default for TrafficSampling {
+    fn default() -> Self {
+        Self {
+            every: Some(1),
+            errors: false,
+            slow_ms: None,
+            max_per_sec: None,
+        }
+    }
+}
+
+impl TrafficSampling {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut sampling = Self {
+            every: None,
+            ..Self::default()
+        };
+        for rule in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+        {
+            if rule == "all" {
+                sampling.every = Some(1);
+            } else if rule == "errors" {
+                sampling.errors = true;
+            } else if let Some(threshold) = rule.strip_prefix("slow=") {
+                let threshold = parse_duration(threshold)
+                    .ok_or_else(|| format!("invalid slow threshold '{threshold}'"))?;
+                sampling.slow_ms = Some(u64::try_from(threshold.as_millis()).unwrap_or(u64::MAX));
+            } else if let Some(rate) = rule.strip_suffix("/s") {
+                let rate = rate
+                    .parse()
+                    .map_err(|_| format!("invalid capture rate '{rule}'"))?;
+                sampling.max_per_sec = Some(rate);
+            } else if let Some(every) = rule.strip_prefix("1/") {
+                let every = every
+                    .parse()
+                    .ok()
+                    .filter(|every| *every > 0)
+                    .ok_or_else(|| format!("invalid sample ratio '{rule}'"))?;
+                sampling.every = Some(every);
+            } else {
+                return Err(format!("unknown sampling rule '{rule}'"));
+            }
+        }
+        if sampling.every.is_none() && !sampling.errors && sampling.slow_ms.is_none() {
+            sampling.every = Some(1);
+        }
+        Ok(sampling)
+    }
+}
+
+pub struct TrafficSampler {
+    config: TrafficSampling,
+    seen: u64,
+    window_start_ms: u64,
+    window_count: u32,
+}
+
+impl TrafficSampler {
+    pub const fn new(config: TrafficSampling) -> Self {
+        Self {
+            config,
+            seen: 0,
+            window_start_ms: 0,
+            window_count: 0,
+        }
+    }
+
+    pub fn keep(&mut self, http: &HttpObservation) -> bool {
+        let seen = self.seen;
+        self.seen += 1;
+        let sampled = self
+            .config
+            .every
+            .is_some_and(|every| seen.is_multiple_of(every));
+        let failed = self.config.errors && http.status.is_some_and(|status| status >= 400);
+        let slow = self
+            .config
+            .slow_ms
+            .is_some_and(|threshold| http.duration_ms.is_some_and(|ms| ms >= threshold));
+        if !(sampled || failed || slow) {
+            return false;
+        }
+        let Some(limit) = self.config.max_per_sec else {
+            return true;
+        };
+        if http.at_ms.saturating_sub(self.window_start_ms) >= 1000 {
+            self.window_start_ms = http.at_ms;
+            self.window_count = 0;
+        }
+        if self.window_count >= limit {
+            return false;
+        }
+        self.window_count += 1;
+        true
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TrafficIgnore {
+    patterns: Vec<String>,
+}
+
+impl TrafficIgnore {
+    pub fn parse(spec: &str) -> Self {
+        Self {
+            patterns: spec
+                .split(',')
+                .map(str::trim)
+                .filter(|pattern| !pattern.is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    pub fn matches(&self, obs: &Observation) -> bool {
+        let http = match obs {
+            Observation::Http(http) => http,
+            Observation::Upgrade(upgraded) => &upgraded.http,
+            Observation::Flow(_) | Observation::Db(_) => return false,
+        };
+        let Some(path) = http.path.as_deref() else {
+            return false;
+        };
+        let path = path.split_once('?').map_or(path, |(path, _)| path);
+        self.patterns
+            .iter()
+            .any(|pattern| glob_match(pattern, path))
+    }
+}
@@ -0,0 +1,112 @@
+use super::sampling::{TrafficIgnore, TrafficSampling};
+use super::traffic::TrafficHub;
+use super::traffic_fixtures::{http_observation, workload};
+use crate::domain::traffic::{
+    EdgeKey, EntityId, HttpObservation, Observation, ObservationSink, Peer, UpgradeInfo,
+    UpgradeObservation,
+};
+
+fn observation(at_ms: u64, status: u16, duration_ms: u64) -> Observation {
+    Observation::Http(HttpObservation {
+        at_ms,
+        peer: Peer {
+            src: Some(EntityId::Host {
+                name: "host".to_string(),
+            }),
+            dst: Some(workload("api")),
+            raw: None,
+        },
+        path: Some("/items".to_string()),
+        status: Some(status),
+        duration_ms: Some(duration_ms),
+        request_body: Some("payload".to_string()),
+        ..http_observation()
+    })
+}
+
+#[test]
+fn parses_sampling_rules() {
+    let sampling = TrafficSampling::parse("1/10,errors,slow=500ms,50/s").unwrap_or_default();
+    assert_eq!(
+        sampling,
+        TrafficSampling {
+            every: Some(10),
+            errors: true,
+            slow_ms: Some(500),
+            max_per_sec: Some(50),
+        }
+    );
+    assert!(TrafficSampling::parse("1/0").is_err());
+    assert!(TrafficSampling::parse("sometimes").is_err());
+}
+
+#[test]
+fn sampled_out_calls_still_count_in_edge_stats() {
+    let hub = TrafficHub::new();
+    hub.set_sampling(TrafficSampling::parse("errors,slow=1s").unwrap_or_default());
+    hub.emit(observation(1_000, 200, 5));
+    hub.emit(observation(1_010, 503, 5));
+    hub.emit(observation(1_020, 200, 1_500));
+    hub.emit(observation(1_030, 204, 3));
+    let (_, calls) = hub.register_call_client();
+    let statuses: Vec<Option<u16>> = calls.iter().map(|call| call.status).collect();
+    assert_eq!(statuses, vec![Some(503), Some(200)]);
+    let edges = hub.edges();
+    assert_eq!(edges.len(), 1);
+    assert_eq!(edges.first().map(|edge| edge.stats.count), Some(4));
+    assert_eq!(edges.first().map(|edge| edge.stats.errors), Some(1));
+}
+
+#[test]
+fn upgraded_connections_form_their_own_edge() {
+    let hub = TrafficHub::new();
+    hub.emit(observation(1_000, 200, 5));
+    if let Observation::Http(http) = observation(1_010, 101, 60_000) {
+        hub.emit(Observation::Upgrade(UpgradeObservation {
+            http,
+            upgrade: UpgradeInfo {
+                protocol: "websocket".to_string(),
+                messages_in: None,
+                messages_out: None,
+            },
+        }));
+    }
+    let (_, calls) = hub.register_call_client();
+    let protocols: Vec<Option<&str>> = calls
+        .iter()
+        .map(|call| {
+            call.upgrade
+                .as_ref()
+                .map(|upgrade| upgrade.protocol.as_str())
+        })
+        .collect();
+    assert_eq!(protocols, vec![None, Some("websocket")]);
+    let upgraded: Vec<Option<u64>> = hub
+        .edges()
+        .iter()
+        .filter(|edge| matches!(&edge.key, EdgeKey::Http { method, .. } if method == "WEBSOCKET"))
+        .map(|edge| edge.stats.p95_ms)
+        .collect();
+    assert_eq!(upgraded, vec![None]);
+}
+
+#[test]
+fn ignore_rules_match_paths_without_the_query() {
+    let ignore = TrafficIgnore::parse("/healthz, /metrics*");
+    let with_path = |path: &str| {
+        let mut obs = observation(1_000, 200, 5);
+        if let Observation::Http(http) = &mut obs {
+            http.path = Some(path.to_string());
+        }
+        obs
+    };
+    assert!(ignore.matches(&with_path("/healthz?verbose=1")));
+    assert!(ignore.matches(&with_path("/metrics/prometheus")));
+    assert!(!ignore.matches(&with_path("/items")));
+    assert!(!TrafficIgnore::default().matches(&with_path("/healthz")));
+
+    let hub = TrafficHub::new();
+    hub.suppress("api");
+    hub.suppress("api");
+    assert_eq!(hub.suppressed().get("api"), Some(&2));
+}
@@ -1,32 +1,222 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::fs;
 
-use crate::domain::ServiceInfo;
+use serde_yaml::Value;
 
-pub fn build_service_info(compose_file: &str) -> Vec<ServiceInfo> {
+use crate::domain::traffic::{EdgeKey, TrafficEdge};
+use crate::domain::{
+    Dependency, DependencyKind, EdgeStatus, GraphEdge, GraphView, PublishedEndpoint, ServiceGraph,
+    ServiceInfo,
+};
+
+pub fn build_service_info(compose_file: &str, host: &str) -> Vec<ServiceInfo> {
     let (services, ports_by_service) = parse_compose_services_and_ports(compose_file);
     let mut info = Vec::new();
     for name in services {
-        let endpoints: Vec<String> = ports_by_service
+        let ports = ports_by_service
             .get(&name)
-            .map(|ports| {
-                ports
-                    .iter()
-                    .map(|port| format!("http://localhost:{port}"))
-                    .collect()
-            })
+            .map(Vec::as_slice)
             .unwrap_or_default();
+        let endpoints: Vec<String> = ports
+            .iter()
+            .map(|port| endpoint_url("http", host, port))
+            .collect();
+        let published = ports
+            .iter()
+            .filter_map(|port| {
+                Some(PublishedEndpoint {
+                    host: host.to_string(),
+                    port: port.parse().ok()?,
+                    url: endpoint_url("http", host, port),
+                    protocol: None,
+                    forwarded: false,
+                })
+            })
+            .collect();
         info.push(ServiceInfo {
             name: name.clone(),
             endpoint: endpoints.first().cloned(),
             exposed: !endpoints.is_empty(),
             endpoints,
+            published,
         });
     }
     info
 }
 
+pub fn service_url(services: &[ServiceInfo], name: &str) -> Option<String> {
+    services
+        .iter()
+        .filter(|service| service.exposed)
+        .find(|service| service.name == name)
+        .and_then(|service| service.endpoint.clone())
+}
+
+pub fn endpoint_rows(services: &[ServiceInfo]) -> Vec<[String; 2]> {
+    services
+        .iter()
+        .flat_map(|service| {
+            service
+                .endpoints
+                .iter()
+                .map(|url| [service.name.clone(), url.clone()])
+        })
+        .collect()
+}
+
+pub fn endpoint_url(scheme: &str, host: &str, port: impl std::fmt::Display) -> String {
+    if host.contains(':') {
+        format!("{scheme}://[{host}]:{port}")
+    } else {
+        format!("{scheme}://{host}:{port}")
+    }
+}
+
+pub fn build_pane(
+    service: &str,
+    aliases: &HashMap<String, String>,
+    no_cache: bool,
+) -> (Vec<String>, String) {
+    let mut args = vec!["build".to_string()];
+    if no_cache {
+        args.push("--no-cache".to_string());
+    }
+    args.push(service.to_string());
+    let name = aliases.get(service).map_or(service, String::as_str);
+    (args, format!("build/{name}"))
+}
+
+pub fn build_dependency_graph(compose_file: &str) -> ServiceGraph {
+    fs::read_to_string(compose_file)
+        .ok()
+        .and_then(|contents| serde_yaml::from_str::<Value>(&contents).ok())
+        .map(|doc| dependency_graph_from_doc(&doc))
+        .unwrap_or_default()
+}
+
+pub fn dependency_graph_from_doc(doc: &Value) -> ServiceGraph {
+    let mut graph = ServiceGraph::default();
+    let Some(services_map) = doc.get("services").and_then(Value::as_mapping) else {
+        return graph;
+    };
+    for (name_val, service_val) in services_map {
+        let Some(name) = name_val.as_str() else {
+            continue;
+        };
+        graph.services.push(name.to_string());
+        let mut push = |to: String, kind: DependencyKind| {
+            graph.dependencies.push(Dependency {
+                from: name.to_string(),
+                to,
+                kind,
+            });
+        };
+        for to in yaml_names(service_val.get("depends_on")) {
+            push(to, DependencyKind::DependsOn);
+        }
+        for link in yaml_names(service_val.get("links")) {
+            let to = link.split(':').next().unwrap_or(&link).to_string();
+            push(to, DependencyKind::Link);
+        }
+        if let Some(to) = service_val
+            .get("network_mode")
+            .and_then(Value::as_str)
+            .and_then(|mode| mode.strip_prefix("service:"))
+        {
+            push(to.to_string(), DependencyKind::NetworkMode);
+        }
+        let mut networks = yaml_names(service_val.get("networks"));
+        if networks.is_empty() && service_val.get("network_mode").is_none() {
+            networks.push("default".to_string());
+        }
+        for network in networks {
+            graph
+                .networks
+                .entry(network)
+                .or_default()
+                .push(name.to_string());
+        }
+    }
+    graph
+}
+
+pub fn overlay_traffic(graph: &ServiceGraph, edges: &[TrafficEdge]) -> GraphView {
+    let mut merged: BTreeMap<(String, String), GraphEdge> = BTreeMap::new();
+    for dependency in &graph.dependencies {
+        let edge = merged
+            .entry((dependency.from.clone(), dependency.to.clone()))
+            .or_insert_with(|| new_graph_edge(graph, &dependency.from, &dependency.to));
+        if !edge.declared.contains(&dependency.kind) {
+            edge.declared.push(dependency.kind);
+        }
+    }
+    for traffic in edges {
+        let Some((from, to)) = edge_workloads(&traffic.key) else {
+            continue;
+        };
+        if from == to {
+            continue;
+        }
+        let edge = merged
+            .entry((from.to_string(), to.to_string()))
+            .or_insert_with(|| new_graph_edge(graph, from, to));
+        edge.status = if edge.declared.is_empty() {
+            EdgeStatus::Undeclared
+        } else {
+            EdgeStatus::Confirmed
+        };
+        edge.calls += traffic.stats.count;
+        edge.errors += traffic.stats.errors;
+    }
+    GraphView {
+        services: graph.services.clone(),
+        networks: graph.networks.clone(),
+        edges: merged.into_values().collect(),
+    }
+}
+
+fn new_graph_edge(graph: &ServiceGraph, from: &str, to: &str) -> GraphEdge {
+    let same_network = graph.networks.values().any(|members| {
+        members.iter().any(|member| member == from) && members.iter().any(|member| member == to)
+    });
+    GraphEdge {
+        from: from.to_string(),
+        to: to.to_string(),
+        declared: Vec::new(),
+        same_network,
+        status: EdgeStatus::Declared,
+        calls: 0,
+        errors: 0,
+    }
+}
+
+fn edge_workloads(key: &EdgeKey) -> Option<(&str, &str)> {
+    let (from, to) = match key {
+        EdgeKey::Flow { from, to, .. }
+        | EdgeKey::Http { from, to, .. }
+        | EdgeKey::Db { from, to, .. }
+        | EdgeKey::Grpc { from, to, .. } => (from, to),
+    };
+    Some((from.workload_name()?, to.workload_name()?))
+}
+
+fn yaml_names(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Sequence(list)) => list
+            .iter()
+            .filter_map(Value::as_str)
+            .map(ToString::to_string)
+            .collect(),
+        Some(Value::Mapping(map)) => map
+            .keys()
+            .filter_map(Value::as_str)
+            .map(ToString::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
 fn parse_compose_services_and_ports(
     compose_file: &str,
 ) -> (Vec<String>, HashMap<String, Vec<String>>) {
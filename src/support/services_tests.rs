@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::fs;
+
+use super::services::{
+    build_pane, build_service_info, dependency_graph_from_doc, endpoint_rows, overlay_traffic,
+    service_url,
+};
+use crate::domain::traffic::{EdgeKey, EdgeStats, EntityId, TrafficEdge, Visibility};
+use crate::domain::{DependencyKind, EdgeStatus};
+
+const COMPOSE: &str = "services:
+  web:
+    depends_on: [api]
+    networks: [front]
+  api:
+    depends_on:
+      db:
+        condition: service_healthy
+    links: ['cache:redis']
+    networks: [front, back]
+  db:
+    networks: [back]
+  cache: {}
+";
+
+fn workload(name: &str) -> EntityId {
+    EntityId::Workload {
+        name: name.to_string(),
+        instance: None,
+    }
+}
+
+fn http_edge(from: &str, to: &str, count: u64) -> TrafficEdge {
+    TrafficEdge {
+        key: EdgeKey::Http {
+            from: workload(from),
+            to: workload(to),
+            method: "GET".to_string(),
+            route: "/".to_string(),
+        },
+        stats: EdgeStats {
+            count,
+            bytes_in: 0,
+            bytes_out: 0,
+            errors: 0,
+            p50_ms: None,
+            p95_ms: None,
+            p99_ms: None,
+            visibility: Visibility::L7Envelope,
+            networks: Vec::new(),
+        },
+        last_seen_ms: 0,
+    }
+}
+
+#[test]
+fn parses_depends_on_links_and_networks() {
+    let doc = serde_yaml::from_str(COMPOSE).unwrap_or(serde_yaml::Value::Null);
+    let graph = dependency_graph_from_doc(&doc);
+    let declared: Vec<(&str, &str, DependencyKind)> = graph
+        .dependencies
+        .iter()
+        .map(|dep| (dep.from.as_str(), dep.to.as_str(), dep.kind))
+        .collect();
+    assert_eq!(
+        declared,
+        vec![
+            ("web", "api", DependencyKind::DependsOn),
+            ("api", "db", DependencyKind::DependsOn),
+            ("api", "cache", DependencyKind::Link),
+        ]
+    );
+    assert_eq!(
+        graph.networks.get("back").cloned().unwrap_or_default(),
+        vec!["api".to_string(), "db".to_string()]
+    );
+    assert_eq!(
+        graph.networks.get("default").cloned().unwrap_or_default(),
+        vec!["cache".to_string()]
+    );
+}
+
+#[test]
+fn observed_traffic_marks_undeclared_edges() {
+    let doc = serde_yaml::from_str(COMPOSE).unwrap_or(serde_yaml::Value::Null);
+    let graph = dependency_graph_from_doc(&doc);
+    let view = overlay_traffic(
+        &graph,
+        &[http_edge("web", "api", 3), http_edge("web", "db", 1)],
+    );
+    let status = |from: &str, to: &str| {
+        view.edges
+            .iter()
+            .find(|edge| edge.from == from && edge.to == to)
+            .map(|edge| edge.status)
+    };
+    assert_eq!(status("web", "api"), Some(EdgeStatus::Confirmed));
+    assert_eq!(status("web", "db"), Some(EdgeStatus::Undeclared));
+    assert_eq!(status("api", "db"), Some(EdgeStatus::Declared));
+}
+
+#[test]
+fn builds_each_service_into_its_own_pane() {
+    let aliases = HashMap::from([("api".to_string(), "backend".to_string())]);
+    let (args, pane) = build_pane("api", &aliases, true);
+    assert_eq!(args, vec!["build", "--no-cache", "api"]);
+    assert_eq!(pane, "build/backend");
+    let (args, pane) = build_pane("worker", &aliases, false);
+    assert_eq!(args, vec!["build", "worker"]);
+    assert_eq!(pane, "build/worker");
+}
+
+#[test]
+fn lists_and_looks_up_published_service_urls() {
+    let dir = std::env::temp_dir().join(format!("sanelens-open-{}", std::process::id()));
+    let _ = fs::create_dir_all(&dir);
+    let compose = dir.join("compose.yaml");
+    let written = fs::write(
+        &compose,
+        "services:\n  web:\n    ports: ['8080:80', '8443:443']\n  worker: {}\n",
+    );
+    assert!(written.is_ok());
+    let services = build_service_info(&compose.to_string_lossy(), "localhost");
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(
+        endpoint_rows(&services),
+        vec![
+            ["web".to_string(), "http://localhost:8080".to_string()],
+            ["web".to_string(), "http://localhost:8443".to_string()],
+        ]
+    );
+    assert_eq!(
+        service_url(&services, "web").as_deref(),
+        Some("http://localhost:8080")
+    );
+    assert_eq!(service_url(&services, "worker"), None);
+    assert_eq!(service_url(&services, "db"), None);
+}
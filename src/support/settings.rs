@@ -0,0 +1,209 @@
+use std::env;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use super::args::{extract_compose_file_arg, first_compose_file};
+use super::constants::BIN_NAME;
+
+pub const ENVOY_IMAGE_ENV: &str = "SANELENS_ENVOY_IMAGE";
+pub const EGRESS_INIT_IMAGE_ENV: &str = "SANELENS_EGRESS_INIT_IMAGE";
+pub const FORWARD_RELAY_IMAGE_ENV: &str = "SANELENS_FORWARD_RELAY_IMAGE";
+pub const BROWSER_READY_TIMEOUT_ENV: &str = "SANELENS_BROWSER_READY_TIMEOUT";
+pub const CRASH_LOOP_ENV: &str = "SANELENS_CRASH_LOOP";
+pub const RUN_DIR_ENV: &str = "SANELENS_RUN_DIR";
+
+const ENV_DEFAULTS: &[(&str, Option<&str>)] = &[
+    ("COMPOSE_CMD", None),
+    ("COMPOSE_FILE", None),
+    ("COMPOSE_PROFILES", None),
+    ("COMPOSE_PROJECT_NAME", None),
+    ("COMPOSE_LOG_UI", Some("1")),
+    ("COMPOSE_LOG_COLOR", Some("1")),
+    ("COMPOSE_LOG_TIMESTAMPS", Some("1")),
+    ("COMPOSE_DEFAULT_BUILD", Some("0")),
+    ("COMPOSE_DEFAULT_REMOVE_ORPHANS", Some("1")),
+    ("PODMAN_CONNECTION", None),
+    ("DOCKER_HOST", None),
+    ("CONTAINER_HOST", None),
+    ("NO_COLOR", None),
+    ("SANELENS_ENGINE_API", Some("1")),
+    ("SANELENS_KEEP_VOLUMES", Some("0")),
+    ("SANELENS_ROOTLESS_COMPAT", Some("auto")),
+    ("SANELENS_EGRESS_PROXY", Some("0")),
+    ("SANELENS_EGRESS_MODE", Some("proxy")),
+    (EGRESS_INIT_IMAGE_ENV, Some("alpine:3.20")),
+    (FORWARD_RELAY_IMAGE_ENV, Some("alpine/socat:latest")),
+    (ENVOY_IMAGE_ENV, Some("envoyproxy/envoy:v1.30-latest")),
+    (BROWSER_READY_TIMEOUT_ENV, Some("30s")),
+    (CRASH_LOOP_ENV, Some("3/5m")),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Flag,
+    Env,
+    Default,
+}
+
+impl Source {
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Flag => "flag",
+            Self::Env => "env",
+            Self::Default => "default",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Setting {
+    pub name: String,
+    pub value: Option<String>,
+    pub source: Source,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl Setting {
+    pub fn new(name: &str, value: Option<String>, source: Source) -> Self {
+        Self {
+            name: name.to_string(),
+            value,
+            source,
+            note: None,
+        }
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+}
+
+pub fn process_env(name: &str) -> Option<String> {
+    env::var(name).ok()
+}
+
+pub fn env_or_default(name: &str) -> Option<String> {
+    process_env(name).or_else(|| env_default(name).map(str::to_string))
+}
+
+fn env_default(name: &str) -> Option<&'static str> {
+    ENV_DEFAULTS
+        .iter()
+        .find(|(key, _)| *key == name)
+        .and_then(|(_, default)| *default)
+}
+
+pub fn env_settings(lookup: &dyn Fn(&str) -> Option<String>) -> Vec<Setting> {
+    ENV_DEFAULTS
+        .iter()
+        .map(|(name, default)| {
+            lookup(name).map_or_else(
+                || Setting::new(name, default.map(str::to_string), Source::Default),
+                |value| Setting::new(name, Some(value), Source::Env),
+            )
+        })
+        .collect()
+}
+
+pub struct ResolvedArgs {
+    pub compose_file: Setting,
+    pub profiles: Setting,
+    pub project_name: Option<Setting>,
+    pub run_dir: Setting,
+}
+
+pub fn resolve_args(args: &[String], lookup: &dyn Fn(&str) -> Option<String>) -> ResolvedArgs {
+    ResolvedArgs {
+        compose_file: compose_file(args, lookup),
+        profiles: profiles(args, lookup),
+        project_name: requested_project_name(args, lookup),
+        run_dir: run_dir(args, lookup),
+    }
+}
+
+pub fn compose_file(args: &[String], lookup: &dyn Fn(&str) -> Option<String>) -> Setting {
+    if let Some(path) = extract_compose_file_arg(args) {
+        return Setting::new("compose file", Some(path), Source::Flag);
+    }
+    lookup("COMPOSE_FILE").map_or_else(
+        || Setting::new("compose file", None, Source::Default),
+        |value| {
+            Setting::new("compose file", first_compose_file(&value), Source::Env)
+                .with_note("first entry of COMPOSE_FILE")
+        },
+    )
+}
+
+pub fn requested_project_name(
+    args: &[String],
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Option<Setting> {
+    let flag = flag_values(args, &["-p", "--project-name"]).pop();
+    let setting = match flag {
+        Some(name) => Setting::new("project name", Some(name), Source::Flag),
+        None => Setting::new(
+            "project name",
+            Some(lookup("COMPOSE_PROJECT_NAME").filter(|name| !name.is_empty())?),
+            Source::Env,
+        ),
+    };
+    Some(setting.with_note("ignored: every run gets its own project"))
+}
+
+pub fn run_dir(args: &[String], lookup: &dyn Fn(&str) -> Option<String>) -> Setting {
+    if let Some(dir) = flag_values(args, &["--run-dir"]).pop() {
+        return Setting::new("run dir", Some(dir), Source::Flag);
+    }
+    if let Some(dir) = lookup(RUN_DIR_ENV).filter(|dir| !dir.is_empty()) {
+        return Setting::new("run dir", Some(dir), Source::Env);
+    }
+    let cache = lookup("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| lookup("HOME").map(|home| PathBuf::from(home).join(".cache")));
+    let setting = Setting::new(
+        "run dir",
+        cache.map(|dir| dir.join(BIN_NAME).to_string_lossy().into_owned()),
+        Source::Default,
+    );
+    setting.with_note("XDG cache; falls back to .sanelens next to the compose file")
+}
+
+pub fn profiles(args: &[String], lookup: &dyn Fn(&str) -> Option<String>) -> Setting {
+    let flags = flag_values(args, &["--profile"]);
+    if !flags.is_empty() {
+        return Setting::new("profiles", Some(flags.join(",")), Source::Flag);
+    }
+    let value = lookup("COMPOSE_PROFILES").filter(|value| !value.is_empty());
+    let source = if value.is_some() {
+        Source::Env
+    } else {
+        Source::Default
+    };
+    Setting::new("profiles", value, source)
+}
+
+fn flag_values(args: &[String], names: &[&str]) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            break;
+        }
+        if names.contains(&arg.as_str()) {
+            values.extend(iter.next().cloned());
+            continue;
+        }
+        if let Some(value) = names.iter().find_map(|name| {
+            arg.strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix('='))
+        }) {
+            values.push(value.to_string());
+        }
+    }
+    values
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use super::args::replace_compose_file_arg;
+use super::settings::{env_settings, resolve_args, run_dir, Source};
+
+fn args(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| (*value).to_string()).collect()
+}
+
+#[test]
+fn flags_win_over_the_environment() {
+    let env: HashMap<&str, &str> = HashMap::from([
+        ("COMPOSE_FILE", "base.yml:override.yml"),
+        ("COMPOSE_PROFILES", "debug"),
+        ("COMPOSE_PROJECT_NAME", "shop"),
+    ]);
+    let lookup = |name: &str| env.get(name).map(|value| (*value).to_string());
+
+    let resolved = resolve_args(&args(&["up"]), &lookup);
+    assert_eq!(resolved.compose_file.value.as_deref(), Some("base.yml"));
+    assert_eq!(resolved.compose_file.source, Source::Env);
+    assert_eq!(resolved.profiles.value.as_deref(), Some("debug"));
+    assert_eq!(
+        resolved.project_name.map(|setting| setting.source),
+        Some(Source::Env)
+    );
+
+    let flags = args(&[
+        "-f",
+        "dev.yml",
+        "--profile",
+        "a",
+        "--profile=b",
+        "-p",
+        "demo",
+        "up",
+    ]);
+    let resolved = resolve_args(&flags, &lookup);
+    assert_eq!(resolved.compose_file.value.as_deref(), Some("dev.yml"));
+    assert_eq!(resolved.compose_file.source, Source::Flag);
+    assert_eq!(resolved.profiles.value.as_deref(), Some("a,b"));
+    assert_eq!(
+        resolved.project_name.and_then(|setting| setting.value),
+        Some("demo".to_string())
+    );
+}
+
+#[test]
+fn unset_variables_report_their_defaults() {
+    let settings = env_settings(&|_| None);
+    let envoy = settings
+        .iter()
+        .find(|setting| setting.name == "SANELENS_ENVOY_IMAGE");
+    assert_eq!(
+        envoy.and_then(|setting| setting.value.as_deref()),
+        Some("envoyproxy/envoy:v1.30-latest")
+    );
+    assert!(settings
+        .iter()
+        .all(|setting| setting.source == Source::Default));
+}
+
+#[test]
+fn run_dir_prefers_flag_then_env_then_xdg_cache() {
+    let env: HashMap<&str, &str> =
+        HashMap::from([("HOME", "/home/dev"), ("SANELENS_RUN_DIR", "/srv/runs")]);
+    let lookup = |name: &str| env.get(name).map(|value| (*value).to_string());
+    let flag = run_dir(&args(&["--run-dir=/tmp/runs", "up"]), &lookup);
+    assert_eq!(flag.value.as_deref(), Some("/tmp/runs"));
+    assert_eq!(flag.source, Source::Flag);
+    let from_env = run_dir(&args(&["up"]), &lookup);
+    assert_eq!(from_env.value.as_deref(), Some("/srv/runs"));
+    assert_eq!(from_env.source, Source::Env);
+
+    let home_only = |name: &str| (name == "HOME").then(|| "/home/dev".to_string());
+    let default = run_dir(&args(&["up"]), &home_only);
+    assert_eq!(default.value.as_deref(), Some("/home/dev/.cache/sanelens"));
+    assert_eq!(default.source, Source::Default);
+    assert_eq!(run_dir(&args(&["up"]), &|_| None).value, None);
+}
+
+#[test]
+fn replaces_stdin_compose_file_args() {
+    let replaced = replace_compose_file_arg(
+        &args(&[
+            "-f", "base.yml", "--file", "-", "-f=-", "up", "--", "-f", "-",
+        ]),
+        "-",
+        "/tmp/run/compose.stdin.yaml",
+    );
+    assert_eq!(
+        replaced,
+        args(&[
+            "-f",
+            "base.yml",
+            "--file",
+            "/tmp/run/compose.stdin.yaml",
+            "-f=/tmp/run/compose.stdin.yaml",
+            "up",
+            "--",
+            "-f",
+            "-",
+        ])
+    );
+}
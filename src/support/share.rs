@@ -0,0 +1,149 @@
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShareTarget {
+    Ssh {
+        destination: String,
+        ssh_port: Option<u16>,
+        remote_port: u16,
+    },
+    Relay {
+        address: String,
+        public_url: Option<String>,
+    },
+}
+
+impl ShareTarget {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "Invalid --share {value:?}. Use ssh://[user@]host[:port][/remote_port] or tcp://relay:port[?url=public_url]."
+            )
+        };
+        let (scheme, rest) = value.trim().split_once("://").ok_or_else(invalid)?;
+        match scheme {
+            "ssh" => {
+                let (address, remote_port) = match rest.split_once('/') {
+                    Some((address, "")) => (address, 0),
+                    Some((address, port)) => (address, port.parse().map_err(|_| invalid())?),
+                    None => (rest, 0),
+                };
+                let (destination, ssh_port) = split_port(address).ok_or_else(invalid)?;
+                if destination.is_empty() || destination.ends_with('@') {
+                    return Err(invalid());
+                }
+                Ok(Self::Ssh {
+                    destination,
+                    ssh_port,
+                    remote_port,
+                })
+            }
+            "tcp" => parse_relay(rest).ok_or_else(invalid),
+            _ => Err(invalid()),
+        }
+    }
+
+    pub fn ssh_args(&self, local_port: u16) -> Vec<String> {
+        let Self::Ssh {
+            destination,
+            ssh_port,
+            remote_port,
+        } = self
+        else {
+            return Vec::new();
+        };
+        let mut args: Vec<String> = [
+            "-N",
+            "-T",
+            "-o",
+            "BatchMode=yes",
+            "-o",
+            "ExitOnForwardFailure=yes",
+            "-o",
+            "ServerAliveInterval=15",
+        ]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+        if let Some(port) = ssh_port {
+            args.extend(["-p".to_string(), port.to_string()]);
+        }
+        args.extend([
+            "-R".to_string(),
+            format!("{remote_port}:127.0.0.1:{local_port}"),
+            destination.clone(),
+        ]);
+        args
+    }
+
+    pub fn link(&self, remote_port: u16, token: &str) -> String {
+        match self {
+            Self::Ssh { destination, .. } => {
+                let host = destination
+                    .rsplit_once('@')
+                    .map_or(destination.as_str(), |(_, host)| host);
+                let host = if host.contains(':') {
+                    format!("[{host}]")
+                } else {
+                    host.to_string()
+                };
+                format!("http://{host}:{remote_port}/?token={token}")
+            }
+            Self::Relay {
+                public_url: Some(url),
+                ..
+            } => format!("{url}/?token={token}"),
+            Self::Relay { address, .. } => {
+                format!("<public URL of {address}>/?token={token}")
+            }
+        }
+    }
+}
+
+fn parse_relay(rest: &str) -> Option<ShareTarget> {
+    let (address, public_url) = match rest.split_once('?') {
+        Some((address, query)) => {
+            let url = query
+                .strip_prefix("url=")
+                .filter(|url| url.starts_with("http://") || url.starts_with("https://"))?;
+            (address, Some(url.trim_end_matches('/').to_string()))
+        }
+        None => (rest, None),
+    };
+    match split_port(address)? {
+        (host, Some(_)) if !host.is_empty() => Some(ShareTarget::Relay {
+            address: address.to_string(),
+            public_url,
+        }),
+        _ => None,
+    }
+}
+
+fn split_port(address: &str) -> Option<(String, Option<u16>)> {
+    let (user, host) = address
+        .rsplit_once('@')
+        .map_or(("", address), |(user, host)| (user, host));
+    let user = if user.is_empty() {
+        String::new()
+    } else {
+        format!("{user}@")
+    };
+    if let Some(rest) = host.strip_prefix('[') {
+        let (host, after) = rest.split_once(']')?;
+        let port = match after {
+            "" => None,
+            after => Some(after.strip_prefix(':')?.parse().ok()?),
+        };
+        return Some((format!("{user}{host}"), port));
+    }
+    match host.split_once(':') {
+        Some((name, port)) if !port.contains(':') => {
+            Some((format!("{user}{name}"), Some(port.parse().ok()?)))
+        }
+        _ => Some((address.to_string(), None)),
+    }
+}
+
+pub fn allocated_port(line: &str) -> Option<u16> {
+    let rest = line.trim().strip_prefix("Allocated port ")?;
+    let (port, _) = rest.split_once(' ')?;
+    port.parse().ok()
+}
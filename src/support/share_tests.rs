@@ -0,0 +1,79 @@
+use super::share::{allocated_port, ShareTarget};
+
+#[test]
+fn parses_ssh_and_relay_targets() {
+    assert_eq!(
+        ShareTarget::parse("ssh://dev@bastion.example.com"),
+        Ok(ShareTarget::Ssh {
+            destination: "dev@bastion.example.com".to_string(),
+            ssh_port: None,
+            remote_port: 0,
+        })
+    );
+    assert_eq!(
+        ShareTarget::parse("ssh://dev@bastion:2222/8443"),
+        Ok(ShareTarget::Ssh {
+            destination: "dev@bastion".to_string(),
+            ssh_port: Some(2222),
+            remote_port: 8443,
+        })
+    );
+    assert_eq!(
+        ShareTarget::parse("ssh://[2001:db8::7]:22/9000"),
+        Ok(ShareTarget::Ssh {
+            destination: "2001:db8::7".to_string(),
+            ssh_port: Some(22),
+            remote_port: 9000,
+        })
+    );
+    assert_eq!(
+        ShareTarget::parse("tcp://relay.internal:7000"),
+        Ok(ShareTarget::Relay {
+            address: "relay.internal:7000".to_string(),
+            public_url: None,
+        })
+    );
+    assert_eq!(
+        ShareTarget::parse("tcp://relay.internal:7000?url=https://share.example.com/"),
+        Ok(ShareTarget::Relay {
+            address: "relay.internal:7000".to_string(),
+            public_url: Some("https://share.example.com".to_string()),
+        })
+    );
+    assert!(ShareTarget::parse("tcp://relay.internal").is_err());
+    assert!(ShareTarget::parse("tcp://relay.internal:7000?url=share.example.com").is_err());
+    assert!(ShareTarget::parse("ssh://dev@").is_err());
+    assert!(ShareTarget::parse("ssh://bastion/http").is_err());
+    assert!(ShareTarget::parse("bastion:22").is_err());
+}
+
+#[test]
+fn builds_reverse_tunnel_commands_and_links() {
+    let target = ShareTarget::parse("ssh://dev@bastion:2222/8443");
+    assert_eq!(
+        target.as_ref().map(|target| target.ssh_args(41000).join(" ")),
+        Ok("-N -T -o BatchMode=yes -o ExitOnForwardFailure=yes -o ServerAliveInterval=15 -p 2222 -R 8443:127.0.0.1:41000 dev@bastion".to_string())
+    );
+    assert_eq!(
+        target.map(|target| target.link(8443, "t0k")),
+        Ok("http://bastion:8443/?token=t0k".to_string())
+    );
+    assert_eq!(
+        ShareTarget::parse("ssh://[2001:db8::7]").map(|target| target.link(40123, "t0k")),
+        Ok("http://[2001:db8::7]:40123/?token=t0k".to_string())
+    );
+    assert_eq!(
+        ShareTarget::parse("tcp://relay.internal:7000?url=https://share.example.com")
+            .map(|target| target.link(0, "t0k")),
+        Ok("https://share.example.com/?token=t0k".to_string())
+    );
+    assert_eq!(
+        ShareTarget::parse("tcp://relay.internal:7000").map(|target| target.link(0, "t0k")),
+        Ok("<public URL of relay.internal:7000>/?token=t0k".to_string())
+    );
+    assert_eq!(
+        allocated_port("Allocated port 40123 for remote forward to 127.0.0.1:41000"),
+        Some(40123)
+    );
+    assert_eq!(allocated_port("Warning: Permanently added 'bastion'"), None);
+}
@@ -0,0 +1,75 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::domain::ServiceGraph;
+
+const FORCE_KILL_WINDOW_MS: u64 = 3_000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShutdownOrder {
+    None,
+    #[default]
+    Graph,
+}
+
+impl ShutdownOrder {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "none" => Ok(Self::None),
+            "graph" => Ok(Self::Graph),
+            _ => Err(format!(
+                "Invalid --shutdown-order '{value}'. Use none or graph."
+            )),
+        }
+    }
+}
+
+pub fn shutdown_tiers(graph: &ServiceGraph) -> Vec<Vec<String>> {
+    let mut remaining: Vec<&str> = Vec::new();
+    for service in &graph.services {
+        if !remaining.contains(&service.as_str()) {
+            remaining.push(service);
+        }
+    }
+    let mut tiers = Vec::new();
+    while !remaining.is_empty() {
+        let (tier, rest): (Vec<&str>, Vec<&str>) = remaining.iter().partition(|service| {
+            !graph.dependencies.iter().any(|dependency| {
+                dependency.to == **service
+                    && dependency.from != dependency.to
+                    && remaining.contains(&dependency.from.as_str())
+            })
+        });
+        if tier.is_empty() {
+            tiers.push(rest.iter().map(ToString::to_string).collect());
+            break;
+        }
+        tiers.push(tier.iter().map(ToString::to_string).collect());
+        remaining = rest;
+    }
+    tiers
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interrupt {
+    Stop,
+    ForceKill,
+    StillStopping,
+}
+
+#[derive(Default)]
+pub struct InterruptTracker {
+    last_ms: AtomicU64,
+}
+
+impl InterruptTracker {
+    pub fn press(&self, now_ms: u64, stopping: bool) -> Interrupt {
+        let last = self.last_ms.swap(now_ms, Ordering::SeqCst);
+        if !stopping {
+            Interrupt::Stop
+        } else if now_ms.saturating_sub(last) <= FORCE_KILL_WINDOW_MS {
+            Interrupt::ForceKill
+        } else {
+            Interrupt::StillStopping
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use super::services::dependency_graph_from_doc;
+use super::shutdown::{shutdown_tiers, Interrupt, InterruptTracker, ShutdownOrder};
+
+#[test]
+fn stops_dependents_before_their_dependencies() {
+    let doc = serde_yaml::from_str(
+        r"
+services:
+  web:
+    depends_on: [api]
+  api:
+    depends_on:
+      db:
+        condition: service_healthy
+      cache:
+        condition: service_started
+  worker:
+    links: ['db:database']
+  db: {}
+  cache: {}
+",
+    )
+    .unwrap_or_default();
+    let tiers = shutdown_tiers(&dependency_graph_from_doc(&doc));
+    assert_eq!(
+        tiers,
+        vec![
+            vec!["web".to_string(), "worker".to_string()],
+            vec!["api".to_string()],
+            vec!["db".to_string(), "cache".to_string()],
+        ]
+    );
+}
+
+#[test]
+fn keeps_cycles_together_in_the_last_tier() {
+    let doc = serde_yaml::from_str(
+        r"
+services:
+  edge:
+    depends_on: [a]
+  a:
+    depends_on: [b]
+  b:
+    depends_on: [a]
+",
+    )
+    .unwrap_or_default();
+    let tiers = shutdown_tiers(&dependency_graph_from_doc(&doc));
+    assert_eq!(
+        tiers,
+        vec![
+            vec!["edge".to_string()],
+            vec!["a".to_string(), "b".to_string()]
+        ]
+    );
+    assert_eq!(ShutdownOrder::parse("none"), Ok(ShutdownOrder::None));
+    assert_eq!(ShutdownOrder::parse("graph"), Ok(ShutdownOrder::Graph));
+    assert!(ShutdownOrder::parse("reverse").is_err());
+}
+
+#[test]
+fn a_quick_second_interrupt_forces_a_kill() {
+    let tracker = InterruptTracker::default();
+    assert_eq!(tracker.press(10_000, false), Interrupt::Stop);
+    assert_eq!(tracker.press(20_000, true), Interrupt::StillStopping);
+    assert_eq!(tracker.press(21_000, true), Interrupt::ForceKill);
+}
@@ -0,0 +1,101 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+pub const DEFAULT_SKEW_THRESHOLD: Duration = Duration::from_secs(2);
+const SKEW_SAMPLES: usize = 32;
+const MIN_SKEW_SAMPLES: usize = 8;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClockSkewPolicy {
+    pub threshold: Duration,
+    pub correct: bool,
+}
+
+impl ClockSkewPolicy {
+    pub const fn enabled(&self) -> bool {
+        !self.threshold.is_zero()
+    }
+
+    fn threshold_ms(&self) -> i64 {
+        i64::try_from(self.threshold.as_millis()).unwrap_or(i64::MAX)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkewChange {
+    Detected(i64),
+    Cleared,
+}
+
+#[derive(Default)]
+struct ServiceSkew {
+    samples: VecDeque<i64>,
+    flagged: bool,
+}
+
+impl ServiceSkew {
+    fn estimate(&self) -> Option<i64> {
+        if self.samples.len() < MIN_SKEW_SAMPLES {
+            return None;
+        }
+        self.samples.iter().copied().max()
+    }
+}
+
+pub struct ClockSkewTracker {
+    policy: ClockSkewPolicy,
+    services: HashMap<String, ServiceSkew>,
+}
+
+impl ClockSkewTracker {
+    pub fn new(policy: ClockSkewPolicy) -> Self {
+        Self {
+            policy,
+            services: HashMap::new(),
+        }
+    }
+
+    pub const fn policy(&self) -> ClockSkewPolicy {
+        self.policy
+    }
+
+    pub fn observe(&mut self, service: &str, ts_ms: i64, received_ms: i64) -> Option<SkewChange> {
+        if !self.policy.enabled() {
+            return None;
+        }
+        let threshold = self.policy.threshold_ms();
+        let skew = self.services.entry(service.to_string()).or_default();
+        skew.samples.push_back(ts_ms.saturating_sub(received_ms));
+        while skew.samples.len() > SKEW_SAMPLES {
+            skew.samples.pop_front();
+        }
+        let estimate = skew.estimate()?;
+        let skewed = estimate.saturating_abs() > threshold;
+        if skewed == skew.flagged {
+            return None;
+        }
+        skew.flagged = skewed;
+        Some(if skewed {
+            SkewChange::Detected(estimate)
+        } else {
+            SkewChange::Cleared
+        })
+    }
+
+    pub fn correction(&self, service: &str) -> i64 {
+        if !self.policy.correct {
+            return 0;
+        }
+        self.services
+            .get(service)
+            .filter(|skew| skew.flagged)
+            .and_then(ServiceSkew::estimate)
+            .unwrap_or(0)
+    }
+}
+
+pub fn format_skew(skew_ms: i64) -> String {
+    let sign = if skew_ms < 0 { '-' } else { '+' };
+    let abs = skew_ms.unsigned_abs();
+    format!("{sign}{}.{:03}s", abs / 1_000, abs % 1_000)
+}
@@ -0,0 +1,21 @@
+use std::time::Duration;
+
+use super::skew::{format_skew, ClockSkewPolicy, ClockSkewTracker, SkewChange};
+
+#[test]
+fn flags_services_whose_clock_runs_ahead() {
+    let mut tracker = ClockSkewTracker::new(ClockSkewPolicy {
+        threshold: Duration::from_secs(2),
+        correct: true,
+    });
+    let mut changes = Vec::new();
+    for offset in 0..10 {
+        let received = 1_000_000 + offset * 100;
+        changes.extend(tracker.observe("api", received + 5_000 - offset, received));
+        changes.extend(tracker.observe("db", received - 40, received));
+    }
+    assert_eq!(changes, vec![SkewChange::Detected(5_000)]);
+    assert_eq!(tracker.correction("api"), 5_000);
+    assert_eq!(tracker.correction("db"), 0);
+    assert_eq!(format_skew(-1_250), "-1.250s");
+}
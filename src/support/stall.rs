@@ -0,0 +1,13 @@
+use std::time::{Duration, Instant};
+
+pub fn stall_idle(
+    progress: Instant,
+    warned_at: Option<Instant>,
+    timeout: Duration,
+    now: Instant,
+) -> Option<Duration> {
+    let idle = now.saturating_duration_since(progress);
+    let warned_recently =
+        warned_at.is_some_and(|warned| now.saturating_duration_since(warned) < timeout);
+    (idle >= timeout && !warned_recently).then_some(idle)
+}
@@ -0,0 +1,21 @@
+use std::time::{Duration, Instant};
+
+use super::stall::stall_idle;
+
+#[test]
+fn reports_a_stall_once_per_timeout_window() {
+    let timeout = Duration::from_mins(1);
+    let progress = Instant::now();
+    let at = |secs| progress + Duration::from_secs(secs);
+    assert_eq!(stall_idle(progress, None, timeout, at(59)), None);
+    assert_eq!(
+        stall_idle(progress, None, timeout, at(60)),
+        Some(Duration::from_mins(1))
+    );
+    assert_eq!(stall_idle(progress, Some(at(60)), timeout, at(90)), None);
+    assert_eq!(
+        stall_idle(progress, Some(at(60)), timeout, at(120)),
+        Some(Duration::from_mins(2))
+    );
+    assert_eq!(stall_idle(at(100), Some(at(60)), timeout, at(130)), None);
+}
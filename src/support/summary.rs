@@ -0,0 +1,82 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::domain::traffic::{EdgeKey, TrafficEdge};
+
+pub const SLOWEST_ENDPOINT_LIMIT: usize = 5;
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RunSummary {
+    pub run_id: String,
+    pub services: Vec<ServiceSummary>,
+    pub requests: BTreeMap<String, u64>,
+    pub suppressed: BTreeMap<String, u64>,
+    pub slowest: Vec<EndpointSummary>,
+    pub external_hosts: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ServiceSummary {
+    pub service: String,
+    pub state: Option<String>,
+    pub exit_code: Option<i64>,
+    pub restarts: u64,
+    pub log_lines: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct EndpointSummary {
+    pub service: String,
+    pub method: String,
+    pub route: String,
+    pub count: u64,
+    pub p95_ms: u64,
+}
+
+pub fn slowest_endpoints(edges: &[TrafficEdge], limit: usize) -> Vec<EndpointSummary> {
+    let mut endpoints: BTreeMap<(String, String, String), EndpointSummary> = BTreeMap::new();
+    for edge in edges {
+        let EdgeKey::Http {
+            to, method, route, ..
+        } = &edge.key
+        else {
+            continue;
+        };
+        let Some(p95_ms) = edge.stats.p95_ms else {
+            continue;
+        };
+        let service = to.workload_name().unwrap_or("?").to_string();
+        let path = route.split('?').next().unwrap_or(route).to_string();
+        let entry = endpoints
+            .entry((service.clone(), method.clone(), path.clone()))
+            .or_insert_with(|| EndpointSummary {
+                service,
+                method: method.clone(),
+                route: path,
+                count: 0,
+                p95_ms: 0,
+            });
+        entry.count += edge.stats.count;
+        entry.p95_ms = entry.p95_ms.max(p95_ms);
+    }
+    let mut endpoints: Vec<EndpointSummary> = endpoints.into_values().collect();
+    endpoints.sort_by_key(|endpoint| std::cmp::Reverse(endpoint.p95_ms));
+    endpoints.truncate(limit);
+    endpoints
+}
+
+pub fn external_hosts(edges: &[TrafficEdge]) -> Vec<String> {
+    edges
+        .iter()
+        .filter_map(|edge| {
+            let (EdgeKey::Flow { to, .. }
+            | EdgeKey::Http { to, .. }
+            | EdgeKey::Db { to, .. }
+            | EdgeKey::Grpc { to, .. }) = &edge.key;
+            to.external_host()
+        })
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
@@ -0,0 +1,89 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use super::summary::{external_hosts, slowest_endpoints};
+use crate::domain::traffic::{EdgeKey, EdgeStats, EntityId, TrafficEdge, Transport, Visibility};
+
+fn workload(name: &str) -> EntityId {
+    EntityId::Workload {
+        name: name.to_string(),
+        instance: None,
+    }
+}
+
+fn edge(key: EdgeKey, count: u64, p95_ms: Option<u64>) -> TrafficEdge {
+    TrafficEdge {
+        key,
+        stats: EdgeStats {
+            count,
+            bytes_in: 0,
+            bytes_out: 0,
+            errors: 0,
+            p50_ms: None,
+            p95_ms,
+            p99_ms: None,
+            visibility: Visibility::L7Envelope,
+            networks: Vec::new(),
+        },
+        last_seen_ms: 0,
+    }
+}
+
+fn http(from: &str, to: &str, route: &str) -> EdgeKey {
+    EdgeKey::Http {
+        from: workload(from),
+        to: workload(to),
+        method: "GET".to_string(),
+        route: route.to_string(),
+    }
+}
+
+#[test]
+fn ranks_endpoints_by_p95_across_callers() {
+    let edges = vec![
+        edge(http("web", "api", "/orders?page=1"), 3, Some(120)),
+        edge(http("worker", "api", "/orders"), 2, Some(450)),
+        edge(http("web", "api", "/health"), 10, Some(2)),
+        edge(http("web", "auth", "/login"), 1, Some(300)),
+        edge(http("web", "auth", "/logout"), 1, None),
+    ];
+    let slowest = slowest_endpoints(&edges, 2);
+    let ranked: Vec<(&str, &str, u64, u64)> = slowest
+        .iter()
+        .map(|endpoint| {
+            (
+                endpoint.service.as_str(),
+                endpoint.route.as_str(),
+                endpoint.count,
+                endpoint.p95_ms,
+            )
+        })
+        .collect();
+    assert_eq!(
+        ranked,
+        vec![("api", "/orders", 5, 450), ("auth", "/login", 1, 300)]
+    );
+}
+
+#[test]
+fn lists_each_external_host_once() {
+    let external = |dns_name: Option<&str>| EntityId::External {
+        ips: vec![IpAddr::V4(Ipv4Addr::new(140, 82, 112, 3))],
+        dns_name: dns_name.map(ToString::to_string),
+    };
+    let flow = |to: EntityId| EdgeKey::Flow {
+        from: workload("api"),
+        to,
+        transport: Transport::Tcp,
+        port: 443,
+    };
+    let edges = vec![
+        edge(flow(external(Some("api.github.com"))), 1, None),
+        edge(flow(external(None)), 1, None),
+        edge(flow(external(Some("api.github.com"))), 1, None),
+        edge(flow(workload("db")), 1, None),
+    ];
+    assert_eq!(
+        external_hosts(&edges),
+        vec!["140.82.112.3".to_string(), "api.github.com".to_string()]
+    );
+}
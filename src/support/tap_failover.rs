@@ -0,0 +1,106 @@
+use std::collections::VecDeque;
+
+pub const CAPTURE_TAG: &str = "capture";
+pub const HEADERS_ONLY: &str = "headers-only";
+const TAP_GRACE_MS: u64 = 5_000;
+const PENDING_LIMIT: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureMode {
+    Probing,
+    Tap,
+    HeadersOnly,
+}
+
+impl CaptureMode {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Probing => "probing",
+            Self::Tap => "tap",
+            Self::HeadersOnly => HEADERS_ONLY,
+        }
+    }
+}
+
+pub struct TapFailover<T> {
+    mode: CaptureMode,
+    pending: VecDeque<T>,
+    pending_since_ms: Option<u64>,
+}
+
+impl<T> TapFailover<T> {
+    pub const fn new() -> Self {
+        Self {
+            mode: CaptureMode::Probing,
+            pending: VecDeque::new(),
+            pending_since_ms: None,
+        }
+    }
+
+    pub const fn mode(&self) -> CaptureMode {
+        self.mode
+    }
+
+    pub fn access_log(&mut self, log: T, completed_at_ms: Option<u64>) -> Option<T> {
+        match self.mode {
+            CaptureMode::HeadersOnly => Some(log),
+            CaptureMode::Tap => None,
+            CaptureMode::Probing => {
+                if let Some(completed_at_ms) = completed_at_ms {
+                    self.pending_since_ms.get_or_insert(completed_at_ms);
+                }
+                if self.pending.len() >= PENDING_LIMIT {
+                    self.pending.pop_front();
+                }
+                self.pending.push_back(log);
+                None
+            }
+        }
+    }
+
+    pub fn tap_seen(&mut self) -> bool {
+        let switched = self.mode != CaptureMode::Tap;
+        self.mode = CaptureMode::Tap;
+        self.pending.clear();
+        self.pending_since_ms = None;
+        switched
+    }
+
+    pub fn tick(&mut self, now_ms: u64) -> Option<Vec<T>> {
+        let since = self
+            .pending_since_ms
+            .filter(|_| self.mode == CaptureMode::Probing)?;
+        if now_ms.saturating_sub(since) < TAP_GRACE_MS {
+            return None;
+        }
+        self.fail()
+    }
+
+    pub fn fail(&mut self) -> Option<Vec<T>> {
+        if self.mode == CaptureMode::HeadersOnly {
+            return None;
+        }
+        self.mode = CaptureMode::HeadersOnly;
+        self.pending_since_ms = None;
+        Some(self.pending.drain(..).collect())
+    }
+}
+
+impl<T> Default for TapFailover<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn is_tap_write_error(line: &str) -> bool {
+    let lower = line.to_ascii_lowercase();
+    lower.contains("/sanelens/tap")
+        && [
+            "permission denied",
+            "read-only file system",
+            "no such file",
+            "failed",
+        ]
+        .iter()
+        .any(|needle| lower.contains(needle))
+}
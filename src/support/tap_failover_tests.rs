@@ -0,0 +1,60 @@
+use super::tap_failover::{is_tap_write_error, CaptureMode, TapFailover};
+
+#[test]
+fn holds_access_logs_until_the_tap_shows_up_or_the_grace_runs_out() {
+    let mut confirmed = TapFailover::new();
+    assert_eq!(confirmed.access_log("GET /a", Some(0)), None);
+    assert!(confirmed.tap_seen());
+    assert!(!confirmed.tap_seen());
+    assert_eq!(confirmed.mode(), CaptureMode::Tap);
+    assert_eq!(confirmed.access_log("GET /b", Some(100)), None);
+    assert_eq!(confirmed.tick(60_000), None);
+
+    let mut silent = TapFailover::new();
+    assert_eq!(silent.tick(60_000), None);
+    assert_eq!(silent.access_log("GET /a", Some(1_000)), None);
+    assert_eq!(silent.access_log("GET /b", Some(2_000)), None);
+    assert_eq!(silent.tick(5_999), None);
+    assert_eq!(silent.tick(6_000), Some(vec!["GET /a", "GET /b"]));
+    assert_eq!(silent.mode(), CaptureMode::HeadersOnly);
+    assert_eq!(silent.access_log("GET /c", Some(6_100)), Some("GET /c"));
+    assert_eq!(silent.fail(), None);
+}
+
+#[test]
+fn a_late_tap_switches_headers_only_back_to_tap() {
+    let mut failover: TapFailover<&str> = TapFailover::new();
+    assert_eq!(failover.fail(), Some(Vec::new()));
+    assert!(failover.tap_seen());
+    assert_eq!(failover.mode(), CaptureMode::Tap);
+    assert_eq!(failover.access_log("GET /a", Some(0)), None);
+}
+
+#[test]
+fn only_completed_requests_start_the_grace_timer() {
+    let mut failover = TapFailover::new();
+    assert_eq!(failover.access_log("GET /stream", None), None);
+    assert_eq!(failover.tick(60_000), None);
+    assert_eq!(failover.access_log("GET /a", Some(60_000)), None);
+    assert_eq!(failover.tick(64_999), None);
+    assert_eq!(failover.tick(65_000), Some(vec!["GET /stream", "GET /a"]));
+}
+
+#[test]
+fn switches_a_confirmed_tap_to_headers_only_on_write_errors() {
+    let mut failover: TapFailover<&str> = TapFailover::new();
+    assert!(failover.tap_seen());
+    assert_eq!(failover.fail(), Some(Vec::new()));
+    assert_eq!(failover.mode().as_str(), "headers-only");
+
+    assert!(is_tap_write_error(
+        "[warning][tap] unable to open /sanelens/tap/api/1_0.json: Permission denied"
+    ));
+    assert!(is_tap_write_error(
+        "Failed to write /sanelens/tap/api/3.json (Read-only file system)"
+    ));
+    assert!(!is_tap_write_error(
+        "[info][main] starting main dispatch loop"
+    ));
+    assert!(!is_tap_write_error("connect failed: upstream reset"));
+}
@@ -0,0 +1,9 @@
+/// Shortens `value` to at most `width` characters, ending in `…` when cut.
+pub fn truncate(value: &str, width: usize) -> String {
+    if value.chars().count() <= width {
+        return value.to_string();
+    }
+    let mut truncated: String = value.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
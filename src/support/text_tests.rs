@@ -0,0 +1,10 @@
+use super::text::truncate;
+
+#[test]
+fn truncates_by_characters_not_bytes() {
+    assert_eq!(truncate("GET /orders", 20), "GET /orders");
+    assert_eq!(truncate("GET /orders", 6), "GET /…");
+    assert_eq!(truncate("日本語のログ行", 7), "日本語のログ行");
+    assert_eq!(truncate("日本語のログ行", 4), "日本語…");
+    assert_eq!(truncate("héllo", 1), "…");
+}
@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+pub const TIMELINE_RESOLUTION_MS: u64 = 1000;
+pub const TIMELINE_EVENT_LIMIT: usize = 2000;
+const TIMELINE_RETENTION_BUCKETS: usize = 6 * 60 * 60;
+const DEFAULT_SPAN_MS: u64 = 15 * 60 * 1000;
+const MAX_BUCKETS: u64 = 240;
+
+pub type ClassCounts = BTreeMap<String, u64>;
+pub type ServiceCounts = BTreeMap<String, ClassCounts>;
+
+#[derive(Default)]
+struct Bucket {
+    services: ServiceCounts,
+    seqs: Option<(u64, u64)>,
+}
+
+#[derive(Default)]
+pub struct TimelineIndex {
+    buckets: BTreeMap<u64, Bucket>,
+}
+
+impl TimelineIndex {
+    pub fn record(&mut self, at_ms: u64, service: &str, class: &str, count: u64) {
+        *self
+            .bucket(at_ms)
+            .services
+            .entry(service.to_string())
+            .or_default()
+            .entry(class.to_string())
+            .or_default() += count;
+    }
+
+    pub fn mark(&mut self, at_ms: u64, seq: u64) {
+        let bucket = self.bucket(at_ms);
+        bucket.seqs = Some(
+            bucket
+                .seqs
+                .map_or((seq, seq), |(first, last)| (first.min(seq), last.max(seq))),
+        );
+    }
+
+    pub fn counts(&self, window: &TimelineWindow) -> BTreeMap<u64, ServiceCounts> {
+        let mut out: BTreeMap<u64, ServiceCounts> = BTreeMap::new();
+        for (start, bucket) in self.buckets.range(window.from..window.to) {
+            let merged = out.entry(window.bucket_start(*start)).or_default();
+            for (service, counts) in &bucket.services {
+                merge_counts(merged.entry(service.clone()).or_default(), counts);
+            }
+        }
+        out
+    }
+
+    pub fn seq_range(&self, window: &TimelineWindow) -> Option<(u64, u64)> {
+        self.buckets
+            .range(window.from..window.to)
+            .filter_map(|(_, bucket)| bucket.seqs)
+            .reduce(|(first, last), (low, high)| (first.min(low), last.max(high)))
+    }
+
+    fn bucket(&mut self, at_ms: u64) -> &mut Bucket {
+        let start = at_ms - at_ms % TIMELINE_RESOLUTION_MS;
+        while self.buckets.len() >= TIMELINE_RETENTION_BUCKETS && !self.buckets.contains_key(&start)
+        {
+            self.buckets.pop_first();
+        }
+        self.buckets.entry(start).or_default()
+    }
+}
+
+fn merge_counts(target: &mut ClassCounts, counts: &ClassCounts) {
+    for (class, count) in counts {
+        *target.entry(class.clone()).or_default() += count;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimelineWindow {
+    pub from: u64,
+    pub to: u64,
+    pub step: u64,
+}
+
+impl TimelineWindow {
+    pub fn parse(
+        from: Option<&str>,
+        to: Option<&str>,
+        step: Option<&str>,
+        now_ms: u64,
+    ) -> Result<Self, String> {
+        let to_ms = parse_ms("to", to)?.unwrap_or(now_ms);
+        let from_ms =
+            parse_ms("from", from)?.unwrap_or_else(|| to_ms.saturating_sub(DEFAULT_SPAN_MS));
+        if from_ms >= to_ms {
+            return Err("from must be before to".to_string());
+        }
+        let span = to_ms - from_ms;
+        let requested = parse_ms("step", step)?.unwrap_or(0);
+        let step_ms = requested
+            .max(span.div_ceil(MAX_BUCKETS))
+            .div_ceil(TIMELINE_RESOLUTION_MS)
+            .max(1)
+            * TIMELINE_RESOLUTION_MS;
+        Ok(Self {
+            from: from_ms - from_ms % step_ms,
+            to: to_ms,
+            step: step_ms,
+        })
+    }
+
+    const fn bucket_start(&self, at_ms: u64) -> u64 {
+        self.from + (at_ms - self.from) / self.step * self.step
+    }
+}
+
+fn parse_ms(name: &str, value: Option<&str>) -> Result<Option<u64>, String> {
+    value
+        .map(|value| {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid {name} {value:?}. Use epoch milliseconds."))
+        })
+        .transpose()
+}
+
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct TimelineCounts {
+    pub logs: ClassCounts,
+    pub requests: ClassCounts,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct TimelineBucket {
+    pub start_ms: u64,
+    pub services: BTreeMap<String, TimelineCounts>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Timeline {
+    pub from_ms: u64,
+    pub to_ms: u64,
+    pub step_ms: u64,
+    pub buckets: Vec<TimelineBucket>,
+}
+
+pub fn build_timeline(
+    window: &TimelineWindow,
+    mut logs: BTreeMap<u64, ServiceCounts>,
+    mut requests: BTreeMap<u64, ServiceCounts>,
+) -> Timeline {
+    let buckets = (window.from..window.to)
+        .step_by(usize::try_from(window.step).unwrap_or(usize::MAX))
+        .map(|start_ms| {
+            let mut services: BTreeMap<String, TimelineCounts> = BTreeMap::new();
+            for (service, counts) in logs.remove(&start_ms).unwrap_or_default() {
+                services.entry(service).or_default().logs = counts;
+            }
+            for (service, counts) in requests.remove(&start_ms).unwrap_or_default() {
+                services.entry(service).or_default().requests = counts;
+            }
+            TimelineBucket { start_ms, services }
+        })
+        .collect();
+    Timeline {
+        from_ms: window.from,
+        to_ms: window.to,
+        step_ms: window.step,
+        buckets,
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::logging::LogHub;
+use super::timeline::{build_timeline, TimelineIndex, TimelineWindow};
+
+const BASE: u64 = 1_700_000_000_000;
+
+#[test]
+fn windows_default_to_the_last_minutes_and_align_steps() {
+    assert_eq!(
+        TimelineWindow::parse(None, None, None, BASE + 900_500),
+        Ok(TimelineWindow {
+            from: BASE,
+            to: BASE + 900_500,
+            step: 4_000,
+        })
+    );
+    let fine = TimelineWindow::parse(Some("1000"), Some("5000"), Some("1"), 0);
+    assert_eq!(
+        fine,
+        Ok(TimelineWindow {
+            from: 1_000,
+            to: 5_000,
+            step: 1_000,
+        })
+    );
+    assert!(TimelineWindow::parse(Some("5000"), Some("1000"), None, 0).is_err());
+    assert!(TimelineWindow::parse(Some("soon"), None, None, 0).is_err());
+}
+
+#[test]
+fn merges_seconds_into_window_buckets() {
+    let mut index = TimelineIndex::default();
+    index.record(BASE + 100, "api", "2xx", 1);
+    index.record(BASE + 1_100, "api", "2xx", 1);
+    index.record(BASE + 2_100, "api", "5xx", 1);
+    index.record(BASE + 9_000, "api", "2xx", 1);
+    index.mark(BASE + 100, 4);
+    index.mark(BASE + 2_100, 9);
+    let window = TimelineWindow {
+        from: BASE,
+        to: BASE + 4_000,
+        step: 2_000,
+    };
+    let timeline = build_timeline(&window, BTreeMap::new(), index.counts(&window));
+    let requests: Vec<BTreeMap<String, u64>> = timeline
+        .buckets
+        .iter()
+        .filter_map(|bucket| bucket.services.get("api"))
+        .map(|counts| counts.requests.clone())
+        .collect();
+    assert_eq!(
+        requests,
+        vec![
+            BTreeMap::from([("2xx".to_string(), 2)]),
+            BTreeMap::from([("5xx".to_string(), 1)]),
+        ]
+    );
+    assert_eq!(index.seq_range(&window), Some((4, 9)));
+}
+
+#[test]
+fn log_hub_counts_levels_and_returns_the_window() {
+    let hub = LogHub::with_reorder(16, Duration::ZERO);
+    let now = i64::try_from(BASE).unwrap_or(0);
+    hub.publish_at("api", "INFO ready", Some("2023-11-14T22:13:20.100Z"), now);
+    hub.publish_at(
+        "api[2]",
+        "ERROR boom",
+        Some("2023-11-14T22:13:21.100Z"),
+        now,
+    );
+    hub.publish_at("db", "plain line", Some("2023-11-14T22:13:25.000Z"), now);
+    let window = TimelineWindow {
+        from: BASE,
+        to: BASE + 2_000,
+        step: 2_000,
+    };
+    let counts = hub.timeline(&window);
+    let api = counts.get(&BASE).and_then(|services| services.get("api"));
+    assert_eq!(
+        api,
+        Some(&BTreeMap::from([
+            ("error".to_string(), 1),
+            ("info".to_string(), 1),
+        ]))
+    );
+    let (events, truncated) = hub.window(&window);
+    let lines: Vec<&str> = events.iter().map(|event| event.line.as_str()).collect();
+    assert_eq!(lines, vec!["INFO ready", "ERROR boom"]);
+    assert!(!truncated);
+}
@@ -1,58 +1,173 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use crossbeam_channel::{bounded, Receiver, Sender, TrySendError};
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender, TrySendError};
+use serde::Serialize;
 
 use crate::domain::traffic::{
-    EdgeKey, EdgeStats, EntityId, FlowObservation, HttpObservation, Observation, ObservationSink,
-    TrafficCall, TrafficEdge, Visibility,
+    Correlation, DbObservation, EdgeKey, EdgeStats, EntityId, FlowObservation, HttpObservation,
+    Observation, ObservationAttrs, ObservationSink, Peer, Trace, TraceNode, TrafficCall,
+    TrafficEdge, UpgradeInfo, Visibility, NETWORK_TAG,
 };
 use crate::support::constants::{TRAFFIC_CALL_HISTORY_LIMIT, TRAFFIC_CLIENT_QUEUE_SIZE};
+use crate::support::sampling::{TrafficSampler, TrafficSampling};
+use crate::support::tap_failover::CaptureMode;
+use crate::support::timeline::{
+    ServiceCounts, TimelineIndex, TimelineWindow, TIMELINE_EVENT_LIMIT,
+};
 
 const LATENCY_SAMPLE_LIMIT: usize = 256;
+const ROUTE_PARAM: &str = "{id}";
+const STATEMENT_LISTS: [(&str, &str); 4] = [
+    ("?, ?", "?"),
+    ("?,?", "?"),
+    ("(?), (?)", "(?)"),
+    ("(?),(?)", "(?)"),
+];
+const UPSTREAM_TIME_HEADER: &str = "x-envoy-upstream-service-time";
 
 struct EdgeState {
+    id: u64,
+    revision: u64,
     stats: EdgeStats,
     latencies: VecDeque<u64>,
     last_seen_ms: u64,
 }
 
+#[derive(Clone, Debug, Serialize)]
+pub struct IndexedEdge {
+    pub id: u64,
+    #[serde(flatten)]
+    pub edge: TrafficEdge,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CallDetail {
+    #[serde(flatten)]
+    pub call: TrafficCall,
+    pub timing: CallTiming,
+}
+
+#[allow(clippy::struct_field_names)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CallTiming {
+    pub started_at_ms: u64,
+    pub finished_at_ms: Option<u64>,
+    pub duration_ms: Option<u64>,
+    pub upstream_ms: Option<u64>,
+    pub proxy_ms: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct EdgeDelta<'a> {
+    pub id: u64,
+    pub stats: &'a EdgeStats,
+    pub last_seen_ms: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct TrafficUpdate {
+    pub revision: u64,
+    pub created: bool,
+    pub edge: IndexedEdge,
+}
+
+impl TrafficUpdate {
+    pub const fn delta(&self) -> EdgeDelta<'_> {
+        EdgeDelta {
+            id: self.edge.id,
+            stats: &self.edge.edge.stats,
+            last_seen_ms: self.edge.edge.last_seen_ms,
+        }
+    }
+}
+
+pub struct TrafficSnapshot {
+    pub revision: u64,
+    pub resumed: bool,
+    pub edges: Vec<IndexedEdge>,
+}
+
 struct TrafficHubState {
     edges: HashMap<EdgeKey, EdgeState>,
-    clients: Vec<(usize, Sender<TrafficEdge>)>,
+    next_edge_id: u64,
+    revision: u64,
+    clients: Vec<(usize, Sender<TrafficUpdate>)>,
     next_client_id: usize,
     calls: VecDeque<TrafficCall>,
     call_clients: Vec<(usize, Sender<TrafficCall>)>,
     next_call_client_id: usize,
+    observation_clients: Vec<Sender<Observation>>,
     next_call_seq: u64,
+    sampler: TrafficSampler,
+    status_classes: BTreeMap<String, u64>,
+    suppressed: BTreeMap<String, u64>,
+    capture_modes: BTreeMap<String, CaptureMode>,
+    timeline: TimelineIndex,
 }
 
 pub struct TrafficHub {
+    epoch: u64,
     state: Mutex<TrafficHubState>,
 }
 
 impl TrafficHub {
     pub fn new() -> Self {
         Self {
+            epoch: new_epoch(),
             state: Mutex::new(TrafficHubState {
                 edges: HashMap::new(),
+                next_edge_id: 1,
+                revision: 0,
                 clients: Vec::new(),
                 next_client_id: 1,
                 calls: VecDeque::with_capacity(TRAFFIC_CALL_HISTORY_LIMIT),
                 call_clients: Vec::new(),
                 next_call_client_id: 1,
+                observation_clients: Vec::new(),
                 next_call_seq: 1,
+                sampler: TrafficSampler::new(TrafficSampling::default()),
+                status_classes: BTreeMap::new(),
+                suppressed: BTreeMap::new(),
+                capture_modes: BTreeMap::new(),
+                timeline: TimelineIndex::default(),
             }),
         }
     }
 
-    pub fn register_client(&self) -> (Receiver<TrafficEdge>, Vec<TrafficEdge>) {
+    pub fn set_sampling(&self, sampling: TrafficSampling) {
+        self.state().sampler = TrafficSampler::new(sampling);
+    }
+
+    pub fn event_id(&self, revision: u64) -> String {
+        format!("{:x}-{revision}", self.epoch)
+    }
+
+    pub fn register_client(
+        &self,
+        last_event_id: Option<&str>,
+    ) -> (Receiver<TrafficUpdate>, TrafficSnapshot) {
+        let last_revision = last_event_id
+            .and_then(|id| id.split_once('-'))
+            .filter(|(epoch, _)| u64::from_str_radix(epoch, 16).ok() == Some(self.epoch))
+            .and_then(|(_, revision)| revision.parse::<u64>().ok());
         let (sender, receiver) = bounded(TRAFFIC_CLIENT_QUEUE_SIZE);
         let mut state = self.state();
         let id = state.next_client_id;
         state.next_client_id += 1;
         state.clients.push((id, sender));
-        let snapshot = state
+        let snapshot = snapshot_since(&state, last_revision);
+        drop(state);
+        (receiver, snapshot)
+    }
+
+    pub fn keyframe(&self) -> TrafficSnapshot {
+        snapshot_since(&self.state(), None)
+    }
+
+    pub fn edges(&self) -> Vec<TrafficEdge> {
+        self.state()
             .edges
             .iter()
             .map(|(key, edge)| TrafficEdge {
@@ -60,9 +175,57 @@ impl TrafficHub {
                 stats: edge.stats.clone(),
                 last_seen_ms: edge.last_seen_ms,
             })
+            .collect()
+    }
+
+    pub fn status_classes(&self) -> BTreeMap<String, u64> {
+        self.state().status_classes.clone()
+    }
+
+    pub fn timeline(&self, window: &TimelineWindow) -> BTreeMap<u64, ServiceCounts> {
+        self.state().timeline.counts(window)
+    }
+
+    pub fn calls_between(&self, window: &TimelineWindow) -> (Vec<TrafficCall>, bool) {
+        let state = self.state();
+        let Some((first, last)) = state.timeline.seq_range(window) else {
+            return (Vec::new(), false);
+        };
+        let mut calls: Vec<TrafficCall> = state
+            .calls
+            .iter()
+            .filter(|call| (first..=last).contains(&call.seq))
+            .take(TIMELINE_EVENT_LIMIT + 1)
+            .cloned()
             .collect();
         drop(state);
-        (receiver, snapshot)
+        let truncated = calls.len() > TIMELINE_EVENT_LIMIT;
+        calls.truncate(TIMELINE_EVENT_LIMIT);
+        (calls, truncated)
+    }
+
+    pub fn suppress(&self, service: &str) {
+        *self
+            .state()
+            .suppressed
+            .entry(service.to_string())
+            .or_default() += 1;
+    }
+
+    pub fn suppressed(&self) -> BTreeMap<String, u64> {
+        self.state().suppressed.clone()
+    }
+
+    pub fn set_capture_mode(&self, service: &str, mode: CaptureMode) {
+        self.state().capture_modes.insert(service.to_string(), mode);
+    }
+
+    pub fn capture_modes(&self) -> BTreeMap<String, &'static str> {
+        self.state()
+            .capture_modes
+            .iter()
+            .map(|(service, mode)| (service.clone(), mode.as_str()))
+            .collect()
     }
 
     pub fn register_call_client(&self) -> (Receiver<TrafficCall>, Vec<TrafficCall>) {
@@ -76,27 +239,48 @@ impl TrafficHub {
         (receiver, snapshot)
     }
 
-    fn publish(&self, edge: &TrafficEdge) {
-        let clients = {
-            let mut state = self.state();
-            if let Some(existing) = state.edges.get_mut(&edge.key) {
-                existing.stats = edge.stats.clone();
-                existing.last_seen_ms = edge.last_seen_ms;
-            } else {
-                state.edges.insert(
-                    edge.key.clone(),
-                    EdgeState {
-                        stats: edge.stats.clone(),
-                        latencies: VecDeque::new(),
-                        last_seen_ms: edge.last_seen_ms,
-                    },
-                );
-            }
-            state.clients.clone()
-        };
+    pub fn register_observation_client(&self) -> Receiver<Observation> {
+        let (sender, receiver) = unbounded();
+        self.state().observation_clients.push(sender);
+        receiver
+    }
+
+    pub fn call(&self, seq: u64) -> Option<TrafficCall> {
+        let state = self.state();
+        let index = state
+            .calls
+            .binary_search_by_key(&seq, |call| call.seq)
+            .ok()?;
+        state.calls.get(index).cloned()
+    }
+
+    pub fn call_detail(&self, seq: u64) -> Option<CallDetail> {
+        let call = self.call(seq)?;
+        Some(CallDetail {
+            timing: call_timing(&call),
+            call,
+        })
+    }
+
+    pub fn trace(&self, request_id: &str) -> Option<Trace> {
+        let calls = self
+            .state()
+            .calls
+            .iter()
+            .filter(|call| call.correlation.request_id.as_deref() == Some(request_id))
+            .cloned()
+            .collect();
+        build_trace(request_id, calls)
+    }
+
+    fn publish(&self, edge: TrafficEdge) {
+        let mut state = self.state();
+        let update = record_edge(&mut state, edge);
+        let clients = state.clients.clone();
+        drop(state);
         let mut disconnected = Vec::new();
         for (id, sender) in clients {
-            match sender.try_send(edge.clone()) {
+            match sender.try_send(update.clone()) {
                 Ok(()) | Err(TrySendError::Full(_)) => {}
                 Err(TrySendError::Disconnected(_)) => {
                     disconnected.push(id);
@@ -115,42 +299,53 @@ impl TrafficHub {
             .unwrap_or_else(std::sync::PoisonError::into_inner)
     }
 
-    fn emit_http(&self, http: &HttpObservation) {
-        let from = http.peer.src.clone().unwrap_or(EntityId::Unknown);
-        let to = http.peer.dst.clone().unwrap_or(EntityId::Unknown);
-        let method = http.method.as_deref().unwrap_or("UNKNOWN").to_uppercase();
-        let route = http.path.clone().unwrap_or_else(|| "/".to_string());
+    fn emit_call(&self, http: &HttpObservation, upgrade: Option<&UpgradeInfo>) {
+        let method = upgrade.map_or_else(
+            || http.method.as_deref().unwrap_or("UNKNOWN").to_uppercase(),
+            |upgrade| upgrade.protocol.to_uppercase(),
+        );
         let key = EdgeKey::Http {
-            from,
-            to,
+            from: http.peer.src.clone().unwrap_or(EntityId::Unknown),
+            to: http.peer.dst.clone().unwrap_or(EntityId::Unknown),
             method,
-            route,
+            route: route_template(http.path.as_deref().unwrap_or("/")),
+        };
+        self.record_call(key, http, upgrade);
+    }
+
+    fn emit_db(&self, db: DbObservation) {
+        let key = EdgeKey::Db {
+            from: db.peer.src.clone().unwrap_or(EntityId::Unknown),
+            to: db.peer.dst.clone().unwrap_or(EntityId::Unknown),
+            protocol: db.protocol.clone(),
+            command: db.command.clone(),
+            statement: statement_template(&db.statement),
         };
+        self.record_call(key, &db_call(db), None);
+    }
+
+    fn record_call(&self, key: EdgeKey, http: &HttpObservation, upgrade: Option<&UpgradeInfo>) {
+        let class = status_class(http.status);
         let mut state = self.state();
-        let edge = state.edges.entry(key.clone()).or_insert_with(|| EdgeState {
-            stats: EdgeStats {
-                count: 0,
-                bytes_in: 0,
-                bytes_out: 0,
-                errors: 0,
-                p50_ms: None,
-                p95_ms: None,
-                visibility: http.attrs.visibility.clone(),
-            },
-            latencies: VecDeque::new(),
-            last_seen_ms: http.at_ms,
-        });
+        if let Some(service) = call_service(&http.peer) {
+            state.timeline.record(http.at_ms, service, &class, 1);
+        }
+        *state.status_classes.entry(class).or_default() += 1;
+        rekey_edge(&mut state.edges, &key);
+        let edge = state
+            .edges
+            .entry(key.clone())
+            .or_insert_with(|| new_edge_state(&http.attrs.visibility, http.at_ms));
         edge.stats.count += 1;
         edge.stats.bytes_in += http.bytes_in.unwrap_or(0);
         edge.stats.bytes_out += http.bytes_out.unwrap_or(0);
-        if let Some(status) = http.status {
-            if status >= 400 {
-                edge.stats.errors += 1;
-            }
+        if http.status.is_some_and(|status| status >= 400) {
+            edge.stats.errors += 1;
         }
         edge.stats.visibility = Visibility::merge(&edge.stats.visibility, &http.attrs.visibility);
+        record_network(&mut edge.stats, &http.attrs);
         edge.last_seen_ms = http.at_ms;
-        if let Some(duration) = http.duration_ms {
+        if let Some(duration) = http.duration_ms.filter(|_| upgrade.is_none()) {
             edge.latencies.push_back(duration);
             while edge.latencies.len() > LATENCY_SAMPLE_LIMIT {
                 edge.latencies.pop_front();
@@ -162,9 +357,12 @@ impl TrafficHub {
             stats: edge.stats.clone(),
             last_seen_ms: edge.last_seen_ms,
         };
+        let captured = state.sampler.keep(http);
         drop(state);
-        self.publish(&snapshot);
-        self.publish_call(http);
+        self.publish(snapshot);
+        if captured {
+            self.publish_call(http, upgrade);
+        }
     }
 
     fn emit_flow(&self, flow: FlowObservation) {
@@ -178,23 +376,16 @@ impl TrafficHub {
             port,
         };
         let mut state = self.state();
-        let edge = state.edges.entry(key.clone()).or_insert_with(|| EdgeState {
-            stats: EdgeStats {
-                count: 0,
-                bytes_in: 0,
-                bytes_out: 0,
-                errors: 0,
-                p50_ms: None,
-                p95_ms: None,
-                visibility: flow.attrs.visibility.clone(),
-            },
-            latencies: VecDeque::new(),
-            last_seen_ms: flow.at_ms,
-        });
+        rekey_edge(&mut state.edges, &key);
+        let edge = state
+            .edges
+            .entry(key.clone())
+            .or_insert_with(|| new_edge_state(&flow.attrs.visibility, flow.at_ms));
         edge.stats.count += 1;
         edge.stats.bytes_in += flow.metrics.bytes_in.unwrap_or(0);
         edge.stats.bytes_out += flow.metrics.bytes_out.unwrap_or(0);
         edge.stats.visibility = Visibility::merge(&edge.stats.visibility, &flow.attrs.visibility);
+        record_network(&mut edge.stats, &flow.attrs);
         edge.last_seen_ms = flow.at_ms;
         let snapshot = TrafficEdge {
             key,
@@ -202,10 +393,10 @@ impl TrafficHub {
             last_seen_ms: edge.last_seen_ms,
         };
         drop(state);
-        self.publish(&snapshot);
+        self.publish(snapshot);
     }
 
-    fn publish_call(&self, http: &HttpObservation) {
+    fn publish_call(&self, http: &HttpObservation, upgrade: Option<&UpgradeInfo>) {
         let (call, clients) = {
             let mut state = self.state();
             let seq = state.next_call_seq;
@@ -226,7 +417,9 @@ impl TrafficHub {
                 response_body: http.response_body.clone(),
                 correlation: http.correlation.clone(),
                 attrs: http.attrs.clone(),
+                upgrade: upgrade.cloned(),
             };
+            state.timeline.mark(call.at_ms, seq);
             state.calls.push_back(call.clone());
             while state.calls.len() > TRAFFIC_CALL_HISTORY_LIMIT {
                 state.calls.pop_front();
@@ -254,29 +447,329 @@ impl TrafficHub {
 
 impl ObservationSink for TrafficHub {
     fn emit(&self, obs: Observation) {
+        self.state()
+            .observation_clients
+            .retain(|sender| match sender.try_send(obs.clone()) {
+                Ok(()) | Err(TrySendError::Full(_)) => true,
+                Err(TrySendError::Disconnected(_)) => false,
+            });
         match obs {
-            Observation::Http(http) => self.emit_http(&http),
+            Observation::Http(http) => self.emit_call(&http, None),
+            Observation::Upgrade(upgraded) => {
+                self.emit_call(&upgraded.http, Some(&upgraded.upgrade));
+            }
             Observation::Flow(flow) => self.emit_flow(flow),
+            Observation::Db(db) => self.emit_db(db),
         }
     }
 }
 
+fn db_call(db: DbObservation) -> HttpObservation {
+    let mut attrs = db.attrs;
+    attrs.tags.insert("protocol".to_string(), db.protocol);
+    HttpObservation {
+        at_ms: db.at_ms,
+        peer: db.peer,
+        method: Some(db.command),
+        path: Some(db.statement),
+        status: None,
+        duration_ms: db.duration_ms,
+        bytes_in: None,
+        bytes_out: None,
+        request_headers: BTreeMap::new(),
+        response_headers: BTreeMap::new(),
+        request_body: None,
+        response_body: None,
+        correlation: Correlation::default(),
+        attrs,
+    }
+}
+
+// Moves an edge whose external peer resolved to more addresses under the new key,
+// keeping its id and counters.
+fn rekey_edge(edges: &mut HashMap<EdgeKey, EdgeState>, key: &EdgeKey) {
+    if edges.contains_key(key) {
+        return;
+    }
+    let stable = key.without_addresses();
+    let previous = edges
+        .keys()
+        .find(|existing| existing.without_addresses() == stable)
+        .cloned();
+    if let Some(state) = previous.and_then(|previous| edges.remove(&previous)) {
+        edges.insert(key.clone(), state);
+    }
+}
+
+fn record_edge(state: &mut TrafficHubState, edge: TrafficEdge) -> TrafficUpdate {
+    state.revision += 1;
+    let revision = state.revision;
+    let existing = state
+        .edges
+        .entry(edge.key.clone())
+        .or_insert_with(|| new_edge_state(&edge.stats.visibility, edge.last_seen_ms));
+    existing.stats = edge.stats.clone();
+    existing.last_seen_ms = edge.last_seen_ms;
+    existing.revision = revision;
+    let created = existing.id == 0;
+    if created {
+        existing.id = state.next_edge_id;
+        state.next_edge_id += 1;
+    }
+    TrafficUpdate {
+        revision,
+        created,
+        edge: IndexedEdge {
+            id: existing.id,
+            edge,
+        },
+    }
+}
+
+fn new_epoch() -> u64 {
+    let mut bytes = [0_u8; 8];
+    if getrandom::getrandom(&mut bytes).is_ok() {
+        return u64::from_le_bytes(bytes);
+    }
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    u64::try_from(nanos & u128::from(u64::MAX)).unwrap_or_default() ^ u64::from(std::process::id())
+}
+
+fn snapshot_since(state: &TrafficHubState, last_revision: Option<u64>) -> TrafficSnapshot {
+    let resume_from = last_revision.filter(|revision| *revision <= state.revision);
+    let mut edges: Vec<IndexedEdge> = state
+        .edges
+        .iter()
+        .filter(|(_, edge)| edge.id != 0 && resume_from.is_none_or(|since| edge.revision > since))
+        .map(|(key, edge)| IndexedEdge {
+            id: edge.id,
+            edge: TrafficEdge {
+                key: key.clone(),
+                stats: edge.stats.clone(),
+                last_seen_ms: edge.last_seen_ms,
+            },
+        })
+        .collect();
+    edges.sort_by_key(|edge| edge.id);
+    TrafficSnapshot {
+        revision: state.revision,
+        resumed: resume_from.is_some(),
+        edges,
+    }
+}
+
+fn call_service(peer: &Peer) -> Option<&str> {
+    [peer.dst.as_ref(), peer.src.as_ref()]
+        .into_iter()
+        .flatten()
+        .find_map(EntityId::workload_name)
+}
+
+fn status_class(status: Option<u16>) -> String {
+    status.map_or_else(
+        || "none".to_string(),
+        |status| format!("{}xx", status / 100),
+    )
+}
+
+fn new_edge_state(visibility: &Visibility, at_ms: u64) -> EdgeState {
+    EdgeState {
+        id: 0,
+        revision: 0,
+        stats: EdgeStats {
+            count: 0,
+            bytes_in: 0,
+            bytes_out: 0,
+            errors: 0,
+            p50_ms: None,
+            p95_ms: None,
+            p99_ms: None,
+            visibility: visibility.clone(),
+            networks: Vec::new(),
+        },
+        latencies: VecDeque::new(),
+        last_seen_ms: at_ms,
+    }
+}
+
+fn record_network(stats: &mut EdgeStats, attrs: &ObservationAttrs) {
+    let Some(network) = attrs.tags.get(NETWORK_TAG) else {
+        return;
+    };
+    if let Err(index) = stats.networks.binary_search(network) {
+        stats.networks.insert(index, network.clone());
+    }
+}
+
 fn update_latency_stats(stats: &mut EdgeStats, samples: &VecDeque<u64>) {
     if samples.is_empty() {
         stats.p50_ms = None;
         stats.p95_ms = None;
+        stats.p99_ms = None;
         return;
     }
     let mut sorted: Vec<u64> = samples.iter().copied().collect();
     sorted.sort_unstable();
     stats.p50_ms = Some(percentile(&sorted, 50));
     stats.p95_ms = Some(percentile(&sorted, 95));
+    stats.p99_ms = Some(percentile(&sorted, 99));
+}
+
+pub fn route_template(path: &str) -> String {
+    let path = path.split(['?', '#']).next().unwrap_or(path);
+    let segments: Vec<&str> = path
+        .split('/')
+        .map(|segment| {
+            if is_identifier_segment(segment) {
+                ROUTE_PARAM
+            } else {
+                segment
+            }
+        })
+        .collect();
+    let template = segments.join("/");
+    if template.is_empty() {
+        "/".to_string()
+    } else {
+        template
+    }
+}
+
+pub fn statement_template(statement: &str) -> String {
+    let mut template = statement
+        .split(' ')
+        .map(|word| {
+            word.split(':')
+                .map(|part| {
+                    if is_identifier_segment(part) {
+                        ROUTE_PARAM
+                    } else {
+                        part
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(":")
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    for (repeated, single) in STATEMENT_LISTS {
+        while template.contains(repeated) {
+            template = template.replace(repeated, single);
+        }
+    }
+    template
+}
+
+fn is_identifier_segment(segment: &str) -> bool {
+    let is_hex_id = segment.len() >= 16
+        && segment.chars().all(|c| c.is_ascii_hexdigit())
+        && segment.chars().any(|c| c.is_ascii_digit());
+    !segment.is_empty() && segment.chars().all(|c| c.is_ascii_digit())
+        || is_hex_id
+        || is_uuid(segment)
 }
 
-fn percentile(sorted: &[u64], pct: usize) -> u64 {
+fn is_uuid(segment: &str) -> bool {
+    let groups: Vec<&str> = segment.split('-').collect();
+    groups.iter().map(|group| group.len()).eq([8, 4, 4, 4, 12])
+        && groups
+            .iter()
+            .all(|group| group.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+pub fn call_summary(call: TrafficCall) -> TrafficCall {
+    TrafficCall {
+        request_body: None,
+        response_body: None,
+        ..call
+    }
+}
+
+fn call_timing(call: &TrafficCall) -> CallTiming {
+    let upstream_ms = call
+        .response_headers
+        .get(UPSTREAM_TIME_HEADER)
+        .and_then(|value| value.trim().parse::<u64>().ok());
+    CallTiming {
+        started_at_ms: call.at_ms,
+        finished_at_ms: call.duration_ms.map(|duration| call.at_ms + duration),
+        duration_ms: call.duration_ms,
+        upstream_ms,
+        proxy_ms: call
+            .duration_ms
+            .zip(upstream_ms)
+            .map(|(duration, upstream)| duration.saturating_sub(upstream)),
+    }
+}
+
+pub fn percentile(sorted: &[u64], pct: usize) -> u64 {
     if sorted.is_empty() {
         return 0;
     }
     let idx = (sorted.len() - 1) * pct / 100;
     sorted.get(idx).copied().unwrap_or(0)
 }
+
+pub fn build_trace(request_id: &str, mut calls: Vec<TrafficCall>) -> Option<Trace> {
+    if calls.is_empty() {
+        return None;
+    }
+    calls.sort_by_key(|call| (call.at_ms, call.seq));
+    let parents: Vec<Option<usize>> = (0..calls.len())
+        .map(|idx| find_parent(&calls, idx))
+        .collect();
+    let started_at_ms = calls.iter().map(|call| call.at_ms).min().unwrap_or(0);
+    let duration_ms = calls
+        .iter()
+        .filter_map(|call| call.duration_ms.map(|duration| call.at_ms + duration))
+        .max()
+        .map(|end| end.saturating_sub(started_at_ms));
+    let call_count = calls.len();
+    let mut slots: Vec<Option<TrafficCall>> = calls.into_iter().map(Some).collect();
+    let roots = (0..call_count)
+        .filter(|idx| parents.get(*idx).copied().flatten().is_none())
+        .filter_map(|idx| build_trace_node(idx, &mut slots, &parents))
+        .collect();
+    Some(Trace {
+        request_id: request_id.to_string(),
+        started_at_ms,
+        duration_ms,
+        call_count,
+        roots,
+    })
+}
+
+fn find_parent(calls: &[TrafficCall], idx: usize) -> Option<usize> {
+    let caller = calls
+        .get(idx)?
+        .peer
+        .src
+        .as_ref()
+        .and_then(EntityId::workload_name)?;
+    calls.get(..idx)?.iter().rposition(|candidate| {
+        candidate
+            .peer
+            .dst
+            .as_ref()
+            .and_then(EntityId::workload_name)
+            == Some(caller)
+    })
+}
+
+fn build_trace_node(
+    idx: usize,
+    slots: &mut [Option<TrafficCall>],
+    parents: &[Option<usize>],
+) -> Option<TraceNode> {
+    let call = slots.get_mut(idx)?.take()?;
+    let children = parents
+        .iter()
+        .enumerate()
+        .filter(|(_, parent)| **parent == Some(idx))
+        .filter_map(|(child, _)| build_trace_node(child, slots, parents))
+        .collect();
+    Some(TraceNode { call, children })
+}
@@ -0,0 +1,74 @@
+//! Shared builders for traffic records in tests.
+
+use std::collections::BTreeMap;
+
+use crate::domain::traffic::{
+    Confidence, Correlation, EntityId, HttpObservation, ObservationAttrs, Peer, TrafficCall,
+    Visibility,
+};
+
+pub fn workload(name: &str) -> EntityId {
+    EntityId::Workload {
+        name: name.to_string(),
+        instance: None,
+    }
+}
+
+pub fn workload_peer(src: &str, dst: &str) -> Peer {
+    Peer {
+        src: Some(workload(src)),
+        dst: Some(workload(dst)),
+        raw: None,
+    }
+}
+
+/// A `GET /` observation between unknown peers answered with a 200.
+pub fn http_observation() -> HttpObservation {
+    HttpObservation {
+        at_ms: 0,
+        peer: Peer {
+            src: None,
+            dst: None,
+            raw: None,
+        },
+        method: Some("GET".to_string()),
+        path: Some("/".to_string()),
+        status: Some(200),
+        duration_ms: None,
+        bytes_in: None,
+        bytes_out: None,
+        request_headers: BTreeMap::new(),
+        response_headers: BTreeMap::new(),
+        request_body: None,
+        response_body: None,
+        correlation: Correlation::default(),
+        attrs: ObservationAttrs {
+            visibility: Visibility::L7Envelope,
+            confidence: Confidence::Exact,
+            tags: BTreeMap::new(),
+        },
+    }
+}
+
+/// The call a hub records for [`http_observation`], with sequence number 1.
+pub fn http_call() -> TrafficCall {
+    let observation = http_observation();
+    TrafficCall {
+        seq: 1,
+        at_ms: observation.at_ms,
+        peer: observation.peer,
+        method: observation.method,
+        path: observation.path,
+        status: observation.status,
+        duration_ms: observation.duration_ms,
+        bytes_in: observation.bytes_in,
+        bytes_out: observation.bytes_out,
+        request_headers: observation.request_headers,
+        response_headers: observation.response_headers,
+        request_body: observation.request_body,
+        response_body: observation.response_body,
+        correlation: observation.correlation,
+        attrs: observation.attrs,
+        upgrade: None,
+    }
+}
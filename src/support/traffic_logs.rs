@@ -0,0 +1,108 @@
+use crate::domain::traffic::{EntityId, TrafficCall};
+use crate::support::glob::glob_match;
+
+pub const TRAFFIC_LOG_SERVICE: &str = "traffic";
+const DEFAULT_MAX_PER_SEC: u32 = 20;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrafficLogs {
+    pub services: Vec<String>,
+    pub paths: Vec<String>,
+    pub errors: bool,
+    pub max_per_sec: u32,
+}
+
+impl TrafficLogs {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut logs = Self {
+            services: Vec::new(),
+            paths: Vec::new(),
+            errors: false,
+            max_per_sec: DEFAULT_MAX_PER_SEC,
+        };
+        for rule in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty() && *rule != "all")
+        {
+            if rule == "errors" {
+                logs.errors = true;
+            } else if let Some(service) = rule.strip_prefix("service=") {
+                logs.services.push(service.to_string());
+            } else if let Some(path) = rule.strip_prefix("path=") {
+                logs.paths.push(path.to_string());
+            } else if let Some(rate) = rule.strip_suffix("/s") {
+                logs.max_per_sec = rate
+                    .parse()
+                    .ok()
+                    .filter(|rate| *rate > 0)
+                    .ok_or_else(|| format!("invalid line rate '{rule}'"))?;
+            } else {
+                return Err(format!("unknown traffic log rule '{rule}'"));
+            }
+        }
+        Ok(logs)
+    }
+
+    pub fn matches(&self, call: &TrafficCall) -> bool {
+        let services = [call.peer.src.as_ref(), call.peer.dst.as_ref()].map(service_name);
+        let service = self.services.is_empty()
+            || self.services.iter().any(|pattern| {
+                services
+                    .iter()
+                    .flatten()
+                    .any(|name| glob_match(pattern, name))
+            });
+        let path = call.path.as_deref().unwrap_or("/");
+        let path = path.split_once('?').map_or(path, |(path, _)| path);
+        let endpoint =
+            self.paths.is_empty() || self.paths.iter().any(|pattern| glob_match(pattern, path));
+        let failed = !self.errors || call.status.is_none_or(|status| status >= 400);
+        service && endpoint && failed
+    }
+}
+
+fn service_name(entity: Option<&EntityId>) -> Option<&str> {
+    match entity? {
+        EntityId::Workload { name, .. } | EntityId::Host { name } => Some(name),
+        EntityId::External { .. } | EntityId::Unknown => None,
+    }
+}
+
+pub struct TrafficLogLimiter {
+    max_per_sec: u32,
+    window_start_ms: u64,
+    window_count: u32,
+    skipped: u64,
+}
+
+impl TrafficLogLimiter {
+    pub const fn new(max_per_sec: u32) -> Self {
+        Self {
+            max_per_sec,
+            window_start_ms: 0,
+            window_count: 0,
+            skipped: 0,
+        }
+    }
+
+    pub const fn admit(&mut self, now_ms: u64) -> bool {
+        if now_ms.saturating_sub(self.window_start_ms) >= 1000 {
+            self.window_start_ms = now_ms;
+            self.window_count = 0;
+        }
+        if self.window_count >= self.max_per_sec {
+            self.skipped += 1;
+            return false;
+        }
+        self.window_count += 1;
+        true
+    }
+
+    pub fn take_skipped(&mut self, now_ms: u64) -> Option<u64> {
+        if self.skipped == 0 || now_ms.saturating_sub(self.window_start_ms) < 1000 {
+            return None;
+        }
+        Some(std::mem::take(&mut self.skipped))
+    }
+}
@@ -0,0 +1,51 @@
+use super::traffic_fixtures::{http_call, workload_peer};
+use super::traffic_logs::{TrafficLogLimiter, TrafficLogs};
+use crate::domain::traffic::TrafficCall;
+
+fn call(src: &str, dst: &str, path: &str, status: u16) -> TrafficCall {
+    TrafficCall {
+        peer: workload_peer(src, dst),
+        path: Some(path.to_string()),
+        status: Some(status),
+        duration_ms: Some(34),
+        ..http_call()
+    }
+}
+
+#[test]
+fn filters_mirrored_calls_by_service_endpoint_and_status() {
+    let all = TrafficLogs::parse("all");
+    assert_eq!(all.as_ref().map(|logs| logs.max_per_sec), Ok(20));
+    assert!(all.is_ok_and(|logs| logs.matches(&call("web", "api", "/orders", 201))));
+
+    let logs = TrafficLogs::parse("service=api,path=/orders*,errors,5/s");
+    assert_eq!(logs.as_ref().map(|logs| logs.max_per_sec), Ok(5));
+    let matched: Vec<bool> = [
+        call("web", "api", "/orders/7?full=1", 500),
+        call("api", "db", "/orders", 503),
+        call("web", "api", "/orders", 201),
+        call("web", "api", "/users", 500),
+        call("web", "auth", "/orders", 500),
+    ]
+    .iter()
+    .map(|call| logs.as_ref().is_ok_and(|logs| logs.matches(call)))
+    .collect();
+    assert_eq!(matched, vec![true, true, false, false, false]);
+
+    assert!(TrafficLogs::parse("0/s").is_err());
+    assert!(TrafficLogs::parse("api").is_err());
+}
+
+#[test]
+fn caps_lines_per_second_and_reports_skipped_calls_once() {
+    let mut limiter = TrafficLogLimiter::new(2);
+    let admitted: Vec<bool> = [0, 100, 200, 300]
+        .into_iter()
+        .map(|now_ms| limiter.admit(now_ms))
+        .collect();
+    assert_eq!(admitted, vec![true, true, false, false]);
+    assert_eq!(limiter.take_skipped(900), None);
+    assert_eq!(limiter.take_skipped(1_000), Some(2));
+    assert_eq!(limiter.take_skipped(1_500), None);
+    assert!(limiter.admit(1_000));
+}
@@ -0,0 +1,315 @@
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr};
+
+use super::traffic::{
+    build_trace, call_summary, route_template, statement_template, CallTiming, TrafficHub,
+};
+use super::traffic_fixtures::{http_call, http_observation, workload_peer};
+use crate::domain::traffic::{
+    Confidence, Correlation, DbObservation, EdgeKey, EntityId, FlowKey, FlowMetrics,
+    FlowObservation, HttpObservation, Observation, ObservationAttrs, ObservationSink, Socket,
+    TrafficCall, Transport, Visibility, NETWORK_TAG,
+};
+
+fn call(seq: u64, src: &str, dst: &str) -> TrafficCall {
+    TrafficCall {
+        seq,
+        at_ms: 1_000 + seq * 10,
+        peer: workload_peer(src, dst),
+        duration_ms: Some(5),
+        correlation: Correlation {
+            request_id: Some("req-1".to_string()),
+            trace_id: None,
+            span_id: None,
+        },
+        ..http_call()
+    }
+}
+
+#[test]
+fn nests_downstream_calls_under_their_caller() {
+    let calls = vec![
+        call(3, "api", "db"),
+        call(1, "web", "api"),
+        call(2, "api", "cache"),
+    ];
+    let trace = build_trace("req-1", calls);
+    assert_eq!(trace.as_ref().map(|trace| trace.call_count), Some(3));
+    assert_eq!(trace.as_ref().map(|trace| trace.roots.len()), Some(1));
+    let children: Vec<u64> = trace
+        .iter()
+        .flat_map(|trace| trace.roots.iter())
+        .flat_map(|root| root.children.iter().map(|child| child.call.seq))
+        .collect();
+    assert_eq!(children, vec![2, 3]);
+}
+
+#[test]
+fn empty_calls_have_no_trace() {
+    assert!(build_trace("req-1", Vec::new()).is_none());
+}
+
+fn flow(src: &str, dst: &str, port: u16) -> Observation {
+    let socket = |port| Socket {
+        ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+        port,
+    };
+    Observation::Flow(FlowObservation {
+        at_ms: 1_000,
+        flow: FlowKey {
+            src: socket(40_000),
+            dst: socket(port),
+            transport: Transport::Tcp,
+        },
+        metrics: FlowMetrics {
+            bytes_in: Some(10),
+            bytes_out: Some(20),
+            packets: None,
+            duration_ms: None,
+        },
+        peer: workload_peer(src, dst),
+        attrs: ObservationAttrs {
+            visibility: Visibility::L4Flow,
+            confidence: Confidence::Exact,
+            tags: BTreeMap::new(),
+        },
+    })
+}
+
+#[test]
+fn edges_stream_as_deltas_and_resume_from_a_revision() {
+    let hub = TrafficHub::new();
+    let (receiver, snapshot) = hub.register_client(None);
+    assert!(snapshot.edges.is_empty());
+    hub.emit(flow("web", "api", 80));
+    hub.emit(flow("api", "db", 5432));
+    hub.emit(flow("web", "api", 80));
+    let updates: Vec<(u64, u64, bool)> = receiver
+        .try_iter()
+        .map(|update| (update.revision, update.edge.id, update.created))
+        .collect();
+    assert_eq!(updates, vec![(1, 1, true), (2, 2, true), (3, 1, false)]);
+
+    let (_, resumed) = hub.register_client(Some(&hub.event_id(2)));
+    assert!(resumed.resumed);
+    assert_eq!(resumed.revision, 3);
+    let ids: Vec<u64> = resumed.edges.iter().map(|edge| edge.id).collect();
+    assert_eq!(ids, vec![1]);
+    assert_eq!(
+        resumed.edges.first().map(|edge| edge.edge.stats.count),
+        Some(2)
+    );
+
+    let (_, stale) = hub.register_client(Some(&hub.event_id(99)));
+    assert!(!stale.resumed);
+    assert_eq!(stale.edges.len(), 2);
+
+    let restarted = TrafficHub::new();
+    let (_, other_epoch) = restarted.register_client(Some(&hub.event_id(2)));
+    assert!(!other_epoch.resumed);
+    let (_, bare) = hub.register_client(Some("2"));
+    assert!(!bare.resumed);
+}
+
+#[test]
+fn named_external_peers_keep_their_edge_as_addresses_resolve() {
+    let hub = TrafficHub::new();
+    let (receiver, _) = hub.register_client(None);
+    let first = IpAddr::V4(Ipv4Addr::new(140, 82, 112, 3));
+    let second = IpAddr::V4(Ipv4Addr::new(140, 82, 112, 4));
+    for ips in [vec![first], vec![first, second]] {
+        let Observation::Flow(mut observation) = flow("api", "api", 443) else {
+            continue;
+        };
+        observation.peer.dst = Some(EntityId::External {
+            ips,
+            dns_name: Some("api.github.com".to_string()),
+        });
+        hub.emit(Observation::Flow(observation));
+    }
+    let ids: Vec<u64> = receiver.try_iter().map(|update| update.edge.id).collect();
+    assert_eq!(ids, vec![1, 1]);
+    let edges = hub.edges();
+    assert_eq!(edges.len(), 1);
+    let Some(EdgeKey::Flow { to, .. }) = edges.first().map(|edge| &edge.key) else {
+        return;
+    };
+    assert_eq!(
+        to,
+        &EntityId::External {
+            ips: vec![first, second],
+            dns_name: Some("api.github.com".to_string()),
+        }
+    );
+    assert_eq!(edges.first().map(|edge| edge.stats.count), Some(2));
+}
+
+#[test]
+fn collapses_identifier_segments_into_templates() {
+    assert_eq!(
+        route_template("/users/123/orders/456"),
+        "/users/{id}/orders/{id}"
+    );
+    assert_eq!(
+        route_template("/items/3f2c9a1e-8b7d-4c6e-9f00-1a2b3c4d5e6f?expand=1"),
+        "/items/{id}"
+    );
+    assert_eq!(
+        route_template("/blobs/5f1d7c2be4b0a9c8d7e6f5a4"),
+        "/blobs/{id}"
+    );
+    assert_eq!(route_template("/v2/api/health"), "/v2/api/health");
+    assert_eq!(route_template("/feed/deadbeef"), "/feed/deadbeef");
+    assert_eq!(route_template(""), "/");
+}
+
+#[test]
+fn calls_share_a_template_edge_but_keep_raw_paths() {
+    let hub = TrafficHub::new();
+    let (calls, _) = hub.register_call_client();
+    for path in ["/users/1", "/users/2?full=true"] {
+        hub.emit(Observation::Http(HttpObservation {
+            at_ms: 1_000,
+            peer: workload_peer("web", "api"),
+            path: Some(path.to_string()),
+            duration_ms: Some(5),
+            ..http_observation()
+        }));
+    }
+    let edges = hub.edges();
+    assert_eq!(edges.len(), 1);
+    let route = edges.first().and_then(|edge| match &edge.key {
+        EdgeKey::Http { route, .. } => Some(route.as_str()),
+        _ => None,
+    });
+    assert_eq!(route, Some("/users/{id}"));
+    assert_eq!(edges.first().map(|edge| edge.stats.count), Some(2));
+    let paths: Vec<Option<String>> = calls.try_iter().map(|call| call.path).collect();
+    assert_eq!(
+        paths,
+        [
+            Some("/users/1".to_string()),
+            Some("/users/2?full=true".to_string())
+        ]
+    );
+}
+
+#[test]
+fn edges_list_the_networks_their_connections_traversed() {
+    let hub = TrafficHub::new();
+    for network in ["frontend", "backend", "frontend"] {
+        let Observation::Flow(mut observation) = flow("web", "api", 80) else {
+            continue;
+        };
+        observation
+            .attrs
+            .tags
+            .insert(NETWORK_TAG.to_string(), network.to_string());
+        hub.emit(Observation::Flow(observation));
+    }
+    let networks = hub.edges().first().map(|edge| edge.stats.networks.clone());
+    assert_eq!(
+        networks,
+        Some(vec!["backend".to_string(), "frontend".to_string()])
+    );
+}
+
+#[test]
+fn keeps_full_calls_for_the_detail_lookup_while_streaming_summaries() {
+    let hub = TrafficHub::new();
+    let (calls, _) = hub.register_call_client();
+    hub.emit(Observation::Http(HttpObservation {
+        at_ms: 2_000,
+        peer: workload_peer("web", "api"),
+        method: Some("POST".to_string()),
+        path: Some("/orders".to_string()),
+        status: Some(201),
+        duration_ms: Some(40),
+        bytes_in: Some(13),
+        bytes_out: Some(9),
+        request_headers: BTreeMap::from([(
+            "content-type".to_string(),
+            "application/json".to_string(),
+        )]),
+        response_headers: BTreeMap::from([(
+            "x-envoy-upstream-service-time".to_string(),
+            "31".to_string(),
+        )]),
+        request_body: Some("{\"item\":\"a\"}".to_string()),
+        response_body: Some("{\"id\":7}".to_string()),
+        attrs: ObservationAttrs {
+            visibility: Visibility::L7Semantics,
+            ..http_observation().attrs
+        },
+        ..http_observation()
+    }));
+    let streamed = calls.try_iter().next().map(call_summary);
+    assert!(streamed
+        .as_ref()
+        .is_some_and(|call| call.request_body.is_none() && call.response_body.is_none()));
+    assert!(streamed
+        .as_ref()
+        .is_some_and(|call| call.request_headers.contains_key("content-type")));
+
+    let seq = streamed.map_or(0, |call| call.seq);
+    let detail = hub.call_detail(seq);
+    assert_eq!(
+        detail
+            .as_ref()
+            .and_then(|detail| detail.call.response_body.as_deref()),
+        Some("{\"id\":7}")
+    );
+    assert_eq!(
+        detail.map(|detail| detail.timing),
+        Some(CallTiming {
+            started_at_ms: 2_000,
+            finished_at_ms: Some(2_040),
+            duration_ms: Some(40),
+            upstream_ms: Some(31),
+            proxy_ms: Some(9),
+        })
+    );
+    assert!(hub.call_detail(seq + 1).is_none());
+}
+
+#[test]
+fn database_statements_share_a_templated_db_edge() {
+    assert_eq!(
+        statement_template("SELECT * FROM t WHERE id IN (?, ?, ?)"),
+        "SELECT * FROM t WHERE id IN (?)"
+    );
+    assert_eq!(
+        statement_template("INSERT INTO t VALUES (?, ?), (?, ?)"),
+        "INSERT INTO t VALUES (?)"
+    );
+    assert_eq!(statement_template("GET session:42 ?"), "GET session:{id} ?");
+
+    let hub = TrafficHub::new();
+    for statement in ["GET session:41", "GET session:42"] {
+        hub.emit(Observation::Db(DbObservation {
+            at_ms: 1_000,
+            peer: workload_peer("api", "cache"),
+            protocol: "redis".to_string(),
+            command: "GET".to_string(),
+            statement: statement.to_string(),
+            duration_ms: Some(1),
+            attrs: ObservationAttrs {
+                visibility: Visibility::L7Semantics,
+                confidence: Confidence::Exact,
+                tags: BTreeMap::new(),
+            },
+        }));
+    }
+    let edges = hub.edges();
+    assert_eq!(edges.len(), 1);
+    let key = edges.first().and_then(|edge| match &edge.key {
+        EdgeKey::Db {
+            protocol,
+            command,
+            statement,
+            ..
+        } => Some((protocol.as_str(), command.as_str(), statement.as_str())),
+        _ => None,
+    });
+    assert_eq!(key, Some(("redis", "GET", "GET session:{id}")));
+}
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+pub const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_mins(2);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaitCondition {
+    Ready,
+    Running,
+    Healthy,
+}
+
+impl WaitCondition {
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Ready => "ready",
+            Self::Running => "running",
+            Self::Healthy => "healthy",
+        }
+    }
+
+    pub fn check(self, state: Option<&str>, health: Option<&str>) -> WaitProgress {
+        let state = state.unwrap_or("created");
+        if matches!(state, "exited" | "dead") {
+            return WaitProgress::Failed(state.to_string());
+        }
+        if state != "running" {
+            return WaitProgress::Waiting(state.to_string());
+        }
+        match (self, health) {
+            (Self::Running, _) | (Self::Ready, None) | (_, Some("healthy")) => WaitProgress::Met,
+            (Self::Healthy, None) => WaitProgress::Failed("no healthcheck".to_string()),
+            (_, Some(health)) => WaitProgress::Waiting(health.to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WaitTarget {
+    pub service: String,
+    pub condition: WaitCondition,
+}
+
+impl WaitTarget {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (service, condition) = spec.split_once(':').unwrap_or((spec, "ready"));
+        let condition = match condition.trim().to_ascii_lowercase().as_str() {
+            "ready" => WaitCondition::Ready,
+            "running" | "started" => WaitCondition::Running,
+            "healthy" => WaitCondition::Healthy,
+            _ => {
+                return Err(format!(
+                    "Invalid --wait-for '{spec}'. Use service, service:running or service:healthy."
+                ))
+            }
+        };
+        let service = service.trim();
+        if service.is_empty() {
+            return Err(format!(
+                "Invalid --wait-for '{spec}': missing service name."
+            ));
+        }
+        Ok(Self {
+            service: service.to_string(),
+            condition,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct WaitOptions {
+    pub targets: Vec<WaitTarget>,
+    pub timeout: Duration,
+}
+
+pub struct ContainerState<'a> {
+    pub service: &'a str,
+    pub state: Option<&'a str>,
+    pub health: Option<&'a str>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WaitProgress {
+    Met,
+    Waiting(String),
+    Failed(String),
+}
+
+pub fn target_progress(target: &WaitTarget, containers: &[ContainerState<'_>]) -> WaitProgress {
+    let mut progress = containers
+        .iter()
+        .filter(|container| container.service == target.service)
+        .map(|container| target.condition.check(container.state, container.health))
+        .peekable();
+    if progress.peek().is_none() {
+        return WaitProgress::Waiting("no container".to_string());
+    }
+    progress.fold(WaitProgress::Met, |acc, next| match (acc, next) {
+        (WaitProgress::Failed(reason), _) | (_, WaitProgress::Failed(reason)) => {
+            WaitProgress::Failed(reason)
+        }
+        (WaitProgress::Waiting(reason), _) | (_, WaitProgress::Waiting(reason)) => {
+            WaitProgress::Waiting(reason)
+        }
+        (WaitProgress::Met, WaitProgress::Met) => WaitProgress::Met,
+    })
+}
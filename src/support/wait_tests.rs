@@ -0,0 +1,64 @@
+use super::wait::{target_progress, ContainerState, WaitCondition, WaitProgress, WaitTarget};
+
+fn container<'a>(service: &'a str, state: &'a str, health: Option<&'a str>) -> ContainerState<'a> {
+    ContainerState {
+        service,
+        state: Some(state),
+        health,
+    }
+}
+
+#[test]
+fn parses_targets_with_optional_conditions() {
+    let target = WaitTarget::parse("db:healthy");
+    assert_eq!(
+        target.map(|target| target.condition),
+        Ok(WaitCondition::Healthy)
+    );
+    assert_eq!(
+        WaitTarget::parse("api").map(|target| (target.service, target.condition)),
+        Ok(("api".to_string(), WaitCondition::Ready))
+    );
+    assert!(WaitTarget::parse("api:up").is_err());
+    assert!(WaitTarget::parse(":healthy").is_err());
+}
+
+#[test]
+fn waits_for_every_replica_and_fails_fast_on_exits() {
+    let healthy = WaitTarget {
+        service: "db".to_string(),
+        condition: WaitCondition::Healthy,
+    };
+    let running = WaitTarget {
+        service: "api".to_string(),
+        condition: WaitCondition::Running,
+    };
+    let starting = [
+        container("db", "running", Some("starting")),
+        container("api", "running", None),
+        container("api", "created", None),
+    ];
+    assert_eq!(
+        target_progress(&healthy, &starting),
+        WaitProgress::Waiting("starting".to_string())
+    );
+    assert_eq!(
+        target_progress(&running, &starting),
+        WaitProgress::Waiting("created".to_string())
+    );
+    let settled = [
+        container("db", "running", Some("healthy")),
+        container("api", "running", None),
+        container("api", "exited", None),
+    ];
+    assert_eq!(target_progress(&healthy, &settled), WaitProgress::Met);
+    assert_eq!(
+        target_progress(&running, &settled),
+        WaitProgress::Failed("exited".to_string())
+    );
+    let no_check = [container("db", "running", None)];
+    assert_eq!(
+        target_progress(&healthy, &no_check),
+        WaitProgress::Failed("no healthcheck".to_string())
+    );
+}